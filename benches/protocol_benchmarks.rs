@@ -1,7 +1,5 @@
-use std::collections::HashMap;
-
 use airplay2::protocol::crypto::Aes128Ctr;
-use airplay2::protocol::plist::{PlistValue, decode, encode};
+use airplay2::protocol::plist::{PlistDict, PlistValue, decode, encode};
 use airplay2::protocol::rtp::RtpCodec;
 use airplay2::protocol::rtp::packet_buffer::{BufferedPacket, PacketBuffer, PacketLossDetector};
 use criterion::{Criterion, Throughput, black_box, criterion_group, criterion_main};
@@ -39,7 +37,7 @@ fn packet_buffer_benchmark(c: &mut Criterion) {
 fn plist_benchmark(c: &mut Criterion) {
     // 1. Prepare data
     // Create a reasonably complex plist
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "StringKey".to_string(),
         PlistValue::String("Some string value".to_string()),
@@ -55,7 +53,7 @@ fn plist_benchmark(c: &mut Criterion) {
         ]),
     );
     // Nested dict
-    let mut inner = HashMap::new();
+    let mut inner = PlistDict::new();
     inner.insert(
         "InnerKey".to_string(),
         PlistValue::String("InnerValue".to_string()),
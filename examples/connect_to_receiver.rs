@@ -144,7 +144,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // 3. Create client and connect
     let config = AirPlayConfig::default();
-    let mut client = AirPlayClient::new(config);
+    let client = AirPlayClient::new(config);
 
     match client.connect(device).await {
         Ok(()) => println!("Connected successfully!"),
@@ -158,10 +158,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\nStreaming 440Hz sine wave for 5 seconds...");
     let source = SineWaveSource::new(440.0, 5);
 
-    match tokio::time::timeout(Duration::from_secs(10), client.stream_audio(source)).await {
-        Ok(Ok(())) => println!("Streaming completed successfully!"),
-        Ok(Err(e)) => println!("Streaming error: {:?}", e),
-        Err(_) => println!("Streaming timed out"),
+    match client.stream_audio(source).await {
+        Ok(stream) => match tokio::time::timeout(Duration::from_secs(10), stream.wait()).await {
+            Ok(Ok(())) => println!("Streaming completed successfully!"),
+            Ok(Err(e)) => println!("Streaming error: {:?}", e),
+            Err(_) => println!("Streaming timed out"),
+        },
+        Err(e) => println!("Streaming error: {:?}", e),
     }
 
     // 5. Disconnect
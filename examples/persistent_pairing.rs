@@ -99,7 +99,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Create client with storage
     let config = AirPlayConfig::default();
-    let mut client = AirPlayClient::new(config).with_pairing_storage(storage);
+    let client = AirPlayClient::new(config).with_pairing_storage(storage);
 
     println!("Scanning for devices...");
     let devices = scan(Duration::from_secs(3)).await?;
@@ -125,7 +125,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Connected! Streaming briefly...");
 
     let source1 = SineWaveSource::new(440.0, 2);
-    client.stream_audio(source1).await?;
+    client.stream_audio(source1).await?.wait().await?;
 
     println!("Disconnecting...");
     client.disconnect().await?;
@@ -146,7 +146,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Connected (should be fast)! Streaming briefly...");
 
     let source2 = SineWaveSource::new(880.0, 2);
-    client.stream_audio(source2).await?;
+    client.stream_audio(source2).await?.wait().await?;
 
     println!("Disconnecting...");
     client.disconnect().await?;
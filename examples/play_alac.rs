@@ -105,21 +105,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .audio_codec(AudioCodec::Alac)
         .build();
 
-    let mut client = AirPlayClient::new(config);
+    let client = AirPlayClient::new(config);
     client.connect(&device).await?;
 
     println!("Streaming 440Hz sine wave (ALAC encoded)...");
     let source = SineSource::new(440.0);
 
-    // Start streaming (blocks until stopped)
-    tokio::select! {
-        result = client.stream_audio(source) => {
-            result?;
-        }
-        _ = tokio::time::sleep(Duration::from_secs(5)) => {
-            println!("Stopping...");
-        }
-    }
+    // Streams in the background until stopped
+    let stream = client.stream_audio(source).await?;
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    println!("Stopping...");
+    stream.stop().await?;
 
     client.disconnect().await?;
     println!("Streaming completed successfully!");
@@ -99,22 +99,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         });
 
     println!("Connecting to {}...", device.name);
-    let mut client = AirPlayClient::default_client();
+    let client = AirPlayClient::default_client();
     client.connect(&device).await?;
 
     println!("Streaming 440Hz sine wave...");
     let source = SineSource::new(440.0);
 
-    // Start streaming (blocks until stopped)
-    // In a real app, you'd spawn this or use a channel
-    tokio::select! {
-        result = client.stream_audio(source) => {
-            result?;
-        }
-        _ = tokio::time::sleep(Duration::from_secs(5)) => {
-            println!("Stopping...");
-        }
-    }
+    // Streams in the background until stopped
+    let stream = client.stream_audio(source).await?;
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    println!("Stopping...");
+    stream.stop().await?;
 
     client.disconnect().await?;
     Ok(())
@@ -126,7 +126,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Streaming audio (PCM)...");
 
     // Use client_mut() to access stream_audio
-    player.client_mut().stream_audio(source).await?;
+    player
+        .client_mut()
+        .stream_audio(source)
+        .await?
+        .wait()
+        .await?;
 
     println!("\nPlayback finished! check your device.");
     println!("Press Enter to stop and exit...");
@@ -71,9 +71,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("Starting stream...");
     let source = TestSource::new();
-    let mut client_clone = client.clone();
+    let client_clone = client.clone();
     tokio::spawn(async move {
-        let _ = client_clone.stream_audio(source).await;
+        if let Ok(stream) = client_clone.stream_audio(source).await {
+            let _ = stream.wait().await;
+        }
     });
 
     tokio::time::sleep(Duration::from_secs(2)).await;
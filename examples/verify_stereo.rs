@@ -135,9 +135,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Streaming Full Verification (Stereo + Volume)...");
     let source = StereoSource::new(10); // 10 seconds
 
-    // Start streaming in a separate task so we can control volume
-    let mut client_clone = client.clone();
-    let stream_handle = tokio::spawn(async move { client_clone.stream_audio(source).await });
+    // Streams in the background so we can control volume
+    let stream = client.stream_audio(source).await?;
 
     // Test Volume Control
     tokio::time::sleep(Duration::from_secs(2)).await;
@@ -157,7 +156,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     client.unmute().await?;
 
     // Wait for stream to finish
-    stream_handle.await??;
+    stream.wait().await?;
 
     println!("Done.");
     Ok(())
@@ -29,7 +29,7 @@ async fn test_aac_streaming_end_to_end() -> Result<(), Box<dyn std::error::Error
         .connection_timeout(Duration::from_secs(10))
         .build();
 
-    let mut client = AirPlayClient::new(config);
+    let client = AirPlayClient::new(config);
 
     // Add retry logic for connection (handling potential auth flakes)
     let mut last_error = None;
@@ -66,7 +66,11 @@ async fn test_aac_streaming_end_to_end() -> Result<(), Box<dyn std::error::Error
     let source = TestSineSource::new(440.0, 5.0); // 5 seconds of audio
 
     // Stream (blocks until done or error)
-    if let Err(e) = client.stream_audio(source).await {
+    let result = match client.stream_audio(source).await {
+        Ok(stream) => stream.wait().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
         tracing::error!("Streaming failed: {}", e);
         let output = receiver.stop().await?;
         if output.log_path.exists() {
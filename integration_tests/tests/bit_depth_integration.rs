@@ -98,7 +98,7 @@ async fn test_bit_depth_24_to_16() -> Result<(), Box<dyn std::error::Error>> {
     sleep(Duration::from_secs(2)).await;
 
     let device = receiver.device_config();
-    let mut client = airplay2::AirPlayClient::default_client();
+    let client = airplay2::AirPlayClient::default_client();
 
     // Retry connection up to 3 times to handle "Authentication failed - invalid proof" flake
     let mut connected = false;
@@ -125,7 +125,7 @@ async fn test_bit_depth_24_to_16() -> Result<(), Box<dyn std::error::Error>> {
 
     tracing::info!("Streaming 24-bit audio...");
     // This should trigger resampling (format conversion)
-    client.stream_audio(source).await?;
+    client.stream_audio(source).await?.wait().await?;
 
     client.disconnect().await?;
     sleep(Duration::from_secs(1)).await;
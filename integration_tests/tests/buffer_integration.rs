@@ -130,14 +130,17 @@ impl AudioSource for FiniteSineWaveSource {
 async fn test_streaming_with_small_buffer() {
     // 100ms buffer (4410 frames at 44.1kHz)
     let buffer_frames = 4410;
-    let (_receiver, mut client, _device) = setup_streaming_test(buffer_frames)
+    let (_receiver, client, _device) = setup_streaming_test(buffer_frames)
         .await
         .expect("Failed to setup test");
 
     // Stream for 2 seconds
     let source = FiniteSineWaveSource::new(440.0, 44100, 2, Duration::from_secs(2));
 
-    let result = client.stream_audio(source).await;
+    let result = match client.stream_audio(source).await {
+        Ok(stream) => stream.wait().await,
+        Err(e) => Err(e),
+    };
     assert!(
         result.is_ok(),
         "Streaming failed with small buffer: {:?}",
@@ -151,14 +154,17 @@ async fn test_streaming_with_small_buffer() {
 async fn test_streaming_with_large_buffer() {
     // 2s buffer (88200 frames at 44.1kHz)
     let buffer_frames = 88200;
-    let (_receiver, mut client, _device) = setup_streaming_test(buffer_frames)
+    let (_receiver, client, _device) = setup_streaming_test(buffer_frames)
         .await
         .expect("Failed to setup test");
 
     // Stream for 3 seconds (to ensure we fill buffer and stream)
     let source = FiniteSineWaveSource::new(880.0, 44100, 2, Duration::from_secs(3));
 
-    let result = client.stream_audio(source).await;
+    let result = match client.stream_audio(source).await {
+        Ok(stream) => stream.wait().await,
+        Err(e) => Err(e),
+    };
     assert!(
         result.is_ok(),
         "Streaming failed with large buffer: {:?}",
@@ -0,0 +1,267 @@
+//! Harness for third-party `AirPlay` receivers installed on the host
+//!
+//! Used by the opt-in conformance suite. A receiver is only driven when its
+//! name appears in `AIRPLAY_CONFORMANCE` (comma separated, or `all`); the
+//! binary can be overridden with `SHAIRPORT_SYNC_BIN` / `UXPLAY_BIN`.
+//! owntone is an `AirPlay` sender rather than a receiver, so it has no entry here.
+
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use airplay2::AirPlayDevice;
+use airplay2::types::{DeviceCapabilities, RaopCapabilities};
+use tempfile::TempDir;
+use tokio::io::AsyncReadExt;
+use tokio::task::JoinHandle;
+
+use super::ports::reserve_port;
+use super::subprocess::{ReadyStrategy, SubprocessConfig, SubprocessHandle, SubprocessOutput};
+
+/// Environment variable listing the receivers to test
+pub const CONFORMANCE_ENV: &str = "AIRPLAY_CONFORMANCE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExternalReceiverKind {
+    /// shairport-sync (`AirPlay` 1 / RAOP build)
+    ShairportSync,
+    /// `UxPlay` (`AirPlay` 2 audio/mirroring server)
+    Uxplay,
+}
+
+impl ExternalReceiverKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::ShairportSync => "shairport-sync",
+            Self::Uxplay => "uxplay",
+        }
+    }
+
+    fn binary(self) -> String {
+        let var = match self {
+            Self::ShairportSync => "SHAIRPORT_SYNC_BIN",
+            Self::Uxplay => "UXPLAY_BIN",
+        };
+        std::env::var(var).unwrap_or_else(|_| self.name().to_string())
+    }
+
+    /// Whether this receiver was requested through `AIRPLAY_CONFORMANCE`
+    pub fn enabled(self) -> bool {
+        std::env::var(CONFORMANCE_ENV).is_ok_and(|list| {
+            list.split(',')
+                .map(str::trim)
+                .any(|entry| entry == "all" || entry == self.name())
+        })
+    }
+}
+
+/// A running third-party receiver
+pub struct ExternalReceiver {
+    pub kind: ExternalReceiverKind,
+    pub port: u16,
+    handle: SubprocessHandle,
+    audio: Option<PipeCapture>,
+    metadata: Option<PipeCapture>,
+    _dir: TempDir,
+}
+
+impl ExternalReceiver {
+    pub async fn start(kind: ExternalReceiverKind) -> Result<Self, Box<dyn std::error::Error>> {
+        let dir = TempDir::new()?;
+        let port = reserve_port()?.free();
+        let name = format!("airplay2-rs conformance {port}");
+
+        let mut audio = None;
+        let mut metadata = None;
+        let args = match kind {
+            ExternalReceiverKind::ShairportSync => {
+                let config = dir.path().join("shairport-sync.conf");
+                std::fs::write(&config, "")?;
+                let audio_pipe = PipeCapture::create(dir.path().join("audio"))?;
+                let metadata_pipe = PipeCapture::create(dir.path().join("metadata"))?;
+                let args = vec![
+                    "-c".to_string(),
+                    config.display().to_string(),
+                    "-v".to_string(),
+                    "-a".to_string(),
+                    name,
+                    "-p".to_string(),
+                    port.to_string(),
+                    "--metadata-enable".to_string(),
+                    format!("--metadata-pipename={}", metadata_pipe.path.display()),
+                    "-o".to_string(),
+                    "pipe".to_string(),
+                    "--".to_string(),
+                    audio_pipe.path.display().to_string(),
+                ];
+                audio = Some(audio_pipe);
+                metadata = Some(metadata_pipe);
+                args
+            }
+            ExternalReceiverKind::Uxplay => vec![
+                "-n".to_string(),
+                name,
+                "-nh".to_string(),
+                "-d".to_string(),
+                "-vs".to_string(),
+                "0".to_string(),
+                "-as".to_string(),
+                "fakesink".to_string(),
+                "-p".to_string(),
+                "tcp".to_string(),
+                port.to_string(),
+            ],
+        };
+
+        let config = SubprocessConfig {
+            command: kind.binary(),
+            args,
+            env_vars: HashMap::new(),
+            ready_strategy: ReadyStrategy::TcpPort(port),
+            ready_timeout: Duration::from_secs(20),
+            log_prefix: format!("[{}]", kind.name()),
+            post_ready_delay: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        let handle = SubprocessHandle::spawn(config).await?;
+
+        Ok(Self {
+            kind,
+            port,
+            handle,
+            audio,
+            metadata,
+            _dir: dir,
+        })
+    }
+
+    pub fn ip(&self) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::LOCALHOST)
+    }
+
+    /// Device description for connecting over RAOP
+    pub fn raop_device(&self) -> AirPlayDevice {
+        let txt: HashMap<String, String> = [
+            ("cn", "0,1"),
+            ("et", "0,1"),
+            ("md", "0,1,2"),
+            ("ch", "2"),
+            ("sr", "44100"),
+            ("ss", "16"),
+            ("tp", "UDP"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        AirPlayDevice {
+            id: format!("{}-{}", self.kind.name(), self.port),
+            name: self.kind.name().to_string(),
+            model: None,
+            addresses: vec![self.ip()],
+            port: 0,
+            capabilities: DeviceCapabilities::default(),
+            raop_port: Some(self.port),
+            raop_capabilities: Some(RaopCapabilities::from_txt_records(&txt)),
+            txt_records: txt,
+            last_seen: None,
+        }
+    }
+
+    /// PCM written to the audio pipe so far (16-bit stereo)
+    pub fn captured_pcm(&self) -> Vec<i16> {
+        self.audio
+            .as_ref()
+            .map(|pipe| {
+                pipe.bytes()
+                    .chunks_exact(2)
+                    .map(|b| i16::from_le_bytes([b[0], b[1]]))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Metadata pipe contents so far
+    pub fn metadata(&self) -> String {
+        self.metadata
+            .as_ref()
+            .map(|pipe| String::from_utf8_lossy(&pipe.bytes()).into_owned())
+            .unwrap_or_default()
+    }
+
+    pub async fn wait_for_log(&self, pattern: &str, timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while tokio::time::Instant::now() < deadline {
+            if self.handle.logs().iter().any(|l| l.line.contains(pattern)) {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    pub async fn stop(self) -> Result<SubprocessOutput, Box<dyn std::error::Error>> {
+        let output = self.handle.stop().await?;
+        if let Some(pipe) = self.audio {
+            pipe.close();
+        }
+        if let Some(pipe) = self.metadata {
+            pipe.close();
+        }
+        Ok(output)
+    }
+}
+
+/// A named pipe whose contents are collected in the background
+struct PipeCapture {
+    path: PathBuf,
+    data: Arc<Mutex<Vec<u8>>>,
+    task: JoinHandle<()>,
+}
+
+impl PipeCapture {
+    fn create(path: PathBuf) -> std::io::Result<Self> {
+        mkfifo(&path)?;
+
+        // Opening read-write keeps the pipe from reporting EOF before the
+        // receiver opens it, and across receiver reconnects.
+        let mut receiver = tokio::net::unix::pipe::OpenOptions::new()
+            .read_write(true)
+            .open_receiver(&path)?;
+        let data = Arc::new(Mutex::new(Vec::new()));
+        let sink = data.clone();
+        let task = tokio::spawn(async move {
+            let mut buf = vec![0u8; 16 * 1024];
+            while let Ok(n) = receiver.read(&mut buf).await {
+                if n == 0 {
+                    break;
+                }
+                sink.lock().unwrap().extend_from_slice(&buf[..n]);
+            }
+        });
+
+        Ok(Self { path, data, task })
+    }
+
+    fn bytes(&self) -> Vec<u8> {
+        self.data.lock().unwrap().clone()
+    }
+
+    fn close(self) {
+        self.task.abort();
+    }
+}
+
+fn mkfifo(path: &Path) -> std::io::Result<()> {
+    let status = std::process::Command::new("mkfifo").arg(path).status()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "mkfifo {} failed: {status}",
+            path.display()
+        )))
+    }
+}
@@ -1,4 +1,7 @@
 pub mod diagnostics;
+#[cfg(target_os = "linux")]
+#[allow(dead_code, reason = "Only used by the conformance suite")]
+pub mod external_receiver;
 pub mod ports;
 pub mod python_receiver;
 pub mod subprocess;
@@ -0,0 +1,172 @@
+//! Conformance tests against third-party receivers
+//!
+//! Opt-in: set `AIRPLAY_CONFORMANCE=shairport-sync,uxplay` (or `all`) with the
+//! receivers installed locally. Each test returns early when its receiver is
+//! not enabled, so the suite is a no-op in normal runs.
+#![cfg(target_os = "linux")]
+
+use std::time::Duration;
+
+use airplay2::audio::AudioFormat;
+use airplay2::streaming::AudioSource;
+use airplay2::testing::audio_compare::{self, CompareOptions};
+use airplay2::testing::test_utils::generate_test_audio;
+use airplay2::types::TrackInfo;
+use airplay2::{
+    AirPlayClient, AirPlayConfig, ClientConfig, PreferredProtocol, UnifiedAirPlayClient,
+};
+
+mod common;
+use common::external_receiver::{ExternalReceiver, ExternalReceiverKind};
+
+/// 441 Hz repeats every 100 frames at 44.1 kHz, so alignment only needs to
+/// search one period.
+const TONE_HZ: f32 = 441.0;
+const FRAMES_PER_PACKET: usize = 352;
+
+fn skip(kind: ExternalReceiverKind) -> bool {
+    if kind.enabled() {
+        common::init_logging();
+        false
+    } else {
+        eprintln!(
+            "{} conformance test skipped (set AIRPLAY_CONFORMANCE to enable)",
+            kind.name()
+        );
+        true
+    }
+}
+
+#[tokio::test]
+async fn test_shairport_sync_tone_and_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    if skip(ExternalReceiverKind::ShairportSync) {
+        return Ok(());
+    }
+
+    let receiver = ExternalReceiver::start(ExternalReceiverKind::ShairportSync).await?;
+    let mut client = UnifiedAirPlayClient::with_config(ClientConfig {
+        preferred_protocol: PreferredProtocol::ForceRaop,
+        enable_metadata: true,
+        connection_timeout: Duration::from_secs(10),
+        ..Default::default()
+    });
+    client.connect(receiver.raop_device()).await?;
+    client.set_volume(1.0).await?;
+
+    let track = TrackInfo {
+        title: "Conformance Tone".to_string(),
+        artist: "airplay2-rs".to_string(),
+        ..Default::default()
+    };
+    if let Some(session) = client.session_mut() {
+        session.set_metadata(&track).await?;
+    }
+
+    // Three seconds of tone, paced in real time
+    let tone = generate_test_audio(TONE_HZ, 44100, 3000, 2);
+    let mut interval =
+        tokio::time::interval(Duration::from_secs_f64(FRAMES_PER_PACKET as f64 / 44100.0));
+    for packet in tone.chunks_exact(FRAMES_PER_PACKET * 2) {
+        interval.tick().await;
+        let bytes: Vec<u8> = packet.iter().flat_map(|s| s.to_le_bytes()).collect();
+        client.stream_audio(&bytes).await?;
+    }
+    // Let the receiver drain its latency buffer
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    client.disconnect().await.ok();
+
+    let captured = receiver.captured_pcm();
+    let metadata = receiver.metadata();
+    receiver.stop().await?;
+
+    // Skip leading silence, then compare one second of steady tone
+    let start = captured
+        .iter()
+        .position(|&s| s.unsigned_abs() > 1000)
+        .ok_or("no audio reached the shairport-sync pipe")?;
+    let window = 44100 * 2;
+    let received = captured
+        .get(start + window / 4..start + window / 4 + window)
+        .ok_or("shairport-sync produced less than a second of audio")?;
+    let reference = generate_test_audio(TONE_HZ, 44100, 1000, 2);
+    let result = audio_compare::compare(
+        &reference,
+        received,
+        &CompareOptions {
+            max_offset_frames: 100,
+            ..CompareOptions::default()
+        },
+    );
+    assert!(
+        result.is_match(0.95, 0.02),
+        "tone mismatch: correlation {:.3}, concealed {:.3}",
+        result.correlation,
+        result.concealed_ratio()
+    );
+
+    // Metadata items are hex-coded: core/minm is the track title
+    assert!(
+        metadata.contains("<code>6d696e6d</code>"),
+        "track title missing from metadata pipe"
+    );
+    Ok(())
+}
+
+/// Finite sine source for the `AirPlay` 2 streaming path
+struct Tone {
+    samples: std::vec::IntoIter<i16>,
+}
+
+impl AudioSource for Tone {
+    fn format(&self) -> AudioFormat {
+        AudioFormat::CD_QUALITY
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> std::io::Result<usize> {
+        let mut written = 0;
+        for chunk in buffer.chunks_exact_mut(2) {
+            let Some(sample) = self.samples.next() else {
+                break;
+            };
+            chunk.copy_from_slice(&sample.to_le_bytes());
+            written += 2;
+        }
+        Ok(written)
+    }
+}
+
+#[tokio::test]
+async fn test_uxplay_connect_and_stream() -> Result<(), Box<dyn std::error::Error>> {
+    if skip(ExternalReceiverKind::Uxplay) {
+        return Ok(());
+    }
+
+    let receiver = ExternalReceiver::start(ExternalReceiverKind::Uxplay).await?;
+    let config = AirPlayConfig::builder()
+        .connection_timeout(Duration::from_secs(15))
+        .build();
+    let client = AirPlayClient::new(config);
+
+    // No mDNS needed: probe the known address directly
+    let device = client.connect_addr(receiver.ip(), receiver.port).await?;
+    assert!(
+        device.supports_airplay2(),
+        "uxplay did not report AirPlay 2"
+    );
+
+    client.set_volume(0.5).await?;
+    let tone = Tone {
+        samples: generate_test_audio(TONE_HZ, 44100, 2000, 2).into_iter(),
+    };
+    let stream = client.stream_audio(tone).await?;
+    tokio::time::timeout(Duration::from_secs(10), stream.wait()).await??;
+    client.disconnect().await.ok();
+
+    // uxplay logs each RTSP request it handles when run with -d
+    let saw_record = receiver
+        .wait_for_log("RECORD", Duration::from_secs(5))
+        .await;
+    receiver.stop().await?;
+    assert!(saw_record, "uxplay never received RECORD");
+    Ok(())
+}
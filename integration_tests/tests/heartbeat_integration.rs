@@ -43,12 +43,11 @@ async fn test_device_presence_heartbeat() -> Result<(), Box<dyn std::error::Erro
                         );
                         if let (Some(initial), Some(current)) =
                             (initial_last_seen, device.last_seen)
+                            && current > initial
                         {
-                            if current > initial {
-                                heartbeats_received += 1;
-                                // Update our initial last seen to the current one
-                                initial_last_seen = Some(current);
-                            }
+                            heartbeats_received += 1;
+                            // Update our initial last seen to the current one
+                            initial_last_seen = Some(current);
                         }
                     }
                 }
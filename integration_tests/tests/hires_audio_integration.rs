@@ -104,7 +104,7 @@ async fn test_hires_audio_streaming() -> Result<(), Box<dyn std::error::Error>>
 
     // Use prefer_hires_audio config option
     let config = AirPlayConfig::builder().prefer_hires_audio(true).build();
-    let mut client = AirPlayClient::new(config);
+    let client = AirPlayClient::new(config);
 
     let device = receiver.device_config();
 
@@ -132,7 +132,7 @@ async fn test_hires_audio_streaming() -> Result<(), Box<dyn std::error::Error>>
     // Create 24-bit 48kHz audio source
     let source = I24HiresSineSource::new(440.0, 3.0);
 
-    client.stream_audio(source).await?;
+    client.stream_audio(source).await?.wait().await?;
 
     client.disconnect().await?;
     sleep(Duration::from_secs(1)).await;
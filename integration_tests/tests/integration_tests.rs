@@ -72,7 +72,7 @@ async fn test_pcm_streaming_end_to_end() -> Result<(), Box<dyn std::error::Error
     tracing::info!("Streaming audio...");
     let source = TestSineSource::new(440.0, 3.0);
 
-    client.stream_audio(source).await?;
+    client.stream_audio(source).await?.wait().await?;
 
     tracing::info!("Disconnecting...");
     client.disconnect().await?;
@@ -117,7 +117,7 @@ async fn test_alac_streaming_end_to_end() -> Result<(), Box<dyn std::error::Erro
     tracing::info!("Streaming ALAC audio...");
     let source = TestSineSource::new(440.0, 3.0);
 
-    client.stream_audio(source).await?;
+    client.stream_audio(source).await?.wait().await?;
 
     tracing::info!("Disconnecting...");
     client.disconnect().await?;
@@ -161,7 +161,7 @@ async fn test_aac_streaming_end_to_end() -> Result<(), Box<dyn std::error::Error
     tracing::info!("Streaming AAC audio...");
     let source = TestSineSource::new(440.0, 3.0);
 
-    client.stream_audio(source).await?;
+    client.stream_audio(source).await?.wait().await?;
 
     tracing::info!("Disconnecting...");
     client.disconnect().await?;
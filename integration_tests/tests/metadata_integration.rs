@@ -5,7 +5,7 @@
 
 use std::time::Duration;
 
-use airplay2::protocol::daap::{DmapProgress, TrackMetadata};
+use airplay2::protocol::daap::{Artwork, DmapProgress, TrackMetadata};
 use airplay2::types::TrackInfo;
 use airplay2::{AirPlayClient, AirPlayConfig, UnifiedAirPlayClient};
 use tokio::time::sleep;
@@ -99,7 +99,7 @@ async fn test_metadata_updates() -> Result<(), Box<dyn std::error::Error>> {
     // 5. Send Artwork
     tracing::info!("Sending artwork...");
     let artwork_data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F']; // Fake JPEG header
-    client.set_artwork(&artwork_data, "image/jpeg").await?;
+    client.set_artwork(Artwork::jpeg(artwork_data)).await?;
 
     tracing::info!("Verifying artwork logs...");
     receiver
@@ -27,7 +27,7 @@ async fn test_multi_room_coordination() -> Result<(), Box<dyn std::error::Error>
         .pin("3939")
         .build();
 
-    let mut client = AirPlayClient::new(config);
+    let client = AirPlayClient::new(config);
     if let Err(e) = client.connect(&device).await {
         tracing::error!("Connection failed: {}", e);
         receiver.stop().await?;
@@ -44,7 +44,11 @@ async fn test_multi_room_coordination() -> Result<(), Box<dyn std::error::Error>
     tracing::info!("Streaming audio to trigger multi-room coordination setup...");
     let source = TestSineSource::new(440.0, 3.0); // 3 seconds of audio
 
-    if let Err(e) = client.stream_audio(source).await {
+    let result = match client.stream_audio(source).await {
+        Ok(stream) => stream.wait().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
         tracing::error!("Streaming failed: {}", e);
         receiver.stop().await?;
         return Err(e.into());
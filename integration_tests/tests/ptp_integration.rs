@@ -30,7 +30,7 @@ async fn test_ptp_synchronization() -> Result<(), Box<dyn std::error::Error>> {
         .pin("3939")
         .build();
 
-    let mut client = AirPlayClient::new(config);
+    let client = AirPlayClient::new(config);
 
     // Retry up to 3 times — the Python receiver occasionally returns
     // "Authentication failed - invalid proof" on the first attempt due to a
@@ -70,7 +70,11 @@ async fn test_ptp_synchronization() -> Result<(), Box<dyn std::error::Error>> {
     let source = TestSineSource::new(440.0, 5.0); // 5 seconds of audio
 
     // Stream (blocks until done or error)
-    if let Err(e) = client.stream_audio(source).await {
+    let result = match client.stream_audio(source).await {
+        Ok(stream) => stream.wait().await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
         tracing::error!("Streaming failed: {}", e);
         let output = receiver.stop().await?;
         if output.log_path.exists() {
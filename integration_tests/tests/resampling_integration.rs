@@ -28,7 +28,7 @@ async fn test_resampling_48k_to_44k() -> Result<(), Box<dyn std::error::Error>>
         .discovery_timeout(Duration::from_secs(5))
         .build();
 
-    let mut client = airplay2::AirPlayClient::new(config);
+    let client = airplay2::AirPlayClient::new(config);
 
     tracing::info!("Connecting to receiver...");
     // Use retry logic for robustness in CI
@@ -51,7 +51,7 @@ async fn test_resampling_48k_to_44k() -> Result<(), Box<dyn std::error::Error>>
 
     tracing::info!("Streaming 48kHz audio...");
     // This should now automatically trigger resampling to 44.1kHz
-    client.stream_audio(source).await?;
+    client.stream_audio(source).await?.wait().await?;
 
     tracing::info!("Disconnecting...");
     client.disconnect().await?;
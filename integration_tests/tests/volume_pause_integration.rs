@@ -97,10 +97,14 @@ async fn test_volume_and_pause() -> Result<(), Box<dyn std::error::Error>> {
 
     // 4. Start Streaming (Background)
     println!("Starting stream...");
-    let mut client_clone = client.clone();
+    let client_clone = client.clone();
     let stream_handle = tokio::spawn(async move {
         let source = SineSource::new(440.0);
-        if let Err(e) = client_clone.stream_audio(source).await {
+        let result = match client_clone.stream_audio(source).await {
+            Ok(stream) => stream.wait().await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
             eprintln!("Streaming error: {:?}", e);
         }
     });
@@ -1,8 +1,22 @@
-//! AAC audio encoder using fdk-aac
+//! AAC audio encoder
+//!
+//! Two backends can be compiled in. The `aac-rs` feature (on by default)
+//! supplies a pure-Rust AAC-LC encoder, so default builds need no C
+//! toolchain. The optional `aac-fdk` feature adds fdk-aac, which sounds
+//! better at low bitrates and is needed for AAC-ELD, but brings a C build
+//! and the FDK licence. With both compiled in, fdk-aac is preferred and the
+//! pure-Rust encoder takes over for AAC-LC if fdk-aac fails to start;
+//! [`AacEncoder::backend`] reports which one is running.
 
-use fdk_aac::enc::{AudioObjectType, BitRate, ChannelMode, Encoder, EncoderParams, Transport};
+#[cfg(feature = "aac-fdk")]
+use fdk_aac::enc::{BitRate, ChannelMode, Encoder, EncoderParams, Transport};
 use thiserror::Error;
 
+use super::encoder::{AudioEncodeError, AudioEncoder};
+
+#[cfg(feature = "aac-rs")]
+use super::aac_lc::LcEncoder;
+
 /// AAC encoder error
 #[derive(Debug, Error)]
 pub enum AacEncoderError {
@@ -14,14 +28,75 @@ pub enum AacEncoderError {
     Encoding,
 }
 
+/// MPEG-4 audio object type to encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioObjectType {
+    /// AAC Low Complexity
+    Mpeg4LowComplexity,
+    /// AAC Enhanced Low Delay
+    Mpeg4EnhancedLowDelay,
+}
+
+/// Implementation behind an [`AacEncoder`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AacBackend {
+    /// fdk-aac (`aac-fdk` feature)
+    Fdk,
+    /// Pure-Rust AAC-LC encoder (`aac-rs` feature)
+    Native,
+}
+
+impl AacBackend {
+    /// Backends compiled into this build, in order of preference
+    pub const AVAILABLE: &'static [Self] = &[
+        #[cfg(feature = "aac-fdk")]
+        Self::Fdk,
+        #[cfg(feature = "aac-rs")]
+        Self::Native,
+    ];
+
+    /// Whether this backend can encode `aot`
+    #[must_use]
+    pub const fn supports(self, aot: AudioObjectType) -> bool {
+        match self {
+            Self::Fdk => true,
+            Self::Native => matches!(aot, AudioObjectType::Mpeg4LowComplexity),
+        }
+    }
+}
+
+impl std::fmt::Display for AacBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Fdk => "fdk-aac",
+            Self::Native => "pure-Rust AAC-LC",
+        })
+    }
+}
+
+enum Backend {
+    #[cfg(feature = "aac-fdk")]
+    Fdk {
+        encoder: Encoder,
+        output_buffer: Vec<u8>,
+    },
+    #[cfg(feature = "aac-rs")]
+    Native(LcEncoder),
+    /// Stands in when no backend is compiled in; never constructed
+    #[cfg(not(any(feature = "aac-fdk", feature = "aac-rs")))]
+    Unavailable(std::convert::Infallible),
+}
+
 /// AAC encoder wrapper
 pub struct AacEncoder {
-    encoder: Encoder,
-    output_buffer: Vec<u8>,
+    backend: Backend,
+    /// Samples of the packet being encoded
+    samples: Vec<i16>,
 }
 
 impl AacEncoder {
-    /// Create a new AAC encoder
+    /// Create a new AAC encoder on the first backend that supports the
+    /// configuration
     ///
     /// # Arguments
     ///
@@ -32,37 +107,115 @@ impl AacEncoder {
     ///
     /// # Errors
     ///
-    /// Returns error if encoder cannot be initialized
+    /// Returns error if encoder cannot be initialized, including when no
+    /// backend in this build supports `aot`
     pub fn new(
         sample_rate: u32,
         channels: u32,
         bitrate: u32,
         aot: AudioObjectType,
     ) -> Result<Self, AacEncoderError> {
-        let params = EncoderParams {
-            bit_rate: BitRate::Cbr(bitrate),
-            transport: Transport::Raw, // Raw AAC frames for RTP
-            audio_object_type: aot,
-            channels: match channels {
-                1 => ChannelMode::Mono,
-                2 => ChannelMode::Stereo,
-                _ => return Err(AacEncoderError::Initialization),
-            },
-            sample_rate,
-        };
+        for &backend in AacBackend::AVAILABLE {
+            match Self::with_backend(sample_rate, channels, bitrate, aot, backend) {
+                Ok(encoder) => return Ok(encoder),
+                Err(e) => tracing::debug!("{backend} cannot encode {aot:?}: {e}"),
+            }
+        }
+        Err(AacEncoderError::Initialization)
+    }
 
-        let encoder = Encoder::new(params).map_err(|_| AacEncoderError::Initialization)?;
+    /// Whether any backend in this build can encode `aot`
+    #[must_use]
+    pub fn is_available(aot: AudioObjectType) -> bool {
+        AacBackend::AVAILABLE
+            .iter()
+            .any(|backend| backend.supports(aot))
+    }
 
-        // Allocate buffer for worst-case output size
-        // 6144 bits per channel is max theoretical size for AAC
-        let buffer_size = 8192 * channels as usize;
+    /// Create a new AAC encoder on a specific backend
+    ///
+    /// # Errors
+    ///
+    /// Returns error if `backend` is not compiled into this build, does not
+    /// support `aot`, or cannot be initialized
+    #[allow(
+        unused_variables,
+        unreachable_code,
+        reason = "Unused when no backend is enabled"
+    )]
+    pub fn with_backend(
+        sample_rate: u32,
+        channels: u32,
+        bitrate: u32,
+        aot: AudioObjectType,
+        backend: AacBackend,
+    ) -> Result<Self, AacEncoderError> {
+        let backend = match backend {
+            #[cfg(feature = "aac-fdk")]
+            AacBackend::Fdk => {
+                let params = EncoderParams {
+                    bit_rate: BitRate::Cbr(bitrate),
+                    transport: Transport::Raw, // Raw AAC frames for RTP
+                    audio_object_type: match aot {
+                        AudioObjectType::Mpeg4LowComplexity => {
+                            fdk_aac::enc::AudioObjectType::Mpeg4LowComplexity
+                        }
+                        AudioObjectType::Mpeg4EnhancedLowDelay => {
+                            fdk_aac::enc::AudioObjectType::Mpeg4EnhancedLowDelay
+                        }
+                    },
+                    channels: match channels {
+                        1 => ChannelMode::Mono,
+                        2 => ChannelMode::Stereo,
+                        _ => return Err(AacEncoderError::Initialization),
+                    },
+                    sample_rate,
+                };
+
+                let encoder = Encoder::new(params).map_err(|_| AacEncoderError::Initialization)?;
+
+                // Allocate buffer for worst-case output size
+                // 6144 bits per channel is max theoretical size for AAC
+                let buffer_size = 8192 * channels as usize;
+
+                Backend::Fdk {
+                    encoder,
+                    output_buffer: vec![0u8; buffer_size],
+                }
+            }
+            #[cfg(feature = "aac-rs")]
+            AacBackend::Native => {
+                if !AacBackend::Native.supports(aot) {
+                    return Err(AacEncoderError::Initialization);
+                }
+                Backend::Native(
+                    LcEncoder::new(sample_rate, channels, bitrate)
+                        .ok_or(AacEncoderError::Initialization)?,
+                )
+            }
+            #[allow(unreachable_patterns, reason = "Reached for backends not compiled in")]
+            _ => return Err(AacEncoderError::Initialization),
+        };
 
         Ok(Self {
-            encoder,
-            output_buffer: vec![0u8; buffer_size],
+            backend,
+            samples: Vec::new(),
         })
     }
 
+    /// Backend doing the encoding
+    #[must_use]
+    pub fn backend(&self) -> AacBackend {
+        match &self.backend {
+            #[cfg(feature = "aac-fdk")]
+            Backend::Fdk { .. } => AacBackend::Fdk,
+            #[cfg(feature = "aac-rs")]
+            Backend::Native(_) => AacBackend::Native,
+            #[cfg(not(any(feature = "aac-fdk", feature = "aac-rs")))]
+            Backend::Unavailable(never) => match *never {},
+        }
+    }
+
     /// Encode PCM samples to AAC frame
     ///
     /// # Arguments
@@ -72,16 +225,28 @@ impl AacEncoder {
     /// # Errors
     ///
     /// Returns error if encoding fails
+    #[allow(unused_variables, reason = "Unused when no backend is enabled")]
     pub fn encode(&mut self, pcm_samples: &[i16]) -> Result<Vec<u8>, AacEncoderError> {
-        let info = self
-            .encoder
-            .encode(pcm_samples, &mut self.output_buffer)
-            .map_err(|_| AacEncoderError::Encoding)?;
-
-        if info.output_size > 0 {
-            Ok(self.output_buffer[..info.output_size].to_vec())
-        } else {
-            Ok(Vec::new())
+        match &mut self.backend {
+            #[cfg(feature = "aac-fdk")]
+            Backend::Fdk {
+                encoder,
+                output_buffer,
+            } => {
+                let info = encoder
+                    .encode(pcm_samples, output_buffer)
+                    .map_err(|_| AacEncoderError::Encoding)?;
+
+                if info.output_size > 0 {
+                    Ok(output_buffer[..info.output_size].to_vec())
+                } else {
+                    Ok(Vec::new())
+                }
+            }
+            #[cfg(feature = "aac-rs")]
+            Backend::Native(encoder) => Ok(encoder.encode(pcm_samples)),
+            #[cfg(not(any(feature = "aac-fdk", feature = "aac-rs")))]
+            Backend::Unavailable(never) => match *never {},
         }
     }
 
@@ -90,22 +255,65 @@ impl AacEncoder {
     /// returns the raw ASC bytes if available
     #[must_use]
     pub fn get_asc(&self) -> Option<Vec<u8>> {
-        if let Ok(info) = self.encoder.info() {
-            if info.confSize > 0 {
+        match &self.backend {
+            #[cfg(feature = "aac-fdk")]
+            Backend::Fdk { encoder, .. } => {
+                let info = encoder.info().ok()?;
                 // confBuf is fixed-size array in EncoderInfo
                 // We need to slice it to confSize
                 let size = info.confSize as usize;
-                if size <= info.confBuf.len() {
-                    return Some(info.confBuf[..size].to_vec());
-                }
+                (size > 0 && size <= info.confBuf.len()).then(|| info.confBuf[..size].to_vec())
             }
+            #[cfg(feature = "aac-rs")]
+            Backend::Native(encoder) => Some(encoder.audio_specific_config()),
+            #[cfg(not(any(feature = "aac-fdk", feature = "aac-rs")))]
+            Backend::Unavailable(never) => match *never {},
         }
-        None
     }
 
     /// Get frame length (samples per channel)
     #[must_use]
     pub fn get_frame_length(&self) -> Option<u32> {
-        self.encoder.info().ok().map(|info| info.frameLength)
+        match &self.backend {
+            #[cfg(feature = "aac-fdk")]
+            Backend::Fdk { encoder, .. } => encoder.info().ok().map(|info| info.frameLength),
+            #[cfg(feature = "aac-rs")]
+            Backend::Native(_) => u32::try_from(super::aac_lc::COEFFS).ok(),
+            #[cfg(not(any(feature = "aac-fdk", feature = "aac-rs")))]
+            Backend::Unavailable(never) => match *never {},
+        }
+    }
+}
+
+impl AudioEncoder for AacEncoder {
+    fn frames_per_packet(&self) -> usize {
+        self.get_frame_length().map_or(1024, |len| len as usize)
+    }
+
+    /// Encode a packet as an RFC 3640 `mpeg4-generic` payload: a single
+    /// AU header followed by the raw AAC frame
+    fn encode_packet(&mut self, pcm: &[u8], out: &mut Vec<u8>) -> Result<(), AudioEncodeError> {
+        let mut samples = std::mem::take(&mut self.samples);
+        samples.clear();
+        samples.extend(
+            pcm.chunks_exact(2)
+                .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]])),
+        );
+        let encoded = self.encode(&samples);
+        self.samples = samples;
+        let encoded = encoded.map_err(|e| AudioEncodeError::Encoding(e.to_string()))?;
+
+        // AU-headers-length: 16 bits (0x0010) = 16
+        // AU-header: size (13 bits) | index (3 bits)
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "AAC frame size fits in u16"
+        )]
+        let header = ((encoded.len() as u16) << 3) & 0xFFF8;
+        out.clear();
+        out.extend_from_slice(&[0x00, 0x10]);
+        out.extend_from_slice(&header.to_be_bytes());
+        out.extend_from_slice(&encoded);
+        Ok(())
     }
 }
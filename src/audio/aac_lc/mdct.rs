@@ -0,0 +1,149 @@
+//! Windowed MDCT for 2048-sample long blocks
+//!
+//! The block is folded into a 1024-point DCT-IV, which is computed with a
+//! 512-point complex FFT between a pre- and post-twiddle.
+
+use std::f64::consts::PI;
+
+/// Coefficients per block
+pub(crate) const COEFFS: usize = 1024;
+
+const BLOCK: usize = COEFFS * 2;
+const HALF: usize = COEFFS / 2;
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Complex {
+    re: f32,
+    im: f32,
+}
+
+impl Complex {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Twiddles are computed in f64 and stored in f32"
+    )]
+    fn from_angle(angle: f64) -> Self {
+        Self {
+            re: angle.cos() as f32,
+            im: angle.sin() as f32,
+        }
+    }
+
+    fn mul(self, other: Self) -> Self {
+        Self {
+            re: self.re * other.re - self.im * other.im,
+            im: self.re * other.im + self.im * other.re,
+        }
+    }
+}
+
+/// Forward MDCT with a sine window
+pub(crate) struct Mdct {
+    window: Vec<f32>,
+    pre: Vec<Complex>,
+    post: Vec<Complex>,
+    twiddles: Vec<Complex>,
+    bit_reverse: Vec<usize>,
+}
+
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    reason = "Table indices are far below f64 precision limits"
+)]
+impl Mdct {
+    pub(crate) fn new() -> Self {
+        let m = COEFFS as f64;
+        let window = (0..BLOCK)
+            .map(|n| (PI * (n as f64 + 0.5) / BLOCK as f64).sin() as f32)
+            .collect();
+        let pre = (0..HALF)
+            .map(|n| Complex::from_angle(-PI * (4.0 * n as f64 + 1.0) / (4.0 * m)))
+            .collect();
+        // The factor of two matches the decoder's inverse transform
+        let post = (0..HALF)
+            .map(|k| {
+                let twiddle = Complex::from_angle(-PI * k as f64 / m);
+                Complex {
+                    re: 2.0 * twiddle.re,
+                    im: 2.0 * twiddle.im,
+                }
+            })
+            .collect();
+        let twiddles = (0..HALF / 2)
+            .map(|k| Complex::from_angle(-2.0 * PI * k as f64 / HALF as f64))
+            .collect();
+        let bits = HALF.trailing_zeros();
+        let bit_reverse = (0..HALF)
+            .map(|i| i.reverse_bits() >> (usize::BITS - bits))
+            .collect();
+        Self {
+            window,
+            pre,
+            post,
+            twiddles,
+            bit_reverse,
+        }
+    }
+
+    /// Transform one block of `2 * COEFFS` samples into `COEFFS` coefficients
+    ///
+    /// The scaling matches the standard inverse transform, so a decoder
+    /// reconstructs samples at their input scale.
+    pub(crate) fn forward(&self, input: &[f32], output: &mut [f32]) {
+        debug_assert_eq!(input.len(), BLOCK);
+        debug_assert_eq!(output.len(), COEFFS);
+        let x = |n: usize| input[n] * self.window[n];
+
+        // Fold the (a, b, c, d) quarters into (-c_r - d, a - b_r)
+        let mut folded = [0.0f32; COEFFS];
+        for n in 0..HALF {
+            folded[n] = -x(COEFFS + HALF - 1 - n) - x(COEFFS + HALF + n);
+            folded[HALF + n] = x(n) - x(COEFFS - 1 - n);
+        }
+
+        let mut z = [Complex::default(); HALF];
+        for (n, slot) in z.iter_mut().enumerate() {
+            let v = Complex {
+                re: folded[2 * n],
+                im: folded[COEFFS - 1 - 2 * n],
+            };
+            *slot = v.mul(self.pre[n]);
+        }
+        self.fft(&mut z);
+
+        for (k, value) in z.iter().enumerate() {
+            let y = value.mul(self.post[k]);
+            output[2 * k] = y.re;
+            output[COEFFS - 1 - 2 * k] = -y.im;
+        }
+    }
+
+    fn fft(&self, buf: &mut [Complex; HALF]) {
+        for (i, &j) in self.bit_reverse.iter().enumerate() {
+            if i < j {
+                buf.swap(i, j);
+            }
+        }
+        let mut len = 2;
+        while len <= HALF {
+            let half = len / 2;
+            let step = HALF / len;
+            for start in (0..HALF).step_by(len) {
+                for j in 0..half {
+                    let a = buf[start + j];
+                    let b = buf[start + j + half].mul(self.twiddles[j * step]);
+                    buf[start + j] = Complex {
+                        re: a.re + b.re,
+                        im: a.im + b.im,
+                    };
+                    buf[start + j + half] = Complex {
+                        re: a.re - b.re,
+                        im: a.im - b.im,
+                    };
+                }
+            }
+            len *= 2;
+        }
+    }
+}
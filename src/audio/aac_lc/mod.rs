@@ -0,0 +1,487 @@
+//! Native AAC-LC encoder
+//!
+//! Used when the crate is built without fdk-aac. It codes long windows only
+//! with a sine window, and leaves out TNS, M/S and intensity stereo. Each
+//! band's scalefactor follows the band's energy, and a common offset is
+//! searched per channel so the frame fits the bit budget for the bitrate.
+//! Frames are raw access units, as `AirPlay` carries them.
+
+mod mdct;
+mod tables;
+
+pub(crate) use mdct::{COEFFS, Mdct};
+
+/// Largest quantized magnitude the escape codebook can carry
+const MAX_QUANT: i32 = 8191;
+
+/// Most bits one channel may use in a frame
+const MAX_CHANNEL_BITS: usize = 6144;
+
+/// Largest scalefactor difference the delta codebook can carry
+const MAX_SF_DELTA: i32 = 60;
+
+/// How closely scalefactors follow band energy, between constant noise (0)
+/// and constant SNR (1)
+const ENERGY_TRACKING: f32 = 0.5;
+
+/// Codebook carrying no spectral data
+const ZERO_HCB: u8 = 0;
+
+/// Escape codebook
+const ESC_HCB: u8 = 11;
+
+/// Native AAC-LC encoder
+pub(crate) struct LcEncoder {
+    sample_rate: u32,
+    channels: usize,
+    bands: &'static [usize],
+    /// Bands below the audio bandwidth
+    max_sfb: usize,
+    /// Bits available to each channel in a frame
+    channel_bits: usize,
+    mdct: Mdct,
+    /// Second half of the previous block for each channel
+    overlap: Vec<Vec<f32>>,
+    /// Interleaved input not yet encoded
+    pending: Vec<i16>,
+}
+
+impl LcEncoder {
+    /// Create an encoder, or `None` for a rate or channel count it cannot code
+    pub(crate) fn new(sample_rate: u32, channels: u32, bitrate: u32) -> Option<Self> {
+        let bands: &'static [usize] = match sample_rate {
+            44100 | 48000 => &tables::SWB_OFFSET_48K,
+            32000 => &tables::SWB_OFFSET_32K,
+            _ => return None,
+        };
+        if !(1..=2).contains(&channels) {
+            return None;
+        }
+
+        let frame_bits = u64::from(bitrate) * COEFFS as u64 / u64::from(sample_rate);
+        // Element headers, the END element and byte alignment
+        let overhead = 3 + 4 + u64::from(channels == 2) + 3 + 7;
+        let channel_bits = (frame_bits.saturating_sub(overhead) / u64::from(channels))
+            .try_into()
+            .unwrap_or(MAX_CHANNEL_BITS)
+            .min(MAX_CHANNEL_BITS);
+
+        let per_channel = bitrate / channels;
+        let cutoff_hz = match per_channel {
+            96_000.. => 20_000,
+            64_000.. => 17_000,
+            48_000.. => 14_000,
+            32_000.. => 11_000,
+            _ => 8_000,
+        }
+        .min(sample_rate / 2);
+        let cutoff_bin = (u64::from(cutoff_hz) * 2 * COEFFS as u64 / u64::from(sample_rate))
+            .try_into()
+            .unwrap_or(COEFFS);
+        let max_sfb = bands[..bands.len() - 1]
+            .iter()
+            .take_while(|&&start| start < cutoff_bin)
+            .count();
+
+        Some(Self {
+            sample_rate,
+            channels: channels as usize,
+            bands,
+            max_sfb,
+            channel_bits,
+            mdct: Mdct::new(),
+            overlap: vec![vec![0.0; COEFFS]; channels as usize],
+            pending: Vec::new(),
+        })
+    }
+
+    /// `AudioSpecificConfig` describing the stream
+    pub(crate) fn audio_specific_config(&self) -> Vec<u8> {
+        let freq_index = match self.sample_rate {
+            48000 => 3,
+            44100 => 4,
+            _ => 5,
+        };
+        let mut bits = BitWriter::default();
+        bits.put(2, 5); // AOT_AAC_LC
+        bits.put(freq_index, 4);
+        bits.put(if self.channels == 2 { 2 } else { 1 }, 4);
+        // frameLengthFlag, dependsOnCoreCoder, extensionFlag
+        bits.put(0, 3);
+        bits.finish()
+    }
+
+    /// Encode interleaved PCM, returning one access unit once a full frame
+    /// of input has been collected and an empty buffer until then
+    pub(crate) fn encode(&mut self, pcm: &[i16]) -> Vec<u8> {
+        self.pending.extend_from_slice(pcm);
+        let frame_samples = COEFFS * self.channels;
+        if self.pending.len() < frame_samples {
+            return Vec::new();
+        }
+        let frame: Vec<i16> = self.pending.drain(..frame_samples).collect();
+
+        let mut coded = Vec::with_capacity(self.channels);
+        let mut block = vec![0.0f32; 2 * COEFFS];
+        let mut spectrum = vec![0.0f32; COEFFS];
+        for ch in 0..self.channels {
+            block[..COEFFS].copy_from_slice(&self.overlap[ch]);
+            for (slot, &sample) in block[COEFFS..]
+                .iter_mut()
+                .zip(frame.iter().skip(ch).step_by(self.channels))
+            {
+                *slot = f32::from(sample);
+            }
+            self.overlap[ch].copy_from_slice(&block[COEFFS..]);
+            self.mdct.forward(&block, &mut spectrum);
+            coded.push(fit_to_budget(
+                &spectrum,
+                &self.bands[..=self.max_sfb],
+                self.channel_bits,
+            ));
+        }
+
+        let mut bits = BitWriter::default();
+        if let [left, right] = coded.as_slice() {
+            bits.put(1, 3); // ID_CPE
+            bits.put(0, 4); // element_instance_tag
+            bits.put(0, 1); // common_window
+            left.write(&mut bits, self.bands);
+            right.write(&mut bits, self.bands);
+        } else {
+            bits.put(0, 3); // ID_SCE
+            bits.put(0, 4);
+            coded[0].write(&mut bits, self.bands);
+        }
+        bits.put(7, 3); // ID_END
+        bits.finish()
+    }
+}
+
+/// One channel's quantized spectrum
+struct ChannelStream {
+    /// Codebook per band, `ZERO_HCB` for bands with nothing coded
+    codebooks: Vec<u8>,
+    scalefactors: Vec<i32>,
+    quantized: Vec<i32>,
+}
+
+impl ChannelStream {
+    fn global_gain(&self) -> i32 {
+        self.codebooks
+            .iter()
+            .zip(&self.scalefactors)
+            .find(|&(&cb, _)| cb != ZERO_HCB)
+            .map_or(100, |(_, &sf)| sf)
+    }
+
+    /// Bands up to the last one carrying data
+    fn max_sfb(&self) -> usize {
+        self.codebooks
+            .iter()
+            .rposition(|&cb| cb != ZERO_HCB)
+            .map_or(0, |last| last + 1)
+    }
+
+    /// Runs of bands sharing a codebook, as `(codebook, bands)`
+    fn sections(&self) -> Vec<(u8, usize)> {
+        let mut sections: Vec<(u8, usize)> = Vec::new();
+        for &cb in &self.codebooks[..self.max_sfb()] {
+            match sections.last_mut() {
+                Some((last, len)) if *last == cb => *len += 1,
+                _ => sections.push((cb, 1)),
+            }
+        }
+        sections
+    }
+
+    fn write<S: BitSink>(&self, bits: &mut S, bands: &[usize]) {
+        let max_sfb = self.max_sfb();
+        let global_gain = self.global_gain();
+
+        bits.put(u32::try_from(global_gain).unwrap_or(0), 8);
+        // ics_info: reserved bit, ONLY_LONG_SEQUENCE, sine window
+        bits.put(0, 1);
+        bits.put(0, 2);
+        bits.put(0, 1);
+        bits.put(u32::try_from(max_sfb).unwrap_or(0), 6);
+        bits.put(0, 1); // predictor_data_present
+
+        for (cb, len) in self.sections() {
+            bits.put(u32::from(cb), 4);
+            write_section_len(bits, len);
+        }
+
+        let mut last = global_gain;
+        for (&cb, &sf) in self.codebooks[..max_sfb].iter().zip(&self.scalefactors) {
+            if cb != ZERO_HCB {
+                let index = usize::try_from(sf - last + MAX_SF_DELTA).unwrap_or(0);
+                let (codes, lens) = tables::SCALEFACTOR;
+                bits.put(codes[index], u32::from(lens[index]));
+                last = sf;
+            }
+        }
+
+        // pulse_data_present, tns_data_present, gain_control_data_present
+        bits.put(0, 3);
+
+        for (band, &cb) in self.codebooks[..max_sfb].iter().enumerate() {
+            if cb != ZERO_HCB {
+                write_band(bits, cb, &self.quantized[bands[band]..bands[band + 1]]);
+            }
+        }
+    }
+}
+
+fn write_section_len<S: BitSink>(bits: &mut S, mut len: usize) {
+    while len >= 31 {
+        bits.put(31, 5);
+        len -= 31;
+    }
+    bits.put(u32::try_from(len).unwrap_or(0), 5);
+}
+
+/// Quantize a channel with the largest scalefactors that still use the budget
+fn fit_to_budget(spectrum: &[f32], bands: &[usize], budget: usize) -> ChannelStream {
+    let shape = BandShape::analyse(spectrum, bands);
+
+    // Bits fall as the offset rises; find the smallest offset that fits
+    let (mut lo, mut hi) = (-100, 256);
+    let mut best = None;
+    while lo <= hi {
+        let offset = lo + (hi - lo) / 2;
+        let stream = shape.quantize(spectrum, bands, offset);
+        if stream_bits(&stream, bands) <= budget {
+            best = Some(stream);
+            hi = offset - 1;
+        } else {
+            lo = offset + 1;
+        }
+    }
+    best.unwrap_or_else(|| shape.quantize(spectrum, bands, 256))
+}
+
+/// Per-band loudness, fixed for a frame while the offset is searched
+struct BandShape {
+    /// Scalefactor relative to the offset, `None` for silent bands
+    relative: Vec<Option<i32>>,
+    /// Smallest scalefactor keeping the band's peak codable
+    floor: Vec<i32>,
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    reason = "Scalefactors are small integers derived from band levels"
+)]
+impl BandShape {
+    fn analyse(spectrum: &[f32], bands: &[usize]) -> Self {
+        let mut relative = Vec::with_capacity(bands.len());
+        let mut floor = Vec::with_capacity(bands.len());
+        for band in bands.windows(2) {
+            let coeffs = &spectrum[band[0]..band[1]];
+            let energy: f32 = coeffs.iter().map(|c| c * c).sum();
+            let peak = coeffs.iter().fold(0.0f32, |m, c| m.max(c.abs()));
+            if peak < 0.5 {
+                relative.push(None);
+                floor.push(0);
+                continue;
+            }
+            let rms = (energy / coeffs.len() as f32).sqrt();
+            relative.push(Some((ENERGY_TRACKING * 4.0 * rms.log2()).round() as i32));
+            // |q| = (peak * 2^(-(sf - 100) / 4))^(3/4) must not exceed MAX_QUANT
+            let min_sf = 100.0 + 4.0 * peak.log2() - 16.0 / 3.0 * (MAX_QUANT as f32).log2();
+            floor.push(min_sf.ceil() as i32);
+        }
+        Self { relative, floor }
+    }
+
+    fn quantize(&self, spectrum: &[f32], bands: &[usize], offset: i32) -> ChannelStream {
+        let scalefactors: Vec<Option<i32>> = self
+            .relative
+            .iter()
+            .zip(&self.floor)
+            .map(|(&relative, &floor)| relative.map(|r| (r + offset).max(floor).clamp(0, 255)))
+            .collect();
+        // Keep every scalefactor within one delta of the largest, so any
+        // pair of coded bands can follow each other
+        let top = scalefactors.iter().flatten().copied().max().unwrap_or(0);
+        let scalefactors: Vec<i32> = scalefactors
+            .iter()
+            .map(|sf| sf.map_or(top, |sf| sf.max(top - MAX_SF_DELTA)))
+            .collect();
+
+        let mut quantized = vec![0i32; COEFFS];
+        let mut codebooks = Vec::with_capacity(scalefactors.len());
+        for (band, &sf) in bands.windows(2).zip(&scalefactors) {
+            let out = &mut quantized[band[0]..band[1]];
+            let step = 2.0f32.powf(-0.1875 * (sf - 100) as f32);
+            for (q, &c) in out.iter_mut().zip(&spectrum[band[0]..band[1]]) {
+                let magnitude = ((c.abs().powf(0.75) * step + 0.4054) as i32).min(MAX_QUANT);
+                *q = if c < 0.0 { -magnitude } else { magnitude };
+            }
+            codebooks.push(best_codebook(out).0);
+        }
+
+        ChannelStream {
+            codebooks,
+            scalefactors,
+            quantized,
+        }
+    }
+}
+
+/// Bits a channel stream takes, element headers excluded
+fn stream_bits(stream: &ChannelStream, bands: &[usize]) -> usize {
+    let mut counter = BitCounter::default();
+    stream.write(&mut counter, bands);
+    counter.0
+}
+
+/// Cheapest codebook for a band and the bits it takes
+fn best_codebook(values: &[i32]) -> (u8, usize) {
+    let peak = values.iter().map(|v| v.unsigned_abs()).max().unwrap_or(0);
+    if peak == 0 {
+        return (ZERO_HCB, 0);
+    }
+    (1..=ESC_HCB)
+        .filter(|&cb| peak <= largest_value(cb))
+        .map(|cb| {
+            let mut counter = BitCounter::default();
+            write_band(&mut counter, cb, values);
+            (cb, counter.0)
+        })
+        .min_by_key(|&(_, bits)| bits)
+        .unwrap_or((ESC_HCB, usize::MAX))
+}
+
+/// Largest magnitude a spectral codebook can carry
+fn largest_value(cb: u8) -> u32 {
+    match cb {
+        1 | 2 => 1,
+        3 | 4 => 2,
+        5 | 6 => 4,
+        7 | 8 => 7,
+        9 | 10 => 12,
+        _ => MAX_QUANT.unsigned_abs(),
+    }
+}
+
+/// Write a band's values with codebook `cb`, which must be able to carry them
+fn write_band<S: BitSink>(bits: &mut S, cb: u8, values: &[i32]) {
+    let (codes, lens) = tables::SPECTRUM[usize::from(cb) - 1];
+    let put_code = |bits: &mut S, index: u32| {
+        let index = index as usize;
+        bits.put(codes[index], u32::from(lens[index]));
+    };
+    match cb {
+        1 | 2 => {
+            for quad in values.chunks_exact(4) {
+                let index = quad.iter().fold(0, |index, &v| {
+                    index * 3 + v.saturating_add(1).unsigned_abs()
+                });
+                put_code(bits, index);
+            }
+        }
+        3 | 4 => {
+            for quad in values.chunks_exact(4) {
+                let index = quad
+                    .iter()
+                    .fold(0, |index, &v| index * 3 + v.unsigned_abs());
+                put_code(bits, index);
+                write_signs(bits, quad);
+            }
+        }
+        5 | 6 => {
+            for pair in values.chunks_exact(2) {
+                let index = (pair[0] + 4).unsigned_abs() * 9 + (pair[1] + 4).unsigned_abs();
+                put_code(bits, index);
+            }
+        }
+        7..=10 => {
+            let modulus = if cb <= 8 { 8 } else { 13 };
+            for pair in values.chunks_exact(2) {
+                let index = pair[0].unsigned_abs() * modulus + pair[1].unsigned_abs();
+                put_code(bits, index);
+                write_signs(bits, pair);
+            }
+        }
+        _ => {
+            for pair in values.chunks_exact(2) {
+                let (y, z) = (pair[0].unsigned_abs(), pair[1].unsigned_abs());
+                put_code(bits, y.min(16) * 17 + z.min(16));
+                write_signs(bits, pair);
+                write_escape(bits, y);
+                write_escape(bits, z);
+            }
+        }
+    }
+}
+
+/// One sign bit per nonzero value, set for negative values
+fn write_signs<S: BitSink>(bits: &mut S, values: &[i32]) {
+    for &v in values {
+        if v != 0 {
+            bits.put(u32::from(v < 0), 1);
+        }
+    }
+}
+
+/// Escape sequence for a magnitude of 16 or more
+fn write_escape<S: BitSink>(bits: &mut S, value: u32) {
+    if value < 16 {
+        return;
+    }
+    // value = 2^(n + 4) + word, sent as n ones, a zero and n + 4 bits of word
+    let n = value.ilog2() - 4;
+    bits.put((1 << (n + 1)) - 2, n + 1);
+    bits.put(value - (1 << (n + 4)), n + 4);
+}
+
+/// Destination for coded bits, so sizes can be measured without writing
+trait BitSink {
+    fn put(&mut self, value: u32, bits: u32);
+}
+
+#[derive(Default)]
+struct BitCounter(usize);
+
+impl BitSink for BitCounter {
+    fn put(&mut self, _value: u32, bits: u32) {
+        self.0 += bits as usize;
+    }
+}
+
+/// MSB-first bit writer
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u64,
+    used: u32,
+}
+
+impl BitSink for BitWriter {
+    fn put(&mut self, value: u32, bits: u32) {
+        if bits == 0 {
+            return;
+        }
+        self.acc = (self.acc << bits) | u64::from(value & (u32::MAX >> (32 - bits)));
+        self.used += bits;
+        while self.used >= 8 {
+            self.used -= 8;
+            #[allow(clippy::cast_possible_truncation, reason = "Masked to one byte")]
+            self.bytes.push((self.acc >> self.used) as u8);
+        }
+    }
+}
+
+impl BitWriter {
+    /// Pad to a byte boundary and return the bytes
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            let pad = 8 - self.used;
+            self.put(0, pad);
+        }
+        self.bytes
+    }
+}
@@ -0,0 +1,331 @@
+//! DSP stages applied to streamed audio
+//!
+//! A [`DspChain`] runs a list of [`DspStage`]s over each packet of
+//! interleaved f32 frames just before it is encoded. [`ParametricEq`] is a
+//! built-in stage: a bank of RBJ biquad filters, by default ten peaking bands
+//! an octave apart.
+
+use super::SampleFormat;
+use super::convert::{from_f32, to_f32};
+
+/// A processing stage run over interleaved f32 frames before encoding
+///
+/// Samples are nominally in -1.0..=1.0 and are clamped when converted back
+/// to the stream format.
+pub trait DspStage: Send {
+    /// Process `frames` in place
+    fn process(&mut self, frames: &mut [f32], channels: usize, sample_rate: u32);
+
+    /// Clear any filter state, e.g. after a seek
+    fn reset(&mut self) {}
+}
+
+/// An ordered list of DSP stages
+#[derive(Default)]
+pub struct DspChain {
+    stages: Vec<Box<dyn DspStage>>,
+}
+
+impl DspChain {
+    /// Create an empty chain, which leaves audio untouched
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder method to append a stage
+    #[must_use]
+    pub fn with<S: DspStage + 'static>(mut self, stage: S) -> Self {
+        self.push(stage);
+        self
+    }
+
+    /// Append a stage
+    pub fn push<S: DspStage + 'static>(&mut self, stage: S) {
+        self.stages.push(Box::new(stage));
+    }
+
+    /// Number of stages
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Whether the chain has no stages
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.stages.is_empty()
+    }
+
+    /// Run the chain over interleaved little-endian PCM in place
+    pub fn process_pcm(
+        &mut self,
+        data: &mut [u8],
+        format: SampleFormat,
+        channels: usize,
+        sample_rate: u32,
+    ) {
+        if self.stages.is_empty() {
+            return;
+        }
+        let mut frames = to_f32(data, format);
+        self.process(&mut frames, channels, sample_rate);
+        let processed = from_f32(&frames, format);
+        data[..processed.len()].copy_from_slice(&processed);
+    }
+}
+
+impl DspStage for DspChain {
+    fn process(&mut self, frames: &mut [f32], channels: usize, sample_rate: u32) {
+        for stage in &mut self.stages {
+            stage.process(frames, channels, sample_rate);
+        }
+    }
+
+    fn reset(&mut self) {
+        for stage in &mut self.stages {
+            stage.reset();
+        }
+    }
+}
+
+impl std::fmt::Debug for DspChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DspChain")
+            .field("stages", &self.stages.len())
+            .finish()
+    }
+}
+
+/// Shape of an EQ band
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterKind {
+    /// Bell around the centre frequency
+    Peaking,
+    /// Shelf below the corner frequency
+    LowShelf,
+    /// Shelf above the corner frequency
+    HighShelf,
+}
+
+/// One band of a [`ParametricEq`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqBand {
+    /// Filter shape
+    pub kind: FilterKind,
+    /// Centre or corner frequency (Hz)
+    pub frequency: f32,
+    /// Gain (dB); 0 leaves the band flat
+    pub gain_db: f32,
+    /// Quality factor; higher is narrower
+    pub q: f32,
+}
+
+impl EqBand {
+    /// A peaking band
+    #[must_use]
+    pub fn peaking(frequency: f32, gain_db: f32, q: f32) -> Self {
+        Self {
+            kind: FilterKind::Peaking,
+            frequency,
+            gain_db,
+            q,
+        }
+    }
+}
+
+/// Normalized biquad coefficients
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Coefficients {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Coefficients {
+    /// Coefficients for `band`, or `None` if it leaves audio unchanged or
+    /// lies above Nyquist
+    ///
+    /// From the RBJ Audio EQ Cookbook.
+    fn design(band: &EqBand, sample_rate: u32) -> Option<Self> {
+        let rate = f64::from(sample_rate);
+        let frequency = f64::from(band.frequency);
+        if band.gain_db.abs() < 0.01 || frequency <= 0.0 || frequency >= rate / 2.0 {
+            return None;
+        }
+        let a = 10f64.powf(f64::from(band.gain_db) / 40.0);
+        let w0 = 2.0 * std::f64::consts::PI * frequency / rate;
+        let (sin, cos) = w0.sin_cos();
+        let alpha = sin / (2.0 * f64::from(band.q.max(0.01)));
+        let shelf = 2.0 * a.sqrt() * alpha;
+
+        let (b, a0, a1, a2) = match band.kind {
+            FilterKind::Peaking => (
+                [1.0 + alpha * a, -2.0 * cos, 1.0 - alpha * a],
+                1.0 + alpha / a,
+                -2.0 * cos,
+                1.0 - alpha / a,
+            ),
+            FilterKind::LowShelf => (
+                [
+                    a * ((a + 1.0) - (a - 1.0) * cos + shelf),
+                    2.0 * a * ((a - 1.0) - (a + 1.0) * cos),
+                    a * ((a + 1.0) - (a - 1.0) * cos - shelf),
+                ],
+                (a + 1.0) + (a - 1.0) * cos + shelf,
+                -2.0 * ((a - 1.0) + (a + 1.0) * cos),
+                (a + 1.0) + (a - 1.0) * cos - shelf,
+            ),
+            FilterKind::HighShelf => (
+                [
+                    a * ((a + 1.0) + (a - 1.0) * cos + shelf),
+                    -2.0 * a * ((a - 1.0) + (a + 1.0) * cos),
+                    a * ((a + 1.0) + (a - 1.0) * cos - shelf),
+                ],
+                (a + 1.0) - (a - 1.0) * cos + shelf,
+                2.0 * ((a - 1.0) - (a + 1.0) * cos),
+                (a + 1.0) - (a - 1.0) * cos - shelf,
+            ),
+        };
+        Some(Self {
+            b: b.map(|b| b / a0),
+            a: [a1 / a0, a2 / a0],
+        })
+    }
+}
+
+/// Multi-band parametric equalizer
+///
+/// Filters are designed lazily for the stream's sample rate, and redesigned
+/// when a band changes.
+#[derive(Debug, Clone)]
+pub struct ParametricEq {
+    bands: Vec<EqBand>,
+    /// Designed filters, one per band
+    filters: Vec<Option<Coefficients>>,
+    /// Transposed direct form II state per band and channel
+    state: Vec<Vec<[f64; 2]>>,
+    /// Sample rate and channel count the filters were set up for
+    layout: Option<(u32, usize)>,
+}
+
+impl ParametricEq {
+    /// Centre frequencies of the ten-band graphic layout (Hz)
+    pub const TEN_BAND_FREQUENCIES: [f32; 10] = [
+        31.25, 62.5, 125.0, 250.0, 500.0, 1000.0, 2000.0, 4000.0, 8000.0, 16000.0,
+    ];
+
+    /// Create an equalizer with the given bands
+    #[must_use]
+    pub fn new(bands: Vec<EqBand>) -> Self {
+        Self {
+            bands,
+            filters: Vec::new(),
+            state: Vec::new(),
+            layout: None,
+        }
+    }
+
+    /// Ten flat peaking bands an octave apart, 31 Hz to 16 kHz
+    #[must_use]
+    pub fn ten_band() -> Self {
+        Self::new(
+            Self::TEN_BAND_FREQUENCIES
+                .iter()
+                .map(|&frequency| EqBand::peaking(frequency, 0.0, std::f32::consts::SQRT_2))
+                .collect(),
+        )
+    }
+
+    /// Ten-band equalizer with the given gains (dB), lowest band first
+    #[must_use]
+    pub fn with_gains(gains_db: [f32; 10]) -> Self {
+        let mut eq = Self::ten_band();
+        for (band, gain_db) in eq.bands.iter_mut().zip(gains_db) {
+            band.gain_db = gain_db;
+        }
+        eq
+    }
+
+    /// The bands
+    #[must_use]
+    pub fn bands(&self) -> &[EqBand] {
+        &self.bands
+    }
+
+    /// Set the gain (dB) of band `index`; returns false if there is no
+    /// such band
+    pub fn set_gain(&mut self, index: usize, gain_db: f32) -> bool {
+        let Some(band) = self.bands.get_mut(index) else {
+            return false;
+        };
+        band.gain_db = gain_db;
+        self.layout = None;
+        true
+    }
+
+    /// Replace band `index`; returns false if there is no such band
+    pub fn set_band(&mut self, index: usize, band: EqBand) -> bool {
+        let Some(slot) = self.bands.get_mut(index) else {
+            return false;
+        };
+        *slot = band;
+        self.layout = None;
+        true
+    }
+
+    fn prepare(&mut self, channels: usize, sample_rate: u32) {
+        if self.layout == Some((sample_rate, channels)) {
+            return;
+        }
+        let filters: Vec<_> = self
+            .bands
+            .iter()
+            .map(|band| Coefficients::design(band, sample_rate))
+            .collect();
+        // Keep the state of unchanged filters so retuning one band is seamless
+        if self.layout.map(|(_, c)| c) != Some(channels) || self.filters.len() != filters.len() {
+            self.state = vec![vec![[0.0; 2]; channels]; filters.len()];
+        } else {
+            for ((old, new), state) in self.filters.iter().zip(&filters).zip(&mut self.state) {
+                if old.is_none() && new.is_some() {
+                    state.fill([0.0; 2]);
+                }
+            }
+        }
+        self.filters = filters;
+        self.layout = Some((sample_rate, channels));
+    }
+}
+
+impl DspStage for ParametricEq {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Filter output is converted back to the sample type"
+    )]
+    fn process(&mut self, frames: &mut [f32], channels: usize, sample_rate: u32) {
+        if channels == 0 {
+            return;
+        }
+        self.prepare(channels, sample_rate);
+        for (filter, state) in self.filters.iter().zip(&mut self.state) {
+            let Some(c) = filter else {
+                continue;
+            };
+            for frame in frames.chunks_exact_mut(channels) {
+                for (sample, z) in frame.iter_mut().zip(state.iter_mut()) {
+                    let x = f64::from(*sample);
+                    let y = c.b[0] * x + z[0];
+                    z[0] = c.b[1] * x - c.a[0] * y + z[1];
+                    z[1] = c.b[2] * x - c.a[1] * y;
+                    *sample = y as f32;
+                }
+            }
+        }
+    }
+
+    fn reset(&mut self) {
+        for state in &mut self.state {
+            state.fill([0.0; 2]);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Packet encoder abstraction
+//!
+//! [`PcmStreamer`](crate::streaming::PcmStreamer) turns the stream into RTP
+//! packets through an [`AudioEncoder`]. The built-in ALAC and AAC encoders
+//! implement it, and an application can plug in its own, such as a licensed
+//! AAC encoder or an experimental codec, with
+//! [`PcmStreamer::use_encoder`](crate::streaming::PcmStreamer::use_encoder).
+
+pub use super::raop_encoder::AudioEncodeError;
+
+/// Encodes packets of PCM into RTP payloads
+///
+/// Input is interleaved 16-bit little-endian PCM in the stream's format,
+/// [`frames_per_packet`](Self::frames_per_packet) frames at a time. Each call
+/// to [`encode_packet`](Self::encode_packet) yields the payload of one RTP
+/// packet, before encryption.
+pub trait AudioEncoder: Send {
+    /// Frames of PCM per packet
+    fn frames_per_packet(&self) -> usize;
+
+    /// Encode one packet of PCM into `out`, replacing its contents
+    ///
+    /// # Errors
+    /// Returns error if `pcm` is not a packet this encoder accepts or
+    /// encoding fails
+    fn encode_packet(&mut self, pcm: &[u8], out: &mut Vec<u8>) -> Result<(), AudioEncodeError>;
+
+    /// Drain payloads still held by the encoder at the end of the stream
+    ///
+    /// # Errors
+    /// Returns error if encoding the remaining audio fails
+    fn flush(&mut self) -> Result<Vec<Vec<u8>>, AudioEncodeError> {
+        Ok(Vec::new())
+    }
+
+    /// Drop buffered audio so the next packet starts afresh, e.g. after a
+    /// seek
+    fn reset(&mut self) {}
+}
+
+impl<E: AudioEncoder + ?Sized> AudioEncoder for Box<E> {
+    fn frames_per_packet(&self) -> usize {
+        (**self).frames_per_packet()
+    }
+
+    fn encode_packet(&mut self, pcm: &[u8], out: &mut Vec<u8>) -> Result<(), AudioEncodeError> {
+        (**self).encode_packet(pcm, out)
+    }
+
+    fn flush(&mut self) -> Result<Vec<Vec<u8>>, AudioEncodeError> {
+        (**self).flush()
+    }
+
+    fn reset(&mut self) {
+        (**self).reset();
+    }
+}
@@ -172,6 +172,24 @@ impl AudioFormat {
     pub fn duration_to_bytes(self, duration: std::time::Duration) -> usize {
         self.duration_to_frames(duration) * self.bytes_per_frame()
     }
+
+    /// `audioFormat` SETUP bit for streaming this format as `codec`
+    ///
+    /// `None` if `AirPlay` defines no bit for the combination, which covers
+    /// every layout above stereo and 32-bit samples.
+    #[must_use]
+    pub fn audio_format_mask(self, codec: AudioCodec) -> Option<u64> {
+        if matches!(self.sample_format, SampleFormat::I32 | SampleFormat::F32) {
+            return None;
+        }
+        AudioFormatDescriptor {
+            codec: codec.stream_codec(),
+            sample_rate: self.sample_rate.as_u32(),
+            bits_per_sample: self.sample_format.bits_per_sample(),
+            channels: self.channels.channels(),
+        }
+        .to_mask()
+    }
 }
 
 impl Default for AudioFormat {
@@ -195,6 +213,32 @@ pub enum AudioCodec {
     Opus,
 }
 
+impl AudioCodec {
+    /// Codec as carried in the SETUP `ct` field
+    #[must_use]
+    pub fn stream_codec(self) -> StreamCodec {
+        match self {
+            Self::Pcm => StreamCodec::Pcm,
+            Self::Alac => StreamCodec::Alac,
+            Self::Aac => StreamCodec::AacLc,
+            Self::AacEld => StreamCodec::AacEld,
+            Self::Opus => StreamCodec::Opus,
+        }
+    }
+
+    /// Whether the encoder for this codec takes samples of `format`
+    ///
+    /// Only PCM is sent at 24 bits; the ALAC and AAC encoders take 16-bit
+    /// input.
+    #[must_use]
+    pub fn encodes(self, format: SampleFormat) -> bool {
+        match self {
+            Self::Pcm => matches!(format, SampleFormat::I16 | SampleFormat::I24),
+            Self::Alac | Self::Aac | Self::AacEld | Self::Opus => format == SampleFormat::I16,
+        }
+    }
+}
+
 /// Codec-specific parameters
 #[derive(Debug, Clone)]
 pub enum CodecParams {
@@ -231,3 +275,138 @@ pub enum AacProfile {
     /// High Efficiency v2 (SBR + PS)
     HeV2,
 }
+
+/// Codec carried by an audio stream, from the SETUP `ct` field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamCodec {
+    /// Linear PCM (ct = 0x1)
+    Pcm,
+    /// Apple Lossless (ct = 0x2)
+    Alac,
+    /// AAC Low Complexity (ct = 0x4)
+    AacLc,
+    /// AAC Enhanced Low Delay (ct = 0x8)
+    AacEld,
+    /// Opus (ct = 0x20)
+    Opus,
+    /// Unknown compression type
+    Unknown(u32),
+}
+
+impl StreamCodec {
+    /// Map a SETUP `ct` (compression type) value to a codec
+    #[must_use]
+    pub fn from_compression_type(ct: u32) -> Self {
+        match ct {
+            0x1 => Self::Pcm,
+            0x2 => Self::Alac,
+            0x4 => Self::AacLc,
+            0x8 => Self::AacEld,
+            0x20 => Self::Opus,
+            other => Self::Unknown(other),
+        }
+    }
+
+    /// The SETUP `ct` value for this codec
+    #[must_use]
+    pub fn compression_type(self) -> u32 {
+        match self {
+            Self::Pcm => 0x1,
+            Self::Alac => 0x2,
+            Self::AacLc => 0x4,
+            Self::AacEld => 0x8,
+            Self::Opus => 0x20,
+            Self::Unknown(ct) => ct,
+        }
+    }
+
+    /// Samples per packet the codec uses when the sender does not say
+    #[must_use]
+    pub fn default_frames_per_packet(self) -> u32 {
+        match self {
+            Self::AacLc => 1024,
+            Self::AacEld | Self::Opus => 480,
+            Self::Pcm | Self::Alac | Self::Unknown(_) => 352,
+        }
+    }
+}
+
+/// Concrete format described by a single `audioFormat` bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioFormatDescriptor {
+    /// Codec
+    pub codec: StreamCodec,
+    /// Sample rate (Hz)
+    pub sample_rate: u32,
+    /// Bits per sample
+    pub bits_per_sample: u8,
+    /// Channels
+    pub channels: u8,
+}
+
+/// `audioFormat` bit table: (bit, codec, sample rate, bits per sample, channels)
+const AUDIO_FORMAT_BITS: &[(u32, StreamCodec, u32, u8, u8)] = &[
+    (2, StreamCodec::Pcm, 8000, 16, 1),
+    (3, StreamCodec::Pcm, 8000, 16, 2),
+    (4, StreamCodec::Pcm, 16000, 16, 1),
+    (5, StreamCodec::Pcm, 16000, 16, 2),
+    (6, StreamCodec::Pcm, 24000, 16, 1),
+    (7, StreamCodec::Pcm, 24000, 16, 2),
+    (8, StreamCodec::Pcm, 32000, 16, 1),
+    (9, StreamCodec::Pcm, 32000, 16, 2),
+    (10, StreamCodec::Pcm, 44100, 16, 1),
+    (11, StreamCodec::Pcm, 44100, 16, 2),
+    (12, StreamCodec::Pcm, 44100, 24, 1),
+    (13, StreamCodec::Pcm, 44100, 24, 2),
+    (14, StreamCodec::Pcm, 48000, 16, 1),
+    (15, StreamCodec::Pcm, 48000, 16, 2),
+    (16, StreamCodec::Pcm, 48000, 24, 1),
+    (17, StreamCodec::Pcm, 48000, 24, 2),
+    (18, StreamCodec::Alac, 44100, 16, 2),
+    (19, StreamCodec::Alac, 44100, 24, 2),
+    (20, StreamCodec::Alac, 48000, 16, 2),
+    (21, StreamCodec::Alac, 48000, 24, 2),
+    (22, StreamCodec::AacLc, 44100, 16, 2),
+    (23, StreamCodec::AacLc, 48000, 16, 2),
+    (24, StreamCodec::AacEld, 44100, 16, 2),
+    (25, StreamCodec::AacEld, 48000, 16, 2),
+    (26, StreamCodec::AacEld, 16000, 16, 1),
+    (27, StreamCodec::AacEld, 24000, 16, 1),
+    (28, StreamCodec::Opus, 16000, 16, 1),
+    (29, StreamCodec::Opus, 24000, 16, 1),
+    (30, StreamCodec::Opus, 48000, 16, 1),
+    (31, StreamCodec::AacEld, 44100, 16, 1),
+    (32, StreamCodec::AacEld, 48000, 16, 1),
+];
+
+impl AudioFormatDescriptor {
+    /// Decode an `audioFormat` value
+    ///
+    /// Senders set exactly one bit; if several are set the lowest wins.
+    #[must_use]
+    pub fn from_mask(mask: u64) -> Option<Self> {
+        AUDIO_FORMAT_BITS
+            .iter()
+            .find(|(bit, ..)| mask & (1u64 << bit) != 0)
+            .map(|&(_, codec, sample_rate, bits_per_sample, channels)| Self {
+                codec,
+                sample_rate,
+                bits_per_sample,
+                channels,
+            })
+    }
+
+    /// Encode back to the `audioFormat` bit
+    #[must_use]
+    pub fn to_mask(&self) -> Option<u64> {
+        AUDIO_FORMAT_BITS
+            .iter()
+            .find(|&&(_, codec, sr, ss, ch)| {
+                codec == self.codec
+                    && sr == self.sample_rate
+                    && ss == self.bits_per_sample
+                    && ch == self.channels
+            })
+            .map(|(bit, ..)| 1u64 << bit)
+    }
+}
@@ -0,0 +1,289 @@
+//! Software gain applied to streamed PCM
+//!
+//! Device volume changes take effect on the receiver and are heard as a
+//! step. For ducking (lowering music under a doorbell chime or a voice
+//! assistant reply) the sender scales the samples itself, fading between
+//! levels so the change is smooth.
+//!
+//! A persistent digital volume sits alongside the ducking envelope and the
+//! two multiply, so an app can trim its own output without touching the
+//! receiver's volume and still duck on top of it.
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::format::SampleFormat;
+
+/// Default fade time when ducking or restoring
+pub const DUCK_FADE: Duration = Duration::from_millis(250);
+
+/// A gain envelope: fade from `from` to `level`, hold, then fade back to unity
+#[derive(Debug, Clone, Copy)]
+struct Envelope {
+    from: f32,
+    level: f32,
+    start: Instant,
+    fade: Duration,
+    /// How long to hold `level` before restoring; `None` holds until changed
+    hold: Option<Duration>,
+}
+
+impl Envelope {
+    fn gain_at(&self, now: Instant) -> f32 {
+        let t = now.saturating_duration_since(self.start);
+        if t < self.fade {
+            return lerp(
+                self.from,
+                self.level,
+                t.as_secs_f32() / self.fade.as_secs_f32(),
+            );
+        }
+        let Some(hold) = self.hold else {
+            return self.level;
+        };
+        let release = self.fade + hold;
+        if t < release {
+            self.level
+        } else if t < release + self.fade {
+            lerp(
+                self.level,
+                1.0,
+                t.saturating_sub(release).as_secs_f32() / self.fade.as_secs_f32(),
+            )
+        } else {
+            1.0
+        }
+    }
+
+    fn is_finished(&self, now: Instant) -> bool {
+        self.hold
+            .is_some_and(|hold| now.saturating_duration_since(self.start) >= hold + self.fade * 2)
+    }
+}
+
+/// A fade of the digital volume from `from` to `to`
+#[derive(Debug, Clone, Copy)]
+struct Level {
+    from: f32,
+    to: f32,
+    start: Instant,
+    fade: Duration,
+}
+
+impl Level {
+    fn gain_at(&self, now: Instant) -> f32 {
+        let t = now.saturating_duration_since(self.start);
+        if t < self.fade {
+            lerp(
+                self.from,
+                self.to,
+                t.as_secs_f32() / self.fade.as_secs_f32(),
+            )
+        } else {
+            self.to
+        }
+    }
+
+    #[allow(clippy::float_cmp, reason = "Unity is stored exactly, never computed")]
+    fn is_unity(&self, now: Instant) -> bool {
+        self.to == 1.0 && now.saturating_duration_since(self.start) >= self.fade
+    }
+}
+
+fn lerp(from: f32, to: f32, t: f32) -> f32 {
+    from + (to - from) * t.clamp(0.0, 1.0)
+}
+
+/// Time-varying software gain
+///
+/// Shared between the controller that ducks and the streamer that applies
+/// it. At unity the samples are passed through untouched.
+#[derive(Debug, Default)]
+pub struct SoftGain {
+    envelope: Mutex<Option<Envelope>>,
+    level: Mutex<Option<Level>>,
+}
+
+impl SoftGain {
+    /// Create a gain at unity
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fade down to `level` (0.0 - 1.0), hold for `duration`, then fade back
+    ///
+    /// Pass `None` to hold until [`restore`](Self::restore). Ducking again
+    /// while ducked starts from the current gain, so there is no jump.
+    pub fn duck(&self, level: f32, duration: Option<Duration>, fade: Duration) {
+        let now = Instant::now();
+        let mut envelope = self.lock();
+        let from = envelope.as_ref().map_or(1.0, |e| e.gain_at(now));
+        *envelope = Some(Envelope {
+            from,
+            level: level.clamp(0.0, 1.0),
+            start: now,
+            fade,
+            hold: duration,
+        });
+    }
+
+    /// Fade back to unity from wherever the gain is now
+    pub fn restore(&self, fade: Duration) {
+        let now = Instant::now();
+        let mut envelope = self.lock();
+        if let Some(current) = envelope.as_ref() {
+            let from = current.gain_at(now);
+            *envelope = Some(Envelope {
+                from,
+                level: 1.0,
+                start: now,
+                fade,
+                hold: Some(Duration::ZERO),
+            });
+        }
+    }
+
+    /// Fade the digital volume to `level` (0.0 - 1.0)
+    ///
+    /// The level stays until changed and multiplies with any ducking.
+    pub fn set_level(&self, level: f32, fade: Duration) {
+        let now = Instant::now();
+        let mut current = self.lock_level();
+        let from = current.as_ref().map_or(1.0, |l| l.gain_at(now));
+        *current = Some(Level {
+            from,
+            to: level.clamp(0.0, 1.0),
+            start: now,
+            fade,
+        });
+    }
+
+    /// Digital volume being faded to, 1.0 when unset
+    #[must_use]
+    pub fn level(&self) -> f32 {
+        self.lock_level().as_ref().map_or(1.0, |l| l.to)
+    }
+
+    /// Gain at a point in time
+    #[must_use]
+    pub fn gain_at(&self, now: Instant) -> f32 {
+        let duck = self.lock().as_ref().map_or(1.0, |e| e.gain_at(now));
+        let level = self.lock_level().as_ref().map_or(1.0, |l| l.gain_at(now));
+        duck * level
+    }
+
+    /// Whether the gain is below unity, or will be, at a point in time
+    #[must_use]
+    pub fn is_ducked(&self, now: Instant) -> bool {
+        self.lock().as_ref().is_some_and(|e| !e.is_finished(now))
+    }
+
+    /// Scale interleaved PCM covering `span` of audio starting at `now`
+    ///
+    /// The gain is interpolated per frame across the buffer, so a fade is
+    /// smooth within a packet. Returns `false` if the samples were left
+    /// untouched.
+    pub fn apply(
+        &self,
+        data: &mut [u8],
+        format: SampleFormat,
+        channels: usize,
+        now: Instant,
+        span: Duration,
+    ) -> bool {
+        let duck = {
+            let mut envelope = self.lock();
+            if envelope.as_ref().is_some_and(|e| e.is_finished(now)) {
+                *envelope = None;
+            }
+            envelope.map(|e| (e.gain_at(now), e.gain_at(now + span)))
+        };
+        let level = {
+            let mut level = self.lock_level();
+            if level.as_ref().is_some_and(|l| l.is_unity(now)) {
+                *level = None;
+            }
+            level.map(|l| (l.gain_at(now), l.gain_at(now + span)))
+        };
+        let (start, end) = match (duck, level) {
+            (None, None) => return false,
+            (Some(gain), None) | (None, Some(gain)) => gain,
+            (Some(duck), Some(level)) => (duck.0 * level.0, duck.1 * level.1),
+        };
+
+        let frame_bytes = format.bytes_per_sample() * channels.max(1);
+        let frames = data.len() / frame_bytes;
+        if frames == 0 {
+            return false;
+        }
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "Frame counts per packet are small"
+        )]
+        let step = (end - start) / frames as f32;
+
+        let mut gain = start;
+        for frame in data.chunks_exact_mut(frame_bytes) {
+            for sample in frame.chunks_exact_mut(format.bytes_per_sample()) {
+                scale_sample(sample, format, gain);
+            }
+            gain += step;
+        }
+        true
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Envelope>> {
+        self.envelope
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn lock_level(&self) -> std::sync::MutexGuard<'_, Option<Level>> {
+        self.level
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Scale every whole sample in `data` by a fixed `gain`
+pub(crate) fn scale_samples(data: &mut [u8], format: SampleFormat, gain: f32) {
+    if (gain - 1.0).abs() < f32::EPSILON {
+        return;
+    }
+    for sample in data.chunks_exact_mut(format.bytes_per_sample()) {
+        scale_sample(sample, format, gain);
+    }
+}
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    reason = "Scaled samples are clamped to the sample range"
+)]
+fn scale_sample(sample: &mut [u8], format: SampleFormat, gain: f32) {
+    match format {
+        SampleFormat::I16 => {
+            let value = f32::from(i16::from_le_bytes([sample[0], sample[1]])) * gain;
+            let scaled = value.clamp(f32::from(i16::MIN), f32::from(i16::MAX)) as i16;
+            sample.copy_from_slice(&scaled.to_le_bytes());
+        }
+        SampleFormat::I24 => {
+            let value = (i32::from_le_bytes([0, sample[0], sample[1], sample[2]]) >> 8) as f32;
+            let scaled = (value * gain).clamp(-8_388_608.0, 8_388_607.0) as i32;
+            sample.copy_from_slice(&scaled.to_le_bytes()[..3]);
+        }
+        SampleFormat::I32 => {
+            let value = f64::from(i32::from_le_bytes([
+                sample[0], sample[1], sample[2], sample[3],
+            ]));
+            let scaled =
+                (value * f64::from(gain)).clamp(f64::from(i32::MIN), f64::from(i32::MAX)) as i32;
+            sample.copy_from_slice(&scaled.to_le_bytes());
+        }
+        SampleFormat::F32 => {
+            let value = f32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]);
+            sample.copy_from_slice(&(value * gain).to_le_bytes());
+        }
+    }
+}
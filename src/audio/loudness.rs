@@ -0,0 +1,317 @@
+//! Loudness measurement and `ReplayGain`
+//!
+//! [`LoudnessMeter`] measures integrated loudness per EBU R128 (ITU-R
+//! BS.1770): K-weighted mean square over 400 ms blocks with 75% overlap,
+//! gated at -70 LUFS and 10 LU below the ungated mean. [`ReplayGain`] holds
+//! gains read from tags or computed from a measurement.
+
+use super::SampleFormat;
+
+/// `ReplayGain` 2.0 reference loudness (LUFS)
+pub const REPLAY_GAIN_REFERENCE_LUFS: f64 = -18.0;
+
+/// EBU R128 broadcast reference loudness (LUFS)
+pub const EBU_R128_REFERENCE_LUFS: f64 = -23.0;
+
+/// Which `ReplayGain` value to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GainMode {
+    /// Per-track gain, for shuffled or mixed-source queues
+    #[default]
+    Track,
+    /// Per-album gain, keeping level differences between album tracks
+    Album,
+}
+
+/// `ReplayGain` values for a track
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ReplayGain {
+    /// Track gain (dB)
+    pub track_gain_db: f32,
+    /// Track sample peak, 1.0 = full scale
+    pub track_peak: Option<f32>,
+    /// Album gain (dB)
+    pub album_gain_db: Option<f32>,
+    /// Album sample peak, 1.0 = full scale
+    pub album_peak: Option<f32>,
+}
+
+impl ReplayGain {
+    /// Gain from a track gain in dB
+    #[must_use]
+    pub fn track(gain_db: f32) -> Self {
+        Self {
+            track_gain_db: gain_db,
+            ..Self::default()
+        }
+    }
+
+    /// Gain bringing a measured track to the `ReplayGain` reference
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Gains are a few tens of dB"
+    )]
+    pub fn from_loudness(loudness: &Loudness) -> Option<Self> {
+        let lufs = loudness.integrated_lufs?;
+        Some(Self {
+            track_gain_db: (REPLAY_GAIN_REFERENCE_LUFS - lufs) as f32,
+            track_peak: Some(loudness.peak),
+            ..Self::default()
+        })
+    }
+
+    /// Gain from an Opus `R128_TRACK_GAIN` tag
+    ///
+    /// The tag is a Q7.8 gain relative to -23 LUFS; it is rebased onto the
+    /// `ReplayGain` reference.
+    #[must_use]
+    pub fn from_r128_track_gain(q78: i16) -> Self {
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "The reference offset is a whole number of dB"
+        )]
+        let offset = (REPLAY_GAIN_REFERENCE_LUFS - EBU_R128_REFERENCE_LUFS) as f32;
+        Self::track(f32::from(q78) / 256.0 + offset)
+    }
+
+    /// Parse a gain tag value such as "-6.54 dB"
+    #[must_use]
+    pub fn parse_gain(value: &str) -> Option<f32> {
+        let value = value.trim();
+        let number = value
+            .strip_suffix("dB")
+            .or_else(|| value.strip_suffix("db"))
+            .unwrap_or(value)
+            .trim();
+        number.parse().ok().filter(|gain: &f32| gain.is_finite())
+    }
+
+    /// Parse a peak tag value such as "0.988553"
+    #[must_use]
+    pub fn parse_peak(value: &str) -> Option<f32> {
+        value
+            .trim()
+            .parse()
+            .ok()
+            .filter(|peak: &f32| peak.is_finite() && *peak >= 0.0)
+    }
+
+    /// Linear gain for `mode`, plus `preamp_db`, lowered if needed so the
+    /// peak stays at or below full scale
+    ///
+    /// Album mode falls back to the track values when no album gain is set.
+    #[must_use]
+    pub fn linear_gain(&self, mode: GainMode, preamp_db: f32) -> f32 {
+        let (gain_db, peak) = match (mode, self.album_gain_db) {
+            (GainMode::Album, Some(album)) => (album, self.album_peak),
+            _ => (self.track_gain_db, self.track_peak),
+        };
+        let gain = db_to_linear(gain_db + preamp_db);
+        match peak {
+            Some(peak) if peak > 0.0 => gain.min(1.0 / peak),
+            _ => gain,
+        }
+    }
+}
+
+/// Convert a gain in dB to a linear factor
+#[must_use]
+pub fn db_to_linear(db: f32) -> f32 {
+    10f32.powf(db / 20.0)
+}
+
+/// Result of a loudness measurement
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Gated integrated loudness (LUFS), `None` if everything was gated out
+    pub integrated_lufs: Option<f64>,
+    /// Largest absolute sample value, 1.0 = full scale
+    pub peak: f32,
+}
+
+/// Second-order IIR section (direct form I)
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+impl Biquad {
+    /// K-weighting stage 1: high shelf modelling the head
+    fn shelf(sample_rate: f64) -> Self {
+        const F0: f64 = 1_681.974_450_955_533;
+        const GAIN_DB: f64 = 3.999_843_853_973_347;
+        const Q: f64 = 0.707_175_236_955_419_6;
+
+        let k = (std::f64::consts::PI * F0 / sample_rate).tan();
+        let vh = 10f64.powf(GAIN_DB / 20.0);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1.0 + k / Q + k * k;
+        Self {
+            b: [
+                (vh + vb * k / Q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / Q + k * k) / a0,
+            ],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / Q + k * k) / a0],
+        }
+    }
+
+    /// K-weighting stage 2: RLB high-pass
+    fn high_pass(sample_rate: f64) -> Self {
+        const F0: f64 = 38.135_470_876_024_44;
+        const Q: f64 = 0.500_327_037_323_877_3;
+
+        let k = (std::f64::consts::PI * F0 / sample_rate).tan();
+        let a0 = 1.0 + k / Q + k * k;
+        Self {
+            b: [1.0, -2.0, 1.0],
+            a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / Q + k * k) / a0],
+        }
+    }
+}
+
+/// Filter state for one biquad on one channel
+#[derive(Debug, Clone, Copy, Default)]
+struct BiquadState {
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl BiquadState {
+    fn process(&mut self, filter: &Biquad, x: f64) -> f64 {
+        let y = filter.b[0] * x + filter.b[1] * self.x[0] + filter.b[2] * self.x[1]
+            - filter.a[0] * self.y[0]
+            - filter.a[1] * self.y[1];
+        self.x = [x, self.x[0]];
+        self.y = [y, self.y[0]];
+        y
+    }
+}
+
+/// EBU R128 integrated loudness meter
+pub struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    shelf: Biquad,
+    high_pass: Biquad,
+    state: Vec<[BiquadState; 2]>,
+    /// Frames per 100 ms step
+    step_frames: usize,
+    /// Frames in the current step so far
+    step_fill: usize,
+    /// Weighted sum of squares in the current step
+    step_energy: f64,
+    /// Energy of the last three complete steps
+    recent: [f64; 3],
+    /// Complete steps seen, saturating at 3
+    recent_len: usize,
+    /// Mean square of each 400 ms block
+    blocks: Vec<f64>,
+    peak: f32,
+}
+
+impl LoudnessMeter {
+    /// Create a meter for interleaved audio
+    ///
+    /// Channels 4 and 5 (surrounds in 5.1) are weighted by 1.41 and the LFE
+    /// channel is ignored, per BS.1770.
+    #[must_use]
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels = channels.max(1);
+        let rate = f64::from(sample_rate);
+        let weights = (0..channels)
+            .map(|ch| match (channels, ch) {
+                (6.., 3) => 0.0,
+                (6.., 4 | 5) => 1.41,
+                _ => 1.0,
+            })
+            .collect();
+        Self {
+            channels,
+            weights,
+            shelf: Biquad::shelf(rate),
+            high_pass: Biquad::high_pass(rate),
+            state: vec![[BiquadState::default(); 2]; channels],
+            step_frames: (sample_rate as usize / 10).max(1),
+            step_fill: 0,
+            step_energy: 0.0,
+            recent: [0.0; 3],
+            recent_len: 0,
+            blocks: Vec::new(),
+            peak: 0.0,
+        }
+    }
+
+    /// Add interleaved samples in the -1.0..=1.0 range
+    pub fn push(&mut self, samples: &[f32]) {
+        for frame in samples.chunks_exact(self.channels) {
+            let mut energy = 0.0;
+            for (ch, &sample) in frame.iter().enumerate() {
+                self.peak = self.peak.max(sample.abs());
+                let state = &mut self.state[ch];
+                let shelved = state[0].process(&self.shelf, f64::from(sample));
+                let y = state[1].process(&self.high_pass, shelved);
+                energy += self.weights[ch] * y * y;
+            }
+            self.step_energy += energy;
+            self.step_fill += 1;
+            if self.step_fill == self.step_frames {
+                self.finish_step();
+            }
+        }
+    }
+
+    /// Add interleaved little-endian PCM
+    pub fn push_pcm(&mut self, data: &[u8], format: SampleFormat) {
+        self.push(&super::to_f32(data, format));
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "Frame counts per block are small"
+    )]
+    fn finish_step(&mut self) {
+        let step = self.step_energy;
+        if self.recent_len == 3 {
+            let total = self.recent.iter().sum::<f64>() + step;
+            self.blocks.push(total / (4 * self.step_frames) as f64);
+        }
+        self.recent = [self.recent[1], self.recent[2], step];
+        self.recent_len = (self.recent_len + 1).min(3);
+        self.step_energy = 0.0;
+        self.step_fill = 0;
+    }
+
+    /// Measurement over everything pushed so far
+    #[must_use]
+    pub fn loudness(&self) -> Loudness {
+        Loudness {
+            integrated_lufs: self.integrated(),
+            peak: self.peak,
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss, reason = "Block counts are small")]
+    fn integrated(&self) -> Option<f64> {
+        let mean = |blocks: &[f64]| blocks.iter().sum::<f64>() / blocks.len() as f64;
+        let lufs = |power: f64| -0.691 + 10.0 * power.log10();
+
+        let absolute: Vec<f64> = self
+            .blocks
+            .iter()
+            .copied()
+            .filter(|&power| power > 0.0 && lufs(power) > -70.0)
+            .collect();
+        if absolute.is_empty() {
+            return None;
+        }
+        let threshold = lufs(mean(&absolute)) - 10.0;
+        let relative: Vec<f64> = absolute
+            .into_iter()
+            .filter(|&power| lufs(power) > threshold)
+            .collect();
+        (!relative.is_empty()).then(|| lufs(mean(&relative)))
+    }
+}
@@ -4,12 +4,19 @@
 #![allow(dead_code)]
 
 pub mod aac_encoder;
+#[cfg(feature = "aac-rs")]
+pub(crate) mod aac_lc;
 pub mod buffer;
 pub mod clock;
 pub mod concealment;
 pub mod convert;
+pub mod dsp;
+pub mod encoder;
 pub mod format;
+pub mod gain;
+#[cfg(feature = "receiver")]
 pub mod jitter;
+pub mod loudness;
 pub mod output;
 pub mod output_coreaudio;
 pub mod output_cpal;
@@ -18,15 +25,21 @@ pub mod raop_encoder;
 #[cfg(test)]
 mod tests;
 
-pub use aac_encoder::AacEncoder;
+pub use aac_encoder::{AacBackend, AacEncoder};
 pub use buffer::AudioRingBuffer;
 pub use clock::{AudioClock, TimingSync};
 pub use concealment::{Concealer, ConcealmentStrategy};
 pub use convert::{
     convert_channels, convert_channels_into, convert_samples, from_f32, resample_linear, to_f32,
 };
+pub use dsp::{DspChain, DspStage, EqBand, FilterKind, ParametricEq};
+pub use encoder::{AudioEncodeError, AudioEncoder};
 pub use format::{
-    AacProfile, AudioCodec, AudioFormat, ChannelConfig, CodecParams, SampleFormat, SampleRate,
+    AacProfile, AudioCodec, AudioFormat, AudioFormatDescriptor, ChannelConfig, CodecParams,
+    SampleFormat, SampleRate, StreamCodec,
 };
+pub use gain::{DUCK_FADE, SoftGain};
+#[cfg(feature = "receiver")]
 pub use jitter::{JitterBuffer, JitterResult, JitterStats, NextPacket};
+pub use loudness::{GainMode, Loudness, LoudnessMeter, ReplayGain};
 pub use output::{AudioDevice, AudioOutput, AudioOutputError, OutputState};
@@ -1,8 +1,188 @@
 //! RAOP audio encoding with encryption
 
+use alac_encoder::{AlacEncoder, FormatDescription};
+
+use super::encoder::AudioEncoder;
+#[cfg(feature = "raop")]
 use crate::protocol::raop::encryption::RaopEncryptor;
 
+/// ALAC frame encoder for interleaved 16-bit little-endian PCM
+///
+/// Compressed frames use ALAC's adaptive prediction and Rice coding, which
+/// typically halves the bitrate of music. With compression off every frame
+/// is an uncompressed escape frame: larger, but nearly free to produce and
+/// accepted by any ALAC decoder.
+pub struct AlacFrameEncoder {
+    /// Compressing encoder, `None` when emitting escape frames
+    encoder: Option<Box<AlacEncoder>>,
+    /// Description of the ALAC output
+    output_format: FormatDescription,
+    /// Description of the PCM input
+    input_format: FormatDescription,
+    /// Channels per frame
+    channels: usize,
+    /// Frames per ALAC packet
+    frame_samples: usize,
+    /// Output buffer, sized for the largest possible packet
+    output: Vec<u8>,
+}
+
+impl AlacFrameEncoder {
+    /// Create an encoder for `frame_samples` frames per packet
+    ///
+    /// # Errors
+    /// Returns `AudioEncodeError::Encoding` if `frame_samples` is zero or
+    /// `channels` is outside 1–8 (1–2 for escape frames)
+    pub fn new(
+        sample_rate: u32,
+        channels: u8,
+        frame_samples: u32,
+        compress: bool,
+    ) -> Result<Self, AudioEncodeError> {
+        let max_channels = if compress { 8 } else { 2 };
+        if !(1..=max_channels).contains(&channels) || frame_samples == 0 {
+            return Err(AudioEncodeError::Encoding(format!(
+                "unsupported ALAC layout: {channels} channels, {frame_samples} frames"
+            )));
+        }
+
+        let output_format =
+            FormatDescription::alac(f64::from(sample_rate), frame_samples, u32::from(channels));
+        Ok(Self {
+            encoder: compress.then(|| Box::new(AlacEncoder::new(&output_format))),
+            input_format: FormatDescription::pcm::<i16>(
+                f64::from(sample_rate),
+                u32::from(channels),
+            ),
+            channels: usize::from(channels),
+            frame_samples: frame_samples as usize,
+            output: vec![0; output_format.max_packet_size()],
+            output_format,
+        })
+    }
+
+    /// Whether frames are compressed rather than escaped
+    #[must_use]
+    pub fn is_compressed(&self) -> bool {
+        self.encoder.is_some()
+    }
+
+    /// Frames per ALAC packet
+    #[must_use]
+    pub fn frame_samples(&self) -> usize {
+        self.frame_samples
+    }
+
+    /// ALAC magic cookie (`ALACSpecificConfig`) describing the stream
+    #[must_use]
+    pub fn magic_cookie(&self) -> Vec<u8> {
+        self.encoder.as_ref().map_or_else(
+            || AlacEncoder::new(&self.output_format).magic_cookie(),
+            |encoder| encoder.magic_cookie(),
+        )
+    }
+
+    /// Encode up to one packet of PCM
+    ///
+    /// Short input produces a partial frame that carries its own length.
+    ///
+    /// # Errors
+    /// Returns `AudioEncodeError::InvalidFrameSize` if `pcm` holds more than
+    /// one packet or is not a whole number of frames
+    pub fn encode(&mut self, pcm: &[u8]) -> Result<&[u8], AudioEncodeError> {
+        let frame_bytes = self.channels * 2;
+        let max_bytes = self.frame_samples * frame_bytes;
+        if pcm.len() > max_bytes || pcm.len() % frame_bytes != 0 {
+            return Err(AudioEncodeError::InvalidFrameSize {
+                expected: max_bytes,
+                actual: pcm.len(),
+            });
+        }
+
+        let size = match &mut self.encoder {
+            Some(encoder) => encoder.encode(&self.input_format, pcm, &mut self.output),
+            None => self.write_escape(pcm),
+        };
+        Ok(&self.output[..size])
+    }
+
+    /// Write an uncompressed frame, returning its size in bytes
+    fn write_escape(&mut self, pcm: &[u8]) -> usize {
+        let frames = pcm.len() / (self.channels * 2);
+        let partial = frames != self.frame_samples;
+        let mut bits = BitWriter::new(&mut self.output);
+
+        // Element (SCE or CPE) with instance tag 0, 12 unused bits, then
+        // the has-size flag, no shifted bytes and the not-compressed flag
+        bits.write(u32::from(self.channels == 2), 3);
+        bits.write(0, 4);
+        bits.write(0, 12);
+        bits.write((u32::from(partial) << 3) | 1, 4);
+        if partial {
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "Frame count is bounded by frame_samples (u32)"
+            )]
+            bits.write(frames as u32, 32);
+        }
+        for sample in pcm.chunks_exact(2) {
+            bits.write(u32::from(u16::from_le_bytes([sample[0], sample[1]])), 16);
+        }
+
+        // END element, then pad to a byte boundary
+        bits.write(7, 3);
+        bits.finish()
+    }
+}
+
+impl AudioEncoder for AlacFrameEncoder {
+    fn frames_per_packet(&self) -> usize {
+        self.frame_samples
+    }
+
+    fn encode_packet(&mut self, pcm: &[u8], out: &mut Vec<u8>) -> Result<(), AudioEncodeError> {
+        let frame = self.encode(pcm)?;
+        out.clear();
+        out.extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+/// MSB-first bit writer over a pre-sized buffer
+struct BitWriter<'a> {
+    buf: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> BitWriter<'a> {
+    fn new(buf: &'a mut [u8]) -> Self {
+        Self { buf, position: 0 }
+    }
+
+    fn write(&mut self, value: u32, bits: u32) {
+        for i in (0..bits).rev() {
+            let byte = self.position / 8;
+            let mask = 0x80 >> (self.position % 8);
+            if value >> i & 1 == 1 {
+                self.buf[byte] |= mask;
+            } else {
+                self.buf[byte] &= !mask;
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Zero-pad to a byte boundary and return the length in bytes
+    fn finish(mut self) -> usize {
+        let pad = (8 - self.position % 8) % 8;
+        #[allow(clippy::cast_possible_truncation, reason = "Padding is below 8 bits")]
+        self.write(0, pad as u32);
+        self.position / 8
+    }
+}
+
 /// RAOP audio encoder with encryption
+#[cfg(feature = "raop")]
 pub struct RaopAudioEncoder {
     /// Audio encryptor
     encryptor: RaopEncryptor,
@@ -14,6 +194,7 @@ pub struct RaopAudioEncoder {
     samples_per_frame: u32,
 }
 
+#[cfg(feature = "raop")]
 impl RaopAudioEncoder {
     /// Samples per ALAC frame
     pub const ALAC_FRAME_SAMPLES: u32 = 352;
@@ -46,9 +227,6 @@ impl RaopAudioEncoder {
             audio_bytes.extend_from_slice(&sample.to_le_bytes());
         }
 
-        // For PCM mode, use raw bytes
-        // For ALAC mode, would encode here (placeholder)
-
         // Encrypt
         let encrypted = self
             .encryptor
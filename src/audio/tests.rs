@@ -1,9 +1,21 @@
+#[cfg(any(feature = "aac-fdk", feature = "aac-rs"))]
 mod aac_encoder;
+#[cfg(feature = "aac-rs")]
+mod aac_lc;
 mod buffer;
 mod clock;
 mod concealment;
 mod concurrency;
+#[cfg(feature = "sender")]
+mod conversion_quality;
+mod dsp;
 mod format;
+mod gain;
+#[cfg(feature = "receiver")]
 mod jitter;
+#[cfg(feature = "receiver")]
 mod jitter_extended;
+mod loudness;
 mod output;
+#[cfg(feature = "raop")]
+mod raop_encoder;
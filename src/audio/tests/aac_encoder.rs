@@ -1,6 +1,4 @@
-use fdk_aac::enc::AudioObjectType;
-
-use crate::audio::aac_encoder::AacEncoder;
+use crate::audio::aac_encoder::{AacEncoder, AudioObjectType};
 
 #[test]
 fn test_aac_encoding() {
@@ -77,3 +75,65 @@ fn test_encoder_errors() {
     let result = AacEncoder::new(44100, 5, 64000, AudioObjectType::Mpeg4LowComplexity);
     assert!(result.is_err());
 }
+
+#[test]
+fn test_encode_packet_adds_au_header() {
+    use crate::audio::AudioEncoder;
+
+    let mut encoder = AacEncoder::new(44100, 2, 64000, AudioObjectType::Mpeg4LowComplexity)
+        .expect("Failed to create encoder");
+    assert_eq!(encoder.frames_per_packet(), 1024);
+
+    let pcm = vec![0u8; 1024 * 4];
+    let mut payload = Vec::new();
+    for _ in 0..3 {
+        encoder.encode_packet(&pcm, &mut payload).unwrap();
+        // AU-headers-length of 16 bits, then one AU-header with the size
+        assert_eq!(&payload[..2], &[0x00, 0x10]);
+        let size = u16::from_be_bytes([payload[2], payload[3]]) >> 3;
+        assert_eq!(usize::from(size), payload.len() - 4);
+    }
+}
+
+#[test]
+fn test_backend_selection() {
+    use crate::audio::AacBackend;
+
+    // The preferred backend takes AAC-LC
+    let encoder = AacEncoder::new(44100, 2, 64000, AudioObjectType::Mpeg4LowComplexity).unwrap();
+    assert_eq!(encoder.backend(), AacBackend::AVAILABLE[0]);
+
+    for backend in [AacBackend::Fdk, AacBackend::Native] {
+        let compiled = AacBackend::AVAILABLE.contains(&backend);
+        let encoder = AacEncoder::with_backend(
+            44100,
+            2,
+            64000,
+            AudioObjectType::Mpeg4LowComplexity,
+            backend,
+        );
+        assert_eq!(encoder.is_ok(), compiled, "{backend}");
+        if let Ok(encoder) = encoder {
+            assert_eq!(encoder.backend(), backend);
+        }
+    }
+
+    // Only fdk-aac encodes AAC-ELD
+    assert!(
+        AacEncoder::with_backend(
+            44100,
+            2,
+            64000,
+            AudioObjectType::Mpeg4EnhancedLowDelay,
+            AacBackend::Native
+        )
+        .is_err()
+    );
+    let eld = AacEncoder::new(44100, 2, 64000, AudioObjectType::Mpeg4EnhancedLowDelay);
+    assert_eq!(eld.is_ok(), cfg!(feature = "aac-fdk"));
+    assert!(!AacBackend::Native.supports(AudioObjectType::Mpeg4EnhancedLowDelay));
+    assert_eq!(
+        AacEncoder::is_available(AudioObjectType::Mpeg4EnhancedLowDelay),
+        cfg!(feature = "aac-fdk")
+    );
+}
@@ -0,0 +1,123 @@
+use crate::audio::aac_lc::{COEFFS, LcEncoder, Mdct};
+
+#[allow(clippy::cast_precision_loss, reason = "Test signal indices are small")]
+fn tone(frames: usize, channels: usize, freq: f32, amplitude: f32) -> Vec<i16> {
+    (0..frames * channels)
+        .map(|i| {
+            let t = (i / channels) as f32 / 44100.0;
+            #[allow(clippy::cast_possible_truncation, reason = "Amplitude is in range")]
+            let s = (amplitude * (2.0 * std::f32::consts::PI * freq * t).sin()) as i16;
+            s
+        })
+        .collect()
+}
+
+#[test]
+#[allow(clippy::cast_precision_loss, reason = "Test indices are small")]
+fn test_mdct_matches_direct_transform() {
+    let input: Vec<f32> = (0..2 * COEFFS)
+        .map(|n| ((n * 7919) % 201) as f32 - 100.0)
+        .collect();
+    let mut fast = vec![0.0f32; COEFFS];
+    Mdct::new().forward(&input, &mut fast);
+
+    let n = (2 * COEFFS) as f64;
+    for k in [0, 1, 2, 100, 511, 512, 1000, COEFFS - 1] {
+        let direct: f64 = input
+            .iter()
+            .enumerate()
+            .map(|(i, &x)| {
+                let window = (std::f64::consts::PI * (i as f64 + 0.5) / n).sin();
+                let phase =
+                    2.0 * std::f64::consts::PI / n * (i as f64 + 0.5 + n / 4.0) * (k as f64 + 0.5);
+                2.0 * f64::from(x) * window * phase.cos()
+            })
+            .sum();
+        assert!(
+            (f64::from(fast[k]) - direct).abs() < 0.05 + direct.abs() * 1e-4,
+            "bin {k}: {} vs {direct}",
+            fast[k]
+        );
+    }
+}
+
+#[test]
+fn test_audio_specific_config() {
+    let stereo = LcEncoder::new(44100, 2, 128_000).unwrap();
+    assert_eq!(stereo.audio_specific_config(), vec![0x12, 0x10]);
+    let mono = LcEncoder::new(48000, 1, 64000).unwrap();
+    assert_eq!(mono.audio_specific_config(), vec![0x11, 0x88]);
+}
+
+#[test]
+fn test_unsupported_configurations() {
+    assert!(LcEncoder::new(22050, 2, 64000).is_none());
+    assert!(LcEncoder::new(44100, 3, 64000).is_none());
+}
+
+#[test]
+fn test_frames_fit_bitrate() {
+    let mut encoder = LcEncoder::new(44100, 2, 96000).unwrap();
+    let max_bytes = 96000 * 1024 / 44100 / 8 + 1;
+
+    // Partial input is held until a frame is complete
+    let pcm = tone(COEFFS * 8, 2, 997.0, 12000.0);
+    assert!(encoder.encode(&pcm[..COEFFS]).is_empty());
+    let mut frames = vec![encoder.encode(&pcm[COEFFS..COEFFS * 2])];
+    for chunk in pcm[COEFFS * 2..].chunks(COEFFS * 2) {
+        frames.push(encoder.encode(chunk));
+    }
+
+    assert_eq!(frames.len(), 8);
+    for frame in &frames[..7] {
+        assert!(!frame.is_empty());
+        assert!(frame.len() <= max_bytes, "{} > {max_bytes}", frame.len());
+    }
+
+    // Silence codes to a few header bytes
+    let mut encoder = LcEncoder::new(48000, 1, 64000).unwrap();
+    let frame = encoder.encode(&[0; COEFFS]);
+    assert!(!frame.is_empty() && frame.len() <= 6);
+}
+
+#[cfg(feature = "decoders")]
+#[test]
+#[allow(clippy::cast_precision_loss, reason = "Sample values are small")]
+fn test_round_trip_through_decoder() {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{CODEC_TYPE_AAC, CodecParameters, DecoderOptions};
+    use symphonia::core::formats::Packet;
+
+    let mut encoder = LcEncoder::new(44100, 2, 128_000).unwrap();
+    let mut params = CodecParameters::new();
+    params
+        .for_codec(CODEC_TYPE_AAC)
+        .with_extra_data(encoder.audio_specific_config().into_boxed_slice());
+    let mut aac = symphonia::default::get_codecs()
+        .make(&params, &DecoderOptions::default())
+        .unwrap();
+
+    let frames = 12;
+    let input = tone(COEFFS * frames, 2, 440.0, 10000.0);
+    let mut output = Vec::new();
+    for (i, chunk) in input.chunks(COEFFS * 2).enumerate() {
+        let au = encoder.encode(chunk);
+        let buffer = aac
+            .decode(&Packet::new_from_slice(0, i as u64 * 1024, 1024, &au))
+            .unwrap();
+        let mut samples = SampleBuffer::<f32>::new(buffer.capacity() as u64, *buffer.spec());
+        samples.copy_interleaved_ref(buffer);
+        output.extend_from_slice(samples.samples());
+    }
+
+    // The decoder output lags the input by one frame
+    let delay = COEFFS * 2;
+    let (mut signal, mut noise) = (0.0f64, 0.0f64);
+    for (i, &original) in input[..input.len() - delay].iter().enumerate().skip(delay) {
+        let sample = f64::from(output[i + delay]) * 32768.0;
+        signal += f64::from(original).powi(2);
+        noise += (sample - f64::from(original)).powi(2);
+    }
+    let snr = 10.0 * (signal / noise).log10();
+    assert!(snr > 30.0, "SNR {snr:.1} dB");
+}
@@ -0,0 +1,380 @@
+//! Objective quality checks for sample-format conversion and resampling
+//!
+//! Each test measures a signal metric and compares it with a golden value
+//! recorded in `tests/fixtures/dsp/`, as well as with a hard floor. A DSP
+//! change that makes things worse fails; one that makes things better is
+//! accepted by regenerating the golden files with
+//! `AIRPLAY2_BLESS_GOLDEN=1 cargo test conversion_quality`.
+
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+use crate::audio::convert::{convert_channels, from_f32, resample_linear, to_f32};
+use crate::audio::{AudioFormat, ChannelConfig, SampleFormat, SampleRate};
+use crate::streaming::{AudioSource, ResamplingSource, SliceSource};
+
+/// How far a metric may fall below its golden value, in dB
+const TOLERANCE_DB: f64 = 0.5;
+
+/// Largest difference allowed between golden and measured samples
+const SAMPLE_TOLERANCE: f64 = 1e-4;
+
+/// Test tone frequency, off any bin of the common rates
+const TONE_HZ: f64 = 997.0;
+
+/// Test tone level, -1 dBFS
+const TONE_AMPLITUDE: f64 = 0.891;
+
+/// Metrics measured by one test, kept in a golden file
+struct Golden {
+    name: &'static str,
+    values: BTreeMap<String, String>,
+}
+
+impl Golden {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            values: BTreeMap::new(),
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        PathBuf::from(format!("tests/fixtures/dsp/{}.txt", self.name))
+    }
+
+    /// Record `value` and check it is no more than [`TOLERANCE_DB`] below
+    /// the golden value and above `floor`
+    fn at_least_db(&mut self, key: &str, value: f64, floor: f64) {
+        assert!(
+            value >= floor,
+            "{}: {key} = {value:.2} dB is below the floor of {floor} dB",
+            self.name
+        );
+        self.values.insert(key.to_string(), format!("{value:.2}"));
+    }
+
+    /// Record samples that must match the golden ones
+    fn samples(&mut self, key: &str, samples: &[f32]) {
+        let list: Vec<String> = samples.iter().map(|s| format!("{s:.6}")).collect();
+        self.values.insert(key.to_string(), list.join(","));
+    }
+
+    /// Compare everything recorded with the golden file, or rewrite it
+    fn check(self) {
+        let path = self.path();
+        if std::env::var_os("AIRPLAY2_BLESS_GOLDEN").is_some() {
+            let mut contents = String::new();
+            for (key, value) in &self.values {
+                let _ = writeln!(contents, "{key}={value}");
+            }
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, contents).unwrap();
+            return;
+        }
+
+        let golden: BTreeMap<String, String> = std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("Golden file {} not found: {e}", path.display()))
+            .lines()
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(
+            golden.keys().collect::<Vec<_>>(),
+            self.values.keys().collect::<Vec<_>>(),
+            "{}: golden keys differ",
+            self.name
+        );
+
+        for (key, measured) in &self.values {
+            let expected = &golden[key];
+            if measured.contains(',') {
+                let parse = |list: &str| -> Vec<f64> {
+                    list.split(',').map(|s| s.parse().unwrap()).collect()
+                };
+                let (measured, expected) = (parse(measured), parse(expected));
+                assert_eq!(measured.len(), expected.len(), "{}: {key}", self.name);
+                for (i, (m, e)) in measured.iter().zip(&expected).enumerate() {
+                    assert!(
+                        (m - e).abs() <= SAMPLE_TOLERANCE,
+                        "{}: {key}[{i}] = {m}, golden {e}",
+                        self.name
+                    );
+                }
+            } else {
+                let measured: f64 = measured.parse().unwrap();
+                let expected: f64 = expected.parse().unwrap();
+                assert!(
+                    measured >= expected - TOLERANCE_DB,
+                    "{}: {key} = {measured:.2} dB regressed from golden {expected:.2} dB",
+                    self.name
+                );
+            }
+        }
+    }
+}
+
+/// `seconds` of a mono sine
+fn sine(freq: f64, rate: u32, seconds: f64) -> Vec<f32> {
+    let rate = f64::from(rate);
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "Test signal lengths are small and positive"
+    )]
+    let frames = (seconds * rate) as usize;
+    (0..frames)
+        .map(|n| {
+            #[allow(clippy::cast_precision_loss, reason = "Frame index is small")]
+            let t = n as f64 / rate;
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "Sample is within f32 range"
+            )]
+            let sample = (TONE_AMPLITUDE * (2.0 * PI * freq * t).sin()) as f32;
+            sample
+        })
+        .collect()
+}
+
+/// Amplitude of `freq` in `signal`, by correlation with sine and cosine
+fn amplitude(signal: &[f32], freq: f64, rate: u32) -> f64 {
+    let w = 2.0 * PI * freq / f64::from(rate);
+    let (mut s, mut c) = (0.0, 0.0);
+    for (n, &x) in signal.iter().enumerate() {
+        #[allow(clippy::cast_precision_loss, reason = "Frame index is small")]
+        let phase = w * n as f64;
+        s += f64::from(x) * phase.sin();
+        c += f64::from(x) * phase.cos();
+    }
+    #[allow(clippy::cast_precision_loss, reason = "Signal length is small")]
+    let len = signal.len() as f64;
+    2.0 * (s * s + c * c).sqrt() / len
+}
+
+/// Signal to noise-and-distortion ratio of a sine at `freq`, in dB
+///
+/// The best-fitting sine (with DC) is subtracted; whatever is left counts
+/// as noise and distortion.
+fn sinad_db(signal: &[f32], freq: f64, rate: u32) -> f64 {
+    let omega = 2.0 * PI * freq / f64::from(rate);
+    let basis = |index: usize| {
+        #[allow(clippy::cast_precision_loss, reason = "Frame index is small")]
+        let phase = omega * index as f64;
+        [phase.sin(), phase.cos(), 1.0]
+    };
+
+    // Least-squares fit of sine, cosine and DC
+    let mut normal = [[0.0f64; 3]; 3];
+    let mut rhs = [0.0f64; 3];
+    for (index, &sample) in signal.iter().enumerate() {
+        let basis = basis(index);
+        for row in 0..3 {
+            for col in 0..3 {
+                normal[row][col] += basis[row] * basis[col];
+            }
+            rhs[row] += basis[row] * f64::from(sample);
+        }
+    }
+    let weights = solve3(normal, rhs);
+
+    let (mut signal_power, mut noise_power) = (0.0, 0.0);
+    for (index, &sample) in signal.iter().enumerate() {
+        let fit: f64 = basis(index).iter().zip(&weights).map(|(b, w)| b * w).sum();
+        signal_power += fit * fit;
+        noise_power += (f64::from(sample) - fit).powi(2);
+    }
+    10.0 * (signal_power / noise_power.max(1e-30)).log10()
+}
+
+/// Ratio of the fundamental to its 2nd-5th harmonics, in dB
+fn thd_db(signal: &[f32], freq: f64, rate: u32) -> f64 {
+    let fundamental = amplitude(signal, freq, rate);
+    let nyquist = f64::from(rate) / 2.0;
+    let harmonics: f64 = (2..=5)
+        .map(|k| f64::from(k) * freq)
+        .filter(|&f| f < nyquist)
+        .map(|f| amplitude(signal, f, rate).powi(2))
+        .sum();
+    20.0 * (fundamental / harmonics.sqrt().max(1e-15)).log10()
+}
+
+/// Solve a 3x3 linear system by Cramer's rule
+fn solve3(m: [[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    let det = |m: [[f64; 3]; 3]| {
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    };
+    let d = det(m);
+    std::array::from_fn(|col| {
+        let mut mc = m;
+        for row in 0..3 {
+            mc[row][col] = v[row];
+        }
+        det(mc) / d
+    })
+}
+
+/// Skip the first and last `edge` samples, where filters settle
+fn trim(signal: &[f32], edge: usize) -> &[f32] {
+    &signal[edge..signal.len() - edge]
+}
+
+/// Run mono `input` at 16 bits through [`ResamplingSource`]
+fn resample_source(input: &[f32], from: SampleRate, to: SampleRate) -> Vec<f32> {
+    let format = |sample_rate| AudioFormat {
+        sample_format: SampleFormat::I16,
+        sample_rate,
+        channels: ChannelConfig::Mono,
+    };
+    let source = SliceSource::new(from_f32(input, SampleFormat::I16), format(from));
+    let mut resampler = ResamplingSource::new(source, format(to)).unwrap();
+
+    let mut output = Vec::new();
+    let mut buf = [0u8; 4096];
+    loop {
+        let n = resampler.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        output.extend_from_slice(&buf[..n]);
+    }
+    to_f32(&output, SampleFormat::I16)
+}
+
+#[test]
+fn test_sample_format_round_trips() {
+    let mut golden = Golden::new("sample_formats");
+    let tone = sine(TONE_HZ, 48_000, 0.5);
+
+    for (format, floor) in [
+        (SampleFormat::I16, 90.0),
+        (SampleFormat::I24, 135.0),
+        (SampleFormat::I32, 135.0),
+    ] {
+        let round_trip = to_f32(&from_f32(&tone, format), format);
+        let name = format!("{format:?}").to_lowercase();
+        golden.at_least_db(
+            &format!("{name}_sinad_db"),
+            sinad_db(&round_trip, TONE_HZ, 48_000),
+            floor,
+        );
+    }
+
+    // 16-bit audio must survive a trip through 24-bit unchanged
+    let pcm16 = from_f32(&tone, SampleFormat::I16);
+    let via24 = from_f32(
+        &to_f32(
+            &from_f32(&to_f32(&pcm16, SampleFormat::I16), SampleFormat::I24),
+            SampleFormat::I24,
+        ),
+        SampleFormat::I16,
+    );
+    let errors = pcm16
+        .chunks_exact(2)
+        .zip(via24.chunks_exact(2))
+        .filter(|(a, b)| {
+            (i32::from(i16::from_le_bytes([a[0], a[1]]))
+                - i32::from(i16::from_le_bytes([b[0], b[1]])))
+            .abs()
+                > 1
+        })
+        .count();
+    assert_eq!(errors, 0, "16-bit samples drifted through 24-bit");
+
+    golden.check();
+}
+
+#[test]
+fn test_channel_conversion_is_transparent() {
+    let tone = sine(TONE_HZ, 44_100, 0.1);
+    let stereo = convert_channels(&tone, ChannelConfig::Mono, ChannelConfig::Stereo);
+    let mono = convert_channels(&stereo, ChannelConfig::Stereo, ChannelConfig::Mono);
+    assert_eq!(mono, tone);
+}
+
+#[test]
+fn test_resampler_sine() {
+    let mut golden = Golden::new("resample_sine");
+
+    for (from, to, floor) in [
+        (SampleRate::Hz44100, SampleRate::Hz48000, 55.0),
+        (SampleRate::Hz48000, SampleRate::Hz44100, 55.0),
+        (SampleRate::Hz96000, SampleRate::Hz44100, 55.0),
+    ] {
+        let tone = sine(TONE_HZ, from.as_u32(), 1.0);
+        let output = resample_source(&tone, from, to);
+        let output = trim(&output, 256);
+        let rates = format!("{}_{}", from.as_u32(), to.as_u32());
+        golden.at_least_db(
+            &format!("source_{rates}_sinad_db"),
+            sinad_db(output, TONE_HZ, to.as_u32()),
+            floor,
+        );
+        golden.at_least_db(
+            &format!("source_{rates}_thd_db"),
+            thd_db(output, TONE_HZ, to.as_u32()),
+            floor,
+        );
+
+        let output = resample_linear(&tone, from.as_u32(), to.as_u32(), 1);
+        golden.at_least_db(
+            &format!("linear_{rates}_sinad_db"),
+            sinad_db(trim(&output, 256), TONE_HZ, to.as_u32()),
+            floor,
+        );
+    }
+
+    golden.check();
+}
+
+#[test]
+fn test_resampler_sweep_response() {
+    let mut golden = Golden::new("resample_sweep");
+
+    // Gain at points along a sweep; linear interpolation rolls off highs
+    for (freq, floor) in [
+        (100.0, -0.1),
+        (1_000.0, -0.1),
+        (5_000.0, -0.5),
+        (10_000.0, -2.0),
+        (15_000.0, -4.5),
+    ] {
+        let tone = sine(freq, 44_100, 0.5);
+        let output = resample_source(&tone, SampleRate::Hz44100, SampleRate::Hz48000);
+        let gain = 20.0 * (amplitude(trim(&output, 256), freq, 48_000) / TONE_AMPLITUDE).log10();
+        golden.at_least_db(&format!("gain_{freq}_hz_db"), gain, floor);
+    }
+
+    golden.check();
+}
+
+#[test]
+fn test_resampler_impulse_response() {
+    let mut golden = Golden::new("resample_impulse");
+
+    let mut impulse = vec![0.0f32; 4096];
+    impulse[1000] = 0.5;
+    let output = resample_source(&impulse, SampleRate::Hz44100, SampleRate::Hz48000);
+
+    // The impulse lands near 1000 * 48000 / 44100 = 1088.4
+    let peak = output
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.abs().total_cmp(&b.1.abs()))
+        .map(|(i, _)| i)
+        .unwrap();
+    assert!((1086..=1090).contains(&peak), "impulse peak at {peak}");
+    golden.samples("response", &output[peak - 4..=peak + 4]);
+
+    let energy: f32 = output.iter().map(|s| s * s).sum();
+    assert!(energy > 0.0);
+    let before: f32 = output[..peak - 4].iter().map(|s| s.abs()).sum();
+    let after: f32 = output[peak + 5..].iter().map(|s| s.abs()).sum();
+    assert!(before < 1e-6 && after < 1e-6, "impulse response spread");
+
+    golden.check();
+}
@@ -0,0 +1,109 @@
+use crate::audio::dsp::{DspChain, DspStage, EqBand, FilterKind, ParametricEq};
+use crate::audio::format::SampleFormat;
+
+#[allow(clippy::cast_precision_loss, reason = "Test signal indices are small")]
+fn stereo_sine(frames: usize, freq: f32) -> Vec<f32> {
+    (0..frames)
+        .flat_map(|i| {
+            let s = 0.25 * (2.0 * std::f32::consts::PI * freq * i as f32 / 48000.0).sin();
+            [s, s]
+        })
+        .collect()
+}
+
+/// Gain (dB) of the second half of `output` relative to `input`
+fn gain_db(input: &[f32], output: &[f32]) -> f32 {
+    let rms = |s: &[f32]| s.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let half = input.len() / 2;
+    20.0 * (rms(&output[half..]) / rms(&input[half..])).log10()
+}
+
+fn eq_response(mut eq: ParametricEq, freq: f32) -> f32 {
+    let input = stereo_sine(48000, freq);
+    let mut output = input.clone();
+    eq.process(&mut output, 2, 48000);
+    gain_db(&input, &output)
+}
+
+#[test]
+fn test_flat_eq_is_transparent() {
+    let input = stereo_sine(4800, 440.0);
+    let mut output = input.clone();
+    ParametricEq::ten_band().process(&mut output, 2, 48000);
+    assert_eq!(input, output);
+}
+
+#[test]
+fn test_peaking_band_response() {
+    let mut gains = [0.0; 10];
+    gains[5] = 6.0;
+    // Full boost at the centre, little an octave-and-a-half away
+    assert!((eq_response(ParametricEq::with_gains(gains), 1000.0) - 6.0).abs() < 0.1);
+    assert!(eq_response(ParametricEq::with_gains(gains), 100.0).abs() < 0.5);
+
+    gains[5] = -12.0;
+    assert!((eq_response(ParametricEq::with_gains(gains), 1000.0) + 12.0).abs() < 0.1);
+}
+
+#[test]
+fn test_shelves() {
+    let high = ParametricEq::new(vec![EqBand {
+        kind: FilterKind::HighShelf,
+        frequency: 2000.0,
+        gain_db: 6.0,
+        q: std::f32::consts::FRAC_1_SQRT_2,
+    }]);
+    assert!((eq_response(high.clone(), 12000.0) - 6.0).abs() < 0.2);
+    assert!(eq_response(high, 100.0).abs() < 0.2);
+
+    let low = ParametricEq::new(vec![EqBand {
+        kind: FilterKind::LowShelf,
+        frequency: 200.0,
+        gain_db: -6.0,
+        q: std::f32::consts::FRAC_1_SQRT_2,
+    }]);
+    assert!((eq_response(low.clone(), 40.0) + 6.0).abs() < 0.2);
+    assert!(eq_response(low, 5000.0).abs() < 0.2);
+}
+
+#[test]
+fn test_set_gain() {
+    let mut eq = ParametricEq::ten_band();
+    assert!(eq.set_gain(9, 3.0));
+    assert!(!eq.set_gain(10, 3.0));
+    assert!((eq.bands()[9].gain_db - 3.0).abs() < f32::EPSILON);
+    assert!((eq.bands()[9].frequency - 16000.0).abs() < f32::EPSILON);
+}
+
+struct Scale(f32);
+
+impl DspStage for Scale {
+    fn process(&mut self, frames: &mut [f32], _channels: usize, _sample_rate: u32) {
+        for sample in frames {
+            *sample *= self.0;
+        }
+    }
+}
+
+#[test]
+fn test_chain_runs_stages_on_pcm() {
+    let mut data: Vec<u8> = [8000i16, -8000, 16000, 0]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    let original = data.clone();
+
+    DspChain::new().process_pcm(&mut data, SampleFormat::I16, 2, 44100);
+    assert_eq!(data, original);
+
+    let mut chain = DspChain::new().with(Scale(0.5)).with(Scale(0.5));
+    assert_eq!(chain.len(), 2);
+    chain.process_pcm(&mut data, SampleFormat::I16, 2, 44100);
+    let samples: Vec<i16> = data
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect();
+    for (got, want) in samples.iter().zip([2000i16, -2000, 4000, 0]) {
+        assert!((got - want).abs() <= 1, "{got} vs {want}");
+    }
+}
@@ -132,3 +132,41 @@ fn test_resample_linear_upsample() {
     assert!((output[1] - 0.5).abs() < 1e-6);
     assert!((output[2] - 1.0).abs() < 1e-6);
 }
+
+#[test]
+fn test_audio_format_mask() {
+    let hires = AudioFormat::new(
+        SampleFormat::I24,
+        SampleRate::Hz48000,
+        ChannelConfig::Stereo,
+    );
+    let alac_48k = AudioFormat::new(
+        SampleFormat::I16,
+        SampleRate::Hz48000,
+        ChannelConfig::Stereo,
+    );
+
+    assert_eq!(
+        AudioFormat::CD_QUALITY.audio_format_mask(AudioCodec::Pcm),
+        Some(1 << 11)
+    );
+    assert_eq!(
+        AudioFormat::CD_QUALITY.audio_format_mask(AudioCodec::Alac),
+        Some(1 << 18)
+    );
+    assert_eq!(hires.audio_format_mask(AudioCodec::Pcm), Some(1 << 17));
+    assert_eq!(alac_48k.audio_format_mask(AudioCodec::Alac), Some(1 << 20));
+    assert_eq!(alac_48k.audio_format_mask(AudioCodec::Aac), Some(1 << 23));
+
+    // No bits for surround layouts or float samples
+    let surround = AudioFormat {
+        channels: ChannelConfig::Surround51,
+        ..AudioFormat::CD_QUALITY
+    };
+    let float = AudioFormat {
+        sample_format: SampleFormat::F32,
+        ..AudioFormat::CD_QUALITY
+    };
+    assert_eq!(surround.audio_format_mask(AudioCodec::Pcm), None);
+    assert_eq!(float.audio_format_mask(AudioCodec::Pcm), None);
+}
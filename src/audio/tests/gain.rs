@@ -0,0 +1,184 @@
+use std::time::{Duration, Instant};
+
+use crate::audio::format::SampleFormat;
+use crate::audio::gain::SoftGain;
+
+fn i16_samples(data: &[u8]) -> Vec<i16> {
+    data.chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+#[test]
+fn test_unity_leaves_samples_untouched() {
+    let gain = SoftGain::new();
+    let mut data: Vec<u8> = [1000i16, -1000]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+    let original = data.clone();
+
+    let applied = gain.apply(
+        &mut data,
+        SampleFormat::I16,
+        2,
+        Instant::now(),
+        Duration::from_millis(8),
+    );
+
+    assert!(!applied);
+    assert_eq!(data, original);
+    assert!((gain.gain_at(Instant::now()) - 1.0).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_duck_envelope() {
+    let gain = SoftGain::new();
+    let start = Instant::now();
+    let fade = Duration::from_millis(100);
+    gain.duck(0.2, Some(Duration::from_secs(1)), fade);
+
+    // Fading down
+    let mid_fade = gain.gain_at(start + Duration::from_millis(50));
+    assert!(mid_fade < 1.0 && mid_fade > 0.2);
+    // Holding
+    assert!((gain.gain_at(start + Duration::from_millis(500)) - 0.2).abs() < 0.01);
+    // Restored
+    assert!((gain.gain_at(start + Duration::from_secs(2)) - 1.0).abs() < f32::EPSILON);
+    assert!(gain.is_ducked(start + Duration::from_millis(500)));
+    assert!(!gain.is_ducked(start + Duration::from_secs(2)));
+}
+
+#[test]
+fn test_duck_without_duration_holds() {
+    let gain = SoftGain::new();
+    gain.duck(0.5, None, Duration::ZERO);
+
+    let later = Instant::now() + Duration::from_secs(3600);
+    assert!((gain.gain_at(later) - 0.5).abs() < f32::EPSILON);
+    assert!(gain.is_ducked(later));
+}
+
+#[test]
+fn test_restore_fades_from_current_level() {
+    let gain = SoftGain::new();
+    gain.duck(0.0, None, Duration::ZERO);
+    gain.restore(Duration::from_millis(100));
+
+    let now = Instant::now();
+    let mid = gain.gain_at(now + Duration::from_millis(50));
+    assert!(mid > 0.0 && mid < 1.0);
+    assert!((gain.gain_at(now + Duration::from_millis(200)) - 1.0).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_level_is_clamped() {
+    let gain = SoftGain::new();
+    gain.duck(-1.0, None, Duration::ZERO);
+    assert!(gain.gain_at(Instant::now()).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_apply_scales_i16() {
+    let gain = SoftGain::new();
+    gain.duck(0.5, None, Duration::ZERO);
+    let mut data: Vec<u8> = [10_000i16, -10_000, 20_000, -20_000]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+
+    assert!(gain.apply(
+        &mut data,
+        SampleFormat::I16,
+        2,
+        Instant::now(),
+        Duration::from_millis(1),
+    ));
+
+    assert_eq!(i16_samples(&data), vec![5_000, -5_000, 10_000, -10_000]);
+}
+
+#[test]
+fn test_apply_scales_i24_and_f32() {
+    let gain = SoftGain::new();
+    gain.duck(0.5, None, Duration::ZERO);
+    let now = Instant::now();
+
+    // -4_000_000 as packed little-endian 24-bit
+    let mut i24 = (-4_000_000i32).to_le_bytes()[..3].to_vec();
+    gain.apply(&mut i24, SampleFormat::I24, 1, now, Duration::ZERO);
+    let value = i32::from_le_bytes([0, i24[0], i24[1], i24[2]]) >> 8;
+    assert_eq!(value, -2_000_000);
+
+    let mut f32_data = 0.8f32.to_le_bytes().to_vec();
+    gain.apply(&mut f32_data, SampleFormat::F32, 1, now, Duration::ZERO);
+    let value = f32::from_le_bytes([f32_data[0], f32_data[1], f32_data[2], f32_data[3]]);
+    assert!((value - 0.4).abs() < 1e-6);
+}
+
+#[test]
+fn test_apply_ramps_within_buffer() {
+    let gain = SoftGain::new();
+    gain.duck(0.0, None, Duration::from_millis(10));
+    let now = Instant::now();
+    let mut data: Vec<u8> = std::iter::repeat_n(10_000i16, 100)
+        .flat_map(i16::to_le_bytes)
+        .collect();
+
+    gain.apply(
+        &mut data,
+        SampleFormat::I16,
+        1,
+        now,
+        Duration::from_millis(10),
+    );
+
+    let samples = i16_samples(&data);
+    assert!(samples.windows(2).all(|w| w[0] >= w[1]));
+    assert!(samples[0] > samples[99]);
+}
+
+#[test]
+fn test_level_persists_and_combines_with_duck() {
+    let gain = SoftGain::new();
+    gain.set_level(0.5, Duration::ZERO);
+    let now = Instant::now();
+    assert!((gain.level() - 0.5).abs() < f32::EPSILON);
+    assert!((gain.gain_at(now + Duration::from_secs(60)) - 0.5).abs() < f32::EPSILON);
+    assert!(!gain.is_ducked(now));
+
+    gain.duck(0.5, Some(Duration::from_millis(10)), Duration::ZERO);
+    assert!((gain.gain_at(Instant::now()) - 0.25).abs() < f32::EPSILON);
+    // The duck releases back to the level, not to unity
+    assert!((gain.gain_at(Instant::now() + Duration::from_secs(1)) - 0.5).abs() < f32::EPSILON);
+}
+
+#[test]
+fn test_level_scales_samples() {
+    let gain = SoftGain::new();
+    gain.set_level(0.5, Duration::ZERO);
+    let mut data: Vec<u8> = [10_000i16, -10_000]
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .collect();
+
+    assert!(gain.apply(
+        &mut data,
+        SampleFormat::I16,
+        2,
+        Instant::now(),
+        Duration::from_millis(1),
+    ));
+    assert_eq!(i16_samples(&data), vec![5_000, -5_000]);
+
+    gain.set_level(1.0, Duration::ZERO);
+    let original = data.clone();
+    assert!(!gain.apply(
+        &mut data,
+        SampleFormat::I16,
+        2,
+        Instant::now(),
+        Duration::from_millis(1),
+    ));
+    assert_eq!(data, original);
+}
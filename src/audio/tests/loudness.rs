@@ -0,0 +1,94 @@
+use crate::audio::loudness::{GainMode, LoudnessMeter, ReplayGain, db_to_linear};
+use crate::audio::{SampleFormat, from_f32};
+
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss,
+    reason = "Test signal lengths are small"
+)]
+fn stereo_sine(sample_rate: u32, secs: f32, freq: f32, amplitude: f32) -> Vec<f32> {
+    let frames = (sample_rate as f32 * secs) as usize;
+    (0..frames)
+        .flat_map(|i| {
+            let t = i as f32 / sample_rate as f32;
+            let s = amplitude * (2.0 * std::f32::consts::PI * freq * t).sin();
+            [s, s]
+        })
+        .collect()
+}
+
+#[test]
+fn test_sine_reference_level() {
+    // EBU Tech 3341 case 1: a 1 kHz stereo sine at -23 dBFS reads -23 LUFS
+    for rate in [44100, 48000] {
+        let mut meter = LoudnessMeter::new(rate, 2);
+        meter.push(&stereo_sine(rate, 20.0, 1000.0, db_to_linear(-23.0)));
+        let lufs = meter.loudness().integrated_lufs.unwrap();
+        assert!((lufs + 23.0).abs() < 0.1, "{rate} Hz: {lufs:.2} LUFS");
+    }
+}
+
+#[test]
+fn test_gating_ignores_silence() {
+    let tone = stereo_sine(48000, 5.0, 1000.0, db_to_linear(-20.0));
+    let mut meter = LoudnessMeter::new(48000, 2);
+    meter.push(&vec![0.0; 48000 * 2 * 10]);
+    assert_eq!(meter.loudness().integrated_lufs, None);
+
+    meter.push(&tone);
+    meter.push(&vec![0.0; 48000 * 2 * 10]);
+    // Blocks straddling the edges pass the gates at a slightly lower level
+    let lufs = meter.loudness().integrated_lufs.unwrap();
+    assert!((lufs + 20.0).abs() < 0.5, "{lufs:.2} LUFS");
+}
+
+#[test]
+fn test_pcm_input_and_peak() {
+    let samples = stereo_sine(44100, 3.0, 1000.0, 0.5);
+    let mut meter = LoudnessMeter::new(44100, 2);
+    meter.push_pcm(&from_f32(&samples, SampleFormat::I16), SampleFormat::I16);
+    let loudness = meter.loudness();
+    assert!((loudness.peak - 0.5).abs() < 0.01);
+    let lufs = loudness.integrated_lufs.unwrap();
+    assert!((lufs + 6.0).abs() < 0.2, "{lufs:.2} LUFS");
+
+    // Measured gain brings the track to -18 LUFS
+    let gain = ReplayGain::from_loudness(&loudness).unwrap();
+    assert!((gain.track_gain_db + 12.0).abs() < 0.2);
+}
+
+#[test]
+fn test_replay_gain_tags() {
+    assert_eq!(ReplayGain::parse_gain("-6.54 dB"), Some(-6.54));
+    assert_eq!(ReplayGain::parse_gain("+2.10dB"), Some(2.1));
+    assert_eq!(ReplayGain::parse_gain("loud"), None);
+    assert_eq!(ReplayGain::parse_peak("0.988553"), Some(0.988_553));
+    assert_eq!(ReplayGain::parse_peak("-1"), None);
+
+    // R128 gains are relative to -23 LUFS, 5 dB below the ReplayGain reference
+    let r128 = ReplayGain::from_r128_track_gain(-256);
+    assert!((r128.track_gain_db - 4.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_linear_gain_modes_and_peak_limit() {
+    let gain = ReplayGain {
+        track_gain_db: -6.0,
+        track_peak: Some(0.5),
+        album_gain_db: Some(-3.0),
+        album_peak: None,
+    };
+    assert!((gain.linear_gain(GainMode::Track, 0.0) - db_to_linear(-6.0)).abs() < 1e-6);
+    assert!((gain.linear_gain(GainMode::Album, 0.0) - db_to_linear(-3.0)).abs() < 1e-6);
+
+    // Boosting is capped where the peak reaches full scale
+    let boost = ReplayGain {
+        track_gain_db: 12.0,
+        track_peak: Some(0.5),
+        ..ReplayGain::default()
+    };
+    assert!((boost.linear_gain(GainMode::Track, 0.0) - 2.0).abs() < 1e-6);
+    // Album mode falls back to the track gain
+    assert!((boost.linear_gain(GainMode::Album, 0.0) - 2.0).abs() < 1e-6);
+}
@@ -0,0 +1,144 @@
+use crate::audio::raop_encoder::{AlacFrameEncoder, AudioEncodeError, RaopAudioEncoder};
+use crate::protocol::raop::encryption::RaopEncryptor;
+
+const FRAMES: u32 = RaopAudioEncoder::ALAC_FRAME_SAMPLES;
+
+fn to_bytes(samples: &[i16]) -> Vec<u8> {
+    samples.iter().flat_map(|s| s.to_le_bytes()).collect()
+}
+
+#[allow(clippy::cast_precision_loss, reason = "Test signal indices are small")]
+fn tone(frames: usize) -> Vec<i16> {
+    (0..frames * 2)
+        .map(|i| {
+            let t = (i / 2) as f32 / 44100.0;
+            #[allow(clippy::cast_possible_truncation, reason = "Amplitude is in range")]
+            let s = (12000.0 * (2.0 * std::f32::consts::PI * 440.0 * t).sin()) as i16;
+            s
+        })
+        .collect()
+}
+
+fn noise(samples: usize) -> Vec<i16> {
+    let mut state = 0x1234_5678_u32;
+    (0..samples)
+        .map(|_| {
+            state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            #[allow(
+                clippy::cast_possible_truncation,
+                clippy::cast_possible_wrap,
+                reason = "Keeps the high bits as a signed sample"
+            )]
+            let s = (state >> 16) as u16 as i16;
+            s
+        })
+        .collect()
+}
+
+#[test]
+fn test_escape_frames_match_reference_encoder() {
+    // Full-scale noise does not compress, so the reference encoder escapes
+    let pcm = to_bytes(&noise(FRAMES as usize * 2));
+    let mut compressed = AlacFrameEncoder::new(44100, 2, FRAMES, true).unwrap();
+    let mut escaped = AlacFrameEncoder::new(44100, 2, FRAMES, false).unwrap();
+    assert!(compressed.is_compressed());
+    assert!(!escaped.is_compressed());
+    assert_eq!(escaped.magic_cookie(), compressed.magic_cookie());
+
+    let reference = compressed.encode(&pcm).unwrap().to_vec();
+    let frame = escaped.encode(&pcm).unwrap();
+    assert_eq!(frame.len(), pcm.len() + 4);
+    assert_eq!(frame, reference.as_slice());
+}
+
+#[test]
+fn test_compressed_frames_are_smaller() {
+    let mut compressed = AlacFrameEncoder::new(44100, 2, FRAMES, true).unwrap();
+    let mut escaped = AlacFrameEncoder::new(44100, 2, FRAMES, false).unwrap();
+    let pcm = to_bytes(&tone(FRAMES as usize * 4));
+
+    let (mut small, mut large) = (0, 0);
+    for packet in pcm.chunks(FRAMES as usize * 4) {
+        small += compressed.encode(packet).unwrap().len();
+        large += escaped.encode(packet).unwrap().len();
+    }
+    assert!(small * 2 < large, "{small} vs {large}");
+}
+
+#[test]
+fn test_partial_and_invalid_frames() {
+    let mut escaped = AlacFrameEncoder::new(44100, 1, FRAMES, false).unwrap();
+
+    // A partial frame carries its sample count after the header
+    let frame = escaped.encode(&to_bytes(&[1, -1, 2])).unwrap();
+    assert_eq!(frame[..3], [0x00, 0x00, 0x12]);
+    assert_eq!(frame.len(), 14);
+
+    assert!(matches!(
+        escaped.encode(&[0; 3]),
+        Err(AudioEncodeError::InvalidFrameSize { .. })
+    ));
+    assert!(matches!(
+        escaped.encode(&vec![0; (FRAMES as usize + 1) * 2]),
+        Err(AudioEncodeError::InvalidFrameSize {
+            expected: 704,
+            actual: 706
+        })
+    ));
+
+    assert!(AlacFrameEncoder::new(44100, 0, FRAMES, true).is_err());
+    assert!(AlacFrameEncoder::new(44100, 6, FRAMES, false).is_err());
+    assert!(AlacFrameEncoder::new(44100, 6, FRAMES, true).is_ok());
+    assert!(AlacFrameEncoder::new(44100, 2, 0, true).is_err());
+}
+
+#[cfg(feature = "decoders")]
+#[test]
+fn test_frames_decode_losslessly() {
+    use symphonia::core::audio::SampleBuffer;
+    use symphonia::core::codecs::{CODEC_TYPE_ALAC, CodecParameters, DecoderOptions};
+    use symphonia::core::formats::Packet;
+
+    for compress in [true, false] {
+        let mut encoder = AlacFrameEncoder::new(44100, 2, FRAMES, compress).unwrap();
+        let mut params = CodecParameters::new();
+        params
+            .for_codec(CODEC_TYPE_ALAC)
+            .with_extra_data(encoder.magic_cookie().into_boxed_slice());
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&params, &DecoderOptions::default())
+            .unwrap();
+
+        let input = [tone(FRAMES as usize * 3), noise(FRAMES as usize * 2)].concat();
+        let mut output = Vec::new();
+        for (i, packet) in input.chunks(FRAMES as usize * 2).enumerate() {
+            let frame = encoder.encode(&to_bytes(packet)).unwrap();
+            let buffer = decoder
+                .decode(&Packet::new_from_slice(0, i as u64, 1, frame))
+                .unwrap();
+            let mut samples = SampleBuffer::<i16>::new(buffer.capacity() as u64, *buffer.spec());
+            samples.copy_interleaved_ref(buffer);
+            output.extend_from_slice(samples.samples());
+        }
+        assert_eq!(output, input, "compress = {compress}");
+    }
+}
+
+#[test]
+fn test_encode_packet_matches_encode() {
+    use crate::audio::AudioEncoder;
+
+    let pcm = to_bytes(&tone(FRAMES as usize));
+    let expected = AlacFrameEncoder::new(44100, 2, FRAMES, true)
+        .unwrap()
+        .encode(&pcm)
+        .unwrap()
+        .to_vec();
+
+    let mut encoder = AlacFrameEncoder::new(44100, 2, FRAMES, true).unwrap();
+    let mut payload = vec![0xAA; 8];
+    encoder.encode_packet(&pcm, &mut payload).unwrap();
+    assert_eq!(payload, expected);
+    assert_eq!(encoder.frames_per_packet(), FRAMES as usize);
+    assert!(encoder.flush().unwrap().is_empty());
+}
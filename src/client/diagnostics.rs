@@ -0,0 +1,195 @@
+//! Diagnostics bundle export
+//!
+//! [`AirPlayClient::export_diagnostics`](super::AirPlayClient::export_diagnostics)
+//! writes a tar archive with everything needed to make sense of a bug report:
+//! library and platform versions, the configuration, the negotiated stream,
+//! the device's `GET /info` answer, PTP status and the latest RTSP exchanges.
+//! Credentials, keys and hardware identifiers are redacted.
+
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::AirPlayClient;
+use crate::error::AirPlayError;
+use crate::protocol::plist::PlistValue;
+
+/// Directory the bundle's files are placed in
+const BUNDLE_DIR: &str = "airplay2-diagnostics";
+
+/// `GET /info` keys holding keys or hardware identifiers
+const REDACTED_INFO_KEYS: [&str; 7] = [
+    "pk",
+    "pi",
+    "psi",
+    "deviceID",
+    "macAddress",
+    "serialNumber",
+    "senderAddress",
+];
+
+/// Tar block size
+const BLOCK: usize = 512;
+
+impl AirPlayClient {
+    /// Write a diagnostics bundle to `path`
+    ///
+    /// The bundle is an uncompressed tar archive that can be attached to a
+    /// bug report. It holds:
+    ///
+    /// - `version.txt`: library version, OS and architecture
+    /// - `config.txt`: the client configuration, PIN removed
+    /// - `session.txt`: connection state, negotiated codec and format,
+    ///   statistics, PTP status and stream metrics
+    /// - `device-info.txt`: the device's `GET /info` answer, keys and
+    ///   identifiers removed
+    /// - `protocol.log`: the latest RTSP exchanges, credentials removed
+    ///
+    /// Works while disconnected, reporting what is known from the last
+    /// session.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written.
+    pub async fn export_diagnostics(&self, path: impl AsRef<Path>) -> Result<(), AirPlayError> {
+        let files = [
+            ("version.txt", version_report()),
+            ("config.txt", self.config_report()),
+            ("session.txt", self.session_report().await),
+            ("device-info.txt", self.device_info_report().await),
+            (
+                "protocol.log",
+                self.connection.protocol_log().join("\n") + "\n",
+            ),
+        ];
+
+        let mtime = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let mut archive = Vec::new();
+        for (name, contents) in &files {
+            append_tar_entry(
+                &mut archive,
+                &format!("{BUNDLE_DIR}/{name}"),
+                contents.as_bytes(),
+                mtime,
+            );
+        }
+        // End of archive
+        archive.resize(archive.len() + 2 * BLOCK, 0);
+
+        let path = path.as_ref();
+        tokio::fs::write(path, archive)
+            .await
+            .map_err(|e| AirPlayError::IoError {
+                message: format!("Failed to write diagnostics to {}", path.display()),
+                source: Some(Box::new(e)),
+            })
+    }
+
+    fn config_report(&self) -> String {
+        let mut config = self.config.clone();
+        if config.pin.is_some() {
+            config.pin = Some("<redacted>".to_string());
+        }
+        format!("{config:#?}\n")
+    }
+
+    async fn session_report(&self) -> String {
+        let connection = &self.connection;
+        let mut report = String::new();
+        let _ = writeln!(report, "state: {:?}", connection.state().await);
+        if let Some(device) = connection.device().await {
+            let _ = writeln!(report, "device: {}", device.name);
+            let _ = writeln!(report, "model: {:?}", device.device_model());
+            let _ = writeln!(report, "receiver profile: {:?}", device.receiver_profile());
+        }
+        let _ = writeln!(report, "codec: {:?}", connection.audio_codec());
+        let _ = writeln!(report, "aac bitrate: {}", connection.aac_bitrate());
+        let _ = writeln!(report, "stream format: {:?}", connection.stream_format());
+        let _ = writeln!(report, "ptp active: {}", connection.is_ptp_active().await);
+        if let Some((synchronized, offset_ms, measurements)) = self.ptp_status().await {
+            let _ = writeln!(
+                report,
+                "ptp: synchronized={synchronized} offset={offset_ms:.3}ms \
+                 measurements={measurements}"
+            );
+        }
+        let _ = writeln!(report, "stats: {:#?}", connection.stats().await);
+        if let Some(metrics) = self.stream_metrics().await {
+            let _ = writeln!(report, "stream metrics: {metrics:#?}");
+        }
+        report
+    }
+
+    async fn device_info_report(&self) -> String {
+        match self.connection.device_info().await {
+            Some(mut info) => {
+                redact_info(&mut info);
+                format!("{info:#?}\n")
+            }
+            None => "No GET /info response recorded\n".to_string(),
+        }
+    }
+}
+
+fn version_report() -> String {
+    format!(
+        "airplay2 {}\nos: {}\narch: {}\n",
+        crate::VERSION,
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    )
+}
+
+/// Replace keys and identifiers in a `GET /info` answer
+pub(crate) fn redact_info(value: &mut PlistValue) {
+    match value {
+        PlistValue::Dictionary(dict) => {
+            for (key, value) in dict.iter_mut() {
+                if REDACTED_INFO_KEYS.contains(&key.as_str()) {
+                    *value = PlistValue::String("<redacted>".to_string());
+                } else {
+                    redact_info(value);
+                }
+            }
+        }
+        PlistValue::Array(items) | PlistValue::Set(items) | PlistValue::OrderedSet(items) => {
+            items.iter_mut().for_each(redact_info);
+        }
+        _ => {}
+    }
+}
+
+/// Append a regular file to a ustar archive
+pub(crate) fn append_tar_entry(archive: &mut Vec<u8>, name: &str, data: &[u8], mtime: u64) {
+    let mut header = [0u8; BLOCK];
+    let name = name.as_bytes();
+    let name_len = name.len().min(100);
+    header[..name_len].copy_from_slice(&name[..name_len]);
+    write_octal(&mut header[100..108], 0o644);
+    write_octal(&mut header[108..116], 0);
+    write_octal(&mut header[116..124], 0);
+    write_octal(&mut header[124..136], data.len() as u64);
+    write_octal(&mut header[136..148], mtime);
+    header[156] = b'0';
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].fill(b' ');
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    write_octal(&mut header[148..155], u64::from(checksum));
+
+    archive.extend_from_slice(&header);
+    archive.extend_from_slice(data);
+    let padding = (BLOCK - data.len() % BLOCK) % BLOCK;
+    archive.resize(archive.len() + padding, 0);
+}
+
+/// Write `value` as zero-padded octal, NUL terminated, filling `field`
+fn write_octal(field: &mut [u8], value: u64) {
+    let digits = format!("{value:0width$o}", width = field.len() - 1);
+    field[..digits.len()].copy_from_slice(digits.as_bytes());
+    field[digits.len()] = 0;
+}
@@ -6,28 +6,48 @@ use std::time::Duration;
 use futures::Stream;
 use tokio::sync::{Mutex, RwLock};
 
-use crate::audio::AudioCodec;
-use crate::connection::{ConnectionManager, ConnectionState, DisconnectReason};
+use crate::audio::{AudioCodec, DUCK_FADE, DspChain, SoftGain};
+use crate::connection::{ConnectionManager, ConnectionState, DisconnectReason, FEEDBACK_INTERVAL};
 use crate::control::playback::{PlaybackController, ShuffleMode};
 use crate::control::queue::PlaybackQueue;
-use crate::control::volume::{Volume, VolumeController};
-use crate::discovery::{DiscoveryEvent, discover, scan};
+use crate::control::remote::{MediaCommand, RemoteEvent};
+use crate::control::volume::{GroupVolumeController, Volume, VolumeController};
+use crate::discovery::{DiscoveryEvent, discover, scan, scan_for};
 use crate::error::AirPlayError;
-use crate::protocol::daap::{DmapProgress, TrackMetadata};
+use crate::net::TaskSet;
+use crate::protocol::daap::{Artwork, DmapProgress, TrackMetadata};
+use crate::protocol::pairing::{PairingEntry, PairingsRequest};
+#[cfg(feature = "raop")]
+use crate::protocol::raop::DigestCredentials;
 use crate::state::{ClientEvent, ClientState, EventBus, StateContainer};
-use crate::streaming::{AudioSource, PcmStreamer, UrlStreamer};
+use crate::streaming::{
+    AnnouncementChannel, AudioSource, GaplessQueue, GaplessSource, MixMode, PcmStreamer,
+    SourceOpener, StreamMetrics, StreamPriority, StreamerState, Timeline, UrlStreamer,
+    normalizing_opener,
+};
 use crate::types::{
     AirPlayConfig, AirPlayDevice, PlaybackState, QueueItem, QueueItemId, RepeatMode, TrackInfo,
 };
 
+mod diagnostics;
+pub mod preflight;
 pub mod protocol;
+#[cfg(feature = "dacp")]
+mod remote_commands;
 pub mod session;
+mod stream_handle;
 
 #[cfg(test)]
 mod tests;
 
+pub use preflight::{CheckStatus, PreflightCheck, PreflightReport};
 pub use protocol::{PreferredProtocol, SelectedProtocol, check_raop_encryption, select_protocol};
-pub use session::{AirPlay2SessionImpl, AirPlaySession, RaopSessionImpl};
+#[cfg(feature = "dacp")]
+use remote_commands::RemoteCommands;
+#[cfg(feature = "raop")]
+pub use session::RaopSessionImpl;
+pub use session::{AirPlay2SessionImpl, AirPlaySession};
+pub use stream_handle::StreamHandle;
 
 /// `AirPlay` client for streaming audio to devices
 ///
@@ -71,14 +91,22 @@ pub struct AirPlayClient {
     volume: Arc<VolumeController>,
     /// Playback queue
     queue: Arc<RwLock<PlaybackQueue>>,
-    /// PCM streamer
-    streamer: Option<Arc<PcmStreamer>>,
+    /// PCM streamer of the current (or last) stream
+    streamer: Arc<Mutex<Option<Arc<PcmStreamer>>>>,
     /// URL streamer
     url_streamer: Arc<Mutex<Option<UrlStreamer>>>,
     /// State container
     state: Arc<StateContainer>,
     /// Event bus
     events: Arc<EventBus>,
+    /// Software gain applied to streamed PCM (ducking)
+    soft_gain: Arc<SoftGain>,
+    /// Announcements mixed into streamed PCM
+    announcements: Arc<AnnouncementChannel>,
+    /// DSP chain run over streamed PCM before encoding
+    dsp: Arc<std::sync::Mutex<DspChain>>,
+    /// Background tasks, aborted when the last clone of the client drops
+    tasks: Arc<TaskSet>,
 }
 
 impl AirPlayClient {
@@ -87,11 +115,13 @@ impl AirPlayClient {
     pub fn new(config: AirPlayConfig) -> Self {
         let connection = Arc::new(ConnectionManager::new(config.clone()));
         let playback = Arc::new(PlaybackController::new(connection.clone()));
-        let volume = Arc::new(VolumeController::new(connection.clone()));
+        let volume =
+            Arc::new(VolumeController::new(connection.clone()).with_ramp(config.volume_ramp));
         let queue = Arc::new(RwLock::new(PlaybackQueue::new()));
         let state = Arc::new(StateContainer::new());
         let events = Arc::new(EventBus::new());
         let url_streamer = Arc::new(Mutex::new(None));
+        let soft_gain = Arc::new(SoftGain::new());
 
         Self {
             config,
@@ -99,10 +129,14 @@ impl AirPlayClient {
             playback,
             volume,
             queue,
-            streamer: None,
+            streamer: Arc::new(Mutex::new(None)),
             url_streamer,
             state,
             events,
+            soft_gain: soft_gain.clone(),
+            announcements: Arc::new(AnnouncementChannel::new(soft_gain)),
+            dsp: Arc::new(std::sync::Mutex::new(DspChain::new())),
+            tasks: Arc::new(TaskSet::new("client")),
         }
     }
 
@@ -119,7 +153,8 @@ impl AirPlayClient {
 
         // Re-create components that depend on connection
         self.playback = Arc::new(PlaybackController::new(connection.clone()));
-        self.volume = Arc::new(VolumeController::new(connection.clone()));
+        self.volume =
+            Arc::new(VolumeController::new(connection.clone()).with_ramp(self.config.volume_ramp));
         self.connection = connection;
 
         self
@@ -142,6 +177,20 @@ impl AirPlayClient {
         scan(timeout).await
     }
 
+    /// Scan until the device with the given name or ID is found
+    ///
+    /// # Errors
+    ///
+    /// Returns error if mDNS discovery fails or the device does not appear
+    /// within `timeout`.
+    pub async fn scan_for(
+        &self,
+        name_or_id: &str,
+        timeout: Duration,
+    ) -> Result<AirPlayDevice, AirPlayError> {
+        scan_for(name_or_id, timeout).await
+    }
+
     /// Discover devices continuously
     ///
     /// # Errors
@@ -157,7 +206,10 @@ impl AirPlayClient {
     ///
     /// # Errors
     ///
-    /// Returns error if connection fails.
+    /// Returns error if connection fails, or if the device does not accept
+    /// the configured codec and [`codec_fallback`] is disabled.
+    ///
+    /// [`codec_fallback`]: crate::AirPlayConfig::codec_fallback
     pub async fn connect(&self, device: &AirPlayDevice) -> Result<(), AirPlayError> {
         self.connection.connect(device).await?;
 
@@ -170,10 +222,115 @@ impl AirPlayClient {
         self.events.emit(ClientEvent::Connected {
             device: device.clone(),
         });
+        for message in self
+            .connection
+            .codec_selection()
+            .map(|selection| selection.warnings)
+            .unwrap_or_default()
+        {
+            self.events.emit(ClientEvent::Warning { message });
+        }
 
         Ok(())
     }
 
+    /// Connect to a device at a known address without mDNS discovery
+    ///
+    /// Probes the device with `GET /info` to fill in its identity and
+    /// capabilities, then connects as [`connect`](Self::connect) does. Useful
+    /// on networks where multicast is blocked.
+    ///
+    /// Returns the probed device.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the probe or the connection fails.
+    pub async fn connect_addr(
+        &self,
+        ip: std::net::IpAddr,
+        port: u16,
+    ) -> Result<AirPlayDevice, AirPlayError> {
+        let device =
+            crate::connection::probe_device(ip, port, self.config.connection_timeout).await?;
+        self.connect(&device).await?;
+        Ok(device)
+    }
+
+    /// Check whether a device can be streamed to, without connecting
+    ///
+    /// Runs non-destructive checks: TCP reachability, `GET /info`, the
+    /// device's authentication requirements, codec compatibility with the
+    /// current configuration, and PTP feasibility. No pairing or session is
+    /// started, so this is safe to call while another sender is playing.
+    pub async fn preflight(&self, device: &AirPlayDevice) -> PreflightReport {
+        preflight::run(&self.config, device).await
+    }
+
+    /// Ask a device to identify itself
+    ///
+    /// Receivers that support it play a chime or flash their status light,
+    /// helping users match a discovered entry to a physical speaker. Uses a
+    /// separate short-lived connection, so no session is needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the device cannot be reached or does not support
+    /// identification.
+    pub async fn identify(&self, device: &AirPlayDevice) -> Result<(), AirPlayError> {
+        crate::connection::identify_device(
+            device.address(),
+            device.port,
+            self.config.connection_timeout,
+        )
+        .await
+    }
+
+    /// List the controllers paired with the connected device
+    ///
+    /// Requires a connection made with a stored admin pairing.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not connected or the device rejects the request.
+    pub async fn list_pairings(&self) -> Result<Vec<PairingEntry>, AirPlayError> {
+        self.connection
+            .manage_pairings(&PairingsRequest::List)
+            .await
+    }
+
+    /// Pair another controller with the connected device
+    ///
+    /// Adding an existing identifier updates its permissions. Requires a
+    /// connection made with a stored admin pairing.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not connected or the device rejects the request.
+    pub async fn add_pairing(&self, entry: PairingEntry) -> Result<(), AirPlayError> {
+        self.connection
+            .manage_pairings(&PairingsRequest::Add(entry))
+            .await
+            .map(|_| ())
+    }
+
+    /// Remove a controller's pairing from the connected device
+    ///
+    /// Unlike [`forget_device`](Self::forget_device), this changes the
+    /// device, not local storage. Requires a connection made with a stored
+    /// admin pairing.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not connected or the device rejects the request.
+    pub async fn remove_pairing(&self, identifier: &str) -> Result<(), AirPlayError> {
+        self.connection
+            .manage_pairings(&PairingsRequest::Remove {
+                identifier: identifier.to_string(),
+            })
+            .await
+            .map(|_| ())
+    }
+
     /// Forget a paired device
     ///
     /// Removes persistent pairing keys for the specified device ID.
@@ -189,9 +346,10 @@ impl AirPlayClient {
         let connection = self.connection.clone();
         let events = self.events.clone();
         let state = self.state.clone();
+        let streamer = self.streamer.clone();
         let mut rx = connection.subscribe();
 
-        tokio::spawn(async move {
+        self.tasks.spawn(async move {
             while let Ok(event) = rx.recv().await {
                 use crate::connection::ConnectionEvent;
                 match event {
@@ -219,18 +377,95 @@ impl AirPlayClient {
                             recoverable
                         );
                     }
+                    ConnectionEvent::ServerRequest { request }
+                        if request.method == crate::protocol::rtsp::Method::Teardown =>
+                    {
+                        if let Some(RemoteEvent::SessionPreempted { by }) =
+                            RemoteEvent::from_request(&request)
+                        {
+                            Self::yield_session(&connection, &state, &streamer, by).await;
+                        }
+                    }
+                    ConnectionEvent::RemoteEvent {
+                        event: RemoteEvent::SessionPreempted { by },
+                    } => {
+                        Self::yield_session(&connection, &state, &streamer, by).await;
+                    }
+                    ConnectionEvent::RemoteEvent { event } => {
+                        Self::apply_remote_event(&state, &events, event).await;
+                    }
+                    ConnectionEvent::SessionPreempted { by } => {
+                        events.emit(ClientEvent::SessionPreempted { by });
+                    }
+                    ConnectionEvent::Degraded { message } => {
+                        events.emit(ClientEvent::ConnectionDegraded { message });
+                    }
                     _ => {}
                 }
             }
         });
     }
 
+    /// Stop streaming and give the session up after another sender took
+    /// over the device
+    async fn yield_session(
+        connection: &ConnectionManager,
+        state: &StateContainer,
+        streamer: &Mutex<Option<Arc<PcmStreamer>>>,
+        by: Option<String>,
+    ) {
+        if let Some(streamer) = streamer.lock().await.take() {
+            if matches!(
+                streamer.state().await,
+                StreamerState::Buffering | StreamerState::Streaming | StreamerState::Paused
+            ) {
+                let _ = streamer.stop().await;
+            }
+        }
+        state.update(|s| s.playback.is_playing = false).await;
+        connection.yield_session(by).await;
+    }
+
+    /// Reflect an event posted by the device in the client state
+    async fn apply_remote_event(state: &StateContainer, events: &EventBus, event: RemoteEvent) {
+        match event {
+            RemoteEvent::NowPlaying(info) => {
+                if let Some(elapsed) = info.elapsed {
+                    state.set_position(elapsed).await;
+                }
+                if let Some(duration) = info.duration {
+                    state.set_duration(duration).await;
+                }
+                let track = info.title.map(|title| TrackInfo {
+                    title,
+                    artist: info.artist.unwrap_or_default(),
+                    album: info.album,
+                    duration_secs: info.duration,
+                    ..TrackInfo::default()
+                });
+                state.set_track(track.clone()).await;
+                events.emit(ClientEvent::TrackChanged { track });
+            }
+            RemoteEvent::VolumeChanged { volume } => {
+                state.set_volume(volume).await;
+                events.emit(ClientEvent::VolumeChanged { volume });
+            }
+            RemoteEvent::SessionPreempted { .. } => {}
+            RemoteEvent::Other { kind, .. } => {
+                tracing::debug!("Ignoring device event {kind}");
+            }
+        }
+    }
+
     fn start_keep_alive(&self) {
         let connection = self.connection.clone();
+        let events = self.events.clone();
+        let event_channel_timeout = self.config.event_channel_timeout;
 
-        tokio::spawn(async move {
-            // Check more frequently (1s) to detect disconnection faster
-            let mut interval = tokio::time::interval(Duration::from_secs(1));
+        self.tasks.spawn(async move {
+            let mut interval = tokio::time::interval(FEEDBACK_INTERVAL);
+            // Devices that reject /feedback are kept alive with GET /info
+            let mut use_feedback = connection.receiver_profile().feedback;
             loop {
                 interval.tick().await;
 
@@ -244,25 +479,50 @@ impl AirPlayClient {
                     continue;
                 }
 
-                tracing::debug!("Sending keep-alive (GET /info)");
-                // Send keep-alive (GET /info)
-                match connection.send_get_command("/info").await {
-                    Ok(_) => {
-                        tracing::debug!("Keep-alive success");
+                let result = if use_feedback {
+                    tracing::debug!("Sending keep-alive (POST /feedback)");
+                    match connection.send_feedback().await {
+                        Ok(streams) => {
+                            if !streams.is_empty() {
+                                events.emit(ClientEvent::Feedback { streams });
+                            }
+                            Ok(())
+                        }
+                        Err(AirPlayError::RtspError {
+                            status_code: Some(404 | 501),
+                            ..
+                        }) => {
+                            tracing::info!(
+                                "Device does not accept /feedback; keeping alive with GET /info"
+                            );
+                            use_feedback = false;
+                            connection.send_get_command("/info").await.map(drop)
+                        }
+                        Err(e) => Err(e),
                     }
-                    Err(e) => {
-                        tracing::warn!("Keep-alive failed: {}", e);
-                        // Trigger disconnect
-                        let reason = DisconnectReason::NetworkError(e.to_string());
-
-                        // We must force state update because send_get_command might not have done
-                        // it if it failed at logic level. But if it failed
-                        // with IO error, connection might be broken.
-                        // Calling disconnect_with_reason will ensure state update and event
-                        // emission.
-                        let _ = connection.disconnect_with_reason(reason).await;
+                } else {
+                    tracing::debug!("Sending keep-alive (GET /info)");
+                    connection.send_get_command("/info").await.map(drop)
+                };
+                // A silent event channel is verified with OPTIONS
+                let result = match (result, event_channel_timeout) {
+                    (Ok(()), Some(timeout)) => {
+                        connection.check_event_channel(timeout).await.map(drop)
+                    }
+                    (result, _) => result,
+                };
+
+                if let Err(e) = result {
+                    // A takeover closes the session under us; that was reported already
+                    if connection.state().await == ConnectionState::Disconnected {
                         break;
                     }
+                    tracing::warn!("Keep-alive failed: {}", e);
+                    // Calling disconnect_with_reason ensures the state update and
+                    // event emission even if the failure left the connection open.
+                    let reason = DisconnectReason::NetworkError(e.to_string());
+                    let _ = connection.disconnect_with_reason(reason).await;
+                    break;
                 }
             }
         });
@@ -320,14 +580,58 @@ impl AirPlayClient {
 
     // === Playback ===
 
+    /// Send a `MediaRemote` transport command
+    ///
+    /// Controls what the device is playing, including its own content such
+    /// as Apple Music on an Apple TV or `HomePod`, without streaming audio.
+    /// In remote-control-only mode the transport methods below ([`play`],
+    /// [`pause`], [`next`], ...) send these commands automatically.
+    ///
+    /// [`play`]: Self::play
+    /// [`pause`]: Self::pause
+    /// [`next`]: Self::next
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not connected or the device rejects the command.
+    pub async fn send_media_command(&self, command: MediaCommand) -> Result<(), AirPlayError> {
+        self.ensure_connected().await?;
+        self.playback.send_media_command(command).await?;
+        match command {
+            MediaCommand::Play => self.state.update(|s| s.playback.is_playing = true).await,
+            MediaCommand::Pause | MediaCommand::Stop => {
+                self.state.update(|s| s.playback.is_playing = false).await;
+            }
+            MediaCommand::TogglePlayPause => {
+                self.state
+                    .update(|s| s.playback.is_playing = !s.playback.is_playing)
+                    .await;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     /// Play (resume if paused)
     ///
     /// # Errors
     ///
     /// Returns error if playback command fails.
     pub async fn play(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::Play).await;
+        }
         self.ensure_connected().await?;
-        self.playback.play().await?;
+        if self.connection.is_buffered().await {
+            // Resume from the anchored position rather than the stream start
+            self.connection.send_set_rate_anchor_time(1.0).await?;
+            if let Some(streamer) = self.running_streamer().await {
+                streamer.resume().await?;
+            }
+            self.playback.set_playing(true).await;
+        } else {
+            self.playback.play().await?;
+        }
         self.state.update(|s| s.playback.is_playing = true).await;
         Ok(())
     }
@@ -338,8 +642,21 @@ impl AirPlayClient {
     ///
     /// Returns error if playback command fails.
     pub async fn pause(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::Pause).await;
+        }
         self.ensure_connected().await?;
-        self.playback.pause().await?;
+        if self.connection.is_buffered().await {
+            // The receiver stops rendering and keeps its buffer; stop
+            // feeding it so the stream resumes where it left off
+            self.connection.send_set_rate_anchor_time(0.0).await?;
+            if let Some(streamer) = self.running_streamer().await {
+                streamer.pause().await?;
+            }
+            self.playback.set_playing(false).await;
+        } else {
+            self.playback.pause().await?;
+        }
         self.state.update(|s| s.playback.is_playing = false).await;
         Ok(())
     }
@@ -350,6 +667,9 @@ impl AirPlayClient {
     ///
     /// Returns error if playback command fails.
     pub async fn toggle_playback(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::TogglePlayPause).await;
+        }
         self.ensure_connected().await?;
         self.playback.toggle().await
     }
@@ -360,6 +680,9 @@ impl AirPlayClient {
     ///
     /// Returns error if playback command fails.
     pub async fn stop(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::Stop).await;
+        }
         self.ensure_connected().await?;
         self.playback.stop().await?;
         self.state
@@ -377,6 +700,9 @@ impl AirPlayClient {
     ///
     /// Returns error if playback command fails.
     pub async fn next(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::NextTrack).await;
+        }
         self.ensure_connected().await?;
         self.playback.next().await?;
 
@@ -396,6 +722,9 @@ impl AirPlayClient {
     ///
     /// Returns error if playback command fails.
     pub async fn previous(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::PreviousTrack).await;
+        }
         self.ensure_connected().await?;
         self.playback.previous().await?;
 
@@ -410,12 +739,93 @@ impl AirPlayClient {
 
     /// Seek to position
     ///
+    /// While audio is streamed from here, the source moves to `position`, the
+    /// device drops the audio it queued and playback is re-anchored on the
+    /// first packet from the new position. Otherwise the device is asked to
+    /// scrub its own playback.
+    ///
     /// # Errors
     ///
-    /// Returns error if playback command fails.
+    /// Returns error if the audio source cannot seek or playback command
+    /// fails.
     pub async fn seek(&self, position: Duration) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return self.send_media_command(MediaCommand::Seek(position)).await;
+        }
         self.ensure_connected().await?;
-        self.playback.seek(position).await
+        let Some(streamer) = self.running_streamer().await else {
+            // Nothing streamed from here; ask the device to scrub
+            return self.playback.seek(position).await;
+        };
+
+        let (seq, timestamp) = streamer.seek_to_packet(position).await?;
+        self.playback
+            .seek_stream(position, seq, timestamp, streamer.frames_per_packet().await)
+            .await?;
+        self.state
+            .update(|s| s.playback.position_secs = position.as_secs_f64())
+            .await;
+        Ok(())
+    }
+
+    /// Current media position of the stream
+    ///
+    /// Estimated from the timeline anchored at the last play, pause or
+    /// seek. `None` until a stream starts.
+    pub async fn position(&self) -> Option<Duration> {
+        self.connection.timeline().await.map(|t| t.position())
+    }
+
+    /// The PCM streamer, if a stream is in progress
+    async fn running_streamer(&self) -> Option<Arc<PcmStreamer>> {
+        let streamer = self.streamer.lock().await.clone()?;
+        matches!(
+            streamer.state().await,
+            StreamerState::Buffering | StreamerState::Streaming | StreamerState::Paused
+        )
+        .then_some(streamer)
+    }
+
+    /// Re-send the playback anchor
+    ///
+    /// Sends a fresh `SETRATEANCHORTIME` at the current rate, so the device
+    /// re-derives its timeline from the current PTP offset. Used to pull a
+    /// drifting group member back in step.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not connected or the device rejects the anchor.
+    pub async fn reanchor(&self) -> Result<(), AirPlayError> {
+        self.ensure_connected().await?;
+        let rate = if self.playback.state().await.is_playing {
+            1.0
+        } else {
+            0.0
+        };
+        self.connection.send_set_rate_anchor_time(rate).await
+    }
+
+    /// Render this device's audio `delay` later than the stream is anchored
+    ///
+    /// Applied to every anchor from the next one on, including
+    /// [`reanchor`](Self::reanchor). Used for a group member's latency trim,
+    /// see [`DeviceGroup::apply_member_delays`](crate::group::DeviceGroup::apply_member_delays).
+    pub fn set_playback_delay(&self, delay: Duration) {
+        self.connection.set_playback_delay(delay);
+    }
+
+    /// Extra delay this device renders the stream with
+    #[must_use]
+    pub fn playback_delay(&self) -> Duration {
+        self.connection.playback_delay()
+    }
+
+    /// Media timeline of the current stream
+    ///
+    /// Maps media position to RTP and PTP time using the last anchor sent to
+    /// the device. `None` until a stream starts.
+    pub async fn timeline(&self) -> Option<Timeline> {
+        self.connection.timeline().await
     }
 
     /// Get current playback state
@@ -504,6 +914,39 @@ impl AirPlayClient {
         Ok(muted)
     }
 
+    /// Get the volume controller for this client's connection
+    #[must_use]
+    pub fn volume_controller(&self) -> Arc<VolumeController> {
+        self.volume.clone()
+    }
+
+    /// Build a group volume controller over several connected clients
+    ///
+    /// Each client contributes its own connection, keyed by the connected
+    /// device ID, so group changes are sent to every member in parallel.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if any client is not connected.
+    pub async fn group_volume(
+        clients: &[&AirPlayClient],
+    ) -> Result<GroupVolumeController, AirPlayError> {
+        let mut group = GroupVolumeController::new();
+        for client in clients {
+            client.ensure_connected().await?;
+            let device =
+                client
+                    .connection
+                    .device()
+                    .await
+                    .ok_or_else(|| AirPlayError::Disconnected {
+                        device_name: "none".to_string(),
+                    })?;
+            group.add_device(device.id, client.volume_controller());
+        }
+        Ok(group)
+    }
+
     /// Set track metadata
     ///
     /// # Errors
@@ -515,6 +958,10 @@ impl AirPlayClient {
 
     /// Set playback progress
     ///
+    /// PCM streams report their progress on their own every
+    /// [`AirPlayConfig::progress_interval`]; this overrides it until the
+    /// next report.
+    ///
     /// # Errors
     ///
     /// Returns error if network fails
@@ -522,13 +969,27 @@ impl AirPlayClient {
         self.playback.set_progress(progress).await
     }
 
-    /// Set artwork
+    /// Set cover art for the current track
+    ///
+    /// Oversized images are scaled down and, if need be, transcoded to
+    /// JPEG first, see [`Artwork::fit`]. The artwork is sent at the RTP time
+    /// of the last [`set_metadata`](Self::set_metadata).
     ///
     /// # Errors
     ///
-    /// Returns error if network fails
-    pub async fn set_artwork(&self, data: &[u8], mime_type: &str) -> Result<(), AirPlayError> {
-        self.playback.set_artwork(data, mime_type).await
+    /// Returns error if an oversized image cannot be scaled, or network
+    /// fails
+    pub async fn set_artwork(&self, artwork: Artwork) -> Result<(), AirPlayError> {
+        let artwork = tokio::task::spawn_blocking(move || artwork.fit())
+            .await
+            .map_err(|e| AirPlayError::InternalError {
+                message: format!("Artwork task failed: {e}"),
+            })?
+            .map_err(|e| AirPlayError::InvalidParameter {
+                name: "artwork".to_string(),
+                message: e.to_string(),
+            })?;
+        self.playback.set_artwork(&artwork).await
     }
 
     // === Queue ===
@@ -601,12 +1062,31 @@ impl AirPlayClient {
 
     /// Play a URL
     ///
+    /// The device fetches the URL itself. HLS playlists (`.m3u8`) are passed
+    /// through to devices that support HLS, such as Apple TV; for other
+    /// devices an audio-only stream is fetched and decoded here and
+    /// streamed as PCM (requires the `decoders` feature).
+    ///
+    /// Song titles in internet radio streams are not seen when the device
+    /// fetches the stream; play radio with `stream_http` to show them.
+    ///
     /// # Errors
     ///
     /// Returns error if playback fails or device is disconnected.
     pub async fn play_url(&self, url: &str) -> Result<(), AirPlayError> {
         self.ensure_connected().await?;
 
+        if UrlStreamer::is_hls(url) {
+            let native = self
+                .connection
+                .device()
+                .await
+                .is_some_and(|device| device.capabilities.supports_hls());
+            if !native {
+                return self.play_hls_locally(url).await;
+            }
+        }
+
         let mut url_streamer_lock = self.url_streamer.lock().await;
 
         if url_streamer_lock.is_none() {
@@ -621,6 +1101,94 @@ impl AirPlayClient {
         Ok(())
     }
 
+    /// Download and decode an `http://` URL here and stream it as PCM
+    /// (requires `decoders` feature)
+    ///
+    /// Unlike [`play_url`](Self::play_url) this works with any receiver.
+    /// For internet radio the song titles in the stream's ICY metadata are
+    /// sent to the device as [`TrackMetadata`] and reported as
+    /// [`ClientEvent::TrackChanged`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL cannot be fetched or decoded, or the stream
+    /// cannot start.
+    #[cfg(feature = "decoders")]
+    pub async fn stream_http(&self, url: &str) -> Result<StreamHandle, AirPlayError> {
+        let owned_url = url.to_string();
+        let source = tokio::task::spawn_blocking(move || {
+            crate::streaming::http::HttpSource::open(&owned_url)
+        })
+        .await
+        .map_err(|e| AirPlayError::InternalError {
+            message: format!("HTTP source task failed: {e}"),
+        })?
+        .map_err(|e| AirPlayError::IoError {
+            message: format!("Failed to open {url}: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+
+        let metadata = source.metadata();
+        let handle = self.stream_audio(source).await?;
+        self.tasks.spawn(Self::follow_stream_metadata(
+            url.to_string(),
+            metadata,
+            self.playback.clone(),
+            self.state.clone(),
+            self.events.clone(),
+        ));
+        Ok(handle)
+    }
+
+    /// Send each new radio title to the device and report it
+    #[cfg(feature = "decoders")]
+    async fn follow_stream_metadata(
+        url: String,
+        mut updates: tokio::sync::watch::Receiver<Option<crate::streaming::icy::IcyMetadata>>,
+        playback: Arc<PlaybackController>,
+        state: Arc<StateContainer>,
+        events: Arc<EventBus>,
+    ) {
+        while updates.changed().await.is_ok() {
+            let Some(metadata) = updates.borrow_and_update().clone() else {
+                continue;
+            };
+            if let Err(e) = playback.set_metadata(metadata.track_metadata()).await {
+                tracing::warn!("Failed to send stream title to device: {e}");
+            }
+            let track = TrackInfo {
+                url: url.clone(),
+                title: metadata.title,
+                artist: metadata.artist.unwrap_or_default(),
+                album: metadata.station,
+                ..TrackInfo::default()
+            };
+            state.set_track(Some(track.clone())).await;
+            events.emit(ClientEvent::TrackChanged { track: Some(track) });
+        }
+    }
+
+    /// Decode an HLS stream here and stream it as PCM
+    #[cfg(feature = "decoders")]
+    async fn play_hls_locally(&self, url: &str) -> Result<(), AirPlayError> {
+        // The stream keeps running after the handle is dropped
+        self.stream_http(url).await?;
+        Ok(())
+    }
+
+    /// Decode an HLS stream here and stream it as PCM
+    #[cfg(not(feature = "decoders"))]
+    #[allow(
+        clippy::unused_async,
+        reason = "Matches the signature of the decoders build"
+    )]
+    async fn play_hls_locally(&self, _url: &str) -> Result<(), AirPlayError> {
+        Err(AirPlayError::UnsupportedFormat {
+            format: "HLS on a device without native HLS support needs the `decoders` feature"
+                .to_string(),
+        })
+    }
+
     /// Get playback info from device (debug)
     ///
     /// Sends a `GET_PARAMETER` request with body "playback-info\r\n"
@@ -665,40 +1233,134 @@ impl AirPlayClient {
         }
     }
 
+    /// Duck streamed audio to `level` (0.0 - 1.0) for `duration`
+    ///
+    /// The samples are faded down, held, then faded back up, leaving the
+    /// device volume alone. Useful for overlaying a notification on music
+    /// without stopping it. Only applies to audio sent with
+    /// [`stream_audio`](Self::stream_audio).
+    pub fn duck(&self, level: f32, duration: Duration) {
+        self.soft_gain.duck(level, Some(duration), DUCK_FADE);
+    }
+
+    /// Fade ducked audio back to full level now
+    pub fn unduck(&self) {
+        self.soft_gain.restore(DUCK_FADE);
+    }
+
+    /// Set the digital volume (0.0 - 1.0) of streamed audio
+    ///
+    /// Scales the samples before they are sent, independent of the device
+    /// volume, and combines with [`duck`](Self::duck). Only applies to audio
+    /// sent with [`stream_audio`](Self::stream_audio).
+    pub fn set_digital_volume(&self, level: f32) {
+        self.soft_gain.set_level(level, DUCK_FADE);
+    }
+
+    /// Digital volume set with [`set_digital_volume`](Self::set_digital_volume)
+    #[must_use]
+    pub fn digital_volume(&self) -> f32 {
+        self.soft_gain.level()
+    }
+
+    /// Replace the DSP chain run over streamed audio before encoding
+    ///
+    /// Takes effect from the next packet, including in a running stream;
+    /// pass an empty [`DspChain`] to turn processing off. Only applies to
+    /// audio sent with [`stream_audio`](Self::stream_audio).
+    pub fn set_dsp_chain(&self, chain: DspChain) {
+        *self
+            .dsp
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = chain;
+    }
+
+    /// Whether streamed audio is currently ducked
+    #[must_use]
+    pub fn is_ducked(&self) -> bool {
+        self.soft_gain.is_ducked(std::time::Instant::now())
+    }
+
+    /// Play a short clip (chime, spoken notification) into the running
+    /// stream
+    ///
+    /// With [`MixMode::Duck`] the clip is mixed over the music, which is
+    /// ducked until the clip ends; with [`MixMode::Replace`] the music is
+    /// held and resumes where it stopped. A clip still playing is replaced.
+    /// Only applies to audio sent with [`stream_audio`](Self::stream_audio).
+    pub fn play_announcement<S: AudioSource + 'static>(&self, source: S, mode: MixMode) {
+        self.announcements.play(Box::new(source), mode);
+    }
+
+    /// Play an announcement or alarm using the configured preemption policy
+    ///
+    /// The policy for `priority` (see
+    /// [`AirPlayConfig::announcement_policy`] and
+    /// [`AirPlayConfig::alarm_policy`]) decides whether the music is ducked,
+    /// paused and resumed at the same position, or finishes first. An alarm
+    /// interrupts a running announcement, which resumes after it.
+    pub fn play_with_priority<S: AudioSource + 'static>(
+        &self,
+        source: S,
+        priority: StreamPriority,
+    ) {
+        let policy = match priority {
+            StreamPriority::Announcement => self.config.announcement_policy,
+            StreamPriority::Alarm => self.config.alarm_policy,
+        };
+        self.announcements
+            .play_with_priority(Box::new(source), priority, policy);
+    }
+
+    /// Stop the announcement that is playing and any that are waiting
+    pub fn stop_announcement(&self) {
+        self.announcements.cancel();
+    }
+
+    /// Metrics of the current (or last) PCM stream
+    ///
+    /// Encode times, packet send jitter and buffer depth, for telling host
+    /// CPU contention apart from network problems. `None` before the first
+    /// [`stream_audio`](Self::stream_audio).
+    pub async fn stream_metrics(&self) -> Option<StreamMetrics> {
+        self.streamer
+            .lock()
+            .await
+            .as_ref()
+            .map(|streamer| streamer.metrics())
+    }
+
     /// Stream raw PCM audio from a source
     ///
+    /// Returns once the stream has started; audio is sent in the background
+    /// until the source ends. The returned handle pauses, resumes or stops
+    /// the stream, or waits for it to finish.
+    ///
     /// # Errors
     ///
-    /// Returns error if streaming fails or device is disconnected.
+    /// Returns error if the device is disconnected, the client is configured
+    /// for remote control only, or the encoder for the negotiated codec
+    /// cannot be initialized.  Errors while streaming are
+    /// returned by [`StreamHandle::wait`] or [`StreamHandle::stop`].
     #[allow(
         clippy::too_many_lines,
         reason = "Complex streaming logic with multiple phases requires length"
     )]
     pub async fn stream_audio<S: AudioSource + 'static>(
-        &mut self,
+        &self,
         source: S,
-    ) -> Result<(), AirPlayError> {
+    ) -> Result<StreamHandle, AirPlayError> {
         self.ensure_connected().await?;
+        if self.config.remote_control_only {
+            return Err(AirPlayError::InvalidState {
+                message: "Connected in remote-control-only mode; audio cannot be streamed"
+                    .to_string(),
+                current_state: "RemoteControlOnly".to_string(),
+            });
+        }
 
-        // Check if high-resolution audio (24-bit/48kHz) should be used.
-        let device = self.connected_device().await;
-        let use_hires = self.config.prefer_hires_audio
-            && device.is_some_and(|d| d.capabilities.supports_hires_audio);
-
-        let target_format = if use_hires {
-            crate::audio::AudioFormat {
-                sample_rate: crate::audio::SampleRate::Hz48000,
-                channels: crate::audio::ChannelConfig::Stereo,
-                sample_format: crate::audio::SampleFormat::I24,
-            }
-        } else {
-            // AirPlay 2 typically uses 44.1kHz, 16-bit, Stereo.
-            crate::audio::AudioFormat {
-                sample_rate: crate::audio::SampleRate::Hz44100,
-                channels: crate::audio::ChannelConfig::Stereo,
-                sample_format: crate::audio::SampleFormat::I16,
-            }
-        };
+        // Negotiated with the device on connect
+        let target_format = self.connection.stream_format();
 
         let streamer = Arc::new(PcmStreamer::new(
             self.connection.clone(),
@@ -706,19 +1368,23 @@ impl AirPlayClient {
             self.config.audio_buffer_frames,
         ));
 
+        streamer.share_gain(self.soft_gain.clone()).await;
+        streamer.share_dsp(self.dsp.clone()).await;
+        streamer.set_announcements(self.announcements.clone()).await;
+        streamer.set_pacing(self.config.pacing).await;
+
         // Enable ALAC encoding if configured
-        if self.config.audio_codec == AudioCodec::Alac {
-            streamer.use_alac().await;
-        } else if self.config.audio_codec == AudioCodec::Aac {
-            streamer.use_aac(self.config.aac_bitrate).await;
+        match self.connection.audio_codec() {
+            AudioCodec::Alac if self.config.alac_compression => streamer.use_alac().await,
+            AudioCodec::Alac => streamer.use_alac_escape_frames().await,
+            AudioCodec::Aac => streamer.use_aac(self.connection.aac_bitrate()).await?,
+            AudioCodec::AacEld => streamer.use_aac_eld(self.connection.aac_bitrate()).await?,
+            _ => {}
         }
 
         // Configure encryption if available
         if let Some(key) = self.connection.encryption_key().await {
-            tracing::info!(
-                "Enabling ChaCha20-Poly1305 audio encryption (key[0..4]={:02X?})",
-                &key[..4]
-            );
+            tracing::info!("Enabling ChaCha20-Poly1305 audio encryption");
             streamer.set_encryption_key(key).await;
         } else {
             tracing::warn!(
@@ -727,7 +1393,10 @@ impl AirPlayClient {
             );
         }
 
-        self.streamer = Some(streamer.clone());
+        *self.streamer.lock().await = Some(streamer.clone());
+        self.connection
+            .start_timeline(target_format.sample_rate.as_u32())
+            .await;
 
         self.state.update(|s| s.playback.is_playing = true).await;
         self.playback.set_playing(true).await;
@@ -796,7 +1465,7 @@ impl AirPlayClient {
             // For non-PTP (AirPlay 1 / NTP) devices where RECORD is
             // deferred until the actual streaming begins.
             let connection = self.connection.clone();
-            tokio::spawn(async move {
+            self.tasks.spawn(async move {
                 // Short delay to allow streamer to fill buffer and start sending
                 tokio::time::sleep(Duration::from_millis(100)).await;
                 tracing::info!("Sending RECORD request to device...");
@@ -807,7 +1476,238 @@ impl AirPlayClient {
             });
         }
 
-        streamer.stream(source).await
+        // Reported alongside the stream, so reporting ends with it
+        let progress = Self::report_progress(
+            self.playback.clone(),
+            streamer.clone(),
+            source.duration(),
+            self.config.progress_interval,
+        );
+        #[cfg(feature = "dacp")]
+        let remote = self.start_remote_commands().await;
+        let task = tokio::spawn({
+            let streamer = streamer.clone();
+            async move {
+                // Commands are taken until the stream ends
+                #[cfg(feature = "dacp")]
+                let _remote = remote;
+                tokio::select! {
+                    result = streamer.stream(source) => result,
+                    () = progress => Ok(()),
+                }
+            }
+        });
+        Ok(StreamHandle::new(streamer, self.connection.clone(), task))
+    }
+
+    /// Start a DACP server passing the device's commands on as
+    /// [`ClientEvent::RemoteCommand`], advertised under the session's DACP-ID
+    ///
+    /// The server is torn down when the returned sender is dropped with the
+    /// stream, or on disconnect. `None` if disabled or the server cannot
+    /// start, in which case the stream plays without it.
+    #[cfg(feature = "dacp")]
+    async fn start_remote_commands(&self) -> Option<tokio::sync::oneshot::Sender<()>> {
+        if !self.config.remote_commands {
+            return None;
+        }
+        let active_remote = self.connection.active_remote().await?;
+        let dacp_id = self.connection.dacp_id().await?;
+        let connection = self.connection.subscribe();
+        match RemoteCommands::start(dacp_id, active_remote, self.events.clone()).await {
+            Ok(remote) => {
+                let (stream_ended, stream_ended_rx) = tokio::sync::oneshot::channel();
+                self.tasks.spawn(remote.serve(stream_ended_rx, connection));
+                Some(stream_ended)
+            }
+            Err(e) => {
+                tracing::warn!("Remote commands unavailable: {e}");
+                None
+            }
+        }
+    }
+
+    /// Send the playback position to the device every `interval` while
+    /// `streamer` is streaming
+    ///
+    /// Never returns; runs until dropped with the stream.
+    async fn report_progress(
+        playback: Arc<PlaybackController>,
+        streamer: Arc<PcmStreamer>,
+        duration: Option<Duration>,
+        interval: Option<Duration>,
+    ) {
+        let Some(interval) = interval.filter(|i| !i.is_zero()) else {
+            return std::future::pending().await;
+        };
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            if streamer.state().await != StreamerState::Streaming {
+                continue;
+            }
+            let (_, sent_rtp_time) = streamer.next_packet().await;
+            let Some(progress) = playback.stream_progress(sent_rtp_time, duration).await else {
+                continue;
+            };
+            if let Err(e) = playback.set_progress(progress).await {
+                tracing::debug!("Failed to send progress: {e}");
+            }
+        }
+    }
+
+    /// Play the queue gaplessly from its current track
+    ///
+    /// `opener` turns each queued track into audio. While a track plays the
+    /// next one is opened and then spliced on at the sample the first ends,
+    /// in the same stream. The queue advances and
+    /// [`ClientEvent::TrackChanged`] is emitted when playback reaches the
+    /// splice. The stream ends after the last track; tracks added before
+    /// then are played too.
+    ///
+    /// With [`AirPlayConfig::crossfade`] set, consecutive tracks overlap by
+    /// that long and the track changes where the fade begins.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the queue is empty, the first track cannot be
+    /// opened, or the stream cannot start. A later track that fails to open
+    /// ends the stream after the track before it.
+    pub async fn play_queue(&self, opener: SourceOpener) -> Result<StreamHandle, AirPlayError> {
+        let first = {
+            let mut queue = self.queue.write().await;
+            if queue.current().is_none() {
+                queue.set_current(0);
+            }
+            queue.current().cloned()
+        }
+        .ok_or_else(|| AirPlayError::InvalidState {
+            message: "Queue is empty".to_string(),
+            current_state: "Empty".to_string(),
+        })?;
+
+        let opener = match self.config.normalization {
+            Some(mode) => normalizing_opener(opener, mode),
+            None => opener,
+        };
+        let source = Self::open_track(&opener, &first.track).await?;
+        let (source, gapless) = GaplessSource::new(source);
+        let source = match self.config.crossfade {
+            Some(fade) => source.with_crossfade(fade),
+            None => source,
+        };
+        Self::preload_next(&gapless, &opener, &self.queue).await;
+        let stream = self.stream_audio(source).await?;
+
+        self.state.set_track(Some(first.track.clone())).await;
+        self.events.emit(ClientEvent::TrackChanged {
+            track: Some(first.track),
+        });
+
+        self.tasks.spawn(Self::run_gapless_queue(
+            gapless,
+            opener,
+            self.queue.clone(),
+            self.connection.clone(),
+            self.state.clone(),
+            self.events.clone(),
+        ));
+        Ok(stream)
+    }
+
+    async fn open_track(
+        opener: &SourceOpener,
+        track: &TrackInfo,
+    ) -> Result<Box<dyn AudioSource>, AirPlayError> {
+        let opener = opener.clone();
+        let track = track.clone();
+        tokio::task::spawn_blocking(move || opener(&track))
+            .await
+            .map_err(|e| AirPlayError::InternalError {
+                message: format!("Track opener failed: {e}"),
+            })?
+            .map_err(|e| AirPlayError::IoError {
+                message: "Failed to open track".to_string(),
+                source: Some(Box::new(e)),
+            })
+    }
+
+    /// Open the track after the last one spliced, if the source wants it
+    async fn preload_next(
+        gapless: &GaplessQueue,
+        opener: &SourceOpener,
+        queue: &RwLock<PlaybackQueue>,
+    ) {
+        if !gapless.needs_next() {
+            return;
+        }
+        // The queue only advances once a splice plays
+        let ahead = gapless.pending_boundaries();
+        let next = queue
+            .read()
+            .await
+            .upcoming(ahead + 1)
+            .get(ahead)
+            .copied()
+            .cloned();
+        let Some(item) = next else {
+            return;
+        };
+
+        gapless.start_opening();
+        let loaded = Self::open_track(opener, &item.track)
+            .await
+            .and_then(|source| {
+                gapless
+                    .preload(item, source)
+                    .map_err(|e| AirPlayError::IoError {
+                        message: "Cannot convert track to the stream format".to_string(),
+                        source: Some(Box::new(e)),
+                    })
+            });
+        if let Err(e) = loaded {
+            tracing::warn!("Gapless queue stops after this track: {e}");
+            gapless.cancel_opening();
+        }
+    }
+
+    /// Open each next queued track ahead of time and report each track
+    /// change as playback reaches it
+    async fn run_gapless_queue(
+        gapless: GaplessQueue,
+        opener: SourceOpener,
+        queue: Arc<RwLock<PlaybackQueue>>,
+        connection: Arc<ConnectionManager>,
+        state: Arc<StateContainer>,
+        events: Arc<EventBus>,
+    ) {
+        const POLL: Duration = Duration::from_millis(50);
+
+        while !gapless.is_closed() {
+            Self::preload_next(&gapless, &opener, &queue).await;
+
+            let mut wait = POLL;
+            if let Some(offset) = gapless.next_boundary() {
+                let position = connection
+                    .timeline()
+                    .await
+                    .map_or(Duration::ZERO, |t| t.position());
+                if position >= offset {
+                    if let Some(boundary) = gapless.pop_boundary() {
+                        queue.write().await.skip_to(boundary.item.id);
+                        state.set_track(Some(boundary.item.track.clone())).await;
+                        events.emit(ClientEvent::TrackChanged {
+                            track: Some(boundary.item.track),
+                        });
+                    }
+                    continue;
+                }
+                wait = wait.min(offset.saturating_sub(position));
+            }
+            tokio::time::sleep(wait).await;
+        }
     }
 
     // === Events ===
@@ -833,6 +1733,13 @@ impl AirPlayClient {
     pub async fn ptp_clock(&self) -> Option<crate::protocol::ptp::handler::SharedPtpClock> {
         self.connection.ptp_clock().await
     }
+
+    /// Leaf `MFi` certificate the device presented during Auth-Setup
+    ///
+    /// Only verified when [`AirPlayConfig::verify_mfi`] is set.
+    pub async fn mfi_certificate(&self) -> Option<crate::protocol::pairing::MfiCertificate> {
+        self.connection.mfi_certificate().await
+    }
 }
 
 /// Unified `AirPlay` client configuration
@@ -846,6 +1753,10 @@ pub struct ClientConfig {
     pub enable_dacp: bool,
     /// Enable metadata transmission
     pub enable_metadata: bool,
+    /// Username for password-protected RAOP receivers (defaults to `iTunes`)
+    pub username: Option<String>,
+    /// Password for password-protected RAOP receivers
+    pub password: Option<String>,
 }
 
 impl Default for ClientConfig {
@@ -855,6 +1766,8 @@ impl Default for ClientConfig {
             connection_timeout: std::time::Duration::from_secs(10),
             enable_dacp: true,
             enable_metadata: true,
+            username: None,
+            password: None,
         }
     }
 }
@@ -904,7 +1817,7 @@ impl UnifiedAirPlayClient {
             AirPlayError::ConnectionFailed {
                 device_name: device.name.clone(),
                 message: e.to_string(),
-                source: None,
+                source: Some(Box::new(e)),
             }
         })?;
 
@@ -914,10 +1827,30 @@ impl UnifiedAirPlayClient {
                 device.clone(),
                 AirPlayConfig::default(),
             )),
+            #[cfg(feature = "raop")]
             SelectedProtocol::Raop => {
                 let addr = device.address();
                 let port = device.raop_port.unwrap_or(5000);
-                Box::new(RaopSessionImpl::new(&addr.to_string(), port))
+                let mut session = RaopSessionImpl::new(&addr.to_string(), port);
+                if let Some(mac) = device.mac_address() {
+                    session = session.with_mac_address(mac);
+                }
+                if let Some(password) = &self.config.password {
+                    let mut credentials = DigestCredentials::new(password.clone());
+                    if let Some(username) = &self.config.username {
+                        credentials.username.clone_from(username);
+                    }
+                    session = session.with_credentials(credentials);
+                }
+                Box::new(session)
+            }
+            #[cfg(not(feature = "raop"))]
+            SelectedProtocol::Raop => {
+                return Err(AirPlayError::ConnectionFailed {
+                    device_name: device.name.clone(),
+                    message: "RAOP support requires the `raop` feature".to_string(),
+                    source: None,
+                });
             }
         };
 
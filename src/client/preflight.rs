@@ -0,0 +1,276 @@
+//! Pre-flight connection checks
+//!
+//! A [`PreflightReport`] describes everything that would stop a device from
+//! streaming with the current configuration, without pairing or starting a
+//! session. Setup wizards can show each check to the user instead of a single
+//! handshake error.
+
+use std::fmt;
+use std::net::SocketAddr;
+
+use tokio::net::TcpStream;
+
+use crate::connection::probe_info;
+use crate::protocol::ptp::handler::PTP_EVENT_PORT;
+use crate::types::{AirPlayConfig, AirPlayDevice, TimingProtocol};
+
+/// A single pre-flight check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PreflightCheck {
+    /// The device accepts TCP connections on its `AirPlay` port
+    Reachability,
+    /// The device answers `GET /info`
+    Info,
+    /// Credentials needed by the device are configured
+    Authentication,
+    /// The configured codec is accepted by the device
+    Codec,
+    /// The configured timing protocol can be used
+    Timing,
+}
+
+impl fmt::Display for PreflightCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Reachability => "reachability",
+            Self::Info => "device info",
+            Self::Authentication => "authentication",
+            Self::Codec => "codec",
+            Self::Timing => "timing",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Outcome of a pre-flight check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CheckStatus {
+    /// Nothing in the way
+    Passed,
+    /// Streaming should work, but with a caveat
+    Warning(String),
+    /// Streaming will fail
+    Failed(String),
+    /// The check does not apply or could not run
+    Skipped(String),
+}
+
+impl CheckStatus {
+    /// Whether this outcome blocks streaming
+    #[must_use]
+    pub fn is_blocking(&self) -> bool {
+        matches!(self, Self::Failed(_))
+    }
+
+    /// Explanation, if any
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Passed => None,
+            Self::Warning(msg) | Self::Failed(msg) | Self::Skipped(msg) => Some(msg),
+        }
+    }
+}
+
+/// Result of [`AirPlayClient::preflight`](super::AirPlayClient::preflight)
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    /// The device, refreshed from `GET /info` when it answered
+    pub device: AirPlayDevice,
+    /// Address that accepted a connection
+    pub address: Option<SocketAddr>,
+    /// Checks in the order they ran
+    pub checks: Vec<(PreflightCheck, CheckStatus)>,
+}
+
+impl PreflightReport {
+    /// Whether no check found a blocking problem
+    #[must_use]
+    pub fn can_connect(&self) -> bool {
+        !self.checks.iter().any(|(_, status)| status.is_blocking())
+    }
+
+    /// Outcome of a particular check
+    #[must_use]
+    pub fn status(&self, check: PreflightCheck) -> Option<&CheckStatus> {
+        self.checks
+            .iter()
+            .find(|(c, _)| *c == check)
+            .map(|(_, status)| status)
+    }
+
+    /// Checks that failed, with the reason
+    pub fn blockers(&self) -> impl Iterator<Item = (PreflightCheck, &str)> {
+        self.checks
+            .iter()
+            .filter_map(|(check, status)| match status {
+                CheckStatus::Failed(msg) => Some((*check, msg.as_str())),
+                _ => None,
+            })
+    }
+
+    /// Checks that passed with a caveat
+    pub fn warnings(&self) -> impl Iterator<Item = (PreflightCheck, &str)> {
+        self.checks
+            .iter()
+            .filter_map(|(check, status)| match status {
+                CheckStatus::Warning(msg) => Some((*check, msg.as_str())),
+                _ => None,
+            })
+    }
+}
+
+/// Run all checks against a device
+pub(crate) async fn run(config: &AirPlayConfig, device: &AirPlayDevice) -> PreflightReport {
+    let mut device = device.clone();
+    let mut checks = Vec::with_capacity(5);
+
+    let address = match reachable_address(config, &device).await {
+        Ok(addr) => {
+            checks.push((PreflightCheck::Reachability, CheckStatus::Passed));
+            Some(addr)
+        }
+        Err(msg) => {
+            checks.push((PreflightCheck::Reachability, CheckStatus::Failed(msg)));
+            None
+        }
+    };
+
+    let info = match address {
+        _ if is_raop_only(&device) => {
+            CheckStatus::Skipped("RAOP-only devices do not serve /info".to_string())
+        }
+        None => CheckStatus::Skipped("device is not reachable".to_string()),
+        Some(addr) => match probe_info(addr.ip(), addr.port(), config.connection_timeout).await {
+            Ok(info) => {
+                device.update_from_info(&info);
+                CheckStatus::Passed
+            }
+            Err(e) => CheckStatus::Warning(format!(
+                "GET /info failed ({e}); using advertised capabilities"
+            )),
+        },
+    };
+    checks.push((PreflightCheck::Info, info));
+
+    checks.push((
+        PreflightCheck::Authentication,
+        check_authentication(config, &device),
+    ));
+    checks.push((PreflightCheck::Codec, check_codec(config, &device)));
+    checks.push((PreflightCheck::Timing, check_timing(config, &device)));
+
+    PreflightReport {
+        device,
+        address,
+        checks,
+    }
+}
+
+/// Try each advertised address in turn
+async fn reachable_address(
+    config: &AirPlayConfig,
+    device: &AirPlayDevice,
+) -> Result<SocketAddr, String> {
+    if device.addresses.is_empty() {
+        return Err("device has no known address".to_string());
+    }
+
+    let mut last_error = String::new();
+    for ip in &device.addresses {
+        let addr = SocketAddr::new(*ip, device.port);
+        match tokio::time::timeout(config.connection_timeout, TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => return Ok(addr),
+            Ok(Err(e)) => last_error = format!("cannot connect to {addr}: {e}"),
+            Err(_) => last_error = format!("timed out connecting to {addr}"),
+        }
+    }
+    Err(last_error)
+}
+
+fn is_raop_only(device: &AirPlayDevice) -> bool {
+    !device.supports_airplay2() && device.raop_port == Some(device.port)
+}
+
+/// Check that the credentials the device asks for are available
+pub(crate) fn check_authentication(config: &AirPlayConfig, device: &AirPlayDevice) -> CheckStatus {
+    if device.requires_password() {
+        return CheckStatus::Failed("device requires a password".to_string());
+    }
+    if device.capabilities.requires_homekit_pairing {
+        return if config.pin.is_some() {
+            CheckStatus::Warning("device will pair using the configured PIN".to_string())
+        } else if config.pairing_storage_path.is_some() {
+            CheckStatus::Warning(
+                "device requires pairing; connecting needs a stored pairing or a PIN".to_string(),
+            )
+        } else {
+            CheckStatus::Failed("device requires pairing with a PIN; none configured".to_string())
+        };
+    }
+    if device.capabilities.access_control_restricted {
+        return CheckStatus::Warning(
+            "device restricts access; it may reject senders outside its home".to_string(),
+        );
+    }
+    CheckStatus::Passed
+}
+
+/// Check the configured codec against what the device advertises
+pub(crate) fn check_codec(config: &AirPlayConfig, device: &AirPlayDevice) -> CheckStatus {
+    if !device.supports_airplay2()
+        && device.raop_capabilities.is_none()
+        && !device.capabilities.supports_audio
+    {
+        return CheckStatus::Failed("device does not advertise audio support".to_string());
+    }
+    match config.select_codec(device) {
+        Err(e) => return CheckStatus::Failed(e.to_string()),
+        Ok(selection) => {
+            if let Some(warning) = selection.warnings.into_iter().next() {
+                return CheckStatus::Warning(warning);
+            }
+        }
+    }
+
+    if config.prefer_hires_audio && !device.capabilities.supports_hires_audio {
+        return CheckStatus::Warning(
+            "device does not support high-resolution audio; 16-bit/44.1kHz will be used"
+                .to_string(),
+        );
+    }
+    CheckStatus::Passed
+}
+
+/// Check that the configured timing protocol can run
+pub(crate) fn check_timing(config: &AirPlayConfig, device: &AirPlayDevice) -> CheckStatus {
+    let device_ptp = device.supports_ptp() || device.supports_airplay2();
+    let use_ptp = match config.timing_protocol {
+        TimingProtocol::Ptp => {
+            if !device_ptp {
+                return CheckStatus::Failed(
+                    "PTP timing is configured but the device does not support it".to_string(),
+                );
+            }
+            true
+        }
+        TimingProtocol::Ntp => {
+            if device.supports_ptp() && device.raop_capabilities.is_none() {
+                return CheckStatus::Warning(
+                    "device expects PTP timing; NTP may not keep it in sync".to_string(),
+                );
+            }
+            false
+        }
+        TimingProtocol::Auto => device_ptp,
+    };
+
+    if use_ptp {
+        if let Err(e) = crate::connection::ConnectionManager::bind_ptp_port(PTP_EVENT_PORT) {
+            return CheckStatus::Warning(format!(
+                "cannot bind PTP port {PTP_EVENT_PORT} ({e}); clock sync may fail"
+            ));
+        }
+    }
+    CheckStatus::Passed
+}
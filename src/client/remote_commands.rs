@@ -0,0 +1,96 @@
+//! DACP commands from the device, passed on as client events
+
+use std::sync::Arc;
+
+use tokio::sync::{broadcast, oneshot};
+
+use crate::connection::ConnectionEvent;
+use crate::protocol::dacp::{
+    CommandResult, DacpCommand, DacpError, DacpHandler, DacpServer, DacpService, DacpServiceConfig,
+};
+use crate::state::{ClientEvent, EventBus};
+
+/// A DACP server for one stream, advertised as `iTunes_Ctrl_<DACP-ID>`
+pub(super) struct RemoteCommands {
+    server: DacpServer<RemoteCommandForwarder>,
+    service: DacpService,
+}
+
+impl RemoteCommands {
+    /// Start a server on any free port and advertise it under the session's
+    /// DACP-ID, accepting commands that carry `active_remote`
+    pub(super) async fn start(
+        dacp_id: String,
+        active_remote: String,
+        events: Arc<EventBus>,
+    ) -> Result<Self, DacpError> {
+        let forwarder = RemoteCommandForwarder::new(active_remote.clone(), events);
+        let mut server = DacpServer::new(forwarder, active_remote.clone(), 0);
+        server.start().await?;
+        let port = server.local_addr().map_or(0, |addr| addr.port());
+
+        let mut service = DacpService::new(DacpServiceConfig {
+            db_id: dacp_id.clone(),
+            dacp_id,
+            active_remote,
+            port,
+        });
+        service.register().await?;
+        Ok(Self { server, service })
+    }
+
+    /// Serve commands until `stream_ended` fires or is dropped, or the
+    /// connection closes, then withdraw the service and stop the server
+    pub(super) async fn serve(
+        mut self,
+        stream_ended: oneshot::Receiver<()>,
+        mut connection: broadcast::Receiver<ConnectionEvent>,
+    ) {
+        let disconnected = async {
+            loop {
+                match connection.recv().await {
+                    Ok(ConnectionEvent::Disconnected { .. })
+                    | Err(broadcast::error::RecvError::Closed) => break,
+                    _ => {}
+                }
+            }
+        };
+        tokio::select! {
+            _ = stream_ended => {}
+            () = disconnected => {}
+        }
+
+        if let Err(e) = self.service.unregister().await {
+            tracing::warn!("Failed to withdraw DACP service: {e}");
+        }
+        self.server.stop().await;
+        tracing::debug!("Remote commands stopped");
+    }
+}
+
+/// Emits each command the device sends as [`ClientEvent::RemoteCommand`]
+///
+/// Commands are accepted as long as they carry the Active-Remote token the
+/// client sent; acting on them is left to the application.
+pub(super) struct RemoteCommandForwarder {
+    token: String,
+    events: Arc<EventBus>,
+}
+
+impl RemoteCommandForwarder {
+    pub(super) fn new(token: String, events: Arc<EventBus>) -> Self {
+        Self { token, events }
+    }
+}
+
+impl DacpHandler for RemoteCommandForwarder {
+    fn handle_command(&self, command: DacpCommand) -> CommandResult {
+        tracing::debug!("Remote command from device: {}", command.description());
+        self.events.emit(ClientEvent::RemoteCommand { command });
+        CommandResult::Success
+    }
+
+    fn verify_token(&self, token: &str) -> bool {
+        crate::protocol::crypto::constant_time_eq(token.as_bytes(), self.token.as_bytes())
+    }
+}
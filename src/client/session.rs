@@ -1,11 +1,16 @@
 //! Unified session abstraction
 
 use async_trait::async_trait;
+#[cfg(feature = "raop")]
 use tokio::net::{TcpStream, UdpSocket};
 
 use crate::client::AirPlayClient;
 use crate::error::AirPlayError;
+#[cfg(feature = "raop")]
 use crate::net::{AsyncReadExt, AsyncWriteExt};
+#[cfg(feature = "raop")]
+use crate::protocol::raop::DigestCredentials;
+#[cfg(feature = "raop")]
 use crate::protocol::rtsp::{Method, RtspCodec, RtspRequest, RtspResponse};
 use crate::types::{AirPlayConfig, AirPlayDevice, PlaybackState, TrackInfo};
 
@@ -56,8 +61,9 @@ pub trait AirPlaySession: Send + Sync {
 }
 
 /// RAOP session implementation
+#[cfg(feature = "raop")]
 pub struct RaopSessionImpl {
-    rtsp_session: crate::protocol::raop::RaopRtspSession,
+    pub(crate) rtsp_session: crate::protocol::raop::RaopRtspSession,
     stream: Option<TcpStream>,
     codec: RtspCodec,
     streamer: Option<crate::streaming::raop_streamer::RaopStreamer>,
@@ -68,8 +74,11 @@ pub struct RaopSessionImpl {
     server_port: u16,
     audio_socket: Option<UdpSocket>,
     control_socket: Option<UdpSocket>,
+    /// Bound before SETUP, handed to the streamer's timing responder
+    timing_socket: Option<UdpSocket>,
 }
 
+#[cfg(feature = "raop")]
 impl RaopSessionImpl {
     /// Create new RAOP session
     #[must_use]
@@ -86,10 +95,45 @@ impl RaopSessionImpl {
             server_port,
             audio_socket: None,
             control_socket: None,
+            timing_socket: None,
         }
     }
 
-    async fn send_request(&mut self, request: RtspRequest) -> Result<RtspResponse, AirPlayError> {
+    /// Verify the receiver's Apple-Response against this hardware address
+    #[must_use]
+    pub fn with_mac_address(mut self, mac: [u8; 6]) -> Self {
+        self.rtsp_session.set_mac_address(mac);
+        self
+    }
+
+    /// Authenticate with a password-protected receiver
+    #[must_use]
+    pub fn with_credentials(mut self, credentials: DigestCredentials) -> Self {
+        self.rtsp_session.set_credentials(credentials);
+        self
+    }
+
+    /// Send a request, answering a digest challenge once if the receiver
+    /// asks for a password
+    async fn send_request(
+        &mut self,
+        mut request: RtspRequest,
+    ) -> Result<RtspResponse, AirPlayError> {
+        self.rtsp_session.authorize(&mut request);
+        let response = self.exchange(&request).await?;
+        if !self.rtsp_session.accept_challenge(&response) {
+            return Ok(response);
+        }
+
+        tracing::debug!(
+            "Receiver requested a password, retrying {}",
+            request.method.as_str()
+        );
+        let retry = self.rtsp_session.retry_request(&request);
+        self.exchange(&retry).await
+    }
+
+    async fn exchange(&mut self, request: &RtspRequest) -> Result<RtspResponse, AirPlayError> {
         let stream = self
             .stream
             .as_mut()
@@ -117,6 +161,7 @@ impl RaopSessionImpl {
                 Err(e) => {
                     return Err(AirPlayError::CodecError {
                         message: e.to_string(),
+                        source: Some(Box::new(e)),
                     });
                 }
             }
@@ -145,6 +190,7 @@ impl RaopSessionImpl {
                 .feed(&buf[..n])
                 .map_err(|e| AirPlayError::CodecError {
                     message: e.to_string(),
+                    source: Some(Box::new(e)),
                 })?;
         }
     }
@@ -169,36 +215,47 @@ impl RaopSessionImpl {
                     device_name: self.server_addr.clone(),
                 })?;
 
-        let audio_socket = self
-            .setup_udp_socket(transport.server_port, "audio")
-            .await?;
-        let control_socket = self
-            .setup_udp_socket(transport.control_port, "control")
+        let audio_socket = self.bind_udp_socket("audio").await?;
+        self.connect_udp_socket(&audio_socket, transport.server_port, "audio")
             .await?;
+        // Bound before SETUP so the receiver knows where to send retransmit
+        // requests
+        if let Some(control_socket) = &self.control_socket {
+            self.connect_udp_socket(control_socket, transport.control_port, "control")
+                .await?;
+        }
 
-        let config = crate::streaming::raop_streamer::RaopStreamConfig::default();
-        let streamer = crate::streaming::raop_streamer::RaopStreamer::new(keys, config);
+        let config = crate::streaming::raop_streamer::RaopStreamConfig {
+            latency: self.rtsp_session.audio_latency(),
+            ..Default::default()
+        };
+        let mut streamer = crate::streaming::raop_streamer::RaopStreamer::new(keys, config);
+        if let Some(timing_socket) = self.timing_socket.take() {
+            streamer.spawn_timing_responder(timing_socket);
+        }
 
         self.streamer = Some(streamer);
         self.audio_socket = Some(audio_socket);
-        self.control_socket = Some(control_socket);
 
         Ok(())
     }
 
-    async fn setup_udp_socket(
+    async fn bind_udp_socket(&self, name: &'static str) -> Result<UdpSocket, AirPlayError> {
+        UdpSocket::bind("0.0.0.0:0")
+            .await
+            .map_err(|e| AirPlayError::ConnectionFailed {
+                message: format!("Failed to bind {name} socket: {e}"),
+                source: Some(Box::new(e)),
+                device_name: self.server_addr.clone(),
+            })
+    }
+
+    async fn connect_udp_socket(
         &self,
+        socket: &UdpSocket,
         port: u16,
         name: &'static str,
-    ) -> Result<UdpSocket, AirPlayError> {
-        let socket =
-            UdpSocket::bind("0.0.0.0:0")
-                .await
-                .map_err(|e| AirPlayError::ConnectionFailed {
-                    message: format!("Failed to bind {name} socket: {e}"),
-                    source: Some(Box::new(e)),
-                    device_name: self.server_addr.clone(),
-                })?;
+    ) -> Result<(), AirPlayError> {
         socket
             .connect((self.server_addr.as_str(), port))
             .await
@@ -206,11 +263,55 @@ impl RaopSessionImpl {
                 message: format!("Failed to connect {name} socket: {e}"),
                 source: Some(Box::new(e)),
                 device_name: self.server_addr.clone(),
-            })?;
-        Ok(socket)
+            })
+    }
+
+    /// Send a sync packet on the control channel if one is due
+    ///
+    /// Receivers place audio on their timeline from these, so they go out
+    /// before the first packet after start/flush and every second after.
+    async fn send_sync_if_due(&mut self) {
+        let (Some(streamer), Some(socket)) = (&mut self.streamer, &self.control_socket) else {
+            return;
+        };
+        if !streamer.should_send_sync() {
+            return;
+        }
+        let packet = streamer.create_sync_packet();
+        if let Err(e) = socket.send(&packet).await {
+            tracing::debug!("Failed to send sync packet: {e}");
+        }
+    }
+
+    /// Answer any retransmit requests waiting on the control channel
+    ///
+    /// Lost packets are resent from the streamer's buffer on the control
+    /// socket, as receivers expect.
+    async fn service_control_channel(&mut self) {
+        let (Some(streamer), Some(socket)) = (&self.streamer, &self.control_socket) else {
+            return;
+        };
+
+        let mut buf = [0u8; 1500];
+        loop {
+            let n = match socket.try_recv(&mut buf) {
+                Ok(n) => n,
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) => {
+                    tracing::debug!("Control channel receive failed: {e}");
+                    break;
+                }
+            };
+            for packet in streamer.handle_control_packet(&buf[..n]) {
+                if let Err(e) = socket.send(&packet).await {
+                    tracing::warn!("Failed to send retransmit packet: {e}");
+                }
+            }
+        }
     }
 }
 
+#[cfg(feature = "raop")]
 #[async_trait]
 impl AirPlaySession for RaopSessionImpl {
     async fn connect(&mut self) -> Result<(), AirPlayError> {
@@ -224,6 +325,9 @@ impl AirPlaySession for RaopSessionImpl {
                     source: Some(Box::new(e)),
                     device_name: addr.clone(),
                 })?;
+        if let (Ok(local), Ok(peer)) = (stream.local_addr(), stream.peer_addr()) {
+            self.rtsp_session.set_addresses(local.ip(), peer.ip());
+        }
         self.stream = Some(stream);
 
         // 1. Send OPTIONS with Apple-Challenge
@@ -234,6 +338,7 @@ impl AirPlaySession for RaopSessionImpl {
             .map_err(|e| AirPlayError::RtspError {
                 message: e,
                 status_code: None,
+                source: None,
             })?;
 
         // 2. Send ANNOUNCE with SDP
@@ -243,6 +348,7 @@ impl AirPlaySession for RaopSessionImpl {
             .map_err(|e| AirPlayError::RtspError {
                 message: e,
                 status_code: None,
+                source: None,
             })?;
         let req = self.rtsp_session.announce_request(&sdp);
         let resp = self.send_request(req).await?;
@@ -251,17 +357,24 @@ impl AirPlaySession for RaopSessionImpl {
             .map_err(|e| AirPlayError::RtspError {
                 message: e,
                 status_code: None,
+                source: None,
             })?;
 
         // 3. Send SETUP to configure transport
-        // We assume dynamic ports for client side (0)
-        let req = self.rtsp_session.setup_request(0, 0);
+        let control_socket = self.bind_udp_socket("control").await?;
+        let control_port = control_socket.local_addr().map_or(0, |addr| addr.port());
+        self.control_socket = Some(control_socket);
+        let timing_socket = self.bind_udp_socket("timing").await?;
+        let timing_port = timing_socket.local_addr().map_or(0, |addr| addr.port());
+        self.timing_socket = Some(timing_socket);
+        let req = self.rtsp_session.setup_request(control_port, timing_port);
         let resp = self.send_request(req).await?;
         self.rtsp_session
             .process_response(Method::Setup, &resp)
             .map_err(|e| AirPlayError::RtspError {
                 message: e,
                 status_code: None,
+                source: None,
             })?;
 
         // 4. Send RECORD to start
@@ -272,6 +385,7 @@ impl AirPlaySession for RaopSessionImpl {
             .map_err(|e| AirPlayError::RtspError {
                 message: e,
                 status_code: None,
+                source: None,
             })?;
 
         self.setup_audio_streaming().await?;
@@ -290,6 +404,8 @@ impl AirPlaySession for RaopSessionImpl {
         }
 
         self.stream = None;
+        // Dropping the streamer stops its timing responder
+        self.streamer = None;
         self.connected = false;
         self.state = PlaybackState::default();
         Ok(())
@@ -316,6 +432,7 @@ impl AirPlaySession for RaopSessionImpl {
             .map_err(|e| AirPlayError::RtspError {
                 message: e,
                 status_code: None,
+                source: None,
             })?;
 
         self.state.is_playing = false;
@@ -353,6 +470,9 @@ impl AirPlaySession for RaopSessionImpl {
     }
 
     async fn stream_audio(&mut self, data: &[u8]) -> Result<(), AirPlayError> {
+        self.service_control_channel().await;
+        self.send_sync_if_due().await;
+
         if let (Some(streamer), Some(socket)) = (&mut self.streamer, &self.audio_socket) {
             let packet = streamer.encode_frame(data);
             socket
@@ -548,9 +668,13 @@ impl AirPlaySession for AirPlay2SessionImpl {
         // Detect format or default
         let format = crate::protocol::daap::ArtworkFormat::detect(data)
             .unwrap_or(crate::protocol::daap::ArtworkFormat::Jpeg);
-        let mime_type = format.mime_type();
 
-        self.client.set_artwork(data, mime_type).await
+        self.client
+            .set_artwork(crate::protocol::daap::Artwork {
+                data: data.to_vec(),
+                format,
+            })
+            .await
     }
 
     async fn playback_state(&self) -> PlaybackState {
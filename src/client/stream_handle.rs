@@ -0,0 +1,124 @@
+//! Control of a PCM stream started with `AirPlayClient::stream_audio`
+
+use std::sync::Arc;
+
+use tokio::task::JoinHandle;
+
+use crate::connection::ConnectionManager;
+use crate::error::AirPlayError;
+use crate::streaming::{PcmStreamer, StreamerState};
+
+/// Handle to an audio stream running in the background
+///
+/// Dropping the handle leaves the stream running to the end of its source;
+/// use [`wait`](Self::wait) to wait for that, or [`stop`](Self::stop) to end
+/// it early.
+pub struct StreamHandle {
+    streamer: Arc<PcmStreamer>,
+    connection: Arc<ConnectionManager>,
+    task: JoinHandle<Result<(), AirPlayError>>,
+}
+
+impl StreamHandle {
+    pub(crate) fn new(
+        streamer: Arc<PcmStreamer>,
+        connection: Arc<ConnectionManager>,
+        task: JoinHandle<Result<(), AirPlayError>>,
+    ) -> Self {
+        Self {
+            streamer,
+            connection,
+            task,
+        }
+    }
+
+    /// Current state of the streamer
+    pub async fn state(&self) -> StreamerState {
+        self.streamer.state().await
+    }
+
+    /// Whether the stream has ended
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.task.is_finished()
+    }
+
+    /// Stop sending audio, keeping the position
+    ///
+    /// This holds the sender only; use `AirPlayClient::pause` to also pause
+    /// the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the stream has ended.
+    pub async fn pause(&self) -> Result<(), AirPlayError> {
+        self.ensure_running()?;
+        self.streamer.pause().await
+    }
+
+    /// Resume sending audio after [`pause`](Self::pause)
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the stream has ended.
+    pub async fn resume(&self) -> Result<(), AirPlayError> {
+        self.ensure_running()?;
+        self.streamer.resume().await
+    }
+
+    /// Stop the stream
+    ///
+    /// The streamer stops sending and its task ends, then the device is
+    /// told to drop the audio it has queued so playback stops at once.
+    /// The session stays connected for the next stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the stream failed before it was stopped.
+    pub async fn stop(mut self) -> Result<(), AirPlayError> {
+        if !self.task.is_finished() {
+            let _ = self.streamer.stop().await;
+        }
+        let result = self.join().await;
+
+        let (seq, timestamp) = self.streamer.next_packet().await;
+        let flushed = if self.connection.is_buffered().await {
+            self.connection
+                .flush_buffered_until(seq, timestamp, self.streamer.frames_per_packet().await)
+                .await
+        } else {
+            self.connection.send_flush(seq, timestamp).await
+        };
+        if let Err(e) = flushed {
+            tracing::warn!("Failed to flush device after stopping stream: {e}");
+        }
+        result
+    }
+
+    /// Wait for the stream to reach the end of its source
+    ///
+    /// # Errors
+    ///
+    /// Returns error if streaming fails.
+    pub async fn wait(mut self) -> Result<(), AirPlayError> {
+        self.join().await
+    }
+
+    async fn join(&mut self) -> Result<(), AirPlayError> {
+        (&mut self.task).await.unwrap_or_else(|e| {
+            Err(AirPlayError::InternalError {
+                message: format!("Streaming task failed: {e}"),
+            })
+        })
+    }
+
+    fn ensure_running(&self) -> Result<(), AirPlayError> {
+        if self.task.is_finished() {
+            return Err(AirPlayError::InvalidState {
+                message: "Stream has ended".to_string(),
+                current_state: "Finished".to_string(),
+            });
+        }
+        Ok(())
+    }
+}
@@ -82,6 +82,22 @@ async fn test_volume_set_fails_without_connection() {
     assert!((client.volume().await - 0.75).abs() < f32::EPSILON);
 }
 
+#[tokio::test]
+async fn test_group_volume_requires_connection() {
+    let client = AirPlayClient::default_client();
+    assert!(
+        AirPlayClient::group_volume(&[])
+            .await
+            .unwrap()
+            .device_ids()
+            .is_empty()
+    );
+    assert!(matches!(
+        AirPlayClient::group_volume(&[&client]).await,
+        Err(crate::error::AirPlayError::Disconnected { .. })
+    ));
+}
+
 #[tokio::test]
 async fn test_event_subscription() {
     let client = AirPlayClient::default_client();
@@ -178,6 +194,51 @@ async fn test_play_url_fails_without_connection() {
     ));
 }
 
+#[tokio::test]
+async fn test_stream_audio_on_shared_client() {
+    use std::sync::Arc;
+
+    use crate::audio::AudioFormat;
+    use crate::streaming::SliceSource;
+
+    let client = Arc::new(AirPlayClient::default_client());
+    let source = SliceSource::new(vec![0u8; 1408], AudioFormat::CD_QUALITY);
+    let res = tokio::spawn({
+        let client = client.clone();
+        async move { client.stream_audio(source).await.map(|_| ()) }
+    })
+    .await
+    .unwrap();
+    assert!(matches!(
+        res,
+        Err(crate::error::AirPlayError::Disconnected { .. })
+    ));
+    assert!(client.stream_metrics().await.is_none());
+}
+
+#[tokio::test]
+async fn test_play_queue_requires_tracks() {
+    use std::sync::Arc;
+
+    use crate::streaming::{SilenceSource, SourceOpener};
+
+    let client = AirPlayClient::default_client();
+    let opener: SourceOpener =
+        Arc::new(|_| Ok(Box::new(SilenceSource::new(crate::audio::AudioFormat::CD_QUALITY)) as _));
+    assert!(matches!(
+        client.play_queue(opener.clone()).await,
+        Err(crate::error::AirPlayError::InvalidState { .. })
+    ));
+
+    client
+        .add_to_queue(TrackInfo::new("file:///a.wav", "A", "Artist"))
+        .await;
+    assert!(matches!(
+        client.play_queue(opener).await,
+        Err(crate::error::AirPlayError::Disconnected { .. })
+    ));
+}
+
 #[tokio::test]
 async fn test_volume_controls_fail_without_connection() {
     let client = AirPlayClient::default_client();
@@ -0,0 +1,110 @@
+use crate::client::AirPlayClient;
+use crate::client::diagnostics::redact_info;
+use crate::protocol::plist::{DictBuilder, PlistValue};
+use crate::types::AirPlayConfig;
+
+/// Read back (name, contents) of each file in a ustar archive, checking
+/// header checksums
+fn read_tar(archive: &[u8]) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while archive[offset..offset + 512].iter().any(|&b| b != 0) {
+        let header = &archive[offset..offset + 512];
+        let field = |range: std::ops::Range<usize>| {
+            let text = std::str::from_utf8(&header[range]).unwrap();
+            text.trim_matches(|c| c == '\0' || c == ' ').to_string()
+        };
+
+        let mut blank = header.to_vec();
+        blank[148..156].fill(b' ');
+        let sum: u32 = blank.iter().map(|&b| u32::from(b)).sum();
+        assert_eq!(u32::from_str_radix(&field(148..156), 8).unwrap(), sum);
+        assert_eq!(&header[257..263], b"ustar\0");
+
+        let size = usize::from_str_radix(&field(124..136), 8).unwrap();
+        let data = &archive[offset + 512..offset + 512 + size];
+        files.push((field(0..100), String::from_utf8(data.to_vec()).unwrap()));
+        offset += 512 + size.div_ceil(512) * 512;
+    }
+    assert_eq!(archive.len(), offset + 1024);
+    files
+}
+
+#[tokio::test]
+async fn test_export_diagnostics_bundle() {
+    let config = AirPlayConfig::builder().pin("1234").build();
+    let client = AirPlayClient::new(config);
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("diagnostics.tar");
+
+    client.export_diagnostics(&path).await.unwrap();
+    let files = read_tar(&std::fs::read(&path).unwrap());
+
+    let names: Vec<&str> = files.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "airplay2-diagnostics/version.txt",
+            "airplay2-diagnostics/config.txt",
+            "airplay2-diagnostics/session.txt",
+            "airplay2-diagnostics/device-info.txt",
+            "airplay2-diagnostics/protocol.log",
+        ]
+    );
+    assert!(
+        files[0]
+            .1
+            .starts_with(&format!("airplay2 {}", crate::VERSION))
+    );
+    assert!(files[1].1.contains("<redacted>"));
+    assert!(!files[1].1.contains("1234"));
+    assert!(files[2].1.contains("state: Disconnected"));
+}
+
+#[tokio::test]
+async fn test_export_diagnostics_unwritable_path() {
+    let client = AirPlayClient::new(AirPlayConfig::default());
+    let dir = tempfile::tempdir().unwrap();
+    let result = client
+        .export_diagnostics(dir.path().join("missing").join("bundle.tar"))
+        .await;
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_redact_device_info() {
+    let mut info = DictBuilder::new()
+        .insert("model", "AudioAccessory5,1")
+        .insert("pk", PlistValue::Data(vec![1, 2, 3]))
+        .insert(
+            "audioLatencies",
+            PlistValue::Array(vec![
+                DictBuilder::new()
+                    .insert("deviceID", "AA:BB:CC:DD:EE:FF")
+                    .build(),
+            ]),
+        )
+        .build();
+    redact_info(&mut info);
+
+    let dict = info.as_dict().unwrap();
+    assert_eq!(
+        dict.get("model").and_then(PlistValue::as_str),
+        Some("AudioAccessory5,1")
+    );
+    assert_eq!(
+        dict.get("pk").and_then(PlistValue::as_str),
+        Some("<redacted>")
+    );
+    let PlistValue::Array(items) = &dict["audioLatencies"] else {
+        panic!("array expected");
+    };
+    assert_eq!(
+        items[0]
+            .as_dict()
+            .unwrap()
+            .get("deviceID")
+            .and_then(PlistValue::as_str),
+        Some("<redacted>")
+    );
+}
@@ -1,4 +1,13 @@
 mod client_tests;
+mod diagnostics_tests;
+mod preflight_tests;
 mod protocol_tests;
+#[cfg(feature = "raop")]
+mod raop_auth_test;
+#[cfg(feature = "raop")]
 mod raop_streaming_test;
+#[cfg(feature = "dacp")]
+mod remote_commands_tests;
+mod stream_handle_tests;
+#[cfg(feature = "raop")]
 mod unified_tests;
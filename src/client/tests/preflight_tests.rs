@@ -0,0 +1,143 @@
+use std::collections::HashMap;
+
+use crate::audio::AudioCodec;
+use crate::client::preflight::{
+    CheckStatus, PreflightCheck, PreflightReport, check_authentication, check_codec, check_timing,
+};
+use crate::types::{
+    AirPlayConfig, AirPlayDevice, DeviceCapabilities, RaopCapabilities, TimingProtocol,
+};
+
+fn airplay2_device() -> AirPlayDevice {
+    AirPlayDevice {
+        id: "test".to_string(),
+        name: "Test Device".to_string(),
+        model: None,
+        addresses: vec!["127.0.0.1".parse().unwrap()],
+        port: 7000,
+        capabilities: DeviceCapabilities::from_features(0x0001_C340_405F_8A00),
+        raop_port: None,
+        raop_capabilities: None,
+        txt_records: HashMap::new(),
+        last_seen: None,
+    }
+}
+
+fn raop_device(codecs: &str) -> AirPlayDevice {
+    let txt: HashMap<String, String> = [("cn".to_string(), codecs.to_string())].into();
+    AirPlayDevice {
+        capabilities: DeviceCapabilities::default(),
+        raop_port: Some(5000),
+        port: 5000,
+        raop_capabilities: Some(RaopCapabilities::from_txt_records(&txt)),
+        txt_records: txt,
+        ..airplay2_device()
+    }
+}
+
+#[test]
+fn test_authentication_check() {
+    let config = AirPlayConfig::default();
+    let mut device = airplay2_device();
+    assert_eq!(check_authentication(&config, &device), CheckStatus::Passed);
+
+    device.capabilities.requires_homekit_pairing = true;
+    assert!(check_authentication(&config, &device).is_blocking());
+    let with_pin = AirPlayConfig {
+        pin: Some("1234".to_string()),
+        ..AirPlayConfig::default()
+    };
+    assert!(matches!(
+        check_authentication(&with_pin, &device),
+        CheckStatus::Warning(_)
+    ));
+
+    device.capabilities.requires_password = true;
+    assert!(check_authentication(&with_pin, &device).is_blocking());
+}
+
+#[test]
+fn test_codec_check() {
+    let config = |codec| AirPlayConfig {
+        audio_codec: codec,
+        ..AirPlayConfig::default()
+    };
+
+    assert_eq!(
+        check_codec(&config(AudioCodec::Alac), &airplay2_device()),
+        CheckStatus::Passed
+    );
+    assert!(check_codec(&config(AudioCodec::Opus), &airplay2_device()).is_blocking());
+
+    let pcm_only = raop_device("0");
+    assert_eq!(
+        check_codec(&config(AudioCodec::Pcm), &pcm_only),
+        CheckStatus::Passed
+    );
+    assert!(check_codec(&config(AudioCodec::Alac), &pcm_only).is_blocking());
+
+    let hires = AirPlayConfig {
+        prefer_hires_audio: true,
+        ..AirPlayConfig::default()
+    };
+    assert!(matches!(
+        check_codec(&hires, &pcm_only),
+        CheckStatus::Warning(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_timing_check() {
+    let ptp = AirPlayConfig {
+        timing_protocol: TimingProtocol::Ptp,
+        ..AirPlayConfig::default()
+    };
+    assert!(check_timing(&ptp, &raop_device("0,1")).is_blocking());
+    // Binding the PTP port may not be permitted here, which is only a warning
+    assert!(!check_timing(&ptp, &airplay2_device()).is_blocking());
+
+    let ntp = AirPlayConfig {
+        timing_protocol: TimingProtocol::Ntp,
+        ..AirPlayConfig::default()
+    };
+    assert_eq!(check_timing(&ntp, &raop_device("0,1")), CheckStatus::Passed);
+}
+
+#[test]
+fn test_report_summary() {
+    let report = PreflightReport {
+        device: airplay2_device(),
+        address: None,
+        checks: vec![
+            (
+                PreflightCheck::Reachability,
+                CheckStatus::Failed("down".to_string()),
+            ),
+            (
+                PreflightCheck::Info,
+                CheckStatus::Skipped("unreachable".to_string()),
+            ),
+            (
+                PreflightCheck::Codec,
+                CheckStatus::Warning("lo-res".to_string()),
+            ),
+        ],
+    };
+
+    assert!(!report.can_connect());
+    assert_eq!(
+        report.blockers().collect::<Vec<_>>(),
+        vec![(PreflightCheck::Reachability, "down")]
+    );
+    assert_eq!(
+        report.warnings().collect::<Vec<_>>(),
+        vec![(PreflightCheck::Codec, "lo-res")]
+    );
+    assert_eq!(
+        report
+            .status(PreflightCheck::Info)
+            .and_then(CheckStatus::message),
+        Some("unreachable")
+    );
+    assert!(report.status(PreflightCheck::Timing).is_none());
+}
@@ -0,0 +1,101 @@
+use crate::client::RaopSessionImpl;
+use crate::client::session::AirPlaySession;
+use crate::protocol::crypto::AppleRsaPublicKey;
+use crate::protocol::raop::DigestCredentials;
+use crate::testing::mock_raop_server::{MockRaopConfig, MockRaopServer};
+
+async fn start_server(sign_challenge: bool) -> MockRaopServer {
+    let config = MockRaopConfig {
+        sign_challenge,
+        ..Default::default()
+    };
+    let mut server = MockRaopServer::new(config);
+    server.start().await.expect("failed to start mock server");
+    server
+}
+
+fn session_for(server: &MockRaopServer, mac: [u8; 6]) -> RaopSessionImpl {
+    let mut session =
+        RaopSessionImpl::new("127.0.0.1", server.config.rtsp_port).with_mac_address(mac);
+    session
+        .rtsp_session
+        .set_receiver_key(AppleRsaPublicKey::from(server.public_key()));
+    session
+}
+
+#[tokio::test]
+async fn test_raop_connect_verifies_apple_response() {
+    let server = start_server(true).await;
+    let mut session = session_for(&server, server.config.mac_address);
+
+    session.connect().await.expect("connect failed");
+    assert!(session.rtsp_session.is_authenticated());
+
+    // The receiver could decrypt the RSA-wrapped AES key from the ANNOUNCE
+    let state = server.state();
+    assert!(state.aes_key.is_some());
+    assert!(state.aes_iv.is_some());
+
+    session.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_raop_connect_rejects_bad_apple_response() {
+    let server = start_server(true).await;
+    let mut session = session_for(&server, [0xDE, 0xAD, 0xBE, 0xEF, 0x00, 0x01]);
+
+    assert!(session.connect().await.is_err());
+    assert!(!session.rtsp_session.is_authenticated());
+    assert!(server.state().aes_key.is_none());
+}
+
+#[tokio::test]
+async fn test_raop_connect_without_apple_response() {
+    let server = start_server(false).await;
+    let mut session = session_for(&server, server.config.mac_address);
+
+    session.connect().await.expect("connect failed");
+    assert!(!session.rtsp_session.is_authenticated());
+}
+
+async fn start_password_server() -> MockRaopServer {
+    let config = MockRaopConfig {
+        password: Some("hunter2".to_string()),
+        ..Default::default()
+    };
+    let mut server = MockRaopServer::new(config);
+    server.start().await.expect("failed to start mock server");
+    server
+}
+
+#[tokio::test]
+async fn test_raop_connect_with_password() {
+    let server = start_password_server().await;
+    let mut session = RaopSessionImpl::new("127.0.0.1", server.config.rtsp_port)
+        .with_credentials(DigestCredentials::new("hunter2"));
+    session
+        .rtsp_session
+        .set_receiver_key(AppleRsaPublicKey::from(server.public_key()));
+
+    session.connect().await.expect("connect failed");
+    assert!(server.state().aes_key.is_some());
+
+    session.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_raop_connect_with_wrong_password() {
+    let server = start_password_server().await;
+    let mut session = RaopSessionImpl::new("127.0.0.1", server.config.rtsp_port)
+        .with_credentials(DigestCredentials::new("wrong"));
+
+    assert!(session.connect().await.is_err());
+}
+
+#[tokio::test]
+async fn test_raop_connect_without_password() {
+    let server = start_password_server().await;
+    let mut session = RaopSessionImpl::new("127.0.0.1", server.config.rtsp_port);
+
+    assert!(session.connect().await.is_err());
+}
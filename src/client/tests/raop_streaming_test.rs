@@ -87,3 +87,58 @@ async fn test_raop_audio_streaming() {
 
     client.disconnect().await.expect("Failed to disconnect");
 }
+
+#[tokio::test]
+async fn test_raop_answers_retransmit_requests() {
+    use crate::client::RaopSessionImpl;
+    use crate::client::session::AirPlaySession;
+
+    let (_device, server) = create_device_with_server().await;
+    let mut session = RaopSessionImpl::new("127.0.0.1", server.config.rtsp_port);
+    session.connect().await.expect("Failed to connect");
+    assert!(server.state().client_control_port.is_some_and(|p| p != 0));
+
+    let audio = vec![0xAB; 352 * 4];
+    for _ in 0..3 {
+        session.stream_audio(&audio).await.unwrap();
+    }
+
+    server.request_retransmit(1, 1).await.unwrap();
+    sleep(Duration::from_millis(50)).await;
+    // Requests are answered on the next send
+    session.stream_audio(&audio).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    let state = server.state();
+    let resent = state
+        .control_packets
+        .iter()
+        .find(|p| p.len() > 4 && p[1] == 0xD6)
+        .expect("no retransmitted packet");
+    assert_eq!(u16::from_be_bytes([resent[2], resent[3]]), 1);
+    assert_eq!(&resent[4..], &state.audio_packets[1][..]);
+}
+
+#[tokio::test]
+async fn test_raop_answers_timing_requests() {
+    use crate::client::RaopSessionImpl;
+    use crate::client::session::AirPlaySession;
+
+    let (_device, server) = create_device_with_server().await;
+    let mut session = RaopSessionImpl::new("127.0.0.1", server.config.rtsp_port);
+    session.connect().await.expect("Failed to connect");
+    assert!(server.state().client_timing_port.is_some_and(|p| p != 0));
+
+    server.request_timing(42).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    let state = server.state();
+    let response = state
+        .timing_packets
+        .iter()
+        .find(|p| p.len() == 32 && p[1] == 0xD3)
+        .expect("no timing response");
+    assert_eq!(u16::from_be_bytes([response[2], response[3]]), 42);
+
+    session.disconnect().await.unwrap();
+}
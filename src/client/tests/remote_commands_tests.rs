@@ -0,0 +1,70 @@
+use std::sync::Arc;
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use crate::client::remote_commands::{RemoteCommandForwarder, RemoteCommands};
+use crate::protocol::dacp::{DacpCommand, DacpServer};
+use crate::state::{ClientEvent, EventBus};
+
+#[tokio::test]
+async fn test_device_commands_become_events() {
+    let events = Arc::new(EventBus::new());
+    let mut rx = events.subscribe();
+    let forwarder = RemoteCommandForwarder::new("4294967295".to_string(), events);
+    let mut server = DacpServer::new(forwarder, "4294967295".to_string(), 0);
+    server.start().await.unwrap();
+    let port = server.local_addr().unwrap().port();
+
+    let mut stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    stream
+        .write_all(
+            b"GET /ctrl-int/1/nextitem HTTP/1.1\r\nActive-Remote: 4294967295\r\nConnection: \
+              close\r\n\r\n",
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 204"));
+
+    let event = rx.recv().await.unwrap();
+    assert!(matches!(
+        event,
+        ClientEvent::RemoteCommand {
+            command: DacpCommand::NextItem
+        }
+    ));
+}
+
+#[tokio::test]
+async fn test_commands_without_token_are_dropped() {
+    let events = Arc::new(EventBus::new());
+    let mut rx = events.subscribe();
+    let forwarder = RemoteCommandForwarder::new("4294967295".to_string(), events.clone());
+    let server = DacpServer::new(forwarder, "4294967295".to_string(), 0);
+
+    let response = server.process_request("GET", "/ctrl-int/1/pause", Some("1"));
+
+    assert_eq!(response.status, 403);
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_remote_commands_end_with_stream() {
+    let events = Arc::new(EventBus::new());
+    let remote = RemoteCommands::start("0123456789ABCDEF".to_string(), "1234".to_string(), events)
+        .await
+        .unwrap();
+    let (connection_tx, connection) = tokio::sync::broadcast::channel(4);
+    let (stream_ended, stream_ended_rx) = tokio::sync::oneshot::channel::<()>();
+    let serve = tokio::spawn(remote.serve(stream_ended_rx, connection));
+
+    drop(stream_ended);
+    tokio::time::timeout(std::time::Duration::from_secs(5), serve)
+        .await
+        .unwrap()
+        .unwrap();
+    drop(connection_tx);
+}
@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use crate::audio::AudioFormat;
+use crate::client::StreamHandle;
+use crate::connection::ConnectionManager;
+use crate::error::AirPlayError;
+use crate::streaming::{PcmStreamer, SliceSource};
+use crate::types::AirPlayConfig;
+
+fn handle() -> StreamHandle {
+    let connection = Arc::new(ConnectionManager::new(AirPlayConfig::default()));
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = Arc::new(PcmStreamer::new(connection.clone(), format, 44100));
+
+    // Without a session the first packet fails to send
+    let source = SliceSource::new(vec![0u8; 176_400], format);
+    let task = tokio::spawn({
+        let streamer = streamer.clone();
+        async move { streamer.stream(source).await }
+    });
+    StreamHandle::new(streamer, connection, task)
+}
+
+#[tokio::test]
+async fn test_ended_stream_rejects_pause_and_resume() {
+    let stream = handle();
+    while !stream.is_finished() {
+        tokio::task::yield_now().await;
+    }
+
+    assert!(matches!(
+        stream.pause().await,
+        Err(AirPlayError::InvalidState { .. })
+    ));
+    assert!(matches!(
+        stream.resume().await,
+        Err(AirPlayError::InvalidState { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_stop_returns_stream_error() {
+    let stream = handle();
+    while !stream.is_finished() {
+        tokio::task::yield_now().await;
+    }
+
+    // The flush fails too without a session, but only the stream's result
+    // is reported
+    let result = stream.stop().await;
+    assert!(matches!(result, Err(AirPlayError::InvalidState { .. })));
+}
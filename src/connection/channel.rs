@@ -0,0 +1,376 @@
+//! Background reader for the RTSP control connection
+//!
+//! Once pairing is done the connection is split: requests are written from
+//! the caller's task while a reader task owns the read half. Responses are
+//! routed to the waiting request by `CSeq`, and requests the receiver sends
+//! on its own (SETPEERS updates, FLUSHBUFFERED, event notifications) are
+//! answered through a handler and published as events. Interleaved binary
+//! frames, RTP and RTCP carried on the connection itself, go to their own
+//! handler.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::{Mutex, broadcast, oneshot};
+use tokio::task::JoinHandle;
+
+use super::state::ConnectionEvent;
+use crate::error::AirPlayError;
+use crate::net::secure::HapSecureSession;
+use crate::protocol::rtsp::server_codec::ResponseBuilder;
+use crate::protocol::rtsp::{
+    InterleavedFrame, RtspDemuxer, RtspMessage, RtspRequest, RtspResponse, StatusCode,
+};
+
+/// Handler for requests the receiver sends on the control connection
+///
+/// Returns the status to answer the request with.
+pub type ServerRequestHandler = Arc<dyn Fn(&RtspRequest) -> StatusCode + Send + Sync>;
+
+/// Handler slot shared between the connection manager and the reader
+pub(crate) type SharedHandler = Arc<std::sync::RwLock<Option<ServerRequestHandler>>>;
+
+/// Handler for interleaved frames the receiver sends on the control
+/// connection
+pub type InterleavedHandler = Arc<dyn Fn(InterleavedFrame) + Send + Sync>;
+
+/// Interleaved handler slot shared between the connection manager and the
+/// reader
+pub(crate) type SharedInterleavedHandler = Arc<std::sync::RwLock<Option<InterleavedHandler>>>;
+
+/// Handlers for what the receiver sends unprompted
+#[derive(Clone, Default)]
+pub(crate) struct Handlers {
+    pub(crate) request: SharedHandler,
+    pub(crate) interleaved: SharedInterleavedHandler,
+}
+
+type Writer = Box<dyn AsyncWrite + Send + Unpin>;
+
+/// State shared between request callers and the reader task
+struct Shared {
+    writer: Mutex<Writer>,
+    secure: std::sync::Mutex<Option<HapSecureSession>>,
+    /// Response senders keyed by request `CSeq`
+    pending: std::sync::Mutex<HashMap<u32, oneshot::Sender<RtspResponse>>>,
+    closed: AtomicBool,
+    /// When data last arrived from the receiver
+    last_received: std::sync::Mutex<Instant>,
+}
+
+impl Shared {
+    /// Write a message, encrypting it once the session is secured
+    ///
+    /// The writer lock is held across encryption so frames reach the wire in
+    /// nonce order.
+    async fn send(&self, message: &[u8]) -> Result<(), AirPlayError> {
+        let mut writer = self.writer.lock().await;
+        let data = match lock(&self.secure).as_mut() {
+            Some(secure) => secure.encrypt(message)?,
+            None => message.to_vec(),
+        };
+        writer.write_all(&data).await?;
+        writer.flush().await?;
+        Ok(())
+    }
+
+    /// Decrypt what has arrived and pass the plaintext to the demuxer
+    fn feed(
+        &self,
+        demux: &mut RtspDemuxer,
+        encrypted: &mut Vec<u8>,
+        data: &[u8],
+    ) -> Result<(), AirPlayError> {
+        let mut secure = lock(&self.secure);
+        let Some(secure) = secure.as_mut() else {
+            return demux.feed(data).map_err(codec_error);
+        };
+
+        encrypted.extend_from_slice(data);
+        while encrypted.len() >= 2 {
+            let block_len = usize::from(u16::from_le_bytes([encrypted[0], encrypted[1]]));
+            let total_len = 2 + block_len + 16;
+            if encrypted.len() < total_len {
+                break;
+            }
+            let block: Vec<u8> = encrypted.drain(..total_len).collect();
+            let (decrypted, _) = secure.decrypt_block(&block)?;
+            demux.feed(&decrypted).map_err(codec_error)?;
+        }
+        Ok(())
+    }
+
+    async fn dispatch(
+        &self,
+        message: RtspMessage,
+        handlers: &Handlers,
+        events: Option<&broadcast::Sender<ConnectionEvent>>,
+    ) {
+        match message {
+            RtspMessage::Response(response) => {
+                let waiter = response
+                    .cseq()
+                    .and_then(|cseq| lock(&self.pending).remove(&cseq));
+                if let Some(waiter) = waiter {
+                    let _ = waiter.send(response);
+                } else {
+                    tracing::info!(
+                        "Discarding response with no waiting request (CSeq={:?}): {} {}",
+                        response.cseq(),
+                        response.status.as_u16(),
+                        response.reason
+                    );
+                }
+            }
+            RtspMessage::Request(request) => {
+                tracing::info!(
+                    "<< Receiver request: {} {}",
+                    request.method.as_str(),
+                    request.uri
+                );
+                let status = handlers
+                    .request
+                    .read()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .as_ref()
+                    .map_or(StatusCode::OK, |handle| handle(&request));
+                self.answer(status, request.headers.cseq()).await;
+                if let Some(events) = events {
+                    let _ = events.send(ConnectionEvent::ServerRequest { request });
+                }
+            }
+            RtspMessage::Unsupported { request_line, cseq } => {
+                tracing::warn!("Unsupported receiver request: {request_line}");
+                self.answer(StatusCode::NOT_IMPLEMENTED, cseq).await;
+            }
+            RtspMessage::Interleaved(frame) => {
+                let handler = handlers
+                    .interleaved
+                    .read()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .clone();
+                if let Some(handle) = handler {
+                    handle(frame);
+                } else {
+                    tracing::trace!(
+                        "Dropping interleaved frame on channel {} ({} bytes)",
+                        frame.channel,
+                        frame.payload.len()
+                    );
+                }
+            }
+        }
+    }
+
+    async fn answer(&self, status: StatusCode, cseq: Option<u32>) {
+        let mut response = ResponseBuilder::new(status);
+        if let Some(cseq) = cseq {
+            response = response.cseq(cseq);
+        }
+        if let Err(e) = self.send(&response.encode()).await {
+            tracing::warn!("Failed to answer receiver request: {e}");
+        }
+    }
+
+    /// Fail every waiting request; later requests fail immediately
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        lock(&self.pending).clear();
+    }
+}
+
+/// Time to wait for a response unless the owner sets another
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A control connection served by a background reader
+pub(crate) struct RtspChannel {
+    shared: Arc<Shared>,
+    task: JoinHandle<()>,
+    local_addr: Option<SocketAddr>,
+    request_timeout: Duration,
+}
+
+impl RtspChannel {
+    /// Take over a connection, starting the reader task
+    ///
+    /// Requests from the receiver are published on `events` if given.
+    pub(crate) fn spawn<R, W>(
+        reader: R,
+        writer: W,
+        secure: Option<HapSecureSession>,
+        local_addr: Option<SocketAddr>,
+        handlers: Handlers,
+        events: Option<broadcast::Sender<ConnectionEvent>>,
+    ) -> Self
+    where
+        R: AsyncRead + Send + Unpin + 'static,
+        W: AsyncWrite + Send + Unpin + 'static,
+    {
+        let shared = Arc::new(Shared {
+            writer: Mutex::new(Box::new(writer)),
+            secure: std::sync::Mutex::new(secure),
+            pending: std::sync::Mutex::new(HashMap::new()),
+            closed: AtomicBool::new(false),
+            last_received: std::sync::Mutex::new(Instant::now()),
+        });
+        let task = tokio::spawn(read_loop(reader, shared.clone(), handlers, events));
+
+        Self {
+            shared,
+            task,
+            local_addr,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+        }
+    }
+
+    /// Set how long `request` waits for the response
+    #[must_use]
+    pub(crate) fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Local address of the connection
+    pub(crate) fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
+    }
+
+    /// Time since data last arrived, or since the channel opened
+    pub(crate) fn idle_for(&self) -> Duration {
+        lock(&self.shared.last_received).elapsed()
+    }
+
+    /// Whether the connection has closed
+    pub(crate) fn is_closed(&self) -> bool {
+        self.shared.closed.load(Ordering::SeqCst)
+    }
+
+    /// Send a request and wait for the response with the same `CSeq`
+    ///
+    /// Fails with `AirPlayError::Timeout` if no response arrives within the
+    /// request timeout.
+    pub(crate) async fn request(
+        &self,
+        request: &RtspRequest,
+    ) -> Result<RtspResponse, AirPlayError> {
+        let cseq = request
+            .headers
+            .cseq()
+            .ok_or_else(|| AirPlayError::InternalError {
+                message: "RTSP request without CSeq".to_string(),
+            })?;
+
+        let (tx, rx) = oneshot::channel();
+        lock(&self.shared.pending).insert(cseq, tx);
+        if self.shared.closed.load(Ordering::SeqCst) {
+            lock(&self.shared.pending).remove(&cseq);
+            return Err(disconnected());
+        }
+
+        if let Err(e) = self.shared.send(&request.encode()).await {
+            lock(&self.shared.pending).remove(&cseq);
+            return Err(e);
+        }
+
+        let Ok(response) = tokio::time::timeout(self.request_timeout, rx).await else {
+            lock(&self.shared.pending).remove(&cseq);
+            return Err(AirPlayError::Timeout);
+        };
+        response.map_err(|_| disconnected())
+    }
+
+    /// Send an interleaved frame
+    pub(crate) async fn send_interleaved(
+        &self,
+        frame: &InterleavedFrame,
+    ) -> Result<(), AirPlayError> {
+        if self.shared.closed.load(Ordering::SeqCst) {
+            return Err(disconnected());
+        }
+        self.shared
+            .send(&frame.encode().map_err(codec_error)?)
+            .await
+    }
+
+    /// Close the connection, failing any waiting requests
+    pub(crate) async fn shutdown(&self) {
+        self.task.abort();
+        self.shared.close();
+        let _ = self.shared.writer.lock().await.shutdown().await;
+    }
+}
+
+impl Drop for RtspChannel {
+    fn drop(&mut self) {
+        self.task.abort();
+        self.shared.close();
+    }
+}
+
+async fn read_loop<R: AsyncRead + Unpin>(
+    mut reader: R,
+    shared: Arc<Shared>,
+    handlers: Handlers,
+    events: Option<broadcast::Sender<ConnectionEvent>>,
+) {
+    let mut demux = RtspDemuxer::new();
+    let mut encrypted = Vec::new();
+    let mut buf = vec![0u8; 4096];
+
+    'read: loop {
+        let n = match reader.read(&mut buf).await {
+            Ok(0) => {
+                tracing::debug!("RTSP connection closed by receiver");
+                break;
+            }
+            Ok(n) => n,
+            Err(e) => {
+                tracing::warn!("RTSP read failed: {e}");
+                break;
+            }
+        };
+        *lock(&shared.last_received) = Instant::now();
+
+        if let Err(e) = shared.feed(&mut demux, &mut encrypted, &buf[..n]) {
+            tracing::warn!("RTSP receive failed: {e}");
+            break;
+        }
+
+        loop {
+            match demux.decode() {
+                Ok(Some(message)) => shared.dispatch(message, &handlers, events.as_ref()).await,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::warn!("Malformed RTSP message: {e}");
+                    break 'read;
+                }
+            }
+        }
+    }
+
+    shared.close();
+}
+
+fn codec_error(e: crate::protocol::rtsp::RtspCodecError) -> AirPlayError {
+    AirPlayError::RtspError {
+        message: e.to_string(),
+        status_code: None,
+        source: Some(Box::new(e)),
+    }
+}
+
+fn disconnected() -> AirPlayError {
+    AirPlayError::Disconnected {
+        device_name: "unknown".to_string(),
+    }
+}
+
+fn lock<T>(mutex: &std::sync::Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
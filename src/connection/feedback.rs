@@ -0,0 +1,75 @@
+//! `POST /feedback` keep-alive
+//!
+//! While connected, senders post an empty request to `/feedback` every two
+//! seconds. Receivers time the session out without it, and answer with a
+//! plist describing their active streams, which carries the buffer, latency
+//! and packet loss figures reported as telemetry and used to adapt the
+//! stream.
+
+use std::time::Duration;
+
+use crate::protocol::plist::PlistValue;
+
+/// Endpoint the keep-alive is posted to
+pub const FEEDBACK_PATH: &str = "/feedback";
+
+/// Interval between keep-alive requests
+pub const FEEDBACK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// State of one stream as reported in a `/feedback` response
+///
+/// Receivers differ in which fields they include; missing ones are `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamFeedback {
+    /// Stream type (96 = realtime, 103 = buffered)
+    pub stream_type: Option<u64>,
+    /// Sample rate the receiver is playing at (Hz)
+    pub sample_rate: Option<f64>,
+    /// Output latency of the receiver, in frames
+    pub latency_frames: Option<u64>,
+    /// Audio the receiver holds ahead of playback, in frames
+    pub buffered_frames: Option<u64>,
+    /// Packets the receiver lost since the stream started
+    pub lost_packets: Option<u64>,
+}
+
+impl StreamFeedback {
+    fn from_plist(value: &PlistValue) -> Option<Self> {
+        let dict = value.as_dict()?;
+        let integer = |key: &str| dict.get(key).and_then(PlistValue::as_u64);
+
+        Some(Self {
+            stream_type: integer("type"),
+            sample_rate: dict.get("sr").and_then(PlistValue::as_f64),
+            latency_frames: integer("audioLatency"),
+            buffered_frames: integer("bufferedAudioFrames"),
+            lost_packets: integer("lostPackets"),
+        })
+    }
+}
+
+/// Parse the stream list of a `/feedback` response body
+///
+/// An empty or undecodable body yields no streams.
+#[must_use]
+pub fn parse_feedback(body: &[u8]) -> Vec<StreamFeedback> {
+    if body.is_empty() {
+        return Vec::new();
+    }
+    let Ok(plist) = crate::protocol::plist::decode(body) else {
+        tracing::debug!("Undecodable /feedback response ({} bytes)", body.len());
+        return Vec::new();
+    };
+
+    plist
+        .as_dict()
+        .and_then(|dict| dict.get("streams"))
+        .and_then(PlistValue::as_array)
+        .map(|streams| {
+            streams
+                .iter()
+                .filter_map(StreamFeedback::from_plist)
+                .collect()
+        })
+        .unwrap_or_default()
+}
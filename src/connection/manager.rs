@@ -7,28 +7,52 @@ use std::sync::Arc;
 use tokio::net::UdpSocket;
 use tokio::sync::{Mutex, RwLock, broadcast};
 
+use super::StreamFeedback;
+use super::channel::{Handlers, InterleavedHandler, RtspChannel, ServerRequestHandler};
+use super::protocol_log::ProtocolLog;
 use super::state::{ConnectionEvent, ConnectionState, ConnectionStats, DisconnectReason};
-use crate::audio::AudioCodec;
+use crate::audio::{AudioCodec, AudioFormat};
+use crate::discovery::parser::feature_bits;
 use crate::error::AirPlayError;
-use crate::net::{AsyncReadExt, AsyncWriteExt, Runtime, TcpStream};
+use crate::net::{AsyncReadExt, AsyncWriteExt, Runtime, TaskSet, TcpStream};
+use crate::protocol::crypto::SecretBytes;
+use crate::protocol::fairplay::{FP_SETUP_PATH, FairPlayError, FairPlaySetup};
 use crate::protocol::pairing::storage::StorageError;
 use crate::protocol::pairing::{
-    AuthSetup, PairSetup, PairVerify, PairingKeys, PairingStepResult, PairingStorage, SessionKeys,
+    AuthSetup, HkpMode, MfiCertificate, PairSetup, PairVerify, PairingEntry, PairingKeys,
+    PairingStepResult, PairingStorage, PairingsRequest, SessionKeys, pairings,
 };
+use crate::protocol::plist::{DictBuilder, PlistValue};
 use crate::protocol::ptp::{PtpHandlerConfig, PtpRole, SharedPtpClock, create_shared_clock};
-use crate::protocol::rtsp::{Method, RtspCodec, RtspRequest, RtspResponse, RtspSession};
-use crate::types::{AirPlayConfig, AirPlayDevice, TimingProtocol};
+use crate::protocol::rtsp::transport::{LowerTransport, TransportHeader};
+use crate::protocol::rtsp::{
+    InterleavedFrame, Method, RtspCodec, RtspRequest, RtspResponse, RtspSession, SessionStream,
+    StatusCode,
+};
+use crate::streaming::Timeline;
+use crate::types::{
+    AirPlayConfig, AirPlayDevice, CodecSelection, DeviceModel, ReceiverProfile, TimingProtocol,
+};
 
 /// Connection manager handles device connections
 pub struct ConnectionManager {
     /// Configuration
     config: AirPlayConfig,
+    /// Codec settings chosen for the connected device
+    codec: std::sync::RwLock<Option<CodecSelection>>,
+    /// Per-device quirks resolved at connect time
+    quirks: std::sync::RwLock<DeviceQuirks>,
     /// Current state
     state: RwLock<ConnectionState>,
     /// Connected device info
     device: RwLock<Option<AirPlayDevice>>,
-    /// TCP connection
+    /// TCP connection, until it is handed to the RTSP channel
     stream: Mutex<Option<TcpStream>>,
+    /// Control connection served by a background reader (after pairing)
+    rtsp_channel: Mutex<Option<Arc<RtspChannel>>>,
+    /// Handlers for requests and interleaved frames the receiver sends on
+    /// the control connection
+    handlers: Handlers,
     /// UDP sockets (audio, control, timing)
     sockets: Mutex<Option<UdpSockets>>,
     /// RTSP session
@@ -51,6 +75,8 @@ pub struct ConnectionManager {
     ptp_clock: Mutex<Option<SharedPtpClock>>,
     /// Shutdown signal sender for PTP handler task
     ptp_shutdown_tx: Mutex<Option<tokio::sync::watch::Sender<bool>>>,
+    /// PTP handler and control socket listener of the current session
+    tasks: TaskSet,
     /// Whether PTP timing is active for the current session
     ptp_active: RwLock<bool>,
     /// Device's PTP clock ID (from SETUP Step 1 timingPeerInfo.ClockID)
@@ -65,10 +91,37 @@ pub struct ConnectionManager {
     /// Internal drop packets list for testing Retransmissions
     #[doc(hidden)]
     pub drop_packets_for_test: Mutex<Vec<u16>>,
-    /// Event channel drain task (keeps `HomePod` event TCP connection alive)
-    event_task: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    /// Event channel the device posts `MediaRemote` events on
+    event_channel: Mutex<Option<RtspChannel>>,
+    /// Whether the event channel has been reported silent
+    event_channel_degraded: std::sync::atomic::AtomicBool,
     /// TCP stream for buffered audio (`AirPlay` 2 type=103)
     audio_tcp_stream: Mutex<Option<TcpStream>>,
+    /// Leaf `MFi` certificate presented during Auth-Setup
+    mfi_certificate: RwLock<Option<MfiCertificate>>,
+    /// Media timeline of the current stream
+    timeline: RwLock<Option<Timeline>>,
+    /// Stream dictionary of the main audio SETUP, to set it up again
+    stream_setup: Mutex<Option<PlistValue>>,
+    /// Whether the audio stream is being set up again after a state error
+    resyncing: std::sync::atomic::AtomicBool,
+    /// Latest RTSP exchanges, for diagnostics
+    protocol_log: ProtocolLog,
+    /// Decoded `GET /info` response of the connected device
+    device_info: RwLock<Option<PlistValue>>,
+    /// Extra delay applied to every anchor, e.g. a group member's latency trim
+    playback_delay: std::sync::RwLock<std::time::Duration>,
+}
+
+/// Per-device protocol quirks
+#[derive(Debug, Clone, Copy)]
+struct DeviceQuirks {
+    /// Hardware family
+    model: DeviceModel,
+    /// `X-Apple-HKP` mode for PIN and stored-key pairing
+    hkp_mode: HkpMode,
+    /// Deviations of third-party receivers
+    profile: ReceiverProfile,
 }
 
 /// UDP sockets for streaming
@@ -92,9 +145,17 @@ impl ConnectionManager {
 
         Self {
             config,
+            codec: std::sync::RwLock::new(None),
+            quirks: std::sync::RwLock::new(DeviceQuirks {
+                model: DeviceModel::Other,
+                hkp_mode: HkpMode::Transient,
+                profile: ReceiverProfile::STANDARD,
+            }),
             state: RwLock::new(ConnectionState::Disconnected),
             device: RwLock::new(None),
             stream: Mutex::new(None),
+            rtsp_channel: Mutex::new(None),
+            handlers: Handlers::default(),
             sockets: Mutex::new(None),
             rtsp_session: Mutex::new(None),
             rtsp_codec: Mutex::new(RtspCodec::new()),
@@ -106,14 +167,23 @@ impl ConnectionManager {
             pairing_storage: Mutex::new(None),
             ptp_clock: Mutex::new(None),
             ptp_shutdown_tx: Mutex::new(None),
+            tasks: TaskSet::new("connection"),
             ptp_active: RwLock::new(false),
             device_clock_id: Mutex::new(None),
             ntp_offset: std::sync::atomic::AtomicI64::new(0),
             pending_record_response: Mutex::new(false),
             time_announce_count: std::sync::atomic::AtomicU64::new(0),
             drop_packets_for_test: Mutex::new(Vec::new()),
-            event_task: Mutex::new(None),
+            event_channel: Mutex::new(None),
+            event_channel_degraded: std::sync::atomic::AtomicBool::new(false),
             audio_tcp_stream: Mutex::new(None),
+            mfi_certificate: RwLock::new(None),
+            timeline: RwLock::new(None),
+            stream_setup: Mutex::new(None),
+            resyncing: std::sync::atomic::AtomicBool::new(false),
+            protocol_log: ProtocolLog::default(),
+            device_info: RwLock::new(None),
+            playback_delay: std::sync::RwLock::new(std::time::Duration::ZERO),
         }
     }
 
@@ -140,25 +210,151 @@ impl ConnectionManager {
         self.device.read().await.clone()
     }
 
+    /// Active-Remote token sent to the device, which it returns with DACP
+    /// commands
+    pub async fn active_remote(&self) -> Option<String> {
+        self.rtsp_session
+            .lock()
+            .await
+            .as_ref()
+            .map(|session| session.active_remote().to_string())
+    }
+
+    /// DACP-ID sent to the device, naming the `_dacp._tcp` service it
+    /// sends commands to
+    pub async fn dacp_id(&self) -> Option<String> {
+        self.rtsp_session
+            .lock()
+            .await
+            .as_ref()
+            .map(|session| session.dacp_id().to_string())
+    }
+
+    /// Error for a request that found no connection, naming the device
+    async fn disconnected(&self) -> AirPlayError {
+        AirPlayError::Disconnected {
+            device_name: self
+                .device
+                .read()
+                .await
+                .as_ref()
+                .map_or_else(|| "none".to_string(), |device| device.name.clone()),
+        }
+    }
+
     /// Get connection statistics
     pub async fn stats(&self) -> ConnectionStats {
         self.stats.read().await.clone()
     }
 
     /// Get the session encryption key for audio (raw shared secret)
-    pub async fn encryption_key(&self) -> Option<[u8; 32]> {
+    pub async fn encryption_key(&self) -> Option<SecretBytes> {
         self.session_keys
             .lock()
             .await
             .as_ref()
-            .map(|k| k.raw_shared_secret)
+            .map(|k| k.raw_shared_secret.clone())
+    }
+
+    /// Leaf `MFi` certificate the device presented during Auth-Setup
+    ///
+    /// Decoded for diagnostics even when [`AirPlayConfig::verify_mfi`] is off,
+    /// in which case it has not been verified.
+    pub async fn mfi_certificate(&self) -> Option<MfiCertificate> {
+        self.mfi_certificate.read().await.clone()
+    }
+
+    /// Latest RTSP exchanges with redacted credentials, oldest first
+    #[must_use]
+    pub fn protocol_log(&self) -> Vec<String> {
+        self.protocol_log.entries()
+    }
+
+    /// Decoded `GET /info` response of the device, if it answered
+    pub async fn device_info(&self) -> Option<PlistValue> {
+        self.device_info.read().await.clone()
+    }
+
+    /// Extra delay the device renders the stream with
+    #[must_use]
+    pub fn playback_delay(&self) -> std::time::Duration {
+        *self
+            .playback_delay
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Render the stream `delay` later than anchored
+    ///
+    /// Applied to every `SETRATEANCHORTIME` and `TimeAnnounce` from now on,
+    /// e.g. to line a fast group member up with a slower one. The timeline
+    /// keeps the undelayed anchor.
+    pub fn set_playback_delay(&self, delay: std::time::Duration) {
+        *self
+            .playback_delay
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = delay;
+    }
+
+    /// Media timeline of the current stream
+    ///
+    /// `None` until a stream starts.
+    pub async fn timeline(&self) -> Option<Timeline> {
+        *self.timeline.read().await
+    }
+
+    /// Start a fresh timeline for a new stream
+    pub(crate) async fn start_timeline(&self, sample_rate: u32) {
+        *self.timeline.write().await = Some(Timeline::new(sample_rate));
+    }
+
+    /// Apply a change to the current timeline, if a stream is running
+    pub(crate) async fn update_timeline(&self, update: impl FnOnce(&mut Timeline)) {
+        if let Some(timeline) = self.timeline.write().await.as_mut() {
+            update(timeline);
+        }
+    }
+
+    /// Codec used for the current session
+    ///
+    /// The configured codec unless it was adjusted to the device on connect.
+    #[must_use]
+    pub fn audio_codec(&self) -> AudioCodec {
+        self.codec_selection()
+            .map_or(self.config.audio_codec, |selection| selection.codec)
+    }
+
+    /// AAC bitrate (bps) used for the current session
+    #[must_use]
+    pub fn aac_bitrate(&self) -> u32 {
+        self.codec_selection()
+            .map_or(self.config.aac_bitrate, |selection| selection.aac_bitrate)
+    }
+
+    /// Format the stream is sent in
+    ///
+    /// Negotiated with the device on connect from
+    /// [`AirPlayConfig::audio_format`]; 44.1 kHz 16-bit stereo before then.
+    #[must_use]
+    pub fn stream_format(&self) -> AudioFormat {
+        self.codec_selection()
+            .map_or(AudioFormat::CD_QUALITY, |selection| selection.format)
+    }
+
+    /// Codec settings chosen on the last connect
+    pub(crate) fn codec_selection(&self) -> Option<CodecSelection> {
+        self.codec
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone()
     }
 
     /// Connect to a device
     ///
     /// # Errors
     ///
-    /// Returns error if connection or pairing fails
+    /// Returns error if connection or pairing fails, or if the device does
+    /// not accept the configured codec and `codec_fallback` is disabled
     pub async fn connect(&self, device: &AirPlayDevice) -> Result<(), AirPlayError> {
         // Check if already connected
         let current_state = *self.state.read().await;
@@ -169,6 +365,15 @@ impl ConnectionManager {
             });
         }
 
+        let codec = self.config.select_codec(device)?;
+        for warning in &codec.warnings {
+            tracing::warn!("{warning}");
+        }
+        *self
+            .codec
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(codec);
+
         self.set_state(ConnectionState::Connecting).await;
         *self.device.write().await = Some(device.clone());
 
@@ -219,9 +424,14 @@ impl ConnectionManager {
                     source: Some(Box::new(e)),
                 })?;
 
+        *self.rtsp_channel.lock().await = None;
+        *self.event_channel.lock().await = None;
+        self.event_channel_degraded
+            .store(false, std::sync::atomic::Ordering::Relaxed);
         *self.stream.lock().await = Some(stream);
         *self.secure_session.lock().await = None;
         *self.session_keys.lock().await = None;
+        *self.mfi_certificate.write().await = None;
 
         // 2. Initialize RTSP session
         let rtsp_session = RtspSession::new(&device.address().to_string(), device.port);
@@ -234,10 +444,13 @@ impl ConnectionManager {
         // 3.5. Try GET /info to check connectivity/auth state
         tracing::debug!("Sending GET /info...");
         let mut manufacturer = String::new();
+        let mut described = device.clone();
+        *self.device_info.write().await = None;
         match self.send_get_command("/info").await {
             Ok(body) => {
                 if let Ok(plist) = crate::protocol::plist::decode(&body) {
                     tracing::debug!("GET /info success. Parsed plist: {:#?}", plist);
+                    described.update_from_info(&plist);
                     if let Some(m) = plist
                         .as_dict()
                         .and_then(|d| d.get("manufacturer"))
@@ -245,6 +458,7 @@ impl ConnectionManager {
                     {
                         manufacturer = m.to_string();
                     }
+                    *self.device_info.write().await = Some(plist);
                 } else {
                     tracing::debug!("GET /info success (binary): {} bytes", body.len());
                 }
@@ -252,13 +466,36 @@ impl ConnectionManager {
             Err(e) => tracing::warn!("GET /info failed: {}", e),
         }
 
+        let quirks = DeviceQuirks {
+            model: described.device_model(),
+            hkp_mode: self
+                .config
+                .hkp_mode
+                .unwrap_or_else(|| HkpMode::resolve(&described)),
+            profile: described.receiver_profile(),
+        };
+        tracing::debug!(
+            "Using X-Apple-HKP {} for {:?} ({:?} profile)",
+            quirks.hkp_mode.header_value(),
+            quirks.model,
+            quirks.profile.vendor
+        );
+        *self
+            .quirks
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = quirks;
+
         // 4. Authenticate if required
         self.set_state(ConnectionState::Authenticating).await;
 
         // 4.1 Perform Auth-Setup (MFi handshake)
         // Some devices (like Sonos) fail 403 on pair-setup if this is not done first.
         // We skip it for OpenAirplay (python) as it expects FairPlay plist.
-        if manufacturer == "OpenAirplay" {
+        // With MFi verification on, or for third-party receivers that
+        // insist on it, the device must pass it.
+        if self.config.verify_mfi || quirks.profile.auth_setup_required {
+            self.auth_setup().await?;
+        } else if manufacturer == "OpenAirplay" {
             tracing::info!("Skipping Auth-Setup for OpenAirplay device");
         } else {
             match self.auth_setup().await {
@@ -272,16 +509,58 @@ impl ConnectionManager {
             }
         }
 
+        // 4.2 FairPlay handshake, for devices that require it before SETUP
+        if device.capabilities.raw_features & feature_bits::FPS_AP_V2_5 != 0 {
+            self.fairplay_setup().await?;
+        }
+
         self.authenticate(device).await?;
 
+        // From here on the receiver may send requests of its own
+        self.start_rtsp_channel().await;
+
         // 5. Setup RTSP session
         self.set_state(ConnectionState::SettingUp).await;
 
-        self.setup_session().await?;
+        if self.config.remote_control_only {
+            self.setup_remote_control_session(device).await?;
+        } else {
+            self.setup_session().await?;
+        }
 
         Ok(())
     }
 
+    /// Run a pairings administration request on the device
+    ///
+    /// Requires a connection verified with a stored (non-transient) admin
+    /// pairing. Returns the listed pairings for
+    /// [`PairingsRequest::List`], and an empty list otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if not connected, the request fails, or the device
+    /// rejects it.
+    pub async fn manage_pairings(
+        &self,
+        request: &PairingsRequest,
+    ) -> Result<Vec<PairingEntry>, AirPlayError> {
+        let reply = self
+            .send_post_command(
+                request.path(),
+                Some(request.encode()),
+                Some(pairings::CONTENT_TYPE.to_string()),
+            )
+            .await?;
+        request
+            .parse_response(&reply)
+            .map_err(|e| AirPlayError::AuthenticationFailed {
+                message: format!("{} failed: {e}", request.path()),
+                recoverable: false,
+                source: Some(Box::new(e)),
+            })
+    }
+
     /// Remove pairing for a device
     ///
     /// # Errors
@@ -337,6 +616,7 @@ impl ConnectionManager {
                 .map_err(|e| AirPlayError::RtspError {
                     message: e,
                     status_code: Some(response.status.as_u16()),
+                    source: None,
                 })?;
         }
 
@@ -365,16 +645,72 @@ impl ConnectionManager {
 
         tracing::debug!("Received Auth-Setup response: {} bytes", response.len());
 
-        auth.process_response(&response)
-            .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: format!("Auth-Setup response invalid: {e}"),
-                recoverable: false,
-            })?;
+        let response =
+            auth.process_response(&response)
+                .map_err(|e| AirPlayError::AuthenticationFailed {
+                    message: format!("Auth-Setup response invalid: {e}"),
+                    recoverable: false,
+                    source: Some(Box::new(e)),
+                })?;
+
+        let certificate = if self.config.verify_mfi {
+            let leaf = auth
+                .verify(&response)
+                .map_err(|e| AirPlayError::AuthenticationFailed {
+                    message: format!("MFi verification failed: {e}"),
+                    recoverable: false,
+                    source: Some(Box::new(e)),
+                })?;
+            tracing::info!("MFi certificate verified: {}", leaf.subject);
+            Some(leaf)
+        } else {
+            match response.certificates() {
+                Ok(certificates) => certificates.into_iter().next(),
+                Err(e) => {
+                    tracing::debug!("Could not decode MFi certificate: {e}");
+                    None
+                }
+            }
+        };
+        *self.mfi_certificate.write().await = certificate;
 
         tracing::info!("Auth-Setup completed successfully.");
         Ok(())
     }
 
+    /// Run the `FairPlay` `/fp-setup` handshake
+    ///
+    /// Skipped with a warning when no key source is configured.
+    async fn fairplay_setup(&self) -> Result<(), AirPlayError> {
+        let Some(source) = self.config.fairplay_key_source.clone() else {
+            tracing::warn!("Device advertises FairPlay auth but no key source is configured");
+            return Ok(());
+        };
+        let auth_error = |e: FairPlayError| AirPlayError::AuthenticationFailed {
+            message: format!("FairPlay setup failed: {e}"),
+            recoverable: false,
+            source: Some(Box::new(e)),
+        };
+
+        let mut setup = FairPlaySetup::new(source.as_ref());
+        let content_type = Some("application/octet-stream".to_string());
+
+        tracing::debug!("Sending fp-setup phase 1...");
+        let reply = self
+            .send_post_command(FP_SETUP_PATH, Some(setup.start()), content_type.clone())
+            .await?;
+        let phase2 = setup.handle_phase1(&reply).map_err(auth_error)?;
+
+        tracing::debug!("Sending fp-setup phase 2...");
+        let reply = self
+            .send_post_command(FP_SETUP_PATH, Some(phase2), content_type)
+            .await?;
+        setup.handle_phase2(&reply).map_err(auth_error)?;
+
+        tracing::info!("FairPlay setup completed");
+        Ok(())
+    }
+
     /// Authenticate with the device
     async fn authenticate(&self, device: &AirPlayDevice) -> Result<(), AirPlayError> {
         // 1. Check if we have stored keys (prioritize existing pairing)
@@ -403,8 +739,8 @@ impl ConnectionManager {
                 tracing::info!("Transient Pairing successful");
                 *self.secure_session.lock().await =
                     Some(crate::net::secure::HapSecureSession::new(
-                        &session_keys.encrypt_key,
-                        &session_keys.decrypt_key,
+                        session_keys.encrypt_key.expose(),
+                        session_keys.decrypt_key.expose(),
                     ));
                 *self.session_keys.lock().await = Some(session_keys);
                 Ok(())
@@ -442,7 +778,7 @@ impl ConnectionManager {
         device: &AirPlayDevice,
         pin: &str,
     ) -> Result<(), AirPlayError> {
-        tracing::info!("Attempting SRP Pairing with configured PIN: '{}'...", pin);
+        tracing::info!("Attempting SRP Pairing with configured PIN...");
         let usernames = ["Pair-Setup", "AirPlay", "admin"];
 
         for user in usernames {
@@ -455,6 +791,7 @@ impl ConnectionManager {
         Err(AirPlayError::AuthenticationFailed {
             message: "Authentication failed with configured PIN".to_string(),
             recoverable: false,
+            source: None,
         })
     }
 
@@ -471,7 +808,7 @@ impl ConnectionManager {
         ];
 
         for (user, pin) in credentials {
-            tracing::info!("Attempting SRP Pairing: User='{}', PIN='{}'...", user, pin);
+            tracing::info!("Attempting SRP Pairing: User='{}'...", user);
             match self.pair_setup(user, pin).await {
                 Ok((session_keys, pairing_keys)) => {
                     self.handle_pairing_success(device, session_keys, pairing_keys)
@@ -488,6 +825,7 @@ impl ConnectionManager {
         Err(AirPlayError::AuthenticationFailed {
             message: "All pairing methods failed".to_string(),
             recoverable: false,
+            source: None,
         })
     }
 
@@ -499,8 +837,8 @@ impl ConnectionManager {
     ) {
         tracing::info!("SRP Pairing successful");
         *self.secure_session.lock().await = Some(crate::net::secure::HapSecureSession::new(
-            &session_keys.encrypt_key,
-            &session_keys.decrypt_key,
+            session_keys.encrypt_key.expose(),
+            session_keys.decrypt_key.expose(),
         ));
         *self.session_keys.lock().await = Some(session_keys);
 
@@ -520,6 +858,7 @@ impl ConnectionManager {
         let mut pairing = PairSetup::new();
         pairing.set_username(username);
         pairing.set_pin(pin);
+        pairing.set_srp_group(self.config.srp_group);
 
         // If PIN is "3939", assume transient mode (for AirPort Express 2)
         // Note: For persistent pairing test, we disable this override.
@@ -532,37 +871,45 @@ impl ConnectionManager {
         let m1 = pairing
             .start()
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Setup M1 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         tracing::debug!("Starting Pair-Setup (SRP)...");
-        let m2 = self.send_pairing_data(&m1, "/pair-setup").await?;
+        let m2 = self
+            .send_pairing_data(&m1, "/pair-setup", self.quirks().hkp_mode)
+            .await?;
 
         // M2 -> M3
         let result = pairing
             .process_m2(&m2)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Setup M2 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         let PairingStepResult::SendData(m3) = result else {
             return Err(AirPlayError::AuthenticationFailed {
                 message: "Unexpected pairing state after M2".to_string(),
                 recoverable: false,
+                source: None,
             });
         };
 
         tracing::debug!("Sending M3...");
-        let m4 = self.send_pairing_data(&m3, "/pair-setup").await?;
+        let m4 = self
+            .send_pairing_data(&m3, "/pair-setup", self.quirks().hkp_mode)
+            .await?;
 
         // M4 -> M5 (or Complete if transient)
         let result = pairing
             .process_m4(&m4)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Setup M4 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         if let PairingStepResult::Complete(keys) = result {
@@ -574,18 +921,22 @@ impl ConnectionManager {
             return Err(AirPlayError::AuthenticationFailed {
                 message: "Unexpected pairing state after M4".to_string(),
                 recoverable: false,
+                source: None,
             });
         };
 
         tracing::debug!("Sending M5...");
-        let m6 = self.send_pairing_data(&m5, "/pair-setup").await?;
+        let m6 = self
+            .send_pairing_data(&m5, "/pair-setup", self.quirks().hkp_mode)
+            .await?;
 
         // M6 -> Complete
         let result = pairing
             .process_m6(&m6)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Setup M6 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         match result {
@@ -613,6 +964,7 @@ impl ConnectionManager {
             _ => Err(AirPlayError::AuthenticationFailed {
                 message: "Pairing did not complete".to_string(),
                 recoverable: false,
+                source: None,
             }),
         }
     }
@@ -623,44 +975,53 @@ impl ConnectionManager {
         pairing.set_transient(true);
         pairing.set_pin("3939");
         pairing.set_username("Pair-Setup");
+        pairing.set_srp_group(self.config.srp_group);
 
         // M1: Start pairing
         let m1 = pairing
             .start()
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Transient Pair-Setup M1 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         tracing::debug!("Starting Transient Pairing (SRP+Transient)...");
-        let m2 = self.send_pairing_data(&m1, "/pair-setup").await?;
+        let m2 = self
+            .send_pairing_data(&m1, "/pair-setup", HkpMode::Transient)
+            .await?;
         tracing::debug!("Received M2 ({} bytes)", m2.len());
 
         // M2 -> M3
         let result = pairing
             .process_m2(&m2)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Transient Pair-Setup M2 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         let PairingStepResult::SendData(m3) = result else {
             return Err(AirPlayError::AuthenticationFailed {
                 message: "Unexpected pairing state after M2".to_string(),
                 recoverable: false,
+                source: None,
             });
         };
 
         tracing::debug!("Sending M3...");
-        let m4 = self.send_pairing_data(&m3, "/pair-setup").await?;
+        let m4 = self
+            .send_pairing_data(&m3, "/pair-setup", HkpMode::Transient)
+            .await?;
         tracing::debug!("Received M4 ({} bytes)", m4.len());
 
         // M4 -> Complete (since transient=true)
         let result = pairing
             .process_m4(&m4)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Transient Pair-Setup M4 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         match result {
@@ -671,10 +1032,12 @@ impl ConnectionManager {
             PairingStepResult::SendData(_) => Err(AirPlayError::AuthenticationFailed {
                 message: "Unexpected continuation after M4 in transient mode".to_string(),
                 recoverable: false,
+                source: None,
             }),
             _ => Err(AirPlayError::AuthenticationFailed {
                 message: "Pairing did not complete".to_string(),
                 recoverable: false,
+                source: None,
             }),
         }
     }
@@ -687,8 +1050,9 @@ impl ConnectionManager {
     ) -> Result<SessionKeys, AirPlayError> {
         let mut pairing = PairVerify::new(keys.clone(), &keys.device_public_key).map_err(|e| {
             AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Verify init failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             }
         })?;
 
@@ -696,35 +1060,43 @@ impl ConnectionManager {
         let m1 = pairing
             .start()
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Verify M1 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
-        let m2 = self.send_pairing_data(&m1, "/pair-verify").await?;
+        let m2 = self
+            .send_pairing_data(&m1, "/pair-verify", self.quirks().hkp_mode)
+            .await?;
 
         // M2 -> M3
         let result = pairing
             .process_m2(&m2)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Verify M2 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         let PairingStepResult::SendData(m3) = result else {
             return Err(AirPlayError::AuthenticationFailed {
                 message: "Unexpected pairing state".to_string(),
                 recoverable: false,
+                source: None,
             });
         };
 
-        let m4 = self.send_pairing_data(&m3, "/pair-verify").await?;
+        let m4 = self
+            .send_pairing_data(&m3, "/pair-verify", self.quirks().hkp_mode)
+            .await?;
 
         // M4 -> Complete
         let result = pairing
             .process_m4(&m4)
             .map_err(|e| AirPlayError::AuthenticationFailed {
-                message: e.to_string(),
+                message: format!("Pair-Verify M4 failed: {e}"),
                 recoverable: false,
+                source: Some(Box::new(e)),
             })?;
 
         match result {
@@ -732,18 +1104,68 @@ impl ConnectionManager {
             _ => Err(AirPlayError::AuthenticationFailed {
                 message: "Verification did not complete".to_string(),
                 recoverable: false,
+                source: None,
             }),
         }
     }
 
     /// Setup RTSP session (`AirPlay` 2 sequence)
+    /// Set up a session without audio: no timing, no streams, event channel only
+    async fn setup_remote_control_session(
+        &self,
+        device: &AirPlayDevice,
+    ) -> Result<(), AirPlayError> {
+        tracing::debug!("Performing remote-control-only SETUP...");
+        let setup_plist = DictBuilder::new()
+            .insert("isRemoteControlOnly", true)
+            .insert("timingProtocol", "None")
+            .insert("macAddress", "AC:07:75:12:4A:1F")
+            .insert("isAudioReceiver", false)
+            .extend(&self.config.setup_overrides)
+            .build();
+
+        let request = {
+            let mut session_guard = self.rtsp_session.lock().await;
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| AirPlayError::InvalidState {
+                    message: "No RTSP session".to_string(),
+                    current_state: "None".to_string(),
+                })?;
+            session.setup_session_request(&setup_plist, None)
+        };
+        let response = self.send_rtsp_request(&request).await?;
+        {
+            let mut session_guard = self.rtsp_session.lock().await;
+            if let Some(session) = session_guard.as_mut() {
+                session
+                    .process_response(Method::Setup, &response)
+                    .map_err(|e| AirPlayError::RtspError {
+                        message: e,
+                        status_code: Some(response.status.as_u16()),
+                        source: None,
+                    })?;
+            }
+        }
+
+        let event_port = crate::protocol::plist::decode(&response.body)
+            .ok()
+            .and_then(|plist| plist.as_dict()?.get("eventPort")?.as_u64())
+            .and_then(|port| u16::try_from(port).ok())
+            .filter(|&port| port > 0);
+        if let Some(port) = event_port {
+            self.connect_event_channel(device.address(), port).await;
+        } else {
+            tracing::debug!("No eventPort in remote-control SETUP response");
+        }
+        Ok(())
+    }
+
     #[allow(
         clippy::too_many_lines,
         reason = "Logic is complex and sequential, hard to split without losing context"
     )]
     async fn setup_session(&self) -> Result<(), AirPlayError> {
-        use crate::protocol::plist::DictBuilder;
-
         // 1. GET /info (Encrypted) - Some devices refresh state here
         tracing::debug!("Performing GET /info (Encrypted)...");
         let _ = self.send_get_command("/info").await?;
@@ -815,6 +1237,7 @@ impl ConnectionManager {
                 .insert("groupUUID", group_uuid)
                 .insert("macAddress", "AC:07:75:12:4A:1F")
                 .insert("isAudioReceiver", false)
+                .extend(&self.config.setup_overrides)
                 .build();
 
             let setup_session_req = {
@@ -835,37 +1258,36 @@ impl ConnectionManager {
         // Sending ANNOUNCE to HomePod returns 455 and may corrupt session state.
         // However, for AAC-ELD (Realtime), we must send ANNOUNCE to provide the ASC (config)
         // because SETUP plist doesn't support it in standard AirPlay 2 flow (or Python Receiver
-        // needs it).
-        let is_aac_eld = matches!(self.config.audio_codec, AudioCodec::AacEld);
-        if use_ptp && !is_aac_eld {
-            tracing::info!("Skipping ANNOUNCE for PTP/Buffered Audio device");
-        } else {
+        // needs it). tvOS rejects it even then.
+        if self
+            .quirks()
+            .model
+            .accepts_announce(use_ptp, self.audio_codec())
+        {
             tracing::debug!("Performing ANNOUNCE...");
-            let use_hires = self.should_use_hires().await;
-            let sdp = match self.config.audio_codec {
-                AudioCodec::Alac => {
-                    let (sr, bit_depth) = if use_hires { (48000, 24) } else { (44100, 16) };
-                    format!(
-                        "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
-                         0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
-                         AppleLossless\r\na=fmtp:96 352 0 {bit_depth} 40 10 14 2 255 0 0 {sr}\r\n",
-                    )
-                }
-                AudioCodec::Pcm => {
-                    let (sr, bit_depth) = if use_hires { (48000, 24) } else { (44100, 16) };
-                    format!(
-                        "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
-                         0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
-                         L{bit_depth}/{sr}/2\r\na=fmtp:96 352 0 {bit_depth} 40 10 14 2 255 0 0 \
-                         {sr}\r\n",
-                    )
-                }
-                AudioCodec::Aac => "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
-                                    0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
-                                    mpeg4-generic/44100/2\r\na=fmtp:96 \
-                                    mode=AAC-hbr;sizelength=13;indexlength=3;indexdeltalength=3;\
-                                    constantDuration=1024\r\n"
-                    .to_string(),
+            let format = self.stream_format();
+            let sr = format.sample_rate.as_u32();
+            let bit_depth = format.sample_format.bits_per_sample();
+            let ch = format.channels.channels();
+            let sdp = match self.audio_codec() {
+                AudioCodec::Alac => format!(
+                    "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
+                     0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
+                     AppleLossless\r\na=fmtp:96 352 0 {bit_depth} 40 10 14 {ch} 255 0 0 {sr}\r\n",
+                ),
+                AudioCodec::Pcm => format!(
+                    "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
+                     0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
+                     L{bit_depth}/{sr}/{ch}\r\na=fmtp:96 352 0 {bit_depth} 40 10 14 {ch} 255 0 0 \
+                     {sr}\r\n",
+                ),
+                AudioCodec::Aac => format!(
+                    "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
+                     0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
+                     mpeg4-generic/{sr}/{ch}\r\na=fmtp:96 \
+                     mode=AAC-hbr;sizelength=13;indexlength=3;indexdeltalength=3;\
+                     constantDuration=1024\r\n"
+                ),
                 AudioCodec::Opus => {
                     return Err(AirPlayError::InvalidParameter {
                         name: "audio_codec".to_string(),
@@ -874,12 +1296,11 @@ impl ConnectionManager {
                 }
                 AudioCodec::AacEld => {
                     // Instantiate encoder to get ASC
-                    // Standard ELD: 44100Hz, Stereo
                     let encoder = crate::audio::AacEncoder::new(
-                        44100,
-                        2,
+                        sr,
+                        u32::from(ch),
                         64000,
-                        fdk_aac::enc::AudioObjectType::Mpeg4EnhancedLowDelay,
+                        crate::audio::aac_encoder::AudioObjectType::Mpeg4EnhancedLowDelay,
                     )
                     .map_err(|e| AirPlayError::InternalError {
                         message: format!("Failed to initialize AAC-ELD encoder for ASC: {e}"),
@@ -901,7 +1322,7 @@ impl ConnectionManager {
                     format!(
                         "v=0\r\no=- 0 0 IN IP4 0.0.0.0\r\ns=airplay2-rs\r\nc=IN IP4 \
                          0.0.0.0\r\nt=0 0\r\nm=audio 0 RTP/AVP 96\r\na=rtpmap:96 \
-                         mpeg4-generic/44100/2\r\na=fmtp:96 \
+                         mpeg4-generic/{sr}/{ch}\r\na=fmtp:96 \
                          mode=AAC-hbr;sizelength=13;indexlength=3;indexdeltalength=3;\
                          config={config_hex};constantDuration={frame_len}\r\n"
                     )
@@ -923,11 +1344,16 @@ impl ConnectionManager {
                 "ANNOUNCE response status: {}",
                 announce_response.status.as_u16()
             );
+        } else {
+            tracing::info!("Skipping ANNOUNCE for PTP/Buffered Audio device");
         }
 
         // 4. Session Setup (SETUP Step 1: Info/Timing/Event)
         tracing::debug!("Performing Session SETUP (Step 1)...");
-        let ek = self.encryption_key().await.unwrap_or([0u8; 32]);
+        let ek = self
+            .encryption_key()
+            .await
+            .unwrap_or_else(|| SecretBytes::new([0u8; 32]));
 
         let eiv = {
             use rand::RngCore;
@@ -947,15 +1373,10 @@ impl ConnectionManager {
             tracing::info!("Device supports Buffered Audio - Using PTP timing protocol");
 
             // Get local IP from the connected stream if possible
-            let local_ip = {
-                let stream_guard = self.stream.lock().await;
-                if let Some(ref stream) = *stream_guard {
-                    stream.local_addr().ok().map(|a| a.ip().to_string())
-                } else {
-                    None
-                }
-            }
-            .unwrap_or_else(|| "0.0.0.0".to_string());
+            let local_ip = self
+                .local_addr()
+                .await
+                .map_or_else(|| "0.0.0.0".to_string(), |a| a.ip().to_string());
 
             // Include our PTP ClockID so the HomePod can match our Delay_Req
             // sourcePortIdentity to an authorised peer. Use Integer format to
@@ -1009,17 +1430,19 @@ impl ConnectionManager {
                 .insert("groupUUID", group_uuid)
                 .insert("macAddress", "AC:07:75:12:4A:1F")
                 .insert("isAudioReceiver", false)
-                .insert("ekey", ek.to_vec())
+                .insert("ekey", ek.expose().to_vec())
                 .insert("eiv", eiv.to_vec())
                 .insert("et", 4)
+                .extend(&self.config.setup_overrides)
                 .build()
         } else {
             tracing::info!("Device does not support Buffered Audio - Using NTP timing protocol");
             DictBuilder::new()
                 .insert("timingProtocol", "NTP")
-                .insert("ekey", ek.to_vec())
+                .insert("ekey", ek.expose().to_vec())
                 .insert("eiv", eiv.to_vec())
                 .insert("et", 4)
+                .extend(&self.config.setup_overrides)
                 .build()
         };
 
@@ -1175,90 +1598,78 @@ impl ConnectionManager {
             time_port
         );
 
-        let transport = format!(
-            "RTP/AVP/UDP;unicast;mode=record;client_port={audio_port};control_port={ctrl_port};\
-             timing_port={time_port}"
-        );
+        let transport = TransportHeader {
+            client_port: Some(audio_port),
+            control_port: Some(ctrl_port),
+            timing_port: Some(time_port),
+            ..TransportHeader::record(LowerTransport::Udp)
+        }
+        .to_string();
 
         // AirPlay 2 Buffered Audio uses stream type 103 (required for HomePod / SETRATEANCHORTIME).
         // Type 96 = real-time audio (AirPlay 1-style); type 103 = buffered audio (AirPlay 2 PTP).
         // SETRATEANCHORTIME is only valid in buffered mode (type=103); HomePod returns 400 for it
         // when the stream is set up as real-time (type=96).
-        let stream_type: u64 = if use_ptp { 103 } else { 96 };
-
-        // Check if high-resolution audio (24-bit/48kHz) should be used.
-        let use_hires = self.should_use_hires().await;
+        // Third-party receivers only take real-time streams.
+        let profile = self.quirks().profile;
+        let stream_type: u64 = if use_ptp && !profile.realtime_aac_only {
+            103
+        } else {
+            96
+        };
 
-        // Determine ct (compression type) and audioFormat
-        // ct: 0x1 = PCM, 0x2 = ALAC, 0x4 = AAC_LC, 0x8 = AAC_ELD
-        let (ct, spf, audio_format) = match self.config.audio_codec {
-            AudioCodec::Pcm => {
-                if use_hires {
-                    (0x1, 352, 1 << 16) // Just a guess, might not matter if audioFormat is ignored
-                } else {
-                    (0x1, 352, 1 << 11) // PCM 44100/16/2 = 2048
-                }
-            }
-            AudioCodec::Alac => {
-                if use_hires {
-                    (0x2, 352, 1 << 16)
-                } else {
-                    (0x2, 352, 0x40000) // ALAC
-                }
-            }
-            AudioCodec::Aac => (0x4, 1024, 1 << 22), // AAC_LC_44100_2
+        // ct: 0x1 = PCM, 0x2 = ALAC, 0x4 = AAC_LC, 0x8 = AAC_ELD; audioFormat
+        // is the single bit naming codec, rate, depth and channels together
+        let format = self.stream_format();
+        let codec = self.audio_codec();
+        let audio_format = format.audio_format_mask(codec).unwrap_or(0);
+        let (ct, spf) = match codec {
+            AudioCodec::Pcm => (0x1, 352),
+            AudioCodec::Alac => (0x2, 352),
+            AudioCodec::Aac => (0x4, 1024),
             AudioCodec::AacEld => {
                 let spf = crate::audio::AacEncoder::new(
-                    44100,
-                    2,
+                    format.sample_rate.as_u32(),
+                    u32::from(format.channels.channels()),
                     64000,
-                    fdk_aac::enc::AudioObjectType::Mpeg4EnhancedLowDelay,
+                    crate::audio::aac_encoder::AudioObjectType::Mpeg4EnhancedLowDelay,
                 )
                 .ok()
                 .and_then(|e| e.get_frame_length())
                 .unwrap_or(512);
-                (0x8, spf, 1 << 24)
+                (0x8, spf)
             }
-            AudioCodec::Opus => (0x0, 480, 0), // Not supported by standard receivers usually
+            AudioCodec::Opus => (0x0, 480), // Not supported by standard receivers usually
         };
 
-        // Note: audioFormat values are bitmasks or specific IDs.
-        // For compatibility with the Python receiver (which expects audioFormat), we send a valid
-        // one. 1<<11 (2048) works for PCM in the receiver.
-        // For AAC, let's try 0x100 (256) or just use the same default if it's ignored for AAC?
-        // Receiver uses audio_format for ALSA setup?
-        // Let's assume 0x400 (1024) or similar?
-        // Actually, Python receiver uses audio_format in AudioRealtime/Buffered.
-        // If we send 1<<11 (2048), it sets up 44100/16/2 PCM.
-        // Even for AAC streaming, the receiver might decode to PCM?
-        // Let's use 1<<11 as a safe default for audioFormat if uncertain, as it defines the output
-        // format?
-
         let mut stream_builder = DictBuilder::new()
             .insert("type", stream_type)
             .insert("ct", ct)
             .insert("audioFormat", audio_format)
             .insert("spf", u64::from(spf))
             .insert("audioType", "default")
-            .insert("shk", ek.to_vec())
+            .insert("shk", ek.expose().to_vec())
             .insert("shiv", eiv.to_vec()) // Include IV for Realtime streams (Python receiver needs it)
             .insert("controlPort", u64::from(ctrl_port))
             .insert("timingPort", u64::from(time_port))
-            .insert("latencyMin", 11025) // 250ms in samples
-            .insert("latencyMax", 88200); // 2s in samples
+            .insert("latencyMin", u64::from(profile.latency_min))
+            .insert("latencyMax", u64::from(profile.latency_max));
 
-        // Add sample rate and bits per sample explicitly for hires
-        if use_hires {
+        // Spell out anything other than CD quality for receivers that
+        // ignore audioFormat
+        if format != AudioFormat::CD_QUALITY {
             stream_builder = stream_builder
-                .insert("sr", 48000_u64)
-                .insert("ss", 24_u64)
-                .insert("ch", 2_u64);
+                .insert("sr", u64::from(format.sample_rate.as_u32()))
+                .insert("ss", u64::from(format.sample_format.bits_per_sample()))
+                .insert("ch", u64::from(format.channels.channels()));
         }
 
-        let stream_entry = stream_builder.build();
+        let stream_entry = stream_builder.extend(&self.config.stream_overrides).build();
+        *self.stream_setup.lock().await = Some(stream_entry.clone());
 
         let setup_plist_step2 = DictBuilder::new()
             .insert("streams", vec![stream_entry])
+            .extend(&self.config.setup_overrides)
             .build();
 
         let setup_req_step2 = {
@@ -1292,6 +1703,15 @@ impl ConnectionManager {
             tracing::info!("  {}: {}", k, v);
         }
 
+        if response_step2.is_success() {
+            let plist = crate::protocol::plist::decode(&response_step2.body)
+                .unwrap_or_else(|_| DictBuilder::new().build());
+            if let Some(session) = self.rtsp_session.lock().await.as_mut() {
+                let streams = session.record_setup_response(&[stream_type], &plist);
+                tracing::debug!("Streams set up: {:?}", streams);
+            }
+        }
+
         let mut server_ports = None;
         match crate::protocol::plist::decode(&response_step2.body) {
             Ok(plist) => {
@@ -1387,16 +1807,21 @@ impl ConnectionManager {
 
         // Check for Transport header in Step 2 response
         if server_ports.is_none() {
-            if let Some(transport_header) = response_step2.headers.get("Transport") {
-                if let Ok((sp, cp, tp)) = Self::parse_transport_ports(transport_header) {
-                    // parse_transport_ports returns (server_port, control_port, timing_port)
-                    // server_port is data port.
-                    // timing_port is usually timing port.
-                    // Where is event port? Only in plist?
-                    // Use step 1 event port.
+            match response_step2.headers.transport() {
+                Ok(Some(TransportHeader {
+                    server_port: Some(sp),
+                    control_port,
+                    timing_port,
+                    ..
+                })) => {
+                    // server_port is the data port. The event port is only
+                    // in the plist, so use the one from step 1.
                     let ep = server_event_port.unwrap_or(0);
-                    server_ports = Some((sp, cp, ep, tp));
+                    server_ports =
+                        Some((sp, control_port.unwrap_or(0), ep, timing_port.unwrap_or(0)));
                 }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Invalid Transport in SETUP Step 2 response: {}", e),
             }
         }
 
@@ -1498,61 +1923,10 @@ impl ConnectionManager {
             let ctrl_arc = std::sync::Arc::new(ctrl_sock);
 
             // 7c. Connect TCP event channel — HomePod requires this before it will
-            //     accept SETRATEANCHORTIME or RECORD.  The HomePod sends plist-encoded
-            //     playback events on this channel; we just need to drain them to prevent
-            //     the TCP send-buffer from stalling.
+            //     accept SETRATEANCHORTIME or RECORD.
             if server_event_port > 0 {
-                tracing::info!(
-                    "Connecting event channel TCP to {}:{}",
-                    device_ip,
-                    server_event_port
-                );
-                let event_connect_result = tokio::time::timeout(
-                    std::time::Duration::from_secs(5),
-                    tokio::net::TcpStream::connect((device_ip, server_event_port)),
-                )
-                .await
-                .unwrap_or_else(|_| {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::TimedOut,
-                        "event channel connect timed out after 5s",
-                    ))
-                });
-                match event_connect_result {
-                    Ok(mut event_stream) => {
-                        tracing::info!("✓ Event channel connected to port {}", server_event_port);
-                        // Drain task: reads and discards any events HomePod sends.
-                        // Moving event_stream into the task keeps the TCP connection alive.
-                        let handle = tokio::spawn(async move {
-                            let mut buf = [0u8; 4096];
-                            loop {
-                                match crate::net::AsyncReadExt::read(&mut event_stream, &mut buf)
-                                    .await
-                                {
-                                    Ok(0) => {
-                                        tracing::debug!("Event channel: HomePod closed connection");
-                                        break;
-                                    }
-                                    Ok(n) => {
-                                        tracing::trace!("Event channel: {} bytes received", n);
-                                    }
-                                    Err(e) => {
-                                        tracing::warn!("Event channel read error: {}", e);
-                                        break;
-                                    }
-                                }
-                            }
-                        });
-                        *self.event_task.lock().await = Some(handle);
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to connect event channel (port {}): {}",
-                            server_event_port,
-                            e
-                        );
-                    }
-                }
+                self.connect_event_channel(device_ip, server_event_port)
+                    .await;
             } else {
                 tracing::warn!(
                     "eventPort is 0 — skipping event channel (SETRATEANCHORTIME may fail)"
@@ -1579,7 +1953,7 @@ impl ConnectionManager {
 
             // Spawn task to listen for RetransmitRequest packets on control socket
             let event_tx = self.event_tx.clone();
-            tokio::spawn(async move {
+            self.tasks.spawn(async move {
                 let mut buf = [0u8; 1024];
                 loop {
                     tokio::select! {
@@ -1627,26 +2001,53 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Third-party receiver profile resolved on the last connect
+    #[must_use]
+    pub fn receiver_profile(&self) -> ReceiverProfile {
+        self.quirks().profile
+    }
+
+    /// Quirks resolved for the device at connect time
+    fn quirks(&self) -> DeviceQuirks {
+        *self
+            .quirks
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
     /// Send pairing data to device
     #[allow(
         clippy::too_many_lines,
         reason = "Refactored byte-by-byte read logic increases line count"
     )]
-    async fn send_pairing_data(&self, data: &[u8], path: &str) -> Result<Vec<u8>, AirPlayError> {
+    async fn send_pairing_data(
+        &self,
+        data: &[u8],
+        path: &str,
+        hkp_mode: HkpMode,
+    ) -> Result<Vec<u8>, AirPlayError> {
         // Send as HTTP POST
         // Note: We need to include the standard RTSP/AirPlay headers here too,
         // as some devices reject bare HTTP POSTs without the correct User-Agent/identifiers.
 
-        let (device_id, session_id, user_agent) = {
+        let (device_id, dacp_id, session_id, active_remote, user_agent) = {
             let session_guard = self.rtsp_session.lock().await;
             if let Some(session) = session_guard.as_ref() {
                 (
                     session.device_id().to_string(),
+                    session.dacp_id().to_string(),
                     session.client_session_id().to_string(),
+                    session.active_remote().to_string(),
                     session.user_agent().to_string(),
                 )
             } else {
-                (String::new(), String::new(), "AirPlay/540.31".to_string())
+                (
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    "AirPlay/540.31".to_string(),
+                )
             }
         };
 
@@ -1664,12 +2065,13 @@ impl ConnectionManager {
         let mut request = format!(
             "POST {path} HTTP/1.1\r\nHost: {host}\r\nContent-Type: \
              application/octet-stream\r\nContent-Length: {}\r\nUser-Agent: \
-             {user_agent}\r\nActive-Remote: 4294967295\r\nX-Apple-Client-Name: airplay2-rs\r\n",
+             {user_agent}\r\nX-Apple-Client-Name: airplay2-rs\r\n",
             data.len()
         );
 
         if !device_id.is_empty() {
-            let _ = write!(request, "DACP-ID: {device_id}\r\n");
+            let _ = write!(request, "Active-Remote: {active_remote}\r\n");
+            let _ = write!(request, "DACP-ID: {dacp_id}\r\n");
             let _ = write!(request, "X-Apple-Device-ID: {device_id}\r\n");
         }
 
@@ -1678,21 +2080,17 @@ impl ConnectionManager {
         }
 
         // Add X-Apple-HKP header for pairing requests
-        // 3 = Normal, 4 = Transient
-        // We default to 4 (Transient) as we are mostly trying 3939 flow
         if path.starts_with("/pair-setup") || path.starts_with("/pair-verify") {
-            request.push_str("X-Apple-HKP: 4\r\n");
+            let _ = write!(request, "X-Apple-HKP: {}\r\n", hkp_mode.header_value());
         }
 
         request.push_str("\r\n");
 
         let mut stream_guard = self.stream.lock().await;
 
-        let stream = stream_guard
-            .as_mut()
-            .ok_or_else(|| AirPlayError::Disconnected {
-                device_name: "unknown".to_string(),
-            })?;
+        let Some(stream) = stream_guard.as_mut() else {
+            return Err(self.disconnected().await);
+        };
 
         // Send request
         stream.write_all(request.as_bytes()).await?;
@@ -1711,6 +2109,7 @@ impl ConnectionManager {
                 return Err(AirPlayError::RtspError {
                     message: "Connection closed while reading headers".to_string(),
                     status_code: None,
+                    source: None,
                 });
             }
 
@@ -1726,6 +2125,7 @@ impl ConnectionManager {
                 return Err(AirPlayError::RtspError {
                     message: "Headers too large".to_string(),
                     status_code: None,
+                    source: None,
                 });
             }
         }
@@ -1735,6 +2135,7 @@ impl ConnectionManager {
             std::str::from_utf8(&buf[..body_start]).map_err(|_| AirPlayError::RtspError {
                 message: "Invalid UTF-8 in headers".to_string(),
                 status_code: None,
+                source: None,
             })?;
 
         tracing::debug!("<< Pairing Response Headers:\n{}", headers_str.trim());
@@ -1765,27 +2166,150 @@ impl ConnectionManager {
         }
 
         // Log pairing response body
-        tracing::debug!(
-            "<< Received Pairing Data ({} bytes): {:02X?}",
-            body.len(),
-            body
-        );
+        tracing::debug!("<< Received Pairing Data ({} bytes)", body.len());
 
         Ok(body)
     }
 
+    /// Hand the control connection to a background reader
+    ///
+    /// Pairing runs in request/response lockstep on the raw stream; after
+    /// it, responses are matched to requests by `CSeq` and requests the
+    /// receiver sends unprompted are answered as they arrive.
+    async fn start_rtsp_channel(&self) {
+        let Some(stream) = self.stream.lock().await.take() else {
+            return;
+        };
+        let local_addr = stream.local_addr().ok();
+        let secure = self.secure_session.lock().await.take();
+        let (reader, writer) = stream.into_split();
+
+        let channel = RtspChannel::spawn(
+            reader,
+            writer,
+            secure,
+            local_addr,
+            self.handlers.clone(),
+            Some(self.event_tx.clone()),
+        )
+        .with_request_timeout(self.config.request_timeout);
+        *self.rtsp_channel.lock().await = Some(Arc::new(channel));
+    }
+
+    /// Set the handler for requests the receiver sends on the control
+    /// connection
+    ///
+    /// The handler's status is sent back to the receiver; without one every
+    /// request is answered `200 OK`. Requests are also published as
+    /// [`ConnectionEvent::ServerRequest`].
+    pub fn set_server_request_handler(
+        &self,
+        handler: impl Fn(&RtspRequest) -> StatusCode + Send + Sync + 'static,
+    ) {
+        let handler: ServerRequestHandler = Arc::new(handler);
+        *self
+            .handlers
+            .request
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(handler);
+    }
+
+    /// Set the handler for interleaved frames the receiver sends on the
+    /// control connection
+    ///
+    /// With a `RTP/AVP/TCP` transport the receiver's RTCP, and any RTP,
+    /// arrives this way. Without a handler the frames are dropped.
+    pub fn set_interleaved_handler(
+        &self,
+        handler: impl Fn(InterleavedFrame) + Send + Sync + 'static,
+    ) {
+        let handler: InterleavedHandler = Arc::new(handler);
+        *self
+            .handlers
+            .interleaved
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(handler);
+    }
+
+    /// Send a binary frame on an interleaved channel of the control
+    /// connection
+    ///
+    /// Carries RTP or RTCP over TCP when the stream was set up with an
+    /// `RTP/AVP/TCP;interleaved=...` transport, for networks that block UDP.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the control connection is not open, the payload
+    /// exceeds 65535 bytes or the write fails.
+    pub async fn send_interleaved(&self, channel: u8, payload: &[u8]) -> Result<(), AirPlayError> {
+        let rtsp_channel = self.rtsp_channel.lock().await.clone();
+        let Some(rtsp_channel) = rtsp_channel else {
+            return Err(AirPlayError::InvalidState {
+                message: "Interleaved data needs an open control connection".to_string(),
+                current_state: format!("{:?}", self.state().await),
+            });
+        };
+        let frame = InterleavedFrame {
+            channel,
+            payload: payload.to_vec(),
+        };
+        rtsp_channel.send_interleaved(&frame).await
+    }
+
+    /// Local address of the control connection
+    async fn local_addr(&self) -> Option<std::net::SocketAddr> {
+        if let Some(channel) = self.rtsp_channel.lock().await.as_ref() {
+            return channel.local_addr();
+        }
+        self.stream
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|stream| stream.local_addr().ok())
+    }
+
     /// Send RTSP request and get response
-    #[allow(clippy::too_many_lines, reason = "Complex RTSP request handling logic")]
     async fn send_rtsp_request(&self, request: &RtspRequest) -> Result<RtspResponse, AirPlayError> {
-        let encoded = request.encode();
+        self.protocol_log.record_request(request);
+        let channel = self.rtsp_channel.lock().await.clone();
+        let Some(channel) = channel else {
+            let response = self.send_rtsp_request_lockstep(request).await?;
+            self.protocol_log.record_response(&response);
+            return Ok(response);
+        };
 
-        let mut secure_guard = self.secure_session.lock().await;
-        let mut stream_guard = self.stream.lock().await;
-        let stream = stream_guard
-            .as_mut()
-            .ok_or_else(|| AirPlayError::Disconnected {
-                device_name: "unknown".to_string(),
-            })?;
+        tracing::debug!(
+            ">> Sending RTSP request: {} {}",
+            request.method.as_str(),
+            request.uri
+        );
+        let response = channel.request(request).await?;
+        tracing::debug!(
+            "<< RTSP response: {} {}",
+            response.status.as_u16(),
+            response.reason
+        );
+
+        self.protocol_log.record_response(&response);
+        let mut stats = self.stats.write().await;
+        stats.record_sent(request.encode().len());
+        stats.record_received(response.body.len());
+        Ok(response)
+    }
+
+    /// Send RTSP request and read its response on the raw stream
+    #[allow(clippy::too_many_lines, reason = "Complex RTSP request handling logic")]
+    async fn send_rtsp_request_lockstep(
+        &self,
+        request: &RtspRequest,
+    ) -> Result<RtspResponse, AirPlayError> {
+        let encoded = request.encode();
+
+        let mut secure_guard = self.secure_session.lock().await;
+        let mut stream_guard = self.stream.lock().await;
+        let Some(stream) = stream_guard.as_mut() else {
+            return Err(self.disconnected().await);
+        };
 
         if let Some(ref mut secure) = *secure_guard {
             // Always log plaintext headers before encryption for diagnostic purposes.
@@ -1836,6 +2360,7 @@ impl ConnectionManager {
             if let Some(response) = codec.decode().map_err(|e| AirPlayError::RtspError {
                 message: e.to_string(),
                 status_code: None,
+                source: Some(Box::new(e)),
             })? {
                 // Check CSeq: if we know our expected CSeq and the response CSeq differs,
                 // this is a deferred response for an earlier request (e.g., RECORD) — discard.
@@ -1855,9 +2380,7 @@ impl ConnectionManager {
 
             let n = stream.read(&mut buf).await?;
             if n == 0 {
-                return Err(AirPlayError::Disconnected {
-                    device_name: "unknown".to_string(),
-                });
+                return Err(self.disconnected().await);
             }
 
             if let Some(ref mut secure) = *secure_guard {
@@ -1886,6 +2409,7 @@ impl ConnectionManager {
                             .map_err(|e| AirPlayError::RtspError {
                                 message: e.to_string(),
                                 status_code: None,
+                                source: Some(Box::new(e)),
                             })?;
                     } else {
                         break;
@@ -1902,6 +2426,7 @@ impl ConnectionManager {
                 codec.feed(&buf[..n]).map_err(|e| AirPlayError::RtspError {
                     message: e.to_string(),
                     status_code: None,
+                    source: Some(Box::new(e)),
                 })?;
             }
 
@@ -1929,15 +2454,10 @@ impl ConnectionManager {
         use crate::protocol::plist::PlistValue;
 
         // Get our local IP from the connected stream
-        let local_ip = {
-            let stream_guard = self.stream.lock().await;
-            if let Some(ref stream) = *stream_guard {
-                stream.local_addr().ok().map(|a| a.ip().to_string())
-            } else {
-                None
-            }
-        }
-        .unwrap_or_else(|| "0.0.0.0".to_string());
+        let local_ip = self
+            .local_addr()
+            .await
+            .map_or_else(|| "0.0.0.0".to_string(), |a| a.ip().to_string());
 
         // AirPlay 2 SETPEERS: simple IP-string array is the accepted format.
         // The HomePod rejects dict-based peer lists (causes disconnect).
@@ -1957,6 +2477,7 @@ impl ConnectionManager {
             crate::protocol::plist::encode(&peer_list).map_err(|e| AirPlayError::RtspError {
                 message: format!("Failed to encode SETPEERS plist: {e}"),
                 status_code: None,
+                source: Some(Box::new(e)),
             })?;
 
         let request = {
@@ -1993,6 +2514,13 @@ impl ConnectionManager {
     ///
     /// Returns error if RTSP request fails
     pub async fn record(&self) -> Result<(), AirPlayError> {
+        if self.config.remote_control_only {
+            return Err(AirPlayError::InvalidState {
+                message: "Connected in remote-control-only mode; no audio stream is set up"
+                    .to_string(),
+                current_state: format!("{:?}", self.state().await),
+            });
+        }
         tracing::debug!("Sending RECORD request...");
         let record_request = {
             let mut session_guard = self.rtsp_session.lock().await;
@@ -2023,6 +2551,7 @@ impl ConnectionManager {
             return Err(AirPlayError::RtspError {
                 message: format!("RECORD failed with status {status}: {}", response.reason),
                 status_code: Some(status),
+                source: None,
             });
         }
         Ok(())
@@ -2038,34 +2567,59 @@ impl ConnectionManager {
     ///
     /// Returns error if plist encoding fails or RTSP request fails.
     pub async fn send_set_rate_anchor_time(&self, rate: f64) -> Result<(), AirPlayError> {
+        // Anchor the sample playing now, so a re-anchor mid-stream keeps the
+        // device's position instead of restarting the timeline.
+        let (rtp_time, position) =
+            self.timeline()
+                .await
+                .map_or((0, std::time::Duration::ZERO), |t| {
+                    let position = t.position();
+                    (t.rtp_time_at(position), position)
+                });
+        self.send_set_rate_anchor_at(rate, rtp_time, position).await
+    }
+
+    /// Send SETRATEANCHORTIME anchoring `rtp_time` to now
+    ///
+    /// The sample with RTP timestamp `rtp_time` renders now and the timeline
+    /// maps it to media `position`. Used after a seek, when the next packet
+    /// sent starts the new position.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if plist encoding fails or RTSP request fails.
+    pub async fn send_set_rate_anchor_at(
+        &self,
+        rate: f64,
+        rtp_time: u32,
+        position: std::time::Duration,
+    ) -> Result<(), AirPlayError> {
         // Get device clock ID
         let device_clock_id = self.device_clock_id().await.unwrap_or(0);
 
         // Get current network time. The HomePod's PTP clock uses its own epoch.
         // We send the master clock time (HomePod's PTP time = local - offset).
         let now = crate::protocol::ptp::timestamp::PtpTimestamp::now();
-        #[allow(clippy::cast_possible_truncation, reason = "NTP fraction fits in u64")]
-        let (network_secs, network_frac) = {
-            let clock_opt = self.ptp_clock().await;
-            if let Some(ref clock_arc) = clock_opt {
-                let clock = clock_arc.read().await;
-                let local_nanos = now.to_nanos();
-                // offset = slave - master, so master_time = local_time - offset
-                let remote_nanos = local_nanos - clock.offset_nanos();
-                let remote = if remote_nanos < 0 {
-                    crate::protocol::ptp::timestamp::PtpTimestamp::ZERO
-                } else {
-                    crate::protocol::ptp::timestamp::PtpTimestamp::from_nanos(remote_nanos)
-                };
-                // NTP-style 64-bit fraction: (nanoseconds / 1e9) * 2^64
-                let frac = ((u128::from(remote.nanoseconds) << 64) / 1_000_000_000) as u64;
-                (remote.seconds, frac)
+        let network_time = if let Some(clock_arc) = self.ptp_clock().await {
+            let clock = clock_arc.read().await;
+            // offset = slave - master, so master_time = local_time - offset
+            let remote_nanos = now.to_nanos() - clock.offset_nanos();
+            if remote_nanos < 0 {
+                crate::protocol::ptp::timestamp::PtpTimestamp::ZERO
             } else {
-                let frac = ((u128::from(now.nanoseconds) << 64) / 1_000_000_000) as u64;
-                (now.seconds, frac)
+                crate::protocol::ptp::timestamp::PtpTimestamp::from_nanos(remote_nanos)
             }
+        } else {
+            now
         };
 
+        // A delayed group member renders the anchored sample that much later
+        let anchor_time = network_time.add_duration(self.playback_delay());
+        let network_secs = anchor_time.seconds;
+        // NTP-style 64-bit fraction: (nanoseconds / 1e9) * 2^64
+        #[allow(clippy::cast_possible_truncation, reason = "NTP fraction fits in u64")]
+        let network_frac = ((u128::from(anchor_time.nanoseconds) << 64) / 1_000_000_000) as u64;
+
         tracing::info!(
             "Sending SETRATEANCHORTIME (rate={}, networkTimeSecs={}, networkTimeFrac=0x{:016X}, \
              timelineID=0x{:016X})",
@@ -2080,7 +2634,7 @@ impl ConnectionManager {
         // networkTimeSecs/networkTimeFrac/networkTimeTimelineID are Integer-encoded.
         let mut body = crate::protocol::plist::DictBuilder::new()
             .insert("rate", rate) // f64 → PlistValue::Real
-            .insert("rtpTime", 0i64);
+            .insert("rtpTime", i64::from(rtp_time));
 
         // Only include timing fields if we have a valid device clock ID
         if device_clock_id != 0 {
@@ -2103,6 +2657,7 @@ impl ConnectionManager {
             crate::protocol::plist::encode(&body).map_err(|e| AirPlayError::RtspError {
                 message: format!("Failed to encode SETRATEANCHORTIME plist: {e}"),
                 status_code: None,
+                source: Some(Box::new(e)),
             })?;
 
         tracing::info!(
@@ -2119,6 +2674,13 @@ impl ConnectionManager {
         .await?;
 
         tracing::info!("SETRATEANCHORTIME accepted by device (rate={})", rate);
+
+        let network_time = (device_clock_id != 0).then_some(network_time);
+        self.update_timeline(|t| {
+            t.anchor(rtp_time, position, network_time);
+            t.set_rate(rate);
+        })
+        .await;
         Ok(())
     }
 
@@ -2159,6 +2721,292 @@ impl ConnectionManager {
         Ok(())
     }
 
+    /// Streams set up in the current session, main audio first
+    pub async fn streams(&self) -> Vec<SessionStream> {
+        self.rtsp_session
+            .lock()
+            .await
+            .as_ref()
+            .map(|session| session.streams().to_vec())
+            .unwrap_or_default()
+    }
+
+    /// Set up an additional stream in the current session
+    ///
+    /// `stream` is the stream dictionary sent in the SETUP `streams` array; it
+    /// must include `type`. The receiver's `streamID` and ports are recorded
+    /// and returned.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if there is no session, `type` is missing, or the
+    /// receiver rejects the SETUP.
+    pub async fn setup_stream(&self, stream: PlistValue) -> Result<SessionStream, AirPlayError> {
+        let stream_type = stream
+            .as_dict()
+            .and_then(|dict| dict.get("type"))
+            .and_then(PlistValue::as_u64)
+            .ok_or_else(|| AirPlayError::InvalidParameter {
+                name: "stream".to_string(),
+                message: "stream dictionary must include a type".to_string(),
+            })?;
+
+        let request = {
+            let mut session_guard = self.rtsp_session.lock().await;
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| AirPlayError::InvalidState {
+                    message: "No RTSP session".to_string(),
+                    current_state: "None".to_string(),
+                })?;
+            let body = DictBuilder::new().insert("streams", vec![stream]).build();
+            session.setup_session_request(&body, None)
+        };
+        let response = self.send_rtsp_request(&request).await?;
+        if !response.is_success() {
+            return Err(AirPlayError::RtspError {
+                message: format!("stream SETUP failed: {}", response.reason),
+                status_code: Some(response.status.as_u16()),
+                source: None,
+            });
+        }
+
+        let plist = crate::protocol::plist::decode(&response.body)
+            .unwrap_or_else(|_| DictBuilder::new().build());
+        let mut session_guard = self.rtsp_session.lock().await;
+        session_guard
+            .as_mut()
+            .and_then(|session| {
+                session
+                    .record_setup_response(&[stream_type], &plist)
+                    .into_iter()
+                    .next()
+            })
+            .ok_or_else(|| AirPlayError::RtspError {
+                message: "stream SETUP response did not describe a stream".to_string(),
+                status_code: Some(response.status.as_u16()),
+                source: None,
+            })
+    }
+
+    /// Tear down one stream, leaving the session and other streams running
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the stream is unknown or the receiver rejects the
+    /// TEARDOWN.
+    pub async fn teardown_stream(&self, stream_id: u64) -> Result<(), AirPlayError> {
+        let request = {
+            let mut session_guard = self.rtsp_session.lock().await;
+            session_guard
+                .as_mut()
+                .and_then(|session| session.teardown_stream_request(stream_id))
+                .ok_or_else(|| AirPlayError::InvalidParameter {
+                    name: "stream_id".to_string(),
+                    message: format!("no stream with ID {stream_id}"),
+                })?
+        };
+        let response = self.send_rtsp_request(&request).await?;
+        if !response.is_success() {
+            return Err(AirPlayError::RtspError {
+                message: format!("stream TEARDOWN failed: {}", response.reason),
+                status_code: Some(response.status.as_u16()),
+                source: None,
+            });
+        }
+
+        if let Some(session) = self.rtsp_session.lock().await.as_mut() {
+            session.remove_stream(stream_id);
+        }
+        Ok(())
+    }
+
+    /// Set the main audio stream up again
+    ///
+    /// Recovers a session whose state has diverged from the device's: the
+    /// stream is torn down, set up with its original parameters and
+    /// re-anchored at the current playback position and rate (RECORD for
+    /// realtime streams). Audio sent meanwhile may be lost, but the session
+    /// and stream position survive.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no audio stream was set up or the device rejects the
+    /// new SETUP or anchor.
+    pub async fn resync_stream(&self) -> Result<(), AirPlayError> {
+        use std::sync::atomic::Ordering;
+
+        let stream =
+            self.stream_setup
+                .lock()
+                .await
+                .clone()
+                .ok_or_else(|| AirPlayError::InvalidState {
+                    message: "No audio stream to set up again".to_string(),
+                    current_state: "None".to_string(),
+                })?;
+
+        self.resyncing.store(true, Ordering::Release);
+        let result = self.resync_stream_with(stream).await;
+        self.resyncing.store(false, Ordering::Release);
+        result
+    }
+
+    async fn resync_stream_with(&self, stream: PlistValue) -> Result<(), AirPlayError> {
+        let old_stream = self
+            .rtsp_session
+            .lock()
+            .await
+            .as_ref()
+            .and_then(|session| session.primary_stream().copied());
+        if let Some(old_stream) = old_stream {
+            if let Err(e) = self.teardown_stream(old_stream.id).await {
+                // The device may have dropped it already
+                tracing::debug!("Stream TEARDOWN before re-SETUP failed: {e}");
+                if let Some(session) = self.rtsp_session.lock().await.as_mut() {
+                    session.remove_stream(old_stream.id);
+                }
+            }
+        }
+
+        let new_stream = self.setup_stream(stream).await?;
+        if let Some(session) = self.rtsp_session.lock().await.as_mut() {
+            session.set_primary_stream(new_stream.id);
+        }
+        tracing::info!("Audio stream set up again: {:?}", new_stream);
+
+        let device_ip = self
+            .device
+            .read()
+            .await
+            .as_ref()
+            .map(AirPlayDevice::address);
+        if let (Some(device_ip), Some(data_port)) = (device_ip, new_stream.data_port) {
+            if let Some(sockets) = self.sockets.lock().await.as_mut() {
+                sockets.audio.connect((device_ip, data_port)).await?;
+                sockets.server_audio_port = data_port;
+                if let Some(control_port) = new_stream.control_port {
+                    sockets.control.connect((device_ip, control_port)).await?;
+                    sockets.server_control_port = control_port;
+                }
+            }
+            if new_stream.stream_type == 103 {
+                let tcp_stream = TcpStream::connect((device_ip, data_port)).await?;
+                *self.audio_tcp_stream.lock().await = Some(tcp_stream);
+            }
+        }
+
+        if new_stream.stream_type == 103 {
+            let rate = self.timeline().await.map_or(0.0, |t| t.rate());
+            self.send_set_rate_anchor_time(rate).await
+        } else {
+            self.record().await
+        }
+    }
+
+    /// Send FLUSHBUFFERED for a buffered stream
+    ///
+    /// Discards queued audio up to `until_seq`/`until_timestamp`, optionally
+    /// starting at `from` (sequence number, timestamp). `stream_id` defaults
+    /// to the main audio stream.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no such stream exists or the receiver rejects the
+    /// request.
+    pub async fn send_flush_buffered(
+        &self,
+        stream_id: Option<u64>,
+        from: Option<(u32, u32)>,
+        until_seq: u32,
+        until_timestamp: u32,
+    ) -> Result<(), AirPlayError> {
+        let request = {
+            let mut session_guard = self.rtsp_session.lock().await;
+            let session = session_guard
+                .as_mut()
+                .ok_or_else(|| AirPlayError::InvalidState {
+                    message: "No RTSP session".to_string(),
+                    current_state: "None".to_string(),
+                })?;
+            let stream = match stream_id {
+                Some(id) => session.stream(id),
+                None => session.primary_stream(),
+            }
+            .copied()
+            .ok_or_else(|| AirPlayError::InvalidParameter {
+                name: "stream_id".to_string(),
+                message: "no such stream in this session".to_string(),
+            })?;
+            session.flush_buffered_request(stream.id, from, until_seq, until_timestamp)
+        };
+
+        let response = self.send_rtsp_request(&request).await?;
+        if !response.is_success() {
+            return Err(AirPlayError::RtspError {
+                message: format!("FLUSHBUFFERED failed: {}", response.reason),
+                status_code: Some(response.status.as_u16()),
+                source: None,
+            });
+        }
+        Ok(())
+    }
+
+    /// Whether the main audio stream is a buffered (type 103) stream
+    ///
+    /// Buffered receivers hold seconds of audio ahead, so pausing and
+    /// seeking act on the receiver's buffer (SETRATEANCHORTIME,
+    /// FLUSHBUFFERED) rather than on the sender alone.
+    pub async fn is_buffered(&self) -> bool {
+        self.rtsp_session
+            .lock()
+            .await
+            .as_ref()
+            .and_then(RtspSession::primary_stream)
+            .is_some_and(|stream| stream.stream_type == 103)
+    }
+
+    /// Flush a buffered stream from the sample playing now up to a packet
+    ///
+    /// Audio the receiver has queued from the current playback position up
+    /// to (excluding) `until_seq`/`until_timestamp` is discarded, so playback
+    /// jumps straight to the packet sent after a seek. `frames_per_packet`
+    /// locates the packet playing now from the timeline.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if there is no buffered stream or the receiver rejects
+    /// the request.
+    pub async fn flush_buffered_until(
+        &self,
+        until_seq: u16,
+        until_timestamp: u32,
+        frames_per_packet: u32,
+    ) -> Result<(), AirPlayError> {
+        let from = self.timeline().await.map(|timeline| {
+            let playing = timeline.rtp_time_at(timeline.position());
+            let packets = until_timestamp.wrapping_sub(playing) / frames_per_packet.max(1);
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "RTP sequence numbers wrap at 16 bits"
+            )]
+            let from_seq = until_seq.wrapping_sub(packets as u16);
+            (
+                u32::from(from_seq),
+                until_timestamp.wrapping_sub(packets * frames_per_packet),
+            )
+        });
+
+        tracing::debug!(
+            "Sending FLUSHBUFFERED (from={:?}, until seq={}, rtptime={})",
+            from,
+            until_seq,
+            until_timestamp
+        );
+        self.send_flush_buffered(None, from, u32::from(until_seq), until_timestamp)
+            .await
+    }
+
     /// Send RTP audio packet
     ///
     /// # Errors
@@ -2191,12 +3039,14 @@ impl ConnectionManager {
                     .map_err(|e| AirPlayError::RtspError {
                         message: format!("Failed to send buffered audio length: {e}"),
                         status_code: None,
+                        source: Some(Box::new(e)),
                     })?;
                 AsyncWriteExt::write_all(tcp_stream, packet)
                     .await
                     .map_err(|e| AirPlayError::RtspError {
                         message: format!("Failed to send buffered audio data: {e}"),
                         status_code: None,
+                        source: Some(Box::new(e)),
                     })?;
                 return Ok(());
             }
@@ -2210,6 +3060,7 @@ impl ConnectionManager {
                 .map_err(|e| AirPlayError::RtspError {
                     message: format!("Failed to send RTP audio: {e}"),
                     status_code: None,
+                    source: Some(Box::new(e)),
                 })?;
             Ok(())
         } else {
@@ -2270,9 +3121,7 @@ impl ConnectionManager {
             if let Some(s) = sockets.as_ref() {
                 (s.control.clone(), s.server_control_port)
             } else {
-                return Err(AirPlayError::Disconnected {
-                    device_name: "none".to_string(),
-                });
+                return Err(self.disconnected().await);
             }
         };
 
@@ -2283,7 +3132,7 @@ impl ConnectionManager {
                 .await
                 .map_err(|e| AirPlayError::IoError {
                     message: format!("Failed to send RTCP control packet: {e}"),
-                    source: Some(Box::new(std::io::Error::other(e))),
+                    source: Some(Box::new(e)),
                 })?;
             Ok(())
         } else {
@@ -2307,6 +3156,10 @@ impl ConnectionManager {
         rtp_timestamp: u32,
         sample_rate: u32,
     ) -> Result<(), AirPlayError> {
+        // A delayed group member plays an earlier sample at the same time
+        let rtp_timestamp =
+            Timeline::new(sample_rate).delayed_rtp_time(rtp_timestamp, self.playback_delay());
+
         // `ptp_timestamp` in TimeAnnounce must be in the MASTER's clock domain
         // (HomePod's custom epoch), not the local Unix epoch.  `master_now()`
         // returns `unix_now − epoch_offset` which is the master's current time
@@ -2379,6 +3232,7 @@ impl ConnectionManager {
                             AirPlayError::RtspError {
                                 message: format!("Failed to send NTP TimeAnnounce: {e}"),
                                 status_code: None,
+                                source: Some(Box::new(e)),
                             }
                         })?;
                     }
@@ -2421,6 +3275,7 @@ impl ConnectionManager {
                 .map_err(|e| AirPlayError::RtspError {
                     message: format!("Failed to send TimeAnnounce: {e}"),
                     status_code: None,
+                    source: Some(Box::new(e)),
                 })?;
         }
 
@@ -2429,6 +3284,10 @@ impl ConnectionManager {
 
     /// Send an arbitrary RTSP command
     ///
+    /// With [`AirPlayConfig::resync_on_state_error`], a command the device
+    /// rejects as invalid in its current state is retried once after
+    /// [`resync_stream`](Self::resync_stream).
+    ///
     /// # Errors
     ///
     /// Returns error if command creation or sending fails
@@ -2438,7 +3297,75 @@ impl ConnectionManager {
         body: Option<Vec<u8>>,
         content_type: Option<String>,
     ) -> Result<Vec<u8>, AirPlayError> {
-        let request = {
+        self.send_command_at(method, body, content_type, None).await
+    }
+
+    /// Send a `SET_PARAMETER` applying from RTP time `rtp_time`
+    ///
+    /// Metadata and artwork carry the RTP time of the track they describe
+    /// in `RTP-Info`, so the receiver shows them when that audio plays.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if command creation or sending fails
+    pub async fn send_parameter_at(
+        &self,
+        body: Vec<u8>,
+        content_type: &str,
+        rtp_time: Option<u32>,
+    ) -> Result<Vec<u8>, AirPlayError> {
+        self.send_command_at(
+            Method::SetParameter,
+            Some(body),
+            Some(content_type.to_string()),
+            rtp_time,
+        )
+        .await
+    }
+
+    async fn send_command_at(
+        &self,
+        method: Method,
+        body: Option<Vec<u8>>,
+        content_type: Option<String>,
+        rtp_time: Option<u32>,
+    ) -> Result<Vec<u8>, AirPlayError> {
+        match self
+            .send_command_once(method, body.clone(), content_type.clone(), rtp_time)
+            .await
+        {
+            Err(AirPlayError::RtspError {
+                status_code: Some(status),
+                message,
+                source: None,
+            }) if self.should_resync(method, status).await => {
+                tracing::warn!("{message}; setting the audio stream up again");
+                // Boxed: re-anchoring sends commands of its own
+                Box::pin(self.resync_stream()).await?;
+                self.send_command_once(method, body, content_type, rtp_time)
+                    .await
+            }
+            result => result,
+        }
+    }
+
+    /// Whether a command rejected with `status` is retried after a re-SETUP
+    async fn should_resync(&self, method: Method, status: u16) -> bool {
+        self.config.resync_on_state_error
+            && method != Method::Teardown
+            && StatusCode(status).is_state_error()
+            && !self.resyncing.load(std::sync::atomic::Ordering::Acquire)
+            && self.stream_setup.lock().await.is_some()
+    }
+
+    async fn send_command_once(
+        &self,
+        method: Method,
+        body: Option<Vec<u8>>,
+        content_type: Option<String>,
+        rtp_time: Option<u32>,
+    ) -> Result<Vec<u8>, AirPlayError> {
+        let mut request = {
             let mut session_guard = self.rtsp_session.lock().await;
             let session = session_guard
                 .as_mut()
@@ -2480,6 +3407,11 @@ impl ConnectionManager {
                 }
             }
         };
+        if let Some(rtp_time) = rtp_time {
+            request
+                .headers
+                .insert("RTP-Info", format!("rtptime={rtp_time}"));
+        }
 
         let response = self.send_rtsp_request(&request).await?;
 
@@ -2520,6 +3452,7 @@ impl ConnectionManager {
                     AirPlayError::RtspError {
                         message: e,
                         status_code: Some(response.status.as_u16()),
+                        source: None,
                     }
                 })?;
             }
@@ -2565,6 +3498,7 @@ impl ConnectionManager {
                     .map_err(|e| AirPlayError::RtspError {
                         message: e,
                         status_code: Some(response.status.as_u16()),
+                        source: None,
                     })?;
             }
         }
@@ -2572,6 +3506,28 @@ impl ConnectionManager {
         Ok(response.body)
     }
 
+    /// Send the `POST /feedback` keep-alive
+    ///
+    /// Returns the stream state the device reports, which is also
+    /// published as [`ConnectionEvent::Feedback`].
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the request fails or the device rejects it
+    pub async fn send_feedback(&self) -> Result<Vec<StreamFeedback>, AirPlayError> {
+        let body = self
+            .send_post_command(super::FEEDBACK_PATH, None, None)
+            .await?;
+        let streams = super::parse_feedback(&body);
+        if !streams.is_empty() {
+            // The streamer adapts bitrate and pacing to it
+            let _ = self.event_tx.send(ConnectionEvent::Feedback {
+                streams: streams.clone(),
+            });
+        }
+        Ok(streams)
+    }
+
     /// Send a GET request
     ///
     /// # Errors
@@ -2619,10 +3575,33 @@ impl ConnectionManager {
         &self,
         reason: DisconnectReason,
     ) -> Result<(), AirPlayError> {
+        self.close(reason, true).await;
+        Ok(())
+    }
+
+    /// Give the device up to another sender
+    ///
+    /// Emits [`ConnectionEvent::SessionPreempted`] and closes the session
+    /// without sending TEARDOWN, which the device no longer expects from us.
+    /// Does nothing if the session is already closed.
+    pub async fn yield_session(&self, by: Option<String>) {
+        if self.state().await == ConnectionState::Disconnected {
+            return;
+        }
+        tracing::info!(
+            "Session taken over by {}",
+            by.as_deref().unwrap_or("another sender")
+        );
+        self.send_event(ConnectionEvent::SessionPreempted { by: by.clone() });
+        self.close(DisconnectReason::Preempted { by }, false).await;
+    }
+
+    /// Close the session and its channels, then report `reason`
+    async fn close(&self, reason: DisconnectReason, send_teardown: bool) {
         let device = self.device.read().await.clone();
 
         // Send TEARDOWN if connected
-        if self.state().await == ConnectionState::Connected {
+        if send_teardown && self.state().await == ConnectionState::Connected {
             let request = {
                 let mut session = self.rtsp_session.lock().await;
                 session.as_mut().map(RtspSession::teardown_request)
@@ -2636,25 +3615,160 @@ impl ConnectionManager {
         // Stop PTP handler if running
         self.stop_ptp().await;
 
-        // Stop event channel drain task
-        if let Some(task) = self.event_task.lock().await.take() {
-            task.abort();
+        // Close the event channel
+        if let Some(channel) = self.event_channel.lock().await.take() {
+            channel.shutdown().await;
         }
 
         // Close connection
+        if let Some(channel) = self.rtsp_channel.lock().await.take() {
+            channel.shutdown().await;
+        }
         *self.stream.lock().await = None;
         *self.sockets.lock().await = None;
         *self.audio_tcp_stream.lock().await = None;
         *self.rtsp_session.lock().await = None;
         *self.session_keys.lock().await = None;
+        *self.timeline.write().await = None;
+        *self.stream_setup.lock().await = None;
 
         self.set_state(ConnectionState::Disconnected).await;
 
         if let Some(device) = device {
             self.send_event(ConnectionEvent::Disconnected { device, reason });
         }
+    }
 
-        Ok(())
+    /// Connect the TCP event channel and serve the device's events
+    ///
+    /// The device posts plist-encoded events (now-playing changes, volume set
+    /// by other controllers) as RTSP requests on this channel, encrypted with
+    /// the event keys of the pairing. Each is answered and published as a
+    /// [`ConnectionEvent::RemoteEvent`]. Reading also keeps the device's TCP
+    /// send buffer from stalling.
+    async fn connect_event_channel(&self, device_ip: std::net::IpAddr, port: u16) {
+        tracing::info!("Connecting event channel TCP to {}:{}", device_ip, port);
+        let event_connect_result = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            tokio::net::TcpStream::connect((device_ip, port)),
+        )
+        .await
+        .unwrap_or_else(|_| {
+            Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "event channel connect timed out after 5s",
+            ))
+        });
+        let event_stream = match event_connect_result {
+            Ok(stream) => stream,
+            Err(e) => {
+                tracing::warn!("Failed to connect event channel (port {}): {}", port, e);
+                return;
+            }
+        };
+        tracing::info!("✓ Event channel connected to port {}", port);
+
+        let secure = self.session_keys.lock().await.as_ref().map(|keys| {
+            crate::net::secure::HapSecureSession::new(
+                keys.event_encrypt_key.expose(),
+                keys.event_decrypt_key.expose(),
+            )
+        });
+        let events = self.event_tx.clone();
+        let handler: ServerRequestHandler = Arc::new(move |request: &RtspRequest| {
+            let event = crate::control::remote::RemoteEvent::from_request(request);
+            if let Some(event) = event {
+                tracing::debug!("Event channel: {:?}", event);
+                let _ = events.send(ConnectionEvent::RemoteEvent { event });
+            } else {
+                tracing::debug!(
+                    "Event channel: undecodable {} {} ({} bytes)",
+                    request.method.as_str(),
+                    request.uri,
+                    request.body.len()
+                );
+            }
+            StatusCode::OK
+        });
+
+        let local_addr = event_stream.local_addr().ok();
+        let (reader, writer) = event_stream.into_split();
+        let channel = RtspChannel::spawn(
+            reader,
+            writer,
+            secure,
+            local_addr,
+            Handlers {
+                request: Arc::new(std::sync::RwLock::new(Some(handler))),
+                ..Handlers::default()
+            },
+            None,
+        )
+        .with_request_timeout(self.config.request_timeout);
+        *self.event_channel.lock().await = Some(channel);
+    }
+
+    /// Time since the device last sent on the event channel
+    ///
+    /// `None` without an open event channel.
+    pub async fn event_channel_idle(&self) -> Option<std::time::Duration> {
+        self.event_channel
+            .lock()
+            .await
+            .as_ref()
+            .filter(|channel| !channel.is_closed())
+            .map(RtspChannel::idle_for)
+    }
+
+    /// Check the event channel is alive
+    ///
+    /// Devices post events and heartbeats on the event channel while a
+    /// session is healthy. If it has been silent for longer than `timeout`
+    /// (or has closed), the session is verified with OPTIONS on the control
+    /// connection. A session that answers is reported once as degraded
+    /// with [`ConnectionEvent::Degraded`]; the report is re-armed when the
+    /// device sends again.
+    ///
+    /// Returns whether the session is degraded.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the device does not answer OPTIONS, meaning the
+    /// session is gone.
+    pub async fn check_event_channel(
+        &self,
+        timeout: std::time::Duration,
+    ) -> Result<bool, AirPlayError> {
+        let (idle, closed) = {
+            let channel = self.event_channel.lock().await;
+            let Some(channel) = channel.as_ref() else {
+                return Ok(false);
+            };
+            (channel.idle_for(), channel.is_closed())
+        };
+
+        if !closed && idle <= timeout {
+            self.event_channel_degraded
+                .store(false, std::sync::atomic::Ordering::Relaxed);
+            return Ok(false);
+        }
+
+        tracing::debug!("Event channel silent for {idle:?}; verifying session with OPTIONS");
+        self.send_options().await?;
+
+        if !self
+            .event_channel_degraded
+            .swap(true, std::sync::atomic::Ordering::Relaxed)
+        {
+            let message = if closed {
+                "event channel closed by device".to_string()
+            } else {
+                format!("no events from device for {}s", idle.as_secs())
+            };
+            tracing::warn!("Connection degraded: {message}");
+            self.send_event(ConnectionEvent::Degraded { message });
+        }
+        Ok(true)
     }
 
     /// Set connection state and emit event
@@ -2685,17 +3799,6 @@ impl ConnectionManager {
         self.event_tx.subscribe()
     }
 
-    /// Determine if high resolution audio should be used.
-    async fn should_use_hires(&self) -> bool {
-        if !self.config.prefer_hires_audio {
-            return false;
-        }
-        let device_guard = self.device.read().await;
-        device_guard
-            .as_ref()
-            .is_some_and(|d| d.capabilities.supports_hires_audio)
-    }
-
     /// Determine if PTP should be used based on config and device capabilities.
     async fn should_use_ptp(&self) -> bool {
         match self.config.timing_protocol {
@@ -2723,7 +3826,7 @@ impl ConnectionManager {
     /// which would require changes throughout every send site.  Using IPv4 directly
     /// is correct and portable.  No `unwrap()` calls are used — `SocketAddr` is
     /// constructed directly and all error paths propagate via `?`.
-    fn bind_ptp_port(port: u16) -> std::io::Result<UdpSocket> {
+    pub(crate) fn bind_ptp_port(port: u16) -> std::io::Result<UdpSocket> {
         use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 
         use socket2::{Domain, Protocol, Socket, Type};
@@ -2866,7 +3969,7 @@ impl ConnectionManager {
 
         let handler_clock = clock.clone();
 
-        tokio::spawn(async move {
+        self.tasks.spawn(async move {
             let mut handler = PtpSlaveHandler::new(
                 ptp_event_socket,
                 ptp_general_socket,
@@ -2909,12 +4012,13 @@ impl ConnectionManager {
         );
     }
 
-    /// Stop the PTP master handler if running.
+    /// Stop the PTP handler and control socket listener if running.
     async fn stop_ptp(&self) {
         if let Some(tx) = self.ptp_shutdown_tx.lock().await.take() {
             let _ = tx.send(true);
             tracing::info!("PTP master handler shutdown signal sent");
         }
+        self.tasks.shutdown().await;
         *self.ptp_clock.lock().await = None;
         *self.ptp_active.write().await = false;
     }
@@ -2943,32 +4047,4 @@ impl ConnectionManager {
             false
         }
     }
-
-    fn parse_transport_ports(transport_header: &str) -> Result<(u16, u16, u16), AirPlayError> {
-        let mut server_audio_port = 0;
-        let mut server_ctrl_port = 0;
-        let mut server_time_port = 0;
-
-        for part in transport_header.split(';') {
-            if let Some((key, value)) = part.trim().split_once('=') {
-                if let Ok(port) = value.parse::<u16>() {
-                    match key {
-                        "server_port" => server_audio_port = port,
-                        "control_port" => server_ctrl_port = port,
-                        "timing_port" => server_time_port = port,
-                        _ => {}
-                    }
-                }
-            }
-        }
-
-        if server_audio_port == 0 {
-            return Err(AirPlayError::RtspError {
-                message: "Could not determine server audio port".to_string(),
-                status_code: None,
-            });
-        }
-
-        Ok((server_audio_port, server_ctrl_port, server_time_port))
-    }
 }
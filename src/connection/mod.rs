@@ -1,9 +1,17 @@
 //! Connection management
 
+mod channel;
+mod feedback;
 mod manager;
+mod probe;
+mod protocol_log;
 mod state;
 
+pub use channel::{InterleavedHandler, ServerRequestHandler};
+pub use feedback::{FEEDBACK_INTERVAL, FEEDBACK_PATH, StreamFeedback, parse_feedback};
 pub use manager::ConnectionManager;
+pub use probe::{identify_device, probe_device, probe_info};
+pub use protocol_log::ProtocolLog;
 pub use state::{ConnectionEvent, ConnectionState, ConnectionStats, DisconnectReason};
 
 #[cfg(test)]
@@ -0,0 +1,143 @@
+//! Direct device probing without mDNS
+
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::error::AirPlayError;
+use crate::net::{AsyncReadExt, AsyncWriteExt, Runtime, TcpStream};
+use crate::protocol::plist::PlistValue;
+use crate::protocol::rtsp::{RtspCodec, RtspRequest, RtspResponse, RtspSession};
+use crate::types::AirPlayDevice;
+
+/// Fetch the `GET /info` plist from a device at a known address
+///
+/// Uses a short-lived plain TCP connection, separate from any session, so it
+/// works before pairing.
+///
+/// # Errors
+///
+/// Returns error if the connection fails, the request times out, the device
+/// answers with a non-success status, or the body is not a plist.
+pub async fn probe_info(
+    ip: IpAddr,
+    port: u16,
+    timeout: Duration,
+) -> Result<PlistValue, AirPlayError> {
+    Runtime::timeout(timeout, fetch_info(ip, port))
+        .await
+        .map_err(|_| AirPlayError::ConnectionTimeout { duration: timeout })?
+}
+
+/// Build an [`AirPlayDevice`] for a known address from its `GET /info` response
+///
+/// # Errors
+///
+/// Returns error if [`probe_info`] fails.
+pub async fn probe_device(
+    ip: IpAddr,
+    port: u16,
+    timeout: Duration,
+) -> Result<AirPlayDevice, AirPlayError> {
+    let info = probe_info(ip, port, timeout).await?;
+    let mut device = AirPlayDevice::from_address(ip, port);
+    device.update_from_info(&info);
+    Ok(device)
+}
+
+/// Ask a device at a known address to identify itself
+///
+/// Sends `POST /identify` on a short-lived connection, which makes receivers
+/// that support it play a chime or flash their status light so a discovered
+/// entry can be matched to a physical speaker. No pairing is needed.
+///
+/// # Errors
+///
+/// Returns error if the connection fails, the request times out, or the
+/// device answers with a non-success status (typically when it does not
+/// support identification).
+pub async fn identify_device(ip: IpAddr, port: u16, timeout: Duration) -> Result<(), AirPlayError> {
+    Runtime::timeout(timeout, post_identify(ip, port))
+        .await
+        .map_err(|_| AirPlayError::ConnectionTimeout { duration: timeout })?
+}
+
+async fn post_identify(ip: IpAddr, port: u16) -> Result<(), AirPlayError> {
+    let request = RtspSession::new(&ip.to_string(), port).post_request(
+        "/identify",
+        "application/octet-stream",
+        Vec::new(),
+    );
+    let response = exchange(ip, port, &request).await?;
+    if !response.is_success() {
+        return Err(AirPlayError::RtspError {
+            message: format!("POST /identify failed: {}", response.reason),
+            status_code: Some(response.status.as_u16()),
+            source: None,
+        });
+    }
+    Ok(())
+}
+
+async fn fetch_info(ip: IpAddr, port: u16) -> Result<PlistValue, AirPlayError> {
+    let request = RtspSession::new(&ip.to_string(), port).get_request("/info");
+    let response = exchange(ip, port, &request).await?;
+
+    if !response.is_success() {
+        return Err(AirPlayError::RtspError {
+            message: format!("GET /info failed: {}", response.reason),
+            status_code: Some(response.status.as_u16()),
+            source: None,
+        });
+    }
+
+    response
+        .body_as_plist()
+        .map_err(|e| AirPlayError::RtspError {
+            message: format!("Invalid /info response: {e}"),
+            status_code: None,
+            source: Some(Box::new(e)),
+        })
+}
+
+/// Send one request on a fresh connection and read its response
+async fn exchange(
+    ip: IpAddr,
+    port: u16,
+    request: &RtspRequest,
+) -> Result<RtspResponse, AirPlayError> {
+    let addr = std::net::SocketAddr::new(ip, port);
+    let mut stream =
+        TcpStream::connect(addr)
+            .await
+            .map_err(|e| AirPlayError::ConnectionFailed {
+                device_name: addr.to_string(),
+                message: e.to_string(),
+                source: Some(Box::new(e)),
+            })?;
+
+    stream.write_all(&request.encode()).await?;
+
+    let mut codec = RtspCodec::new();
+    let mut buf = vec![0u8; 4096];
+    loop {
+        if let Some(response) = codec.decode().map_err(|e| AirPlayError::RtspError {
+            message: e.to_string(),
+            status_code: None,
+            source: Some(Box::new(e)),
+        })? {
+            return Ok(response);
+        }
+
+        let n = stream.read(&mut buf).await?;
+        if n == 0 {
+            return Err(AirPlayError::Disconnected {
+                device_name: addr.to_string(),
+            });
+        }
+        codec.feed(&buf[..n]).map_err(|e| AirPlayError::RtspError {
+            message: e.to_string(),
+            status_code: None,
+            source: Some(Box::new(e)),
+        })?;
+    }
+}
@@ -0,0 +1,100 @@
+//! Recent RTSP exchanges kept for diagnostics
+//!
+//! Each request and response is kept as its start line and headers; bodies
+//! are reduced to their length. Headers carrying credentials or identifiers
+//! are redacted so the log can be attached to a bug report as-is.
+
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use crate::protocol::rtsp::{Headers, RtspRequest, RtspResponse};
+
+/// Headers whose values are never logged
+const REDACTED_HEADERS: [&str; 8] = [
+    "Authorization",
+    "WWW-Authenticate",
+    "Apple-Challenge",
+    "Apple-Response",
+    "Active-Remote",
+    "DACP-ID",
+    "X-Apple-Device-ID",
+    "X-Apple-Client-Name",
+];
+
+/// Bounded log of the latest RTSP messages on a connection
+#[derive(Debug)]
+pub struct ProtocolLog {
+    started: Instant,
+    capacity: usize,
+    entries: Mutex<VecDeque<String>>,
+}
+
+impl ProtocolLog {
+    /// Messages kept by default
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    /// Create a log keeping the latest `capacity` messages
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            capacity,
+            entries: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// Record a request sent to the device
+    pub fn record_request(&self, request: &RtspRequest) {
+        let line = format!(">> {} {}", request.method.as_str(), request.uri);
+        self.push(&line, &request.headers, request.body.len());
+    }
+
+    /// Record a response received from the device
+    pub fn record_response(&self, response: &RtspResponse) {
+        let line = format!("<< {} {}", response.status.as_u16(), response.reason);
+        self.push(&line, &response.headers, response.body.len());
+    }
+
+    /// Logged messages, oldest first
+    #[must_use]
+    pub fn entries(&self) -> Vec<String> {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    fn push(&self, line: &str, headers: &Headers, body_len: usize) {
+        let mut entry = format!("[{:>10.3}s] {line}", self.started.elapsed().as_secs_f64());
+        for (name, value) in headers.iter() {
+            let value = if REDACTED_HEADERS
+                .iter()
+                .any(|redacted| redacted.eq_ignore_ascii_case(name))
+            {
+                "<redacted>"
+            } else {
+                value
+            };
+            let _ = write!(entry, "\n    {name}: {value}");
+        }
+        if body_len > 0 {
+            let _ = write!(entry, "\n    ({body_len} byte body)");
+        }
+
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() >= self.capacity {
+            entries.pop_front();
+        }
+        entries.push_back(entry);
+    }
+}
+
+impl Default for ProtocolLog {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_CAPACITY)
+    }
+}
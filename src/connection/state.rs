@@ -2,6 +2,8 @@
 
 use std::time::Instant;
 
+use crate::control::remote::RemoteEvent;
+use crate::protocol::rtsp::RtspRequest;
 use crate::types::AirPlayDevice;
 
 /// Connection state
@@ -87,6 +89,32 @@ pub enum ConnectionEvent {
         /// Whether the error is recoverable
         recoverable: bool,
     },
+    /// Request sent by the receiver on the control connection
+    ServerRequest {
+        /// The request, already answered
+        request: RtspRequest,
+    },
+    /// Event posted by the device on the event channel
+    RemoteEvent {
+        /// The decoded event
+        event: RemoteEvent,
+    },
+    /// Another sender took over the device; the session is being closed
+    /// without a TEARDOWN of our own
+    SessionPreempted {
+        /// Name of the sender that took over, if known
+        by: Option<String>,
+    },
+    /// Session still answers but the event channel went silent
+    Degraded {
+        /// What was detected
+        message: String,
+    },
+    /// Stream state reported in a `/feedback` response
+    Feedback {
+        /// Reported streams
+        streams: Vec<super::StreamFeedback>,
+    },
     /// Retransmit request received
     RetransmitRequest {
         /// Starting sequence number
@@ -111,6 +139,11 @@ pub enum DisconnectReason {
     ProtocolError(String),
     /// Timeout
     Timeout,
+    /// Another sender took over the device
+    Preempted {
+        /// Name of the sender that took over, if known
+        by: Option<String>,
+    },
 }
 
 /// Connection statistics
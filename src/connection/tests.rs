@@ -25,6 +25,70 @@ fn test_connection_stats() {
     assert_eq!(stats.bytes_received, 200);
 }
 
+#[tokio::test]
+async fn test_resync_stream_requires_audio_stream() {
+    use crate::connection::ConnectionManager;
+    use crate::error::AirPlayError;
+    use crate::types::AirPlayConfig;
+
+    let config = AirPlayConfig::builder().resync_on_state_error(true).build();
+    let manager = ConnectionManager::new(config);
+    assert!(matches!(
+        manager.resync_stream().await,
+        Err(AirPlayError::InvalidState { .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_yield_session_when_disconnected_is_silent() {
+    use crate::connection::ConnectionManager;
+    use crate::types::AirPlayConfig;
+
+    let manager = ConnectionManager::new(AirPlayConfig::default());
+    let mut events = manager.subscribe();
+    manager
+        .yield_session(Some("Other sender".to_string()))
+        .await;
+    assert!(events.try_recv().is_err());
+}
+
+#[test]
+fn test_protocol_log_redacts_and_is_bounded() {
+    use crate::connection::ProtocolLog;
+    use crate::protocol::rtsp::{Headers, Method, RtspRequest, RtspResponse, StatusCode};
+
+    let log = ProtocolLog::new(2);
+    let request = RtspRequest::builder(Method::Options, "*")
+        .header("CSeq", "1")
+        .header("Apple-Challenge", "c2VjcmV0")
+        .body(vec![0; 10])
+        .build();
+    log.record_request(&request);
+    let mut headers = Headers::new();
+    headers.insert("CSeq", "1");
+    log.record_response(&RtspResponse {
+        version: "RTSP/1.0".to_string(),
+        status: StatusCode::OK,
+        reason: "OK".to_string(),
+        headers,
+        body: Vec::new(),
+    });
+
+    let entries = log.entries();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].contains(">> OPTIONS *"));
+    assert!(entries[0].contains("CSeq: 1"));
+    assert!(entries[0].contains("Apple-Challenge: <redacted>"));
+    assert!(!entries[0].contains("c2VjcmV0"));
+    assert!(entries[0].contains("(10 byte body)"));
+    assert!(entries[1].contains("<< 200 OK"));
+
+    log.record_request(&request);
+    let entries = log.entries();
+    assert_eq!(entries.len(), 2);
+    assert!(entries[0].contains("<< 200 OK"));
+}
+
 #[cfg(test)]
 mod ptp_integration_tests {
     use std::collections::HashMap;
@@ -219,3 +283,351 @@ mod parsing_tests {
         assert_eq!(server_time_port, 6002);
     }
 }
+
+#[cfg(test)]
+mod rtsp_channel_tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt, DuplexStream};
+    use tokio::sync::broadcast;
+
+    use crate::connection::ConnectionEvent;
+    use crate::connection::channel::{Handlers, RtspChannel, SharedHandler};
+    use crate::error::AirPlayError;
+    use crate::protocol::rtsp::{
+        InterleavedFrame, Method, RtspCodec, RtspRequest, RtspServerCodec, StatusCode,
+    };
+
+    fn channel(
+        handler: SharedHandler,
+    ) -> (
+        RtspChannel,
+        DuplexStream,
+        broadcast::Receiver<ConnectionEvent>,
+    ) {
+        let (client, server) = tokio::io::duplex(4096);
+        let (reader, writer) = tokio::io::split(client);
+        let (events, rx) = broadcast::channel(8);
+        let handlers = Handlers {
+            request: handler,
+            ..Handlers::default()
+        };
+        let channel = RtspChannel::spawn(reader, writer, None, None, handlers, Some(events));
+        (channel, server, rx)
+    }
+
+    fn request(method: Method, cseq: u32) -> RtspRequest {
+        RtspRequest::builder(method, "rtsp://10.0.0.2/1")
+            .cseq(cseq)
+            .build()
+    }
+
+    async fn read_requests(server: &mut DuplexStream, count: usize) -> Vec<RtspRequest> {
+        let mut codec = RtspServerCodec::new();
+        let mut requests = Vec::new();
+        let mut buf = [0u8; 1024];
+        while requests.len() < count {
+            let n = server.read(&mut buf).await.unwrap();
+            codec.feed(&buf[..n]);
+            while let Some(request) = codec.decode().unwrap() {
+                requests.push(request);
+            }
+        }
+        requests
+    }
+
+    #[tokio::test]
+    async fn test_responses_are_matched_by_cseq() {
+        let (channel, mut server, _events) = channel(SharedHandler::default());
+        let channel = Arc::new(channel);
+
+        let first = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request(&request(Method::Options, 1)).await }
+        });
+        let second = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request(&request(Method::GetParameter, 2)).await }
+        });
+        assert_eq!(read_requests(&mut server, 2).await.len(), 2);
+
+        // Answer out of order, with a stale response first
+        server
+            .write_all(
+                b"RTSP/1.0 200 OK\r\nCSeq: 99\r\n\r\n\
+                  RTSP/1.0 404 Not Found\r\nCSeq: 2\r\n\r\n\
+                  RTSP/1.0 200 OK\r\nCSeq: 1\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let first = first.await.unwrap().unwrap();
+        let second = second.await.unwrap().unwrap();
+        assert_eq!(first.status, StatusCode::OK);
+        assert_eq!(first.cseq(), Some(1));
+        assert_eq!(second.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_server_request_is_answered_and_published() {
+        let handler = SharedHandler::default();
+        *handler.write().unwrap() = Some(Arc::new(|request: &RtspRequest| {
+            if request.method == Method::SetPeers {
+                StatusCode::OK
+            } else {
+                StatusCode::NOT_IMPLEMENTED
+            }
+        }));
+        let (_channel, mut server, mut events) = channel(handler);
+
+        server
+            .write_all(
+                b"SETPEERS rtsp://10.0.0.1/1 RTSP/1.0\r\nCSeq: 5\r\n\r\n\
+                  FROBNICATE * RTSP/1.0\r\nCSeq: 6\r\n\r\n",
+            )
+            .await
+            .unwrap();
+
+        let mut codec = RtspCodec::new();
+        let mut responses = Vec::new();
+        let mut buf = [0u8; 1024];
+        while responses.len() < 2 {
+            let n = server.read(&mut buf).await.unwrap();
+            codec.feed(&buf[..n]).unwrap();
+            while let Some(response) = codec.decode().unwrap() {
+                responses.push(response);
+            }
+        }
+        assert_eq!(responses[0].status, StatusCode::OK);
+        assert_eq!(responses[0].cseq(), Some(5));
+        assert_eq!(responses[1].status, StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(responses[1].cseq(), Some(6));
+
+        let event = tokio::time::timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        let ConnectionEvent::ServerRequest { request } = event else {
+            panic!("expected a server request event");
+        };
+        assert_eq!(request.method, Method::SetPeers);
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_event_channel() {
+        use crate::net::secure::HapSecureSession;
+        use crate::protocol::pairing::SessionKeys;
+
+        let keys = SessionKeys::derive(&[7u8; 32], [0u8; 32]).unwrap();
+        let ours = HapSecureSession::new(
+            keys.event_encrypt_key.expose(),
+            keys.event_decrypt_key.expose(),
+        );
+        // The device writes with the key we read with
+        let mut device = HapSecureSession::new(
+            keys.event_decrypt_key.expose(),
+            keys.event_encrypt_key.expose(),
+        );
+
+        let (received_tx, mut received) = tokio::sync::mpsc::unbounded_channel();
+        let handler = SharedHandler::default();
+        *handler.write().unwrap() = Some(Arc::new(move |request: &RtspRequest| {
+            let _ = received_tx.send(request.body.clone());
+            StatusCode::OK
+        }));
+
+        let (client, mut server) = tokio::io::duplex(4096);
+        let (reader, writer) = tokio::io::split(client);
+        let handlers = Handlers {
+            request: handler,
+            ..Handlers::default()
+        };
+        let _channel = RtspChannel::spawn(reader, writer, Some(ours), None, handlers, None);
+
+        let mut message =
+            b"POST /command RTSP/1.0\r\nCSeq: 3\r\nContent-Length: 4\r\n\r\n".to_vec();
+        message.extend_from_slice(b"body");
+        server
+            .write_all(&device.encrypt(&message).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(received.recv().await.unwrap(), b"body");
+
+        let mut encrypted = Vec::new();
+        let mut buf = [0u8; 1024];
+        let response = loop {
+            let n = server.read(&mut buf).await.unwrap();
+            encrypted.extend_from_slice(&buf[..n]);
+            if let Ok((plain, _)) = device.decrypt_block(&encrypted) {
+                let mut codec = RtspCodec::new();
+                codec.feed(&plain).unwrap();
+                break codec.decode().unwrap().unwrap();
+            }
+        };
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.cseq(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_unanswered_request_times_out() {
+        let (channel, mut server, _events) = channel(SharedHandler::default());
+        let channel = Arc::new(channel.with_request_timeout(Duration::from_millis(50)));
+
+        let waiting = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request(&request(Method::Options, 1)).await }
+        });
+        read_requests(&mut server, 1).await;
+
+        let result = waiting.await.unwrap();
+        assert!(matches!(result, Err(AirPlayError::Timeout)));
+        assert!(!channel.is_closed());
+
+        // A late response finds no waiter and is discarded; the channel
+        // keeps serving requests
+        server
+            .write_all(b"RTSP/1.0 200 OK\r\nCSeq: 1\r\n\r\n")
+            .await
+            .unwrap();
+        let next = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request(&request(Method::Options, 2)).await }
+        });
+        read_requests(&mut server, 1).await;
+        server
+            .write_all(b"RTSP/1.0 200 OK\r\nCSeq: 2\r\n\r\n")
+            .await
+            .unwrap();
+        assert_eq!(next.await.unwrap().unwrap().cseq(), Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_closed_connection_fails_waiting_requests() {
+        let (channel, mut server, _events) = channel(SharedHandler::default());
+        let channel = Arc::new(channel);
+
+        let waiting = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request(&request(Method::Options, 1)).await }
+        });
+        read_requests(&mut server, 1).await;
+        drop(server);
+
+        assert!(waiting.await.unwrap().is_err());
+        assert!(channel.request(&request(Method::Options, 2)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_interleaved_frames_in_both_directions() {
+        let (client, mut server) = tokio::io::duplex(4096);
+        let (reader, writer) = tokio::io::split(client);
+        let (frames_tx, mut frames) = tokio::sync::mpsc::unbounded_channel();
+        let handlers = Handlers::default();
+        *handlers.interleaved.write().unwrap() = Some(Arc::new(move |frame| {
+            let _ = frames_tx.send(frame);
+        }));
+        let channel = RtspChannel::spawn(reader, writer, None, None, handlers, None);
+        let channel = Arc::new(channel);
+
+        let rtcp = InterleavedFrame {
+            channel: 1,
+            payload: b"\x80\xd4sync".to_vec(),
+        };
+        let waiting = tokio::spawn({
+            let channel = channel.clone();
+            async move { channel.request(&request(Method::Options, 1)).await }
+        });
+        read_requests(&mut server, 1).await;
+
+        let mut data = rtcp.encode().unwrap();
+        data.extend_from_slice(b"RTSP/1.0 200 OK\r\nCSeq: 1\r\n\r\n");
+        server.write_all(&data).await.unwrap();
+        assert_eq!(frames.recv().await, Some(rtcp));
+        assert_eq!(waiting.await.unwrap().unwrap().status, StatusCode::OK);
+
+        let audio = InterleavedFrame {
+            channel: 0,
+            payload: vec![0x80, 0x60, 0, 1],
+        };
+        channel.send_interleaved(&audio).await.unwrap();
+        let mut buf = [0u8; 8];
+        server.read_exact(&mut buf).await.unwrap();
+        assert_eq!(buf.as_slice(), audio.encode().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_idle_time_tracks_received_data() {
+        let (channel, mut server, mut events) = channel(SharedHandler::default());
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(channel.idle_for() >= Duration::from_millis(50));
+
+        server
+            .write_all(b"POST /command RTSP/1.0\r\nCSeq: 1\r\n\r\n")
+            .await
+            .unwrap();
+        events.recv().await.unwrap();
+        assert!(channel.idle_for() < Duration::from_millis(50));
+        assert!(!channel.is_closed());
+
+        drop(server);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(channel.is_closed());
+    }
+}
+
+#[cfg(test)]
+mod feedback_tests {
+    use crate::connection::{StreamFeedback, parse_feedback};
+    use crate::protocol::plist::{DictBuilder, PlistValue, encode};
+
+    #[test]
+    fn test_parse_feedback_streams() {
+        let body = encode(
+            &DictBuilder::new()
+                .insert(
+                    "streams",
+                    PlistValue::Array(vec![
+                        DictBuilder::new()
+                            .insert("type", 103i64)
+                            .insert("sr", 44100.0)
+                            .insert("audioLatency", 11025i64)
+                            .insert("bufferedAudioFrames", 88200i64)
+                            .insert("lostPackets", 3i64)
+                            .build(),
+                        DictBuilder::new().insert("type", 96i64).build(),
+                    ]),
+                )
+                .build(),
+        )
+        .unwrap();
+
+        let streams = parse_feedback(&body);
+        assert_eq!(
+            streams,
+            vec![
+                StreamFeedback {
+                    stream_type: Some(103),
+                    sample_rate: Some(44100.0),
+                    latency_frames: Some(11025),
+                    buffered_frames: Some(88200),
+                    lost_packets: Some(3),
+                },
+                StreamFeedback {
+                    stream_type: Some(96),
+                    ..StreamFeedback::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_feedback_without_streams() {
+        assert!(parse_feedback(&[]).is_empty());
+        assert!(parse_feedback(b"not a plist").is_empty());
+        let empty = encode(&DictBuilder::new().build()).unwrap();
+        assert!(parse_feedback(&empty).is_empty());
+    }
+}
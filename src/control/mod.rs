@@ -2,6 +2,7 @@
 
 pub mod playback;
 pub mod queue;
+pub mod remote;
 pub mod volume;
 
 #[cfg(test)]
@@ -9,4 +10,7 @@ mod tests;
 
 pub use playback::{PlaybackController, PlaybackProgress, ShuffleMode};
 pub use queue::PlaybackQueue;
-pub use volume::{DeviceVolume, GroupVolumeController, Volume, VolumeController};
+pub use remote::{MediaCommand, NowPlayingInfo, RemoteEvent};
+pub use volume::{
+    DEFAULT_GROUP_VOLUME_STEP, DeviceVolume, GroupVolumeController, Volume, VolumeController,
+};
@@ -6,10 +6,13 @@ use std::time::Duration;
 use tokio::sync::RwLock;
 
 use crate::connection::ConnectionManager;
+use crate::control::remote::{COMMAND_PATH, MediaCommand};
 use crate::error::AirPlayError;
-use crate::protocol::daap::{DmapProgress, TrackMetadata};
+use crate::protocol::daap::{Artwork, DmapProgress, TrackMetadata};
 use crate::protocol::plist::DictBuilder;
+use crate::protocol::ptp::timestamp::PtpTimestamp;
 use crate::protocol::rtsp::Method;
+use crate::streaming::Timeline;
 use crate::types::{PlaybackState, RepeatMode};
 
 /// Shuffle mode
@@ -32,6 +35,8 @@ pub struct PlaybackController {
     repeat_mode: RwLock<RepeatMode>,
     /// Current shuffle mode
     shuffle_mode: RwLock<ShuffleMode>,
+    /// RTP time the last metadata was sent for
+    metadata_rtp_time: RwLock<Option<u32>>,
 }
 
 impl PlaybackController {
@@ -43,6 +48,7 @@ impl PlaybackController {
             state: RwLock::new(PlaybackState::default()),
             repeat_mode: RwLock::new(RepeatMode::Off),
             shuffle_mode: RwLock::new(ShuffleMode::Off),
+            metadata_rtp_time: RwLock::new(None),
         }
     }
 
@@ -106,6 +112,7 @@ impl PlaybackController {
                 crate::protocol::plist::encode(&body).map_err(|e| AirPlayError::RtspError {
                     message: format!("Failed to encode plist: {e}"),
                     status_code: None,
+                    source: Some(Box::new(e)),
                 })?;
 
             self.connection
@@ -115,6 +122,7 @@ impl PlaybackController {
                     Some("application/x-apple-binary-plist".to_string()),
                 )
                 .await?;
+            self.connection.update_timeline(|t| t.set_rate(1.0)).await;
             state.is_playing = true;
         }
 
@@ -139,6 +147,7 @@ impl PlaybackController {
             crate::protocol::plist::encode(&body).map_err(|e| AirPlayError::RtspError {
                 message: format!("Failed to encode plist: {e}"),
                 status_code: None,
+                source: Some(Box::new(e)),
             })?;
 
         self.connection
@@ -148,6 +157,7 @@ impl PlaybackController {
                 Some("application/x-apple-binary-plist".to_string()),
             )
             .await?;
+        self.connection.update_timeline(|t| t.set_rate(0.0)).await;
         state.is_playing = false;
 
         Ok(())
@@ -216,6 +226,48 @@ impl PlaybackController {
         Ok(())
     }
 
+    /// Seek a running stream
+    ///
+    /// The streamer has already moved its source: the packet with sequence
+    /// number `seq` and RTP timestamp `timestamp` is the first carrying audio
+    /// from `position`. The receiver drops what it queued before that packet
+    /// (FLUSHBUFFERED for buffered streams, FLUSH otherwise), and a playing
+    /// buffered stream is re-anchored so `timestamp` renders now.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the receiver rejects the flush or anchor.
+    pub async fn seek_stream(
+        &self,
+        position: Duration,
+        seq: u16,
+        timestamp: u32,
+        frames_per_packet: u32,
+    ) -> Result<(), AirPlayError> {
+        let buffered = self.connection.is_buffered().await;
+        if buffered {
+            self.connection
+                .flush_buffered_until(seq, timestamp, frames_per_packet)
+                .await?;
+        } else {
+            self.connection.send_flush(seq, timestamp).await?;
+        }
+
+        let mut state = self.state.write().await;
+        if buffered && state.is_playing {
+            self.connection
+                .send_set_rate_anchor_at(1.0, timestamp, position)
+                .await?;
+        } else {
+            // Anchored when playback resumes
+            self.connection
+                .update_timeline(|t| t.anchor(timestamp, position, None))
+                .await;
+        }
+        state.position_secs = position.as_secs_f64();
+        Ok(())
+    }
+
     /// Seek relative to current position
     ///
     /// # Errors
@@ -328,6 +380,7 @@ impl PlaybackController {
             disc_number: metadata.disc_number,
             genre: metadata.genre,
             content_id: None,
+            replay_gain: None,
         });
         Ok(())
     }
@@ -351,52 +404,118 @@ impl PlaybackController {
 
     /// Set artwork
     ///
+    /// Sent at the RTP time of the last metadata, so the receiver shows
+    /// both for the same track.
+    ///
     /// # Errors
     ///
     /// Returns error if network fails
-    pub async fn set_artwork(&self, data: &[u8], mime_type: &str) -> Result<(), AirPlayError> {
+    pub async fn set_artwork(&self, artwork: &Artwork) -> Result<(), AirPlayError> {
+        let rtp_time = match *self.metadata_rtp_time.read().await {
+            Some(rtp_time) => Some(rtp_time),
+            None => self.current_rtp_time().await,
+        };
         self.connection
-            .send_command(
-                Method::SetParameter,
-                Some(data.to_vec()),
-                Some(mime_type.to_string()),
-            )
+            .send_parameter_at(artwork.data.clone(), artwork.mime_type(), rtp_time)
             .await?;
         Ok(())
     }
 
+    /// Progress of the stream at the sample playing now
+    ///
+    /// The position is read from the timeline against the PTP clock when
+    /// one is synchronized, and is held back to `sent_rtp_time`, the RTP
+    /// time of the next packet to send, so it never runs ahead of the audio
+    /// actually streamed. `None` if no stream is running.
+    pub(crate) async fn stream_progress(
+        &self,
+        sent_rtp_time: u32,
+        duration: Option<Duration>,
+    ) -> Option<DmapProgress> {
+        let timeline = self.connection.timeline().await?;
+        let position = match self.connection.get_ptp_network_time().await {
+            Some((secs, frac, _)) => {
+                let nanos = (u128::from(frac) * 1_000_000_000) >> 64;
+                timeline.position_at_network_time(PtpTimestamp::new(
+                    secs,
+                    u32::try_from(nanos).unwrap_or(0),
+                ))
+            }
+            None => timeline.position(),
+        };
+        let mut position = position.min(timeline.position_at_rtp(sent_rtp_time));
+        if let Some(duration) = duration {
+            position = position.min(duration);
+        }
+        Some(DmapProgress::from_timeline(&timeline, position, duration))
+    }
+
+    /// RTP time of the sample playing now, if a stream is running
+    async fn current_rtp_time(&self) -> Option<u32> {
+        let timeline = self.connection.timeline().await?;
+        Some(timeline.rtp_time_at(timeline.position()))
+    }
+
     /// Internal: send scrub command
     async fn send_scrub(&self, position: f64) -> Result<(), AirPlayError> {
-        // AirPlay 2 uses progress parameter for scrub
-        // We need a base RTP timestamp. For now we use a dummy one if not provided,
-        // but high-quality implementations should track the actual RTP base.
-        let base_rtp: u32 = 0;
-        #[allow(
-            clippy::cast_possible_truncation,
-            clippy::cast_sign_loss,
-            reason = "Samples fit in u32"
-        )]
-        let pos_samples = (position * 44100.0) as u32;
-        // We don't know duration here, so we use current for end as well or a large value
-        let progress = DmapProgress::new(
-            base_rtp,
-            base_rtp.wrapping_add(pos_samples),
-            base_rtp.wrapping_add(pos_samples),
-        );
-
-        self.set_progress(progress).await
+        // AirPlay 2 uses progress parameter for scrub, expressed in the
+        // stream's RTP time
+        let position = Duration::from_secs_f64(position.max(0.0));
+        let timeline = self
+            .connection
+            .timeline()
+            .await
+            .unwrap_or_else(|| Timeline::new(44100));
+        // We don't know duration here, so the end is the target position
+        let progress = DmapProgress::from_timeline(&timeline, position, None);
+
+        self.set_progress(progress).await?;
+        self.connection.update_timeline(|t| t.seek(position)).await;
+        Ok(())
     }
 
     /// Internal: send metadata command
     async fn send_metadata(&self, metadata: TrackMetadata) -> Result<(), AirPlayError> {
         let body = metadata.encode_dmap();
+        let rtp_time = self.current_rtp_time().await;
         self.connection
-            .send_command(
-                Method::SetParameter,
-                Some(body),
-                Some("application/x-dmap-tagged".to_string()),
+            .send_parameter_at(body, "application/x-dmap-tagged", rtp_time)
+            .await?;
+        *self.metadata_rtp_time.write().await = rtp_time;
+        Ok(())
+    }
+
+    /// Send a `MediaRemote` transport command
+    ///
+    /// Controls whatever the device is playing, including its own content,
+    /// without streaming audio from this sender.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the command cannot be encoded or the device rejects it
+    pub async fn send_media_command(&self, command: MediaCommand) -> Result<(), AirPlayError> {
+        let encoded = crate::protocol::plist::encode(&command.to_plist()).map_err(|e| {
+            AirPlayError::RtspError {
+                message: format!("Failed to encode plist: {e}"),
+                status_code: None,
+                source: Some(Box::new(e)),
+            }
+        })?;
+        self.connection
+            .send_post_command(
+                COMMAND_PATH,
+                Some(encoded),
+                Some("application/x-apple-binary-plist".to_string()),
             )
             .await?;
+
+        let mut state = self.state.write().await;
+        match command {
+            MediaCommand::Play => state.is_playing = true,
+            MediaCommand::Pause | MediaCommand::Stop => state.is_playing = false,
+            MediaCommand::TogglePlayPause => state.is_playing = !state.is_playing,
+            _ => {}
+        }
         Ok(())
     }
 
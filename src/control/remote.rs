@@ -0,0 +1,225 @@
+//! `MediaRemote` transport commands
+//!
+//! Apple TVs and `HomePods` playing their own content (Apple Music, a TV app)
+//! accept transport commands from a connected sender even when the sender
+//! streams no audio. Commands are posted as a binary plist to `/command` on
+//! the session's RTSP connection, typically one set up in remote-control-only
+//! mode (see [`AirPlayConfig::remote_control_only`]).
+//!
+//! In the other direction, the device posts events (now-playing changes,
+//! volume set by another controller) on the event channel opened during
+//! SETUP; these are decoded into [`RemoteEvent`]s. A TEARDOWN from the
+//! device, or an event announcing another sender's session, means the device
+//! was taken over and is decoded as [`RemoteEvent::SessionPreempted`].
+//!
+//! [`AirPlayConfig::remote_control_only`]: crate::types::AirPlayConfig::remote_control_only
+
+use std::time::Duration;
+
+use crate::protocol::plist::{DictBuilder, PlistValue};
+use crate::protocol::rtsp::{Method, RtspRequest};
+
+/// Endpoint `MediaRemote` commands are posted to
+pub const COMMAND_PATH: &str = "/command";
+
+/// Event types announcing that another sender took over the session
+const PREEMPTION_EVENTS: [&str; 2] = ["sessionPreempted", "updateSessionOwner"];
+
+/// Headers naming the sender on a TEARDOWN sent by the device, in order of
+/// preference
+const SENDER_NAME_HEADERS: [&str; 2] = ["X-Apple-Client-Name", "User-Agent"];
+
+/// A `MediaRemote` transport command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MediaCommand {
+    /// Start or resume playback
+    Play,
+    /// Pause playback
+    Pause,
+    /// Toggle between play and pause
+    TogglePlayPause,
+    /// Stop playback
+    Stop,
+    /// Skip to the next track
+    NextTrack,
+    /// Go back to the previous track
+    PreviousTrack,
+    /// Skip forward by an interval
+    SkipForward(Duration),
+    /// Skip backward by an interval
+    SkipBackward(Duration),
+    /// Seek to an absolute position in the current item
+    Seek(Duration),
+    /// Cycle to the next shuffle mode
+    AdvanceShuffleMode,
+    /// Cycle to the next repeat mode
+    AdvanceRepeatMode,
+    /// Change the playback rate (1.0 = normal speed)
+    SetPlaybackRate(f32),
+}
+
+impl MediaCommand {
+    /// `MediaRemote` command number
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        match self {
+            Self::Play => 1,
+            Self::Pause => 2,
+            Self::TogglePlayPause => 3,
+            Self::Stop => 4,
+            Self::NextTrack => 5,
+            Self::PreviousTrack => 6,
+            Self::AdvanceShuffleMode => 7,
+            Self::AdvanceRepeatMode => 8,
+            Self::SkipForward(_) => 18,
+            Self::SkipBackward(_) => 19,
+            Self::SetPlaybackRate(_) => 20,
+            Self::Seek(_) => 45,
+        }
+    }
+
+    /// Build the `/command` request body
+    #[must_use]
+    pub fn to_plist(&self) -> PlistValue {
+        let mut params = DictBuilder::new().insert("command", i64::from(self.id()));
+        params = match *self {
+            Self::SkipForward(interval) | Self::SkipBackward(interval) => {
+                params.insert("skipInterval", interval.as_secs_f64())
+            }
+            Self::Seek(position) => params.insert("playbackPosition", position.as_secs_f64()),
+            Self::SetPlaybackRate(rate) => params.insert("playbackRate", f64::from(rate)),
+            _ => params,
+        };
+
+        DictBuilder::new()
+            .insert("type", "sendMediaRemoteCommand")
+            .insert("params", params.build())
+            .build()
+    }
+}
+
+/// `MediaRemote` key prefix of now-playing info entries
+const NOW_PLAYING_PREFIX: &str = "kMRMediaRemoteNowPlayingInfo";
+
+/// An event posted by the device on the event channel
+#[derive(Debug, Clone, PartialEq)]
+pub enum RemoteEvent {
+    /// The item playing on the device changed
+    NowPlaying(NowPlayingInfo),
+    /// The device volume was changed, e.g. by another controller
+    VolumeChanged {
+        /// New volume level (0.0 - 1.0)
+        volume: f32,
+    },
+    /// Another sender took over the device
+    SessionPreempted {
+        /// Name of the sender that took over, if the device gave one
+        by: Option<String>,
+    },
+    /// Event of a type not decoded here
+    Other {
+        /// Value of the event's `type` key
+        kind: String,
+        /// The event's `params`
+        params: Option<PlistValue>,
+    },
+}
+
+/// Now-playing information sent by the device
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NowPlayingInfo {
+    /// Item title
+    pub title: Option<String>,
+    /// Artist name
+    pub artist: Option<String>,
+    /// Album name
+    pub album: Option<String>,
+    /// Item duration in seconds
+    pub duration: Option<f64>,
+    /// Elapsed time in seconds
+    pub elapsed: Option<f64>,
+    /// Playback rate (0.0 when paused)
+    pub playback_rate: Option<f64>,
+}
+
+impl RemoteEvent {
+    /// Decode a request sent by the device
+    ///
+    /// A TEARDOWN becomes [`RemoteEvent::SessionPreempted`], naming the new
+    /// sender from its `X-Apple-Client-Name` or `User-Agent` header; other
+    /// requests are decoded from their plist body with
+    /// [`RemoteEvent::from_plist`].
+    #[must_use]
+    pub fn from_request(request: &RtspRequest) -> Option<Self> {
+        if request.method == Method::Teardown {
+            let by = SENDER_NAME_HEADERS
+                .iter()
+                .find_map(|name| request.headers.get(name))
+                .map(str::to_string);
+            return Some(Self::SessionPreempted { by });
+        }
+        let body = crate::protocol::plist::decode(&request.body).ok()?;
+        Self::from_plist(&body)
+    }
+
+    /// Decode an event from the body of an event channel request
+    ///
+    /// `updateMRNowPlayingInfo` events become [`RemoteEvent::NowPlaying`];
+    /// `sessionPreempted` events become [`RemoteEvent::SessionPreempted`];
+    /// any event carrying a `volume` parameter (in dB, as sent with
+    /// `SET_PARAMETER`) becomes [`RemoteEvent::VolumeChanged`]. Returns `None`
+    /// if the body is not a dictionary with a `type`.
+    #[must_use]
+    pub fn from_plist(value: &PlistValue) -> Option<Self> {
+        let dict = value.as_dict()?;
+        let kind = dict.get("type")?.as_str()?;
+        let params = dict.get("params");
+
+        if kind == "updateMRNowPlayingInfo" {
+            let info = params
+                .and_then(|p| p.as_dict()?.get("mrNowPlayingInfo"))
+                .or(params)
+                .and_then(PlistValue::as_dict)?;
+            let text = |key: &str| {
+                info.get(&format!("{NOW_PLAYING_PREFIX}{key}"))
+                    .and_then(PlistValue::as_str)
+                    .map(str::to_string)
+            };
+            let number = |key: &str| {
+                info.get(&format!("{NOW_PLAYING_PREFIX}{key}"))
+                    .and_then(PlistValue::as_f64)
+            };
+            return Some(Self::NowPlaying(NowPlayingInfo {
+                title: text("Title"),
+                artist: text("Artist"),
+                album: text("Album"),
+                duration: number("Duration"),
+                elapsed: number("ElapsedTime"),
+                playback_rate: number("PlaybackRate"),
+            }));
+        }
+
+        if PREEMPTION_EVENTS.contains(&kind) {
+            let by = params
+                .and_then(PlistValue::as_dict)
+                .and_then(|p| p.get("senderName").or_else(|| p.get("name")))
+                .and_then(PlistValue::as_str)
+                .map(str::to_string);
+            return Some(Self::SessionPreempted { by });
+        }
+
+        if let Some(db) = params
+            .and_then(|p| p.as_dict()?.get("volume"))
+            .and_then(PlistValue::as_f64)
+        {
+            #[allow(clippy::cast_possible_truncation, reason = "Volume in dB fits in f32")]
+            let volume = crate::control::volume::Volume::from_db(db as f32).as_f32();
+            return Some(Self::VolumeChanged { volume });
+        }
+
+        Some(Self::Other {
+            kind: kind.to_string(),
+            params: params.cloned(),
+        })
+    }
+}
@@ -1,3 +1,4 @@
 mod playback;
 mod queue;
+mod remote;
 mod volume;
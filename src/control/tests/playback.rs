@@ -35,6 +35,20 @@ async fn test_seek_fail_disconnected() {
     }
 }
 
+#[tokio::test]
+async fn test_seek_stream_fail_disconnected() {
+    let config = AirPlayConfig::default();
+    let manager = Arc::new(ConnectionManager::new(config));
+    let controller = crate::control::playback::PlaybackController::new(manager);
+
+    let result = controller
+        .seek_stream(Duration::from_secs(10), 100, 35_200, 352)
+        .await;
+    assert!(result.is_err());
+    // Position is only reported once the receiver accepted the flush
+    assert!(controller.state().await.position_secs.abs() < f64::EPSILON);
+}
+
 #[test]
 fn test_playback_progress() {
     let progress = PlaybackProgress {
@@ -131,3 +145,31 @@ async fn test_playback_controller_set_shuffle_and_repeat() {
     // it returns an error, state might remain unchanged.
     assert!(res.is_err());
 }
+
+#[tokio::test]
+async fn test_stream_progress_follows_timeline() {
+    let manager = Arc::new(ConnectionManager::new(AirPlayConfig::default()));
+    let controller = crate::control::playback::PlaybackController::new(manager.clone());
+
+    // No stream running
+    assert!(controller.stream_progress(0, None).await.is_none());
+
+    manager.start_timeline(44100).await;
+    manager.update_timeline(|t| t.set_rate(1.0)).await;
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // Held back to the audio sent so far
+    let progress = controller
+        .stream_progress(4410, Some(Duration::from_secs(10)))
+        .await
+        .unwrap();
+    assert_eq!(progress.start, 0);
+    assert_eq!(progress.current, 4410);
+    assert_eq!(progress.end, 441_000);
+
+    // Otherwise the timeline's position
+    let progress = controller.stream_progress(441_000, None).await.unwrap();
+    assert!(progress.current >= 8820);
+    assert!(progress.current < 441_000);
+    assert_eq!(progress.end, progress.current);
+}
@@ -0,0 +1,136 @@
+use std::time::Duration;
+
+use crate::control::remote::{MediaCommand, NowPlayingInfo, RemoteEvent};
+use crate::protocol::plist::{DictBuilder, PlistDict, PlistValue};
+
+fn params(command: MediaCommand) -> PlistDict {
+    let plist = command.to_plist();
+    let dict = plist.as_dict().unwrap();
+    assert_eq!(dict["type"].as_str(), Some("sendMediaRemoteCommand"));
+    dict["params"].as_dict().unwrap().clone()
+}
+
+#[test]
+fn test_transport_command_ids() {
+    assert_eq!(MediaCommand::Play.id(), 1);
+    assert_eq!(MediaCommand::Pause.id(), 2);
+    assert_eq!(MediaCommand::NextTrack.id(), 5);
+    assert_eq!(MediaCommand::PreviousTrack.id(), 6);
+
+    let pause = params(MediaCommand::Pause);
+    assert_eq!(pause["command"].as_i64(), Some(2));
+    assert_eq!(pause.len(), 1);
+}
+
+#[test]
+fn test_command_options() {
+    let skip = params(MediaCommand::SkipForward(Duration::from_secs(15)));
+    assert_eq!(skip["command"].as_i64(), Some(18));
+    assert_eq!(skip["skipInterval"].as_f64(), Some(15.0));
+
+    let seek = params(MediaCommand::Seek(Duration::from_millis(61_500)));
+    assert_eq!(seek["command"].as_i64(), Some(45));
+    assert_eq!(seek["playbackPosition"].as_f64(), Some(61.5));
+
+    let rate = params(MediaCommand::SetPlaybackRate(2.0));
+    assert_eq!(rate["playbackRate"].as_f64(), Some(2.0));
+}
+
+#[test]
+fn test_now_playing_event() {
+    let info = DictBuilder::new()
+        .insert("kMRMediaRemoteNowPlayingInfoTitle", "Song")
+        .insert("kMRMediaRemoteNowPlayingInfoArtist", "Band")
+        .insert("kMRMediaRemoteNowPlayingInfoDuration", 200.5)
+        .insert("kMRMediaRemoteNowPlayingInfoElapsedTime", 12i64)
+        .build();
+    let event = DictBuilder::new()
+        .insert("type", "updateMRNowPlayingInfo")
+        .insert(
+            "params",
+            DictBuilder::new().insert("mrNowPlayingInfo", info).build(),
+        )
+        .build();
+
+    assert_eq!(
+        RemoteEvent::from_plist(&event),
+        Some(RemoteEvent::NowPlaying(NowPlayingInfo {
+            title: Some("Song".to_string()),
+            artist: Some("Band".to_string()),
+            album: None,
+            duration: Some(200.5),
+            elapsed: Some(12.0),
+            playback_rate: None,
+        }))
+    );
+}
+
+#[test]
+fn test_volume_and_other_events() {
+    let volume = DictBuilder::new()
+        .insert("type", "updateVolume")
+        .insert("params", DictBuilder::new().insert("volume", 0.0).build())
+        .build();
+    assert_eq!(
+        RemoteEvent::from_plist(&volume),
+        Some(RemoteEvent::VolumeChanged { volume: 1.0 })
+    );
+
+    let other = DictBuilder::new()
+        .insert("type", "updateMRSupportedCommands")
+        .build();
+    assert_eq!(
+        RemoteEvent::from_plist(&other),
+        Some(RemoteEvent::Other {
+            kind: "updateMRSupportedCommands".to_string(),
+            params: None,
+        })
+    );
+
+    assert_eq!(RemoteEvent::from_plist(&PlistValue::Integer(1)), None);
+}
+
+#[test]
+fn test_session_preempted_events() {
+    use crate::protocol::rtsp::{Method, RtspRequest};
+
+    let event = DictBuilder::new()
+        .insert("type", "sessionPreempted")
+        .insert(
+            "params",
+            DictBuilder::new()
+                .insert("senderName", "Kitchen iPad")
+                .build(),
+        )
+        .build();
+    assert_eq!(
+        RemoteEvent::from_plist(&event),
+        Some(RemoteEvent::SessionPreempted {
+            by: Some("Kitchen iPad".to_string())
+        })
+    );
+
+    let teardown = RtspRequest::builder(Method::Teardown, "rtsp://10.0.0.1/1")
+        .header("X-Apple-Client-Name", "Living Room Mac")
+        .build();
+    assert_eq!(
+        RemoteEvent::from_request(&teardown),
+        Some(RemoteEvent::SessionPreempted {
+            by: Some("Living Room Mac".to_string())
+        })
+    );
+
+    let anonymous = RtspRequest::builder(Method::Teardown, "*").build();
+    assert_eq!(
+        RemoteEvent::from_request(&anonymous),
+        Some(RemoteEvent::SessionPreempted { by: None })
+    );
+
+    let posted = RtspRequest::builder(Method::Post, "/command")
+        .body_plist(&event)
+        .build();
+    assert!(matches!(
+        RemoteEvent::from_request(&posted),
+        Some(RemoteEvent::SessionPreempted { .. })
+    ));
+}
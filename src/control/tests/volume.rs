@@ -43,6 +43,34 @@ fn test_is_silent() {
     assert!(!Volume::new(0.01).is_silent());
 }
 
+#[test]
+fn test_volume_interpolate() {
+    let from = Volume::new(0.2);
+    let to = Volume::new(0.6);
+    assert!((from.interpolate(to, 0.0).as_f32() - 0.2).abs() < f32::EPSILON);
+    assert!((from.interpolate(to, 0.5).as_f32() - 0.4).abs() < 1e-6);
+    assert!((from.interpolate(to, 1.0).as_f32() - 0.6).abs() < f32::EPSILON);
+    assert!((to.interpolate(from, 2.0).as_f32() - 0.2).abs() < f32::EPSILON);
+}
+
+#[tokio::test]
+async fn test_volume_ramp_stops_on_failure() {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use crate::connection::ConnectionManager;
+    use crate::control::volume::VolumeController;
+    use crate::types::AirPlayConfig;
+
+    let manager = Arc::new(ConnectionManager::new(AirPlayConfig::default()));
+    let controller = VolumeController::new(manager).with_ramp(Duration::from_millis(200));
+    assert_eq!(controller.ramp(), Duration::from_millis(200));
+
+    // The first step fails, leaving the volume where it was
+    assert!(controller.set(Volume::new(0.1)).await.is_err());
+    assert_eq!(controller.get().await, Volume::DEFAULT);
+}
+
 #[tokio::test]
 async fn test_volume_controller_not_connected() {
     use std::sync::Arc;
@@ -114,3 +142,99 @@ async fn test_group_volume_controller() {
             .is_err()
     );
 }
+
+#[tokio::test]
+async fn test_group_volume_relative_adjustments() {
+    use std::sync::Arc;
+
+    use crate::connection::ConnectionManager;
+    use crate::control::volume::{
+        DEFAULT_GROUP_VOLUME_STEP, GroupVolumeController, VolumeController,
+    };
+    use crate::types::AirPlayConfig;
+
+    let mut group = GroupVolumeController::new();
+    assert!((group.step() - DEFAULT_GROUP_VOLUME_STEP).abs() < f32::EPSILON);
+
+    let mut group_with_step = GroupVolumeController::new().with_step(2.0);
+    assert!((group_with_step.step() - 1.0).abs() < f32::EPSILON);
+    group_with_step.set_step(0.1);
+    assert!((group_with_step.step() - 0.1).abs() < f32::EPSILON);
+
+    // An empty group only tracks the master volume
+    group.set_step(0.2);
+    let raised = group.raise_all().await.unwrap();
+    assert!((raised.as_f32() - 0.95).abs() < 0.001);
+    let raised = group.raise_all().await.unwrap();
+    assert!(raised.is_max());
+    let lowered = group.lower_all().await.unwrap();
+    assert!((lowered.as_f32() - 0.8).abs() < 0.001);
+
+    // Relative member volumes scale with the master
+    let manager = Arc::new(ConnectionManager::new(AirPlayConfig::default()));
+    group.add_device(
+        "d1".to_string(),
+        Arc::new(VolumeController::new(manager.clone())),
+    );
+    group.add_device("d2".to_string(), Arc::new(VolumeController::new(manager)));
+    assert_eq!(group.device_ids(), vec!["d1", "d2"]);
+
+    // Members are not connected, so sending fails, but local state is kept
+    assert!(
+        group
+            .set_device_volume("d2", Volume::new(0.5))
+            .await
+            .is_err()
+    );
+    assert!((group.device_volume("d2").unwrap().as_f32() - 0.5).abs() < f32::EPSILON);
+    assert!((group.effective_volume("d1").unwrap().as_f32() - 0.8).abs() < 0.001);
+    assert!((group.effective_volume("d2").unwrap().as_f32() - 0.4).abs() < 0.001);
+
+    assert!(group.lower_all().await.is_err());
+    assert!((group.master_volume().as_f32() - 0.6).abs() < 0.001);
+    assert!((group.effective_volume("d2").unwrap().as_f32() - 0.3).abs() < 0.001);
+    assert!(group.effective_volume("missing").is_none());
+}
+
+#[tokio::test]
+async fn test_group_volume_calibration() {
+    use std::sync::Arc;
+
+    use crate::connection::ConnectionManager;
+    use crate::control::volume::{GroupVolumeController, MAX_CALIBRATION_DB, VolumeController};
+    use crate::types::AirPlayConfig;
+
+    let manager = Arc::new(ConnectionManager::new(AirPlayConfig::default()));
+    let mut group = GroupVolumeController::new();
+    for id in ["homepod", "soundbar", "bedroom"] {
+        group.add_device(
+            id.to_string(),
+            Arc::new(VolumeController::new(manager.clone())),
+        );
+    }
+    assert_eq!(group.calibration("homepod"), Some(0.0));
+    assert!(group.calibration("missing").is_none());
+
+    // Measured at the same volume: the soundbar is 6 dB louder than the
+    // HomePod, the bedroom speaker is not measured
+    let offsets = group.apply_measurements(&[("homepod", 70.0), ("soundbar", 76.0)]);
+    assert_eq!(offsets.len(), 2);
+    assert!((group.calibration("homepod").unwrap()).abs() < f32::EPSILON);
+    assert!((group.calibration("soundbar").unwrap() + 6.0).abs() < 0.001);
+    assert_eq!(group.calibration("bedroom"), Some(0.0));
+
+    // Not connected, but local state is kept
+    assert!(group.set_master_volume(Volume::new(0.5)).await.is_err());
+    let homepod = group.effective_volume("homepod").unwrap();
+    let soundbar = group.effective_volume("soundbar").unwrap();
+    assert!((homepod.as_f32() - 0.5).abs() < 0.001);
+    assert!((homepod.to_db() - soundbar.to_db() - 6.0).abs() < 0.01);
+
+    // Offsets are clamped
+    assert!(group.set_calibration("bedroom", 40.0).await.is_err());
+    assert!((group.calibration("bedroom").unwrap() - MAX_CALIBRATION_DB).abs() < f32::EPSILON);
+    assert!(group.effective_volume("bedroom").unwrap().is_max());
+
+    // Measurements for unknown devices are ignored
+    assert!(group.apply_measurements(&[("missing", 50.0)]).is_empty());
+}
@@ -1,7 +1,10 @@
 //! Volume control for `AirPlay` devices
 
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 
+use futures::future::join_all;
 use tokio::sync::RwLock;
 
 use crate::connection::ConnectionManager;
@@ -81,6 +84,16 @@ impl Volume {
     pub fn is_max(&self) -> bool {
         self.0 >= 0.999
     }
+
+    /// Level `t` of the way from this volume to `target` (0.0 - 1.0)
+    ///
+    /// Interpolates the linear level, so the signal amplitude changes
+    /// evenly.
+    #[must_use]
+    pub fn interpolate(self, target: Self, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Self::new(self.0 + (target.0 - self.0) * t)
+    }
 }
 
 impl Default for Volume {
@@ -95,7 +108,15 @@ impl From<f32> for Volume {
     }
 }
 
+/// Time between the volume steps of a ramp
+pub const VOLUME_RAMP_STEP: Duration = Duration::from_millis(25);
+
 /// Volume controller
+///
+/// Volume changes jump straight to the new level unless a ramp is set with
+/// [`with_ramp`](Self::with_ramp), in which case intermediate levels are
+/// sent every [`VOLUME_RAMP_STEP`] to avoid the pop some receivers make on
+/// a sudden change.
 pub struct VolumeController {
     /// Connection manager
     connection: Arc<ConnectionManager>,
@@ -105,6 +126,10 @@ pub struct VolumeController {
     muted: RwLock<bool>,
     /// Volume before mute (for unmute)
     pre_mute_volume: RwLock<Volume>,
+    /// Time taken by [`set`](Self::set) to reach a new level
+    ramp: Duration,
+    /// Bumped by each ramp so a newer one stops an older one
+    ramp_generation: AtomicU64,
 }
 
 impl VolumeController {
@@ -116,9 +141,24 @@ impl VolumeController {
             volume: RwLock::new(Volume::DEFAULT),
             muted: RwLock::new(false),
             pre_mute_volume: RwLock::new(Volume::DEFAULT),
+            ramp: Duration::ZERO,
+            ramp_generation: AtomicU64::new(0),
         }
     }
 
+    /// Ramp volume changes made with [`set`](Self::set) over `ramp`
+    #[must_use]
+    pub fn with_ramp(mut self, ramp: Duration) -> Self {
+        self.ramp = ramp;
+        self
+    }
+
+    /// Time taken by [`set`](Self::set) to reach a new level
+    #[must_use]
+    pub fn ramp(&self) -> Duration {
+        self.ramp
+    }
+
     /// Get current volume
     pub async fn get(&self) -> Volume {
         *self.volume.read().await
@@ -126,15 +166,13 @@ impl VolumeController {
 
     /// Set volume
     ///
+    /// Ramps to the new level if a ramp is configured.
+    ///
     /// # Errors
     ///
     /// Returns error if command fails
     pub async fn set(&self, volume: Volume) -> Result<(), AirPlayError> {
-        // Send to device
-        self.send_volume(volume).await?;
-
-        // Update local state
-        *self.volume.write().await = volume;
+        self.ramp_to(volume, self.ramp).await?;
 
         // Unmute if setting non-zero volume
         if !volume.is_silent() {
@@ -144,6 +182,39 @@ impl VolumeController {
         Ok(())
     }
 
+    /// Move to `volume` in steps over `duration`
+    ///
+    /// Returns once the last step is sent, or early if another volume
+    /// change starts meanwhile. [`get`](Self::get) follows the ramp. A zero
+    /// `duration` sets the level at once.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a step fails; the ramp stops there.
+    pub async fn ramp_to(&self, volume: Volume, duration: Duration) -> Result<(), AirPlayError> {
+        let generation = self.ramp_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let from = self.get().await;
+        let steps = (duration.as_millis() / VOLUME_RAMP_STEP.as_millis()).max(1);
+
+        for step in 1..=steps {
+            if step > 1 {
+                tokio::time::sleep(VOLUME_RAMP_STEP).await;
+                if self.ramp_generation.load(Ordering::SeqCst) != generation {
+                    return Ok(());
+                }
+            }
+
+            #[allow(
+                clippy::cast_precision_loss,
+                reason = "Step counts are far below f32 precision limits"
+            )]
+            let level = from.interpolate(volume, step as f32 / steps as f32);
+            self.send_volume(level).await?;
+            *self.volume.write().await = level;
+        }
+        Ok(())
+    }
+
     /// Set volume from percentage
     ///
     /// # Errors
@@ -294,6 +365,7 @@ impl VolumeController {
         let response_str = String::from_utf8(response).map_err(|_| AirPlayError::RtspError {
             message: "Invalid UTF-8 in volume response".to_string(),
             status_code: None,
+            source: None,
         })?;
 
         for line in response_str.lines() {
@@ -304,6 +376,7 @@ impl VolumeController {
                     .map_err(|_| AirPlayError::RtspError {
                         message: "Invalid volume value".to_string(),
                         status_code: None,
+                        source: None,
                     })?;
                 return Ok(Volume::from_db(val));
             }
@@ -313,12 +386,32 @@ impl VolumeController {
     }
 }
 
+/// Default step used by [`GroupVolumeController::raise_all`] and
+/// [`GroupVolumeController::lower_all`]
+pub const DEFAULT_GROUP_VOLUME_STEP: f32 = 0.05;
+
+/// Largest calibration offset, in dB, in either direction
+pub const MAX_CALIBRATION_DB: f32 = 20.0;
+
 /// Multi-device volume control
+///
+/// Each member has a relative volume; the level sent to a device is the
+/// master volume scaled by that member's relative volume, so changing the
+/// master keeps the balance between rooms intact.
+///
+/// Speaker models differ in how loud they play at the same `AirPlay`
+/// volume. A per-member calibration offset (in dB) compensates for this so
+/// the same master volume sounds equally loud in every room. Offsets can be
+/// set directly or derived from sound levels measured while each member
+/// plays a [`CalibrationTone`](crate::streaming::CalibrationTone); see
+/// [`apply_measurements`](Self::apply_measurements).
 pub struct GroupVolumeController {
     /// Device controllers
     devices: Vec<DeviceVolume>,
     /// Master volume
     master_volume: Volume,
+    /// Step for relative adjustments
+    step: f32,
 }
 
 /// Volume for a single device in a group
@@ -327,6 +420,8 @@ pub struct DeviceVolume {
     pub device_id: String,
     /// Individual volume multiplier
     pub volume: Volume,
+    /// Calibration offset in dB (negative for louder models)
+    pub calibration_db: f32,
     /// Controller
     controller: Arc<VolumeController>,
 }
@@ -338,14 +433,81 @@ impl GroupVolumeController {
         Self {
             devices: Vec::new(),
             master_volume: Volume::DEFAULT,
+            step: DEFAULT_GROUP_VOLUME_STEP,
         }
     }
 
+    /// Set the step used for relative adjustments
+    #[must_use]
+    pub fn with_step(mut self, step: f32) -> Self {
+        self.set_step(step);
+        self
+    }
+
+    /// Set the step used for relative adjustments (clamped to 0.0 - 1.0)
+    pub fn set_step(&mut self, step: f32) {
+        self.step = step.clamp(0.0, 1.0);
+    }
+
+    /// Get the step used for relative adjustments
+    #[must_use]
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Get the master volume
+    #[must_use]
+    pub fn master_volume(&self) -> Volume {
+        self.master_volume
+    }
+
+    /// Get a member's relative volume
+    #[must_use]
+    pub fn device_volume(&self, device_id: &str) -> Option<Volume> {
+        self.devices
+            .iter()
+            .find(|d| d.device_id == device_id)
+            .map(|d| d.volume)
+    }
+
+    /// Get a member's calibration offset in dB
+    #[must_use]
+    pub fn calibration(&self, device_id: &str) -> Option<f32> {
+        self.devices
+            .iter()
+            .find(|d| d.device_id == device_id)
+            .map(|d| d.calibration_db)
+    }
+
+    /// Get the volume actually sent to a member
+    ///
+    /// The master volume scaled by the member's relative volume, then
+    /// shifted by its calibration offset.
+    #[must_use]
+    pub fn effective_volume(&self, device_id: &str) -> Option<Volume> {
+        self.devices
+            .iter()
+            .find(|d| d.device_id == device_id)
+            .map(|d| self.output_volume(d))
+    }
+
+    fn output_volume(&self, device: &DeviceVolume) -> Volume {
+        let level = self.master_volume.as_f32() * device.volume.as_f32();
+        Volume::new(level * 10.0_f32.powf(device.calibration_db / 20.0))
+    }
+
+    /// IDs of the group members
+    #[must_use]
+    pub fn device_ids(&self) -> Vec<&str> {
+        self.devices.iter().map(|d| d.device_id.as_str()).collect()
+    }
+
     /// Add a device
     pub fn add_device(&mut self, device_id: String, controller: Arc<VolumeController>) {
         self.devices.push(DeviceVolume {
             device_id,
             volume: Volume::MAX, // Full relative volume
+            calibration_db: 0.0,
             controller,
         });
     }
@@ -365,6 +527,28 @@ impl GroupVolumeController {
         self.apply_volumes().await
     }
 
+    /// Raise the master volume by the configured step
+    ///
+    /// # Errors
+    ///
+    /// Returns error if command fails
+    pub async fn raise_all(&mut self) -> Result<Volume, AirPlayError> {
+        let volume = Volume::new(self.master_volume.as_f32() + self.step);
+        self.set_master_volume(volume).await?;
+        Ok(volume)
+    }
+
+    /// Lower the master volume by the configured step
+    ///
+    /// # Errors
+    ///
+    /// Returns error if command fails
+    pub async fn lower_all(&mut self) -> Result<Volume, AirPlayError> {
+        let volume = Volume::new(self.master_volume.as_f32() - self.step);
+        self.set_master_volume(volume).await?;
+        Ok(volume)
+    }
+
     /// Set individual device volume (relative to master)
     ///
     /// # Errors
@@ -381,13 +565,65 @@ impl GroupVolumeController {
         self.apply_volumes().await
     }
 
+    /// Set a member's calibration offset in dB
+    ///
+    /// The offset is clamped to [`MAX_CALIBRATION_DB`] either way.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if command fails
+    pub async fn set_calibration(
+        &mut self,
+        device_id: &str,
+        offset_db: f32,
+    ) -> Result<(), AirPlayError> {
+        if let Some(device) = self.devices.iter_mut().find(|d| d.device_id == device_id) {
+            device.calibration_db = offset_db.clamp(-MAX_CALIBRATION_DB, MAX_CALIBRATION_DB);
+        }
+        self.apply_volumes().await
+    }
+
+    /// Derive calibration offsets from measured sound levels
+    ///
+    /// Play a [`CalibrationTone`](crate::streaming::CalibrationTone) on each
+    /// member in turn at the same volume and measure the sound level (e.g.
+    /// dB SPL) at the listening position. Every member is then attenuated to
+    /// match the quietest one, so no member is pushed past its own maximum.
+    /// Members without a measurement keep their offset. Returns the new
+    /// offsets; call [`set_master_volume`](Self::set_master_volume) or
+    /// [`set_calibration`](Self::set_calibration) to apply them.
+    pub fn apply_measurements(&mut self, levels: &[(&str, f32)]) -> Vec<(String, f32)> {
+        let Some(quietest) = levels
+            .iter()
+            .filter(|(id, _)| self.devices.iter().any(|d| d.device_id == *id))
+            .map(|(_, level)| *level)
+            .reduce(f32::min)
+        else {
+            return Vec::new();
+        };
+
+        let mut offsets = Vec::new();
+        for device in &mut self.devices {
+            if let Some((_, level)) = levels.iter().find(|(id, _)| *id == device.device_id) {
+                device.calibration_db = (quietest - level).max(-MAX_CALIBRATION_DB);
+                offsets.push((device.device_id.clone(), device.calibration_db));
+            }
+        }
+        offsets
+    }
+
     /// Apply volumes to all devices
+    ///
+    /// Every member is updated over its own connection concurrently; a
+    /// failure on one member does not stop the others from being updated.
     async fn apply_volumes(&self) -> Result<(), AirPlayError> {
-        for device in &self.devices {
-            let effective = Volume::new(self.master_volume.as_f32() * device.volume.as_f32());
-            device.controller.set(effective).await?;
-        }
-        Ok(())
+        let results = join_all(
+            self.devices
+                .iter()
+                .map(|device| device.controller.set(self.output_volume(device))),
+        )
+        .await;
+        results.into_iter().collect()
     }
 
     /// Mute all devices
@@ -396,10 +632,10 @@ impl GroupVolumeController {
     ///
     /// Returns error if command fails
     pub async fn mute_all(&self) -> Result<(), AirPlayError> {
-        for device in &self.devices {
-            device.controller.mute().await?;
-        }
-        Ok(())
+        join_all(self.devices.iter().map(|device| device.controller.mute()))
+            .await
+            .into_iter()
+            .collect()
     }
 
     /// Unmute all devices
@@ -408,10 +644,10 @@ impl GroupVolumeController {
     ///
     /// Returns error if command fails
     pub async fn unmute_all(&self) -> Result<(), AirPlayError> {
-        for device in &self.devices {
-            device.controller.unmute().await?;
-        }
-        Ok(())
+        join_all(self.devices.iter().map(|device| device.controller.unmute()))
+            .await
+            .into_iter()
+            .collect()
     }
 }
 
@@ -6,6 +6,8 @@ use std::sync::Arc;
 use mdns_sd::{Error as MdnsError, ServiceDaemon, ServiceInfo};
 use tokio::sync::{Mutex, RwLock, mpsc};
 
+use super::names::{host_label, instance_name};
+
 /// Errors from service advertisement
 #[derive(Debug, thiserror::Error)]
 pub enum AdvertiserError {
@@ -406,8 +408,20 @@ impl RaopAdvertiser {
     ///
     /// Returns error if mDNS daemon cannot be initialized or MAC address cannot be retrieved.
     pub fn new(config: AdvertiserConfig) -> Result<Self, AdvertiserError> {
-        let daemon = ServiceDaemon::new()?;
+        Self::with_daemon(config, ServiceDaemon::new()?)
+    }
 
+    /// Create an advertiser that registers on an existing mDNS daemon
+    ///
+    /// Used when several receivers in one process share a daemon.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the MAC address is not provided in the config.
+    pub fn with_daemon(
+        config: AdvertiserConfig,
+        daemon: ServiceDaemon,
+    ) -> Result<Self, AdvertiserError> {
         let mac = config.mac_override.ok_or_else(|| {
             AdvertiserError::MacRetrievalFailed(
                 "MAC address must be provided in config".to_string(),
@@ -424,9 +438,13 @@ impl RaopAdvertiser {
     }
 
     /// Get the service name that will be advertised
+    ///
+    /// The friendly name is put in Unicode NFC and truncated to fit the
+    /// 63-byte limit on instance names.
     #[must_use]
     pub fn service_name(&self) -> String {
-        format!("{}@{}", format_mac_for_service(&self.mac), self.config.name)
+        let prefix = format!("{}@", format_mac_for_service(&self.mac));
+        instance_name(&prefix, &self.config.name)
     }
 
     /// Register the service on the network
@@ -448,10 +466,7 @@ impl RaopAdvertiser {
 
         // Create service info
         // Note: mdns-sd ServiceInfo requires careful construction
-        let hostname = format!(
-            "{}.local.",
-            self.config.name.replace(' ', "-").to_lowercase()
-        );
+        let hostname = format!("{}.local.", host_label(&self.config.name));
         let service_info = ServiceInfo::new(
             service_type,
             &service_name,
@@ -568,7 +583,21 @@ impl AsyncRaopAdvertiser {
     /// # Errors
     ///
     /// Returns error if advertiser creation fails (e.g. mDNS init or MAC retrieval).
-    pub async fn start(mut config: AdvertiserConfig) -> Result<Self, AdvertiserError> {
+    pub async fn start(config: AdvertiserConfig) -> Result<Self, AdvertiserError> {
+        Self::start_with_daemon(config, None).await
+    }
+
+    /// Create and start the advertiser on an optional shared mDNS daemon
+    ///
+    /// If `daemon` is `None` a dedicated daemon is created.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if advertiser creation fails (e.g. mDNS init or MAC retrieval).
+    pub async fn start_with_daemon(
+        mut config: AdvertiserConfig,
+        daemon: Option<ServiceDaemon>,
+    ) -> Result<Self, AdvertiserError> {
         let (command_tx, mut command_rx) = mpsc::channel(16);
 
         let mac = if let Some(mac) = config.mac_override {
@@ -579,7 +608,8 @@ impl AsyncRaopAdvertiser {
                 .map_err(|e| AdvertiserError::MacRetrievalFailed(e.to_string()))?
         }?;
 
-        let service_name = format!("{}@{}", format_mac_for_service(&mac), config.name);
+        let prefix = format!("{}@", format_mac_for_service(&mac));
+        let service_name = instance_name(&prefix, &config.name);
         let status = Arc::new(RwLock::new(ReceiverStatusFlags::default()));
         // let status_clone = status.clone();
 
@@ -587,7 +617,11 @@ impl AsyncRaopAdvertiser {
         config.mac_override = Some(mac);
 
         tokio::task::spawn_blocking(move || {
-            let mut advertiser = match RaopAdvertiser::new(config) {
+            let created = match daemon {
+                Some(daemon) => RaopAdvertiser::with_daemon(config, daemon),
+                None => RaopAdvertiser::new(config),
+            };
+            let mut advertiser = match created {
                 Ok(a) => a,
                 Err(e) => {
                     tracing::error!("Failed to create advertiser: {}", e);
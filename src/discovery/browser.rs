@@ -5,9 +5,12 @@ use std::time::Duration;
 
 use futures::{Stream, StreamExt};
 
-use super::{parser, raop};
+use super::parser::status_flags;
+use super::raop::{ServiceMerger, ServiceRecord};
 use crate::error::AirPlayError;
-use crate::types::{AirPlayConfig, AirPlayDevice, DeviceCapabilities, RaopCapabilities};
+#[cfg(feature = "sender")]
+use crate::types::AirPlayConfig;
+use crate::types::AirPlayDevice;
 
 /// Extended discovery options for both `AirPlay` 1 and 2
 #[derive(Debug, Clone)]
@@ -20,6 +23,8 @@ pub struct DiscoveryOptions {
     pub timeout: Duration,
     /// Filter by device capabilities
     pub filter: Option<DeviceFilter>,
+    /// Network interfaces to browse on (e.g. "eth0"); empty means all
+    pub interfaces: Vec<String>,
 }
 
 impl Default for DiscoveryOptions {
@@ -29,10 +34,20 @@ impl Default for DiscoveryOptions {
             discover_raop: true,
             timeout: Duration::from_secs(5),
             filter: None,
+            interfaces: Vec::new(),
         }
     }
 }
 
+impl DiscoveryOptions {
+    /// Restrict browsing to the given network interfaces
+    #[must_use]
+    pub fn interfaces(mut self, interfaces: Vec<String>) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+}
+
 /// Device filter criteria
 #[derive(Debug, Clone, Default)]
 pub struct DeviceFilter {
@@ -51,6 +66,109 @@ pub enum DiscoveryEvent {
     Removed(String),
     /// Device information was updated
     Updated(AirPlayDevice),
+    /// A device's advertised status changed (password, busy, group role)
+    ///
+    /// Sent instead of [`Updated`](Self::Updated) when the change includes the
+    /// status flags or group membership; `device` carries the other updates.
+    StatusChanged {
+        /// The updated device
+        device: AirPlayDevice,
+        /// Status before the change
+        previous: DeviceStatus,
+        /// Status now advertised
+        status: DeviceStatus,
+    },
+}
+
+impl DiscoveryEvent {
+    /// ID of the device the event is about
+    #[must_use]
+    pub fn device_id(&self) -> &str {
+        match self {
+            Self::Added(device) | Self::Updated(device) | Self::StatusChanged { device, .. } => {
+                &device.id
+            }
+            Self::Removed(id) => id,
+        }
+    }
+
+    /// The device description, unless the device was removed
+    #[must_use]
+    pub fn device(&self) -> Option<&AirPlayDevice> {
+        match self {
+            Self::Added(device) | Self::Updated(device) | Self::StatusChanged { device, .. } => {
+                Some(device)
+            }
+            Self::Removed(_) => None,
+        }
+    }
+
+    /// Consume the event, returning the device unless it was removed
+    #[must_use]
+    pub fn into_device(self) -> Option<AirPlayDevice> {
+        match self {
+            Self::Added(device) | Self::Updated(device) | Self::StatusChanged { device, .. } => {
+                Some(device)
+            }
+            Self::Removed(_) => None,
+        }
+    }
+}
+
+/// Advertised device status, as reported by [`DiscoveryEvent::StatusChanged`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeviceStatus {
+    /// Status flags (`flags` / `sf`), see [`parser::status_flags`]
+    pub flags: u32,
+    /// Group UUID (`gid`)
+    pub group_id: Option<String>,
+    /// Whether the device leads its group (`igl`)
+    pub is_group_leader: bool,
+}
+
+impl DeviceStatus {
+    /// Status advertised by a device
+    #[must_use]
+    pub fn of(device: &AirPlayDevice) -> Self {
+        let txt = device.txt();
+        Self {
+            flags: txt.status_flags.unwrap_or(0),
+            group_id: txt.group_id,
+            is_group_leader: txt.is_group_leader,
+        }
+    }
+
+    /// A password is required to connect
+    #[must_use]
+    pub fn requires_password(&self) -> bool {
+        self.flags & status_flags::PASSWORD_REQUIRED != 0
+    }
+
+    /// Another sender is playing to the device
+    #[must_use]
+    pub fn is_busy(&self) -> bool {
+        self.flags & status_flags::RECEIVER_SESSION_ACTIVE != 0
+    }
+
+    /// The device reports a problem
+    #[must_use]
+    pub fn has_problem(&self) -> bool {
+        self.flags & status_flags::PROBLEM_DETECTED != 0
+    }
+}
+
+/// Event for a device that is already known, given its last reported status
+pub(crate) fn update_event(previous: &DeviceStatus, device: AirPlayDevice) -> DiscoveryEvent {
+    let status = DeviceStatus::of(&device);
+    if status == *previous {
+        DiscoveryEvent::Updated(device)
+    } else {
+        DiscoveryEvent::StatusChanged {
+            device,
+            previous: previous.clone(),
+            status,
+        }
+    }
 }
 
 /// mDNS browser for discovering `AirPlay` devices
@@ -62,6 +180,7 @@ impl DeviceBrowser {
     /// Create a new device browser with default config (`AirPlay` 2 only for backward compat?)
     /// or should it default to both?
     /// The original `new` took `AirPlayConfig`.
+    #[cfg(feature = "sender")]
     #[must_use]
     pub fn new(config: &AirPlayConfig) -> Self {
         // Map AirPlayConfig to DiscoveryOptions if possible, or use defaults
@@ -96,9 +215,10 @@ struct DeviceBrowserStream {
     mdns: mdns_sd::ServiceDaemon,
     // Stream of events from all browsers
     stream: Pin<Box<dyn Stream<Item = (String, mdns_sd::ServiceEvent)> + Send>>,
-    known_devices: HashMap<String, AirPlayDevice>,
-    // Map full service name to device ID
-    fullname_map: HashMap<String, String>,
+    // Devices merged from both service types
+    merger: ServiceMerger,
+    // Devices reported to the consumer, with their last reported status
+    reported: HashMap<String, DeviceStatus>,
     // Timer for pruning stale devices
     prune_interval: Option<tokio::time::Interval>,
 }
@@ -107,7 +227,13 @@ impl DeviceBrowserStream {
     fn new(options: DiscoveryOptions) -> Result<Self, AirPlayError> {
         let mdns = mdns_sd::ServiceDaemon::new().map_err(|e| AirPlayError::DiscoveryFailed {
             message: format!("Failed to create mDNS daemon: {e}"),
-            source: None,
+            source: Some(Box::new(e)),
+        })?;
+        super::restrict_interfaces(&mdns, &options.interfaces).map_err(|e| {
+            AirPlayError::DiscoveryFailed {
+                message: format!("Failed to select network interfaces: {e}"),
+                source: Some(Box::new(e)),
+            }
         })?;
 
         let mut streams = Vec::new();
@@ -116,7 +242,7 @@ impl DeviceBrowserStream {
             let receiver = mdns.browse(super::AIRPLAY_SERVICE_TYPE).map_err(|e| {
                 AirPlayError::DiscoveryFailed {
                     message: format!("Failed to browse AirPlay 2: {e}"),
-                    source: None,
+                    source: Some(Box::new(e)),
                 }
             })?;
             // Tag events with service type
@@ -132,7 +258,7 @@ impl DeviceBrowserStream {
             let receiver = mdns.browse(super::RAOP_SERVICE_TYPE).map_err(|e| {
                 AirPlayError::DiscoveryFailed {
                     message: format!("Failed to browse RAOP: {e}"),
-                    source: None,
+                    source: Some(Box::new(e)),
                 }
             })?;
             let s = receiver
@@ -147,8 +273,8 @@ impl DeviceBrowserStream {
             options,
             mdns,
             stream: Box::pin(stream),
-            known_devices: HashMap::new(),
-            fullname_map: HashMap::new(),
+            merger: ServiceMerger::default(),
+            reported: HashMap::new(),
             prune_interval: None,
         })
     }
@@ -167,150 +293,40 @@ impl DeviceBrowserStream {
         }
     }
 
-    #[allow(
-        clippy::too_many_lines,
-        reason = "Complex logic handling resolved mDNS services"
-    )]
     fn handle_resolved(
         &mut self,
         service_type: &str,
         info: &mdns_sd::ResolvedService,
     ) -> Option<DiscoveryEvent> {
-        let name = info.get_fullname().to_string();
-
-        // Parse TXT records
-        let txt_records: HashMap<String, String> = info
-            .get_properties()
-            .iter()
-            .map(|prop| {
-                let key = prop.key().to_string();
-                (key, prop.val_str().to_string())
-            })
-            .collect();
-
-        // Determine Device ID
-        let device_id = if service_type == super::RAOP_SERVICE_TYPE {
-            // For RAOP, parse from service name: MAC@Name
-            // Extract MAC and format it to match standard ID format (if needed)
-            // Assuming standard ID format is MAC address with colons?
-            // AirPlay 2 usually sends MAC.
-            if let Some((mac, _)) = raop::parse_raop_service_name(info.get_fullname()) {
-                raop::format_mac_address(&mac)
-            } else {
-                // Fallback
-                name.clone()
-            }
-        } else {
-            // AirPlay 2
-            txt_records
-                .get("deviceid")
-                .or_else(|| txt_records.get("pk"))
-                .cloned()
-                .unwrap_or_else(|| name.clone())
+        let record = ServiceRecord {
+            service_type: service_type.to_string(),
+            fullname: info.get_fullname().to_string(),
+            port: info.get_port(),
+            addresses: info
+                .get_addresses()
+                .iter()
+                .map(|ip| {
+                    // Handle ScopedIp from mdns-sd 0.17
+                    match ip {
+                        mdns_sd::ScopedIp::V4(scoped) => std::net::IpAddr::V4(*scoped.addr()),
+                        mdns_sd::ScopedIp::V6(scoped) => std::net::IpAddr::V6(*scoped.addr()),
+                        _ => unreachable!("Unknown ScopedIp variant"),
+                    }
+                })
+                .collect(),
+            txt_records: info
+                .get_properties()
+                .iter()
+                .map(|prop| (prop.key().to_string(), prop.val_str().to_string()))
+                .collect(),
         };
 
-        // Update map
-        self.fullname_map.insert(name.clone(), device_id.clone());
-
-        // Get resolved addresses
-        let addresses: Vec<std::net::IpAddr> = info
-            .get_addresses()
-            .iter()
-            .map(|ip| {
-                // Handle ScopedIp from mdns-sd 0.17
-                match ip {
-                    mdns_sd::ScopedIp::V4(scoped) => std::net::IpAddr::V4(*scoped.addr()),
-                    mdns_sd::ScopedIp::V6(scoped) => std::net::IpAddr::V6(*scoped.addr()),
-                    _ => unreachable!("Unknown ScopedIp variant"),
-                }
-            })
-            .collect();
-        if addresses.is_empty() {
-            return None;
-        }
-
-        // Get friendly name
-        let friendly_name = if service_type == super::RAOP_SERVICE_TYPE {
-            raop::parse_raop_service_name(info.get_fullname())
-                .map_or_else(|| "Unknown RAOP Device".to_string(), |(_, n)| n)
-        } else {
-            // For AirPlay 2 devices, the service instance name (before the service type)
-            // is the user-assigned friendly name (e.g., "Kitchen" from
-            // "Kitchen._airplay._tcp.local.")
-            name.split('.')
-                .next()
-                .filter(|n| !n.is_empty())
-                .map_or_else(
-                    || {
-                        txt_records
-                            .get("model")
-                            .cloned()
-                            .unwrap_or_else(|| "AirPlay Device".to_string())
-                    },
-                    ToString::to_string,
-                )
-        };
-
-        // Create or update device
-        let mut device = self
-            .known_devices
-            .get(&device_id)
-            .cloned()
-            .unwrap_or_else(|| {
-                // Initialize new device
-                AirPlayDevice {
-                    id: device_id.clone(),
-                    name: friendly_name.clone(),
-                    model: txt_records.get("model").cloned(),
-                    addresses: addresses.clone(),
-                    port: 0, // Will be set below
-                    capabilities: DeviceCapabilities::default(),
-                    raop_port: None,
-                    raop_capabilities: None,
-                    txt_records: HashMap::new(),
-                    last_seen: Some(std::time::Instant::now()),
-                }
-            });
-
-        // Update name/model if missing or if this is the "better" source?
-        // Usually assume AirPlay 2 info is better if available, but for now just update.
-        if device.name == "AirPlay Device" || device.name == "Unknown RAOP Device" {
-            device.name = friendly_name;
-        }
-        if device.model.is_none() {
-            device.model = txt_records.get("model").cloned();
-        }
-
-        // Merge addresses (deduplicate?)
-        for addr in addresses {
-            if !device.addresses.contains(&addr) {
-                device.addresses.push(addr);
-            }
-        }
-
-        // Merge TXT records
-        device.txt_records.extend(txt_records.clone());
-
-        // Update protocol specific info
-        if service_type == super::AIRPLAY_SERVICE_TYPE {
-            device.port = info.get_port();
-            if let Some(features) = txt_records.get("features") {
-                if let Some(caps) = parser::parse_features(features) {
-                    device.capabilities = caps;
-                }
-            }
-        } else if service_type == super::RAOP_SERVICE_TYPE {
-            device.raop_port = Some(info.get_port());
-            device.raop_capabilities = Some(RaopCapabilities::from_txt_records(&txt_records));
-
-            // If only RAOP, set main port to RAOP port for convenience?
-            // But main port is u16 (mandatory).
-            if device.port == 0 {
-                device.port = info.get_port();
-            }
-        }
+        let device = self.merger.resolved(&record)?;
+        self.report(device)
+    }
 
-        // Filter check
+    /// Turn a merged device into an event, applying the filter
+    fn report(&mut self, device: AirPlayDevice) -> Option<DiscoveryEvent> {
         if let Some(filter) = &self.options.filter {
             if filter.audio_only
                 && !device.capabilities.supports_audio
@@ -318,50 +334,29 @@ impl DeviceBrowserStream {
             {
                 return None;
             }
-            // Add more filter checks...
+            if filter.exclude_password_protected && device.requires_password() {
+                return None;
+            }
         }
 
-        // Update last_seen
-        device.last_seen = Some(std::time::Instant::now());
-
-        // Check if this is new or updated
-        let event = if self.known_devices.contains_key(&device_id) {
-            DiscoveryEvent::Updated(device.clone())
-        } else {
-            DiscoveryEvent::Added(device.clone())
+        let status = DeviceStatus::of(&device);
+        let event = match self.reported.get(&device.id) {
+            Some(previous) => update_event(previous, device),
+            None => DiscoveryEvent::Added(device),
         };
-
-        self.known_devices.insert(device_id, device);
-
+        self.reported.insert(event.device_id().to_string(), status);
         Some(event)
     }
 
     fn handle_removed(&mut self, fullname: &str) -> Option<DiscoveryEvent> {
-        // Find device ID by fullname
-        let device_id = self.fullname_map.get(fullname).cloned();
-
-        if let Some(id) = device_id {
-            self.fullname_map.remove(fullname);
-            // We only remove the device if ALL services are gone?
-            // Currently simplified: if any service is removed, we send removed event?
-            // No, we should probably check if other services map to the same ID.
-            // But for now, if we lose a service, we might assume device is gone or just update?
-            // If we remove it from known_devices, it's GONE.
-
-            // Better logic: Check if other fullnames map to this ID.
-            let has_other_services = self.fullname_map.values().any(|v| v == &id);
-
-            if has_other_services {
-                // Maybe update?
-                // For now, ignoring partial removal (e.g. RAOP gone but AirPlay 2 stays).
-                // Ideally we should update the device to reflect lost capabilities.
-                None
-            } else {
-                self.known_devices.remove(&id);
-                Some(DiscoveryEvent::Removed(id))
-            }
-        } else {
-            None
+        match self.merger.removed(fullname)? {
+            // Still reachable through its other service
+            Ok(device) if self.reported.contains_key(&device.id) => self.report(device),
+            Ok(_) => None,
+            Err(id) => self
+                .reported
+                .remove(&id)
+                .map(|_| DiscoveryEvent::Removed(id)),
         }
     }
 }
@@ -386,25 +381,10 @@ impl Stream for DeviceBrowserStream {
                 .is_ready()
             {
                 let stale_timeout = std::time::Duration::from_secs(360); // 3 missed 120s heartbeats
-                let now = std::time::Instant::now();
-                let stale_ids: Vec<String> = self
-                    .known_devices
-                    .iter()
-                    .filter_map(|(id, device)| {
-                        if let Some(last_seen) = device.last_seen {
-                            if now.duration_since(last_seen) > stale_timeout {
-                                return Some(id.clone());
-                            }
-                        }
-                        None
-                    })
-                    .collect();
-
-                if let Some(id) = stale_ids.into_iter().next() {
-                    self.known_devices.remove(&id);
-                    // Also clean up fullname map
-                    self.fullname_map.retain(|_, v| v != &id);
-                    return Poll::Ready(Some(DiscoveryEvent::Removed(id)));
+                if let Some(id) = self.merger.prune(stale_timeout) {
+                    if self.reported.remove(&id).is_some() {
+                        return Poll::Ready(Some(DiscoveryEvent::Removed(id)));
+                    }
                 }
             }
 
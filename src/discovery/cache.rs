@@ -0,0 +1,351 @@
+//! Persistent cache of discovered devices
+//!
+//! mDNS answers can take seconds to arrive. The cache remembers devices seen
+//! in earlier sessions so they can be listed immediately on startup, while a
+//! background browse refreshes their addresses and capabilities.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+
+use super::{DiscoveryEvent, DiscoveryOptions, discover_with_options, scan_with_options};
+use crate::error::AirPlayError;
+use crate::types::{AirPlayDevice, DeviceCapabilities, RaopCapabilities};
+
+/// How stale a stored sighting time may get before an unchanged device is
+/// rewritten
+const LAST_SEEN_RESOLUTION: Duration = Duration::from_secs(3600);
+
+/// A device as stored in the cache
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedDevice {
+    /// Device identifier
+    pub id: String,
+    /// Friendly name
+    pub name: String,
+    /// Model identifier
+    pub model: Option<String>,
+    /// Last known addresses
+    pub addresses: Vec<IpAddr>,
+    /// `AirPlay` port
+    pub port: u16,
+    /// RAOP port, if the device advertised `_raop._tcp`
+    pub raop_port: Option<u16>,
+    /// Raw features bitmask
+    pub features: u64,
+    /// TXT records from the last announcement
+    pub txt_records: HashMap<String, String>,
+    /// When the device was last seen, in seconds since the Unix epoch
+    pub last_seen: u64,
+}
+
+impl CachedDevice {
+    fn new(device: &AirPlayDevice, now: SystemTime) -> Self {
+        Self {
+            id: device.id.clone(),
+            name: device.name.clone(),
+            model: device.model.clone(),
+            addresses: device.addresses.clone(),
+            port: device.port,
+            raop_port: device.raop_port,
+            features: device.capabilities.raw_features,
+            txt_records: device.txt_records.clone(),
+            last_seen: unix_secs(now),
+        }
+    }
+
+    /// Rebuild the device description
+    ///
+    /// `last_seen` is left unset since the device has not been seen by this
+    /// process.
+    #[must_use]
+    pub fn to_device(&self) -> AirPlayDevice {
+        let mut capabilities = DeviceCapabilities::from_features(self.features);
+        capabilities.update_auth_hints(&self.txt_records);
+        AirPlayDevice {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            model: self.model.clone(),
+            addresses: self.addresses.clone(),
+            port: self.port,
+            capabilities,
+            raop_port: self.raop_port,
+            raop_capabilities: self
+                .raop_port
+                .map(|_| RaopCapabilities::from_txt_records(&self.txt_records)),
+            txt_records: self.txt_records.clone(),
+            last_seen: None,
+        }
+    }
+
+    /// Whether the cached details match a fresh announcement
+    fn same_details(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.model == other.model
+            && self.addresses == other.addresses
+            && self.port == other.port
+            && self.raop_port == other.raop_port
+            && self.features == other.features
+            && self.txt_records == other.txt_records
+    }
+}
+
+/// Cache of previously discovered devices, optionally backed by a JSON file
+#[derive(Debug, Default)]
+pub struct DeviceCache {
+    path: Option<PathBuf>,
+    devices: HashMap<String, CachedDevice>,
+    max_age: Option<Duration>,
+}
+
+impl DeviceCache {
+    /// Create a cache that is not persisted
+    #[must_use]
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Open a cache file, loading any devices it already contains
+    ///
+    /// A missing file yields an empty cache; it is created on the first save.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file exists but cannot be read or parsed.
+    pub async fn open(path: impl AsRef<Path>) -> Result<Self, AirPlayError> {
+        let path = path.as_ref().to_path_buf();
+        let devices = match tokio::fs::read(&path).await {
+            Ok(bytes) if bytes.is_empty() => HashMap::new(),
+            Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| AirPlayError::IoError {
+                message: format!("invalid device cache {}: {e}", path.display()),
+                source: Some(Box::new(e)),
+            })?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(Self {
+            path: Some(path),
+            devices,
+            max_age: None,
+        })
+    }
+
+    /// Ignore devices not seen for longer than `max_age`
+    #[must_use]
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Backing file, if persistent
+    #[must_use]
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Number of cached devices, including expired ones
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.devices.len()
+    }
+
+    /// Whether the cache holds no devices
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.devices.is_empty()
+    }
+
+    /// Cached entries that have not expired
+    pub fn entries(&self) -> impl Iterator<Item = &CachedDevice> {
+        let cutoff = self
+            .max_age
+            .map(|age| unix_secs(SystemTime::now()).saturating_sub(age.as_secs()));
+        self.devices
+            .values()
+            .filter(move |entry| cutoff.is_none_or(|cutoff| entry.last_seen >= cutoff))
+    }
+
+    /// Cached devices that have not expired
+    #[must_use]
+    pub fn devices(&self) -> Vec<AirPlayDevice> {
+        self.entries().map(CachedDevice::to_device).collect()
+    }
+
+    /// Look up a cached device by ID
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<AirPlayDevice> {
+        self.entries()
+            .find(|entry| entry.id == id)
+            .map(CachedDevice::to_device)
+    }
+
+    /// Record a device sighting
+    ///
+    /// Returns `true` if the device is new, its details changed, or its stored
+    /// sighting time is more than an hour old, i.e. the cache should be saved.
+    pub fn insert(&mut self, device: &AirPlayDevice) -> bool {
+        let entry = CachedDevice::new(device, SystemTime::now());
+        let changed = self.devices.get(&entry.id).is_none_or(|old| {
+            !old.same_details(&entry)
+                || entry.last_seen.saturating_sub(old.last_seen) > LAST_SEEN_RESOLUTION.as_secs()
+        });
+        self.devices.insert(entry.id.clone(), entry);
+        changed
+    }
+
+    /// Forget a device
+    pub fn remove(&mut self, id: &str) -> bool {
+        self.devices.remove(id).is_some()
+    }
+
+    /// Drop entries older than the configured maximum age
+    pub fn prune(&mut self) {
+        let fresh: Vec<String> = self.entries().map(|entry| entry.id.clone()).collect();
+        self.devices.retain(|id, _| fresh.contains(id));
+    }
+
+    /// Write the cache to its backing file
+    ///
+    /// Does nothing for an in-memory cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written.
+    pub async fn save(&self) -> Result<(), AirPlayError> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let json = serde_json::to_vec_pretty(&self.devices).map_err(|e| AirPlayError::IoError {
+            message: format!("failed to serialize device cache: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+        tokio::fs::write(path, json).await?;
+        Ok(())
+    }
+
+    /// Run a scan and merge the results into the cache
+    ///
+    /// Returns every unexpired device, cached or fresh. The cache is saved if
+    /// anything changed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if discovery fails or the cache cannot be saved.
+    pub async fn refresh(
+        &mut self,
+        options: DiscoveryOptions,
+    ) -> Result<Vec<AirPlayDevice>, AirPlayError> {
+        let found = scan_with_options(options).await?;
+        self.merge(found).await
+    }
+
+    /// Merge scan results, returning them followed by the unexpired cached
+    /// devices they did not cover
+    async fn merge(
+        &mut self,
+        found: Vec<AirPlayDevice>,
+    ) -> Result<Vec<AirPlayDevice>, AirPlayError> {
+        let mut changed = false;
+        for device in &found {
+            changed |= self.insert(device);
+        }
+        if changed {
+            self.save().await?;
+        }
+
+        let cached: Vec<AirPlayDevice> = self
+            .devices()
+            .into_iter()
+            .filter(|cached| !found.iter().any(|d| d.id == cached.id))
+            .collect();
+        let mut devices = found;
+        devices.extend(cached);
+        Ok(devices)
+    }
+}
+
+/// Scan for devices, answering from the cache
+///
+/// Returns the unexpired cached devices at once, along with a task that
+/// scans over mDNS in the background and merges the results into the cache.
+/// The task resolves to the same list [`DeviceCache::refresh`] returns. The
+/// cache is only locked while reading and merging, not during the scan.
+pub async fn scan_cached(
+    cache: Arc<Mutex<DeviceCache>>,
+    options: DiscoveryOptions,
+) -> (
+    Vec<AirPlayDevice>,
+    JoinHandle<Result<Vec<AirPlayDevice>, AirPlayError>>,
+) {
+    let cached = cache.lock().await.devices();
+    let refresh = tokio::spawn(async move {
+        let found = scan_with_options(options).await?;
+        cache.lock().await.merge(found).await
+    });
+    (cached, refresh)
+}
+
+/// Discover devices, starting with those already in the cache
+///
+/// Cached devices are yielded immediately as [`DiscoveryEvent::Added`]. Live
+/// mDNS results follow; a live announcement for a device that was cached is
+/// reported as [`DiscoveryEvent::Updated`]. Sightings are written back to the
+/// cache, which is saved whenever a device's details change. Removals are
+/// passed through but do not evict the cached entry.
+///
+/// # Errors
+///
+/// Returns an error if the mDNS daemon cannot be initialized.
+pub async fn discover_cached(
+    cache: Arc<Mutex<DeviceCache>>,
+    options: DiscoveryOptions,
+) -> Result<impl Stream<Item = DiscoveryEvent> + 'static, AirPlayError> {
+    let live = discover_with_options(options)?;
+    let cached: Vec<DiscoveryEvent> = cache
+        .lock()
+        .await
+        .devices()
+        .into_iter()
+        .map(DiscoveryEvent::Added)
+        .collect();
+
+    let live = live.then(move |event| {
+        let cache = cache.clone();
+        async move {
+            let Some(device) = event.device() else {
+                return event;
+            };
+
+            let mut cache = cache.lock().await;
+            let known = cache.get(&device.id).is_some();
+            if cache.insert(device) {
+                if let Err(e) = cache.save().await {
+                    tracing::warn!("Failed to save device cache: {e}");
+                }
+            }
+
+            match event {
+                DiscoveryEvent::Added(device) if known => DiscoveryEvent::Updated(device),
+                event => event,
+            }
+        }
+    });
+
+    Ok(futures::stream::iter(cached).chain(live))
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
@@ -1,28 +1,76 @@
 //! mDNS device discovery for `AirPlay` devices
+//!
+//! TXT record parsing and name handling are always available; browsing,
+//! advertising, the device cache and wake-on-LAN need the `tokio-runtime`
+//! feature.
 
 /// RAOP service advertisement
+#[cfg(feature = "tokio-runtime")]
 pub mod advertiser;
+#[cfg(feature = "tokio-runtime")]
 mod browser;
+/// Persistent cache of discovered devices
+#[cfg(feature = "tokio-runtime")]
+pub mod cache;
+/// Unicode normalization and matching of device names
+pub mod names;
 pub mod parser;
 /// RAOP discovery logic
 pub mod raop;
 #[cfg(test)]
 mod tests;
+/// Typed TXT record schema and views
+pub mod txt;
+/// Wake-on-LAN support for sleeping devices
+#[cfg(feature = "tokio-runtime")]
+pub mod wake;
 
+#[cfg(feature = "tokio-runtime")]
 use std::time::Duration;
 
-pub use browser::{DeviceBrowser, DeviceFilter, DiscoveryEvent, DiscoveryOptions};
+#[cfg(feature = "tokio-runtime")]
+pub use browser::{DeviceBrowser, DeviceFilter, DeviceStatus, DiscoveryEvent, DiscoveryOptions};
+#[cfg(feature = "tokio-runtime")]
 use futures::Stream;
+pub use names::{fold_name, name_contains, normalize_name};
 pub use parser::parse_txt_records;
+pub use txt::{AirPlayTxt, RaopTxt, SourceVersion, TxtFields, TxtSchema, TxtValue};
+#[cfg(feature = "tokio-runtime")]
+pub use wake::{WakeOptions, wake_device};
 
+#[cfg(feature = "tokio-runtime")]
 use crate::error::AirPlayError;
-use crate::types::{AirPlayConfig, AirPlayDevice};
+#[cfg(feature = "sender")]
+use crate::types::AirPlayConfig;
+#[cfg(feature = "tokio-runtime")]
+use crate::types::AirPlayDevice;
 
 /// Service type for `AirPlay` discovery
 pub const AIRPLAY_SERVICE_TYPE: &str = "_airplay._tcp.local.";
 
 pub use raop::RAOP_SERVICE_TYPE;
 
+/// Restrict an mDNS daemon to the named network interfaces
+///
+/// An empty list leaves the daemon on every interface.
+#[cfg(feature = "tokio-runtime")]
+pub(crate) fn restrict_interfaces(
+    daemon: &mdns_sd::ServiceDaemon,
+    interfaces: &[String],
+) -> Result<(), mdns_sd::Error> {
+    if interfaces.is_empty() {
+        return Ok(());
+    }
+
+    daemon.disable_interface(mdns_sd::IfKind::All)?;
+    daemon.enable_interface(
+        interfaces
+            .iter()
+            .map(|name| mdns_sd::IfKind::Name(name.clone()))
+            .collect::<Vec<_>>(),
+    )
+}
+
 /// Discover `AirPlay` devices continuously
 ///
 /// Returns a stream that yields devices as they are discovered.
@@ -55,9 +103,9 @@ pub use raop::RAOP_SERVICE_TYPE;
 /// # Errors
 ///
 /// Returns an error if the mDNS daemon cannot be initialized.
+#[cfg(feature = "tokio-runtime")]
 pub fn discover() -> Result<impl Stream<Item = DiscoveryEvent> + 'static, AirPlayError> {
-    let browser = DeviceBrowser::new(&AirPlayConfig::default());
-    browser.browse()
+    discover_with_options(DiscoveryOptions::default())
 }
 
 /// Discover devices with custom configuration
@@ -65,6 +113,7 @@ pub fn discover() -> Result<impl Stream<Item = DiscoveryEvent> + 'static, AirPla
 /// # Errors
 ///
 /// Returns an error if the mDNS daemon cannot be initialized.
+#[cfg(feature = "sender")]
 pub fn discover_with_config(
     config: &AirPlayConfig,
 ) -> Result<impl Stream<Item = DiscoveryEvent> + 'static, AirPlayError> {
@@ -77,6 +126,7 @@ pub fn discover_with_config(
 /// # Errors
 ///
 /// Returns an error if the mDNS daemon cannot be initialized.
+#[cfg(feature = "tokio-runtime")]
 pub fn discover_with_options(
     options: DiscoveryOptions,
 ) -> Result<impl Stream<Item = DiscoveryEvent> + 'static, AirPlayError> {
@@ -86,7 +136,9 @@ pub fn discover_with_options(
 
 /// Scan for devices with timeout
 ///
-/// Performs a one-shot scan and returns all discovered devices.
+/// Performs a one-shot scan and returns all discovered devices. To list
+/// previously seen devices without waiting, use
+/// [`cache::scan_cached`].
 ///
 /// # Arguments
 ///
@@ -112,8 +164,13 @@ pub fn discover_with_options(
 /// # Errors
 ///
 /// Returns an error if the mDNS daemon cannot be initialized.
+#[cfg(feature = "tokio-runtime")]
 pub async fn scan(timeout: Duration) -> Result<Vec<AirPlayDevice>, AirPlayError> {
-    scan_with_config(timeout, AirPlayConfig::default()).await
+    scan_with_options(DiscoveryOptions {
+        timeout,
+        ..DiscoveryOptions::default()
+    })
+    .await
 }
 
 /// Scan for devices with custom configuration
@@ -121,6 +178,7 @@ pub async fn scan(timeout: Duration) -> Result<Vec<AirPlayDevice>, AirPlayError>
 /// # Errors
 ///
 /// Returns an error if the mDNS daemon cannot be initialized.
+#[cfg(feature = "sender")]
 pub async fn scan_with_config(
     timeout: Duration,
     config: AirPlayConfig,
@@ -146,12 +204,14 @@ pub async fn scan_with_config(
             }
             event = stream.next() => {
                 match event {
-                    Some(DiscoveryEvent::Added(device) | DiscoveryEvent::Updated(device)) => {
-                        devices.insert(device.id.clone(), device);
-                    }
                     Some(DiscoveryEvent::Removed(id)) => {
                         devices.remove(&id);
                     }
+                    Some(event) => {
+                        if let Some(device) = event.into_device() {
+                            devices.insert(device.id.clone(), device);
+                        }
+                    }
                     None => break,
                 }
             }
@@ -161,11 +221,93 @@ pub async fn scan_with_config(
     Ok(devices.into_values().collect())
 }
 
+/// Scan until a particular device is found
+///
+/// `name_or_id` matches a device ID (MAC addresses in any common notation) or
+/// a case-insensitive substring of the device name. Returns as soon as the
+/// first matching device resolves, instead of waiting out the whole timeout.
+///
+/// # Errors
+///
+/// Returns an error if the mDNS daemon cannot be initialized, or
+/// [`AirPlayError::DeviceNotFound`] if no matching device appears within
+/// `timeout`.
+#[cfg(feature = "tokio-runtime")]
+pub async fn scan_for(name_or_id: &str, timeout: Duration) -> Result<AirPlayDevice, AirPlayError> {
+    let browser = DeviceBrowser::with_options(DiscoveryOptions::default());
+    scan_for_with_browser(browser, name_or_id, timeout).await
+}
+
+/// Scan until a particular device is found, with custom configuration
+///
+/// See [`scan_for`].
+///
+/// # Errors
+///
+/// Returns an error if the mDNS daemon cannot be initialized or the device is
+/// not found within `timeout`.
+#[cfg(feature = "sender")]
+pub async fn scan_for_with_config(
+    name_or_id: &str,
+    timeout: Duration,
+    config: AirPlayConfig,
+) -> Result<AirPlayDevice, AirPlayError> {
+    scan_for_with_browser(DeviceBrowser::new(&config), name_or_id, timeout).await
+}
+
+#[cfg(feature = "tokio-runtime")]
+async fn scan_for_with_browser(
+    browser: DeviceBrowser,
+    name_or_id: &str,
+    timeout: Duration,
+) -> Result<AirPlayDevice, AirPlayError> {
+    use futures::StreamExt;
+
+    let stream = browser.browse()?;
+    let deadline = tokio::time::Instant::now() + timeout;
+
+    tokio::pin!(stream);
+
+    loop {
+        tokio::select! {
+            () = tokio::time::sleep_until(deadline) => break,
+            event = stream.next() => match event {
+                Some(event) => {
+                    if let Some(device) = event
+                        .into_device()
+                        .filter(|device| device_matches(device, name_or_id))
+                    {
+                        return Ok(device);
+                    }
+                }
+                None => break,
+            },
+        }
+    }
+
+    Err(AirPlayError::DeviceNotFound {
+        device_id: name_or_id.to_string(),
+    })
+}
+
+/// Whether a device's ID or name matches a user-supplied query
+#[cfg(feature = "tokio-runtime")]
+pub(crate) fn device_matches(device: &AirPlayDevice, name_or_id: &str) -> bool {
+    let query = name_or_id.trim();
+    if query.is_empty() {
+        return false;
+    }
+    device.id.eq_ignore_ascii_case(query)
+        || raop::normalize_device_id(&device.id) == raop::normalize_device_id(query)
+        || names::name_contains(&device.name, query)
+}
+
 /// Scan for devices with custom options
 ///
 /// # Errors
 ///
 /// Returns an error if the mDNS daemon cannot be initialized.
+#[cfg(feature = "tokio-runtime")]
 pub async fn scan_with_options(
     options: DiscoveryOptions,
 ) -> Result<Vec<AirPlayDevice>, AirPlayError> {
@@ -189,12 +331,14 @@ pub async fn scan_with_options(
             }
             event = stream.next() => {
                 match event {
-                    Some(DiscoveryEvent::Added(device) | DiscoveryEvent::Updated(device)) => {
-                        devices.insert(device.id.clone(), device);
-                    }
                     Some(DiscoveryEvent::Removed(id)) => {
                         devices.remove(&id);
                     }
+                    Some(event) => {
+                        if let Some(device) = event.into_device() {
+                            devices.insert(device.id.clone(), device);
+                        }
+                    }
                     None => break,
                 }
             }
@@ -0,0 +1,99 @@
+//! Device name normalization
+//!
+//! Names arrive in whatever Unicode form the advertiser used: macOS and iOS
+//! publish NFD on some versions and NFC on others, and users type straight
+//! quotes where the device name has curly ones. Names are stored in NFC,
+//! and compared after [`fold_name`] so "Théo’s HomePod 🎵" is found by
+//! `théo's homepod`.
+
+use unicode_normalization::UnicodeNormalization;
+#[cfg(feature = "tokio-runtime")]
+use unicode_normalization::char::is_combining_mark;
+
+/// Longest DNS label, in bytes
+#[cfg(feature = "tokio-runtime")]
+const MAX_LABEL_LEN: usize = 63;
+
+/// Hostname label used when a name has no usable characters
+#[cfg(feature = "tokio-runtime")]
+const FALLBACK_HOST_LABEL: &str = "airplay-receiver";
+
+/// Put a device name in NFC, the form it is stored and displayed in
+#[must_use]
+pub fn normalize_name(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// Fold a name for case- and form-insensitive comparison
+///
+/// Applies NFKC, lower-cases, maps curly quotes to straight ones and
+/// collapses runs of whitespace.
+#[must_use]
+pub fn fold_name(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for word in name
+        .nfkc()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            '\u{2018}' | '\u{2019}' | '\u{02BC}' | '\u{2032}' => '\'',
+            '\u{201C}' | '\u{201D}' => '"',
+            c => c,
+        })
+        .collect::<String>()
+        .split_whitespace()
+    {
+        if !folded.is_empty() {
+            folded.push(' ');
+        }
+        folded.push_str(word);
+    }
+    folded
+}
+
+/// Whether `query` occurs in `name` once both are folded
+#[must_use]
+pub fn name_contains(name: &str, query: &str) -> bool {
+    let query = fold_name(query);
+    !query.is_empty() && fold_name(name).contains(&query)
+}
+
+/// Service instance name `prefix` + `name`, cut to fit a DNS label
+///
+/// The name is put in NFC and truncated on a character boundary so the
+/// whole instance name stays within 63 bytes of UTF-8.
+#[cfg(feature = "tokio-runtime")]
+pub(crate) fn instance_name(prefix: &str, name: &str) -> String {
+    let mut instance = prefix.to_string();
+    for c in normalize_name(name).chars() {
+        if instance.len() + c.len_utf8() > MAX_LABEL_LEN {
+            break;
+        }
+        instance.push(c);
+    }
+    instance.trim_end().to_string()
+}
+
+/// ASCII hostname label derived from a device name
+///
+/// Accents are stripped, anything else outside `[a-z0-9]` becomes a
+/// hyphen, and the result is cut to a DNS label.
+#[cfg(feature = "tokio-runtime")]
+pub(crate) fn host_label(name: &str) -> String {
+    let mut label = String::new();
+    for c in name.nfkd().filter(|&c| !is_combining_mark(c)) {
+        if label.len() == MAX_LABEL_LEN {
+            break;
+        }
+        if c.is_ascii_alphanumeric() {
+            label.push(c.to_ascii_lowercase());
+        } else if !label.is_empty() && !label.ends_with('-') {
+            label.push('-');
+        }
+    }
+    let label = label.trim_end_matches('-');
+    if label.is_empty() {
+        FALLBACK_HOST_LABEL.to_string()
+    } else {
+        label.to_string()
+    }
+}
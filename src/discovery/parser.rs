@@ -2,6 +2,7 @@
 
 use std::collections::HashMap;
 
+use super::txt::AirPlayTxt;
 use crate::types::DeviceCapabilities;
 
 /// Parse TXT records from mDNS response
@@ -53,6 +54,28 @@ fn parse_hex(s: &str) -> Option<u64> {
     u64::from_str_radix(s, 16).ok()
 }
 
+/// Parse a MAC address
+///
+/// Accepts six hex octets separated by `:` or `-` ("AA:BB:CC:DD:EE:FF"), or
+/// twelve bare hex digits as used in RAOP service names ("AABBCCDDEEFF").
+#[must_use]
+pub fn parse_mac_address(s: &str) -> Option<[u8; 6]> {
+    let digits: String = s
+        .trim()
+        .chars()
+        .filter(|c| *c != ':' && *c != '-')
+        .collect();
+    if digits.len() != 12 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let mut mac = [0u8; 6];
+    for (i, octet) in mac.iter_mut().enumerate() {
+        *octet = u8::from_str_radix(&digits[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(mac)
+}
+
 /// Parse device model from model string
 #[must_use]
 pub fn parse_model_name(model: &str) -> &str {
@@ -100,6 +123,134 @@ pub mod txt_keys {
     pub const IS_GROUP_LEADER: &str = "igl";
     /// `AirPlay` version
     pub const AIRPLAY_VERSION: &str = "am";
+    /// RAOP status flags
+    pub const RAOP_STATUS_FLAGS: &str = "sf";
+    /// Access control level (0 = everyone)
+    pub const ACCESS_CONTROL: &str = "acl";
+    /// Public system identifier
+    pub const PUBLIC_SYSTEM_ID: &str = "psi";
+    /// Pairing identity
+    pub const PAIRING_ID: &str = "pi";
+    /// RAOP features bitmask
+    pub const RAOP_FEATURES: &str = "ft";
+    /// RAOP source version
+    pub const RAOP_SOURCE_VERSION: &str = "vs";
+}
+
+/// String-typed view of an `AirPlay` (or RAOP) TXT record
+///
+/// Kept for callers written before [`AirPlayTxt`]; it is parsed through the
+/// same schema, with the source version as the advertised text. Prefer
+/// [`AirPlayTxt`], which also keeps unknown keys and orders versions.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AirPlayTxtRecord {
+    /// Device ID, normally the MAC address (`deviceid`)
+    pub device_id: Option<String>,
+    /// Features bitmask (`features` / `ft`)
+    pub features: Option<u64>,
+    /// Status flags (`flags` / `sf`), see [`status_flags`]
+    pub status_flags: Option<u32>,
+    /// Model identifier (`model` / `am`)
+    pub model: Option<String>,
+    /// Source version (`srcvers` / `vs`)
+    pub source_version: Option<String>,
+    /// Protocol version (`protovers`)
+    pub protocol_version: Option<String>,
+    /// Ed25519 public key (`pk`)
+    pub public_key: Option<Vec<u8>>,
+    /// Pairing identity (`pi`)
+    pub pairing_id: Option<String>,
+    /// Public system identifier (`psi`)
+    pub public_system_id: Option<String>,
+    /// Password required (`pw`)
+    pub password: bool,
+    /// Access control level (`acl`), 0 = everyone
+    pub access_control_level: Option<u32>,
+    /// Group UUID (`gid`)
+    pub group_id: Option<String>,
+    /// Device is the group leader (`igl`)
+    pub is_group_leader: bool,
+    /// Group contains a discoverable leader (`gcgl`)
+    pub group_contains_leader: bool,
+    /// Advertised volume (`vv`)
+    pub volume: Option<f32>,
+}
+
+impl AirPlayTxtRecord {
+    /// Parse raw TXT key/value pairs
+    #[must_use]
+    pub fn parse(records: &HashMap<String, String>) -> Self {
+        AirPlayTxt::parse(records).into()
+    }
+
+    /// Whether a status flag bit is set
+    #[must_use]
+    pub fn has_status_flag(&self, flag: u32) -> bool {
+        self.status_flags.is_some_and(|flags| flags & flag != 0)
+    }
+
+    /// Hardware address from the device ID, if it is a MAC address
+    #[must_use]
+    pub fn mac_address(&self) -> Option<[u8; 6]> {
+        self.device_id.as_deref().and_then(parse_mac_address)
+    }
+}
+
+impl From<AirPlayTxt> for AirPlayTxtRecord {
+    fn from(txt: AirPlayTxt) -> Self {
+        Self {
+            device_id: txt.device_id,
+            features: txt.features,
+            status_flags: txt.status_flags,
+            model: txt.model,
+            source_version: txt
+                .source_version
+                .map(|version| version.as_str().to_owned()),
+            protocol_version: txt.protocol_version,
+            public_key: txt.public_key,
+            pairing_id: txt.pairing_id,
+            public_system_id: txt.public_system_id,
+            password: txt.password,
+            access_control_level: txt.access_control_level,
+            group_id: txt.group_id,
+            is_group_leader: txt.is_group_leader,
+            group_contains_leader: txt.group_contains_leader,
+            volume: txt.volume,
+        }
+    }
+}
+
+/// `AirPlay` status flag bits (`flags` / `sf` TXT records)
+///
+/// Reference: <https://emanuelecozzi.net/docs/airplay2/discovery>
+pub mod status_flags {
+    /// Problem detected
+    pub const PROBLEM_DETECTED: u32 = 1 << 0;
+    /// Device not configured
+    pub const NOT_CONFIGURED: u32 = 1 << 1;
+    /// Audio cable attached
+    pub const AUDIO_CABLE_ATTACHED: u32 = 1 << 2;
+    /// PIN must be entered to pair
+    pub const PIN_REQUIRED: u32 = 1 << 3;
+    /// Password required
+    pub const PASSWORD_REQUIRED: u32 = 1 << 7;
+    /// One-time pairing required before connecting
+    pub const ONE_TIME_PAIRING_REQUIRED: u32 = 1 << 9;
+    /// Device set up for `HomeKit` access control
+    pub const HOMEKIT_ACCESS_CONTROL: u32 = 1 << 10;
+    /// A sender is currently playing to the device
+    pub const RECEIVER_SESSION_ACTIVE: u32 = 1 << 17;
+}
+
+/// Parse a status flags value, which may be hex ("0x44") or decimal
+#[must_use]
+pub fn parse_status_flags(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if value.starts_with("0x") || value.starts_with("0X") {
+        parse_hex(value).and_then(|flags| u32::try_from(flags).ok())
+    } else {
+        value.parse().ok()
+    }
 }
 
 /// `AirPlay` feature bits
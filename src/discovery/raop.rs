@@ -1,5 +1,19 @@
 //! RAOP (AirPlay 1) service discovery logic
 
+#[cfg(feature = "tokio-runtime")]
+use std::collections::HashMap;
+#[cfg(feature = "tokio-runtime")]
+use std::net::IpAddr;
+#[cfg(feature = "tokio-runtime")]
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "tokio-runtime")]
+use super::names::normalize_name;
+#[cfg(feature = "tokio-runtime")]
+use super::parser;
+#[cfg(feature = "tokio-runtime")]
+use crate::types::{AirPlayDevice, DeviceCapabilities, RaopCapabilities};
+
 /// RAOP service type for mDNS discovery
 pub const RAOP_SERVICE_TYPE: &str = "_raop._tcp.local.";
 
@@ -36,3 +50,262 @@ pub fn format_mac_address(mac: &str) -> String {
         .collect::<Vec<_>>()
         .join(":")
 }
+
+/// Normalize a device ID so RAOP and `AirPlay` 2 announcements agree
+///
+/// MAC addresses, with or without separators, become upper-case and
+/// colon-separated ("aabbccddeeff" -> "AA:BB:CC:DD:EE:FF"). Other IDs are
+/// returned unchanged.
+#[must_use]
+pub fn normalize_device_id(id: &str) -> String {
+    super::parser::parse_mac_address(id).map_or_else(
+        || id.to_string(),
+        |mac| {
+            mac.iter()
+                .map(|octet| format!("{octet:02X}"))
+                .collect::<Vec<_>>()
+                .join(":")
+        },
+    )
+}
+
+/// A resolved `_airplay._tcp` or `_raop._tcp` service
+#[cfg(feature = "tokio-runtime")]
+#[derive(Debug, Clone)]
+pub(crate) struct ServiceRecord {
+    /// Service type the record was found under
+    pub service_type: String,
+    /// Full service instance name
+    pub fullname: String,
+    /// Advertised port
+    pub port: u16,
+    /// Resolved addresses
+    pub addresses: Vec<IpAddr>,
+    /// TXT records
+    pub txt_records: HashMap<String, String>,
+}
+
+#[cfg(feature = "tokio-runtime")]
+impl ServiceRecord {
+    fn is_raop(&self) -> bool {
+        self.service_type == RAOP_SERVICE_TYPE
+    }
+
+    /// Device ID announced by this record
+    fn device_id(&self) -> String {
+        if self.is_raop() {
+            parse_raop_service_name(&self.fullname).map_or_else(
+                || self.fullname.clone(),
+                |(mac, _)| format_mac_address(&mac),
+            )
+        } else {
+            self.txt_records
+                .get("deviceid")
+                .map(|id| normalize_device_id(id))
+                .or_else(|| self.txt_records.get("pk").cloned())
+                .unwrap_or_else(|| self.fullname.clone())
+        }
+    }
+
+    /// Friendly name from the service instance name
+    fn friendly_name(&self) -> Option<String> {
+        if self.is_raop() {
+            parse_raop_service_name(&self.fullname).map(|(_, name)| normalize_name(&name))
+        } else {
+            self.fullname
+                .strip_suffix(&format!(".{}", self.service_type))
+                .filter(|name| !name.is_empty())
+                .map(normalize_name)
+        }
+    }
+}
+
+/// Correlates `_airplay._tcp` and `_raop._tcp` announcements into devices
+///
+/// Both services of one device are keyed by its MAC address: the `deviceid`
+/// TXT record for `AirPlay` 2 and the `MAC@Name` instance name for RAOP.
+/// Records that cannot be matched that way are paired by friendly name and
+/// address. `AirPlay` 2 data takes precedence for the device name, port,
+/// capabilities and shared TXT keys; RAOP-only devices (`AirPort` Express,
+/// shairport-sync) are described from their RAOP record alone.
+#[cfg(feature = "tokio-runtime")]
+#[derive(Debug, Default)]
+pub(crate) struct ServiceMerger {
+    devices: HashMap<String, AirPlayDevice>,
+    /// Service full name -> device ID
+    services: HashMap<String, String>,
+}
+
+#[cfg(feature = "tokio-runtime")]
+impl ServiceMerger {
+    /// Merge a resolved service, returning the updated device
+    pub fn resolved(&mut self, record: &ServiceRecord) -> Option<AirPlayDevice> {
+        if record.addresses.is_empty() {
+            return None;
+        }
+
+        let id = self
+            .services
+            .get(&record.fullname)
+            .cloned()
+            .or_else(|| Some(record.device_id()).filter(|id| self.devices.contains_key(id)))
+            .or_else(|| self.correlate(record))
+            .unwrap_or_else(|| record.device_id());
+        self.services.insert(record.fullname.clone(), id.clone());
+        let has_airplay2 = self.has_service(&id, super::AIRPLAY_SERVICE_TYPE);
+
+        let device = self
+            .devices
+            .entry(id.clone())
+            .or_insert_with(|| AirPlayDevice {
+                id,
+                name: String::new(),
+                model: None,
+                addresses: Vec::new(),
+                port: 0,
+                capabilities: DeviceCapabilities::default(),
+                raop_port: None,
+                raop_capabilities: None,
+                txt_records: HashMap::new(),
+                last_seen: None,
+            });
+
+        for addr in &record.addresses {
+            if !device.addresses.contains(addr) {
+                device.addresses.push(*addr);
+            }
+        }
+
+        if record.is_raop() {
+            for (key, value) in &record.txt_records {
+                if !has_airplay2 || !device.txt_records.contains_key(key) {
+                    device.txt_records.insert(key.clone(), value.clone());
+                }
+            }
+            device
+                .txt_records
+                .entry("deviceid".to_string())
+                .or_insert_with(|| device.id.clone());
+            device.raop_port = Some(record.port);
+            device.raop_capabilities =
+                Some(RaopCapabilities::from_txt_records(&record.txt_records));
+
+            if !has_airplay2 {
+                device.port = record.port;
+                if let Some(caps) = record
+                    .txt_records
+                    .get("ft")
+                    .and_then(|ft| parser::parse_features(ft))
+                {
+                    device.capabilities = caps;
+                }
+                if let Some(name) = record.friendly_name() {
+                    device.name = name;
+                }
+            }
+        } else {
+            device.txt_records.extend(record.txt_records.clone());
+            device.port = record.port;
+            if let Some(caps) = record
+                .txt_records
+                .get("features")
+                .and_then(|features| parser::parse_features(features))
+            {
+                device.capabilities = caps;
+            }
+            if let Some(name) = record.friendly_name() {
+                device.name = name;
+            }
+        }
+
+        if device.name.is_empty() {
+            device.name = record
+                .txt_records
+                .get("model")
+                .cloned()
+                .unwrap_or_else(|| "AirPlay Device".to_string());
+        }
+        if let Some(model) = record
+            .txt_records
+            .get("model")
+            .or_else(|| record.txt_records.get("am"))
+        {
+            if device.model.is_none() || !record.is_raop() {
+                device.model = Some(model.clone());
+            }
+        }
+        device.capabilities.update_auth_hints(&device.txt_records);
+        device.last_seen = Some(Instant::now());
+
+        Some(device.clone())
+    }
+
+    /// Forget a service
+    ///
+    /// Returns `Some(Ok(device))` if the device is still reachable through its
+    /// other service, `Some(Err(id))` if it is gone, and `None` if the service
+    /// was unknown.
+    pub fn removed(&mut self, fullname: &str) -> Option<Result<AirPlayDevice, String>> {
+        let id = self.services.remove(fullname)?;
+        if !self.services.values().any(|other| *other == id) {
+            self.devices.remove(&id);
+            return Some(Err(id));
+        }
+
+        let device = self.devices.get_mut(&id)?;
+        if fullname.ends_with(RAOP_SERVICE_TYPE) {
+            device.raop_port = None;
+            device.raop_capabilities = None;
+        } else if let Some(raop_port) = device.raop_port {
+            device.port = raop_port;
+        }
+        Some(Ok(device.clone()))
+    }
+
+    /// Drop the first device not seen for longer than `timeout`
+    pub fn prune(&mut self, timeout: Duration) -> Option<String> {
+        let now = Instant::now();
+        let id = self
+            .devices
+            .values()
+            .find(|device| {
+                device
+                    .last_seen
+                    .is_some_and(|seen| now.duration_since(seen) > timeout)
+            })?
+            .id
+            .clone();
+        self.devices.remove(&id);
+        self.services.retain(|_, other| *other != id);
+        Some(id)
+    }
+
+    fn has_service(&self, id: &str, service_type: &str) -> bool {
+        self.services
+            .iter()
+            .any(|(fullname, other)| other == id && fullname.ends_with(service_type))
+    }
+
+    /// Find a device announced under the other service type with the same
+    /// name and an overlapping address
+    fn correlate(&self, record: &ServiceRecord) -> Option<String> {
+        let name = record.friendly_name()?;
+        let other_type = if record.is_raop() {
+            super::AIRPLAY_SERVICE_TYPE
+        } else {
+            RAOP_SERVICE_TYPE
+        };
+        self.devices
+            .values()
+            .find(|device| {
+                device.name == name
+                    && device
+                        .addresses
+                        .iter()
+                        .any(|a| record.addresses.contains(a))
+                    && self.has_service(&device.id, other_type)
+                    && !self.has_service(&device.id, &record.service_type)
+            })
+            .map(|device| device.id.clone())
+    }
+}
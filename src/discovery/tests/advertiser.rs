@@ -131,6 +131,21 @@ fn test_service_name_special_characters() {
     assert_eq!(advertiser.service_name(), "AABBCCDDEEFF@John's Speaker");
 }
 
+#[test]
+fn test_service_name_unicode_is_nfc() {
+    let config = AdvertiserConfig {
+        name: "The\u{301}o\u{2019}s HomePod \u{1F3B5}".to_string(),
+        mac_override: Some([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]),
+        ..Default::default()
+    };
+    let advertiser = RaopAdvertiser::new(config).unwrap();
+
+    assert_eq!(
+        advertiser.service_name(),
+        "AABBCCDDEEFF@Th\u{e9}o\u{2019}s HomePod \u{1F3B5}"
+    );
+}
+
 #[test]
 fn test_new_requires_mac() {
     let config = AdvertiserConfig::default();
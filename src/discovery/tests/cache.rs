@@ -0,0 +1,146 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::sync::Mutex;
+
+use crate::discovery::cache::{DeviceCache, discover_cached, scan_cached};
+use crate::discovery::{DiscoveryEvent, DiscoveryOptions};
+use crate::types::{AirPlayDevice, DeviceCapabilities};
+
+fn device(id: &str, ip: &str) -> AirPlayDevice {
+    let mut txt = HashMap::new();
+    txt.insert("features".to_string(), "0x445F8A00,0x1C340".to_string());
+    AirPlayDevice {
+        id: id.to_string(),
+        name: format!("Speaker {id}"),
+        model: Some("AudioAccessory5,1".to_string()),
+        addresses: vec![ip.parse().unwrap()],
+        port: 7000,
+        capabilities: DeviceCapabilities::from_features(0x0001_C340_445F_8A00),
+        raop_port: Some(7000),
+        raop_capabilities: None,
+        txt_records: txt,
+        last_seen: Some(std::time::Instant::now()),
+    }
+}
+
+#[tokio::test]
+async fn test_cache_persists_devices() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("devices.json");
+
+    let mut cache = DeviceCache::open(&path).await.unwrap();
+    assert!(cache.is_empty());
+    assert!(cache.insert(&device("A", "192.168.1.10")));
+    assert!(!cache.insert(&device("A", "192.168.1.10")));
+    assert!(cache.insert(&device("A", "192.168.1.11")));
+    assert!(cache.insert(&device("B", "192.168.1.20")));
+    cache.save().await.unwrap();
+
+    let reloaded = DeviceCache::open(&path).await.unwrap();
+    assert_eq!(reloaded.len(), 2);
+    let a = reloaded.get("A").unwrap();
+    assert_eq!(a.name, "Speaker A");
+    assert_eq!(
+        a.addresses,
+        vec!["192.168.1.11".parse::<std::net::IpAddr>().unwrap()]
+    );
+    assert!(a.supports_airplay2());
+    assert_eq!(a.capabilities.raw_features, 0x0001_C340_445F_8A00);
+    assert!(a.raop_capabilities.is_some());
+    assert!(a.last_seen.is_none());
+}
+
+#[tokio::test]
+async fn test_cache_rejects_corrupt_file_and_expires_entries() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("devices.json");
+    std::fs::write(&path, b"not json").unwrap();
+    assert!(DeviceCache::open(&path).await.is_err());
+
+    let mut cache = DeviceCache::in_memory().with_max_age(Duration::from_secs(3600));
+    cache.insert(&device("A", "10.0.0.1"));
+    assert_eq!(cache.devices().len(), 1);
+    // In-memory caches have nothing to write
+    cache.save().await.unwrap();
+    assert!(cache.path().is_none());
+
+    let mut stale = DeviceCache::in_memory();
+    stale.insert(&device("A", "10.0.0.1"));
+    let json = serde_json::to_string(&stale.entries().cloned().collect::<Vec<_>>()).unwrap();
+    let mut entries: Vec<crate::discovery::cache::CachedDevice> =
+        serde_json::from_str(&json).unwrap();
+    entries[0].last_seen -= 7200;
+    let map: HashMap<_, _> = entries.into_iter().map(|e| (e.id.clone(), e)).collect();
+    std::fs::write(&path, serde_json::to_vec(&map).unwrap()).unwrap();
+
+    let mut cache = DeviceCache::open(&path)
+        .await
+        .unwrap()
+        .with_max_age(Duration::from_secs(3600));
+    assert_eq!(cache.len(), 1);
+    assert!(cache.devices().is_empty());
+    cache.prune();
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn test_discover_cached_yields_cached_devices_first() {
+    let mut cache = DeviceCache::in_memory();
+    cache.insert(&device("A", "10.0.0.1"));
+    cache.insert(&device("B", "10.0.0.2"));
+    let cache = Arc::new(Mutex::new(cache));
+
+    let options = DiscoveryOptions {
+        interfaces: vec!["lo".to_string()],
+        ..Default::default()
+    };
+    let stream = discover_cached(cache, options).await.unwrap();
+    tokio::pin!(stream);
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let event = tokio::time::timeout(Duration::from_millis(100), stream.next())
+            .await
+            .unwrap();
+        match event {
+            Some(DiscoveryEvent::Added(device)) => ids.push(device.id),
+            other => panic!("expected cached device, got {other:?}"),
+        }
+    }
+    ids.sort();
+    assert_eq!(ids, vec!["A", "B"]);
+}
+
+#[tokio::test]
+async fn test_scan_cached_answers_before_scanning() {
+    let mut cache = DeviceCache::in_memory();
+    cache.insert(&device("A", "10.0.0.1"));
+    let cache = Arc::new(Mutex::new(cache));
+
+    let options = DiscoveryOptions {
+        timeout: Duration::from_millis(300),
+        interfaces: vec!["lo".to_string()],
+        ..Default::default()
+    };
+    let (devices, refresh) = tokio::time::timeout(
+        Duration::from_millis(100),
+        scan_cached(cache.clone(), options),
+    )
+    .await
+    .unwrap();
+    assert_eq!(devices.len(), 1);
+    assert_eq!(devices[0].id, "A");
+    assert!(!refresh.is_finished());
+
+    // The cache stays usable while the scan runs
+    let guard = tokio::time::timeout(Duration::from_millis(100), cache.lock())
+        .await
+        .unwrap();
+    drop(guard);
+
+    let refreshed = refresh.await.unwrap().unwrap();
+    assert!(refreshed.iter().any(|device| device.id == "A"));
+}
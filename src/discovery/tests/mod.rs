@@ -1,6 +1,10 @@
 mod advertiser;
+mod cache;
+mod names;
 mod parser_tests;
 mod raop;
+mod status;
+mod wake;
 
 #[tokio::test]
 async fn test_scan_with_timeout() {
@@ -13,3 +17,67 @@ async fn test_scan_with_timeout() {
     assert!(result.is_ok());
 }
 mod advertiser_extra;
+
+#[tokio::test]
+async fn test_scan_restricted_to_interface() {
+    use std::time::Duration;
+
+    use super::{DiscoveryOptions, scan_with_options};
+
+    let options = DiscoveryOptions {
+        timeout: Duration::from_millis(100),
+        ..Default::default()
+    }
+    .interfaces(vec!["lo".to_string()]);
+    assert_eq!(options.interfaces, vec!["lo".to_string()]);
+
+    let result = scan_with_options(options).await;
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_device_matches_name_or_id() {
+    use super::device_matches;
+    use crate::types::AirPlayDevice;
+
+    let device = AirPlayDevice {
+        id: "AA:BB:CC:DD:EE:FF".to_string(),
+        name: "Living Room HomePod".to_string(),
+        model: None,
+        addresses: Vec::new(),
+        port: 7000,
+        capabilities: crate::types::DeviceCapabilities::default(),
+        raop_port: None,
+        raop_capabilities: None,
+        txt_records: std::collections::HashMap::new(),
+        last_seen: None,
+    };
+
+    assert!(device_matches(&device, "living room"));
+    assert!(device_matches(&device, "HomePod"));
+    assert!(device_matches(&device, "aa:bb:cc:dd:ee:ff"));
+    assert!(device_matches(&device, "AABBCCDDEEFF"));
+    assert!(device_matches(&device, "aa-bb-cc-dd-ee-ff"));
+    assert!(!device_matches(&device, "Kitchen"));
+    assert!(!device_matches(&device, "  "));
+
+    let device = AirPlayDevice {
+        name: "The\u{301}o\u{2019}s HomePod \u{1F3B5}".to_string(),
+        ..device
+    };
+    assert!(device_matches(&device, "Th\u{e9}o's homepod"));
+    assert!(device_matches(&device, "\u{1F3B5}"));
+}
+
+#[tokio::test]
+async fn test_scan_for_gives_up_after_timeout() {
+    use std::time::{Duration, Instant};
+
+    use super::scan_for;
+    use crate::error::AirPlayError;
+
+    let start = Instant::now();
+    let result = scan_for("no such device 7f3a", Duration::from_millis(200)).await;
+    assert!(matches!(result, Err(AirPlayError::DeviceNotFound { .. })));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
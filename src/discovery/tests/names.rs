@@ -0,0 +1,53 @@
+use crate::discovery::names::{
+    fold_name, host_label, instance_name, name_contains, normalize_name,
+};
+
+/// `Théo’s HomePod 🎵` with the accent as a combining mark (NFD)
+const NFD_NAME: &str = "The\u{301}o\u{2019}s HomePod \u{1F3B5}";
+
+#[test]
+fn test_normalize_name_composes() {
+    assert_eq!(
+        normalize_name(NFD_NAME),
+        "Th\u{e9}o\u{2019}s HomePod \u{1F3B5}"
+    );
+    assert_eq!(normalize_name("Kitchen"), "Kitchen");
+}
+
+#[test]
+fn test_fold_name() {
+    assert_eq!(fold_name(NFD_NAME), "th\u{e9}o's homepod \u{1F3B5}");
+    assert_eq!(fold_name("  Living\t Room  "), "living room");
+    // Compatibility forms fold to their plain equivalent
+    assert_eq!(fold_name("\u{FF2B}itchen \u{2460}"), "kitchen 1");
+}
+
+#[test]
+fn test_name_contains_ignores_form_case_and_quotes() {
+    assert!(name_contains(NFD_NAME, "TH\u{c9}O'S HOMEPOD"));
+    assert!(name_contains(NFD_NAME, "\u{1F3B5}"));
+    assert!(name_contains(
+        "\u{c5}ngstr\u{f6}m",
+        "a\u{30a}ngstro\u{308}m"
+    ));
+    assert!(!name_contains(NFD_NAME, "theo"));
+    assert!(!name_contains(NFD_NAME, " "));
+}
+
+#[test]
+fn test_instance_name_truncates_on_char_boundary() {
+    let name = "\u{1F3B5}".repeat(20);
+    let instance = instance_name("AABBCCDDEEFF@", &name);
+    assert!(instance.len() <= 63);
+    assert_eq!(instance, format!("AABBCCDDEEFF@{}", "\u{1F3B5}".repeat(12)));
+
+    assert_eq!(instance_name("", NFD_NAME), normalize_name(NFD_NAME));
+}
+
+#[test]
+fn test_host_label() {
+    assert_eq!(host_label(NFD_NAME), "theo-s-homepod");
+    assert_eq!(host_label("Living Room"), "living-room");
+    assert_eq!(host_label("\u{1F3B5}\u{1F3B5}"), "airplay-receiver");
+    assert_eq!(host_label(&"a".repeat(100)).len(), 63);
+}
@@ -123,3 +123,282 @@ fn test_parse_features_large_values() {
     let caps = parser::parse_features(&max_hex).unwrap();
     assert_eq!(caps.raw_features, u64::MAX);
 }
+
+#[test]
+fn test_parse_mac_address() {
+    let mac = [0x00, 0x50, 0xC2, 0x12, 0xA2, 0x3F];
+    assert_eq!(parser::parse_mac_address("00:50:C2:12:A2:3F"), Some(mac));
+    assert_eq!(parser::parse_mac_address("00-50-c2-12-a2-3f"), Some(mac));
+    assert_eq!(parser::parse_mac_address("0050C212A23F"), Some(mac));
+    assert_eq!(parser::parse_mac_address("0050C212A2"), None);
+    assert_eq!(parser::parse_mac_address("192.168.1.10:7000"), None);
+}
+
+#[test]
+fn test_parse_status_flags() {
+    assert_eq!(parser::parse_status_flags("0x44"), Some(0x44));
+    assert_eq!(parser::parse_status_flags("68"), Some(68));
+    assert_eq!(parser::parse_status_flags("0x1FFFFFFFF"), None);
+    assert_eq!(parser::parse_status_flags("yes"), None);
+}
+
+#[test]
+fn test_typed_txt_record() {
+    use std::collections::HashMap;
+
+    use crate::discovery::parser::status_flags;
+    use crate::discovery::txt::AirPlayTxt;
+
+    let raw: HashMap<String, String> = [
+        ("deviceid", "aa:bb:cc:dd:ee:ff"),
+        ("features", "0x4A7FDFD5,0xBC157FDE"),
+        ("flags", "0x884"),
+        ("model", "AppleTV11,1"),
+        ("srcvers", "670.6.2"),
+        ("protovers", "1.1"),
+        ("pk", "0a0b0c"),
+        ("pi", "2e388006-13ba-4041-9a67-25dd4a43d536"),
+        ("psi", "00000000-0000-0000-0000-0123456789ab"),
+        ("acl", "0"),
+        ("gid", "5DCCBD4D-8AE7-4E4F-B3D8-2A1E5E29A3C5"),
+        ("igl", "1"),
+        ("gcgl", "0"),
+        ("vv", "2"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let txt = AirPlayTxt::parse(&raw);
+    assert_eq!(txt.device_id.as_deref(), Some("aa:bb:cc:dd:ee:ff"));
+    assert_eq!(
+        txt.mac_address(),
+        Some([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF])
+    );
+    assert_eq!(txt.features, Some(0xBC15_7FDE_4A7F_DFD5));
+    assert_eq!(txt.status_flags, Some(0x884));
+    assert!(txt.has_status_flag(status_flags::PASSWORD_REQUIRED));
+    assert!(!txt.has_status_flag(status_flags::PIN_REQUIRED));
+    assert_eq!(txt.model.as_deref(), Some("AppleTV11,1"));
+    let version = txt.source_version.as_ref().unwrap();
+    assert_eq!(version.components(), &[670, 6, 2]);
+    assert_eq!(version.to_string(), "670.6.2");
+    assert_eq!(txt.protocol_version.as_deref(), Some("1.1"));
+    assert_eq!(txt.public_key, Some(vec![0x0A, 0x0B, 0x0C]));
+    assert!(txt.pairing_id.is_some());
+    assert!(txt.public_system_id.is_some());
+    assert!(!txt.password);
+    assert_eq!(txt.access_control_level, Some(0));
+    assert!(txt.group_id.is_some());
+    assert!(txt.is_group_leader);
+    assert!(!txt.group_contains_leader);
+    assert_eq!(txt.volume, Some(2.0));
+}
+
+#[test]
+fn test_typed_txt_record_raop_keys_and_bad_values() {
+    use std::collections::HashMap;
+
+    use crate::discovery::txt::AirPlayTxt;
+
+    let raw: HashMap<String, String> = [
+        ("ft", "0x5A7FFFF7,0x1E"),
+        ("sf", "0x4"),
+        ("am", "AirPort10,115"),
+        ("vs", "366.0"),
+        ("pw", "true"),
+        ("pk", "not hex"),
+        ("acl", "x"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let txt = AirPlayTxt::parse(&raw);
+    assert_eq!(txt.features, Some(0x1E_5A7F_FFF7));
+    assert_eq!(txt.status_flags, Some(0x4));
+    assert_eq!(txt.model.as_deref(), Some("AirPort10,115"));
+    assert_eq!(
+        txt.source_version
+            .as_ref()
+            .map(ToString::to_string)
+            .as_deref(),
+        Some("366.0")
+    );
+    assert!(txt.password);
+    assert_eq!(txt.public_key, None);
+    assert_eq!(txt.access_control_level, None);
+    assert_eq!(txt.device_id, None);
+
+    assert_eq!(AirPlayTxt::parse(&HashMap::new()), AirPlayTxt::default());
+}
+
+#[test]
+fn test_legacy_txt_record_matches_typed_view() {
+    use std::collections::HashMap;
+
+    use crate::discovery::parser::{AirPlayTxtRecord, status_flags};
+    use crate::discovery::txt::AirPlayTxt;
+
+    let raw: HashMap<String, String> = [
+        ("deviceid", "aa:bb:cc:dd:ee:ff"),
+        ("features", "0x5A7FFFF7,0x1E"),
+        ("flags", "0x84"),
+        ("model", "AudioAccessory5,1"),
+        ("srcvers", "670.6.2"),
+        ("pk", "0a0b0c"),
+        ("pw", "true"),
+        ("gid", "5DCF0A6C-3B4E-4D64-9D7B-8D4F7F0C1C2A"),
+        ("igl", "1"),
+        ("vv", "2"),
+        ("fv", "p20.T8020.1"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let record = AirPlayTxtRecord::parse(&raw);
+    assert_eq!(record, AirPlayTxtRecord::from(AirPlayTxt::parse(&raw)));
+    assert_eq!(record.source_version.as_deref(), Some("670.6.2"));
+    assert_eq!(record.features, Some(0x1E_5A7F_FFF7));
+    assert!(record.has_status_flag(status_flags::PASSWORD_REQUIRED));
+    assert_eq!(
+        record.mac_address(),
+        Some([0xaa, 0xbb, 0xcc, 0xdd, 0xee, 0xff])
+    );
+    assert_eq!(record.public_key, Some(vec![0x0A, 0x0B, 0x0C]));
+    assert!(record.password && record.is_group_leader);
+    assert_eq!(record.volume, Some(2.0));
+    assert_eq!(
+        AirPlayTxtRecord::parse(&HashMap::new()),
+        AirPlayTxtRecord::default()
+    );
+}
+
+#[test]
+fn test_source_version_ordering() {
+    use crate::discovery::txt::SourceVersion;
+
+    let v = |s: &str| SourceVersion::parse(s).unwrap();
+    assert_eq!(v("366"), v("366.0"));
+    assert!(v("366.0") < v("366.0.1"));
+    assert!(v("220.68") < v("366.0"));
+    assert!(v("377.40.00") > v("377.25.6"));
+    assert_eq!(v("670.6.2"), SourceVersion::new(&[670, 6, 2]));
+    assert_eq!(
+        (v("105.1").major(), v("105.1").minor(), v("105.1").patch()),
+        (105, 1, 0)
+    );
+    assert_eq!(v(" 366.0 ").as_str(), "366.0");
+
+    assert!(SourceVersion::parse("").is_none());
+    assert!(SourceVersion::parse("1..2").is_none());
+    assert!(SourceVersion::parse("1.2b").is_none());
+    assert!(SourceVersion::parse("+1").is_none());
+}
+
+#[test]
+fn test_txt_schema_keeps_unknown_keys() {
+    use std::collections::HashMap;
+
+    use crate::discovery::txt::{AirPlayTxt, TxtSchema, TxtValue};
+
+    let raw: HashMap<String, String> = [
+        ("features", "0x1,0x2"),
+        ("srcvers", "bogus"),
+        ("fv", "p20.T8020.1"),
+        ("xbits", "0x10"),
+        ("empty", ""),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let fields = TxtSchema::airplay().parse(&raw);
+    assert_eq!(fields.bits("features"), Some(0x2_0000_0001));
+    assert_eq!(fields.get("srcvers"), None);
+    assert_eq!(
+        fields.unknown().get("fv").map(String::as_str),
+        Some("p20.T8020.1")
+    );
+    assert_eq!(fields.unknown().get("empty").map(String::as_str), Some(""));
+    assert!(fields.unknown().contains_key("xbits"));
+
+    // Registering a key moves it out of the unknown set without affecting others
+    let mut schema = TxtSchema::airplay();
+    assert!(schema.register("xbits", TxtValue::flags).is_none());
+    assert!(schema.contains("xbits"));
+    let fields = schema.parse(&raw);
+    assert_eq!(fields.bits("xbits"), Some(0x10));
+    assert!(!fields.unknown().contains_key("xbits"));
+
+    let txt = AirPlayTxt::from_fields(&fields);
+    assert_eq!(txt.features, Some(0x2_0000_0001));
+    assert_eq!(txt.source_version, None);
+    assert_eq!(txt.unknown.keys().collect::<Vec<_>>(), ["empty", "fv"]);
+}
+
+#[test]
+fn test_raop_txt_record() {
+    use std::collections::HashMap;
+
+    use crate::discovery::txt::{RaopTxt, SourceVersion};
+    use crate::types::{RaopCodec, RaopEncryption, RaopMetadataType};
+
+    let raw: HashMap<String, String> = [
+        ("txtvers", "1"),
+        ("ch", "2"),
+        ("cn", "0,1,2,9"),
+        ("et", "0,3,5"),
+        ("md", "0,1,2"),
+        ("da", "true"),
+        ("sr", "44100"),
+        ("ss", "16"),
+        ("tp", "UDP"),
+        ("vs", "366.0"),
+        ("vn", "65537"),
+        ("am", "AirPort10,115"),
+        ("ft", "0x5A7FFFF7,0x1E"),
+        ("sf", "0x4"),
+        ("pk", "0a0b"),
+        ("ov", "8.4.4"),
+    ]
+    .into_iter()
+    .map(|(k, v)| (k.to_string(), v.to_string()))
+    .collect();
+
+    let txt = RaopTxt::parse(&raw);
+    assert_eq!(txt.txt_version, Some(1));
+    assert_eq!(txt.channels, Some(2));
+    assert_eq!(
+        txt.codecs,
+        vec![RaopCodec::Pcm, RaopCodec::Alac, RaopCodec::Aac]
+    );
+    assert!(txt.supports_codec(RaopCodec::Alac));
+    assert!(!txt.supports_codec(RaopCodec::AacEld));
+    assert_eq!(
+        txt.encryption_types,
+        vec![
+            RaopEncryption::None,
+            RaopEncryption::FairPlay,
+            RaopEncryption::FairPlaySap25
+        ]
+    );
+    assert_eq!(txt.metadata_types.len(), 3);
+    assert_eq!(txt.metadata_types[1], RaopMetadataType::Artwork);
+    assert!(txt.metadata);
+    assert!(!txt.password);
+    assert_eq!(txt.sample_rate, Some(44100));
+    assert_eq!(txt.sample_size, Some(16));
+    assert_eq!(txt.transport.as_deref(), Some("UDP"));
+    assert_eq!(txt.source_version, Some(SourceVersion::new(&[366])));
+    assert_eq!(txt.version_number, Some(65537));
+    assert_eq!(txt.model.as_deref(), Some("AirPort10,115"));
+    assert_eq!(txt.features, Some(0x1E_5A7F_FFF7));
+    assert_eq!(txt.status_flags, Some(0x4));
+    assert_eq!(txt.public_key, Some(vec![0x0A, 0x0B]));
+    assert_eq!(txt.unknown.get("ov").map(String::as_str), Some("8.4.4"));
+
+    // Absent keys stay unset rather than taking protocol defaults
+    assert_eq!(RaopTxt::parse(&HashMap::new()), RaopTxt::default());
+}
@@ -18,3 +18,168 @@ fn test_parse_raop_service_name_with_special_chars() {
 fn test_format_mac_address() {
     assert_eq!(format_mac_address("0050C212A23F"), "00:50:C2:12:A2:3F");
 }
+
+#[test]
+fn test_normalize_device_id() {
+    assert_eq!(
+        normalize_device_id("aa:bb:cc:dd:ee:ff"),
+        "AA:BB:CC:DD:EE:FF"
+    );
+    assert_eq!(normalize_device_id("AABBCCDDEEFF"), "AA:BB:CC:DD:EE:FF");
+    assert_eq!(normalize_device_id("not-a-mac"), "not-a-mac");
+}
+
+mod merger {
+    use std::collections::HashMap;
+
+    use crate::discovery::AIRPLAY_SERVICE_TYPE;
+    use crate::discovery::raop::{RAOP_SERVICE_TYPE, ServiceMerger, ServiceRecord};
+
+    fn record(
+        service_type: &str,
+        fullname: &str,
+        port: u16,
+        txt: &[(&str, &str)],
+    ) -> ServiceRecord {
+        ServiceRecord {
+            service_type: service_type.to_string(),
+            fullname: fullname.to_string(),
+            port,
+            addresses: vec!["192.168.1.30".parse().unwrap()],
+            txt_records: txt
+                .iter()
+                .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn raop() -> ServiceRecord {
+        record(
+            RAOP_SERVICE_TYPE,
+            "A1B2C3D4E5F6@Kitchen._raop._tcp.local.",
+            7000,
+            &[
+                ("cn", "0,1,2,3"),
+                ("et", "0,3,5"),
+                ("ft", "0x4A7FDFD5,0xBC157FDE"),
+                ("am", "AudioAccessory5,1"),
+            ],
+        )
+    }
+
+    fn airplay(deviceid: &str) -> ServiceRecord {
+        record(
+            AIRPLAY_SERVICE_TYPE,
+            "Kitchen.Home._airplay._tcp.local.",
+            7001,
+            &[
+                ("deviceid", deviceid),
+                ("features", "0x4A7FDFD5,0xBC157FDE"),
+                ("model", "AudioAccessory5,1"),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_merges_both_services_by_mac() {
+        let mut services = ServiceMerger::default();
+        let first = services.resolved(&raop()).unwrap();
+        assert_eq!(first.id, "A1:B2:C3:D4:E5:F6");
+        assert_eq!(first.name, "Kitchen");
+        assert_eq!(first.port, 7000);
+        // RAOP `ft` describes the device until the AirPlay 2 record arrives
+        assert!(first.supports_airplay2());
+
+        // Lower-case deviceid still correlates with the RAOP MAC
+        let merged = services.resolved(&airplay("a1:b2:c3:d4:e5:f6")).unwrap();
+        assert_eq!(merged.id, "A1:B2:C3:D4:E5:F6");
+        assert_eq!(merged.name, "Kitchen.Home");
+        assert_eq!(merged.port, 7001);
+        assert_eq!(merged.raop_port, Some(7000));
+        assert!(merged.raop_capabilities.is_some());
+        assert!(merged.supports_airplay2());
+        assert_eq!(merged.addresses.len(), 1);
+
+        // A later RAOP refresh does not override AirPlay 2 data
+        let merged = services.resolved(&raop()).unwrap();
+        assert_eq!(merged.port, 7001);
+        assert_eq!(merged.name, "Kitchen.Home");
+    }
+
+    #[test]
+    fn test_correlates_by_name_without_deviceid() {
+        let mut services = ServiceMerger::default();
+        services.resolved(&raop()).unwrap();
+
+        let ap2 = record(
+            AIRPLAY_SERVICE_TYPE,
+            "Kitchen._airplay._tcp.local.",
+            7001,
+            &[("pk", "abcdef")],
+        );
+        let merged = services.resolved(&ap2).unwrap();
+        assert_eq!(merged.id, "A1:B2:C3:D4:E5:F6");
+        assert_eq!(merged.raop_port, Some(7000));
+
+        // Different address: a separate device
+        let mut other = ap2.clone();
+        other.fullname = "Study._airplay._tcp.local.".to_string();
+        other.addresses = vec!["192.168.1.31".parse().unwrap()];
+        assert_eq!(services.resolved(&other).unwrap().id, "abcdef");
+    }
+
+    #[test]
+    fn test_raop_only_device() {
+        let mut services = ServiceMerger::default();
+        let device = services
+            .resolved(&record(
+                RAOP_SERVICE_TYPE,
+                "0050C212A23F@AirPort Express._raop._tcp.local.",
+                5000,
+                &[
+                    ("cn", "0,1"),
+                    ("et", "0,1"),
+                    ("pw", "true"),
+                    ("am", "AirPort4,107"),
+                ],
+            ))
+            .unwrap();
+
+        assert_eq!(device.id, "00:50:C2:12:A2:3F");
+        assert_eq!(device.name, "AirPort Express");
+        assert_eq!(device.model.as_deref(), Some("AirPort4,107"));
+        assert_eq!(device.port, 5000);
+        assert_eq!(device.raop_port, Some(5000));
+        assert!(!device.supports_airplay2());
+        assert!(device.requires_password());
+        assert_eq!(
+            device.mac_address(),
+            Some([0x00, 0x50, 0xC2, 0x12, 0xA2, 0x3F])
+        );
+    }
+
+    #[test]
+    fn test_removing_one_service_keeps_device() {
+        let mut services = ServiceMerger::default();
+        services.resolved(&raop()).unwrap();
+        services.resolved(&airplay("A1:B2:C3:D4:E5:F6")).unwrap();
+
+        let device = services
+            .removed("A1B2C3D4E5F6@Kitchen._raop._tcp.local.")
+            .unwrap()
+            .unwrap();
+        assert_eq!(device.raop_port, None);
+        assert!(device.raop_capabilities.is_none());
+        assert_eq!(device.port, 7001);
+
+        assert_eq!(
+            services.removed("Kitchen.Home._airplay._tcp.local."),
+            Some(Err("A1:B2:C3:D4:E5:F6".to_string()))
+        );
+        assert!(
+            services
+                .removed("Kitchen.Home._airplay._tcp.local.")
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+
+use crate::discovery::browser::update_event;
+use crate::discovery::parser::status_flags;
+use crate::discovery::{DeviceStatus, DiscoveryEvent};
+use crate::types::{AirPlayDevice, DeviceCapabilities};
+
+fn device(txt: &[(&str, &str)]) -> AirPlayDevice {
+    let txt_records: HashMap<String, String> = txt
+        .iter()
+        .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+        .collect();
+    AirPlayDevice {
+        id: "AA:BB:CC:DD:EE:FF".to_string(),
+        name: "Kitchen".to_string(),
+        model: None,
+        addresses: vec!["192.168.1.50".parse().unwrap()],
+        port: 7000,
+        capabilities: DeviceCapabilities::default(),
+        raop_port: None,
+        raop_capabilities: None,
+        txt_records,
+        last_seen: None,
+    }
+}
+
+#[test]
+fn test_device_status_from_txt() {
+    let status = DeviceStatus::of(&device(&[
+        ("flags", "0x20084"),
+        ("gid", "5DCCD9A3-1234"),
+        ("igl", "1"),
+    ]));
+    assert_eq!(status.flags, 0x20084);
+    assert!(status.requires_password());
+    assert!(status.is_busy());
+    assert!(!status.has_problem());
+    assert_eq!(status.group_id.as_deref(), Some("5DCCD9A3-1234"));
+    assert!(status.is_group_leader);
+
+    let idle = DeviceStatus::of(&device(&[]));
+    assert_eq!(idle, DeviceStatus::default());
+    assert!(!idle.is_busy());
+}
+
+#[test]
+fn test_update_event_reports_status_changes() {
+    let idle = DeviceStatus::of(&device(&[("flags", "0x4")]));
+
+    // Same status: plain update
+    let event = update_event(&idle, device(&[("flags", "0x4"), ("vv", "2")]));
+    assert!(matches!(event, DiscoveryEvent::Updated(_)));
+
+    // Password now required
+    let event = update_event(&idle, device(&[("flags", "0x84")]));
+    match event {
+        DiscoveryEvent::StatusChanged {
+            device,
+            previous,
+            status,
+        } => {
+            assert_eq!(device.name, "Kitchen");
+            assert!(!previous.requires_password());
+            assert!(status.requires_password());
+            assert_eq!(status.flags & status_flags::AUDIO_CABLE_ATTACHED, 0x4);
+        }
+        other => panic!("expected StatusChanged, got {other:?}"),
+    }
+
+    // Group leader changed
+    let event = update_event(&idle, device(&[("flags", "0x4"), ("igl", "1")]));
+    assert_eq!(event.device_id(), "AA:BB:CC:DD:EE:FF");
+    assert!(matches!(
+        event,
+        DiscoveryEvent::StatusChanged { ref status, .. } if status.is_group_leader
+    ));
+    assert!(event.into_device().is_some());
+    assert!(DiscoveryEvent::Removed("x".to_string()).device().is_none());
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::Duration;
+
+use tokio::net::UdpSocket;
+
+use crate::AirPlayError;
+use crate::discovery::wake::{
+    WakeOptions, magic_packet, send_magic_packet, wake_device_with_options,
+};
+use crate::types::AirPlayDevice;
+
+#[test]
+fn test_magic_packet_layout() {
+    let mac = [0x00, 0x50, 0xC2, 0x12, 0xA2, 0x3F];
+    let packet = magic_packet(&mac);
+
+    assert_eq!(&packet[..6], &[0xFF; 6]);
+    for chunk in packet[6..].chunks(6) {
+        assert_eq!(chunk, &mac);
+    }
+}
+
+#[test]
+fn test_device_mac_address() {
+    // RAOP-discovered devices use the MAC as their ID
+    let mut device = AirPlayDevice::from_address("10.0.0.5".parse().unwrap(), 7000);
+    assert_eq!(device.mac_address(), None);
+
+    device.id = "00:50:C2:12:A2:3F".to_string();
+    assert_eq!(
+        device.mac_address(),
+        Some([0x00, 0x50, 0xC2, 0x12, 0xA2, 0x3F])
+    );
+
+    // The deviceid TXT record takes precedence
+    device.txt_records = HashMap::from([("deviceid".to_string(), "AA:BB:CC:DD:EE:FF".to_string())]);
+    assert_eq!(
+        device.mac_address(),
+        Some([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF])
+    );
+}
+
+#[tokio::test]
+async fn test_send_magic_packet() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = receiver.local_addr().unwrap();
+    let mac = [1, 2, 3, 4, 5, 6];
+
+    send_magic_packet(&mac, target).await.unwrap();
+
+    let mut buf = [0u8; 128];
+    let len = tokio::time::timeout(Duration::from_secs(1), receiver.recv(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(&buf[..len], &magic_packet(&mac)[..]);
+}
+
+#[tokio::test]
+async fn test_wake_requires_mac_address() {
+    let ip: IpAddr = "10.0.0.5".parse().unwrap();
+    let device = AirPlayDevice::from_address(ip, 7000);
+
+    let result = wake_device_with_options(&device, &WakeOptions::default()).await;
+    assert!(matches!(result, Err(AirPlayError::InvalidParameter { .. })));
+}
@@ -0,0 +1,629 @@
+//! Typed TXT record schema
+//!
+//! A [`TxtSchema`] maps TXT keys to value parsers. Parsing a record yields
+//! [`TxtFields`]: typed values for the keys the schema knows and the raw
+//! strings for everything else, so keys added by newer firmware never break
+//! parsing. [`AirPlayTxt`] and [`RaopTxt`] are typed views built on the
+//! default `AirPlay` and RAOP schemas.
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use super::parser::{parse_features, parse_mac_address, parse_status_flags, txt_keys};
+use crate::types::raop::txt_keys as raop_keys;
+use crate::types::{RaopCodec, RaopEncryption, RaopMetadataType};
+
+/// Dotted numeric version advertised in `srcvers` / `vs` (e.g. "366.0")
+///
+/// Versions compare component by component, with missing trailing
+/// components treated as zero, so "366" equals "366.0" and is older than
+/// "366.0.1". The advertised spelling is kept for display.
+#[derive(Debug, Clone)]
+pub struct SourceVersion {
+    text: String,
+    components: Vec<u32>,
+}
+
+impl SourceVersion {
+    /// Build a version from numeric components
+    #[must_use]
+    pub fn new(components: &[u32]) -> Self {
+        let text = components
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(".");
+        Self {
+            text,
+            components: components.to_vec(),
+        }
+    }
+
+    /// Parse a dotted version string
+    ///
+    /// Returns `None` unless every component is a decimal number.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let text = value.trim();
+        let components = text
+            .split('.')
+            .map(|part| {
+                if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                part.parse().ok()
+            })
+            .collect::<Option<Vec<u32>>>()?;
+        Some(Self {
+            text: text.to_string(),
+            components,
+        })
+    }
+
+    /// Numeric components in order
+    #[must_use]
+    pub fn components(&self) -> &[u32] {
+        &self.components
+    }
+
+    /// Major version (first component)
+    #[must_use]
+    pub fn major(&self) -> u32 {
+        self.component(0)
+    }
+
+    /// Minor version (second component, 0 if absent)
+    #[must_use]
+    pub fn minor(&self) -> u32 {
+        self.component(1)
+    }
+
+    /// Patch version (third component, 0 if absent)
+    #[must_use]
+    pub fn patch(&self) -> u32 {
+        self.component(2)
+    }
+
+    /// The version as advertised
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    fn component(&self, index: usize) -> u32 {
+        self.components.get(index).copied().unwrap_or(0)
+    }
+
+    /// Components without trailing zeros, so equal versions compare equal
+    fn significant(&self) -> &[u32] {
+        let len = self
+            .components
+            .iter()
+            .rposition(|&c| c != 0)
+            .map_or(0, |i| i + 1);
+        &self.components[..len]
+    }
+}
+
+impl PartialEq for SourceVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.significant() == other.significant()
+    }
+}
+
+impl Eq for SourceVersion {}
+
+impl Hash for SourceVersion {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant().hash(state);
+    }
+}
+
+impl PartialOrd for SourceVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for SourceVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.significant().cmp(other.significant())
+    }
+}
+
+impl fmt::Display for SourceVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// A parsed TXT record value
+#[derive(Debug, Clone, PartialEq)]
+pub enum TxtValue {
+    /// Free-form text
+    Text(String),
+    /// Decimal integer
+    Number(u64),
+    /// Decimal number with a fractional part
+    Float(f64),
+    /// `true`/`1` style flag
+    Bool(bool),
+    /// Bitmask, from hex or a `lo,hi` pair of 32-bit hex words
+    Bits(u64),
+    /// Hex-encoded bytes
+    Bytes(Vec<u8>),
+    /// Dotted version
+    Version(SourceVersion),
+    /// Comma-separated decimal list
+    List(Vec<u64>),
+}
+
+impl TxtValue {
+    /// Keep the value as text
+    #[must_use]
+    pub fn text(value: &str) -> Option<Self> {
+        Some(Self::Text(value.to_string()))
+    }
+
+    /// Parse a decimal integer
+    #[must_use]
+    pub fn number(value: &str) -> Option<Self> {
+        value.parse().ok().map(Self::Number)
+    }
+
+    /// Parse a decimal number
+    #[must_use]
+    pub fn float(value: &str) -> Option<Self> {
+        value.parse().ok().map(Self::Float)
+    }
+
+    /// Parse a flag; anything other than `true` or `1` is false
+    #[must_use]
+    pub fn boolean(value: &str) -> Option<Self> {
+        Some(Self::Bool(
+            value.eq_ignore_ascii_case("true") || value == "1",
+        ))
+    }
+
+    /// Parse a features bitmask (`0x1234` or `0x1234,0x5678`)
+    #[must_use]
+    pub fn features(value: &str) -> Option<Self> {
+        parse_features(value).map(|caps| Self::Bits(caps.raw_features))
+    }
+
+    /// Parse status flags, hex or decimal
+    #[must_use]
+    pub fn flags(value: &str) -> Option<Self> {
+        parse_status_flags(value).map(|flags| Self::Bits(u64::from(flags)))
+    }
+
+    /// Parse hex-encoded bytes
+    #[must_use]
+    pub fn bytes(value: &str) -> Option<Self> {
+        hex::decode(value).ok().map(Self::Bytes)
+    }
+
+    /// Parse a dotted version
+    #[must_use]
+    pub fn version(value: &str) -> Option<Self> {
+        SourceVersion::parse(value).map(Self::Version)
+    }
+
+    /// Parse a comma-separated list of decimal numbers
+    #[must_use]
+    pub fn list(value: &str) -> Option<Self> {
+        value
+            .split(',')
+            .map(|item| item.trim().parse().ok())
+            .collect::<Option<Vec<u64>>>()
+            .map(Self::List)
+    }
+}
+
+/// Parser for a single TXT value; `None` means the value is malformed
+pub type TxtParser = fn(&str) -> Option<TxtValue>;
+
+/// Registry of TXT keys and how to parse their values
+///
+/// Keys without a parser are kept verbatim, so registering new keys never
+/// changes how the rest of a record parses.
+#[derive(Debug, Clone, Default)]
+pub struct TxtSchema {
+    parsers: HashMap<String, TxtParser>,
+}
+
+impl TxtSchema {
+    /// Create an empty schema
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Schema for `_airplay._tcp` records, including RAOP fallback keys
+    #[must_use]
+    pub fn airplay() -> Self {
+        Self::new()
+            .with(txt_keys::DEVICE_ID, TxtValue::text)
+            .with(txt_keys::FEATURES, TxtValue::features)
+            .with(txt_keys::RAOP_FEATURES, TxtValue::features)
+            .with(txt_keys::FLAGS, TxtValue::flags)
+            .with(txt_keys::RAOP_STATUS_FLAGS, TxtValue::flags)
+            .with(txt_keys::MODEL, TxtValue::text)
+            .with(txt_keys::AIRPLAY_VERSION, TxtValue::text)
+            .with(txt_keys::SOURCE_VERSION, TxtValue::version)
+            .with(txt_keys::RAOP_SOURCE_VERSION, TxtValue::version)
+            .with(txt_keys::PROTOCOL_VERSION, TxtValue::text)
+            .with(txt_keys::PUBLIC_KEY, TxtValue::bytes)
+            .with(txt_keys::PAIRING_ID, TxtValue::text)
+            .with(txt_keys::PUBLIC_SYSTEM_ID, TxtValue::text)
+            .with(txt_keys::PASSWORD, TxtValue::boolean)
+            .with(txt_keys::ACCESS_CONTROL, TxtValue::number)
+            .with(txt_keys::GROUP_UUID, TxtValue::text)
+            .with(txt_keys::IS_GROUP_LEADER, TxtValue::boolean)
+            .with(txt_keys::GROUP_CONTAINS_LEADER, TxtValue::boolean)
+            .with(txt_keys::VOLUME, TxtValue::float)
+    }
+
+    /// Schema for `_raop._tcp` records
+    #[must_use]
+    pub fn raop() -> Self {
+        Self::new()
+            .with(raop_keys::TXTVERS, TxtValue::number)
+            .with(raop_keys::CHANNELS, TxtValue::number)
+            .with(raop_keys::CODECS, TxtValue::list)
+            .with(raop_keys::ENCRYPTION, TxtValue::list)
+            .with(raop_keys::METADATA, TxtValue::boolean)
+            .with(raop_keys::METADATA_TYPES, TxtValue::list)
+            .with(raop_keys::PASSWORD, TxtValue::boolean)
+            .with(raop_keys::SAMPLE_RATE, TxtValue::number)
+            .with(raop_keys::SAMPLE_SIZE, TxtValue::number)
+            .with(raop_keys::TRANSPORT, TxtValue::text)
+            .with(raop_keys::VERSION, TxtValue::version)
+            .with(raop_keys::VERSION_NUM, TxtValue::number)
+            .with(raop_keys::MODEL, TxtValue::text)
+            .with(raop_keys::FLAGS, TxtValue::flags)
+            .with(txt_keys::RAOP_FEATURES, TxtValue::features)
+            .with(txt_keys::PUBLIC_KEY, TxtValue::bytes)
+    }
+
+    /// Add or replace the parser for `key`
+    #[must_use]
+    pub fn with(mut self, key: impl Into<String>, parser: TxtParser) -> Self {
+        self.register(key, parser);
+        self
+    }
+
+    /// Add or replace the parser for `key`, returning the previous one
+    pub fn register(&mut self, key: impl Into<String>, parser: TxtParser) -> Option<TxtParser> {
+        self.parsers.insert(key.into(), parser)
+    }
+
+    /// Whether `key` has a parser
+    #[must_use]
+    pub fn contains(&self, key: &str) -> bool {
+        self.parsers.contains_key(key)
+    }
+
+    /// Parse raw TXT key/value pairs
+    ///
+    /// Known keys with empty or malformed values are dropped; unknown keys
+    /// are kept as-is in [`TxtFields::unknown`].
+    #[must_use]
+    pub fn parse(&self, records: &HashMap<String, String>) -> TxtFields {
+        let mut fields = TxtFields::default();
+        for (key, value) in records {
+            match self.parsers.get(key) {
+                Some(parser) => {
+                    let value = value.trim();
+                    if let Some(parsed) = (!value.is_empty()).then(|| parser(value)).flatten() {
+                        fields.values.insert(key.clone(), parsed);
+                    }
+                }
+                None => {
+                    fields.unknown.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        fields
+    }
+}
+
+/// TXT record parsed against a [`TxtSchema`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TxtFields {
+    values: HashMap<String, TxtValue>,
+    unknown: BTreeMap<String, String>,
+}
+
+impl TxtFields {
+    /// Parsed value for `key`
+    #[must_use]
+    pub fn get(&self, key: &str) -> Option<&TxtValue> {
+        self.values.get(key)
+    }
+
+    /// Keys the schema does not know, with their raw values
+    #[must_use]
+    pub fn unknown(&self) -> &BTreeMap<String, String> {
+        &self.unknown
+    }
+
+    /// Text value for `key`
+    #[must_use]
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            TxtValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Integer value for `key`
+    #[must_use]
+    pub fn number(&self, key: &str) -> Option<u64> {
+        match self.get(key)? {
+            TxtValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Fractional value for `key`
+    #[must_use]
+    pub fn float(&self, key: &str) -> Option<f64> {
+        match self.get(key)? {
+            TxtValue::Float(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Flag value for `key`, false when absent
+    #[must_use]
+    pub fn flag(&self, key: &str) -> bool {
+        matches!(self.get(key), Some(TxtValue::Bool(true)))
+    }
+
+    /// Bitmask value for `key`
+    #[must_use]
+    pub fn bits(&self, key: &str) -> Option<u64> {
+        match self.get(key)? {
+            TxtValue::Bits(bits) => Some(*bits),
+            _ => None,
+        }
+    }
+
+    /// Byte value for `key`
+    #[must_use]
+    pub fn bytes(&self, key: &str) -> Option<&[u8]> {
+        match self.get(key)? {
+            TxtValue::Bytes(bytes) => Some(bytes),
+            _ => None,
+        }
+    }
+
+    /// Version value for `key`
+    #[must_use]
+    pub fn version(&self, key: &str) -> Option<&SourceVersion> {
+        match self.get(key)? {
+            TxtValue::Version(version) => Some(version),
+            _ => None,
+        }
+    }
+
+    /// List value for `key`, empty when absent
+    #[must_use]
+    pub fn list(&self, key: &str) -> &[u64] {
+        match self.get(key) {
+            Some(TxtValue::List(items)) => items,
+            _ => &[],
+        }
+    }
+}
+
+/// Typed view of an `AirPlay` (or RAOP) TXT record
+///
+/// Built with [`AirPlayTxt::parse`] from the raw key/value pairs, or with
+/// [`AirPlayTxt::from_fields`] after parsing against an extended
+/// [`TxtSchema::airplay`]. RAOP spellings (`ft`, `sf`, `am`, `vs`) are used
+/// when the `AirPlay` 2 key is absent. Values that fail to parse are left as
+/// `None`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AirPlayTxt {
+    /// Device ID, normally the MAC address (`deviceid`)
+    pub device_id: Option<String>,
+    /// Features bitmask (`features` / `ft`)
+    pub features: Option<u64>,
+    /// Status flags (`flags` / `sf`), see [`super::parser::status_flags`]
+    pub status_flags: Option<u32>,
+    /// Model identifier (`model` / `am`)
+    pub model: Option<String>,
+    /// Source version (`srcvers` / `vs`)
+    pub source_version: Option<SourceVersion>,
+    /// Protocol version (`protovers`)
+    pub protocol_version: Option<String>,
+    /// Ed25519 public key (`pk`)
+    pub public_key: Option<Vec<u8>>,
+    /// Pairing identity (`pi`)
+    pub pairing_id: Option<String>,
+    /// Public system identifier (`psi`)
+    pub public_system_id: Option<String>,
+    /// Password required (`pw`)
+    pub password: bool,
+    /// Access control level (`acl`), 0 = everyone
+    pub access_control_level: Option<u32>,
+    /// Group UUID (`gid`)
+    pub group_id: Option<String>,
+    /// Device is the group leader (`igl`)
+    pub is_group_leader: bool,
+    /// Group contains a discoverable leader (`gcgl`)
+    pub group_contains_leader: bool,
+    /// Advertised volume (`vv`)
+    pub volume: Option<f32>,
+    /// Keys outside the schema, with their raw values
+    pub unknown: BTreeMap<String, String>,
+}
+
+impl AirPlayTxt {
+    /// Parse raw TXT key/value pairs with [`TxtSchema::airplay`]
+    #[must_use]
+    pub fn parse(records: &HashMap<String, String>) -> Self {
+        Self::from_fields(&TxtSchema::airplay().parse(records))
+    }
+
+    /// Build the view from already-parsed fields
+    #[must_use]
+    pub fn from_fields(fields: &TxtFields) -> Self {
+        let text = |key: &str| fields.text(key).map(ToString::to_string);
+        let either_text = |key: &str, fallback: &str| text(key).or_else(|| text(fallback));
+
+        Self {
+            device_id: text(txt_keys::DEVICE_ID),
+            features: fields
+                .bits(txt_keys::FEATURES)
+                .or_else(|| fields.bits(txt_keys::RAOP_FEATURES)),
+            status_flags: fields
+                .bits(txt_keys::FLAGS)
+                .or_else(|| fields.bits(txt_keys::RAOP_STATUS_FLAGS))
+                .and_then(|flags| u32::try_from(flags).ok()),
+            model: either_text(txt_keys::MODEL, txt_keys::AIRPLAY_VERSION),
+            source_version: fields
+                .version(txt_keys::SOURCE_VERSION)
+                .or_else(|| fields.version(txt_keys::RAOP_SOURCE_VERSION))
+                .cloned(),
+            protocol_version: text(txt_keys::PROTOCOL_VERSION),
+            public_key: fields.bytes(txt_keys::PUBLIC_KEY).map(<[u8]>::to_vec),
+            pairing_id: text(txt_keys::PAIRING_ID),
+            public_system_id: text(txt_keys::PUBLIC_SYSTEM_ID),
+            password: fields.flag(txt_keys::PASSWORD),
+            access_control_level: fields
+                .number(txt_keys::ACCESS_CONTROL)
+                .and_then(|acl| u32::try_from(acl).ok()),
+            group_id: text(txt_keys::GROUP_UUID),
+            is_group_leader: fields.flag(txt_keys::IS_GROUP_LEADER),
+            group_contains_leader: fields.flag(txt_keys::GROUP_CONTAINS_LEADER),
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "Volume is a small advertised value"
+            )]
+            volume: fields.float(txt_keys::VOLUME).map(|v| v as f32),
+            unknown: fields.unknown().clone(),
+        }
+    }
+
+    /// Whether a status flag bit is set
+    #[must_use]
+    pub fn has_status_flag(&self, flag: u32) -> bool {
+        self.status_flags.is_some_and(|flags| flags & flag != 0)
+    }
+
+    /// Hardware address from the device ID, if it is a MAC address
+    #[must_use]
+    pub fn mac_address(&self) -> Option<[u8; 6]> {
+        self.device_id.as_deref().and_then(parse_mac_address)
+    }
+}
+
+/// Typed view of a RAOP (`_raop._tcp`) TXT record
+///
+/// Unlike [`crate::types::RaopCapabilities`], absent keys stay `None`
+/// rather than taking protocol defaults, so callers can tell an advertised
+/// value from an assumed one.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RaopTxt {
+    /// TXT record version (`txtvers`)
+    pub txt_version: Option<u32>,
+    /// Audio channels (`ch`)
+    pub channels: Option<u8>,
+    /// Supported codecs (`cn`); unknown codec numbers are skipped
+    pub codecs: Vec<RaopCodec>,
+    /// Supported encryption types (`et`)
+    pub encryption_types: Vec<RaopEncryption>,
+    /// Supported metadata types (`md`)
+    pub metadata_types: Vec<RaopMetadataType>,
+    /// Metadata supported (`da`)
+    pub metadata: bool,
+    /// Password required (`pw`)
+    pub password: bool,
+    /// Sample rate in Hz (`sr`)
+    pub sample_rate: Option<u32>,
+    /// Sample size in bits (`ss`)
+    pub sample_size: Option<u8>,
+    /// Transport protocols (`tp`, e.g. "UDP" or "TCP,UDP")
+    pub transport: Option<String>,
+    /// Server version (`vs`)
+    pub source_version: Option<SourceVersion>,
+    /// Numeric version (`vn`)
+    pub version_number: Option<u64>,
+    /// Model identifier (`am`)
+    pub model: Option<String>,
+    /// Features bitmask (`ft`)
+    pub features: Option<u64>,
+    /// Status flags (`sf`)
+    pub status_flags: Option<u32>,
+    /// Public key (`pk`)
+    pub public_key: Option<Vec<u8>>,
+    /// Keys outside the schema, with their raw values
+    pub unknown: BTreeMap<String, String>,
+}
+
+impl RaopTxt {
+    /// Parse raw TXT key/value pairs with [`TxtSchema::raop`]
+    #[must_use]
+    pub fn parse(records: &HashMap<String, String>) -> Self {
+        Self::from_fields(&TxtSchema::raop().parse(records))
+    }
+
+    /// Build the view from already-parsed fields
+    #[must_use]
+    pub fn from_fields(fields: &TxtFields) -> Self {
+        Self {
+            txt_version: fields
+                .number(raop_keys::TXTVERS)
+                .and_then(|n| u32::try_from(n).ok()),
+            channels: fields
+                .number(raop_keys::CHANNELS)
+                .and_then(|n| u8::try_from(n).ok()),
+            codecs: enum_list(fields.list(raop_keys::CODECS), RaopCodec::from_u8),
+            encryption_types: enum_list(
+                fields.list(raop_keys::ENCRYPTION),
+                RaopEncryption::from_u8,
+            ),
+            metadata_types: enum_list(
+                fields.list(raop_keys::METADATA_TYPES),
+                RaopMetadataType::from_u8,
+            ),
+            metadata: fields.flag(raop_keys::METADATA),
+            password: fields.flag(raop_keys::PASSWORD),
+            sample_rate: fields
+                .number(raop_keys::SAMPLE_RATE)
+                .and_then(|n| u32::try_from(n).ok()),
+            sample_size: fields
+                .number(raop_keys::SAMPLE_SIZE)
+                .and_then(|n| u8::try_from(n).ok()),
+            transport: fields.text(raop_keys::TRANSPORT).map(ToString::to_string),
+            source_version: fields.version(raop_keys::VERSION).cloned(),
+            version_number: fields.number(raop_keys::VERSION_NUM),
+            model: fields.text(raop_keys::MODEL).map(ToString::to_string),
+            features: fields.bits(txt_keys::RAOP_FEATURES),
+            status_flags: fields
+                .bits(raop_keys::FLAGS)
+                .and_then(|flags| u32::try_from(flags).ok()),
+            public_key: fields.bytes(txt_keys::PUBLIC_KEY).map(<[u8]>::to_vec),
+            unknown: fields.unknown().clone(),
+        }
+    }
+
+    /// Whether `codec` is advertised
+    #[must_use]
+    pub fn supports_codec(&self, codec: RaopCodec) -> bool {
+        self.codecs.contains(&codec)
+    }
+}
+
+/// Map a numeric list to enum values, skipping numbers without a variant
+fn enum_list<T>(items: &[u64], from_u8: fn(u8) -> Option<T>) -> Vec<T> {
+    items
+        .iter()
+        .filter_map(|&n| u8::try_from(n).ok().and_then(from_u8))
+        .collect()
+}
@@ -0,0 +1,169 @@
+//! Wake-on-LAN support
+//!
+//! Apple TVs and other devices that have gone to sleep behind a Bonjour sleep
+//! proxy stop answering mDNS queries, so they drop out of scans. If the
+//! device's MAC address is known (from an earlier sighting or a
+//! [`DeviceCache`](super::cache::DeviceCache)), a magic packet wakes it and a
+//! fresh browse picks up its current addresses.
+
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::time::Duration;
+
+use futures::StreamExt;
+use tokio::net::UdpSocket;
+
+use super::{DiscoveryOptions, discover_with_options};
+use crate::error::AirPlayError;
+use crate::types::AirPlayDevice;
+
+/// UDP port magic packets are sent to ("discard")
+pub const WOL_PORT: u16 = 9;
+
+/// Options for [`wake_device_with_options`]
+#[derive(Debug, Clone)]
+pub struct WakeOptions {
+    /// Number of magic packets to send
+    pub attempts: u32,
+    /// Delay between magic packets
+    pub interval: Duration,
+    /// How long to wait for the device to reappear in mDNS
+    pub resolve_timeout: Duration,
+    /// Options for the re-resolving browse (its timeout is ignored)
+    pub discovery: DiscoveryOptions,
+}
+
+impl Default for WakeOptions {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            interval: Duration::from_secs(1),
+            resolve_timeout: Duration::from_secs(15),
+            discovery: DiscoveryOptions::default(),
+        }
+    }
+}
+
+/// Build a Wake-on-LAN magic packet: six `0xFF` bytes followed by the MAC
+/// address repeated sixteen times
+#[must_use]
+pub fn magic_packet(mac: &[u8; 6]) -> [u8; 102] {
+    let mut packet = [0xFF; 102];
+    for chunk in packet[6..].chunks_exact_mut(6) {
+        chunk.copy_from_slice(mac);
+    }
+    packet
+}
+
+/// Send a single magic packet to `target`
+///
+/// # Errors
+///
+/// Returns error if the socket cannot be opened or the packet cannot be sent.
+pub async fn send_magic_packet(mac: &[u8; 6], target: SocketAddr) -> Result<(), AirPlayError> {
+    let bind: SocketAddr = match target {
+        SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+        SocketAddr::V6(_) => (std::net::Ipv6Addr::UNSPECIFIED, 0).into(),
+    };
+    let socket = UdpSocket::bind(bind).await?;
+    socket.set_broadcast(true)?;
+    socket.send_to(&magic_packet(mac), target).await?;
+    Ok(())
+}
+
+/// Wake a sleeping device and re-resolve it
+///
+/// Equivalent to [`wake_device_with_options`] with default options.
+///
+/// # Errors
+///
+/// See [`wake_device_with_options`].
+pub async fn wake_device(device: &AirPlayDevice) -> Result<AirPlayDevice, AirPlayError> {
+    wake_device_with_options(device, &WakeOptions::default()).await
+}
+
+/// Wake a sleeping device and re-resolve it
+///
+/// Magic packets are broadcast on the local network and sent directly to
+/// each last known IPv4 address, repeating every `interval`, while an mDNS
+/// browse waits for the device to announce itself again. Returns the freshly
+/// resolved device, whose addresses should be used to connect.
+///
+/// # Errors
+///
+/// Returns error if the device's MAC address is unknown, no magic packet
+/// could be sent, or the device does not reappear within `resolve_timeout`.
+pub async fn wake_device_with_options(
+    device: &AirPlayDevice,
+    options: &WakeOptions,
+) -> Result<AirPlayDevice, AirPlayError> {
+    let mac = device
+        .mac_address()
+        .ok_or_else(|| AirPlayError::InvalidParameter {
+            name: "device".to_string(),
+            message: format!("MAC address of {} is unknown", device.name),
+        })?;
+
+    let mut targets = vec![SocketAddr::new(IpAddr::V4(Ipv4Addr::BROADCAST), WOL_PORT)];
+    targets.extend(
+        device
+            .addresses
+            .iter()
+            .filter(|ip| ip.is_ipv4())
+            .map(|ip| SocketAddr::new(*ip, WOL_PORT)),
+    );
+
+    // Start browsing first so an announcement right after waking is not missed
+    let events = discover_with_options(options.discovery.clone())?;
+    tokio::pin!(events);
+
+    let deadline = tokio::time::sleep(options.resolve_timeout);
+    tokio::pin!(deadline);
+    let mut resend = tokio::time::interval(options.interval);
+    let mut sent = 0;
+
+    loop {
+        tokio::select! {
+            _ = resend.tick(), if sent < options.attempts => {
+                send_to_any(&mac, &targets).await?;
+                sent += 1;
+            }
+            event = events.next() => match event {
+                Some(event) => {
+                    if let Some(found) = event
+                        .into_device()
+                        .filter(|found| found.id == device.id || found.mac_address() == Some(mac))
+                    {
+                        tracing::debug!("{} is awake at {:?}", found.name, found.addresses);
+                        return Ok(found);
+                    }
+                }
+                None => break,
+            },
+            () = &mut deadline => break,
+        }
+    }
+
+    Err(AirPlayError::DeviceNotFound {
+        device_id: device.id.clone(),
+    })
+}
+
+/// Send a magic packet to every target, succeeding if any send succeeds
+async fn send_to_any(mac: &[u8; 6], targets: &[SocketAddr]) -> Result<(), AirPlayError> {
+    let mut last_error = None;
+    let mut delivered = false;
+    for target in targets {
+        match send_magic_packet(mac, *target).await {
+            Ok(()) => delivered = true,
+            Err(e) => {
+                tracing::debug!("Wake-on-LAN to {target} failed: {e}");
+                last_error = Some(e);
+            }
+        }
+    }
+
+    match last_error {
+        Some(e) if !delivered => Err(e),
+        _ => Ok(()),
+    }
+}
@@ -2,6 +2,8 @@ use std::io;
 
 use thiserror::Error;
 
+use crate::audio::AudioCodec;
+
 /// RAOP-specific errors
 #[derive(Debug, Error)]
 pub enum RaopError {
@@ -94,6 +96,9 @@ pub enum AirPlayError {
         message: String,
         /// Whether the error is recoverable by retrying
         recoverable: bool,
+        /// The underlying source of the error
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
     },
 
     /// Pairing required but not initiated
@@ -118,6 +123,9 @@ pub enum AirPlayError {
         message: String,
         /// HTTP/RTSP status code if available
         status_code: Option<u16>,
+        /// The underlying source of the error
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
     },
 
     /// RTP protocol error
@@ -141,6 +149,9 @@ pub enum AirPlayError {
     CodecError {
         /// Description of the error
         message: String,
+        /// The underlying source of the error
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
     },
 
     // ===== Playback Errors =====
@@ -167,6 +178,15 @@ pub enum AirPlayError {
         format: String,
     },
 
+    /// Codec not accepted by the device
+    #[error("device does not support {codec:?} (supported: {supported:?})")]
+    UnsupportedCodec {
+        /// The requested codec
+        codec: AudioCodec,
+        /// Codecs the device accepts
+        supported: Vec<AudioCodec>,
+    },
+
     /// Queue operation failed
     #[error("queue error: {message}")]
     QueueError {
@@ -88,6 +88,7 @@ fn test_airplay_error_display_authentication() {
     let err = AirPlayError::AuthenticationFailed {
         message: "invalid PIN".to_string(),
         recoverable: true,
+        source: None,
     };
     assert_eq!(err.to_string(), "authentication failed: invalid PIN");
 
@@ -107,6 +108,7 @@ fn test_airplay_error_display_protocol() {
     let err = AirPlayError::RtspError {
         message: "bad request".to_string(),
         status_code: Some(400),
+        source: None,
     };
     assert_eq!(err.to_string(), "RTSP error: bad request");
 
@@ -126,6 +128,7 @@ fn test_airplay_error_display_protocol() {
 
     let err = AirPlayError::CodecError {
         message: "missing plist header".to_string(),
+        source: None,
     };
     assert_eq!(err.to_string(), "codec error: missing plist header");
 }
@@ -227,12 +230,14 @@ fn test_error_is_recoverable() {
     let auth_err_recoverable = AirPlayError::AuthenticationFailed {
         message: "wait".to_string(),
         recoverable: true,
+        source: None,
     };
     assert!(auth_err_recoverable.is_recoverable());
 
     let auth_err_fatal = AirPlayError::AuthenticationFailed {
         message: "bad pin".to_string(),
         recoverable: false,
+        source: None,
     };
     assert!(!auth_err_fatal.is_recoverable());
 
@@ -299,3 +304,73 @@ fn test_error_send_sync() {
     assert_send_sync::<RaopError>();
     assert_send_sync::<AirPlayError>();
 }
+
+/// Number of errors in the `source()` chain, counting `err` itself
+fn chain_depth(err: &dyn std::error::Error) -> usize {
+    std::iter::successors(Some(err), |e| e.source()).count()
+}
+
+#[test]
+fn test_source_chain_depth() {
+    use crate::protocol::crypto::CryptoError;
+    use crate::protocol::pairing::PairingError;
+
+    let err = AirPlayError::AuthenticationFailed {
+        message: "Pair-Verify M2 failed".to_string(),
+        recoverable: false,
+        source: Some(Box::new(PairingError::CryptoError(
+            CryptoError::InvalidSignature,
+        ))),
+    };
+    assert_eq!(chain_depth(&err), 3);
+
+    let err: AirPlayError = io::Error::new(io::ErrorKind::ConnectionReset, "reset").into();
+    assert_eq!(chain_depth(&err), 2);
+
+    assert_eq!(chain_depth(&AirPlayError::Timeout), 1);
+}
+
+#[cfg(feature = "sender")]
+#[test]
+fn test_plist_error_keeps_source() {
+    use crate::protocol::plist::PlistDecodeError;
+    use crate::streaming::UrlStreamer;
+
+    let err = UrlStreamer::parse_playback_info(b"not a plist").unwrap_err();
+
+    assert!(matches!(err, AirPlayError::CodecError { .. }));
+    assert_eq!(chain_depth(&err), 2);
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.is::<PlistDecodeError>());
+}
+
+#[cfg(feature = "sender")]
+#[tokio::test]
+async fn test_rtsp_codec_error_keeps_source() {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    use crate::protocol::rtsp::RtspCodecError;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let device = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0u8; 1024];
+        let _ = stream.read(&mut buf).await.unwrap();
+        stream.write_all(b"garbage\r\n\r\n").await.unwrap();
+    });
+
+    let err = crate::connection::probe_info(
+        "127.0.0.1".parse().unwrap(),
+        port,
+        std::time::Duration::from_secs(5),
+    )
+    .await
+    .unwrap_err();
+    device.await.unwrap();
+
+    assert!(matches!(err, AirPlayError::RtspError { .. }));
+    assert_eq!(chain_depth(&err), 2);
+    let source = std::error::Error::source(&err).unwrap();
+    assert!(source.is::<RtspCodecError>());
+}
@@ -1,12 +1,19 @@
 //! Multi-room group management
 
 use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 
 use rand::Rng;
 use tokio::sync::RwLock;
 
+use super::persist::{SavedGroup, load_groups, save_groups};
+use crate::client::AirPlayClient;
 use crate::control::volume::Volume;
+use crate::discovery::cache::DeviceCache;
 use crate::error::AirPlayError;
+use crate::protocol::ptp::PtpTimestamp;
+use crate::streaming::Timeline;
 use crate::types::AirPlayDevice;
 
 /// Unique identifier for a group
@@ -51,6 +58,8 @@ pub struct GroupMember {
     pub is_leader: bool,
     /// Connection state
     pub connected: bool,
+    /// Extra playback delay applied to this member (latency trim)
+    pub delay: Duration,
 }
 
 /// A group of `AirPlay` devices
@@ -90,6 +99,7 @@ impl DeviceGroup {
             volume: Volume::MAX,
             is_leader: true,
             connected: false,
+            delay: Duration::ZERO,
         };
 
         Self {
@@ -117,6 +127,7 @@ impl DeviceGroup {
             volume: Volume::MAX,
             is_leader,
             connected: false,
+            delay: Duration::ZERO,
         });
     }
 
@@ -166,6 +177,108 @@ impl DeviceGroup {
         }
     }
 
+    /// Set the latency trim for a device
+    ///
+    /// A positive delay makes this member play later than the rest of the
+    /// group, e.g. delaying fast `HomePods` to line up with a slow soundbar.
+    pub fn set_member_delay(&mut self, device_id: &str, delay: Duration) {
+        if let Some(member) = self.member_mut(device_id) {
+            member.delay = delay;
+        }
+    }
+
+    /// Derive latency trims from measured output delays
+    ///
+    /// `latencies` are the acoustic delays measured per member, e.g. with
+    /// `measure_latency` (feature `loopback-measure`). Every measured
+    /// member is delayed to line up with the slowest one. Members without a
+    /// measurement keep their trim. Returns the new trims.
+    pub fn apply_latency_measurements(
+        &mut self,
+        latencies: &[(&str, Duration)],
+    ) -> Vec<(String, Duration)> {
+        let Some(slowest) = latencies
+            .iter()
+            .filter(|(id, _)| self.member(id).is_some())
+            .map(|(_, latency)| *latency)
+            .max()
+        else {
+            return Vec::new();
+        };
+
+        let mut trims = Vec::new();
+        for member in &mut self.members {
+            if let Some((_, latency)) = latencies.iter().find(|(id, _)| *id == member.device.id) {
+                member.delay = slowest.saturating_sub(*latency);
+                trims.push((member.device.id.clone(), member.delay));
+            }
+        }
+        trims
+    }
+
+    /// Get the latency trim for a device
+    #[must_use]
+    pub fn member_delay(&self, device_id: &str) -> Duration {
+        self.member(device_id).map_or(Duration::ZERO, |m| m.delay)
+    }
+
+    /// Get a member's delay in RTP samples at the given sample rate
+    #[must_use]
+    pub fn member_delay_samples(&self, device_id: &str, sample_rate: u32) -> u32 {
+        let samples = Timeline::new(sample_rate).samples_for(self.member_delay(device_id));
+        u32::try_from(samples).unwrap_or(u32::MAX)
+    }
+
+    /// A member's view of the group timeline, shifted for its delay
+    ///
+    /// Members share the group's RTP and media positions; only the network
+    /// time each renders them at differs.
+    #[must_use]
+    pub fn member_timeline(&self, device_id: &str, timeline: &Timeline) -> Timeline {
+        timeline.delayed(self.member_delay(device_id))
+    }
+
+    /// Shift an RTP anchor timestamp for a member's delay
+    ///
+    /// Anchoring an earlier RTP time to the same network time delays that
+    /// member's output by its configured trim.
+    #[must_use]
+    pub fn member_anchor_rtp_time(&self, device_id: &str, rtp_time: u32, sample_rate: u32) -> u32 {
+        Timeline::new(sample_rate).delayed_rtp_time(rtp_time, self.member_delay(device_id))
+    }
+
+    /// Shift an anchor network (PTP) time for a member's delay
+    #[must_use]
+    pub fn member_anchor_network_time(
+        &self,
+        device_id: &str,
+        network_time: PtpTimestamp,
+    ) -> PtpTimestamp {
+        network_time.add_duration(self.member_delay(device_id))
+    }
+
+    /// Hand each member's latency trim to its client
+    ///
+    /// Every connected client whose device is a member shifts its anchors
+    /// as [`member_anchor_network_time`](Self::member_anchor_network_time)
+    /// and [`member_anchor_rtp_time`](Self::member_anchor_rtp_time) describe,
+    /// from its next `SETRATEANCHORTIME` or `TimeAnnounce` on. Call
+    /// [`AirPlayClient::reanchor`] to apply a new trim mid-stream. Returns
+    /// the number of clients updated.
+    pub async fn apply_member_delays(&self, clients: &[AirPlayClient]) -> usize {
+        let mut applied = 0;
+        for client in clients {
+            let Some(device) = client.connected_device().await else {
+                continue;
+            };
+            if self.member(&device.id).is_some() {
+                client.set_playback_delay(self.member_delay(&device.id));
+                applied += 1;
+            }
+        }
+        applied
+    }
+
     /// Get group volume
     #[must_use]
     pub fn volume(&self) -> Volume {
@@ -298,6 +411,16 @@ impl GroupManager {
         self.groups.read().await.values().cloned().collect()
     }
 
+    /// Find a group by name
+    pub async fn find_group_by_name(&self, name: &str) -> Option<GroupId> {
+        self.groups
+            .read()
+            .await
+            .values()
+            .find(|g| g.name == name)
+            .map(|g| g.id.clone())
+    }
+
     /// Find group containing a device
     pub async fn find_device_group(&self, device_id: &str) -> Option<GroupId> {
         self.device_groups.read().await.get(device_id).cloned()
@@ -406,6 +529,118 @@ impl GroupManager {
         group.set_member_volume(device_id, volume);
         Ok(())
     }
+
+    /// Set member latency trim
+    ///
+    /// # Errors
+    ///
+    /// Returns error if group not found
+    pub async fn set_member_delay(
+        &self,
+        group_id: &GroupId,
+        device_id: &str,
+        delay: Duration,
+    ) -> Result<(), AirPlayError> {
+        let mut groups = self.groups.write().await;
+        let group = groups
+            .get_mut(group_id)
+            .ok_or(AirPlayError::GroupNotFound {
+                group_id: group_id.as_str().to_string(),
+            })?;
+
+        group.set_member_delay(device_id, delay);
+        Ok(())
+    }
+
+    /// Definitions of all groups, sorted by name
+    pub async fn saved_groups(&self) -> Vec<SavedGroup> {
+        let mut saved: Vec<SavedGroup> = self
+            .groups
+            .read()
+            .await
+            .values()
+            .map(SavedGroup::from_group)
+            .collect();
+        saved.sort_by(|a, b| a.name.cmp(&b.name));
+        saved
+    }
+
+    /// Save all group definitions to a file
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be written.
+    pub async fn save(&self, path: impl AsRef<Path>) -> Result<(), AirPlayError> {
+        save_groups(path, &self.saved_groups().await).await
+    }
+
+    /// Recreate saved groups, resolving member device IDs through `devices`
+    ///
+    /// Members missing from the cache, and devices already in a group, are
+    /// skipped. Groups whose name is already taken, or none of whose members
+    /// resolve, are not recreated. Returns the IDs of the recreated groups.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be read or parsed.
+    pub async fn restore(
+        &self,
+        path: impl AsRef<Path>,
+        devices: &DeviceCache,
+    ) -> Result<Vec<GroupId>, AirPlayError> {
+        let saved = load_groups(path).await?;
+
+        // Lock order: groups -> device_groups
+        let mut groups = self.groups.write().await;
+        let mut device_groups = self.device_groups.write().await;
+        let mut restored = Vec::new();
+
+        for saved in saved {
+            if groups.values().any(|g| g.name == saved.name) {
+                tracing::warn!("Group '{}' already exists, not restoring", saved.name);
+                continue;
+            }
+
+            let mut group = DeviceGroup::new(saved.name.clone());
+            group.id = GroupId::from_string(saved.id.clone());
+            if groups.contains_key(&group.id) {
+                group.id = GroupId::new();
+            }
+            group.set_volume(Volume::new(saved.volume));
+
+            for member in &saved.members {
+                if device_groups.contains_key(&member.device_id) {
+                    tracing::warn!(
+                        "Device {} of group '{}' is already grouped",
+                        member.device_id,
+                        saved.name
+                    );
+                    continue;
+                }
+                let Some(device) = devices.get(&member.device_id) else {
+                    tracing::warn!(
+                        "Device {} of group '{}' is unknown",
+                        member.device_id,
+                        saved.name
+                    );
+                    continue;
+                };
+                group.add_member(device);
+                group.set_member_volume(&member.device_id, Volume::new(member.volume));
+                group.set_member_delay(&member.device_id, SavedGroup::member_delay(member));
+                device_groups.insert(member.device_id.clone(), group.id.clone());
+            }
+
+            if group.is_empty() {
+                tracing::warn!("No members of group '{}' could be resolved", saved.name);
+                continue;
+            }
+            restored.push(group.id.clone());
+            groups.insert(group.id.clone(), group);
+        }
+
+        Ok(restored)
+    }
 }
 
 impl Default for GroupManager {
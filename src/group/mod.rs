@@ -1,8 +1,12 @@
 //! Multi-room support module
 
 mod manager;
+mod persist;
+mod watchdog;
 
 #[cfg(test)]
 mod tests;
 
 pub use manager::*;
+pub use persist::*;
+pub use watchdog::*;
@@ -0,0 +1,117 @@
+//! Saved group definitions
+//!
+//! Named groups ("Downstairs" = kitchen + living room) are stored as a JSON
+//! file of device IDs. Device addresses change between sessions, so on
+//! startup [`GroupManager::restore`](super::GroupManager::restore) resolves
+//! each ID through a [`DeviceCache`](crate::discovery::cache::DeviceCache)
+//! rather than trusting stored addresses.
+
+use std::path::Path;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use super::DeviceGroup;
+use crate::error::AirPlayError;
+
+/// A group member as stored on disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedMember {
+    /// Device identifier
+    pub device_id: String,
+    /// Member volume relative to the group (0.0 - 1.0)
+    pub volume: f32,
+    /// Latency trim in milliseconds
+    #[serde(default)]
+    pub delay_ms: u64,
+}
+
+/// A group definition as stored on disk
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedGroup {
+    /// Group identifier
+    pub id: String,
+    /// Group name
+    pub name: String,
+    /// Group volume (0.0 - 1.0)
+    pub volume: f32,
+    /// Members, leader first
+    pub members: Vec<SavedMember>,
+}
+
+impl SavedGroup {
+    /// Snapshot a group's definition
+    #[must_use]
+    pub fn from_group(group: &DeviceGroup) -> Self {
+        let mut members: Vec<SavedMember> = group
+            .members()
+            .iter()
+            .map(|m| SavedMember {
+                device_id: m.device.id.clone(),
+                volume: m.volume.as_f32(),
+                delay_ms: u64::try_from(m.delay.as_millis()).unwrap_or(u64::MAX),
+            })
+            .collect();
+        // Keep the leader first so it is re-elected on restore
+        if let Some(pos) = group.members().iter().position(|m| m.is_leader) {
+            members[..=pos].rotate_right(1);
+        }
+
+        Self {
+            id: group.id.as_str().to_string(),
+            name: group.name.clone(),
+            volume: group.volume().as_f32(),
+            members,
+        }
+    }
+
+    /// Device IDs of all members
+    pub fn device_ids(&self) -> impl Iterator<Item = &str> {
+        self.members.iter().map(|m| m.device_id.as_str())
+    }
+
+    pub(crate) fn member_delay(member: &SavedMember) -> Duration {
+        Duration::from_millis(member.delay_ms)
+    }
+}
+
+/// Read saved groups from a file
+///
+/// A missing or empty file yields no groups.
+///
+/// # Errors
+///
+/// Returns error if the file exists but cannot be read or parsed.
+pub async fn load_groups(path: impl AsRef<Path>) -> Result<Vec<SavedGroup>, AirPlayError> {
+    let path = path.as_ref();
+    match tokio::fs::read(path).await {
+        Ok(bytes) if bytes.is_empty() => Ok(Vec::new()),
+        Ok(bytes) => serde_json::from_slice(&bytes).map_err(|e| AirPlayError::IoError {
+            message: format!("invalid groups file {}: {e}", path.display()),
+            source: Some(Box::new(e)),
+        }),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Write saved groups to a file, creating its directory if needed
+///
+/// # Errors
+///
+/// Returns error if the file cannot be written.
+pub async fn save_groups(
+    path: impl AsRef<Path>,
+    groups: &[SavedGroup],
+) -> Result<(), AirPlayError> {
+    let path = path.as_ref();
+    if let Some(parent) = path.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let json = serde_json::to_vec_pretty(groups).map_err(|e| AirPlayError::IoError {
+        message: format!("failed to serialize groups: {e}"),
+        source: Some(Box::new(e)),
+    })?;
+    tokio::fs::write(path, json).await?;
+    Ok(())
+}
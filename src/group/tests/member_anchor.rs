@@ -0,0 +1,73 @@
+use std::time::Duration;
+
+use super::test_device;
+use crate::client::AirPlayClient;
+use crate::group::manager::DeviceGroup;
+use crate::testing::mock_server::{MockServer, MockServerConfig};
+
+/// Client connected to a mock member that reports a PTP clock, so its
+/// anchors carry network times
+async fn connected_member(id: &str) -> (MockServer, AirPlayClient) {
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        clock_id: Some(0x1122_3344_5566_7788),
+        ..Default::default()
+    });
+    let addr = server.start().await.unwrap();
+
+    let mut device = test_device(id);
+    device.addresses = vec![addr.ip()];
+    device.port = addr.port();
+    device.capabilities.airplay2 = true;
+    device.capabilities.supports_audio = true;
+
+    let client = AirPlayClient::default_client();
+    client.connect(&device).await.unwrap();
+    (server, client)
+}
+
+/// Network time of the last anchor a mock received
+async fn last_anchor_time(server: &MockServer) -> Duration {
+    let plists = server.rate_anchor_plists().await;
+    let anchor = plists.last().unwrap().as_dict().unwrap();
+    let secs = anchor["networkTimeSecs"].as_u64().unwrap();
+    // Encoded as a signed integer with the fraction's bit pattern
+    let frac = anchor["networkTimeFrac"].as_i64().unwrap().cast_unsigned();
+    let nanos = (u128::from(frac) * 1_000_000_000) >> 64;
+    Duration::new(secs, u32::try_from(nanos).unwrap())
+}
+
+#[tokio::test]
+async fn test_delayed_member_gets_offset_anchor() {
+    let (fast_server, fast) = connected_member("fast").await;
+    let (slow_server, slow) = connected_member("slow").await;
+
+    let mut group = DeviceGroup::new("Living Room");
+    group.add_member(test_device("fast"));
+    group.add_member(test_device("slow"));
+    group.set_member_delay("fast", Duration::from_millis(250));
+
+    let clients = [fast.clone(), slow.clone()];
+    assert_eq!(group.apply_member_delays(&clients).await, 2);
+    assert_eq!(fast.playback_delay(), Duration::from_millis(250));
+    assert_eq!(slow.playback_delay(), Duration::ZERO);
+
+    slow.reanchor().await.unwrap();
+    fast.reanchor().await.unwrap();
+
+    let offset = last_anchor_time(&fast_server)
+        .await
+        .checked_sub(last_anchor_time(&slow_server).await)
+        .unwrap();
+    assert!(offset >= Duration::from_millis(250), "offset {offset:?}");
+    assert!(offset < Duration::from_millis(400), "offset {offset:?}");
+
+    // The timeline keeps the group's undelayed anchor
+    let group_time = fast.timeline().await.and_then(|t| t.anchor_network_time());
+    if let Some(group_time) = group_time {
+        assert!(group_time.to_duration() < last_anchor_time(&fast_server).await);
+    }
+}
@@ -1,10 +1,17 @@
+#[cfg(feature = "receiver")]
+mod member_anchor;
+mod persist;
+mod watchdog;
+
 use std::collections::HashMap;
+use std::time::Duration;
 
 use crate::control::volume::Volume;
 use crate::group::manager::*;
+use crate::protocol::ptp::PtpTimestamp;
 use crate::types::{AirPlayDevice, DeviceCapabilities};
 
-fn test_device(id: &str) -> AirPlayDevice {
+pub(super) fn test_device(id: &str) -> AirPlayDevice {
     AirPlayDevice {
         id: id.to_string(),
         name: format!("Device {id}"),
@@ -344,3 +351,101 @@ async fn test_create_group_with_multiple_devices_success() {
     assert!(!group.member("d2").unwrap().is_leader);
     assert!(!group.member("d3").unwrap().is_leader);
 }
+
+#[test]
+fn test_member_delay() {
+    let mut group = DeviceGroup::new("Delay");
+    group.add_member(test_device("homepod"));
+    group.add_member(test_device("soundbar"));
+
+    group.set_member_delay("homepod", Duration::from_millis(100));
+
+    assert_eq!(group.member_delay("homepod"), Duration::from_millis(100));
+    assert_eq!(group.member_delay("soundbar"), Duration::ZERO);
+    assert_eq!(group.member_delay("unknown"), Duration::ZERO);
+    assert_eq!(group.member_delay_samples("homepod", 44100), 4410);
+
+    // Delayed member anchors an earlier RTP time to the same network time
+    assert_eq!(group.member_anchor_rtp_time("homepod", 10_000, 44100), 5590);
+    assert_eq!(
+        group.member_anchor_rtp_time("homepod", 0, 44100),
+        0u32.wrapping_sub(4410)
+    );
+    assert_eq!(
+        group.member_anchor_rtp_time("soundbar", 10_000, 44100),
+        10_000
+    );
+
+    let base = PtpTimestamp::new(100, 0);
+    assert_eq!(
+        group.member_anchor_network_time("homepod", base),
+        PtpTimestamp::new(100, 100_000_000)
+    );
+}
+
+#[test]
+fn test_apply_latency_measurements() {
+    let mut group = DeviceGroup::new("Latency");
+    group.add_member(test_device("homepod"));
+    group.add_member(test_device("soundbar"));
+    group.add_member(test_device("unmeasured"));
+    group.set_member_delay("unmeasured", Duration::from_millis(40));
+
+    let trims = group.apply_latency_measurements(&[
+        ("homepod", Duration::from_millis(2050)),
+        ("soundbar", Duration::from_millis(2180)),
+        ("stranger", Duration::from_secs(5)),
+    ]);
+
+    // The faster member waits for the slower one
+    assert_eq!(trims.len(), 2);
+    assert_eq!(group.member_delay("homepod"), Duration::from_millis(130));
+    assert_eq!(group.member_delay("soundbar"), Duration::ZERO);
+    assert_eq!(group.member_delay("unmeasured"), Duration::from_millis(40));
+
+    assert!(group.apply_latency_measurements(&[]).is_empty());
+}
+
+#[test]
+fn test_member_timeline() {
+    let mut group = DeviceGroup::new("Timeline");
+    group.add_member(test_device("homepod"));
+    group.add_member(test_device("soundbar"));
+    group.set_member_delay("homepod", Duration::from_millis(100));
+
+    let mut timeline = crate::streaming::Timeline::new(44100);
+    timeline.anchor(0, Duration::ZERO, Some(PtpTimestamp::new(100, 0)));
+
+    let homepod = group.member_timeline("homepod", &timeline);
+    assert_eq!(homepod.rtp_time_at(Duration::from_secs(1)), 44100);
+    assert_eq!(
+        homepod.network_time_at_rtp(0),
+        Some(group.member_anchor_network_time("homepod", PtpTimestamp::new(100, 0)))
+    );
+    assert_eq!(group.member_timeline("soundbar", &timeline), timeline);
+}
+
+#[tokio::test]
+async fn test_manager_set_member_delay() {
+    let manager = GroupManager::new();
+    let group_id = manager
+        .create_group_with_devices("Delay", vec![test_device("d1")])
+        .await
+        .unwrap();
+
+    manager
+        .set_member_delay(&group_id, "d1", Duration::from_millis(250))
+        .await
+        .unwrap();
+
+    let group = manager.get_group(&group_id).await.unwrap();
+    assert_eq!(group.member_delay("d1"), Duration::from_millis(250));
+
+    let missing = GroupId::from_string("missing");
+    assert!(
+        manager
+            .set_member_delay(&missing, "d1", Duration::ZERO)
+            .await
+            .is_err()
+    );
+}
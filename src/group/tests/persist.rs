@@ -0,0 +1,124 @@
+use std::time::Duration;
+
+use super::test_device;
+use crate::control::volume::Volume;
+use crate::discovery::cache::DeviceCache;
+use crate::group::{GroupManager, SavedGroup, load_groups};
+
+fn cache_with(ids: &[&str]) -> DeviceCache {
+    let mut cache = DeviceCache::in_memory();
+    for id in ids {
+        cache.insert(&test_device(id));
+    }
+    cache
+}
+
+#[tokio::test]
+async fn test_save_and_restore_named_group() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("config").join("groups.json");
+
+    let manager = GroupManager::new();
+    let id = manager
+        .create_group_with_devices(
+            "Downstairs",
+            vec![test_device("kitchen"), test_device("living")],
+        )
+        .await
+        .unwrap();
+    manager
+        .set_group_volume(&id, Volume::from_percent(40))
+        .await
+        .unwrap();
+    manager
+        .set_member_volume(&id, "living", Volume::from_percent(80))
+        .await
+        .unwrap();
+    manager
+        .set_member_delay(&id, "kitchen", Duration::from_millis(25))
+        .await
+        .unwrap();
+    manager.save(&path).await.unwrap();
+
+    let restored = GroupManager::new();
+    let ids = restored
+        .restore(&path, &cache_with(&["kitchen", "living"]))
+        .await
+        .unwrap();
+    assert_eq!(ids, vec![id.clone()]);
+
+    assert_eq!(
+        restored.find_group_by_name("Downstairs").await,
+        Some(id.clone())
+    );
+    let group = restored.get_group(&id).await.unwrap();
+    assert_eq!(group.member_count(), 2);
+    assert_eq!(group.leader().unwrap().device.id, "kitchen");
+    assert_eq!(group.volume().as_percent(), 40);
+    assert_eq!(group.member("living").unwrap().volume.as_percent(), 80);
+    assert_eq!(group.member_delay("kitchen"), Duration::from_millis(25));
+    assert_eq!(restored.find_device_group("living").await, Some(id));
+}
+
+#[tokio::test]
+async fn test_restore_skips_unknown_devices() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("groups.json");
+
+    let manager = GroupManager::new();
+    manager
+        .create_group_with_devices("Downstairs", vec![test_device("a"), test_device("b")])
+        .await
+        .unwrap();
+    manager
+        .create_group_with_devices("Upstairs", vec![test_device("c")])
+        .await
+        .unwrap();
+    manager.save(&path).await.unwrap();
+
+    let restored = GroupManager::new();
+    let ids = restored.restore(&path, &cache_with(&["b"])).await.unwrap();
+    assert_eq!(ids.len(), 1);
+
+    let group = restored.get_group(&ids[0]).await.unwrap();
+    assert_eq!(group.name, "Downstairs");
+    assert_eq!(group.member_count(), 1);
+    assert!(group.leader().unwrap().is_leader);
+    assert!(restored.find_group_by_name("Upstairs").await.is_none());
+}
+
+#[tokio::test]
+async fn test_restore_does_not_duplicate_names() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("groups.json");
+
+    let manager = GroupManager::new();
+    manager
+        .create_group_with_devices("Downstairs", vec![test_device("a")])
+        .await
+        .unwrap();
+    manager.save(&path).await.unwrap();
+
+    let ids = manager.restore(&path, &cache_with(&["a"])).await.unwrap();
+    assert!(ids.is_empty());
+    assert_eq!(manager.all_groups().await.len(), 1);
+}
+
+#[tokio::test]
+async fn test_load_missing_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let groups = load_groups(dir.path().join("missing.json")).await.unwrap();
+    assert!(groups.is_empty());
+}
+
+#[test]
+fn test_saved_group_after_leader_removed() {
+    let mut group = crate::group::DeviceGroup::new("Test");
+    group.add_member(test_device("one"));
+    group.add_member(test_device("two"));
+    group.add_member(test_device("three"));
+    group.remove_member("one");
+
+    let saved = SavedGroup::from_group(&group);
+    assert_eq!(saved.device_ids().collect::<Vec<_>>(), vec!["two", "three"]);
+}
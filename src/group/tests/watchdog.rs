@@ -0,0 +1,110 @@
+use std::time::{Duration, Instant};
+
+use crate::group::{GroupEvent, GroupWatchdog, ResyncAction, WatchdogConfig};
+
+fn watchdog(now: Instant) -> GroupWatchdog {
+    let mut watchdog = GroupWatchdog::new(WatchdogConfig {
+        max_drift: Duration::from_millis(2),
+        response_timeout: Duration::from_secs(3),
+        max_attempts: 2,
+        ..WatchdogConfig::default()
+    });
+    watchdog.add_member("kitchen", now);
+    watchdog.add_member("lounge", now);
+    watchdog
+}
+
+#[test]
+fn test_drifting_member_is_reanchored() {
+    let start = Instant::now();
+    let mut watchdog = watchdog(start);
+
+    watchdog.record_offset("kitchen", 1_000_000);
+    watchdog.record_offset("lounge", -40_000_000);
+    watchdog.record_offset("lounge", -39_000_000);
+    assert!(watchdog.check(start).is_empty());
+    assert_eq!(watchdog.drift("lounge"), Some(Duration::from_millis(1)));
+
+    // Only the drifting member is resynced
+    watchdog.record_offset("kitchen", 4_500_000);
+    assert_eq!(
+        watchdog.check(start),
+        vec![("kitchen".to_string(), ResyncAction::Reanchor)]
+    );
+
+    let event = watchdog.resync_succeeded("kitchen", ResyncAction::Reanchor, start);
+    assert_eq!(
+        event,
+        GroupEvent::MemberResynced {
+            device_id: "kitchen".to_string(),
+            action: ResyncAction::Reanchor,
+        }
+    );
+    // The next sample becomes the new baseline
+    assert_eq!(watchdog.drift("kitchen"), None);
+    watchdog.record_offset("kitchen", 4_600_000);
+    assert!(watchdog.check(start).is_empty());
+}
+
+#[test]
+fn test_silent_member_is_reconnected_then_lost() {
+    let start = Instant::now();
+    let mut watchdog = watchdog(start);
+
+    let later = start + Duration::from_secs(4);
+    watchdog.record_response("lounge", later);
+    assert_eq!(
+        watchdog.check(later),
+        vec![("kitchen".to_string(), ResyncAction::Reconnect)]
+    );
+
+    assert!(
+        watchdog
+            .resync_failed("kitchen", ResyncAction::Reconnect, "refused", later)
+            .is_none()
+    );
+    // The failed attempt restarts the timeout
+    assert!(watchdog.check(later).is_empty());
+
+    let retry = later + Duration::from_secs(4);
+    watchdog.record_response("lounge", retry);
+    assert_eq!(watchdog.check(retry).len(), 1);
+    let lost = watchdog.resync_failed("kitchen", ResyncAction::Reconnect, "refused", retry);
+    assert!(matches!(
+        lost,
+        Some(GroupEvent::MemberLost { ref device_id, .. }) if device_id == "kitchen"
+    ));
+    assert!(watchdog.is_lost("kitchen"));
+    assert!(
+        watchdog
+            .check(retry + Duration::from_secs(10))
+            .iter()
+            .all(|(id, _)| id != "kitchen")
+    );
+}
+
+#[test]
+fn test_failed_reanchor_escalates_to_reconnect() {
+    let start = Instant::now();
+    let mut watchdog = watchdog(start);
+
+    watchdog.record_offset("lounge", 0);
+    watchdog.record_offset("lounge", 10_000_000);
+    assert_eq!(
+        watchdog.check(start),
+        vec![("lounge".to_string(), ResyncAction::Reanchor)]
+    );
+    assert!(
+        watchdog
+            .resync_failed("lounge", ResyncAction::Reanchor, "400", start)
+            .is_none()
+    );
+    assert_eq!(
+        watchdog.check(start),
+        vec![("lounge".to_string(), ResyncAction::Reconnect)]
+    );
+
+    watchdog.resync_succeeded("lounge", ResyncAction::Reconnect, start);
+    assert!(watchdog.check(start).is_empty());
+    assert!(watchdog.remove_member("lounge"));
+}
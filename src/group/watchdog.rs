@@ -0,0 +1,376 @@
+//! Group stream watchdog
+//!
+//! While a group plays, each member keeps its own PTP session with the
+//! sender. A member whose clock offset wanders away from the value it was
+//! anchored with will play out of step with the rest of the room, and one
+//! that stops answering timing exchanges has usually dropped off the network.
+//! The watchdog spots both cases and resyncs just that member, re-anchoring
+//! first and reconnecting when it stops responding, while the others keep
+//! playing.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+use crate::client::AirPlayClient;
+
+/// Events reported by the group watchdog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GroupEvent {
+    /// A member drifted or went quiet and was brought back in sync
+    MemberResynced {
+        /// Device ID of the member
+        device_id: String,
+        /// How the member was resynced
+        action: ResyncAction,
+    },
+    /// A member could not be resynced and is no longer watched
+    MemberLost {
+        /// Device ID of the member
+        device_id: String,
+        /// Why the member was given up on
+        reason: String,
+    },
+}
+
+/// How a member is brought back in sync
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResyncAction {
+    /// Send a fresh `SETRATEANCHORTIME` so the member re-derives its timeline
+    Reanchor,
+    /// Tear down and re-establish the member's connection
+    Reconnect,
+}
+
+impl std::fmt::Display for ResyncAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Reanchor => f.write_str("re-anchor"),
+            Self::Reconnect => f.write_str("reconnect"),
+        }
+    }
+}
+
+/// Watchdog thresholds
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    /// How often members are checked
+    pub interval: Duration,
+    /// Largest change in PTP offset since the last anchor before re-anchoring
+    pub max_drift: Duration,
+    /// How long a member may go without answering before reconnecting
+    pub response_timeout: Duration,
+    /// Consecutive failed resyncs before a member is reported lost
+    pub max_attempts: u32,
+}
+
+impl Default for WatchdogConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            max_drift: Duration::from_millis(5),
+            response_timeout: Duration::from_secs(5),
+            max_attempts: 3,
+        }
+    }
+}
+
+/// Health of a single member
+#[derive(Debug)]
+struct MemberWatch {
+    /// PTP offset at the last anchor, in nanoseconds
+    baseline: Option<i128>,
+    /// Latest PTP offset, in nanoseconds
+    offset: Option<i128>,
+    /// When the member last answered
+    last_response: Instant,
+    /// Consecutive failed resyncs
+    failures: u32,
+    /// A re-anchor failed, so the next resync reconnects
+    escalate: bool,
+    /// Given up on
+    lost: bool,
+}
+
+/// Per-member drift and liveness tracking
+///
+/// Sans-IO: the caller feeds in offset samples and responses, asks which
+/// members need resyncing, and reports back how each resync went.
+/// [`WatchdogHandle::spawn`] drives it for a set of connected clients.
+#[derive(Debug)]
+pub struct GroupWatchdog {
+    config: WatchdogConfig,
+    members: HashMap<String, MemberWatch>,
+}
+
+impl GroupWatchdog {
+    /// Create a watchdog with no members
+    #[must_use]
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self {
+            config,
+            members: HashMap::new(),
+        }
+    }
+
+    /// Thresholds in use
+    #[must_use]
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    /// Start watching a member
+    pub fn add_member(&mut self, device_id: impl Into<String>, now: Instant) {
+        self.members.insert(
+            device_id.into(),
+            MemberWatch {
+                baseline: None,
+                offset: None,
+                last_response: now,
+                failures: 0,
+                escalate: false,
+                lost: false,
+            },
+        );
+    }
+
+    /// Stop watching a member
+    pub fn remove_member(&mut self, device_id: &str) -> bool {
+        self.members.remove(device_id).is_some()
+    }
+
+    /// Whether a member has been given up on
+    #[must_use]
+    pub fn is_lost(&self, device_id: &str) -> bool {
+        self.members.get(device_id).is_some_and(|m| m.lost)
+    }
+
+    /// Record a member's current PTP offset
+    ///
+    /// The first sample after an anchor becomes the baseline drift is
+    /// measured from.
+    pub fn record_offset(&mut self, device_id: &str, offset_nanos: i128) {
+        if let Some(member) = self.members.get_mut(device_id) {
+            member.baseline.get_or_insert(offset_nanos);
+            member.offset = Some(offset_nanos);
+        }
+    }
+
+    /// Record that a member answered (a timing exchange, feedback, or any
+    /// other response)
+    pub fn record_response(&mut self, device_id: &str, now: Instant) {
+        if let Some(member) = self.members.get_mut(device_id) {
+            member.last_response = now;
+        }
+    }
+
+    /// Change in a member's offset since it was last anchored
+    #[must_use]
+    pub fn drift(&self, device_id: &str) -> Option<Duration> {
+        let member = self.members.get(device_id)?;
+        let drift = member.offset?.abs_diff(member.baseline?);
+        Some(Duration::from_nanos(
+            u64::try_from(drift).unwrap_or(u64::MAX),
+        ))
+    }
+
+    /// Members that need resyncing, and how
+    ///
+    /// A member that has not answered within the response timeout is
+    /// reconnected; one that drifted too far is re-anchored.
+    #[must_use]
+    pub fn check(&self, now: Instant) -> Vec<(String, ResyncAction)> {
+        let mut actions: Vec<(String, ResyncAction)> = self
+            .members
+            .iter()
+            .filter(|(_, member)| !member.lost)
+            .filter_map(|(id, member)| {
+                let silent =
+                    now.duration_since(member.last_response) > self.config.response_timeout;
+                let drifted = self.drift(id).is_some_and(|d| d > self.config.max_drift);
+                if silent || (drifted && member.escalate) {
+                    Some((id.clone(), ResyncAction::Reconnect))
+                } else if drifted {
+                    Some((id.clone(), ResyncAction::Reanchor))
+                } else {
+                    None
+                }
+            })
+            .collect();
+        actions.sort_by(|a, b| a.0.cmp(&b.0));
+        actions
+    }
+
+    /// Report a successful resync
+    ///
+    /// Resets the member's baseline, so the next offset sample is measured
+    /// against its new anchor.
+    pub fn resync_succeeded(
+        &mut self,
+        device_id: &str,
+        action: ResyncAction,
+        now: Instant,
+    ) -> GroupEvent {
+        if let Some(member) = self.members.get_mut(device_id) {
+            member.baseline = None;
+            member.offset = None;
+            member.last_response = now;
+            member.failures = 0;
+            member.escalate = false;
+        }
+        GroupEvent::MemberResynced {
+            device_id: device_id.to_string(),
+            action,
+        }
+    }
+
+    /// Report a failed resync
+    ///
+    /// A failed re-anchor escalates to a reconnect. Returns
+    /// [`GroupEvent::MemberLost`] once `max_attempts` consecutive resyncs
+    /// have failed; the member is then no longer checked.
+    pub fn resync_failed(
+        &mut self,
+        device_id: &str,
+        action: ResyncAction,
+        reason: &str,
+        now: Instant,
+    ) -> Option<GroupEvent> {
+        let member = self.members.get_mut(device_id)?;
+        member.failures += 1;
+        member.escalate = true;
+        // Wait out another timeout before the next reconnect attempt
+        if action == ResyncAction::Reconnect {
+            member.last_response = now;
+        }
+
+        if member.failures < self.config.max_attempts {
+            return None;
+        }
+        member.lost = true;
+        Some(GroupEvent::MemberLost {
+            device_id: device_id.to_string(),
+            reason: format!("{} failed {} times: {reason}", action, member.failures),
+        })
+    }
+}
+
+/// A watchdog running in the background over connected group members
+pub struct WatchdogHandle {
+    events: broadcast::Sender<GroupEvent>,
+    task: JoinHandle<()>,
+}
+
+impl WatchdogHandle {
+    /// Watch a set of connected clients, one per group member
+    ///
+    /// Clients that are not connected are ignored. Each check samples every
+    /// member's PTP clock: a rising measurement count counts as a response,
+    /// and the offset is compared against the one at the last anchor.
+    /// Members without PTP timing are only checked for liveness.
+    #[must_use]
+    pub fn spawn(clients: Vec<AirPlayClient>, config: WatchdogConfig) -> Self {
+        let (events, _) = broadcast::channel(32);
+        let task = tokio::spawn(run(clients, GroupWatchdog::new(config), events.clone()));
+        Self { events, task }
+    }
+
+    /// Subscribe to watchdog events
+    #[must_use]
+    pub fn subscribe(&self) -> broadcast::Receiver<GroupEvent> {
+        self.events.subscribe()
+    }
+
+    /// Stop watching
+    pub fn stop(self) {
+        self.task.abort();
+    }
+}
+
+impl Drop for WatchdogHandle {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+struct Watched {
+    client: AirPlayClient,
+    device: crate::types::AirPlayDevice,
+    measurements: usize,
+}
+
+async fn run(
+    clients: Vec<AirPlayClient>,
+    mut watchdog: GroupWatchdog,
+    events: broadcast::Sender<GroupEvent>,
+) {
+    let mut members = Vec::with_capacity(clients.len());
+    let now = Instant::now();
+    for client in clients {
+        if let Some(device) = client.connected_device().await {
+            watchdog.add_member(device.id.clone(), now);
+            members.push(Watched {
+                client,
+                device,
+                measurements: 0,
+            });
+        }
+    }
+
+    let mut interval = tokio::time::interval(watchdog.config().interval);
+    loop {
+        interval.tick().await;
+        let now = Instant::now();
+
+        for member in &mut members {
+            sample(&mut watchdog, member, now).await;
+        }
+
+        for (device_id, action) in watchdog.check(now) {
+            let Some(member) = members.iter_mut().find(|m| m.device.id == device_id) else {
+                continue;
+            };
+            tracing::info!("Group watchdog: {action} {device_id}");
+            let result = match action {
+                ResyncAction::Reanchor => member.client.reanchor().await,
+                ResyncAction::Reconnect => {
+                    member.client.disconnect().await.ok();
+                    member.measurements = 0;
+                    member.client.connect(&member.device).await
+                }
+            };
+
+            let now = Instant::now();
+            let event = match result {
+                Ok(()) => Some(watchdog.resync_succeeded(&device_id, action, now)),
+                Err(e) => {
+                    tracing::warn!("Group watchdog: {action} {device_id} failed: {e}");
+                    watchdog.resync_failed(&device_id, action, &e.to_string(), now)
+                }
+            };
+            if let Some(event) = event {
+                let _ = events.send(event);
+            }
+        }
+
+        members.retain(|m| !watchdog.is_lost(&m.device.id));
+    }
+}
+
+async fn sample(watchdog: &mut GroupWatchdog, member: &mut Watched, now: Instant) {
+    let id = &member.device.id;
+    if let Some(clock) = member.client.ptp_clock().await {
+        let clock = clock.read().await;
+        if clock.measurement_count() != member.measurements {
+            member.measurements = clock.measurement_count();
+            watchdog.record_response(id, now);
+        }
+        if clock.is_synchronized() {
+            watchdog.record_offset(id, clock.offset_nanos());
+        }
+    } else if member.client.is_connected().await {
+        watchdog.record_response(id, now);
+    }
+}
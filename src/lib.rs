@@ -54,11 +54,13 @@
 pub mod error;
 
 /// State management
+#[cfg(feature = "sender")]
 pub mod state;
 /// Core types
 pub mod types;
 
 /// Receiver implementation
+#[cfg(feature = "receiver")]
 pub mod receiver;
 
 /// Testing utilities
@@ -66,33 +68,46 @@ pub mod testing;
 
 // Internal modules
 pub mod audio;
+#[cfg(feature = "sender")]
 mod client;
+#[cfg(feature = "sender")]
 pub mod connection;
+#[cfg(feature = "sender")]
 pub mod control;
 pub mod discovery;
+#[cfg(feature = "sender")]
 pub mod group;
 pub mod net;
+#[cfg(feature = "sender")]
 mod player;
 pub mod protocol;
 /// Streaming support
+#[cfg(feature = "sender")]
 pub mod streaming;
 
 // Re-exports
 pub use audio::AudioFormat;
+#[cfg(feature = "sender")]
 pub use client::{
-    AirPlayClient, ClientConfig, PreferredProtocol, SelectedProtocol, UnifiedAirPlayClient,
-    check_raop_encryption,
+    AirPlayClient, CheckStatus, ClientConfig, PreferredProtocol, PreflightCheck, PreflightReport,
+    SelectedProtocol, StreamHandle, UnifiedAirPlayClient, check_raop_encryption,
 };
+#[cfg(feature = "sender")]
+pub use control::remote::{MediaCommand, NowPlayingInfo, RemoteEvent};
+#[cfg(feature = "sender")]
 pub use control::volume::Volume;
-pub use discovery::{DiscoveryEvent, discover, scan};
+#[cfg(feature = "sender")]
+pub use discovery::{DiscoveryEvent, discover, scan, scan_for};
 pub use error::AirPlayError;
-pub use group::{DeviceGroup, GroupId, GroupManager};
+#[cfg(feature = "sender")]
+pub use group::{DeviceGroup, GroupEvent, GroupId, GroupManager};
+#[cfg(feature = "sender")]
 pub use player::{AirPlayPlayer, PlayerBuilder, quick_connect, quick_connect_to, quick_play};
+#[cfg(feature = "sender")]
 pub use state::{ClientEvent, ClientState};
-pub use types::{
-    AirPlayConfig, AirPlayDevice, DeviceCapabilities, PlaybackState, RepeatMode, TimingProtocol,
-    TrackInfo,
-};
+#[cfg(feature = "sender")]
+pub use types::{AirPlayConfig, PreemptedReconnect, TimingProtocol};
+pub use types::{AirPlayDevice, DeviceCapabilities, PlaybackState, RepeatMode, TrackInfo};
 
 /// Library version
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -100,6 +115,7 @@ pub const VERSION: &str = env!("CARGO_PKG_VERSION");
 /// Prelude for common imports
 ///
 /// Convenient re-exports
+#[cfg(feature = "sender")]
 pub mod prelude {
     pub use crate::{
         AirPlayClient, AirPlayConfig, AirPlayDevice, AirPlayError, AirPlayPlayer, AudioFormat,
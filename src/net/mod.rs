@@ -3,6 +3,8 @@
 //! This module provides runtime-agnostic networking primitives.
 
 pub mod secure;
+#[cfg(any(feature = "sender", feature = "dacp"))]
+mod task_set;
 mod traits;
 
 #[cfg(feature = "tokio-runtime")]
@@ -17,9 +19,12 @@ mod tests;
 
 // #[cfg(all(feature = "async-std-runtime", not(feature = "tokio-runtime")))]
 // pub use async_std_impl::*;
+#[cfg(feature = "tokio-runtime")]
 use std::future::Future;
 
 // Re-export the active runtime's types
+#[cfg(any(feature = "sender", feature = "dacp"))]
+pub(crate) use task_set::TaskSet;
 #[cfg(feature = "tokio-runtime")]
 pub use tokio_impl::*;
 pub use traits::{
@@ -44,6 +44,7 @@ impl HapSecureSession {
             let len = u16::try_from(chunk.len()).map_err(|_| AirPlayError::RtspError {
                 message: "Chunk size exceeds u16".to_string(),
                 status_code: None,
+                source: None,
             })?;
             let mut len_bytes = [0u8; 2];
             LittleEndian::write_u16(&mut len_bytes, len);
@@ -60,6 +61,7 @@ impl HapSecureSession {
                 .map_err(|_| AirPlayError::AuthenticationFailed {
                     message: "Encryption failed".to_string(),
                     recoverable: false,
+                    source: None,
                 })?;
 
             output.extend_from_slice(&len_bytes);
@@ -86,6 +88,7 @@ impl HapSecureSession {
             return Err(AirPlayError::RtspError {
                 message: "Buffer too small for HAP block".to_string(),
                 status_code: None,
+                source: None,
             });
         }
 
@@ -94,6 +97,7 @@ impl HapSecureSession {
             return Err(AirPlayError::RtspError {
                 message: "Incomplete HAP block".to_string(),
                 status_code: None,
+                source: None,
             });
         }
 
@@ -106,6 +110,7 @@ impl HapSecureSession {
             AirPlayError::AuthenticationFailed {
                 message: "Invalid tag length".to_string(),
                 recoverable: false,
+                source: None,
             }
         })?;
 
@@ -115,6 +120,7 @@ impl HapSecureSession {
             .map_err(|_| AirPlayError::AuthenticationFailed {
                 message: "Decryption failed".to_string(),
                 recoverable: false,
+                source: None,
             })?;
 
         self.decrypt_count += 1;
@@ -0,0 +1,80 @@
+//! Ownership of background tasks
+
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use tokio::task::{AbortHandle, JoinError, JoinSet};
+
+/// Background tasks owned by a component
+///
+/// Tasks are aborted when the set is shut down or dropped, so none outlive
+/// the component that started them. Finished tasks are collected whenever
+/// another is spawned; one that panicked is logged, and in unit tests its
+/// panic is resumed so the test fails.
+pub(crate) struct TaskSet {
+    /// Owner named in logs
+    owner: &'static str,
+    tasks: Mutex<JoinSet<()>>,
+}
+
+impl TaskSet {
+    /// Create an empty set for `owner`
+    pub(crate) fn new(owner: &'static str) -> Self {
+        Self {
+            owner,
+            tasks: Mutex::new(JoinSet::new()),
+        }
+    }
+
+    /// Spawn a task owned by the set
+    pub(crate) fn spawn<F>(&self, task: F) -> AbortHandle
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut tasks = self.lock();
+        self.reap(&mut tasks);
+        tasks.spawn(task)
+    }
+
+    /// Abort every task and wait for them to end
+    pub(crate) async fn shutdown(&self) {
+        let mut tasks = std::mem::take(&mut *self.lock());
+        tasks.abort_all();
+        while let Some(result) = tasks.join_next().await {
+            self.check(result);
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, JoinSet<()>> {
+        // The set stays consistent even if a holder panicked
+        self.tasks.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Collect finished tasks
+    fn reap(&self, tasks: &mut JoinSet<()>) {
+        while let Some(result) = tasks.try_join_next() {
+            self.check(result);
+        }
+    }
+
+    fn check(&self, result: Result<(), JoinError>) {
+        let Err(e) = result else {
+            return;
+        };
+        if e.is_panic() {
+            tracing::error!("{} background task panicked: {}", self.owner, e);
+            if cfg!(test) {
+                std::panic::resume_unwind(e.into_panic());
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for TaskSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TaskSet")
+            .field("owner", &self.owner)
+            .field("tasks", &self.lock().len())
+            .finish()
+    }
+}
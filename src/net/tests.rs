@@ -90,3 +90,71 @@ mod tokio_tests {
         assert_eq!(handle.await.unwrap(), 42);
     }
 }
+
+#[cfg(any(feature = "sender", feature = "dacp"))]
+mod task_set_tests {
+    use std::time::Duration;
+
+    use crate::net::TaskSet;
+
+    /// Sends on drop, so a test can tell when a task ended
+    struct DropSignal(Option<tokio::sync::oneshot::Sender<()>>);
+
+    impl Drop for DropSignal {
+        fn drop(&mut self) {
+            if let Some(tx) = self.0.take() {
+                let _ = tx.send(());
+            }
+        }
+    }
+
+    fn forever_task() -> (
+        impl std::future::Future<Output = ()>,
+        tokio::sync::oneshot::Receiver<()>,
+    ) {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+        let signal = DropSignal(Some(tx));
+        let task = async move {
+            let _signal = signal;
+            std::future::pending::<()>().await;
+        };
+        (task, rx)
+    }
+
+    #[tokio::test]
+    async fn test_task_set_shutdown_aborts_tasks() {
+        let tasks = TaskSet::new("test");
+        let (task, ended) = forever_task();
+        let handle = tasks.spawn(task);
+
+        tasks.shutdown().await;
+        assert!(handle.is_finished());
+        ended.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_task_set_drop_aborts_tasks() {
+        let tasks = TaskSet::new("test");
+        let (task, ended) = forever_task();
+        tasks.spawn(task);
+
+        drop(tasks);
+        tokio::time::timeout(Duration::from_secs(1), ended)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "task failed")]
+    async fn test_task_set_surfaces_panics() {
+        let tasks = TaskSet::new("test");
+        let handle = tasks.spawn(async { panic!("task failed") });
+        while !handle.is_finished() {
+            tokio::task::yield_now().await;
+        }
+
+        // The panic is collected with the next spawn
+        tasks.spawn(async {});
+    }
+}
@@ -7,9 +7,13 @@ use std::time::Duration;
 use tokio::sync::RwLock;
 
 use crate::client::AirPlayClient;
+use crate::discovery::DeviceStatus;
 use crate::error::AirPlayError;
+use crate::net::TaskSet;
 use crate::state::ClientEvent;
-use crate::types::{AirPlayConfig, AirPlayDevice, PlaybackState, RepeatMode, TrackInfo};
+use crate::types::{
+    AirPlayConfig, AirPlayDevice, PlaybackState, PreemptedReconnect, RepeatMode, TrackInfo,
+};
 
 #[cfg(test)]
 mod tests;
@@ -27,6 +31,16 @@ pub struct AirPlayPlayer {
     last_device: Arc<RwLock<Option<AirPlayDevice>>>,
     /// Reconnection in progress flag
     is_reconnecting: Arc<AtomicBool>,
+    /// Whether to reconnect after another sender took over
+    preempted_reconnect: PreemptedReconnect,
+    /// Crossfade between queued tracks
+    #[cfg(feature = "decoders")]
+    crossfade: Option<Duration>,
+    /// Loudness normalization of queued files
+    #[cfg(feature = "decoders")]
+    normalization: Option<crate::audio::GainMode>,
+    /// Reconnect monitor, aborted when the last clone of the player drops
+    tasks: Arc<TaskSet>,
 }
 
 impl Default for AirPlayPlayer {
@@ -46,11 +60,17 @@ impl AirPlayPlayer {
     #[must_use]
     pub fn with_config(config: AirPlayConfig) -> Self {
         let player = Self {
+            #[cfg(feature = "decoders")]
+            crossfade: config.crossfade,
+            #[cfg(feature = "decoders")]
+            normalization: config.normalization,
+            preempted_reconnect: config.preempted_reconnect,
             client: AirPlayClient::new(config),
             auto_reconnect: Arc::new(AtomicBool::new(true)),
             target_device_name: Arc::new(RwLock::new(None)),
             last_device: Arc::new(RwLock::new(None)),
             is_reconnecting: Arc::new(AtomicBool::new(false)),
+            tasks: Arc::new(TaskSet::new("player")),
         };
 
         player.start_reconnect_monitor();
@@ -72,9 +92,10 @@ impl AirPlayPlayer {
         let auto_reconnect = self.auto_reconnect.clone();
         let last_device = self.last_device.clone();
         let is_reconnecting = self.is_reconnecting.clone();
+        let preempted_reconnect = self.preempted_reconnect;
         let mut events = client.subscribe_events();
 
-        tokio::spawn(async move {
+        self.tasks.spawn(async move {
             while let Ok(event) = events.recv().await {
                 if let ClientEvent::Disconnected { reason, .. } = event {
                     tracing::info!("Player detected disconnect: {}", reason);
@@ -95,6 +116,20 @@ impl AirPlayPlayer {
                         continue;
                     }
 
+                    if reason.starts_with("Preempted")
+                        && !Self::await_preempted_device(
+                            &client,
+                            &last_device,
+                            &auto_reconnect,
+                            preempted_reconnect,
+                        )
+                        .await
+                    {
+                        tracing::info!("Leaving the device to the sender that took it over");
+                        is_reconnecting.store(false, Ordering::SeqCst);
+                        continue;
+                    }
+
                     tracing::info!("Attempting auto-reconnect in 2s...");
                     tokio::time::sleep(Duration::from_secs(2)).await;
 
@@ -180,6 +215,54 @@ impl AirPlayPlayer {
         });
     }
 
+    /// Wait until `policy` allows taking a preempted device back
+    ///
+    /// With [`PreemptedReconnect::AfterIdle`] the device is rediscovered
+    /// periodically until it has stopped advertising an active session for
+    /// long enough. Returns false if the device is left alone: the policy
+    /// never reconnects, auto-reconnect was turned off or the player was
+    /// connected again meanwhile.
+    async fn await_preempted_device(
+        client: &AirPlayClient,
+        last_device: &RwLock<Option<AirPlayDevice>>,
+        auto_reconnect: &AtomicBool,
+        policy: PreemptedReconnect,
+    ) -> bool {
+        /// Interval between checks of the device's advertised status
+        const IDLE_POLL: Duration = Duration::from_secs(5);
+
+        if !matches!(policy, PreemptedReconnect::AfterIdle(_)) {
+            return policy.allows(None);
+        }
+        let Some(device) = last_device.read().await.clone() else {
+            return false;
+        };
+
+        let mut idle_since = None;
+        loop {
+            tokio::time::sleep(IDLE_POLL).await;
+            if !auto_reconnect.load(Ordering::SeqCst) || client.is_connected().await {
+                return false;
+            }
+
+            // A device that cannot be found is not known to be idle
+            let idle = client
+                .scan_for(&device.id, IDLE_POLL)
+                .await
+                .is_ok_and(|found| !DeviceStatus::of(&found).is_busy());
+            if idle {
+                idle_since.get_or_insert_with(std::time::Instant::now);
+            } else {
+                idle_since = None;
+            }
+
+            if policy.allows(idle_since.map(|since| since.elapsed())) {
+                tracing::info!("Device {} is idle again", device.id);
+                return true;
+            }
+        }
+    }
+
     // === Quick Connect Methods ===
 
     /// Auto-connect to first available device (or target device if set)
@@ -193,10 +276,9 @@ impl AirPlayPlayer {
         let target = self.target_device_name.read().await.clone();
 
         let device = if let Some(target_name) = target {
-            let name_lower = target_name.to_lowercase();
             devices
                 .into_iter()
-                .find(|d| d.name.to_lowercase().contains(&name_lower))
+                .find(|d| crate::discovery::name_contains(&d.name, &target_name))
                 .ok_or_else(|| AirPlayError::DeviceNotFound {
                     device_id: target_name.clone(),
                 })?
@@ -213,7 +295,10 @@ impl AirPlayPlayer {
         Ok(device)
     }
 
-    /// Connect to device by name (partial match)
+    /// Connect to device by name (partial match) or ID
+    ///
+    /// Scanning stops as soon as a matching device is found; `timeout` only
+    /// bounds how long to wait for it.
     ///
     /// # Errors
     ///
@@ -223,15 +308,7 @@ impl AirPlayPlayer {
         name: &str,
         timeout: Duration,
     ) -> Result<AirPlayDevice, AirPlayError> {
-        let devices = self.client.scan(timeout).await?;
-
-        let name_lower = name.to_lowercase();
-        let device = devices
-            .into_iter()
-            .find(|d| d.name.to_lowercase().contains(&name_lower))
-            .ok_or_else(|| AirPlayError::DeviceNotFound {
-                device_id: name.to_string(),
-            })?;
+        let device = self.client.scan_for(name, timeout).await?;
 
         self.connect(&device).await?;
         Ok(device)
@@ -266,6 +343,11 @@ impl AirPlayPlayer {
 
     /// Play tracks from a list of (url, title, artist) tuples
     ///
+    /// With a crossfade or normalization configured and the `decoders`
+    /// feature enabled, the tracks are local files (paths or `file://` URLs)
+    /// that are decoded and streamed as one queue, fading each into the next
+    /// and played at a consistent loudness respectively.
+    ///
     /// # Errors
     ///
     /// Returns error if adding to queue or playback fails.
@@ -280,6 +362,14 @@ impl AirPlayPlayer {
             self.client.add_to_queue(track).await;
         }
 
+        #[cfg(feature = "decoders")]
+        if (self.crossfade.is_some() || self.normalization.is_some()) && !tracks.is_empty() {
+            let normalization = self.normalization;
+            let opener = Arc::new(move |track: &TrackInfo| open_file_track(track, normalization));
+            self.client.play_queue(opener).await?;
+            return Ok(());
+        }
+
         if let Some((url, _, _)) = tracks.first() {
             self.client.play_url(url).await
         } else {
@@ -506,7 +596,31 @@ impl AirPlayPlayer {
                 source: Some(Box::new(e)),
             })?;
 
-        // Ensure connected
+        self.reconnect_target().await?;
+        self.client.stream_audio(source).await?.wait().await
+    }
+
+    /// Download, decode and stream an `http://` URL (requires `decoders`
+    /// feature)
+    ///
+    /// Unlike [`AirPlayClient::play_url`], which asks the device to fetch
+    /// the URL itself, the audio is decoded here and streamed as PCM, so this
+    /// also works with receivers that reject `play_url`, such as RAOP-only
+    /// speakers. Internet radio song titles are shown on the device.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL cannot be fetched or decoded, or playback
+    /// fails.
+    #[cfg(feature = "decoders")]
+    pub async fn play_http(&mut self, url: &str) -> Result<(), AirPlayError> {
+        self.reconnect_target().await?;
+        self.client.stream_http(url).await?.wait().await
+    }
+
+    /// Connect to the target or last device if not connected
+    #[cfg(feature = "decoders")]
+    async fn reconnect_target(&mut self) -> Result<(), AirPlayError> {
         if !self.is_connected().await {
             if let Some(ref name) = *self.target_device_name.read().await {
                 self.connect_by_name(name, Duration::from_secs(5)).await?;
@@ -522,8 +636,24 @@ impl AirPlayPlayer {
                 }
             }
         }
+        Ok(())
+    }
 
-        self.client.stream_audio(source).await
+    /// Speak `text` over the current stream (requires `tts` feature)
+    ///
+    /// Speech is synthesized by the platform engine with the given `voice`
+    /// (or its default) and played as an announcement under the configured
+    /// announcement policy.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no speech engine is available or synthesis fails.
+    #[cfg(feature = "tts")]
+    pub async fn say(&self, text: &str, voice: Option<&str>) -> Result<(), AirPlayError> {
+        let speech = crate::streaming::tts::synthesize(text, voice).await?;
+        self.client
+            .play_with_priority(speech, crate::streaming::StreamPriority::Announcement);
+        Ok(())
     }
 }
 
@@ -559,6 +689,27 @@ impl PlayerBuilder {
         self
     }
 
+    /// Set whether to reconnect after another sender took over the device
+    #[must_use]
+    pub fn preempted_reconnect(mut self, policy: PreemptedReconnect) -> Self {
+        self.config.preempted_reconnect = policy;
+        self
+    }
+
+    /// Set the crossfade between queued tracks
+    #[must_use]
+    pub fn crossfade(mut self, fade: Duration) -> Self {
+        self.config.crossfade = Some(fade);
+        self
+    }
+
+    /// Normalize the loudness of queued tracks
+    #[must_use]
+    pub fn normalization(mut self, mode: crate::audio::GainMode) -> Self {
+        self.config.normalization = Some(mode);
+        self
+    }
+
     /// Set device name filter
     #[must_use]
     pub fn device_name(mut self, name: impl Into<String>) -> Self {
@@ -584,6 +735,27 @@ impl Default for PlayerBuilder {
     }
 }
 
+/// Open a queued local file for streaming
+///
+/// With normalization on, files whose track carries no `ReplayGain` values
+/// are normalized from their tags or a loudness analysis; the client applies
+/// the values of tracks that have them.
+#[cfg(feature = "decoders")]
+fn open_file_track(
+    track: &TrackInfo,
+    normalization: Option<crate::audio::GainMode>,
+) -> std::io::Result<Box<dyn crate::streaming::AudioSource>> {
+    use crate::streaming::file::FileSource;
+
+    let path = track.url.strip_prefix("file://").unwrap_or(&track.url);
+    match normalization {
+        Some(mode) if track.replay_gain.is_none() => {
+            Ok(Box::new(FileSource::normalized(path, mode)?))
+        }
+        _ => Ok(Box::new(FileSource::new(path)?)),
+    }
+}
+
 // === Convenience Functions ===
 
 /// Quick play to the first available device
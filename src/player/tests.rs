@@ -28,6 +28,14 @@ async fn test_builder() {
 async fn test_builder_defaults() {
     let player = PlayerBuilder::new().build();
     assert!(player.auto_reconnect.load(Ordering::SeqCst));
+    assert_eq!(player.preempted_reconnect, PreemptedReconnect::Never);
+}
+
+#[tokio::test]
+async fn test_builder_preempted_reconnect() {
+    let policy = PreemptedReconnect::AfterIdle(Duration::from_secs(60));
+    let player = PlayerBuilder::new().preempted_reconnect(policy).build();
+    assert_eq!(player.preempted_reconnect, policy);
 }
 
 #[tokio::test]
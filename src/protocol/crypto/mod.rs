@@ -17,6 +17,7 @@ mod error;
 mod hkdf;
 #[cfg(feature = "raop")]
 mod rsa;
+mod secret;
 mod srp;
 #[cfg(test)]
 mod tests;
@@ -30,8 +31,11 @@ pub use self::ed25519::{Ed25519KeyPair, Ed25519PublicKey, Ed25519Signature};
 pub use self::error::CryptoError;
 pub use self::hkdf::{AirPlayKeys, HkdfSha512, derive_key};
 #[cfg(feature = "raop")]
-pub use self::rsa::{AppleRsaPublicKey, CompatibleOsRng, RaopRsaPrivateKey, sizes as rsa_sizes};
-pub use self::srp::{SrpClient, SrpParams, SrpServer, SrpVerifier};
+pub use self::rsa::{
+    AppleRsaPublicKey, CompatibleOsRng, RaopRsaPrivateKey, RsaVerifyingKey, sizes as rsa_sizes,
+};
+pub use self::secret::{SecretBytes, constant_time_eq};
+pub use self::srp::{MIN_SALT_LEN, SrpClient, SrpGroup, SrpParams, SrpServer, SrpVerifier};
 pub use self::x25519::{X25519KeyPair, X25519PublicKey, X25519SharedSecret};
 
 /// Length of various cryptographic values
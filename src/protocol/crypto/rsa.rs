@@ -12,12 +12,17 @@ pub mod sizes {
     pub const OAEP_MAX_PLAINTEXT: usize = 86; // 128 - 2*20 - 2
     /// PKCS#1 signature size
     pub const SIGNATURE_BYTES: usize = 128;
+    /// Apple public key modulus size (2048 bits)
+    pub const APPLE_MODULUS_BITS: usize = 2048;
+    /// Apple public key modulus size in bytes
+    pub const APPLE_MODULUS_BYTES: usize = 256;
 }
 
 /// Apple's RSA public key used for RAOP authentication
 ///
-/// This is the well-known public key extracted from iTunes.
-/// Modulus: 1024 bits, Exponent: 65537
+/// This is the well-known public key extracted from iTunes, matching the
+/// private key in `AirPort` Express units.
+/// Modulus: 2048 bits, Exponent: 65537
 #[derive(Clone)]
 pub struct AppleRsaPublicKey {
     inner: rsa::RsaPublicKey,
@@ -26,14 +31,14 @@ pub struct AppleRsaPublicKey {
 impl AppleRsaPublicKey {
     /// The known Apple RSA public key modulus (hex)
     const MODULUS_HEX: &'static str = concat!(
-        "e7d7447851a2c8f3d70a3c9d18e63b5b",
-        "5f23e8c0f2e6c6b2a7f8e0c7a8b9d1e2",
-        "f3a4b5c6d7e8f90a1b2c3d4e5f60718",
-        "293a4b5c6d7e8f90a1b2c3d4e5f6071",
-        "8293a4b5c6d7e8f90a1b2c3d4e5f607",
-        "18293a4b5c6d7e8f90a1b2c3d4e5f60",
-        "718293a4b5c6d7e8f90a1b2c3d4e5f6",
-        "0718293a4b5c6d7e8f90a1b2c3d4e5f"
+        "e7d744f2a2e2788b6c1f55a08eb70544a8fa7945aa8be6c62ce5f51cbdd4dc68",
+        "42fe3d1083dd2edec1bfd4252dc02e6f398bdf0e6148ea84855e2e442da6d626",
+        "64f674a1f304929ade4f6893ef2df6e711a8c77a0d91c9d980822e50d12922af",
+        "ea40ea9f0e14c0f76938c5f3882fc0323dd9fe55155f51bb5921c201629fd733",
+        "52d5e2efaabf9ba048d7b813a2b6767f6c3ccf1eb4ce673d037b0d2ea30c5fff",
+        "eb06f8d08adde409571a9c689fef10728855dd8cfb9a8bef5c8943ef3b5faa15",
+        "dde698beddf3599603eb3e6f61372bb628f6559f599a78bf500687aa7f4976c0",
+        "562d412956f8989e18a6355bd81597825e0fc875343ec782117625cdbf98447b"
     );
 
     /// Standard RSA exponent
@@ -43,16 +48,11 @@ impl AppleRsaPublicKey {
     pub fn load() -> Result<Self, CryptoError> {
         use crypto_bigint::BoxedUint;
 
-        // Pad hex string to expected length (256 chars for 1024 bits)
-        let hex = Self::MODULUS_HEX;
-        let padded = if hex.len() < sizes::MODULUS_BYTES * 2 {
-            format!("{:0>width$}", hex, width = sizes::MODULUS_BYTES * 2)
-        } else {
-            hex.to_string()
-        };
-
-        let n = Option::from(BoxedUint::from_be_hex(&padded, sizes::MODULUS_BITS as u32))
-            .ok_or(CryptoError::InvalidPublicKey)?;
+        let n = Option::from(BoxedUint::from_be_hex(
+            Self::MODULUS_HEX,
+            sizes::APPLE_MODULUS_BITS as u32,
+        ))
+        .ok_or(CryptoError::InvalidPublicKey)?;
         let e = BoxedUint::from(Self::EXPONENT);
 
         let inner = rsa::RsaPublicKey::new(n, e).map_err(|_| CryptoError::InvalidPublicKey)?;
@@ -82,15 +82,83 @@ impl AppleRsaPublicKey {
             .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))
     }
 
-    /// Verify a PKCS#1 v1.5 signature
+    /// Verify a raw PKCS#1 v1.5 signature
     ///
-    /// Used to verify the Apple-Response header
+    /// Used to verify the Apple-Response header. Receivers sign the message
+    /// itself rather than a digest of it (`RSA_private_encrypt` with PKCS#1
+    /// padding), so no hash or `DigestInfo` prefix is involved.
     pub fn verify_pkcs1(&self, message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+        use rsa::Pkcs1v15Sign;
+        use rsa::traits::PublicKeyParts;
+
+        if signature.len() != self.inner.size() {
+            return Err(CryptoError::InvalidSignature);
+        }
+
+        self.inner
+            .verify(Pkcs1v15Sign::new_unprefixed(), message, signature)
+            .map_err(|_| CryptoError::VerificationFailed)
+    }
+}
+
+/// Use a receiver's own key in place of Apple's
+///
+/// Emulated receivers (shairport-style servers, the mock server) may hold a
+/// key pair other than the one in `AirPort` hardware.
+impl From<rsa::RsaPublicKey> for AppleRsaPublicKey {
+    fn from(inner: rsa::RsaPublicKey) -> Self {
+        Self { inner }
+    }
+}
+
+/// RSA public key from a certificate, for verifying PKCS#1 v1.5 signatures
+#[derive(Clone)]
+pub struct RsaVerifyingKey {
+    inner: rsa::RsaPublicKey,
+}
+
+impl RsaVerifyingKey {
+    /// Build from big-endian modulus and exponent bytes
+    pub fn from_components(modulus: &[u8], exponent: &[u8]) -> Result<Self, CryptoError> {
+        use crypto_bigint::BoxedUint;
+
+        let strip = |bytes: &[u8]| -> Vec<u8> {
+            let start = bytes.iter().position(|&b| b != 0).unwrap_or(bytes.len());
+            bytes[start..].to_vec()
+        };
+        let n = strip(modulus);
+        let e = strip(exponent);
+        if n.is_empty() || e.is_empty() {
+            return Err(CryptoError::InvalidPublicKey);
+        }
+
+        let inner = rsa::RsaPublicKey::new(
+            BoxedUint::from_be_slice_vartime(&n),
+            BoxedUint::from_be_slice_vartime(&e),
+        )
+        .map_err(|_| CryptoError::InvalidPublicKey)?;
+
+        Ok(Self { inner })
+    }
+
+    /// Verify a PKCS#1 v1.5 signature over a SHA-1 digest of `message`
+    pub fn verify_pkcs1_sha1(&self, message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+        self.verify::<sha1::Sha1>(message, signature)
+    }
+
+    /// Verify a PKCS#1 v1.5 signature over a SHA-256 digest of `message`
+    pub fn verify_pkcs1_sha256(&self, message: &[u8], signature: &[u8]) -> Result<(), CryptoError> {
+        self.verify::<sha2::Sha256>(message, signature)
+    }
+
+    fn verify<D>(&self, message: &[u8], signature: &[u8]) -> Result<(), CryptoError>
+    where
+        D: sha2::Digest + rsa::pkcs8::AssociatedOid,
+    {
         use rsa::pkcs1v15::{Signature, VerifyingKey};
         use rsa::signature::Verifier;
-        use sha1::Sha1;
 
-        let verifying_key = VerifyingKey::<Sha1>::new(self.inner.clone());
+        let verifying_key = VerifyingKey::<D>::new(self.inner.clone());
         let sig = Signature::try_from(signature).map_err(|_| CryptoError::InvalidSignature)?;
 
         verifying_key
@@ -99,6 +167,12 @@ impl AppleRsaPublicKey {
     }
 }
 
+impl From<rsa::RsaPublicKey> for RsaVerifyingKey {
+    fn from(inner: rsa::RsaPublicKey) -> Self {
+        Self { inner }
+    }
+}
+
 /// RSA private key for RAOP server emulation (testing)
 ///
 /// This represents the private key held by AirPlay receivers.
@@ -157,6 +231,18 @@ impl RaopRsaPrivateKey {
         Ok(signature.to_vec())
     }
 
+    /// Sign a message with raw PKCS#1 v1.5 padding
+    ///
+    /// Used by receivers to sign the Apple-Response: the message is padded
+    /// and signed as-is, without hashing.
+    pub fn sign_raw_pkcs1(&self, message: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        use rsa::Pkcs1v15Sign;
+
+        self.inner
+            .sign(Pkcs1v15Sign::new_unprefixed(), message)
+            .map_err(|e| CryptoError::EncryptionFailed(e.to_string()))
+    }
+
     /// Get the corresponding public key
     pub fn public_key(&self) -> rsa::RsaPublicKey {
         self.inner.to_public_key()
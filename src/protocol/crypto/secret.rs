@@ -0,0 +1,75 @@
+//! Secret key material that is wiped on drop
+
+use std::fmt;
+
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
+
+/// Fixed-size secret bytes (keys, shared secrets)
+///
+/// The bytes are zeroed when the value is dropped, comparisons run in
+/// constant time, and `Debug` never prints the contents. Use
+/// [`expose`](Self::expose) where the raw bytes are needed.
+#[derive(Clone)]
+pub struct SecretBytes<const N: usize = 32>([u8; N]);
+
+impl<const N: usize> SecretBytes<N> {
+    /// Wrap secret bytes
+    #[must_use]
+    pub const fn new(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+
+    /// Copy secret bytes from a slice of exactly `N` bytes
+    #[must_use]
+    pub fn from_slice(bytes: &[u8]) -> Option<Self> {
+        bytes.try_into().ok().map(Self)
+    }
+
+    /// Access the raw bytes
+    #[must_use]
+    pub fn expose(&self) -> &[u8; N] {
+        &self.0
+    }
+
+    /// Whether every byte is zero
+    #[must_use]
+    pub fn is_zero(&self) -> bool {
+        bool::from(self.0.ct_eq(&[0u8; N]))
+    }
+}
+
+impl<const N: usize> From<[u8; N]> for SecretBytes<N> {
+    fn from(bytes: [u8; N]) -> Self {
+        Self(bytes)
+    }
+}
+
+impl<const N: usize> PartialEq for SecretBytes<N> {
+    fn eq(&self, other: &Self) -> bool {
+        bool::from(self.0.ct_eq(&other.0))
+    }
+}
+
+impl<const N: usize> Eq for SecretBytes<N> {}
+
+impl<const N: usize> fmt::Debug for SecretBytes<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes<{N}>(..)")
+    }
+}
+
+impl<const N: usize> Drop for SecretBytes<N> {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+/// Compare two byte strings without leaking where they differ
+///
+/// Use for proofs, MACs and digests received from a peer. Slices of
+/// different lengths compare unequal.
+#[must_use]
+pub fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    bool::from(a.ct_eq(b))
+}
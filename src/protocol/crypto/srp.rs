@@ -5,6 +5,7 @@ use sha2::{Digest, Sha512};
 use zeroize::Zeroize;
 
 use super::CryptoError;
+use super::secret::constant_time_eq;
 
 /// SRP-6a Parameters
 #[derive(Clone, Debug)]
@@ -17,7 +18,46 @@ pub struct SrpParams {
     pub size: usize,
 }
 
+/// Shortest salt accepted from a server, in bytes
+pub const MIN_SALT_LEN: usize = 16;
+
+/// SRP group used for Pair-Setup
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SrpGroup {
+    /// RFC 5054 2048-bit group
+    Rfc5054_2048,
+    /// RFC 5054 3072-bit group, used by `HomeKit` accessories
+    #[default]
+    Rfc5054_3072,
+}
+
+impl SrpGroup {
+    /// Parameters of the group
+    pub fn params(self) -> LazyParams {
+        match self {
+            Self::Rfc5054_2048 => SrpParams::RFC5054_2048,
+            Self::Rfc5054_3072 => SrpParams::RFC5054_3072,
+        }
+    }
+}
+
 impl SrpParams {
+    /// RFC 5054 2048-bit group parameters
+    pub const RFC5054_2048: LazyParams = LazyParams {
+        n_hex: "AC6BDB41324A9A9BF166DE5E1389582FAF72B6651987EE07FC319294\
+                3DB56050A37329CBB4A099ED8193E0757767A13DD52312AB4B03310D\
+                CD7F48A9DA04FD50E8083969EDB767B0CF6095179A163AB3661A05FB\
+                D5FAAAE82918A9962F0B93B855F97993EC975EEAA80D740ADBF4FF74\
+                7359D041D5C33EA71D281E446B14773BCA97B43A23FB801676BD207A\
+                436C6481F1D2B9078717461A5B9D32E688F87748544523B524B0D57D\
+                5EA77A2775D2ECFA032CFBDBF52FB3786160279004E57AE6AF874E73\
+                03CE53299CCC041C7BC308D82A5698F3A8D0C38271AE35F8E9DBFBB6\
+                94B5C803D89F7AE435DE236D525F54759B65E372FCD68EF20FA7111F\
+                9E4AFF73",
+        g: 2,
+        size: 256,
+    };
+
     /// RFC 5054 3072-bit group parameters
     pub const RFC5054_3072: LazyParams = LazyParams {
         n_hex: "FFFFFFFFFFFFFFFFC90FDAA22168C234C4C6628B80DC1CD129024E08\
@@ -39,7 +79,7 @@ impl SrpParams {
     };
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub struct LazyParams {
     pub n_hex: &'static str,
     pub g: u32,
@@ -66,10 +106,19 @@ pub struct SrpClient {
 
 impl Drop for SrpClient {
     fn drop(&mut self) {
-        // BigUint doesn't implement Zeroize easily.
+        wipe(&mut self.a);
     }
 }
 
+/// Best-effort wipe of a secret big integer
+///
+/// `BigUint` does not implement `Zeroize`; overwriting its digits in place
+/// clears the existing allocation before it is freed.
+fn wipe(n: &mut BigUint) {
+    let digits = n.iter_u32_digits().len();
+    n.assign_from_slice(&vec![0u32; digits]);
+}
+
 impl SrpClient {
     pub fn new(params_lazy: &LazyParams) -> Result<Self, CryptoError> {
         let params: SrpParams = (*params_lazy).into();
@@ -77,12 +126,20 @@ impl SrpClient {
     }
 
     pub fn with_params(params: SrpParams) -> Result<Self, CryptoError> {
+        let mut rng = rand::thread_rng();
+        let a: BigUint = rng.sample(RandomBits::new(256));
+        Self::with_private_key(params, &a.to_bytes_be())
+    }
+
+    /// Create a client with a fixed private key, for test vectors
+    pub(crate) fn with_private_key(params: SrpParams, private: &[u8]) -> Result<Self, CryptoError> {
         // k = H(N, pad(g))
         let k = compute_k(&params);
 
-        let mut rng = rand::thread_rng();
-        let a: BigUint = rng.sample(RandomBits::new(256));
-        let a = a % &params.n;
+        let a = BigUint::from_bytes_be(private) % &params.n;
+        if a == BigUint::from(0u32) {
+            return Err(CryptoError::SrpError("Invalid private key".to_string()));
+        }
 
         // A = g^a % n
         let a_pub = params.g.modpow(&a, &params.n);
@@ -107,6 +164,18 @@ impl SrpClient {
         salt: &[u8],
         server_public: &[u8],
     ) -> Result<SrpVerifier, CryptoError> {
+        if salt.len() < MIN_SALT_LEN {
+            return Err(CryptoError::SrpError(format!(
+                "Salt too short: {} bytes",
+                salt.len()
+            )));
+        }
+        if server_public.len() > self.params.size {
+            return Err(CryptoError::SrpError(format!(
+                "Server public key too long: {} bytes",
+                server_public.len()
+            )));
+        }
         let b_pub = BigUint::from_bytes_be(server_public);
         if &b_pub % &self.params.n == BigUint::from(0u32) {
             return Err(CryptoError::SrpError(
@@ -118,6 +187,11 @@ impl SrpClient {
 
         // u = H(pad(A), pad(B))
         let u = compute_u(&self.public_key, server_public, self.params.size);
+        if u == BigUint::from(0u32) {
+            return Err(CryptoError::SrpError(
+                "Invalid scrambling parameter".to_string(),
+            ));
+        }
 
         // x = H(salt, H(username, ":", password))
         let x = compute_x(salt, username, password);
@@ -171,7 +245,7 @@ impl SrpVerifier {
         hasher.update(&self.k_session);
         let expected_m2 = hasher.finalize();
 
-        if expected_m2.as_slice() != server_proof {
+        if !constant_time_eq(expected_m2.as_slice(), server_proof) {
             return Err(CryptoError::SrpError(
                 "Server proof verification failed".to_string(),
             ));
@@ -183,6 +257,12 @@ impl SrpVerifier {
     }
 }
 
+impl Drop for SrpVerifier {
+    fn drop(&mut self) {
+        self.k_session.zeroize();
+    }
+}
+
 pub struct SessionKey {
     key: Vec<u8>,
 }
@@ -208,6 +288,12 @@ pub struct SrpServer {
     public_key: Vec<u8>,
 }
 
+impl Drop for SrpServer {
+    fn drop(&mut self) {
+        wipe(&mut self.b);
+    }
+}
+
 impl SrpServer {
     pub fn new(verifier: &[u8], params_lazy: &LazyParams) -> Self {
         let params: SrpParams = (*params_lazy).into();
@@ -284,7 +370,7 @@ impl SrpServer {
         // M1 = H(H(N) ^ H(g), H(username), salt, A, B, K)
         let expected_m1 = compute_m1(&self.params, username, salt, &a_pub, &b_pub, &k_session);
 
-        if expected_m1 != client_proof {
+        if !constant_time_eq(&expected_m1, client_proof) {
             return Err(CryptoError::SrpError(
                 "Client proof verification failed".to_string(),
             ));
@@ -4,6 +4,7 @@ mod ed25519;
 mod hkdf;
 #[cfg(feature = "raop")]
 mod rsa;
+mod secret;
 mod srp;
 mod srp_integration;
 mod x25519;
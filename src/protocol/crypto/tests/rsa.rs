@@ -76,3 +76,11 @@ fn test_apple_public_key_load() {
     let key = AppleRsaPublicKey::load();
     assert!(key.is_ok());
 }
+
+#[test]
+fn test_apple_public_key_size() {
+    // AirPort hardware expects a 2048-bit rsaaeskey
+    let key = AppleRsaPublicKey::load().unwrap();
+    let encrypted = key.encrypt_oaep(&[0x42; 16]).unwrap();
+    assert_eq!(encrypted.len(), rsa_sizes::APPLE_MODULUS_BYTES);
+}
@@ -0,0 +1,48 @@
+use crate::protocol::crypto::{SecretBytes, constant_time_eq};
+
+#[test]
+fn test_secret_bytes_debug_is_redacted() {
+    let secret = SecretBytes::new([0xAB; 32]);
+    let debug = format!("{secret:?}");
+    assert_eq!(debug, "SecretBytes<32>(..)");
+    assert!(!debug.contains("171"));
+    assert!(!debug.to_lowercase().contains("ab"));
+}
+
+#[test]
+fn test_secret_bytes_equality() {
+    let a = SecretBytes::new([1u8; 32]);
+    let b = SecretBytes::from([1u8; 32]);
+    let mut other = [1u8; 32];
+    other[31] = 2;
+    let c = SecretBytes::new(other);
+
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+}
+
+#[test]
+fn test_secret_bytes_from_slice() {
+    let secret = SecretBytes::<16>::from_slice(&[7u8; 16]).unwrap();
+    assert_eq!(secret.expose(), &[7u8; 16]);
+
+    assert!(SecretBytes::<16>::from_slice(&[7u8; 15]).is_none());
+    assert!(SecretBytes::<16>::from_slice(&[7u8; 17]).is_none());
+}
+
+#[test]
+fn test_secret_bytes_is_zero() {
+    assert!(SecretBytes::new([0u8; 32]).is_zero());
+
+    let mut bytes = [0u8; 32];
+    bytes[16] = 1;
+    assert!(!SecretBytes::new(bytes).is_zero());
+}
+
+#[test]
+fn test_constant_time_eq() {
+    assert!(constant_time_eq(b"proof", b"proof"));
+    assert!(!constant_time_eq(b"proof", b"proog"));
+    assert!(!constant_time_eq(b"proof", b"proo"));
+    assert!(constant_time_eq(b"", b""));
+}
@@ -17,7 +17,7 @@ fn test_srp_handshake() {
     let client_a = client.public_key();
 
     // 2. Server setup (simulation)
-    let salt = b"randomsalt";
+    let salt = b"randomsalt123456";
 
     // Use Server to compute verifier (simulating registration)
     let verifier = SrpServer::compute_verifier(username, password, salt, &SrpParams::RFC5054_3072);
@@ -54,7 +54,7 @@ fn test_srp_invalid_password_fails() {
     let username = b"Pair-Setup";
     let password = b"correct";
     let client = SrpClient::new(&SrpParams::RFC5054_3072).unwrap();
-    let salt = b"salt";
+    let salt = b"salt1234salt1234";
 
     // Helper for registration
     // Server registered with "wrong" password
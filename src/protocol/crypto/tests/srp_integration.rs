@@ -28,7 +28,7 @@ fn test_srp_m1_calculation_format() {
     let client = SrpClient::new(&SrpParams::RFC5054_3072).unwrap();
 
     // Create a mock salt and server public key
-    let salt = vec![0x12, 0x34, 0x56, 0x78];
+    let salt = vec![0x12, 0x34, 0x56, 0x78].repeat(4);
     let server_public = vec![0x01; 384]; // Padded to 384 bytes
 
     // Process challenge - this should not panic and should produce valid M1
@@ -120,3 +120,131 @@ fn test_chacha_roundtrip() {
             .is_err()
     );
 }
+
+struct SrpVector {
+    group: SrpGroup,
+    /// SHA-512 of the padded client public key A
+    a_pub_sha512: &'static str,
+    m1: &'static str,
+    m2: &'static str,
+    k: &'static str,
+}
+
+/// Fixed inputs: a = 0x11 * 32, salt = 00..0F, B = 0x5A * 32,
+/// "Pair-Setup" / "3939"
+const SRP_VECTORS: [SrpVector; 2] = [
+    SrpVector {
+        group: SrpGroup::Rfc5054_2048,
+        a_pub_sha512: "0ffd49d0a469b28543a20f7dcfebd132cc0986c5953615a81e988cf1e6b716c6\
+                       993feac367511ea9b04c2700493c35324d8cb93bf6d8291df805de7422b9c2f0",
+        m1: "91b4d9774643f7120545b270785e97694aaa8bb9cffa82c68f5c68ad23ba6f79\
+             bbf81916b43b89564a70675b5ff66f20b657bb92f33b3b58a3372f84443d90e6",
+        m2: "da1175f3df45e2625e00d07b3e04408509e814d9e536aba0524adf07f536ecaf\
+             52d7d8a624938b2bb50ecba0c8ed4815d215a1204a9716c0f832ec2908218350",
+        k: "7967e2e86f69158fb67a56ab2c8734edd097ed7bf680e94a3c55d85e611bc3ab\
+            00b2fe1adcc4413e232a2628db0d996c401ab78a1119f2d8a3137d2e07af1287",
+    },
+    SrpVector {
+        group: SrpGroup::Rfc5054_3072,
+        a_pub_sha512: "240bed1fdef46f6a9a57818b0724a41175feb8d6595b91b9c4a17fe35be0442d\
+                       413a791b3172a73683b97c3eb9b703debc896228d4d2fecb56c95c268b17f264",
+        m1: "7d4ebe9778af632274d32c4f6c3c1132ce7a3f4923b09f9fc6a21f24654a9032\
+             a55d2cdfaaa93edc453e86a0b023b88f38d65fe321f551dc86c7d12928d8fffd",
+        m2: "e30d16a6eff8dd66ee55659c6937288cdb4da0e37d7e51da51bf5ea91d30b230\
+             2c9ef3477b8324dfdba757ded43c2b54fdad1c539e995c2bbe08eb3e6da193c0",
+        k: "4ab63e9128f34deabb921e36232ae9d8d1d4ff09eaa1326d4f7a7519ffa2c270\
+            18fa83411d1ce615a296f68927b6ed8dc042ac8ec3dda32012a12c145118c09d",
+    },
+];
+
+fn srp_vector_client(group: SrpGroup) -> SrpClient {
+    SrpClient::with_private_key(group.params().into(), &[0x11; 32]).unwrap()
+}
+
+#[test]
+fn test_srp_vectors() {
+    use sha2::{Digest, Sha512};
+
+    let salt: Vec<u8> = (0..16).collect();
+    for vector in &SRP_VECTORS {
+        let client = srp_vector_client(vector.group);
+        assert_eq!(client.public_key().len(), vector.group.params().size);
+        assert_eq!(
+            hex::encode(Sha512::digest(client.public_key())),
+            vector.a_pub_sha512
+        );
+
+        let verifier = client
+            .process_challenge(b"Pair-Setup", b"3939", &salt, &[0x5A; 32])
+            .unwrap();
+        assert_eq!(hex::encode(verifier.client_proof()), vector.m1);
+
+        let key = verifier
+            .verify_server(&hex::decode(vector.m2).unwrap())
+            .unwrap();
+        assert_eq!(hex::encode(key.as_bytes()), vector.k);
+    }
+}
+
+#[test]
+fn test_srp_rejects_invalid_server_public_key() {
+    let salt = [7u8; 16];
+    for group in [SrpGroup::Rfc5054_2048, SrpGroup::Rfc5054_3072] {
+        let params: SrpParams = group.params().into();
+        let client = srp_vector_client(group);
+
+        // B = 0 and B = N are both 0 mod N
+        let zero = vec![0u8; params.size];
+        let n = params.n.to_bytes_be();
+        for b_pub in [&zero[..], &n[..]] {
+            assert!(matches!(
+                client.process_challenge(b"Pair-Setup", b"3939", &salt, b_pub),
+                Err(CryptoError::SrpError(_))
+            ));
+        }
+
+        // Longer than the group's modulus
+        let too_long = vec![1u8; params.size + 1];
+        assert!(
+            client
+                .process_challenge(b"Pair-Setup", b"3939", &salt, &too_long)
+                .is_err()
+        );
+    }
+}
+
+#[test]
+fn test_srp_rejects_short_salt() {
+    let client = srp_vector_client(SrpGroup::Rfc5054_3072);
+    let result = client.process_challenge(b"Pair-Setup", b"3939", &[1u8; 8], &[0x5A; 32]);
+    assert!(matches!(result, Err(CryptoError::SrpError(_))));
+
+    assert!(
+        client
+            .process_challenge(b"Pair-Setup", b"3939", &[1u8; MIN_SALT_LEN], &[0x5A; 32])
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_srp_2048_handshake() {
+    let params = SrpGroup::Rfc5054_2048.params();
+    let salt = [9u8; 16];
+    let client = SrpClient::new(&params).unwrap();
+    let verifier = SrpServer::compute_verifier(b"Pair-Setup", b"1234", &salt, &params);
+    let server = SrpServer::new(&verifier, &params);
+
+    let client_verifier = client
+        .process_challenge(b"Pair-Setup", b"1234", &salt, server.public_key())
+        .unwrap();
+    let (server_key, m2) = server
+        .verify_client(
+            b"Pair-Setup",
+            &salt,
+            client.public_key(),
+            client_verifier.client_proof(),
+        )
+        .unwrap();
+    let client_key = client_verifier.verify_server(&m2).unwrap();
+    assert_eq!(client_key.as_bytes(), server_key.as_bytes());
+}
@@ -1,4 +1,20 @@
 //! Album artwork for RAOP
+//!
+//! Receivers keep artwork in memory and some reject large uploads, so
+//! [`Artwork::fit`] scales images down to [`Artwork::MAX_DIMENSION`] and
+//! re-encodes ones over [`Artwork::MAX_BYTES`] as JPEG (requires the
+//! `artwork` feature; without it images are sent as given).
+
+/// Artwork that cannot be prepared for upload
+#[derive(Debug, thiserror::Error)]
+pub enum ArtworkError {
+    /// The image could not be decoded
+    #[error("cannot decode artwork: {0}")]
+    Decode(String),
+    /// The scaled image could not be encoded
+    #[error("cannot encode artwork: {0}")]
+    Encode(String),
+}
 
 /// Artwork image format
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -50,6 +66,16 @@ pub struct Artwork {
 }
 
 impl Artwork {
+    /// Longest side sent to receivers, in pixels
+    pub const MAX_DIMENSION: u32 = 1024;
+
+    /// Largest image sent to receivers, in bytes
+    pub const MAX_BYTES: usize = 1024 * 1024;
+
+    /// JPEG qualities tried, best first, when re-encoding artwork
+    #[cfg(feature = "artwork")]
+    const JPEG_QUALITIES: [u8; 3] = [85, 70, 50];
+
     /// Create artwork from JPEG data
     #[must_use]
     pub fn jpeg(data: Vec<u8>) -> Self {
@@ -90,11 +116,99 @@ impl Artwork {
         }
     }
 
+    /// Whether the image is larger than receivers accept
+    ///
+    /// Images whose dimensions cannot be read only count by size.
+    #[must_use]
+    pub fn is_oversized(&self) -> bool {
+        self.data.len() > Self::MAX_BYTES
+            || self
+                .dimensions()
+                .is_some_and(|(width, height)| width.max(height) > Self::MAX_DIMENSION)
+    }
+
+    /// Scale and re-encode the image to fit what receivers accept
+    ///
+    /// Images within the limits are returned unchanged. Larger ones are
+    /// scaled to fit [`MAX_DIMENSION`](Self::MAX_DIMENSION), keeping their
+    /// aspect ratio. Images still over [`MAX_BYTES`](Self::MAX_BYTES) are
+    /// encoded as JPEG at decreasing quality until they fit.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if an oversized image cannot be decoded or encoded
+    #[cfg(feature = "artwork")]
+    pub fn fit(self) -> Result<Self, ArtworkError> {
+        use image::ImageFormat;
+        use image::imageops::FilterType;
+
+        if !self.is_oversized() {
+            return Ok(self);
+        }
+
+        let format = match self.format {
+            ArtworkFormat::Jpeg => ImageFormat::Jpeg,
+            ArtworkFormat::Png => ImageFormat::Png,
+        };
+        let mut image = image::load_from_memory_with_format(&self.data, format)
+            .map_err(|e| ArtworkError::Decode(e.to_string()))?;
+        if image.width().max(image.height()) > Self::MAX_DIMENSION {
+            image = image.resize(
+                Self::MAX_DIMENSION,
+                Self::MAX_DIMENSION,
+                FilterType::Triangle,
+            );
+        }
+
+        if self.format == ArtworkFormat::Png {
+            let mut png = Vec::new();
+            image
+                .write_to(&mut std::io::Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(|e| ArtworkError::Encode(e.to_string()))?;
+            if png.len() <= Self::MAX_BYTES {
+                return Ok(Self::png(png));
+            }
+        }
+
+        // JPEG has no alpha channel
+        let rgb = image.to_rgb8();
+        let mut jpeg = Vec::new();
+        for quality in Self::JPEG_QUALITIES {
+            jpeg.clear();
+            image::codecs::jpeg::JpegEncoder::new_with_quality(&mut jpeg, quality)
+                .encode_image(&rgb)
+                .map_err(|e| ArtworkError::Encode(e.to_string()))?;
+            if jpeg.len() <= Self::MAX_BYTES {
+                break;
+            }
+        }
+        Ok(Self::jpeg(jpeg))
+    }
+
+    /// Scale and re-encode the image to fit what receivers accept
+    ///
+    /// Without the `artwork` feature images cannot be decoded, so they are
+    /// returned unchanged and oversized ones are only logged.
+    ///
+    /// # Errors
+    ///
+    /// Never fails without the `artwork` feature
+    #[cfg(not(feature = "artwork"))]
+    pub fn fit(self) -> Result<Self, ArtworkError> {
+        if self.is_oversized() {
+            tracing::warn!(
+                "Sending {} bytes of artwork unscaled; enable the `artwork` feature to fit it",
+                self.data.len()
+            );
+        }
+        Ok(self)
+    }
+
     fn jpeg_dimensions(&self) -> Option<(u32, u32)> {
         // Simple JPEG dimension parser
         let mut pos = 2;
 
-        while pos < self.data.len() - 4 {
+        while pos + 4 < self.data.len() {
             if self.data[pos] != 0xFF {
                 pos += 1;
                 continue;
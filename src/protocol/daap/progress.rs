@@ -37,6 +37,25 @@ impl DmapProgress {
         }
     }
 
+    /// Create progress for a position on a stream's timeline
+    ///
+    /// The track starts at position zero. Without a known duration the end is
+    /// the current position.
+    #[cfg(feature = "sender")]
+    #[must_use]
+    pub fn from_timeline(
+        timeline: &crate::streaming::Timeline,
+        position: std::time::Duration,
+        duration: Option<std::time::Duration>,
+    ) -> Self {
+        let current = timeline.rtp_time_at(position);
+        Self {
+            start: timeline.rtp_time_at(std::time::Duration::ZERO),
+            current,
+            end: duration.map_or(current, |d| timeline.rtp_time_at(d)),
+        }
+    }
+
     /// Encode as text/parameters body
     #[must_use]
     pub fn encode(&self) -> String {
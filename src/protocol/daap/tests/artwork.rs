@@ -60,3 +60,86 @@ fn test_png_dimensions() {
     let artwork = Artwork::png(data);
     assert_eq!(artwork.dimensions(), Some((800, 600)));
 }
+
+#[test]
+fn test_oversized_artwork() {
+    let png = |width: u32, height: u32| {
+        let mut data = vec![0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A];
+        data.extend_from_slice(&[0x00, 0x00, 0x00, 0x0D]);
+        data.extend_from_slice(b"IHDR");
+        data.extend_from_slice(&width.to_be_bytes());
+        data.extend_from_slice(&height.to_be_bytes());
+        Artwork::png(data)
+    };
+    assert!(!png(600, 600).is_oversized());
+    assert!(!png(1024, 300).is_oversized());
+    assert!(png(3000, 3000).is_oversized());
+
+    // Unreadable dimensions only count by size
+    assert!(!Artwork::jpeg(vec![0xFF, 0xD8]).is_oversized());
+    assert!(Artwork::jpeg(vec![0; Artwork::MAX_BYTES + 1]).is_oversized());
+}
+
+#[test]
+fn test_fit_keeps_small_artwork() {
+    let data = vec![0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+    let artwork = Artwork::jpeg(data.clone()).fit().unwrap();
+    assert_eq!(artwork.data, data);
+    assert_eq!(artwork.format, ArtworkFormat::Jpeg);
+}
+
+#[cfg(feature = "artwork")]
+fn encode(image: &image::DynamicImage, format: image::ImageFormat) -> Vec<u8> {
+    let mut data = Vec::new();
+    image
+        .write_to(&mut std::io::Cursor::new(&mut data), format)
+        .unwrap();
+    data
+}
+
+#[cfg(feature = "artwork")]
+#[test]
+fn test_fit_scales_large_artwork() {
+    let image = image::DynamicImage::ImageRgb8(image::RgbImage::from_fn(2000, 1000, |x, y| {
+        image::Rgb([x.to_le_bytes()[0], y.to_le_bytes()[0], 128])
+    }));
+
+    let artwork = Artwork::jpeg(encode(&image, image::ImageFormat::Jpeg))
+        .fit()
+        .unwrap();
+    assert_eq!(artwork.format, ArtworkFormat::Jpeg);
+    assert_eq!(artwork.dimensions(), Some((1024, 512)));
+
+    let artwork = Artwork::png(encode(&image, image::ImageFormat::Png))
+        .fit()
+        .unwrap();
+    assert_eq!(artwork.format, ArtworkFormat::Png);
+    assert_eq!(artwork.dimensions(), Some((1024, 512)));
+    assert!(!artwork.is_oversized());
+}
+
+#[cfg(feature = "artwork")]
+#[test]
+fn test_fit_transcodes_large_png_to_jpeg() {
+    // Noise does not compress, so even scaled down the PNG is too large
+    let mut state = 0x1234_5678_u32;
+    let image = image::DynamicImage::ImageRgba8(image::RgbaImage::from_fn(1200, 1200, |_, _| {
+        state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+        image::Rgba(state.to_le_bytes())
+    }));
+
+    let artwork = Artwork::png(encode(&image, image::ImageFormat::Png))
+        .fit()
+        .unwrap();
+    assert_eq!(artwork.format, ArtworkFormat::Jpeg);
+    assert_eq!(artwork.dimensions(), Some((1024, 1024)));
+    assert!(artwork.data.len() <= Artwork::MAX_BYTES);
+}
+
+#[cfg(feature = "artwork")]
+#[test]
+fn test_fit_rejects_corrupt_oversized_artwork() {
+    let mut data = vec![0xFF, 0xD8];
+    data.resize(Artwork::MAX_BYTES + 1, 0);
+    assert!(Artwork::jpeg(data).fit().is_err());
+}
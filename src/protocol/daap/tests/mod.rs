@@ -1 +1,2 @@
+mod artwork;
 mod parser_tests;
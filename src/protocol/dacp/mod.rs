@@ -6,7 +6,7 @@ mod service;
 
 pub use commands::{CommandResult, DacpCommand};
 pub use server::{CallbackHandler, DacpHandler, DacpServer};
-pub use service::{DacpService, DacpServiceConfig};
+pub use service::{DacpError, DacpService, DacpServiceConfig};
 
 /// DACP service type for mDNS
 pub const DACP_SERVICE_TYPE: &str = "_dacp._tcp.local.";
@@ -1,9 +1,17 @@
 //! DACP HTTP server for receiving commands
 
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
 use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinSet;
 
 use super::commands::{CommandResult, DacpCommand};
 use super::service::DacpError;
+use crate::net::TaskSet;
 
 /// Handler trait for DACP commands
 pub trait DacpHandler: Send + Sync {
@@ -14,18 +22,27 @@ pub trait DacpHandler: Send + Sync {
     fn verify_token(&self, token: &str) -> bool;
 }
 
+/// Longest request or header line accepted
+const MAX_LINE: usize = 8192;
+
 /// DACP HTTP server
+///
+/// Receivers send the commands from their buttons, remotes and Siri as
+/// HTTP `GET /ctrl-int/1/<command>` requests carrying the `Active-Remote`
+/// token the sender gave them. Each is passed to the [`DacpHandler`] while
+/// the server runs; stopping or dropping the server closes its connections.
 pub struct DacpServer<H: DacpHandler> {
     /// Handler for commands
     handler: Arc<H>,
     /// Expected Active-Remote token
     #[allow(dead_code, reason = "Reserved for future use")]
     expected_token: String,
-    /// Server port
-    #[allow(dead_code, reason = "Reserved for future use")]
+    /// Port to listen on, 0 for any free port
     port: u16,
-    /// Running state
-    running: bool,
+    /// Address listened on while running
+    local_addr: Option<SocketAddr>,
+    /// Accept loop and the connections it serves
+    tasks: TaskSet,
 }
 
 impl<H: DacpHandler + 'static> DacpServer<H> {
@@ -36,51 +53,48 @@ impl<H: DacpHandler + 'static> DacpServer<H> {
             handler: Arc::new(handler),
             expected_token,
             port,
-            running: false,
+            local_addr: None,
+            tasks: TaskSet::new("DACP server"),
         }
     }
 
     /// Start the HTTP server
     ///
+    /// Returns once listening; requests are served in the background.
+    ///
     /// # Errors
     ///
     /// Returns error if server fails to start
-    #[allow(clippy::unused_async, reason = "Async required by trait or future use")]
     pub async fn start(&mut self) -> Result<(), DacpError> {
-        // HTTP server implementation using hyper or similar
-        // Listen for GET requests on /ctrl-int/1/*
-
-        self.running = true;
-
-        // Pseudo-code for request handling:
-        // loop {
-        //     let request = accept_connection().await;
-        //     let token = request.headers.get("Active-Remote");
-        //
-        //     if !self.handler.verify_token(token) {
-        //         respond_403();
-        //         continue;
-        //     }
-        //
-        //     let command = DacpCommand::from_path(request.path());
-        //     let result = self.handler.handle_command(command);
-        //
-        //     respond_204();
-        // }
+        if self.is_running() {
+            return Ok(());
+        }
+        let listener = TcpListener::bind((Ipv4Addr::UNSPECIFIED, self.port)).await?;
+        let local_addr = listener.local_addr()?;
+        tracing::debug!("DACP server listening on {local_addr}");
 
+        self.tasks
+            .spawn(Self::accept_loop(listener, self.handler.clone()));
+        self.local_addr = Some(local_addr);
         Ok(())
     }
 
     /// Stop the server
-    #[allow(clippy::unused_async, reason = "Async required by trait or future use")]
     pub async fn stop(&mut self) {
-        self.running = false;
+        self.tasks.shutdown().await;
+        self.local_addr = None;
     }
 
     /// Check if server is running
     #[must_use]
     pub fn is_running(&self) -> bool {
-        self.running
+        self.local_addr.is_some()
+    }
+
+    /// Address the server listens on, if running
+    #[must_use]
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.local_addr
     }
 
     /// Process an HTTP request (for testing)
@@ -91,31 +105,182 @@ impl<H: DacpHandler + 'static> DacpServer<H> {
         path: &str,
         active_remote: Option<&str>,
     ) -> HttpResponse {
-        // Only accept GET requests
-        if method != "GET" {
-            return HttpResponse::method_not_allowed();
+        respond(&*self.handler, method, path, active_remote)
+    }
+
+    async fn accept_loop(listener: TcpListener, handler: Arc<H>) {
+        let mut connections = JoinSet::new();
+        loop {
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((stream, peer)) => {
+                        tracing::debug!("DACP connection from {peer}");
+                        connections.spawn(Self::serve_connection(stream, handler.clone()));
+                    }
+                    Err(e) => {
+                        tracing::warn!("DACP accept failed: {e}");
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                    }
+                },
+                Some(_) = connections.join_next() => {}
+            }
         }
+    }
 
-        // Verify Active-Remote token
-        match active_remote {
-            Some(token) if self.handler.verify_token(token) => {}
-            _ => return HttpResponse::forbidden(),
+    /// Answer requests on one connection until the client closes it
+    async fn serve_connection(stream: TcpStream, handler: Arc<H>) {
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+
+        loop {
+            let request = match read_request(&mut reader).await {
+                Ok(Some(request)) => request,
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::debug!("DACP request unreadable: {e}");
+                    break;
+                }
+            };
+            let response = respond(
+                &*handler,
+                &request.method,
+                &request.path,
+                request.active_remote.as_deref(),
+            );
+            tracing::debug!(
+                "DACP {} {} -> {}",
+                request.method,
+                request.path,
+                response.status
+            );
+
+            if let Err(e) = writer.write_all(response.encode().as_bytes()).await {
+                tracing::debug!("DACP response not sent: {e}");
+                break;
+            }
+            if request.close {
+                break;
+            }
         }
+    }
+}
 
-        // Parse command
-        let Some(command) = DacpCommand::from_path(path) else {
-            return HttpResponse::not_found();
-        };
+/// Check the token and run the command a request names
+fn respond<H: DacpHandler + ?Sized>(
+    handler: &H,
+    method: &str,
+    path: &str,
+    active_remote: Option<&str>,
+) -> HttpResponse {
+    // Only accept GET requests
+    if method != "GET" {
+        return HttpResponse::method_not_allowed();
+    }
 
-        // Execute command
-        let result = self.handler.handle_command(command);
+    // Verify Active-Remote token
+    match active_remote {
+        Some(token) if handler.verify_token(token) => {}
+        _ => return HttpResponse::forbidden(),
+    }
 
-        match result {
-            CommandResult::Success => HttpResponse::no_content(),
-            CommandResult::NotSupported => HttpResponse::not_implemented(),
-            CommandResult::Failed(_) => HttpResponse::internal_error(),
+    // Parse command, ignoring any query
+    let path = path.split_once('?').map_or(path, |(path, _)| path);
+    let Some(command) = DacpCommand::from_path(path) else {
+        return HttpResponse::not_found();
+    };
+
+    // Execute command
+    let result = handler.handle_command(command);
+
+    match result {
+        CommandResult::Success => HttpResponse::no_content(),
+        CommandResult::NotSupported => HttpResponse::not_implemented(),
+        CommandResult::Failed(_) => HttpResponse::internal_error(),
+    }
+}
+
+/// The parts of an HTTP request the server uses
+struct Request {
+    method: String,
+    path: String,
+    active_remote: Option<String>,
+    /// Client asked to close the connection after the response
+    close: bool,
+}
+
+/// Read the next request, or `None` if the client closed the connection
+async fn read_request<R>(reader: &mut R) -> io::Result<Option<Request>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    // Tolerate blank lines between requests
+    let request_line = loop {
+        let Some(line) = read_line(reader).await? else {
+            return Ok(None);
+        };
+        if !line.is_empty() {
+            break line;
+        }
+    };
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("bad request line: {request_line:?}"),
+        ));
+    };
+    let mut request = Request {
+        method: method.to_string(),
+        path: path.to_string(),
+        active_remote: None,
+        close: parts.next() == Some("HTTP/1.0"),
+    };
+
+    let mut content_length = 0;
+    loop {
+        let line = read_line(reader)
+            .await?
+            .ok_or_else(|| io::Error::from(io::ErrorKind::UnexpectedEof))?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("Active-Remote") {
+            request.active_remote = Some(value.to_string());
+        } else if name.eq_ignore_ascii_case("Content-Length") {
+            content_length = value
+                .parse()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        } else if name.eq_ignore_ascii_case("Connection") {
+            request.close = value.eq_ignore_ascii_case("close");
         }
     }
+
+    // Commands carry no body, but skip one if sent
+    tokio::io::copy(&mut reader.take(content_length), &mut tokio::io::sink()).await?;
+    Ok(Some(request))
+}
+
+/// Read a line without its line ending, or `None` at end of stream
+async fn read_line<R>(reader: &mut R) -> io::Result<Option<String>>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut line = String::new();
+    let n = reader.take(MAX_LINE as u64).read_line(&mut line).await?;
+    if n == 0 {
+        return Ok(None);
+    }
+    if !line.ends_with('\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "line too long or truncated",
+        ));
+    }
+    Ok(Some(line.trim_end().to_string()))
 }
 
 /// Simple HTTP response representation
@@ -172,6 +337,15 @@ impl HttpResponse {
             reason: "Internal Server Error",
         }
     }
+
+    /// Encode as an HTTP/1.1 response without a body
+    #[must_use]
+    pub fn encode(&self) -> String {
+        format!(
+            "HTTP/1.1 {} {}\r\nContent-Length: 0\r\n\r\n",
+            self.status, self.reason
+        )
+    }
 }
 
 /// Default command handler that forwards to callbacks
@@ -289,6 +463,6 @@ impl DacpHandler for CallbackHandler {
     }
 
     fn verify_token(&self, token: &str) -> bool {
-        token == self.token
+        crate::protocol::crypto::constant_time_eq(token.as_bytes(), self.token.as_bytes())
     }
 }
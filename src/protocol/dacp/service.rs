@@ -2,7 +2,9 @@
 
 use std::collections::HashMap;
 
-use super::{DACP_DEFAULT_PORT, txt_keys};
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+
+use super::{DACP_DEFAULT_PORT, DACP_SERVICE_TYPE, txt_keys};
 
 /// DACP service configuration
 #[derive(Debug, Clone)]
@@ -60,11 +62,15 @@ impl Default for DacpServiceConfig {
 }
 
 /// DACP service for mDNS registration
+///
+/// Devices find where to send commands by looking up the
+/// `iTunes_Ctrl_<DACP-ID>` service named in our requests. The service is
+/// withdrawn when unregistered or dropped.
 pub struct DacpService {
     /// Configuration
     config: DacpServiceConfig,
-    /// Whether service is registered
-    registered: bool,
+    /// mDNS daemon and full service name while registered
+    registration: Option<(ServiceDaemon, String)>,
 }
 
 impl DacpService {
@@ -73,7 +79,7 @@ impl DacpService {
     pub fn new(config: DacpServiceConfig) -> Self {
         Self {
             config,
-            registered: false,
+            registration: None,
         }
     }
 
@@ -97,15 +103,46 @@ impl DacpService {
 
     /// Register service with mDNS
     ///
+    /// Does nothing if already registered.
+    ///
     /// # Errors
     ///
     /// Returns error if registration fails
     #[allow(clippy::unused_async, reason = "Async required by trait or future use")]
     pub async fn register(&mut self) -> Result<(), DacpError> {
-        // Use mdns-sd to register service
-        // Implementation depends on mDNS library
+        if self.registration.is_some() {
+            return Ok(());
+        }
 
-        self.registered = true;
+        let failed = |e: mdns_sd::Error| DacpError::RegistrationFailed(e.to_string());
+        let hostname = hostname::get().map_or_else(
+            |_| "airplay2-rs.local.".to_string(),
+            |s| format!("{}.local.", s.to_string_lossy()),
+        );
+        let service_info = ServiceInfo::new(
+            DACP_SERVICE_TYPE,
+            &self.config.instance_name(),
+            &hostname,
+            "",
+            self.config.port,
+            self.config.txt_records(),
+        )
+        .map_err(failed)?
+        .enable_addr_auto();
+        let fullname = service_info.get_fullname().to_string();
+
+        let daemon = ServiceDaemon::new().map_err(failed)?;
+        if let Err(e) = daemon.register(service_info) {
+            let _ = daemon.shutdown();
+            return Err(failed(e));
+        }
+
+        tracing::info!(
+            name = %fullname,
+            port = %self.config.port,
+            "DACP service registered"
+        );
+        self.registration = Some((daemon, fullname));
         Ok(())
     }
 
@@ -113,29 +150,56 @@ impl DacpService {
     ///
     /// # Errors
     ///
-    /// Returns error if unregistration fails
+    /// Returns error if the service is not registered or unregistration
+    /// fails
     #[allow(clippy::unused_async, reason = "Async required by trait or future use")]
     pub async fn unregister(&mut self) -> Result<(), DacpError> {
-        self.registered = false;
-        Ok(())
+        self.withdraw()
     }
 
     /// Check if service is registered
     #[must_use]
     pub fn is_registered(&self) -> bool {
-        self.registered
+        self.registration.is_some()
+    }
+
+    fn withdraw(&mut self) -> Result<(), DacpError> {
+        let (daemon, fullname) = self.registration.take().ok_or(DacpError::NotRegistered)?;
+        let result = daemon
+            .unregister(&fullname)
+            .map(drop)
+            .map_err(|e| DacpError::RegistrationFailed(e.to_string()));
+        let _ = daemon.shutdown();
+        tracing::info!(name = %fullname, "DACP service unregistered");
+        result
+    }
+}
+
+impl Drop for DacpService {
+    fn drop(&mut self) {
+        // Best-effort unregister on drop
+        if self.registration.is_some() {
+            let _ = self.withdraw();
+        }
     }
 }
 
 /// DACP errors
 #[derive(Debug, thiserror::Error)]
 pub enum DacpError {
+    /// mDNS registration or unregistration failed
     #[error("service registration failed: {0}")]
     RegistrationFailed(String),
+    /// The service is not registered
     #[error("service not registered")]
     NotRegistered,
+    /// The command is not recognized
     #[error("invalid command")]
     InvalidCommand,
+    /// The Active-Remote token did not match
     #[error("authentication failed")]
     AuthenticationFailed,
+    /// The HTTP server could not be started
+    #[error("server failed: {0}")]
+    Server(#[from] std::io::Error),
 }
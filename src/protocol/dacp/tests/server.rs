@@ -2,7 +2,7 @@ use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::protocol::dacp::commands::{CommandResult, DacpCommand};
 use crate::protocol::dacp::server::{DacpHandler, DacpServer};
-use crate::protocol::dacp::service::DacpServiceConfig;
+use crate::protocol::dacp::service::{DacpError, DacpService, DacpServiceConfig};
 
 struct TestHandler {
     token: String,
@@ -75,3 +75,96 @@ fn test_service_config() {
     assert!(!config.active_remote.is_empty());
     assert!(config.instance_name().starts_with("iTunes_Ctrl_"));
 }
+
+#[tokio::test]
+async fn test_service_register_and_unregister() {
+    let mut service = DacpService::new(DacpServiceConfig::new());
+    assert!(!service.is_registered());
+
+    service.register().await.unwrap();
+    assert!(service.is_registered());
+    // Registering again keeps the one registration
+    service.register().await.unwrap();
+
+    service.unregister().await.unwrap();
+    assert!(!service.is_registered());
+    assert!(matches!(
+        service.unregister().await,
+        Err(DacpError::NotRegistered)
+    ));
+}
+
+#[test]
+fn test_process_request_ignores_query() {
+    let handler = TestHandler {
+        token: "12345".to_string(),
+        play_called: AtomicBool::new(false),
+    };
+
+    let server = DacpServer::new(handler, "12345".to_string(), 3689);
+
+    let response = server.process_request("GET", "/ctrl-int/1/play?session-id=1", Some("12345"));
+
+    assert_eq!(response.status, 204);
+}
+
+/// Send a request and read the status line of the reply
+async fn send_command(
+    stream: &mut tokio::io::BufReader<tokio::net::TcpStream>,
+    path: &str,
+    token: &str,
+) -> String {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+    let request = format!("GET {path} HTTP/1.1\r\nHost: test\r\nActive-Remote: {token}\r\n\r\n");
+    stream
+        .get_mut()
+        .write_all(request.as_bytes())
+        .await
+        .unwrap();
+
+    let mut status = String::new();
+    stream.read_line(&mut status).await.unwrap();
+    // Skip the headers
+    let mut line = String::new();
+    while line != "\r\n" {
+        line.clear();
+        stream.read_line(&mut line).await.unwrap();
+    }
+    status.trim_end().to_string()
+}
+
+#[tokio::test]
+async fn test_server_handles_commands_over_http() {
+    let handler = TestHandler {
+        token: "12345".to_string(),
+        play_called: AtomicBool::new(false),
+    };
+    let mut server = DacpServer::new(handler, "12345".to_string(), 0);
+    server.start().await.unwrap();
+    assert!(server.is_running());
+    let port = server.local_addr().unwrap().port();
+
+    let stream = tokio::net::TcpStream::connect(("127.0.0.1", port))
+        .await
+        .unwrap();
+    let mut stream = tokio::io::BufReader::new(stream);
+
+    // Requests share the connection
+    assert_eq!(
+        send_command(&mut stream, "/ctrl-int/1/play", "wrong").await,
+        "HTTP/1.1 403 Forbidden"
+    );
+    assert_eq!(
+        send_command(&mut stream, "/ctrl-int/1/play", "12345").await,
+        "HTTP/1.1 204 No Content"
+    );
+
+    server.stop().await;
+    assert!(!server.is_running());
+    assert!(
+        tokio::net::TcpStream::connect(("127.0.0.1", port))
+            .await
+            .is_err()
+    );
+}
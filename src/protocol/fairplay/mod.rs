@@ -0,0 +1,318 @@
+//! `FairPlay` (`/fp-setup`) handshake
+//!
+//! Receivers advertising `FairPlay` auth (feature bit 12) may refuse SETUP
+//! until the sender completes a two-phase exchange of `FPLY` messages, each
+//! sent as `POST /fp-setup`:
+//!
+//! 1. A 16-byte request naming a mode (0-3), answered with 142 bytes.
+//! 2. A 164-byte request carrying the sender's key message, answered with 32
+//!    bytes that echo the last 20 bytes of the request.
+//!
+//! Computing the phase 2 key message needs Apple's `FairPlay` client, which
+//! this crate does not contain. It is supplied by a [`FairPlayKeySource`];
+//! receivers accept a replayed message captured from a real sender, which
+//! [`RecordedKeyMessage`] provides.
+
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// Endpoint the handshake is posted to
+pub const FP_SETUP_PATH: &str = "/fp-setup";
+
+/// Magic at the start of every `FPLY` message
+pub const FPLY_MAGIC: [u8; 4] = *b"FPLY";
+
+/// Length of the `FPLY` header
+pub const HEADER_LEN: usize = 12;
+
+/// Length of the phase 2 key message (excluding the header)
+pub const KEY_MESSAGE_LEN: usize = 152;
+
+/// Length of the phase 1 reply
+pub const PHASE1_REPLY_LEN: usize = 142;
+
+/// Number of trailing request bytes echoed in the phase 2 reply
+const PHASE2_ECHO_LEN: usize = 20;
+
+/// `FairPlay` handshake errors
+#[derive(Debug, thiserror::Error)]
+pub enum FairPlayError {
+    /// A message did not have the expected structure
+    #[error("malformed FPLY message: {0}")]
+    Malformed(String),
+
+    /// The receiver's reply did not match the request
+    #[error("unexpected fp-setup reply: {0}")]
+    UnexpectedReply(String),
+
+    /// The key source cannot produce a message for the requested mode
+    #[error("no FairPlay key message for mode {0}")]
+    NoKeyMessage(u8),
+
+    /// A handshake step was called out of order
+    #[error("invalid state: {0}")]
+    InvalidState(String),
+}
+
+/// `FPLY` message header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FplyHeader {
+    /// Major version (3)
+    pub major: u8,
+    /// Minor version (1)
+    pub minor: u8,
+    /// Message type: 1 and 3 are requests, 2 and 4 their replies
+    pub message_type: u8,
+    /// Payload length following the header
+    pub length: u32,
+}
+
+impl FplyHeader {
+    /// Header for a message of the given type and payload length
+    #[must_use]
+    pub fn new(message_type: u8, length: u32) -> Self {
+        Self {
+            major: 3,
+            minor: 1,
+            message_type,
+            length,
+        }
+    }
+
+    /// Encode the 12-byte header
+    #[must_use]
+    pub fn encode(&self) -> [u8; HEADER_LEN] {
+        let mut out = [0u8; HEADER_LEN];
+        out[..4].copy_from_slice(&FPLY_MAGIC);
+        out[4] = self.major;
+        out[5] = self.minor;
+        out[6] = self.message_type;
+        out[8..].copy_from_slice(&self.length.to_be_bytes());
+        out
+    }
+
+    /// Parse the header of a message, checking the payload is complete
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the magic is wrong or the message is truncated.
+    pub fn parse(message: &[u8]) -> Result<Self, FairPlayError> {
+        if message.len() < HEADER_LEN || message[..4] != FPLY_MAGIC {
+            return Err(FairPlayError::Malformed(format!(
+                "not an FPLY message ({} bytes)",
+                message.len()
+            )));
+        }
+        let header = Self {
+            major: message[4],
+            minor: message[5],
+            message_type: message[6],
+            length: u32::from_be_bytes([message[8], message[9], message[10], message[11]]),
+        };
+        if message.len() - HEADER_LEN < header.length as usize {
+            return Err(FairPlayError::Malformed(format!(
+                "payload truncated: {} of {} bytes",
+                message.len() - HEADER_LEN,
+                header.length
+            )));
+        }
+        Ok(header)
+    }
+}
+
+/// Supplies the phase 2 key message
+pub trait FairPlayKeySource: fmt::Debug + Send + Sync {
+    /// Mode to request in phase 1 (0-3)
+    fn mode(&self) -> u8;
+
+    /// The 152-byte key message answering a phase 1 reply
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no message can be produced for the reply.
+    fn key_message(&self, mode: u8, phase1_reply: &[u8]) -> Result<Vec<u8>, FairPlayError>;
+}
+
+/// Key source that replays a key message captured from a real sender
+#[derive(Clone, PartialEq, Eq)]
+pub struct RecordedKeyMessage {
+    mode: u8,
+    message: Vec<u8>,
+}
+
+impl RecordedKeyMessage {
+    /// Use a captured phase 2 message for `mode`
+    ///
+    /// `message` may be the full 164-byte request or just its 152-byte
+    /// payload.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the mode is out of range or the message has the wrong
+    /// length or header.
+    pub fn new(mode: u8, message: &[u8]) -> Result<Self, FairPlayError> {
+        if mode > 3 {
+            return Err(FairPlayError::Malformed(format!(
+                "mode {mode} out of range"
+            )));
+        }
+        let payload = match message.len() {
+            KEY_MESSAGE_LEN => message,
+            len if len == HEADER_LEN + KEY_MESSAGE_LEN => {
+                let header = FplyHeader::parse(message)?;
+                if header.message_type != 3 {
+                    return Err(FairPlayError::Malformed(format!(
+                        "expected a phase 2 request, got message type {}",
+                        header.message_type
+                    )));
+                }
+                &message[HEADER_LEN..]
+            }
+            len => {
+                return Err(FairPlayError::Malformed(format!(
+                    "key message must be {KEY_MESSAGE_LEN} bytes, got {len}"
+                )));
+            }
+        };
+        Ok(Self {
+            mode,
+            message: payload.to_vec(),
+        })
+    }
+}
+
+impl fmt::Debug for RecordedKeyMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RecordedKeyMessage")
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FairPlayKeySource for RecordedKeyMessage {
+    fn mode(&self) -> u8 {
+        self.mode
+    }
+
+    fn key_message(&self, mode: u8, _phase1_reply: &[u8]) -> Result<Vec<u8>, FairPlayError> {
+        if mode == self.mode {
+            Ok(self.message.clone())
+        } else {
+            Err(FairPlayError::NoKeyMessage(mode))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum State {
+    Init,
+    Phase1Sent,
+    Phase2Sent([u8; PHASE2_ECHO_LEN]),
+    Complete,
+}
+
+/// `/fp-setup` handshake (sans-IO)
+#[derive(Debug)]
+pub struct FairPlaySetup<'a> {
+    source: &'a dyn FairPlayKeySource,
+    state: State,
+}
+
+impl<'a> FairPlaySetup<'a> {
+    /// Create a handshake using `source` for the key message
+    #[must_use]
+    pub fn new(source: &'a dyn FairPlayKeySource) -> Self {
+        Self {
+            source,
+            state: State::Init,
+        }
+    }
+
+    /// Phase 1 request body
+    #[must_use]
+    pub fn start(&mut self) -> Vec<u8> {
+        self.state = State::Phase1Sent;
+        let mut body = FplyHeader::new(1, 4).encode().to_vec();
+        body.extend_from_slice(&[0x02, 0x00, self.source.mode(), 0xBB]);
+        body
+    }
+
+    /// Handle the phase 1 reply, returning the phase 2 request body
+    ///
+    /// # Errors
+    ///
+    /// Returns error if called out of order, the reply is malformed, or the
+    /// key source has no message for the mode.
+    pub fn handle_phase1(&mut self, reply: &[u8]) -> Result<Vec<u8>, FairPlayError> {
+        if self.state != State::Phase1Sent {
+            return Err(FairPlayError::InvalidState(
+                "phase 1 reply before phase 1 request".to_string(),
+            ));
+        }
+        let header = FplyHeader::parse(reply)?;
+        if header.message_type != 2 || reply.len() != PHASE1_REPLY_LEN {
+            return Err(FairPlayError::UnexpectedReply(format!(
+                "phase 1 reply of type {} and {} bytes",
+                header.message_type,
+                reply.len()
+            )));
+        }
+        let mode = self.source.mode();
+        if reply[HEADER_LEN + 2] != mode {
+            return Err(FairPlayError::UnexpectedReply(format!(
+                "reply is for mode {}, requested {mode}",
+                reply[HEADER_LEN + 2]
+            )));
+        }
+
+        let key_message = self.source.key_message(mode, reply)?;
+        if key_message.len() != KEY_MESSAGE_LEN {
+            return Err(FairPlayError::Malformed(format!(
+                "key message must be {KEY_MESSAGE_LEN} bytes, got {}",
+                key_message.len()
+            )));
+        }
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "KEY_MESSAGE_LEN is a small constant"
+        )]
+        let mut body = FplyHeader::new(3, KEY_MESSAGE_LEN as u32).encode().to_vec();
+        body.extend_from_slice(&key_message);
+
+        let mut echo = [0u8; PHASE2_ECHO_LEN];
+        echo.copy_from_slice(&body[body.len() - PHASE2_ECHO_LEN..]);
+        self.state = State::Phase2Sent(echo);
+        Ok(body)
+    }
+
+    /// Handle the phase 2 reply, completing the handshake
+    ///
+    /// # Errors
+    ///
+    /// Returns error if called out of order or the reply does not echo the
+    /// request.
+    pub fn handle_phase2(&mut self, reply: &[u8]) -> Result<(), FairPlayError> {
+        let State::Phase2Sent(echo) = &self.state else {
+            return Err(FairPlayError::InvalidState(
+                "phase 2 reply before phase 2 request".to_string(),
+            ));
+        };
+        let header = FplyHeader::parse(reply)?;
+        if header.message_type != 4 || reply[HEADER_LEN..] != echo[..] {
+            return Err(FairPlayError::UnexpectedReply(
+                "phase 2 reply does not echo the key message".to_string(),
+            ));
+        }
+        self.state = State::Complete;
+        Ok(())
+    }
+
+    /// Whether both phases completed
+    #[must_use]
+    pub fn is_complete(&self) -> bool {
+        self.state == State::Complete
+    }
+}
@@ -0,0 +1,103 @@
+use super::*;
+
+fn phase1_reply(mode: u8) -> Vec<u8> {
+    let mut reply = FplyHeader::new(2, 130).encode().to_vec();
+    reply.extend_from_slice(&[0x02, 0x00, mode]);
+    reply.resize(PHASE1_REPLY_LEN, 0xA5);
+    reply
+}
+
+fn phase2_reply(request: &[u8]) -> Vec<u8> {
+    let mut reply = FplyHeader::new(4, 20).encode().to_vec();
+    reply.extend_from_slice(&request[request.len() - 20..]);
+    reply
+}
+
+fn key_message() -> Vec<u8> {
+    (0..=255u8).cycle().take(KEY_MESSAGE_LEN).collect()
+}
+
+#[test]
+fn test_header_round_trip() {
+    let header = FplyHeader::new(3, 152);
+    let encoded = header.encode();
+    assert_eq!(&encoded[..4], b"FPLY");
+    assert_eq!(encoded[4..8], [3, 1, 3, 0]);
+    assert_eq!(encoded[8..], [0, 0, 0, 0x98]);
+
+    let mut message = encoded.to_vec();
+    assert!(FplyHeader::parse(&message).is_err(), "payload missing");
+    message.extend_from_slice(&key_message());
+    assert_eq!(FplyHeader::parse(&message).unwrap(), header);
+    assert!(FplyHeader::parse(b"RTSP/1.0 200").is_err());
+}
+
+#[test]
+fn test_handshake_phases() {
+    let source = RecordedKeyMessage::new(2, &key_message()).unwrap();
+    let mut setup = FairPlaySetup::new(&source);
+
+    let phase1 = setup.start();
+    assert_eq!(phase1.len(), 16);
+    assert_eq!(
+        phase1,
+        [
+            0x46, 0x50, 0x4C, 0x59, 0x03, 0x01, 0x01, 0x00, 0x00, 0x00, 0x00, 0x04, 0x02, 0x00,
+            0x02, 0xBB
+        ]
+    );
+
+    let phase2 = setup.handle_phase1(&phase1_reply(2)).unwrap();
+    assert_eq!(phase2.len(), 164);
+    assert_eq!(&phase2[HEADER_LEN..], key_message().as_slice());
+    assert!(!setup.is_complete());
+
+    setup.handle_phase2(&phase2_reply(&phase2)).unwrap();
+    assert!(setup.is_complete());
+}
+
+#[test]
+fn test_handshake_rejects_bad_replies() {
+    let source = RecordedKeyMessage::new(0, &key_message()).unwrap();
+
+    let mut setup = FairPlaySetup::new(&source);
+    assert!(matches!(
+        setup.handle_phase1(&phase1_reply(0)),
+        Err(FairPlayError::InvalidState(_))
+    ));
+    let _ = setup.start();
+    assert!(matches!(
+        setup.handle_phase1(&phase1_reply(1)),
+        Err(FairPlayError::UnexpectedReply(_))
+    ));
+
+    let mut setup = FairPlaySetup::new(&source);
+    let _ = setup.start();
+    let phase2 = setup.handle_phase1(&phase1_reply(0)).unwrap();
+    let mut reply = phase2_reply(&phase2);
+    reply[31] ^= 0xFF;
+    assert!(matches!(
+        setup.handle_phase2(&reply),
+        Err(FairPlayError::UnexpectedReply(_))
+    ));
+    assert!(!setup.is_complete());
+}
+
+#[test]
+fn test_recorded_key_message() {
+    let mut full = FplyHeader::new(3, 152).encode().to_vec();
+    full.extend_from_slice(&key_message());
+    let source = RecordedKeyMessage::new(1, &full).unwrap();
+    assert_eq!(source.mode(), 1);
+    assert_eq!(source.key_message(1, &[]).unwrap(), key_message());
+    assert!(matches!(
+        source.key_message(2, &[]),
+        Err(FairPlayError::NoKeyMessage(2))
+    ));
+
+    assert!(RecordedKeyMessage::new(4, &key_message()).is_err());
+    assert!(RecordedKeyMessage::new(0, &[0u8; 100]).is_err());
+    full[6] = 1;
+    assert!(RecordedKeyMessage::new(0, &full).is_err());
+    assert!(!format!("{source:?}").contains("message"));
+}
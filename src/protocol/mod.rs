@@ -4,10 +4,14 @@
 
 pub mod crypto;
 pub mod daap;
+#[cfg(feature = "dacp")]
 pub mod dacp;
+pub mod fairplay;
 pub mod pairing;
 pub mod plist;
+#[cfg(feature = "ptp")]
 pub mod ptp;
+#[cfg(feature = "raop")]
 pub mod raop;
 pub mod rtp;
 pub mod rtsp;
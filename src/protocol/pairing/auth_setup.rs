@@ -1,9 +1,11 @@
 //! Auth-Setup - `MFi` authentication handshake
 //!
-//! This step is required by `AirPlay` 2 devices, even if we don't perform full `MFi` verification.
-//! It establishes an ephemeral Curve25519 shared secret.
+//! This step is required by `AirPlay` 2 devices. It establishes an ephemeral
+//! Curve25519 shared secret, and the device proves it holds a genuine `MFi`
+//! authentication chip by signing the exchanged keys.
 
 use super::PairingError;
+use super::mfi::{self, MfiCertificate};
 use crate::protocol::crypto::X25519KeyPair;
 
 /// Auth-Setup session
@@ -39,10 +41,7 @@ impl AuthSetup {
         body
     }
 
-    /// Process response - returns result
-    ///
-    /// Note: In a real `MFi` implementation, we would verify the signature here.
-    /// Since we are open source and don't have `MFi` keys, we just accept it.
+    /// Parse the response
     ///
     /// Response Format:
     /// <32:Server’s Curve25119 public key>
@@ -51,53 +50,120 @@ impl AuthSetup {
     /// <4:Signature length (int32be)>
     /// <n:Signature>
     ///
+    /// Only the framing is checked; see [`verify`](Self::verify) for the
+    /// certificate and signature.
+    ///
     /// # Errors
     ///
     /// Returns error if response is too short or malformed
-    ///
-    /// # Panics
-    ///
-    /// Panics if the internal cursor logic fails (should not happen if length checks pass)
-    pub fn process_response(&self, data: &[u8]) -> Result<(), PairingError> {
+    pub fn process_response(&self, data: &[u8]) -> Result<AuthSetupResponse, PairingError> {
         if data.len() < 32 {
             return Err(PairingError::AuthenticationFailed(
                 "Auth-Setup response too short".to_string(),
             ));
         }
+        let mut server_public_key = [0u8; 32];
+        server_public_key.copy_from_slice(&data[..32]);
 
-        // We can parse it just to validate structure, even if we don't verify signature
-        let mut cursor = 32;
+        let (certificate, rest) = read_block(&data[32..], "certificate")?;
+        let (signature, _) = read_block(rest, "signature")?;
 
-        if data.len() < cursor + 4 {
-            return Err(PairingError::AuthenticationFailed(
-                "Auth-Setup response missing cert length".to_string(),
-            ));
-        }
+        Ok(AuthSetupResponse {
+            server_public_key,
+            certificate: certificate.to_vec(),
+            signature: signature.to_vec(),
+        })
+    }
 
-        let cert_len = u32::from_be_bytes(data[cursor..cursor + 4].try_into().unwrap()) as usize;
-        cursor += 4;
+    /// Verify the device's `MFi` certificate chain and signature
+    ///
+    /// The signature is encrypted with AES-128-CTR under a key and IV taken
+    /// from SHA-1 of `"AES-KEY"` / `"AES-IV"` followed by the Curve25519
+    /// shared secret. Once decrypted it must be a PKCS#1 v1.5 SHA-1
+    /// signature, by the chain's leaf key, over the server's public key
+    /// followed by ours. Returns the leaf certificate.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the chain cannot be parsed or verified, or the
+    /// signature does not match.
+    #[cfg(feature = "raop")]
+    pub fn verify(&self, response: &AuthSetupResponse) -> Result<MfiCertificate, PairingError> {
+        use sha1::{Digest, Sha1};
 
-        if data.len() < cursor + cert_len {
-            return Err(PairingError::AuthenticationFailed(
-                "Auth-Setup response missing certificate data".to_string(),
-            ));
-        }
-        cursor += cert_len;
+        use crate::protocol::crypto::{Aes128Ctr, X25519PublicKey};
 
-        if data.len() < cursor + 4 {
-            return Err(PairingError::AuthenticationFailed(
-                "Auth-Setup response missing signature length".to_string(),
-            ));
-        }
-        let sig_len = u32::from_be_bytes(data[cursor..cursor + 4].try_into().unwrap()) as usize;
-        cursor += 4;
+        let chain = mfi::verify_chain(&response.certificates()?)?;
+        let leaf = chain.into_iter().next().ok_or_else(|| {
+            PairingError::InvalidCertificate("empty certificate chain".to_string())
+        })?;
 
-        if data.len() < cursor + sig_len {
-            return Err(PairingError::AuthenticationFailed(
-                "Auth-Setup response missing signature data".to_string(),
-            ));
-        }
+        let server_public = X25519PublicKey::from_bytes(&response.server_public_key)?;
+        let shared = self.keypair.diffie_hellman(&server_public);
+        let derive = |label: &[u8]| {
+            let mut hasher = Sha1::new();
+            hasher.update(label);
+            hasher.update(shared.as_bytes());
+            hasher.finalize()
+        };
+        let mut signature = response.signature.clone();
+        Aes128Ctr::new(&derive(b"AES-KEY")[..16], &derive(b"AES-IV")[..16])?
+            .apply_keystream(&mut signature);
+
+        let mut message = response.server_public_key.to_vec();
+        message.extend_from_slice(self.keypair.public_key().as_bytes());
+        leaf.verify_signature(&message, &signature)?;
+
+        Ok(leaf)
+    }
+
+    /// Verify the device's `MFi` certificate chain and signature
+    ///
+    /// # Errors
+    ///
+    /// Always fails: RSA verification requires the `raop` feature.
+    #[cfg(not(feature = "raop"))]
+    pub fn verify(&self, _response: &AuthSetupResponse) -> Result<MfiCertificate, PairingError> {
+        Err(PairingError::InvalidCertificate(
+            "MFi verification requires the `raop` feature".to_string(),
+        ))
+    }
+}
+
+/// Parsed `/auth-setup` response
+#[derive(Debug, Clone)]
+pub struct AuthSetupResponse {
+    /// Device's ephemeral Curve25519 public key
+    pub server_public_key: [u8; 32],
+    /// PKCS#7 DER encoded certificate chain
+    pub certificate: Vec<u8>,
+    /// Encrypted signature
+    pub signature: Vec<u8>,
+}
+
+impl AuthSetupResponse {
+    /// Decode the certificate chain, without verifying it
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the certificate data is malformed.
+    pub fn certificates(&self) -> Result<Vec<MfiCertificate>, PairingError> {
+        mfi::parse_certificates(&self.certificate)
+    }
+}
 
-        Ok(())
+/// Split a length-prefixed (int32be) block off the front of `data`
+fn read_block<'a>(data: &'a [u8], what: &str) -> Result<(&'a [u8], &'a [u8]), PairingError> {
+    let Some((len, rest)) = data.split_first_chunk::<4>() else {
+        return Err(PairingError::AuthenticationFailed(format!(
+            "Auth-Setup response missing {what} length"
+        )));
+    };
+    let len = u32::from_be_bytes(*len) as usize;
+    if rest.len() < len {
+        return Err(PairingError::AuthenticationFailed(format!(
+            "Auth-Setup response missing {what} data"
+        )));
     }
+    Ok(rest.split_at(len))
 }
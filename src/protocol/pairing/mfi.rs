@@ -0,0 +1,451 @@
+//! `MFi` certificate parsing and verification
+//!
+//! Devices with an Apple authentication coprocessor return its certificate
+//! chain in the `/auth-setup` response, as a PKCS#7 `SignedData` bundle (or,
+//! on some firmware, a bare X.509 certificate). Only the parts needed to
+//! identify the chip and check signatures are decoded here.
+
+use std::fmt::Write as _;
+
+use super::PairingError;
+
+/// Object identifiers, DER-encoded
+mod oid {
+    pub const SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+    pub const RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+    pub const SHA1_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x05];
+    pub const SHA256_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x0B];
+    pub const COMMON_NAME: &[u8] = &[0x55, 0x04, 0x03];
+    pub const COUNTRY: &[u8] = &[0x55, 0x04, 0x06];
+    pub const ORGANIZATION: &[u8] = &[0x55, 0x04, 0x0A];
+    pub const ORGANIZATIONAL_UNIT: &[u8] = &[0x55, 0x04, 0x0B];
+}
+
+/// DER tags
+mod tag {
+    pub const INTEGER: u8 = 0x02;
+    pub const BIT_STRING: u8 = 0x03;
+    pub const OID: u8 = 0x06;
+    pub const SEQUENCE: u8 = 0x30;
+    pub const SET: u8 = 0x31;
+    pub const CONTEXT_0: u8 = 0xA0;
+}
+
+/// Public key of a certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MfiPublicKey {
+    /// RSA key
+    Rsa {
+        /// Big-endian modulus
+        modulus: Vec<u8>,
+        /// Big-endian public exponent
+        exponent: Vec<u8>,
+    },
+    /// Key of an algorithm this crate cannot verify with
+    Unsupported {
+        /// Algorithm OID, dotted
+        algorithm: String,
+    },
+}
+
+/// Signature algorithm of a certificate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureAlgorithm {
+    /// `sha1WithRSAEncryption`
+    Sha1WithRsa,
+    /// `sha256WithRSAEncryption`
+    Sha256WithRsa,
+    /// Any other algorithm, by dotted OID
+    Other(String),
+}
+
+/// An X.509 certificate from an `MFi` chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MfiCertificate {
+    /// Serial number, big-endian
+    pub serial: Vec<u8>,
+    /// Subject distinguished name, e.g. `"C=US, O=Apple Inc., CN=..."`
+    pub subject: String,
+    /// Issuer distinguished name
+    pub issuer: String,
+    /// Start of validity, as encoded (`UTCTime` or `GeneralizedTime`)
+    pub not_before: String,
+    /// End of validity, as encoded
+    pub not_after: String,
+    /// Subject public key
+    pub public_key: MfiPublicKey,
+    /// Algorithm the issuer signed this certificate with
+    pub signature_algorithm: SignatureAlgorithm,
+    subject_der: Vec<u8>,
+    issuer_der: Vec<u8>,
+    tbs: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl MfiCertificate {
+    /// Parse a DER-encoded X.509 certificate
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the certificate is malformed.
+    pub fn from_der(der: &[u8]) -> Result<Self, PairingError> {
+        let mut outer = Der::new(der);
+        let mut cert = Der::new(outer.expect(tag::SEQUENCE)?);
+
+        let tbs = cert.expect_raw(tag::SEQUENCE)?;
+        let signature_algorithm = signature_algorithm(&mut Der::new(cert.expect(tag::SEQUENCE)?))?;
+        let signature = bit_string(cert.expect(tag::BIT_STRING)?)?.to_vec();
+
+        let mut fields = Der::new(Der::new(tbs).expect(tag::SEQUENCE)?);
+        if fields.peek_tag() == Some(tag::CONTEXT_0) {
+            fields.next()?; // version
+        }
+        let serial = fields.expect(tag::INTEGER)?.to_vec();
+        fields.expect(tag::SEQUENCE)?; // inner signature algorithm
+        let issuer_der = fields.expect_raw(tag::SEQUENCE)?.to_vec();
+        let mut validity = Der::new(fields.expect(tag::SEQUENCE)?);
+        let not_before = String::from_utf8_lossy(validity.next()?.1).into_owned();
+        let not_after = String::from_utf8_lossy(validity.next()?.1).into_owned();
+        let subject_der = fields.expect_raw(tag::SEQUENCE)?.to_vec();
+        let public_key = public_key(&mut Der::new(fields.expect(tag::SEQUENCE)?))?;
+
+        Ok(Self {
+            serial,
+            subject: name(&subject_der)?,
+            issuer: name(&issuer_der)?,
+            not_before,
+            not_after,
+            public_key,
+            signature_algorithm,
+            subject_der,
+            issuer_der,
+            tbs: tbs.to_vec(),
+            signature,
+        })
+    }
+
+    /// Common name of the subject, if present
+    #[must_use]
+    pub fn common_name(&self) -> Option<&str> {
+        self.subject
+            .split(", ")
+            .find_map(|part| part.strip_prefix("CN="))
+    }
+
+    /// Whether the certificate names itself as issuer
+    #[must_use]
+    pub fn is_self_issued(&self) -> bool {
+        self.subject_der == self.issuer_der
+    }
+
+    /// Whether `issuer` is named as this certificate's issuer
+    #[must_use]
+    pub fn is_issued_by(&self, issuer: &Self) -> bool {
+        self.issuer_der == issuer.subject_der
+    }
+
+    /// Check this certificate's signature against `issuer`'s public key
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the signature does not verify or the algorithms are
+    /// unsupported.
+    pub fn verify_signed_by(&self, issuer: &Self) -> Result<(), PairingError> {
+        match self.signature_algorithm {
+            SignatureAlgorithm::Sha1WithRsa => issuer.verify_rsa(&self.tbs, &self.signature, false),
+            SignatureAlgorithm::Sha256WithRsa => {
+                issuer.verify_rsa(&self.tbs, &self.signature, true)
+            }
+            SignatureAlgorithm::Other(ref algorithm) => Err(PairingError::InvalidCertificate(
+                format!("unsupported signature algorithm {algorithm}"),
+            )),
+        }
+    }
+
+    /// Check a PKCS#1 v1.5 SHA-1 signature made with this certificate's key
+    ///
+    /// This is how the authentication coprocessor signs challenges.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the signature does not verify or the key is not RSA.
+    pub fn verify_signature(&self, message: &[u8], signature: &[u8]) -> Result<(), PairingError> {
+        self.verify_rsa(message, signature, false)
+    }
+
+    #[cfg(feature = "raop")]
+    fn verify_rsa(
+        &self,
+        message: &[u8],
+        signature: &[u8],
+        sha256: bool,
+    ) -> Result<(), PairingError> {
+        use crate::protocol::crypto::RsaVerifyingKey;
+
+        let MfiPublicKey::Rsa { modulus, exponent } = &self.public_key else {
+            return Err(PairingError::InvalidCertificate(format!(
+                "cannot verify with key of {}",
+                self.subject
+            )));
+        };
+        let key = RsaVerifyingKey::from_components(modulus, exponent)?;
+        let result = if sha256 {
+            key.verify_pkcs1_sha256(message, signature)
+        } else {
+            key.verify_pkcs1_sha1(message, signature)
+        };
+        result.map_err(|_| PairingError::SignatureVerificationFailed)
+    }
+
+    #[cfg(not(feature = "raop"))]
+    #[allow(
+        clippy::unused_self,
+        reason = "Same signature as with the `raop` feature"
+    )]
+    fn verify_rsa(
+        &self,
+        _message: &[u8],
+        _signature: &[u8],
+        _sha256: bool,
+    ) -> Result<(), PairingError> {
+        Err(PairingError::InvalidCertificate(
+            "RSA verification requires the `raop` feature".to_string(),
+        ))
+    }
+}
+
+/// Parse the certificates of an `MFi` chain
+///
+/// Accepts a PKCS#7 `SignedData` bundle or a single DER certificate.
+///
+/// # Errors
+///
+/// Returns error if the data is malformed or contains no certificate.
+pub fn parse_certificates(der: &[u8]) -> Result<Vec<MfiCertificate>, PairingError> {
+    let mut outer = Der::new(der);
+    let mut content_info = Der::new(outer.expect(tag::SEQUENCE)?);
+
+    // A bare certificate starts with its TBS sequence instead of an OID
+    if content_info.peek_tag() == Some(tag::SEQUENCE) {
+        return Ok(vec![MfiCertificate::from_der(der)?]);
+    }
+
+    if content_info.expect(tag::OID)? != oid::SIGNED_DATA {
+        return Err(PairingError::InvalidCertificate(
+            "not a PKCS#7 SignedData bundle".to_string(),
+        ));
+    }
+    let mut explicit = Der::new(content_info.expect(tag::CONTEXT_0)?);
+    let mut signed_data = Der::new(explicit.expect(tag::SEQUENCE)?);
+    signed_data.expect(tag::INTEGER)?; // version
+    signed_data.expect(tag::SET)?; // digest algorithms
+    signed_data.expect(tag::SEQUENCE)?; // encapsulated content
+
+    let mut certificates = Vec::new();
+    if signed_data.peek_tag() == Some(tag::CONTEXT_0) {
+        let mut set = Der::new(signed_data.expect(tag::CONTEXT_0)?);
+        while !set.is_empty() {
+            certificates.push(MfiCertificate::from_der(set.expect_raw(tag::SEQUENCE)?)?);
+        }
+    }
+
+    if certificates.is_empty() {
+        return Err(PairingError::InvalidCertificate(
+            "bundle contains no certificate".to_string(),
+        ));
+    }
+    Ok(certificates)
+}
+
+/// Order a chain leaf first and verify every link in it
+///
+/// Each certificate must be signed by the next; a self-issued top
+/// certificate must verify against its own key. The chain is only checked
+/// for internal consistency: Apple's root is not bundled, so the top
+/// certificate is trusted as presented. Returns the chain, leaf first.
+///
+/// # Errors
+///
+/// Returns error if the certificates do not form a single chain or any
+/// signature fails to verify.
+pub fn verify_chain(certificates: &[MfiCertificate]) -> Result<Vec<MfiCertificate>, PairingError> {
+    // The leaf is the one certificate that issued none of the others
+    let mut leaves = certificates.iter().filter(|candidate| {
+        !certificates
+            .iter()
+            .any(|other| !std::ptr::eq(*candidate, other) && other.is_issued_by(candidate))
+    });
+    let (Some(leaf), None) = (leaves.next(), leaves.next()) else {
+        return Err(PairingError::InvalidCertificate(
+            "certificates do not form a single chain".to_string(),
+        ));
+    };
+
+    let mut chain = vec![leaf.clone()];
+    let mut current = leaf;
+    while !current.is_self_issued() {
+        let Some(issuer) = certificates.iter().find(|c| current.is_issued_by(c)) else {
+            break;
+        };
+        current.verify_signed_by(issuer)?;
+        chain.push(issuer.clone());
+        current = issuer;
+    }
+    if current.is_self_issued() {
+        current.verify_signed_by(current)?;
+    }
+
+    if chain.len() != certificates.len() {
+        return Err(PairingError::InvalidCertificate(
+            "certificates do not form a single chain".to_string(),
+        ));
+    }
+    Ok(chain)
+}
+
+fn signature_algorithm(alg: &mut Der<'_>) -> Result<SignatureAlgorithm, PairingError> {
+    let oid = alg.expect(tag::OID)?;
+    Ok(match oid {
+        oid::SHA1_WITH_RSA => SignatureAlgorithm::Sha1WithRsa,
+        oid::SHA256_WITH_RSA => SignatureAlgorithm::Sha256WithRsa,
+        other => SignatureAlgorithm::Other(dotted(other)),
+    })
+}
+
+fn public_key(spki: &mut Der<'_>) -> Result<MfiPublicKey, PairingError> {
+    let algorithm = Der::new(spki.expect(tag::SEQUENCE)?).expect(tag::OID)?;
+    let key = bit_string(spki.expect(tag::BIT_STRING)?)?;
+    if algorithm != oid::RSA_ENCRYPTION {
+        return Ok(MfiPublicKey::Unsupported {
+            algorithm: dotted(algorithm),
+        });
+    }
+
+    let mut rsa = Der::new(Der::new(key).expect(tag::SEQUENCE)?);
+    Ok(MfiPublicKey::Rsa {
+        modulus: rsa.expect(tag::INTEGER)?.to_vec(),
+        exponent: rsa.expect(tag::INTEGER)?.to_vec(),
+    })
+}
+
+/// Render a distinguished name as `"C=US, O=..., CN=..."`
+fn name(der: &[u8]) -> Result<String, PairingError> {
+    let mut rdns = Der::new(Der::new(der).expect(tag::SEQUENCE)?);
+    let mut parts = Vec::new();
+    while !rdns.is_empty() {
+        let mut set = Der::new(rdns.expect(tag::SET)?);
+        while !set.is_empty() {
+            let mut attribute = Der::new(set.expect(tag::SEQUENCE)?);
+            let oid = attribute.expect(tag::OID)?;
+            let value = String::from_utf8_lossy(attribute.next()?.1);
+            let key = match oid {
+                oid::COMMON_NAME => "CN".to_string(),
+                oid::COUNTRY => "C".to_string(),
+                oid::ORGANIZATION => "O".to_string(),
+                oid::ORGANIZATIONAL_UNIT => "OU".to_string(),
+                other => dotted(other),
+            };
+            parts.push(format!("{key}={value}"));
+        }
+    }
+    Ok(parts.join(", "))
+}
+
+fn bit_string(content: &[u8]) -> Result<&[u8], PairingError> {
+    match content.split_first() {
+        Some((0, bits)) => Ok(bits),
+        _ => Err(PairingError::InvalidCertificate(
+            "unexpected bit string padding".to_string(),
+        )),
+    }
+}
+
+/// Dotted form of a DER-encoded OID
+fn dotted(oid: &[u8]) -> String {
+    let mut out = String::new();
+    let mut value: u64 = 0;
+    for byte in oid {
+        value = (value << 7) | u64::from(byte & 0x7F);
+        if byte & 0x80 != 0 {
+            continue;
+        }
+        if out.is_empty() {
+            let first = value.min(80) / 40;
+            let _ = write!(out, "{first}.{}", value - first * 40);
+        } else {
+            let _ = write!(out, ".{value}");
+        }
+        value = 0;
+    }
+    out
+}
+
+/// Minimal DER reader
+struct Der<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Der<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn peek_tag(&self) -> Option<u8> {
+        self.data.first().copied()
+    }
+
+    /// Read the next element, returning its tag, content and full encoding
+    fn read(&mut self) -> Result<(u8, &'a [u8], &'a [u8]), PairingError> {
+        let truncated = || PairingError::InvalidCertificate("truncated DER".to_string());
+
+        let (&tag, rest) = self.data.split_first().ok_or_else(truncated)?;
+        let (&first, rest) = rest.split_first().ok_or_else(truncated)?;
+        let (len, rest) = if first & 0x80 == 0 {
+            (usize::from(first), rest)
+        } else {
+            let count = usize::from(first & 0x7F);
+            if count == 0 || count > 4 || rest.len() < count {
+                return Err(truncated());
+            }
+            let len = rest[..count]
+                .iter()
+                .fold(0usize, |len, &b| (len << 8) | usize::from(b));
+            (len, &rest[count..])
+        };
+        if rest.len() < len {
+            return Err(truncated());
+        }
+
+        let header = self.data.len() - rest.len();
+        let raw = &self.data[..header + len];
+        let content = &rest[..len];
+        self.data = &rest[len..];
+        Ok((tag, content, raw))
+    }
+
+    fn next(&mut self) -> Result<(u8, &'a [u8]), PairingError> {
+        self.read().map(|(tag, content, _)| (tag, content))
+    }
+
+    fn expect(&mut self, expected: u8) -> Result<&'a [u8], PairingError> {
+        self.expect_raw_parts(expected).map(|(content, _)| content)
+    }
+
+    fn expect_raw(&mut self, expected: u8) -> Result<&'a [u8], PairingError> {
+        self.expect_raw_parts(expected).map(|(_, raw)| raw)
+    }
+
+    fn expect_raw_parts(&mut self, expected: u8) -> Result<(&'a [u8], &'a [u8]), PairingError> {
+        let (tag, content, raw) = self.read()?;
+        if tag != expected {
+            return Err(PairingError::InvalidCertificate(format!(
+                "expected DER tag {expected:#04x}, got {tag:#04x}"
+            )));
+        }
+        Ok((content, raw))
+    }
+}
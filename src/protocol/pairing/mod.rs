@@ -1,6 +1,8 @@
 //! `HomeKit` pairing protocol implementation
 
 pub mod auth_setup;
+pub mod mfi;
+pub mod pairings;
 pub mod setup;
 pub mod storage;
 pub mod tlv;
@@ -10,14 +12,16 @@ pub mod verify;
 #[cfg(test)]
 mod tests;
 
-pub use auth_setup::AuthSetup;
+pub use auth_setup::{AuthSetup, AuthSetupResponse};
+pub use mfi::MfiCertificate;
+pub use pairings::{PairingEntry, PairingsRequest};
 pub use setup::PairSetup;
 pub use storage::{PairingKeys, PairingStorage};
 pub use tlv::{TlvDecoder, TlvEncoder, TlvError, TlvType};
 pub use transient::TransientPairing;
 pub use verify::PairVerify;
 
-use crate::protocol::crypto::{ChaCha20Poly1305Cipher, Nonce};
+use crate::protocol::crypto::{ChaCha20Poly1305Cipher, HkdfSha512, Nonce, SecretBytes};
 
 /// Pairing session state
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,6 +42,59 @@ pub enum PairingState {
     Failed,
 }
 
+/// `X-Apple-HKP` mode sent with `/pair-setup` and `/pair-verify`
+///
+/// Receivers pick the pairing flow from this header. `HomePod`s and most
+/// third-party receivers take transient pairing for everything, while tvOS
+/// rejects it for PIN and stored-credential pairing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HkpMode {
+    /// Regular `HomeKit` pairing (PIN pair-setup, stored-key pair-verify)
+    Normal,
+    /// Transient pairing with no stored credentials
+    Transient,
+    /// System pairing, for tvOS devices under `HomeKit` access control
+    System,
+}
+
+impl HkpMode {
+    /// Header value
+    #[must_use]
+    pub fn header_value(self) -> &'static str {
+        match self {
+            Self::Normal => "3",
+            Self::Transient => "4",
+            Self::System => "6",
+        }
+    }
+
+    /// Mode for non-transient pairing with `device`
+    ///
+    /// Takes the model and flags from discovery, or from `GET /info` once
+    /// applied with [`AirPlayDevice::update_from_info`]. Transient pairing
+    /// always uses [`HkpMode::Transient`].
+    ///
+    /// [`AirPlayDevice::update_from_info`]: crate::types::AirPlayDevice::update_from_info
+    #[must_use]
+    pub fn resolve(device: &crate::types::AirPlayDevice) -> Self {
+        use crate::discovery::parser::{feature_bits, status_flags};
+
+        if !device.device_model().runs_tvos() {
+            return Self::Transient;
+        }
+        let system_pairing = device.capabilities.raw_features & feature_bits::SYSTEM_PAIRING != 0;
+        if system_pairing
+            && device
+                .txt()
+                .has_status_flag(status_flags::HOMEKIT_ACCESS_CONTROL)
+        {
+            Self::System
+        } else {
+            Self::Normal
+        }
+    }
+}
+
 /// Result of a pairing step
 #[derive(Debug)]
 pub enum PairingStepResult {
@@ -52,28 +109,64 @@ pub enum PairingStepResult {
 }
 
 /// Established session keys after pairing
+///
+/// Key material is wiped when the keys are dropped.
 #[derive(Clone, Debug)]
 pub struct SessionKeys {
     /// Key for encrypting data sent to device
-    pub encrypt_key: [u8; 32],
+    pub encrypt_key: SecretBytes,
     /// Key for decrypting data from device
-    pub decrypt_key: [u8; 32],
+    pub decrypt_key: SecretBytes,
     /// Initial nonce for encryption
     pub encrypt_nonce: u64,
     /// Initial nonce for decryption
     pub decrypt_nonce: u64,
     /// Raw shared secret for audio encryption
-    pub raw_shared_secret: [u8; 32],
+    pub raw_shared_secret: SecretBytes,
+    /// Key for encrypting our replies on the event channel
+    pub event_encrypt_key: SecretBytes,
+    /// Key for decrypting events the device sends
+    pub event_decrypt_key: SecretBytes,
 }
 
 impl SessionKeys {
+    /// Derive the control and event channel keys from a pairing secret
+    ///
+    /// The device writes on the event channel, so its write key is our
+    /// decryption key.
+    pub(crate) fn derive(
+        shared_secret: &[u8],
+        raw_shared_secret: [u8; 32],
+    ) -> Result<Self, crate::protocol::crypto::CryptoError> {
+        let control = HkdfSha512::new(Some(b"Control-Salt"), shared_secret);
+        let events = HkdfSha512::new(Some(b"Events-Salt"), shared_secret);
+
+        Ok(Self {
+            encrypt_key: control
+                .expand_fixed::<32>(b"Control-Write-Encryption-Key")?
+                .into(),
+            decrypt_key: control
+                .expand_fixed::<32>(b"Control-Read-Encryption-Key")?
+                .into(),
+            encrypt_nonce: 0,
+            decrypt_nonce: 0,
+            raw_shared_secret: raw_shared_secret.into(),
+            event_encrypt_key: events
+                .expand_fixed::<32>(b"Events-Read-Encryption-Key")?
+                .into(),
+            event_decrypt_key: events
+                .expand_fixed::<32>(b"Events-Write-Encryption-Key")?
+                .into(),
+        })
+    }
+
     /// Create cipher for encrypting outgoing messages
     ///
     /// # Errors
     ///
     /// Returns error if key is invalid
     pub fn encryptor(&self) -> Result<EncryptedChannel, crate::protocol::crypto::CryptoError> {
-        EncryptedChannel::new(&self.encrypt_key, self.encrypt_nonce, true)
+        EncryptedChannel::new(self.encrypt_key.expose(), self.encrypt_nonce, true)
     }
 
     /// Create cipher for decrypting incoming messages
@@ -82,7 +175,7 @@ impl SessionKeys {
     ///
     /// Returns error if key is invalid
     pub fn decryptor(&self) -> Result<EncryptedChannel, crate::protocol::crypto::CryptoError> {
-        EncryptedChannel::new(&self.decrypt_key, self.decrypt_nonce, false)
+        EncryptedChannel::new(self.decrypt_key.expose(), self.decrypt_nonce, false)
     }
 }
 
@@ -202,6 +295,9 @@ pub enum PairingError {
     #[error("stored keys invalid")]
     InvalidStoredKeys,
 
+    #[error("invalid MFi certificate: {0}")]
+    InvalidCertificate(String),
+
     #[error("TLV error: {0}")]
     Tlv(#[from] tlv::TlvError),
 }
@@ -0,0 +1,166 @@
+//! Pairings administration (`/pair-add`, `/pair-remove`, `/pair-list`)
+//!
+//! A controller holding an admin pairing can manage the other controllers a
+//! device trusts. Each operation is a single M1/M2 TLV exchange sent over the
+//! encrypted session established by Pair-Verify.
+
+use super::PairingError;
+use super::tlv::{TlvDecoder, TlvEncoder, TlvType, methods};
+
+/// Content type of pairings requests and responses
+pub const CONTENT_TYPE: &str = "application/pairing+tlv8";
+
+/// Permission flags of a pairing
+pub mod permissions {
+    /// Regular controller
+    pub const USER: u8 = 0x00;
+    /// Admin controller, allowed to manage pairings
+    pub const ADMIN: u8 = 0x01;
+}
+
+/// A controller paired with the device
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PairingEntry {
+    /// Controller pairing identifier
+    pub identifier: String,
+    /// Controller Ed25519 long-term public key
+    pub public_key: [u8; 32],
+    /// Whether the controller may manage pairings
+    pub admin: bool,
+}
+
+impl PairingEntry {
+    /// Create an entry for a controller
+    #[must_use]
+    pub fn new(identifier: impl Into<String>, public_key: [u8; 32], admin: bool) -> Self {
+        Self {
+            identifier: identifier.into(),
+            public_key,
+            admin,
+        }
+    }
+
+    fn permissions(&self) -> u8 {
+        if self.admin {
+            permissions::ADMIN
+        } else {
+            permissions::USER
+        }
+    }
+
+    fn from_tlv(tlv: &TlvDecoder) -> Result<Self, PairingError> {
+        let identifier = tlv.get_required(TlvType::Identifier)?;
+        let identifier = String::from_utf8(identifier.to_vec())
+            .map_err(|_| PairingError::InvalidTlv("pairing identifier is not UTF-8".to_string()))?;
+        let public_key = tlv
+            .get_required(TlvType::PublicKey)?
+            .try_into()
+            .map_err(|_| {
+                PairingError::InvalidTlv("pairing public key is not 32 bytes".to_string())
+            })?;
+        let admin = tlv
+            .get(TlvType::Permissions)
+            .and_then(|p| p.first())
+            .is_some_and(|p| p & permissions::ADMIN != 0);
+
+        Ok(Self {
+            identifier,
+            public_key,
+            admin,
+        })
+    }
+}
+
+/// A pairings administration request
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairingsRequest {
+    /// Add a controller, or update the permissions of an existing one
+    Add(PairingEntry),
+    /// Remove a controller
+    Remove {
+        /// Pairing identifier of the controller
+        identifier: String,
+    },
+    /// List all controllers
+    List,
+}
+
+impl PairingsRequest {
+    /// Endpoint the request is posted to
+    #[must_use]
+    pub fn path(&self) -> &'static str {
+        match self {
+            Self::Add(_) => "/pair-add",
+            Self::Remove { .. } => "/pair-remove",
+            Self::List => "/pair-list",
+        }
+    }
+
+    /// Encode the M1 request body
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let encoder = TlvEncoder::new().add_state(1);
+        match self {
+            Self::Add(entry) => encoder
+                .add_method(methods::ADD_PAIRING)
+                .add(TlvType::Identifier, entry.identifier.as_bytes())
+                .add(TlvType::PublicKey, &entry.public_key)
+                .add_byte(TlvType::Permissions, entry.permissions()),
+            Self::Remove { identifier } => encoder
+                .add_method(methods::REMOVE_PAIRING)
+                .add(TlvType::Identifier, identifier.as_bytes()),
+            Self::List => encoder.add_method(methods::LIST_PAIRINGS),
+        }
+        .build()
+    }
+
+    /// Parse the M2 response
+    ///
+    /// Returns the listed controllers for [`List`](Self::List), and an empty
+    /// list otherwise.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the response is malformed or the device reports an
+    /// error, e.g. because this controller is not an admin.
+    pub fn parse_response(&self, data: &[u8]) -> Result<Vec<PairingEntry>, PairingError> {
+        let records = TlvDecoder::decode_list(data)?;
+        let first = &records[0];
+
+        if let Some(code) = first.get_error() {
+            return Err(PairingError::DeviceError { code });
+        }
+        let state = first.get_state()?;
+        if state != 2 {
+            return Err(PairingError::InvalidState {
+                expected: "2".to_string(),
+                actual: state.to_string(),
+            });
+        }
+
+        match self {
+            Self::List => records
+                .iter()
+                .filter(|record| record.get(TlvType::Identifier).is_some())
+                .map(PairingEntry::from_tlv)
+                .collect(),
+            Self::Add(_) | Self::Remove { .. } => Ok(Vec::new()),
+        }
+    }
+}
+
+/// Encode a `/pair-list` M2 response listing `entries`
+#[must_use]
+pub fn encode_list_response(entries: &[PairingEntry]) -> Vec<u8> {
+    let mut encoder = TlvEncoder::new().add_state(2);
+    for (i, entry) in entries.iter().enumerate() {
+        if i > 0 {
+            encoder = encoder.add(TlvType::Separator, &[]);
+        }
+        encoder = encoder
+            .add(TlvType::Identifier, entry.identifier.as_bytes())
+            .add(TlvType::PublicKey, &entry.public_key)
+            .add_byte(TlvType::Permissions, entry.permissions());
+    }
+    encoder.build()
+}
@@ -7,7 +7,7 @@ use super::tlv::{TlvDecoder, TlvEncoder, TlvType, errors, methods};
 use super::{PairingError, PairingState, PairingStepResult, SessionKeys};
 use crate::protocol::crypto::{
     ChaCha20Poly1305Cipher, Ed25519KeyPair, Ed25519PublicKey, Ed25519Signature, HkdfSha512, Nonce,
-    SrpClient, SrpParams, SrpVerifier,
+    SrpClient, SrpGroup, SrpVerifier,
 };
 
 /// Pair-Setup session for PIN-based pairing
@@ -30,6 +30,8 @@ pub struct PairSetup {
     transient: bool,
     /// Username for SRP authentication
     username: String,
+    /// SRP group the device uses
+    srp_group: SrpGroup,
 }
 
 impl Default for PairSetup {
@@ -54,6 +56,7 @@ impl PairSetup {
             device_ltpk: None,
             transient: false,
             username: "Pair-Setup".to_string(),
+            srp_group: SrpGroup::default(),
         }
     }
 
@@ -72,6 +75,11 @@ impl PairSetup {
         self.username = username.to_string();
     }
 
+    /// Set the SRP group (3072-bit by default)
+    pub fn set_srp_group(&mut self, group: SrpGroup) {
+        self.srp_group = group;
+    }
+
     /// Start pairing - returns M1 message
     ///
     /// # Errors
@@ -133,7 +141,7 @@ impl PairSetup {
         ))?;
 
         // Create SRP client and process challenge
-        let srp_client = SrpClient::new(&SrpParams::RFC5054_3072)?;
+        let srp_client = SrpClient::new(&self.srp_group.params())?;
         let client_public = srp_client.public_key().to_vec();
 
         tracing::debug!("SRP Salt: {:02X?}", salt);
@@ -147,8 +155,6 @@ impl PairSetup {
             server_public,
         )?;
 
-        tracing::debug!("SRP Client Proof (M1): {:02X?}", verifier.client_proof());
-
         // Build M3: state=3, public_key=A, proof=M1
 
         let m3 = TlvEncoder::new()
@@ -209,22 +215,12 @@ impl PairSetup {
 
         if self.transient {
             // For transient pairing, we stop here and derive final session keys
-            let hkdf = HkdfSha512::new(Some(b"Control-Salt"), &session_key);
-            let encrypt_key = hkdf.expand_fixed::<32>(b"Control-Write-Encryption-Key")?;
-            let decrypt_key = hkdf.expand_fixed::<32>(b"Control-Read-Encryption-Key")?;
-
             // For audio encryption, use first 32 bytes of SRP session key
             let mut raw_shared_secret = [0u8; 32];
             let copy_len = session_key.len().min(32);
             raw_shared_secret[..copy_len].copy_from_slice(&session_key[..copy_len]);
 
-            let session_keys = SessionKeys {
-                encrypt_key,
-                decrypt_key,
-                encrypt_nonce: 0,
-                decrypt_nonce: 0,
-                raw_shared_secret,
-            };
+            let session_keys = SessionKeys::derive(&session_key, raw_shared_secret)?;
 
             self.session_key = Some(session_key);
             self.state = PairingState::Complete;
@@ -241,10 +237,7 @@ impl PairSetup {
         sign_data.extend_from_slice(b"airplay2-rs");
         sign_data.extend_from_slice(self.signing_keypair.public_key().as_bytes());
 
-        tracing::debug!("Signing Data (hex): {:02X?}", sign_data);
-
         let signature = self.signing_keypair.sign(&sign_data);
-        tracing::debug!("Signature (hex): {:02X?}", signature.to_bytes());
 
         let signed_tlv = TlvEncoder::new()
             .add(TlvType::Identifier, b"airplay2-rs")
@@ -352,22 +345,12 @@ impl PairSetup {
         self.state = PairingState::Complete;
 
         // Derive final session keys
-        let hkdf = HkdfSha512::new(Some(b"Control-Salt"), session_key);
-        let encrypt_key = hkdf.expand_fixed::<32>(b"Control-Write-Encryption-Key")?;
-        let decrypt_key = hkdf.expand_fixed::<32>(b"Control-Read-Encryption-Key")?;
-
         // For audio encryption, use first 32 bytes of SRP session key
         let mut raw_shared_secret = [0u8; 32];
         let copy_len = session_key.len().min(32);
         raw_shared_secret[..copy_len].copy_from_slice(&session_key[..copy_len]);
 
-        let session_keys = SessionKeys {
-            encrypt_key,
-            decrypt_key,
-            encrypt_nonce: 0,
-            decrypt_nonce: 0,
-            raw_shared_secret,
-        };
+        let session_keys = SessionKeys::derive(session_key, raw_shared_secret)?;
 
         Ok(PairingStepResult::Complete(session_keys))
     }
@@ -3,8 +3,11 @@
 use std::collections::HashMap;
 
 use async_trait::async_trait;
+#[cfg(feature = "tokio-runtime")]
 use chacha20poly1305::aead::{Aead, KeyInit};
+#[cfg(feature = "tokio-runtime")]
 use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+#[cfg(feature = "tokio-runtime")]
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
@@ -97,6 +100,7 @@ impl PairingStorage for MemoryStorage {
 }
 
 /// File-based pairing storage
+#[cfg(feature = "tokio-runtime")]
 pub struct FileStorage {
     #[allow(dead_code, reason = "Reserved for future use")]
     path: std::path::PathBuf,
@@ -104,6 +108,7 @@ pub struct FileStorage {
     encryption_key: Option<[u8; 32]>,
 }
 
+#[cfg(feature = "tokio-runtime")]
 impl FileStorage {
     /// Create file storage at the given path
     ///
@@ -207,6 +212,7 @@ impl FileStorage {
     }
 }
 
+#[cfg(feature = "tokio-runtime")]
 #[async_trait]
 impl PairingStorage for FileStorage {
     async fn load(&self, device_id: &str) -> Option<PairingKeys> {
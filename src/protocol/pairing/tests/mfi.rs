@@ -0,0 +1,271 @@
+use crate::protocol::pairing::mfi::{
+    MfiCertificate, MfiPublicKey, SignatureAlgorithm, parse_certificates,
+};
+use crate::protocol::pairing::{AuthSetup, PairingError};
+
+const SHA1_WITH_RSA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x05];
+const RSA_ENCRYPTION: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x01, 0x01];
+const SIGNED_DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x02];
+const DATA: &[u8] = &[0x2A, 0x86, 0x48, 0x86, 0xF7, 0x0D, 0x01, 0x07, 0x01];
+
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut out = vec![tag];
+    let len = content.len();
+    if len < 0x80 {
+        out.push(u8::try_from(len).unwrap());
+    } else {
+        let bytes = u32::try_from(len).unwrap().to_be_bytes();
+        let skip = bytes.iter().take_while(|&&b| b == 0).count();
+        out.push(0x80 | u8::try_from(4 - skip).unwrap());
+        out.extend_from_slice(&bytes[skip..]);
+    }
+    out.extend_from_slice(content);
+    out
+}
+
+fn seq(parts: &[Vec<u8>]) -> Vec<u8> {
+    der(0x30, &parts.concat())
+}
+
+fn integer(bytes: &[u8]) -> Vec<u8> {
+    let mut content = bytes.to_vec();
+    if content.first().is_some_and(|b| b & 0x80 != 0) {
+        content.insert(0, 0);
+    }
+    der(0x02, &content)
+}
+
+fn bit_string(bytes: &[u8]) -> Vec<u8> {
+    let mut content = vec![0];
+    content.extend_from_slice(bytes);
+    der(0x03, &content)
+}
+
+fn name(cn: &str) -> Vec<u8> {
+    let country = seq(&[der(0x06, &[0x55, 0x04, 0x06]), der(0x13, b"US")]);
+    let org = seq(&[der(0x06, &[0x55, 0x04, 0x0A]), der(0x13, b"Apple Inc.")]);
+    let common = seq(&[der(0x06, &[0x55, 0x04, 0x03]), der(0x13, cn.as_bytes())]);
+    seq(&[der(0x31, &country), der(0x31, &org), der(0x31, &common)])
+}
+
+fn rsa_spki(modulus: &[u8], exponent: &[u8]) -> Vec<u8> {
+    let key = seq(&[integer(modulus), integer(exponent)]);
+    seq(&[
+        seq(&[der(0x06, RSA_ENCRYPTION), der(0x05, &[])]),
+        bit_string(&key),
+    ])
+}
+
+fn tbs(serial: u8, issuer: &str, subject: &str, spki: Vec<u8>) -> Vec<u8> {
+    seq(&[
+        der(0xA0, &integer(&[2])),
+        integer(&[serial]),
+        seq(&[der(0x06, SHA1_WITH_RSA), der(0x05, &[])]),
+        name(issuer),
+        seq(&[der(0x17, b"200101000000Z"), der(0x17, b"400101000000Z")]),
+        name(subject),
+        spki,
+    ])
+}
+
+fn certificate(tbs: Vec<u8>, signature: &[u8]) -> Vec<u8> {
+    seq(&[
+        tbs,
+        seq(&[der(0x06, SHA1_WITH_RSA), der(0x05, &[])]),
+        bit_string(signature),
+    ])
+}
+
+fn pkcs7(certificates: &[Vec<u8>]) -> Vec<u8> {
+    let signed_data = seq(&[
+        integer(&[1]),
+        der(0x31, &[]),
+        seq(&[der(0x06, DATA)]),
+        der(0xA0, &certificates.concat()),
+        der(0x31, &[]),
+    ]);
+    seq(&[der(0x06, SIGNED_DATA), der(0xA0, &signed_data)])
+}
+
+#[test]
+fn test_parse_bare_certificate() {
+    let cert = certificate(
+        tbs(7, "Test CA", "Test Chip", rsa_spki(&[0xC1; 64], &[1, 0, 1])),
+        &[0xAA; 64],
+    );
+
+    let parsed = MfiCertificate::from_der(&cert).unwrap();
+    assert_eq!(parsed.serial, vec![7]);
+    assert_eq!(parsed.subject, "C=US, O=Apple Inc., CN=Test Chip");
+    assert_eq!(parsed.issuer, "C=US, O=Apple Inc., CN=Test CA");
+    assert_eq!(parsed.common_name(), Some("Test Chip"));
+    assert_eq!(parsed.not_before, "200101000000Z");
+    assert_eq!(parsed.not_after, "400101000000Z");
+    assert_eq!(parsed.signature_algorithm, SignatureAlgorithm::Sha1WithRsa);
+    assert!(!parsed.is_self_issued());
+    match &parsed.public_key {
+        MfiPublicKey::Rsa { modulus, exponent } => {
+            assert_eq!(modulus[1..], [0xC1; 64]);
+            assert_eq!(exponent, &vec![1, 0, 1]);
+        }
+        MfiPublicKey::Unsupported { .. } => panic!("expected RSA key"),
+    }
+
+    assert_eq!(parse_certificates(&cert).unwrap(), vec![parsed]);
+}
+
+#[test]
+fn test_parse_pkcs7_bundle() {
+    let spki = rsa_spki(&[0xC1; 64], &[1, 0, 1]);
+    let leaf = certificate(tbs(2, "Test CA", "Test Chip", spki.clone()), &[1; 64]);
+    let ca = certificate(tbs(1, "Test CA", "Test CA", spki), &[2; 64]);
+
+    let certificates = parse_certificates(&pkcs7(&[leaf, ca])).unwrap();
+    assert_eq!(certificates.len(), 2);
+    assert_eq!(certificates[0].common_name(), Some("Test Chip"));
+    assert!(certificates[0].is_issued_by(&certificates[1]));
+    assert!(certificates[1].is_self_issued());
+}
+
+#[test]
+fn test_parse_rejects_garbage() {
+    assert!(matches!(
+        parse_certificates(&[0x30, 0x05, 0x01]),
+        Err(PairingError::InvalidCertificate(_))
+    ));
+    assert!(parse_certificates(&pkcs7(&[])).is_err());
+    assert!(parse_certificates(&[]).is_err());
+}
+
+#[test]
+fn test_auth_setup_response_framing() {
+    let mut data = vec![9u8; 32];
+    data.extend_from_slice(&3u32.to_be_bytes());
+    data.extend_from_slice(&[1, 2, 3]);
+    data.extend_from_slice(&2u32.to_be_bytes());
+    data.extend_from_slice(&[4, 5]);
+
+    let response = AuthSetup::new().process_response(&data).unwrap();
+    assert_eq!(response.server_public_key, [9; 32]);
+    assert_eq!(response.certificate, vec![1, 2, 3]);
+    assert_eq!(response.signature, vec![4, 5]);
+
+    assert!(AuthSetup::new().process_response(&data[..38]).is_err());
+    assert!(AuthSetup::new().process_response(&[0; 16]).is_err());
+}
+
+#[cfg(feature = "raop")]
+mod signatures {
+    use rsa::traits::PublicKeyParts;
+    use sha1::{Digest, Sha1};
+
+    use super::*;
+    use crate::protocol::crypto::{Aes128Ctr, RaopRsaPrivateKey, X25519KeyPair};
+    use crate::protocol::pairing::mfi::verify_chain;
+
+    struct Chain {
+        chip_key: RaopRsaPrivateKey,
+        leaf: Vec<u8>,
+        ca: Vec<u8>,
+    }
+
+    fn spki_of(key: &RaopRsaPrivateKey) -> Vec<u8> {
+        let public = key.public_key();
+        rsa_spki(&public.n_bytes(), &public.e_bytes())
+    }
+
+    fn chain() -> Chain {
+        let ca_key = RaopRsaPrivateKey::generate().unwrap();
+        let chip_key = RaopRsaPrivateKey::generate().unwrap();
+
+        let ca_tbs = tbs(1, "Test CA", "Test CA", spki_of(&ca_key));
+        let ca = certificate(ca_tbs.clone(), &ca_key.sign_pkcs1(&ca_tbs).unwrap());
+        let leaf_tbs = tbs(2, "Test CA", "Test Chip", spki_of(&chip_key));
+        let leaf = certificate(leaf_tbs.clone(), &ca_key.sign_pkcs1(&leaf_tbs).unwrap());
+
+        Chain { chip_key, leaf, ca }
+    }
+
+    #[test]
+    fn test_verify_chain() {
+        let chain = chain();
+        // Order in the bundle does not matter
+        let certificates = parse_certificates(&pkcs7(&[chain.ca, chain.leaf.clone()])).unwrap();
+
+        let verified = verify_chain(&certificates).unwrap();
+        assert_eq!(verified.len(), 2);
+        assert_eq!(verified[0].common_name(), Some("Test Chip"));
+
+        // A leaf whose issuer is not included verifies up to itself
+        let leaf_only = parse_certificates(&chain.leaf).unwrap();
+        assert_eq!(verify_chain(&leaf_only).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_verify_chain_rejects_tampered_certificate() {
+        let chain = chain();
+        let mut leaf = chain.leaf.clone();
+        // Flip a byte inside the subject common name
+        let pos = leaf.windows(9).position(|w| w == b"Test Chip").unwrap();
+        leaf[pos] ^= 0x20;
+
+        let certificates = parse_certificates(&pkcs7(&[leaf, chain.ca])).unwrap();
+        assert!(verify_chain(&certificates).is_err());
+    }
+
+    /// Play the device side of Auth-Setup
+    fn respond(chain: &Chain, client: &AuthSetup, sign_with: &RaopRsaPrivateKey) -> Vec<u8> {
+        let request = client.start();
+        let server = X25519KeyPair::generate();
+        let client_public =
+            crate::protocol::crypto::X25519PublicKey::from_bytes(&request[1..]).unwrap();
+        let shared = server.diffie_hellman(&client_public);
+
+        let mut message = server.public_key().as_bytes().to_vec();
+        message.extend_from_slice(&request[1..]);
+        let mut signature = sign_with.sign_pkcs1(&message).unwrap();
+
+        let derive = |label: &[u8]| {
+            let mut hasher = Sha1::new();
+            hasher.update(label);
+            hasher.update(shared.as_bytes());
+            hasher.finalize()
+        };
+        Aes128Ctr::new(&derive(b"AES-KEY")[..16], &derive(b"AES-IV")[..16])
+            .unwrap()
+            .apply_keystream(&mut signature);
+
+        let bundle = pkcs7(&[chain.leaf.clone(), chain.ca.clone()]);
+        let mut body = server.public_key().as_bytes().to_vec();
+        body.extend_from_slice(&u32::try_from(bundle.len()).unwrap().to_be_bytes());
+        body.extend_from_slice(&bundle);
+        body.extend_from_slice(&u32::try_from(signature.len()).unwrap().to_be_bytes());
+        body.extend_from_slice(&signature);
+        body
+    }
+
+    #[test]
+    fn test_auth_setup_verify() {
+        let chain = chain();
+        let client = AuthSetup::new();
+        let body = respond(&chain, &client, &chain.chip_key);
+
+        let response = client.process_response(&body).unwrap();
+        let leaf = client.verify(&response).unwrap();
+        assert_eq!(leaf.common_name(), Some("Test Chip"));
+    }
+
+    #[test]
+    fn test_auth_setup_verify_rejects_wrong_signer() {
+        let chain = chain();
+        let client = AuthSetup::new();
+        let impostor = RaopRsaPrivateKey::generate().unwrap();
+        let body = respond(&chain, &client, &impostor);
+
+        let response = client.process_response(&body).unwrap();
+        assert!(matches!(
+            client.verify(&response),
+            Err(PairingError::SignatureVerificationFailed)
+        ));
+    }
+}
@@ -1,4 +1,6 @@
 mod m6_verification;
+mod mfi;
+mod pairings;
 mod setup;
 mod tlv;
 mod tlv_extra;
@@ -0,0 +1,102 @@
+use crate::protocol::pairing::PairingError;
+use crate::protocol::pairing::pairings::{
+    PairingEntry, PairingsRequest, encode_list_response, permissions,
+};
+use crate::protocol::pairing::tlv::{TlvDecoder, TlvEncoder, TlvType, errors, methods};
+
+#[test]
+fn test_add_request_encoding() {
+    let request = PairingsRequest::Add(PairingEntry::new("controller-2", [7; 32], true));
+    assert_eq!(request.path(), "/pair-add");
+
+    let tlv = TlvDecoder::decode(&request.encode()).unwrap();
+    assert_eq!(tlv.get_state().unwrap(), 1);
+    assert_eq!(tlv.get(TlvType::Method), Some(&[methods::ADD_PAIRING][..]));
+    assert_eq!(tlv.get(TlvType::Identifier), Some(&b"controller-2"[..]));
+    assert_eq!(tlv.get(TlvType::PublicKey), Some(&[7; 32][..]));
+    assert_eq!(
+        tlv.get(TlvType::Permissions),
+        Some(&[permissions::ADMIN][..])
+    );
+}
+
+#[test]
+fn test_remove_and_list_request_encoding() {
+    let remove = PairingsRequest::Remove {
+        identifier: "controller-2".to_string(),
+    };
+    assert_eq!(remove.path(), "/pair-remove");
+    let tlv = TlvDecoder::decode(&remove.encode()).unwrap();
+    assert_eq!(
+        tlv.get(TlvType::Method),
+        Some(&[methods::REMOVE_PAIRING][..])
+    );
+    assert_eq!(tlv.get(TlvType::Identifier), Some(&b"controller-2"[..]));
+    assert!(tlv.get(TlvType::PublicKey).is_none());
+
+    let list = PairingsRequest::List;
+    assert_eq!(list.path(), "/pair-list");
+    let tlv = TlvDecoder::decode(&list.encode()).unwrap();
+    assert_eq!(
+        tlv.get(TlvType::Method),
+        Some(&[methods::LIST_PAIRINGS][..])
+    );
+}
+
+#[test]
+fn test_list_response_roundtrip() {
+    let entries = vec![
+        PairingEntry::new("admin", [1; 32], true),
+        PairingEntry::new("guest", [2; 32], false),
+    ];
+    let response = encode_list_response(&entries);
+    assert_eq!(TlvDecoder::decode_list(&response).unwrap().len(), 2);
+
+    let parsed = PairingsRequest::List.parse_response(&response).unwrap();
+    assert_eq!(parsed, entries);
+
+    let empty = PairingsRequest::List
+        .parse_response(&encode_list_response(&[]))
+        .unwrap();
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn test_response_errors() {
+    let request = PairingsRequest::Remove {
+        identifier: "guest".to_string(),
+    };
+    assert!(
+        request
+            .parse_response(&TlvEncoder::new().add_state(2).build())
+            .unwrap()
+            .is_empty()
+    );
+
+    let rejected = TlvEncoder::new()
+        .add_state(2)
+        .add_byte(TlvType::Error, errors::AUTHENTICATION)
+        .build();
+    assert!(matches!(
+        request.parse_response(&rejected),
+        Err(PairingError::DeviceError {
+            code: errors::AUTHENTICATION
+        })
+    ));
+
+    let wrong_state = TlvEncoder::new().add_state(4).build();
+    assert!(matches!(
+        request.parse_response(&wrong_state),
+        Err(PairingError::InvalidState { .. })
+    ));
+
+    let short_key = TlvEncoder::new()
+        .add_state(2)
+        .add(TlvType::Identifier, b"guest")
+        .add(TlvType::PublicKey, &[0; 16])
+        .build();
+    assert!(matches!(
+        PairingsRequest::List.parse_response(&short_key),
+        Err(PairingError::InvalidTlv(_))
+    ));
+}
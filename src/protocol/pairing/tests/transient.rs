@@ -119,7 +119,7 @@ fn test_transient_pairing_flow() {
 
             match client.process_m4(&m4) {
                 Ok(PairingStepResult::Complete(keys)) => {
-                    assert_ne!(keys.encrypt_key, [0u8; 32]);
+                    assert!(!keys.encrypt_key.is_zero());
                 }
                 _ => panic!("Expected Complete"),
             }
@@ -200,6 +200,41 @@ impl TlvDecoder {
         Ok(Self { items })
     }
 
+    /// Decode a list of TLV records delimited by [`TlvType::Separator`]
+    ///
+    /// Used by responses that carry several records of the same shape, such
+    /// as `/pair-list`. Values within a record are reassembled as in
+    /// [`decode`](Self::decode).
+    ///
+    /// # Errors
+    ///
+    /// Returns error if buffer is too small or malformed
+    pub fn decode_list(data: &[u8]) -> Result<Vec<Self>, TlvError> {
+        let mut records = Vec::new();
+        let mut start = 0;
+        let mut pos = 0;
+
+        while pos < data.len() {
+            if pos + 2 > data.len() {
+                return Err(TlvError::BufferTooSmall);
+            }
+            let tlv_type = data[pos];
+            let end = pos + 2 + data[pos + 1] as usize;
+            if end > data.len() {
+                return Err(TlvError::BufferTooSmall);
+            }
+
+            if tlv_type == TlvType::Separator as u8 {
+                records.push(Self::decode(&data[start..pos])?);
+                start = end;
+            }
+            pos = end;
+        }
+        records.push(Self::decode(&data[start..])?);
+
+        Ok(records)
+    }
+
     /// Get a value by type
     #[must_use]
     pub fn get(&self, tlv_type: TlvType) -> Option<&[u8]> {
@@ -184,18 +184,7 @@ impl TransientPairing {
                 actual: "none".to_string(),
             })?;
 
-        let hkdf = HkdfSha512::new(Some(b"Control-Salt"), shared_secret);
-
-        let encrypt_key = hkdf.expand_fixed::<32>(b"Control-Write-Encryption-Key")?;
-        let decrypt_key = hkdf.expand_fixed::<32>(b"Control-Read-Encryption-Key")?;
-
-        let session_keys = SessionKeys {
-            encrypt_key,
-            decrypt_key,
-            encrypt_nonce: 0,
-            decrypt_nonce: 0,
-            raw_shared_secret: *shared_secret,
-        };
+        let session_keys = SessionKeys::derive(shared_secret, *shared_secret)?;
 
         self.session_keys = Some(session_keys.clone());
         self.state = PairingState::Complete;
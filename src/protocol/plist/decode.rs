@@ -1,8 +1,16 @@
-use std::collections::{HashMap, HashSet};
+//! Binary plist decoding
+//!
+//! [`decode`] builds a whole [`PlistValue`] tree. [`PlistReader`] instead
+//! checks the trailer and offset table once and then reads objects in place,
+//! so a caller after a few keys of a large `/info` response decodes only
+//! those, borrowing strings and data from the input.
+
+use std::borrow::Cow;
+use std::collections::HashSet;
 
 use thiserror::Error;
 
-use super::PlistValue;
+use super::{PlistDict, PlistValue};
 
 /// Errors that can occur during plist decoding
 #[derive(Debug, Error)]
@@ -33,6 +41,9 @@ pub enum PlistDecodeError {
 
     #[error("integer overflow")]
     IntegerOverflow,
+
+    #[error("object reference {0} is outside the offset table")]
+    InvalidObjectRef(u64),
 }
 
 /// Binary plist trailer (last 32 bytes)
@@ -79,68 +90,317 @@ impl Trailer {
 
 /// Decode binary plist data into a `PlistValue`
 pub fn decode(data: &[u8]) -> Result<PlistValue, PlistDecodeError> {
-    // Check magic header
-    if data.len() < 8 {
-        return Err(PlistDecodeError::BufferTooSmall {
-            needed: 8,
-            have: data.len(),
-        });
+    PlistReader::new(data)?.root()?.to_value()
+}
+
+/// Binary plist with a validated layout, decoded on demand
+///
+/// Creating a reader checks the header, the trailer and every offset table
+/// entry, so a malformed file is rejected before any object is read.
+pub struct PlistReader<'a> {
+    decoder: Decoder<'a>,
+    root: u64,
+}
+
+impl<'a> PlistReader<'a> {
+    /// Check the layout of `data`
+    pub fn new(data: &'a [u8]) -> Result<Self, PlistDecodeError> {
+        if data.len() < 8 {
+            return Err(PlistDecodeError::BufferTooSmall {
+                needed: 8,
+                have: data.len(),
+            });
+        }
+
+        let magic = &data[0..8];
+        if magic != b"bplist00" {
+            let mut arr = [0u8; 8];
+            arr.copy_from_slice(magic);
+            return Err(PlistDecodeError::InvalidMagic(arr));
+        }
+
+        let trailer = Trailer::parse(data)?;
+        let decoder = Decoder::new(data, &trailer)?;
+        if trailer.root_object_index >= trailer.num_objects {
+            return Err(PlistDecodeError::InvalidObjectRef(
+                trailer.root_object_index,
+            ));
+        }
+
+        Ok(Self {
+            decoder,
+            root: trailer.root_object_index,
+        })
+    }
+
+    /// Number of objects in the file
+    pub fn object_count(&self) -> usize {
+        self.decoder.num_objects
+    }
+
+    /// The top-level object
+    pub fn root(&self) -> Result<PlistRef<'_, 'a>, PlistDecodeError> {
+        self.decoder.object(self.root)
+    }
+}
+
+/// An object inside a [`PlistReader`], read without decoding its children
+#[derive(Clone, Copy)]
+pub struct PlistRef<'r, 'a> {
+    decoder: &'r Decoder<'a>,
+    index: u64,
+    marker: u8,
+    pos: usize,
+}
+
+impl<'r, 'a> PlistRef<'r, 'a> {
+    /// Whether this is a dictionary
+    pub fn is_dict(&self) -> bool {
+        self.marker >> 4 == 0xD
+    }
+
+    /// Whether this is an array
+    pub fn is_array(&self) -> bool {
+        self.marker >> 4 == 0xA
+    }
+
+    /// Whether this is a set or ordered set
+    pub fn is_set(&self) -> bool {
+        matches!(self.marker >> 4, 0xB | 0xC)
+    }
+
+    /// Number of entries of an array, set or dictionary
+    pub fn len(&self) -> Result<Option<usize>, PlistDecodeError> {
+        if !self.is_array() && !self.is_set() && !self.is_dict() {
+            return Ok(None);
+        }
+        let (count, _) = self.decoder.decode_size(self.pos, self.marker & 0x0F)?;
+        Ok(Some(count))
+    }
+
+    /// Value stored under `key`, if this is a dictionary holding it
+    pub fn get(&self, key: &str) -> Result<Option<PlistRef<'r, 'a>>, PlistDecodeError> {
+        if !self.is_dict() {
+            return Ok(None);
+        }
+        let refs = self.decoder.container_refs(self.pos, self.marker, 2)?;
+        for i in 0..refs.count {
+            let key_ref = self.decoder.object(refs.index(self.decoder, i)?)?;
+            if key_ref.as_str()?.is_some_and(|k| k == key) {
+                let value = refs.index(self.decoder, refs.count + i)?;
+                return self.decoder.object(value).map(Some);
+            }
+        }
+        Ok(None)
+    }
+
+    /// Element `i`, if this is an array or set that long
+    pub fn index(&self, i: usize) -> Result<Option<PlistRef<'r, 'a>>, PlistDecodeError> {
+        if !self.is_array() && !self.is_set() {
+            return Ok(None);
+        }
+        let refs = self.decoder.container_refs(self.pos, self.marker, 1)?;
+        if i >= refs.count {
+            return Ok(None);
+        }
+        self.decoder.object(refs.index(self.decoder, i)?).map(Some)
+    }
+
+    /// Text of a string, borrowed unless it is stored as UTF-16
+    pub fn as_str(&self) -> Result<Option<Cow<'a, str>>, PlistDecodeError> {
+        let data: &'a [u8] = self.decoder.data;
+        match self.marker >> 4 {
+            0x5 => {
+                let (len, start) = self.decoder.decode_size(self.pos, self.marker & 0x0F)?;
+                let end = self.decoder.check_range(start, len)?;
+                let s = std::str::from_utf8(&data[start..end])
+                    .map_err(|_| PlistDecodeError::InvalidUtf8)?;
+                Ok(Some(Cow::Borrowed(s)))
+            }
+            0x6 => match self
+                .decoder
+                .decode_utf16_string(self.pos, self.marker & 0x0F)?
+            {
+                PlistValue::String(s) => Ok(Some(Cow::Owned(s))),
+                _ => Ok(None),
+            },
+            _ => Ok(None),
+        }
+    }
+
+    /// Bytes of a data object, borrowed from the input
+    pub fn as_data(&self) -> Result<Option<&'a [u8]>, PlistDecodeError> {
+        let data: &'a [u8] = self.decoder.data;
+        if self.marker >> 4 != 0x4 {
+            return Ok(None);
+        }
+        let (len, start) = self.decoder.decode_size(self.pos, self.marker & 0x0F)?;
+        let end = self.decoder.check_range(start, len)?;
+        Ok(Some(&data[start..end]))
+    }
+
+    /// Decode this object and everything below it
+    pub fn to_value(&self) -> Result<PlistValue, PlistDecodeError> {
+        self.decoder.decode_object(self.index, &mut HashSet::new())
+    }
+
+    /// Decode a scalar; containers give `None`
+    fn scalar(&self) -> Result<Option<PlistValue>, PlistDecodeError> {
+        if self.is_array() || self.is_set() || self.is_dict() {
+            return Ok(None);
+        }
+        self.to_value().map(Some)
+    }
+
+    /// Value of an integer
+    pub fn as_i64(&self) -> Result<Option<i64>, PlistDecodeError> {
+        Ok(self.scalar()?.and_then(|v| v.as_i64()))
     }
 
-    let magic = &data[0..8];
-    if magic != b"bplist00" {
-        let mut arr = [0u8; 8];
-        arr.copy_from_slice(magic);
-        return Err(PlistDecodeError::InvalidMagic(arr));
+    /// Value of an integer, if it is not negative
+    pub fn as_u64(&self) -> Result<Option<u64>, PlistDecodeError> {
+        Ok(self.scalar()?.and_then(|v| v.as_u64()))
     }
 
-    let trailer = Trailer::parse(data)?;
-    let decoder = Decoder::new(data, &trailer)?;
+    /// Value of a boolean
+    pub fn as_bool(&self) -> Result<Option<bool>, PlistDecodeError> {
+        Ok(self.scalar()?.and_then(|v| v.as_bool()))
+    }
+
+    /// Value of a UID
+    pub fn as_uid(&self) -> Result<Option<u64>, PlistDecodeError> {
+        Ok(self.scalar()?.and_then(|v| v.as_uid()))
+    }
+}
+
+impl std::fmt::Debug for PlistRef<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PlistRef")
+            .field("index", &self.index)
+            .field("marker", &self.marker)
+            .finish()
+    }
+}
+
+/// Object references of an array or dictionary
+struct ContainerRefs {
+    count: usize,
+    start: usize,
+}
 
-    decoder.decode_object(trailer.root_object_index, &mut HashSet::new())
+impl ContainerRefs {
+    fn index(&self, decoder: &Decoder<'_>, i: usize) -> Result<u64, PlistDecodeError> {
+        decoder.read_ref(self.start + i * decoder.object_ref_size)
+    }
 }
 
 struct Decoder<'a> {
     data: &'a [u8],
-    offset_table: Vec<u64>,
+    offset_table: &'a [u8],
+    offset_size: usize,
+    num_objects: usize,
     object_ref_size: usize,
 }
 
 impl<'a> Decoder<'a> {
     fn new(data: &'a [u8], trailer: &Trailer) -> Result<Self, PlistDecodeError> {
-        let offset_table = Self::parse_offset_table(data, trailer)?;
-
-        Ok(Self {
-            data,
-            offset_table,
-            object_ref_size: trailer.object_ref_size as usize,
-        })
-    }
+        let offset_size = trailer.offset_size as usize;
+        let object_ref_size = trailer.object_ref_size as usize;
+        if ![1, 2, 4, 8].contains(&offset_size) || ![1, 2, 4, 8].contains(&object_ref_size) {
+            return Err(PlistDecodeError::InvalidTrailer);
+        }
 
-    fn parse_offset_table(data: &[u8], trailer: &Trailer) -> Result<Vec<u64>, PlistDecodeError> {
         let start = usize::try_from(trailer.offset_table_offset)
             .map_err(|_| PlistDecodeError::InvalidTrailer)?;
-        let entry_size = trailer.offset_size as usize;
-        let count =
+        let num_objects =
             usize::try_from(trailer.num_objects).map_err(|_| PlistDecodeError::InvalidTrailer)?;
-
-        if start + count * entry_size > data.len() {
+        // The table sits between the objects and the trailer
+        let trailer_start = data.len() - 32;
+        let end = num_objects
+            .checked_mul(offset_size)
+            .and_then(|len| len.checked_add(start))
+            .ok_or(PlistDecodeError::InvalidTrailer)?;
+        if end > trailer_start {
             return Err(PlistDecodeError::BufferTooSmall {
-                needed: start + count * entry_size,
+                needed: end + 32,
                 have: data.len(),
             });
         }
+        if start < 8 {
+            return Err(PlistDecodeError::InvalidTrailer);
+        }
+
+        let decoder = Self {
+            data,
+            offset_table: &data[start..end],
+            offset_size,
+            num_objects,
+            object_ref_size,
+        };
+
+        // Every object must start after the header and before the table
+        for entry in decoder.offset_table.chunks_exact(offset_size) {
+            let offset = Self::read_sized_int(entry, offset_size)?;
+            if offset < 8 || offset >= start as u64 {
+                return Err(PlistDecodeError::InvalidOffset(offset));
+            }
+        }
+
+        Ok(decoder)
+    }
 
-        let mut offsets = Vec::with_capacity(count);
+    /// Position of object `index`
+    fn offset_of(&self, index: u64) -> Result<usize, PlistDecodeError> {
+        let i = usize::try_from(index)
+            .ok()
+            .filter(|&i| i < self.num_objects)
+            .ok_or(PlistDecodeError::InvalidObjectRef(index))?;
+        let entry = &self.offset_table[i * self.offset_size..(i + 1) * self.offset_size];
+        let offset = Self::read_sized_int(entry, self.offset_size)?;
+        // Checked against the table start in `new`
+        Ok(offset as usize)
+    }
+
+    fn object(&self, index: u64) -> Result<PlistRef<'_, 'a>, PlistDecodeError> {
+        let pos = self.offset_of(index)?;
+        Ok(PlistRef {
+            decoder: self,
+            index,
+            marker: self.data[pos],
+            pos: pos + 1,
+        })
+    }
 
-        for i in 0..count {
-            let offset_start = start + i * entry_size;
-            let offset =
-                Self::read_sized_int(&data[offset_start..offset_start + entry_size], entry_size)?;
-            offsets.push(offset);
+    /// Read the object reference at `pos`
+    fn read_ref(&self, pos: usize) -> Result<u64, PlistDecodeError> {
+        let end = self.check_range(pos, self.object_ref_size)?;
+        Self::read_sized_int(&self.data[pos..end], self.object_ref_size)
+    }
+
+    /// End of `len` bytes from `start`, if they are inside the data
+    fn check_range(&self, start: usize, len: usize) -> Result<usize, PlistDecodeError> {
+        match start.checked_add(len) {
+            Some(end) if end <= self.data.len() => Ok(end),
+            end => Err(PlistDecodeError::BufferTooSmall {
+                needed: end.unwrap_or(usize::MAX),
+                have: self.data.len(),
+            }),
         }
+    }
 
-        Ok(offsets)
+    /// Locate the references of a container holding `per_entry` refs per entry
+    fn container_refs(
+        &self,
+        pos: usize,
+        marker: u8,
+        per_entry: usize,
+    ) -> Result<ContainerRefs, PlistDecodeError> {
+        let (count, start) = self.decode_size(pos, marker & 0x0F)?;
+        let len = count
+            .checked_mul(per_entry * self.object_ref_size)
+            .ok_or(PlistDecodeError::IntegerOverflow)?;
+        self.check_range(start, len)?;
+        Ok(ContainerRefs { count, start })
     }
 
     fn read_sized_int(data: &[u8], size: usize) -> Result<u64, PlistDecodeError> {
@@ -163,17 +423,7 @@ impl<'a> Decoder<'a> {
             return Err(PlistDecodeError::CircularReference);
         }
 
-        let index_usize =
-            usize::try_from(index).map_err(|_| PlistDecodeError::InvalidOffset(index))?;
-        let offset = *self
-            .offset_table
-            .get(index_usize)
-            .ok_or(PlistDecodeError::InvalidOffset(index))?;
-
-        let pos = usize::try_from(offset).map_err(|_| PlistDecodeError::InvalidOffset(offset))?;
-        if pos >= self.data.len() {
-            return Err(PlistDecodeError::InvalidOffset(offset));
-        }
+        let pos = self.offset_of(index)?;
         let marker = self.data[pos];
 
         let value = self.decode_value(marker, pos + 1, seen)?;
@@ -200,7 +450,13 @@ impl<'a> Decoder<'a> {
             0x5 => self.decode_ascii_string(pos, low_nibble),
             0x6 => self.decode_utf16_string(pos, low_nibble),
             0x8 => self.decode_uid(pos, low_nibble),
-            0xA => self.decode_array(pos, low_nibble, seen),
+            0xA => Ok(PlistValue::Array(
+                self.decode_members(pos, low_nibble, seen)?,
+            )),
+            0xB => Ok(PlistValue::OrderedSet(
+                self.decode_members(pos, low_nibble, seen)?,
+            )),
+            0xC => Ok(PlistValue::Set(self.decode_members(pos, low_nibble, seen)?)),
             0xD => self.decode_dictionary(pos, low_nibble, seen),
             _ => Err(PlistDecodeError::InvalidObjectMarker(marker)),
         }
@@ -333,17 +589,9 @@ impl<'a> Decoder<'a> {
 
     fn decode_data(&self, pos: usize, length_nibble: u8) -> Result<PlistValue, PlistDecodeError> {
         let (len, data_start) = self.decode_size(pos, length_nibble)?;
+        let end = self.check_range(data_start, len)?;
 
-        if data_start + len > self.data.len() {
-            return Err(PlistDecodeError::BufferTooSmall {
-                needed: data_start + len,
-                have: self.data.len(),
-            });
-        }
-
-        Ok(PlistValue::Data(
-            self.data[data_start..data_start + len].to_vec(),
-        ))
+        Ok(PlistValue::Data(self.data[data_start..end].to_vec()))
     }
 
     fn decode_ascii_string(
@@ -352,15 +600,9 @@ impl<'a> Decoder<'a> {
         len_nibble: u8,
     ) -> Result<PlistValue, PlistDecodeError> {
         let (len, str_start) = self.decode_size(pos, len_nibble)?;
+        let end = self.check_range(str_start, len)?;
 
-        if str_start + len > self.data.len() {
-            return Err(PlistDecodeError::BufferTooSmall {
-                needed: str_start + len,
-                have: self.data.len(),
-            });
-        }
-
-        let s = std::str::from_utf8(&self.data[str_start..str_start + len])
+        let s = std::str::from_utf8(&self.data[str_start..end])
             .map_err(|_| PlistDecodeError::InvalidUtf8)?;
 
         Ok(PlistValue::String(s.to_string()))
@@ -373,16 +615,12 @@ impl<'a> Decoder<'a> {
     ) -> Result<PlistValue, PlistDecodeError> {
         let (len, str_start) = self.decode_size(pos, len_nibble)?;
 
-        let byte_len = len * 2;
-
-        if str_start + byte_len > self.data.len() {
-            return Err(PlistDecodeError::BufferTooSmall {
-                needed: str_start + byte_len,
-                have: self.data.len(),
-            });
-        }
+        let byte_len = len
+            .checked_mul(2)
+            .ok_or(PlistDecodeError::IntegerOverflow)?;
+        let end = self.check_range(str_start, byte_len)?;
 
-        let bytes = &self.data[str_start..str_start + byte_len];
+        let bytes = &self.data[str_start..end];
         let u16s: Vec<u16> = bytes
             .chunks_exact(2)
             .map(|c| u16::from_be_bytes(c.try_into().unwrap()))
@@ -395,49 +633,34 @@ impl<'a> Decoder<'a> {
 
     fn decode_uid(&self, pos: usize, len_nibble: u8) -> Result<PlistValue, PlistDecodeError> {
         let len = (len_nibble + 1) as usize;
-        if pos + len > self.data.len() {
-            return Err(PlistDecodeError::BufferTooSmall {
-                needed: pos + len,
-                have: self.data.len(),
-            });
+        if len > 8 {
+            return Err(PlistDecodeError::UnsupportedType(format!("{len}-byte UID")));
         }
+        let end = self.check_range(pos, len)?;
 
-        let mut val = 0u64;
-        for i in 0..len {
-            val = (val << 8) | u64::from(self.data[pos + i]);
-        }
+        let val = self.data[pos..end]
+            .iter()
+            .fold(0u64, |val, &b| (val << 8) | u64::from(b));
 
         Ok(PlistValue::Uid(val))
     }
 
-    fn decode_array(
+    /// Members of an array, set or ordered set
+    fn decode_members(
         &self,
         pos: usize,
         count_nibble: u8,
         seen: &mut HashSet<u64>,
-    ) -> Result<PlistValue, PlistDecodeError> {
-        let (count, refs_start) = self.decode_size(pos, count_nibble)?;
-
-        if refs_start + count * self.object_ref_size > self.data.len() {
-            return Err(PlistDecodeError::BufferTooSmall {
-                needed: refs_start + count * self.object_ref_size,
-                have: self.data.len(),
-            });
-        }
+    ) -> Result<Vec<PlistValue>, PlistDecodeError> {
+        let refs = self.container_refs(pos, count_nibble, 1)?;
 
-        let mut items = Vec::with_capacity(count);
+        let mut items = Vec::with_capacity(refs.count);
 
-        for i in 0..count {
-            let curr_ref_offset = refs_start + i * self.object_ref_size;
-            let index = Self::read_sized_int(
-                &self.data[curr_ref_offset..curr_ref_offset + self.object_ref_size],
-                self.object_ref_size,
-            )?;
-
-            items.push(self.decode_object(index, seen)?);
+        for i in 0..refs.count {
+            items.push(self.decode_object(refs.index(self, i)?, seen)?);
         }
 
-        Ok(PlistValue::Array(items))
+        Ok(items)
     }
 
     fn decode_dictionary(
@@ -446,30 +669,13 @@ impl<'a> Decoder<'a> {
         count_nibble: u8,
         seen: &mut HashSet<u64>,
     ) -> Result<PlistValue, PlistDecodeError> {
-        let (count, refs_start) = self.decode_size(pos, count_nibble)?;
-
-        if refs_start + count * 2 * self.object_ref_size > self.data.len() {
-            return Err(PlistDecodeError::BufferTooSmall {
-                needed: refs_start + count * 2 * self.object_ref_size,
-                have: self.data.len(),
-            });
-        }
-
-        let mut dict = HashMap::with_capacity(count);
-
-        for i in 0..count {
-            let key_ref_start = refs_start + i * self.object_ref_size;
-            let val_ref_start = refs_start + (count + i) * self.object_ref_size;
+        let refs = self.container_refs(pos, count_nibble, 2)?;
 
-            let key_index = Self::read_sized_int(
-                &self.data[key_ref_start..key_ref_start + self.object_ref_size],
-                self.object_ref_size,
-            )?;
+        let mut dict = PlistDict::with_capacity(refs.count);
 
-            let val_index = Self::read_sized_int(
-                &self.data[val_ref_start..val_ref_start + self.object_ref_size],
-                self.object_ref_size,
-            )?;
+        for i in 0..refs.count {
+            let key_index = refs.index(self, i)?;
+            let val_index = refs.index(self, refs.count + i)?;
 
             let key_val = self.decode_object(key_index, seen)?;
             let PlistValue::String(key_str) = key_val else {
@@ -112,23 +112,29 @@ impl Encoder {
                 for item in arr {
                     refs.push(self.encode_value(item)?);
                 }
-                Some(self.create_array_body(&refs)?)
+                Some(self.create_array_body(0xA, &refs)?)
+            }
+            PlistValue::Set(members) | PlistValue::OrderedSet(members) => {
+                let mut refs = Vec::with_capacity(members.len());
+                for item in members {
+                    refs.push(self.encode_value(item)?);
+                }
+                let kind = if matches!(value, PlistValue::Set(_)) {
+                    0xC
+                } else {
+                    0xB
+                };
+                Some(self.create_array_body(kind, &refs)?)
             }
             PlistValue::Dictionary(dict) => {
-                // Keys must be strings. And we should sort them.
-                // We need to encode keys and values.
-                // Sorted by key string.
-                let mut sorted_keys: Vec<&String> = dict.keys().collect();
-                sorted_keys.sort();
-
+                // Keys are written in dictionary order, so a decoded plist
+                // re-encodes the way it arrived.
                 let mut key_refs = Vec::with_capacity(dict.len());
                 let mut val_refs = Vec::with_capacity(dict.len());
 
-                for k in sorted_keys {
-                    // Encode key (String)
+                for (k, v) in dict {
                     key_refs.push(self.encode_value(&PlistValue::String(k.clone()))?);
-                    // Encode value
-                    val_refs.push(self.encode_value(&dict[k])?);
+                    val_refs.push(self.encode_value(v)?);
                 }
 
                 Some(self.create_dict_body(&key_refs, &val_refs)?)
@@ -155,7 +161,10 @@ impl Encoder {
                 PlistValue::Data(d) => self.encode_data(d),
                 PlistValue::Date(d) => self.encode_date(*d),
                 PlistValue::Uid(u) => self.encode_uid(*u),
-                PlistValue::Array(_) | PlistValue::Dictionary(_) => unreachable!(),
+                PlistValue::Array(_)
+                | PlistValue::Dictionary(_)
+                | PlistValue::Set(_)
+                | PlistValue::OrderedSet(_) => unreachable!(),
             }
         }
 
@@ -285,9 +294,10 @@ impl Encoder {
         }
     }
 
-    fn create_array_body(&self, refs: &[usize]) -> Result<Vec<u8>, PlistEncodeError> {
+    /// Body of an array (0xA), ordered set (0xB) or set (0xC)
+    fn create_array_body(&self, kind: u8, refs: &[usize]) -> Result<Vec<u8>, PlistEncodeError> {
         let mut body = Vec::new();
-        Self::write_header_to(&mut body, 0xA, refs.len());
+        Self::write_header_to(&mut body, kind, refs.len());
 
         for &r in refs {
             self.write_ref(&mut body, r)?;
@@ -13,11 +13,16 @@ pub mod airplay;
 pub mod decode;
 pub mod encode;
 
-use std::collections::HashMap;
-
-pub use decode::{PlistDecodeError, decode};
+pub use decode::{PlistDecodeError, PlistReader, PlistRef, decode};
 pub use encode::{PlistEncodeError, encode};
 
+/// Dictionary contents, kept in insertion order
+///
+/// Decoded dictionaries keep the order of the source plist and are
+/// re-encoded in the same order, so a decode/modify/encode round trip
+/// leaves untouched keys where they were. Equality ignores order.
+pub type PlistDict = indexmap::IndexMap<String, PlistValue>;
+
 /// A property list value
 #[derive(Debug, Clone, PartialEq)]
 pub enum PlistValue {
@@ -46,10 +51,16 @@ pub enum PlistValue {
     Array(Vec<PlistValue>),
 
     /// Dictionary (key-value pairs)
-    Dictionary(HashMap<String, PlistValue>),
+    Dictionary(PlistDict),
 
-    /// UID reference (used internally)
+    /// UID reference, an index into a keyed archive's `$objects` array
     Uid(u64),
+
+    /// Unordered set of values, kept in the order they were stored
+    Set(Vec<PlistValue>),
+
+    /// Ordered set of values
+    OrderedSet(Vec<PlistValue>),
 }
 
 impl PlistValue {
@@ -126,13 +137,42 @@ impl PlistValue {
     }
 
     /// Try to get as dictionary reference
-    pub fn as_dict(&self) -> Option<&HashMap<String, PlistValue>> {
+    pub fn as_dict(&self) -> Option<&PlistDict> {
         match self {
             PlistValue::Dictionary(d) => Some(d),
             _ => None,
         }
     }
 
+    /// Try to get as UID
+    pub fn as_uid(&self) -> Option<u64> {
+        match self {
+            PlistValue::Uid(u) => Some(*u),
+            _ => None,
+        }
+    }
+
+    /// Try to get the members of a set or ordered set
+    pub fn as_set(&self) -> Option<&[PlistValue]> {
+        match self {
+            PlistValue::Set(s) | PlistValue::OrderedSet(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Object a UID points at, if this is a keyed archive
+    ///
+    /// Keyed archives store every object once in a top-level `$objects`
+    /// array and link them with UIDs. Passing a [`PlistValue::Uid`] looks
+    /// it up; any other value is returned as is.
+    pub fn archived_object<'a>(&'a self, value: &'a PlistValue) -> Option<&'a PlistValue> {
+        let Some(uid) = value.as_uid() else {
+            return Some(value);
+        };
+        let objects = self.as_dict()?.get("$objects")?.as_array()?;
+        objects.get(usize::try_from(uid).ok()?)
+    }
+
     /// Check if value is null/empty
     pub fn is_null(&self) -> bool {
         matches!(self, PlistValue::Data(d) if d.is_empty())
@@ -206,7 +246,7 @@ impl<K: Into<String>, V: Into<PlistValue>> FromIterator<(K, V)> for PlistValue {
 /// Builder for creating plist dictionaries
 #[derive(Debug, Default)]
 pub struct DictBuilder {
-    map: HashMap<String, PlistValue>,
+    map: PlistDict,
 }
 
 impl DictBuilder {
@@ -233,6 +273,16 @@ impl DictBuilder {
         self
     }
 
+    /// Merge entries, replacing any existing values for the same keys
+    pub fn extend<'a>(
+        mut self,
+        entries: impl IntoIterator<Item = (&'a String, &'a PlistValue)>,
+    ) -> Self {
+        self.map
+            .extend(entries.into_iter().map(|(k, v)| (k.clone(), v.clone())));
+        self
+    }
+
     /// Build the dictionary
     pub fn build(self) -> PlistValue {
         PlistValue::Dictionary(self.map)
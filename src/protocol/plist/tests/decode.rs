@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-use crate::protocol::plist::{PlistDecodeError, PlistValue};
+use crate::protocol::plist::{PlistDecodeError, PlistDict, PlistReader, PlistValue};
 
 #[test]
 fn test_decode_invalid_magic() {
@@ -82,7 +80,7 @@ fn test_decode_boolean() {
 
 #[test]
 fn test_decode_empty_dict() {
-    let val = PlistValue::Dictionary(HashMap::new());
+    let val = PlistValue::Dictionary(PlistDict::new());
     let bytes = crate::protocol::plist::encode(&val).unwrap();
     let decoded = crate::protocol::plist::decode(&bytes).unwrap();
     match decoded {
@@ -148,9 +146,9 @@ fn test_decode_array() {
 
 #[test]
 fn test_decode_nested_dict() {
-    let mut inner = HashMap::new();
+    let mut inner = PlistDict::new();
     inner.insert("a".to_string(), PlistValue::Integer(1));
-    let mut outer = HashMap::new();
+    let mut outer = PlistDict::new();
     outer.insert("inner".to_string(), PlistValue::Dictionary(inner));
 
     let val = PlistValue::Dictionary(outer);
@@ -214,7 +212,7 @@ fn test_decode_empty_string() {
 fn test_decode_deeply_nested_recursion_limit() {
     let mut val = PlistValue::Integer(0);
     for _ in 0..500 {
-        let mut map = HashMap::new();
+        let mut map = PlistDict::new();
         map.insert("n".to_string(), val);
         val = PlistValue::Dictionary(map);
     }
@@ -294,3 +292,165 @@ fn test_decode_unknown_marker() {
         Err(PlistDecodeError::InvalidObjectMarker(0x70))
     ));
 }
+
+/// One-object plist holding `object`, with the trailer fields given
+fn single_object_plist(object: &[u8], offset: u8, offset_size: u8, num_objects: u64) -> Vec<u8> {
+    let mut data = b"bplist00".to_vec();
+    data.extend_from_slice(object);
+    let offset_table_start = data.len();
+    data.push(offset);
+    data.extend_from_slice(&[0; 5]);
+    data.push(0); // sort
+    data.push(offset_size);
+    data.push(1); // object_ref_size
+    data.extend_from_slice(&num_objects.to_be_bytes());
+    data.extend_from_slice(&0u64.to_be_bytes()); // root_index
+    data.extend_from_slice(&(offset_table_start as u64).to_be_bytes());
+    data
+}
+
+#[test]
+fn test_reader_reads_nested_values_in_place() {
+    let value = crate::plist_dict! {
+        "name" => "Living Room",
+        "pk" => vec![1u8, 2, 3],
+        "audioFormats" => PlistValue::Array(vec![
+            crate::plist_dict! { "type" => 100i64, "audioInputFormats" => 1u64 << 11 },
+            crate::plist_dict! { "type" => 96i64, "audioInputFormats" => 1u64 << 18 },
+        ]),
+    };
+    let bytes = crate::protocol::plist::encode(&value).unwrap();
+
+    let reader = PlistReader::new(&bytes).unwrap();
+    let root = reader.root().unwrap();
+    assert!(root.is_dict());
+    assert_eq!(root.len().unwrap(), Some(3));
+
+    let name = root.get("name").unwrap().unwrap().as_str().unwrap();
+    assert!(matches!(
+        name,
+        Some(std::borrow::Cow::Borrowed("Living Room"))
+    ));
+    assert_eq!(
+        root.get("pk").unwrap().unwrap().as_data().unwrap(),
+        Some(&[1u8, 2, 3][..])
+    );
+    assert!(root.get("missing").unwrap().is_none());
+
+    let formats = root.get("audioFormats").unwrap().unwrap();
+    assert!(formats.is_array());
+    let alac = formats.index(1).unwrap().unwrap();
+    assert_eq!(
+        alac.get("type").unwrap().unwrap().as_i64().unwrap(),
+        Some(96)
+    );
+    assert!(formats.index(2).unwrap().is_none());
+
+    assert_eq!(
+        root.to_value().unwrap(),
+        crate::protocol::plist::decode(&bytes).unwrap()
+    );
+}
+
+#[test]
+fn test_reader_rejects_offsets_outside_object_area() {
+    // Object 0 claims to start inside the trailer
+    let data = single_object_plist(&[0x09], 40, 1, 1);
+    assert!(matches!(
+        PlistReader::new(&data),
+        Err(PlistDecodeError::InvalidOffset(40))
+    ));
+
+    // ...or inside the header
+    let data = single_object_plist(&[0x09], 3, 1, 1);
+    assert!(matches!(
+        PlistReader::new(&data),
+        Err(PlistDecodeError::InvalidOffset(3))
+    ));
+}
+
+#[test]
+fn test_reader_rejects_bad_trailer_sizes() {
+    let data = single_object_plist(&[0x09], 8, 3, 1);
+    assert!(matches!(
+        PlistReader::new(&data),
+        Err(PlistDecodeError::InvalidTrailer)
+    ));
+
+    // An object count far beyond the file cannot overflow the bounds check
+    let data = single_object_plist(&[0x09], 8, 8, u64::MAX / 4);
+    assert!(PlistReader::new(&data).is_err());
+}
+
+#[test]
+fn test_decode_rejects_dangling_object_ref() {
+    // One-element array referring to object 5 of 1
+    let data = single_object_plist(&[0xA1, 0x05], 8, 1, 1);
+    assert!(matches!(
+        crate::protocol::plist::decode(&data),
+        Err(PlistDecodeError::InvalidObjectRef(5))
+    ));
+}
+
+#[test]
+fn test_decode_rejects_oversized_length_without_panicking() {
+    // Data object whose 8-byte length is close to usize::MAX
+    let mut object = vec![0x4F, 0x13];
+    object.extend_from_slice(&(u64::MAX - 4).to_be_bytes());
+    let data = single_object_plist(&object, 8, 1, 1);
+    assert!(matches!(
+        crate::protocol::plist::decode(&data),
+        Err(PlistDecodeError::BufferTooSmall { .. } | PlistDecodeError::IntegerOverflow)
+    ));
+}
+
+#[test]
+fn test_decode_set_markers() {
+    // Objects: 0 = set {1}, 1 = ordered set {1}, 2 = integer 5, 3 = array [0, 1]
+    let mut data = b"bplist00".to_vec();
+    let objects: [&[u8]; 4] = [&[0xC1, 2], &[0xB1, 2], &[0x10, 5], &[0xA2, 0, 1]];
+    let mut offsets = Vec::new();
+    for object in objects {
+        offsets.push(u8::try_from(data.len()).unwrap());
+        data.extend_from_slice(object);
+    }
+    let offset_table_start = data.len();
+    data.extend_from_slice(&offsets);
+    data.extend_from_slice(&[0; 6]);
+    data.push(1); // offset_size
+    data.push(1); // object_ref_size
+    data.extend_from_slice(&4u64.to_be_bytes());
+    data.extend_from_slice(&3u64.to_be_bytes()); // root_index
+    data.extend_from_slice(&(offset_table_start as u64).to_be_bytes());
+
+    let value = crate::protocol::plist::decode(&data).unwrap();
+    assert_eq!(
+        value,
+        PlistValue::Array(vec![
+            PlistValue::Set(vec![PlistValue::Integer(5)]),
+            PlistValue::OrderedSet(vec![PlistValue::Integer(5)]),
+        ])
+    );
+
+    let reader = PlistReader::new(&data).unwrap();
+    let set = reader.root().unwrap().index(0).unwrap().unwrap();
+    assert!(set.is_set());
+    assert_eq!(set.len().unwrap(), Some(1));
+    assert_eq!(set.index(0).unwrap().unwrap().as_i64().unwrap(), Some(5));
+}
+
+#[test]
+fn test_decode_uid_lengths() {
+    let data = single_object_plist(&[0x81, 0x12, 0x34], 8, 1, 1);
+    let value = crate::protocol::plist::decode(&data).unwrap();
+    assert_eq!(value.as_uid(), Some(0x1234));
+
+    // UIDs wider than 64 bits cannot be represented
+    let mut object = vec![0x8F];
+    object.extend_from_slice(&[0xFF; 16]);
+    let data = single_object_plist(&object, 8, 1, 1);
+    assert!(matches!(
+        crate::protocol::plist::decode(&data),
+        Err(PlistDecodeError::UnsupportedType(_))
+    ));
+}
@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 
 #[test]
 fn test_encode_boolean() {
@@ -56,7 +54,7 @@ fn test_encode_array() {
 
 #[test]
 fn test_encode_dictionary() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("key1".to_string(), PlistValue::Integer(42));
     dict.insert("key2".to_string(), PlistValue::String("value".to_string()));
 
@@ -71,7 +69,7 @@ fn test_encode_dictionary() {
 
 #[test]
 fn test_encode_decode_large_dict() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     for i in 0..100 {
         dict.insert(format!("key{i}"), PlistValue::Integer(i));
     }
@@ -87,7 +85,7 @@ fn test_encode_decode_large_dict() {
 
 #[test]
 fn test_encode_decode_nested_mixed() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("int".to_string(), PlistValue::Integer(1));
     dict.insert(
         "arr".to_string(),
@@ -105,3 +103,98 @@ fn test_encode_decode_nested_mixed() {
     let arr = d.get("arr").unwrap().as_array().unwrap();
     assert_eq!(arr[0].as_bool(), Some(true));
 }
+
+#[test]
+fn test_dictionary_keeps_key_order() {
+    let value = crate::plist_dict! {
+        "zeta" => 1i64,
+        "alpha" => 2i64,
+        "mid" => 3i64,
+    };
+    let encoded = crate::protocol::plist::encode(&value).unwrap();
+    let decoded = crate::protocol::plist::decode(&encoded).unwrap();
+
+    let keys: Vec<&str> = decoded
+        .as_dict()
+        .unwrap()
+        .keys()
+        .map(String::as_str)
+        .collect();
+    assert_eq!(keys, vec!["zeta", "alpha", "mid"]);
+}
+
+#[test]
+fn test_modified_dictionary_reencodes_byte_exact() {
+    let original = crate::plist_dict! {
+        "streams" => PlistValue::Array(vec![crate::plist_dict! {
+            "type" => 96i64,
+            "ct" => 2i64,
+        }]),
+        "timingProtocol" => "PTP",
+        "isMultiSelectAirPlay" => true,
+    };
+    let encoded = crate::protocol::plist::encode(&original).unwrap();
+
+    // Unchanged round trip
+    let decoded = crate::protocol::plist::decode(&encoded).unwrap();
+    assert_eq!(crate::protocol::plist::encode(&decoded).unwrap(), encoded);
+
+    // Changing a value leaves every key where it was
+    let PlistValue::Dictionary(mut dict) = decoded else {
+        panic!("expected a dictionary");
+    };
+    dict["timingProtocol"] = PlistValue::String("NTP".to_string());
+    let expected = crate::plist_dict! {
+        "streams" => PlistValue::Array(vec![crate::plist_dict! {
+            "type" => 96i64,
+            "ct" => 2i64,
+        }]),
+        "timingProtocol" => "NTP",
+        "isMultiSelectAirPlay" => true,
+    };
+    assert_eq!(
+        crate::protocol::plist::encode(&PlistValue::Dictionary(dict)).unwrap(),
+        crate::protocol::plist::encode(&expected).unwrap()
+    );
+}
+
+#[test]
+fn test_encode_decode_sets_and_uids() {
+    let value = PlistValue::Array(vec![
+        PlistValue::Set(vec![PlistValue::from("a"), PlistValue::from(2i64)]),
+        PlistValue::OrderedSet(vec![PlistValue::from(3i64), PlistValue::from(1i64)]),
+        PlistValue::Uid(7),
+        PlistValue::Uid(0x1_0000),
+        PlistValue::Set(vec![]),
+    ]);
+    let encoded = crate::protocol::plist::encode(&value).unwrap();
+    let decoded = crate::protocol::plist::decode(&encoded).unwrap();
+    assert_eq!(decoded, value);
+
+    let items = decoded.as_array().unwrap();
+    assert_eq!(items[1].as_set().unwrap().len(), 2);
+    assert_eq!(items[3].as_uid(), Some(0x1_0000));
+}
+
+#[test]
+fn test_keyed_archive_uid_lookup() {
+    let archive = crate::plist_dict! {
+        "$archiver" => "NSKeyedArchiver",
+        "$top" => crate::plist_dict! { "root" => PlistValue::Uid(1) },
+        "$objects" => PlistValue::Array(vec![
+            PlistValue::from("$null"),
+            crate::plist_dict! { "title" => PlistValue::Uid(2) },
+            PlistValue::from("Song"),
+        ]),
+    };
+    let decoded =
+        crate::protocol::plist::decode(&crate::protocol::plist::encode(&archive).unwrap()).unwrap();
+
+    let top = decoded.as_dict().unwrap()["$top"].as_dict().unwrap();
+    let root = decoded.archived_object(&top["root"]).unwrap();
+    let title = decoded
+        .archived_object(&root.as_dict().unwrap()["title"])
+        .unwrap();
+    assert_eq!(title.as_str(), Some("Song"));
+    assert!(decoded.archived_object(&PlistValue::Uid(9)).is_none());
+}
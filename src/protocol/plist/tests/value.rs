@@ -1,4 +1,4 @@
-use crate::protocol::plist::{DictBuilder, PlistValue};
+use crate::protocol::plist::{DictBuilder, PlistDict, PlistValue};
 
 #[test]
 fn test_plist_value_accessors() {
@@ -42,6 +42,27 @@ fn test_dict_builder() {
     assert!(!d.contains_key("key4"));
 }
 
+#[test]
+fn test_dict_builder_extend_overrides() {
+    let overrides: PlistDict = [
+        ("key2".to_string(), PlistValue::from("replaced")),
+        ("extra".to_string(), PlistValue::from(true)),
+    ]
+    .into();
+
+    let dict = DictBuilder::new()
+        .insert("key1", "value1")
+        .insert("key2", 42i64)
+        .extend(&overrides)
+        .build();
+
+    let d = dict.as_dict().unwrap();
+    assert_eq!(d.len(), 3);
+    assert_eq!(d["key1"].as_str(), Some("value1"));
+    assert_eq!(d["key2"].as_str(), Some("replaced"));
+    assert_eq!(d["extra"].as_bool(), Some(true));
+}
+
 #[test]
 fn test_plist_dict_macro() {
     let dict = plist_dict! {
@@ -474,6 +474,7 @@ impl PtpClock {
     /// Convert an RTP timestamp to a local PTP timestamp.
     ///
     /// Uses the sample rate to convert from samples to time.
+    #[cfg(feature = "sender")]
     #[must_use]
     pub fn rtp_to_local_ptp(
         &self,
@@ -482,18 +483,11 @@ impl PtpClock {
         rtp_anchor: u32,
         ptp_anchor: PtpTimestamp,
     ) -> PtpTimestamp {
-        #[allow(
-            clippy::cast_possible_wrap,
-            reason = "RTP timestamp wrapping arithmetic"
-        )]
-        let sample_diff = i64::from(rtp_timestamp.wrapping_sub(rtp_anchor) as i32);
-        let nanos_diff = sample_diff * 1_000_000_000 / i64::from(sample_rate);
-        let remote_ptp_nanos = ptp_anchor.to_nanos() + i128::from(nanos_diff);
-        let remote_ptp = if remote_ptp_nanos >= 0 {
-            PtpTimestamp::from_nanos(remote_ptp_nanos)
-        } else {
-            PtpTimestamp::ZERO
-        };
+        let mut timeline = crate::streaming::Timeline::new(sample_rate);
+        timeline.anchor(rtp_anchor, Duration::ZERO, Some(ptp_anchor));
+        let remote_ptp = timeline
+            .network_time_at_rtp(rtp_timestamp)
+            .unwrap_or(PtpTimestamp::ZERO);
         self.remote_to_local(remote_ptp)
     }
 }
@@ -360,6 +360,7 @@ fn test_remote_local_roundtrip() {
 
 // ===== RTP to local PTP conversion =====
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_rtp_to_local_ptp_basic() {
     let clock = PtpClock::new(0, PtpRole::Slave);
@@ -374,6 +375,7 @@ fn test_rtp_to_local_ptp_basic() {
     assert!(local_ptp.nanoseconds < 1_000_000); // Should be very close to 0.
 }
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_rtp_to_local_ptp_wrapping() {
     let clock = PtpClock::new(0, PtpRole::Slave);
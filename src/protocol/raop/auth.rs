@@ -82,12 +82,17 @@ pub fn generate_response(
     mac_address: &[u8; 6],
 ) -> Result<String, CryptoError> {
     let message = build_response_message(challenge, ip_address, mac_address);
-    let signature = private_key.sign_pkcs1(&message)?;
+    let signature = private_key.sign_raw_pkcs1(&message)?;
     Ok(BASE64.encode(&signature))
 }
 
 /// Verify Apple-Response header (client-side)
 ///
+/// `server_ip` and `server_mac` are the receiver's own addresses: the IP the
+/// client connected to and the hardware address from its RAOP service name.
+/// Receivers differ on whether the Base64 is padded, so both forms are
+/// accepted.
+///
 /// # Errors
 ///
 /// Returns `CryptoError` if verification fails or header is invalid base64.
@@ -99,7 +104,7 @@ pub fn verify_response(
     server_mac: &[u8; 6],
 ) -> Result<(), CryptoError> {
     let signature = BASE64
-        .decode(response_header.trim())
+        .decode(response_header.trim().trim_end_matches('='))
         .map_err(|_| CryptoError::VerificationFailed)?;
 
     let message = build_response_message(challenge, server_ip, server_mac);
@@ -159,7 +164,7 @@ impl RaopAuthenticator {
         self.state = AuthState::ChallengeSent;
     }
 
-    /// Verify the Apple-Response header
+    /// Verify the Apple-Response header against Apple's public key
     ///
     /// # Errors
     ///
@@ -169,14 +174,29 @@ impl RaopAuthenticator {
         response_header: &str,
         server_ip: &std::net::IpAddr,
         server_mac: &[u8; 6],
+    ) -> Result<(), CryptoError> {
+        let public_key = AppleRsaPublicKey::load()?;
+        self.verify_with(&public_key, response_header, server_ip, server_mac)
+    }
+
+    /// Verify the Apple-Response header against a specific receiver key
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError` if verification fails or state is invalid.
+    pub fn verify_with(
+        &mut self,
+        public_key: &AppleRsaPublicKey,
+        response_header: &str,
+        server_ip: &std::net::IpAddr,
+        server_mac: &[u8; 6],
     ) -> Result<(), CryptoError> {
         if self.state != AuthState::ChallengeSent {
             return Err(CryptoError::VerificationFailed);
         }
 
-        let public_key = AppleRsaPublicKey::load()?;
         let result = verify_response(
-            &public_key,
+            public_key,
             response_header,
             &self.challenge,
             server_ip,
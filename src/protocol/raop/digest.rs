@@ -0,0 +1,116 @@
+//! RTSP digest authentication for password-protected RAOP receivers
+//!
+//! Speakers advertising `pw=true` answer the first request with
+//! `401 Unauthorized` and a `WWW-Authenticate: Digest realm=..., nonce=...`
+//! challenge. The request is repeated with an `Authorization` header
+//! carrying `MD5(MD5(user:realm:password):nonce:MD5(method:uri))`, as in
+//! RFC 2617 without `qop`.
+
+use md5::{Digest, Md5};
+
+/// Username sent when none is configured; receivers only check the password
+pub const DEFAULT_USERNAME: &str = "iTunes";
+
+/// Credentials for a password-protected receiver
+#[derive(Clone, PartialEq, Eq)]
+pub struct DigestCredentials {
+    /// Username
+    pub username: String,
+    /// Password
+    pub password: String,
+}
+
+impl DigestCredentials {
+    /// Credentials with the default username
+    #[must_use]
+    pub fn new(password: impl Into<String>) -> Self {
+        Self {
+            username: DEFAULT_USERNAME.to_string(),
+            password: password.into(),
+        }
+    }
+}
+
+impl std::fmt::Debug for DigestCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DigestCredentials")
+            .field("username", &self.username)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A `WWW-Authenticate: Digest` challenge
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestChallenge {
+    /// Protection realm
+    pub realm: String,
+    /// Server nonce
+    pub nonce: String,
+}
+
+impl DigestChallenge {
+    /// Parse a `WWW-Authenticate` header value
+    ///
+    /// Returns `None` for non-digest schemes or when `realm` or `nonce` is
+    /// missing.
+    #[must_use]
+    pub fn parse(header: &str) -> Option<Self> {
+        let header = header.trim();
+        let (scheme, params) = header.split_once(char::is_whitespace)?;
+        if !scheme.eq_ignore_ascii_case("Digest") {
+            return None;
+        }
+
+        let mut realm = None;
+        let mut nonce = None;
+        for param in params.split(',') {
+            let Some((key, value)) = param.split_once('=') else {
+                continue;
+            };
+            let value = value.trim().trim_matches('"').to_string();
+            match key.trim().to_ascii_lowercase().as_str() {
+                "realm" => realm = Some(value),
+                "nonce" => nonce = Some(value),
+                _ => {}
+            }
+        }
+
+        Some(Self {
+            realm: realm?,
+            nonce: nonce?,
+        })
+    }
+
+    /// Compute the digest response for a request
+    #[must_use]
+    pub fn response(&self, credentials: &DigestCredentials, method: &str, uri: &str) -> String {
+        let ha1 = md5_hex(&format!(
+            "{}:{}:{}",
+            credentials.username, self.realm, credentials.password
+        ));
+        let ha2 = md5_hex(&format!("{method}:{uri}"));
+        md5_hex(&format!("{ha1}:{}:{ha2}", self.nonce))
+    }
+
+    /// Build the `Authorization` header value for a request
+    #[must_use]
+    pub fn authorization(
+        &self,
+        credentials: &DigestCredentials,
+        method: &str,
+        uri: &str,
+    ) -> String {
+        format!(
+            "Digest username=\"{}\", realm=\"{}\", nonce=\"{}\", uri=\"{uri}\", \
+             response=\"{}\"",
+            credentials.username,
+            self.realm,
+            self.nonce,
+            self.response(credentials, method, uri)
+        )
+    }
+}
+
+fn md5_hex(input: &str) -> String {
+    hex::encode(Md5::digest(input.as_bytes()))
+}
@@ -22,12 +22,23 @@ pub struct RaopSessionKeys {
 }
 
 impl RaopSessionKeys {
-    /// Generate new random session keys
+    /// Generate new random session keys, encrypting the AES key for Apple's
+    /// public key
     ///
     /// # Errors
     ///
     /// Returns `CryptoError` if key generation or encryption fails.
     pub fn generate() -> Result<Self, CryptoError> {
+        Self::generate_for(&AppleRsaPublicKey::load()?)
+    }
+
+    /// Generate new random session keys, encrypting the AES key for a
+    /// specific receiver key
+    ///
+    /// # Errors
+    ///
+    /// Returns `CryptoError` if key generation or encryption fails.
+    pub fn generate_for(public_key: &AppleRsaPublicKey) -> Result<Self, CryptoError> {
         use rand::RngCore;
 
         let mut aes_key = [0u8; AES_KEY_SIZE];
@@ -37,8 +48,7 @@ impl RaopSessionKeys {
         rng.fill_bytes(&mut aes_key);
         rng.fill_bytes(&mut aes_iv);
 
-        // Encrypt AES key with Apple's RSA public key
-        let public_key = AppleRsaPublicKey::load()?;
+        // Encrypt AES key with the receiver's RSA public key
         let encrypted_key = public_key.encrypt_oaep(&aes_key)?;
 
         Ok(Self {
@@ -1,6 +1,7 @@
 //! RAOP (`AirPlay` 1) protocol implementation
 
 mod auth;
+pub mod digest;
 pub mod encryption;
 mod key_exchange;
 pub mod session;
@@ -12,5 +13,6 @@ pub use auth::{
     AuthState, CHALLENGE_SIZE, RaopAuthenticator, build_response_message, decode_challenge,
     encode_challenge, generate_challenge, generate_response, verify_response,
 };
+pub use digest::{DigestChallenge, DigestCredentials};
 pub use key_exchange::{AES_IV_SIZE, AES_KEY_SIZE, RaopSessionKeys, parse_session_keys};
 pub use session::{RaopRtspSession, RaopSessionState};
@@ -1,10 +1,15 @@
 //! RAOP RTSP session management
 
+use std::net::IpAddr;
+
 use super::auth::RaopAuthenticator;
+use super::digest::{DigestChallenge, DigestCredentials};
 use super::key_exchange::RaopSessionKeys;
+use crate::protocol::crypto::AppleRsaPublicKey;
 use crate::protocol::daap::{Artwork, DmapProgress, TrackMetadata};
 use crate::protocol::rtsp::headers::{names, raop};
-use crate::protocol::rtsp::{Method, RtspRequest, RtspRequestBuilder, RtspResponse};
+use crate::protocol::rtsp::transport::{LowerTransport, TransportHeader};
+use crate::protocol::rtsp::{Method, RtspRequest, RtspRequestBuilder, RtspResponse, StatusCode};
 
 /// RAOP session states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -60,6 +65,18 @@ pub struct RaopRtspSession {
     server_port: u16,
     /// Authentication state
     authenticator: RaopAuthenticator,
+    /// Receiver RSA key, when it is not Apple's
+    receiver_key: Option<AppleRsaPublicKey>,
+    /// Receiver hardware address, signed into the Apple-Response
+    server_mac: Option<[u8; 6]>,
+    /// Receiver IP address as connected to
+    server_ip: Option<IpAddr>,
+    /// Local IP address of the connection
+    local_ip: Option<IpAddr>,
+    /// Password for receivers that require one
+    credentials: Option<DigestCredentials>,
+    /// Digest challenge from the receiver, once it has asked for a password
+    digest: Option<DigestChallenge>,
     /// Session encryption keys
     session_keys: Option<RaopSessionKeys>,
     /// Transport configuration
@@ -86,6 +103,12 @@ impl RaopRtspSession {
             server_addr: server_addr.to_string(),
             server_port,
             authenticator: RaopAuthenticator::new(),
+            receiver_key: None,
+            server_mac: None,
+            server_ip: None,
+            local_ip: None,
+            credentials: None,
+            digest: None,
             session_keys: None,
             transport: None,
             audio_latency: 11025, // Default ~250ms at 44.1kHz
@@ -104,6 +127,12 @@ impl RaopRtspSession {
         self.transport.as_ref()
     }
 
+    /// Audio latency announced by the receiver, in samples
+    #[must_use]
+    pub fn audio_latency(&self) -> u32 {
+        self.audio_latency
+    }
+
     /// Get session keys
     #[must_use]
     pub fn session_keys(&self) -> Option<&RaopSessionKeys> {
@@ -116,6 +145,79 @@ impl RaopRtspSession {
         self.session_id.as_deref()
     }
 
+    /// Set the receiver's hardware address
+    ///
+    /// Needed to verify the Apple-Response; without it the response is
+    /// accepted unverified.
+    pub fn set_mac_address(&mut self, mac: [u8; 6]) {
+        self.server_mac = Some(mac);
+    }
+
+    /// Use a receiver-specific RSA key instead of Apple's, both for
+    /// verifying the Apple-Response and for encrypting the AES key
+    pub fn set_receiver_key(&mut self, key: AppleRsaPublicKey) {
+        self.receiver_key = Some(key);
+    }
+
+    /// Record the connection's local and receiver IP addresses
+    pub fn set_addresses(&mut self, local: IpAddr, server: IpAddr) {
+        self.local_ip = Some(local);
+        self.server_ip = Some(server);
+    }
+
+    /// Set the credentials for a password-protected receiver
+    pub fn set_credentials(&mut self, credentials: DigestCredentials) {
+        self.credentials = Some(credentials);
+    }
+
+    /// Add an `Authorization` header if the receiver has asked for a
+    /// password
+    pub fn authorize(&self, request: &mut RtspRequest) {
+        if let (Some(challenge), Some(credentials)) = (&self.digest, &self.credentials) {
+            let authorization =
+                challenge.authorization(credentials, request.method.as_str(), &request.uri);
+            request.headers.insert(raop::AUTHORIZATION, authorization);
+        }
+    }
+
+    /// Take up a `401 Unauthorized` digest challenge
+    ///
+    /// Returns `true` if the request should be retried with
+    /// [`retry_request`](Self::retry_request). A second challenge with the
+    /// same nonce means the password was rejected, so it is not retried.
+    pub fn accept_challenge(&mut self, response: &RtspResponse) -> bool {
+        if response.status != StatusCode::UNAUTHORIZED || self.credentials.is_none() {
+            return false;
+        }
+        let Some(challenge) = response
+            .headers
+            .get(raop::WWW_AUTHENTICATE)
+            .and_then(DigestChallenge::parse)
+        else {
+            return false;
+        };
+        if self.digest.as_ref() == Some(&challenge) {
+            return false;
+        }
+        self.digest = Some(challenge);
+        true
+    }
+
+    /// Repeat a request with a new `CSeq` and the current credentials
+    pub fn retry_request(&mut self, request: &RtspRequest) -> RtspRequest {
+        let mut retry = request.clone();
+        let cseq = self.next_cseq();
+        retry.headers.insert(names::CSEQ, cseq.to_string());
+        self.authorize(&mut retry);
+        retry
+    }
+
+    /// Whether the receiver proved it holds the RSA private key
+    #[must_use]
+    pub fn is_authenticated(&self) -> bool {
+        self.authenticator.is_authenticated()
+    }
+
     /// Get next `CSeq`
     fn next_cseq(&mut self) -> u32 {
         self.cseq += 1;
@@ -155,9 +257,12 @@ impl RaopRtspSession {
         let cseq = self.next_cseq();
         let builder = RtspRequest::builder(Method::Options, self.uri("*"));
 
-        self.add_common_headers(builder, cseq)
+        let request = self
+            .add_common_headers(builder, cseq)
             .header(raop::APPLE_CHALLENGE, self.authenticator.challenge_header())
-            .build()
+            .build();
+        self.authenticator.mark_sent();
+        request
     }
 
     /// Create ANNOUNCE request with SDP
@@ -176,13 +281,15 @@ impl RaopRtspSession {
         let cseq = self.next_cseq();
         let builder = RtspRequest::builder(Method::Setup, self.uri(""));
 
-        let transport = format!(
-            "RTP/AVP/UDP;unicast;interleaved=0-1;mode=record;control_port={control_port};\
-             timing_port={timing_port}"
-        );
+        let transport = TransportHeader {
+            interleaved: Some((0, 1)),
+            control_port: Some(control_port),
+            timing_port: Some(timing_port),
+            ..TransportHeader::record(LowerTransport::Udp)
+        };
 
         self.add_common_headers(builder, cseq)
-            .header(names::TRANSPORT, &transport)
+            .header(names::TRANSPORT, transport.to_string())
             .build()
     }
 
@@ -285,19 +392,15 @@ impl RaopRtspSession {
         }
 
         // Extract session ID
-        if let Some(session) = response.session() {
-            let session_id = session.split(';').next().unwrap_or(session);
-            self.session_id = Some(session_id.to_string());
+        if let Some(session) = response.headers.session_header() {
+            self.session_id = Some(session.id);
         }
 
         match method {
             Method::Options => {
-                // Verify Apple-Response if present
-                if let Some(_apple_response) = response.headers.get(raop::APPLE_RESPONSE) {
-                    // TODO: Verify with known server parameters
-                    // For now, accept any response
+                if let Some(apple_response) = response.headers.apple_response() {
+                    self.verify_apple_response(apple_response)?;
                 }
-                self.authenticator.mark_sent();
                 self.state = RaopSessionState::OptionsExchange;
             }
             Method::Announce => {
@@ -309,8 +412,8 @@ impl RaopRtspSession {
                     self.transport = Some(Self::parse_transport(transport)?);
                 }
                 // Extract audio latency
-                if let Some(latency) = response.headers.get(raop::AUDIO_LATENCY) {
-                    self.audio_latency = latency.parse().unwrap_or(11025);
+                if let Some(latency) = response.headers.audio_latency() {
+                    self.audio_latency = latency;
                 }
                 self.state = RaopSessionState::SettingUp;
             }
@@ -329,34 +432,41 @@ impl RaopRtspSession {
         Ok(())
     }
 
+    /// Check the receiver signed our challenge with the expected key
+    ///
+    /// Skipped when the receiver's addresses are unknown, since they are part
+    /// of the signed message.
+    fn verify_apple_response(&mut self, apple_response: &str) -> Result<(), String> {
+        let (Some(server_ip), Some(server_mac)) = (self.server_ip, self.server_mac) else {
+            tracing::debug!("Apple-Response not verified: receiver address unknown");
+            return Ok(());
+        };
+
+        let result = match &self.receiver_key {
+            Some(key) => {
+                self.authenticator
+                    .verify_with(key, apple_response, &server_ip, &server_mac)
+            }
+            None => self
+                .authenticator
+                .verify(apple_response, &server_ip, &server_mac),
+        };
+        result.map_err(|e| format!("Apple-Response verification failed: {e}"))
+    }
+
     pub(crate) fn parse_transport(transport: &str) -> Result<RaopTransport, String> {
         // Parse transport header like:
         // RTP/AVP/UDP;unicast;mode=record;server_port=6000;control_port=6001;timing_port=6002
-
-        let mut server_port = 0u16;
-        let mut control_port = 0u16;
-        let mut timing_port = 0u16;
-
-        for part in transport.split(';') {
-            let part = part.trim();
-            if let Some((key, value)) = part.split_once('=') {
-                match key {
-                    "server_port" => server_port = value.parse().unwrap_or(0),
-                    "control_port" => control_port = value.parse().unwrap_or(0),
-                    "timing_port" => timing_port = value.parse().unwrap_or(0),
-                    _ => {}
-                }
-            }
-        }
-
-        if server_port == 0 {
+        let transport =
+            TransportHeader::parse(transport).map_err(|e| format!("invalid transport: {e}"))?;
+        let Some(server_port) = transport.server_port.filter(|&port| port != 0) else {
             return Err("missing server_port in transport".to_string());
-        }
+        };
 
         Ok(RaopTransport {
             server_port,
-            control_port,
-            timing_port,
+            control_port: transport.control_port.unwrap_or(0),
+            timing_port: transport.timing_port.unwrap_or(0),
             client_control_port: 0, // Set by caller
             client_timing_port: 0,
         })
@@ -368,12 +478,22 @@ impl RaopRtspSession {
     ///
     /// Returns `String` error if key generation fails.
     pub fn prepare_announce(&mut self) -> Result<String, String> {
-        let keys = RaopSessionKeys::generate().map_err(|e| e.to_string())?;
-
+        let keys = match &self.receiver_key {
+            Some(key) => RaopSessionKeys::generate_for(key),
+            None => RaopSessionKeys::generate(),
+        }
+        .map_err(|e| e.to_string())?;
+
+        let client_ip = self
+            .local_ip
+            .map_or_else(|| "0.0.0.0".to_string(), |ip| ip.to_string());
+        let server_ip = self
+            .server_ip
+            .map_or_else(|| self.server_addr.clone(), |ip| ip.to_string());
         let sdp = crate::protocol::sdp::create_raop_announce_sdp(
             &self.client_instance,
-            "0.0.0.0", // Will be filled by actual client IP
-            &self.server_addr,
+            &client_ip,
+            &server_ip,
             &keys.rsaaeskey(),
             &keys.aesiv(),
         );
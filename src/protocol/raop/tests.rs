@@ -2,5 +2,6 @@
 use super::*;
 
 mod auth;
+mod digest;
 mod encryption;
 mod session;
@@ -154,3 +154,44 @@ fn test_zeroization() {
         keys.aes_key[0] = 0x43;
     }
 }
+
+#[test]
+fn test_verify_response_with_receiver_key() {
+    use crate::protocol::crypto::AppleRsaPublicKey;
+
+    let private = RaopRsaPrivateKey::generate().unwrap();
+    let public = AppleRsaPublicKey::from(private.public_key());
+    let ip: IpAddr = "192.168.1.100".parse().unwrap();
+    let mac = [0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF];
+
+    let mut auth = RaopAuthenticator::new();
+    let challenge = decode_challenge(&auth.challenge_header()).unwrap();
+    let response = generate_response(&private, &challenge, &ip, &mac).unwrap();
+
+    // Not yet sent
+    assert!(auth.verify_with(&public, &response, &ip, &mac).is_err());
+
+    auth.mark_sent();
+    // Padded Base64 is accepted too
+    let padded = format!("{response}{}", "=".repeat((4 - response.len() % 4) % 4));
+    auth.verify_with(&public, &padded, &ip, &mac).unwrap();
+    assert!(auth.is_authenticated());
+}
+
+#[test]
+fn test_response_is_raw_pkcs1_signature() {
+    // Receivers sign the 32-byte message itself, not a digest of it
+    let private = RaopRsaPrivateKey::generate().unwrap();
+    let challenge = [0x5Au8; CHALLENGE_SIZE];
+    let ip: IpAddr = "10.0.0.2".parse().unwrap();
+    let mac = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+
+    let response = generate_response(&private, &challenge, &ip, &mac).unwrap();
+    let signature = BASE64.decode(&response).unwrap();
+    let message = build_response_message(&challenge, &ip, &mac);
+
+    private
+        .public_key()
+        .verify(rsa::Pkcs1v15Sign::new_unprefixed(), &message, &signature)
+        .unwrap();
+}
@@ -0,0 +1,41 @@
+use super::*;
+
+#[test]
+fn test_parse_challenge() {
+    let challenge = DigestChallenge::parse(r#"Digest realm="raop", nonce="abc123""#).unwrap();
+
+    assert_eq!(challenge.realm, "raop");
+    assert_eq!(challenge.nonce, "abc123");
+}
+
+#[test]
+fn test_parse_challenge_rejects_other_schemes() {
+    assert!(DigestChallenge::parse(r#"Basic realm="raop""#).is_none());
+    assert!(DigestChallenge::parse(r#"Digest realm="raop""#).is_none());
+}
+
+#[test]
+fn test_digest_response() {
+    let challenge = DigestChallenge {
+        realm: "raop".to_string(),
+        nonce: "abc123".to_string(),
+    };
+    let credentials = DigestCredentials::new("secret");
+
+    assert_eq!(credentials.username, "iTunes");
+    assert_eq!(
+        challenge.response(&credentials, "ANNOUNCE", "rtsp://10.0.0.2/1234"),
+        "4db0d2c6cd1b021fa28ebad75ff0a36b"
+    );
+
+    let header = challenge.authorization(&credentials, "ANNOUNCE", "rtsp://10.0.0.2/1234");
+    assert!(header.starts_with("Digest username=\"iTunes\""));
+    assert!(header.contains("uri=\"rtsp://10.0.0.2/1234\""));
+    assert!(header.contains("response=\"4db0d2c6cd1b021fa28ebad75ff0a36b\""));
+}
+
+#[test]
+fn test_credentials_debug_hides_password() {
+    let credentials = DigestCredentials::new("secret");
+    assert!(!format!("{credentials:?}").contains("secret"));
+}
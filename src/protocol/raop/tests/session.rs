@@ -194,3 +194,138 @@ fn test_process_response_failure() {
     assert!(result.is_err());
     assert!(result.unwrap_err().contains("400 Bad Request"));
 }
+
+fn options_response(apple_response: &str) -> RtspResponse {
+    let mut headers = Headers::new();
+    headers.insert("Apple-Response", apple_response);
+    RtspResponse {
+        version: "RTSP/1.0".to_string(),
+        status: StatusCode::OK,
+        reason: "OK".to_string(),
+        headers,
+        body: Vec::new(),
+    }
+}
+
+#[test]
+fn test_apple_response_verified() {
+    use crate::protocol::crypto::{AppleRsaPublicKey, RaopRsaPrivateKey};
+
+    let receiver = RaopRsaPrivateKey::generate().unwrap();
+    let server_ip: std::net::IpAddr = "192.168.1.50".parse().unwrap();
+    let mac = [0x00, 0x50, 0xC2, 0x12, 0xA2, 0x3F];
+
+    let mut session = RaopRtspSession::new("192.168.1.50", 5000);
+    session.set_receiver_key(AppleRsaPublicKey::from(receiver.public_key()));
+    session.set_mac_address(mac);
+    session.set_addresses("192.168.1.10".parse().unwrap(), server_ip);
+
+    let request = session.options_request();
+    let challenge = decode_challenge(request.headers.get("Apple-Challenge").unwrap()).unwrap();
+    let signed = generate_response(&receiver, &challenge, &server_ip, &mac).unwrap();
+
+    session
+        .process_response(Method::Options, &options_response(&signed))
+        .unwrap();
+    assert!(session.is_authenticated());
+
+    // The announced AES key is encrypted for the receiver's key
+    let sdp = session.prepare_announce().unwrap();
+    assert!(sdp.contains("o=iTunes"));
+    assert!(sdp.contains("IN IP4 192.168.1.10"));
+    let keys = session.session_keys().unwrap();
+    let (key, iv) = parse_session_keys(&keys.rsaaeskey(), &keys.aesiv(), &receiver).unwrap();
+    assert_eq!(&key, keys.aes_key());
+    assert_eq!(&iv, keys.aes_iv());
+}
+
+#[test]
+fn test_apple_response_rejected() {
+    use crate::protocol::crypto::{AppleRsaPublicKey, RaopRsaPrivateKey};
+
+    let receiver = RaopRsaPrivateKey::generate().unwrap();
+    let server_ip: std::net::IpAddr = "192.168.1.50".parse().unwrap();
+    let mac = [0x00, 0x50, 0xC2, 0x12, 0xA2, 0x3F];
+
+    let mut session = RaopRtspSession::new("192.168.1.50", 5000);
+    session.set_receiver_key(AppleRsaPublicKey::from(receiver.public_key()));
+    session.set_mac_address(mac);
+    session.set_addresses("192.168.1.10".parse().unwrap(), server_ip);
+
+    let request = session.options_request();
+    let challenge = decode_challenge(request.headers.get("Apple-Challenge").unwrap()).unwrap();
+    // Signed for a different hardware address
+    let signed = generate_response(&receiver, &challenge, &server_ip, &[0xFF; 6]).unwrap();
+
+    assert!(
+        session
+            .process_response(Method::Options, &options_response(&signed))
+            .is_err()
+    );
+    assert!(!session.is_authenticated());
+}
+
+#[test]
+fn test_apple_response_unverified_without_mac() {
+    let mut session = RaopRtspSession::new("192.168.1.50", 5000);
+    session.set_addresses(
+        "192.168.1.10".parse().unwrap(),
+        "192.168.1.50".parse().unwrap(),
+    );
+    session.options_request();
+
+    session
+        .process_response(Method::Options, &options_response("bm90IGEgc2lnbmF0dXJl"))
+        .unwrap();
+    assert_eq!(session.state(), RaopSessionState::OptionsExchange);
+    assert!(!session.is_authenticated());
+}
+
+fn unauthorized(nonce: &str) -> RtspResponse {
+    let mut headers = Headers::new();
+    headers.insert(
+        "WWW-Authenticate",
+        format!(r#"Digest realm="raop", nonce="{nonce}""#),
+    );
+    RtspResponse {
+        version: "RTSP/1.0".to_string(),
+        status: StatusCode::UNAUTHORIZED,
+        reason: "Unauthorized".to_string(),
+        headers,
+        body: Vec::new(),
+    }
+}
+
+#[test]
+fn test_digest_challenge_retry() {
+    let mut session = RaopRtspSession::new("192.168.1.50", 5000);
+    session.set_credentials(DigestCredentials::new("secret"));
+
+    let request = session.options_request();
+    assert!(request.headers.get("Authorization").is_none());
+
+    assert!(session.accept_challenge(&unauthorized("n1")));
+    let retry = session.retry_request(&request);
+    assert_ne!(retry.headers.cseq(), request.headers.cseq());
+    let authorization = retry.headers.get("Authorization").unwrap();
+    assert!(authorization.contains("nonce=\"n1\""));
+
+    // Later requests carry credentials up front
+    let mut announce = session.announce_request("v=0\r\n");
+    session.authorize(&mut announce);
+    assert!(announce.headers.get("Authorization").is_some());
+
+    // The same nonce again means the password was wrong
+    assert!(!session.accept_challenge(&unauthorized("n1")));
+    assert!(session.accept_challenge(&unauthorized("n2")));
+}
+
+#[test]
+fn test_digest_challenge_without_credentials() {
+    let mut session = RaopRtspSession::new("192.168.1.50", 5000);
+    assert!(!session.accept_challenge(&unauthorized("n1")));
+
+    let mut request = session.options_request();
+    session.authorize(&mut request);
+    assert!(request.headers.get("Authorization").is_none());
+}
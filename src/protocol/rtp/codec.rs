@@ -128,6 +128,15 @@ impl RtpCodec {
         self.nonce_counter = 0;
     }
 
+    /// Give up the next packet's sequence number and timestamp
+    ///
+    /// Keeps later packets on the stream's clock when one is dropped; the
+    /// receiver sees a lost packet.
+    pub fn skip_packet(&mut self) {
+        self.sequence = self.sequence.wrapping_add(1);
+        self.timestamp = self.timestamp.wrapping_add(self.frames_per_packet);
+    }
+
     /// Get current sequence number
     #[must_use]
     pub fn sequence(&self) -> u16 {
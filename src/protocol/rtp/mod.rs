@@ -2,6 +2,7 @@
 
 mod codec;
 mod control;
+#[cfg(feature = "tokio-runtime")]
 pub mod ntp_client;
 mod packet;
 pub mod packet_buffer;
@@ -169,6 +169,31 @@ impl RetransmitRequest {
             count: u16::from_be_bytes([buf[2], buf[3]]),
         })
     }
+
+    /// Decode a full control packet, including its 4-byte RTP header
+    ///
+    /// Returns `None` if the packet is not a retransmit request.
+    #[must_use]
+    pub fn from_packet(packet: &[u8]) -> Option<Self> {
+        if packet.len() < Self::SIZE
+            || RaopPayloadType::from_byte(packet[1]) != Some(RaopPayloadType::RetransmitRequest)
+        {
+            return None;
+        }
+        Self::decode(&packet[4..]).ok()
+    }
+
+    /// Encode as a full control packet
+    #[must_use]
+    pub fn encode(&self, sequence: u16) -> [u8; Self::SIZE] {
+        let mut buf = [0u8; Self::SIZE];
+        buf[0] = 0x80;
+        buf[1] = 0x80 | RaopPayloadType::RetransmitRequest as u8;
+        buf[2..4].copy_from_slice(&sequence.to_be_bytes());
+        buf[4..6].copy_from_slice(&self.seq_start.to_be_bytes());
+        buf[6..8].copy_from_slice(&self.count.to_be_bytes());
+        buf
+    }
 }
 
 /// RAOP audio packet with header
@@ -32,6 +32,14 @@ fn test_codec_timestamp_increment() {
     assert_eq!(codec.timestamp(), 704);
 }
 
+#[test]
+fn test_codec_skip_packet() {
+    let mut codec = RtpCodec::new(0x1234_5678);
+    codec.skip_packet();
+    assert_eq!(codec.sequence(), 1);
+    assert_eq!(codec.timestamp(), 352);
+}
+
 #[test]
 fn test_codec_invalid_audio_size() {
     let mut codec = RtpCodec::new(0);
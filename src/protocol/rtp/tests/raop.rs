@@ -137,3 +137,26 @@ fn test_loss_detector() {
     let missing = detector.process(106);
     assert_eq!(missing, vec![104, 105]);
 }
+
+#[test]
+fn test_retransmit_request_packet_roundtrip() {
+    let request = RetransmitRequest {
+        seq_start: 0xFFFE,
+        count: 3,
+    };
+    let packet = request.encode(7);
+
+    assert_eq!(packet[0], 0x80);
+    assert_eq!(
+        RaopPayloadType::from_byte(packet[1]),
+        Some(RaopPayloadType::RetransmitRequest)
+    );
+    let decoded = RetransmitRequest::from_packet(&packet).unwrap();
+    assert_eq!(decoded.seq_start, 0xFFFE);
+    assert_eq!(decoded.count, 3);
+
+    // Not a retransmit request
+    let sync = SyncPacket::new(0, NtpTimestamp::now(), 352, false).encode();
+    assert!(RetransmitRequest::from_packet(&sync).is_none());
+    assert!(RetransmitRequest::from_packet(&packet[..4]).is_none());
+}
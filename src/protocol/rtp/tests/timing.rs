@@ -111,3 +111,52 @@ fn test_offset_calculation() {
 
     assert!((offset - expected).abs() < tolerance, "Offset was {offset}");
 }
+
+#[test]
+fn test_raop_timing_request_answer() {
+    use crate::protocol::rtp::TimingPacket;
+    use crate::protocol::rtp::raop_timing::RaopTimingRequest;
+
+    let encoded = RaopTimingRequest::new().encode(9);
+    let TimingPacket::Request(request) = TimingPacket::decode_raop(&encoded).unwrap() else {
+        panic!("expected a timing request");
+    };
+
+    let received = NtpTimestamp::now();
+    let response = TimingResponse::answer(&request, received).encode_raop(9);
+    assert_eq!(response.len(), TimingPacket::RAOP_SIZE);
+    assert_eq!(response[1], 0xD3);
+    assert_eq!(&response[2..4], &9u16.to_be_bytes());
+
+    let TimingPacket::Response(decoded) = TimingPacket::decode_raop(&response).unwrap() else {
+        panic!("expected a timing response");
+    };
+    assert_eq!(decoded.reference_time.encode(), request.send_time.encode());
+    assert_eq!(decoded.receive_time.encode(), received.encode());
+}
+
+#[test]
+fn test_raop_timing_decode_rejects_other_packets() {
+    use crate::protocol::rtp::{RtpDecodeError, TimingPacket};
+
+    assert!(matches!(
+        TimingPacket::decode_raop(&[0x80, 0xD2, 0, 1]),
+        Err(RtpDecodeError::BufferTooSmall { .. })
+    ));
+    let mut sync = [0u8; 32];
+    sync[0] = 0x80;
+    sync[1] = 0xD4;
+    assert!(matches!(
+        TimingPacket::decode_raop(&sync),
+        Err(RtpDecodeError::UnknownPayloadType(0x54))
+    ));
+}
+
+#[test]
+fn test_ntp_timestamp_from_micros() {
+    let micros = 3_900_000_000_123_456;
+    let ts = NtpTimestamp::from_micros(micros);
+    assert_eq!(ts.seconds, 3_900_000_000);
+    // Fraction round trip loses at most a microsecond
+    assert!(micros - ts.to_micros() <= 1);
+}
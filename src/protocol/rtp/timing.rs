@@ -62,6 +62,24 @@ impl NtpTimestamp {
         let frac_micros = (u64::from(self.fraction) * 1_000_000) >> 32;
         secs * 1_000_000 + frac_micros
     }
+
+    /// Create from microseconds since NTP epoch
+    #[must_use]
+    pub fn from_micros(micros: u64) -> Self {
+        let fraction = ((micros % 1_000_000) << 32) / 1_000_000;
+        Self {
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "NTP timestamp seconds wrap around in 2036 (Year 2038 problem)"
+            )]
+            seconds: (micros / 1_000_000) as u32,
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "Fractional part calculation fits within u32"
+            )]
+            fraction: fraction as u32,
+        }
+    }
 }
 
 /// Timing request packet
@@ -150,6 +168,30 @@ impl TimingResponse {
         })
     }
 
+    /// Answer a timing request received at `receive_time`
+    #[must_use]
+    pub fn answer(request: &TimingRequest, receive_time: NtpTimestamp) -> Self {
+        Self {
+            reference_time: request.send_time,
+            receive_time,
+            send_time: NtpTimestamp::now(),
+        }
+    }
+
+    /// Encode as a RAOP timing response (8-byte header, no SSRC)
+    #[must_use]
+    pub fn encode_raop(&self, sequence: u16) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(TimingPacket::RAOP_SIZE);
+        buf.push(0x80); // V=2
+        buf.push(0xD3); // M=1, PT=0x53
+        buf.extend_from_slice(&sequence.to_be_bytes());
+        buf.extend_from_slice(&[0u8; 4]); // Timestamp (not used)
+        buf.extend_from_slice(&self.reference_time.encode());
+        buf.extend_from_slice(&self.receive_time.encode());
+        buf.extend_from_slice(&self.send_time.encode());
+        buf
+    }
+
     /// Calculate clock offset (server time - client time)
     ///
     /// Returns offset in microseconds
@@ -196,3 +238,40 @@ pub enum TimingPacket {
     Request(TimingRequest),
     Response(TimingResponse),
 }
+
+impl TimingPacket {
+    /// RAOP timing packet size (8-byte header, three timestamps)
+    pub const RAOP_SIZE: usize = 32;
+
+    /// Decode a RAOP timing packet, including its 8-byte header
+    ///
+    /// # Errors
+    ///
+    /// Returns `RtpDecodeError` if the buffer is too small or the payload
+    /// type is not a timing request or response
+    pub fn decode_raop(buf: &[u8]) -> Result<Self, super::packet::RtpDecodeError> {
+        if buf.len() < Self::RAOP_SIZE {
+            return Err(super::packet::RtpDecodeError::BufferTooSmall {
+                needed: Self::RAOP_SIZE,
+                have: buf.len(),
+            });
+        }
+
+        let reference_time = NtpTimestamp::decode(&buf[8..16]);
+        let receive_time = NtpTimestamp::decode(&buf[16..24]);
+        let send_time = NtpTimestamp::decode(&buf[24..32]);
+        match buf[1] & 0x7F {
+            0x52 => Ok(Self::Request(TimingRequest {
+                reference_time,
+                receive_time,
+                send_time,
+            })),
+            0x53 => Ok(Self::Response(TimingResponse {
+                reference_time,
+                receive_time,
+                send_time,
+            })),
+            other => Err(super::packet::RtpDecodeError::UnknownPayloadType(other)),
+        }
+    }
+}
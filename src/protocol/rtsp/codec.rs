@@ -1,5 +1,8 @@
+use std::collections::VecDeque;
+
 use thiserror::Error;
 
+use super::interleaved::{self, InterleavedFrame};
 use super::{Headers, RtspResponse, StatusCode};
 
 /// Errors during RTSP parsing
@@ -19,15 +22,23 @@ pub enum RtspCodecError {
 
     #[error("response too large: {size} bytes")]
     ResponseTooLarge { size: usize },
+
+    #[error("interleaved payload too large: {size} bytes")]
+    InterleavedTooLarge { size: usize },
 }
 
 /// Sans-IO RTSP codec for parsing responses
 ///
 /// This codec handles incremental parsing of RTSP responses.
 /// Feed bytes with `feed()`, check for complete responses with `decode()`.
+///
+/// Interleaved binary frames between responses are set aside as they are
+/// reached and taken with `decode_interleaved()`.
 pub struct RtspCodec {
     /// Internal buffer for partial data
     buffer: Vec<u8>,
+    /// Interleaved frames parsed but not yet taken
+    frames: VecDeque<InterleavedFrame>,
     /// Maximum response size (default 1MB)
     max_size: usize,
     /// Parser state
@@ -60,6 +71,7 @@ impl RtspCodec {
     pub fn new() -> Self {
         Self {
             buffer: Vec::with_capacity(4096),
+            frames: VecDeque::new(),
             max_size: 1024 * 1024, // 1MB
             state: ParseState::StatusLine,
         }
@@ -97,6 +109,12 @@ impl RtspCodec {
         loop {
             match &self.state {
                 ParseState::StatusLine => {
+                    if self.buffer.first() == Some(&interleaved::MAGIC) {
+                        if !self.take_frame() {
+                            return Ok(None);
+                        }
+                        continue;
+                    }
                     if let Some(line_end) = self.find_line_end() {
                         let line = String::from_utf8_lossy(&self.buffer[..line_end]).to_string();
                         let (version, status, reason) = Self::parse_status_line(&line)?;
@@ -177,9 +195,21 @@ impl RtspCodec {
         }
     }
 
+    /// Take the next interleaved frame
+    ///
+    /// Frames are returned in the order they arrived. Ones that follow a
+    /// response are reached once that response has been decoded.
+    pub fn decode_interleaved(&mut self) -> Option<InterleavedFrame> {
+        if self.frames.is_empty() && matches!(self.state, ParseState::StatusLine) {
+            while self.take_frame() {}
+        }
+        self.frames.pop_front()
+    }
+
     /// Clear the codec buffer and reset state
     pub fn reset(&mut self) {
         self.buffer.clear();
+        self.frames.clear();
         self.state = ParseState::StatusLine;
     }
 
@@ -191,6 +221,16 @@ impl RtspCodec {
 
     // Helper methods
 
+    /// Move a complete interleaved frame from the buffer to the queue
+    fn take_frame(&mut self) -> bool {
+        let Some((frame, len)) = InterleavedFrame::parse(&self.buffer) else {
+            return false;
+        };
+        self.buffer.drain(..len);
+        self.frames.push_back(frame);
+        true
+    }
+
     fn find_line_end(&self) -> Option<usize> {
         self.buffer.windows(2).position(|w| w == b"\r\n")
     }
@@ -0,0 +1,129 @@
+//! Demultiplexing of the client side of an RTSP control connection
+//!
+//! `AirPlay` 2 receivers send their own requests (SETPEERS updates,
+//! FLUSHBUFFERED, event notifications) on the connection the sender uses
+//! for its requests, so a message read from it may be either a response or
+//! a request. The demuxer frames complete messages and decodes each with
+//! the matching codec. With a TCP transport, interleaved RTP and RTCP
+//! frames arrive between them.
+
+use super::interleaved::{self, InterleavedFrame};
+use super::{RtspCodec, RtspCodecError, RtspRequest, RtspResponse, RtspServerCodec};
+
+/// A message read from the control connection
+#[derive(Debug, Clone)]
+pub enum RtspMessage {
+    /// Response to one of our requests
+    Response(RtspResponse),
+    /// Request sent by the receiver
+    Request(RtspRequest),
+    /// Request that could not be decoded (e.g. an unknown method)
+    Unsupported {
+        /// Request line as received
+        request_line: String,
+        /// `CSeq` of the request, for answering it
+        cseq: Option<u32>,
+    },
+    /// Binary frame on an interleaved channel
+    Interleaved(InterleavedFrame),
+}
+
+/// Sans-IO demuxer for responses and server-initiated requests
+pub struct RtspDemuxer {
+    buffer: Vec<u8>,
+    max_size: usize,
+}
+
+impl RtspDemuxer {
+    /// Create a new demuxer
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            buffer: Vec::with_capacity(4096),
+            max_size: 1024 * 1024,
+        }
+    }
+
+    /// Feed bytes read from the connection
+    ///
+    /// # Errors
+    /// Returns `RtspCodecError::ResponseTooLarge` if the buffer exceeds the
+    /// maximum message size.
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<(), RtspCodecError> {
+        if self.buffer.len() + bytes.len() > self.max_size {
+            return Err(RtspCodecError::ResponseTooLarge {
+                size: self.buffer.len() + bytes.len(),
+            });
+        }
+        self.buffer.extend_from_slice(bytes);
+        Ok(())
+    }
+
+    /// Try to decode the next complete message
+    ///
+    /// # Errors
+    /// Returns `RtspCodecError` if a response is malformed or a header
+    /// cannot be framed.
+    pub fn decode(&mut self) -> Result<Option<RtspMessage>, RtspCodecError> {
+        if self.buffer.first() == Some(&interleaved::MAGIC) {
+            let Some((frame, len)) = InterleavedFrame::parse(&self.buffer) else {
+                return Ok(None);
+            };
+            self.buffer.drain(..len);
+            return Ok(Some(RtspMessage::Interleaved(frame)));
+        }
+
+        let Some(header_end) = self.buffer.windows(4).position(|w| w == b"\r\n\r\n") else {
+            return Ok(None);
+        };
+
+        let head = String::from_utf8_lossy(&self.buffer[..header_end]).to_string();
+        let content_length = header_value(&head, "Content-Length")
+            .map(str::parse::<usize>)
+            .transpose()
+            .map_err(|_| RtspCodecError::InvalidContentLength)?
+            .unwrap_or(0);
+
+        let total = header_end + 4 + content_length;
+        if self.buffer.len() < total {
+            return Ok(None);
+        }
+        let message: Vec<u8> = self.buffer.drain(..total).collect();
+
+        let start_line = head.lines().next().unwrap_or_default();
+        if start_line.starts_with("RTSP/") || start_line.starts_with("HTTP/") {
+            let mut codec = RtspCodec::new();
+            codec.feed(&message)?;
+            return codec
+                .decode()?
+                .map(RtspMessage::Response)
+                .ok_or(RtspCodecError::Incomplete)
+                .map(Some);
+        }
+
+        let mut codec = RtspServerCodec::new();
+        codec.feed(&message);
+        Ok(Some(match codec.decode() {
+            Ok(Some(request)) => RtspMessage::Request(request),
+            Ok(None) | Err(_) => RtspMessage::Unsupported {
+                request_line: start_line.to_string(),
+                cseq: header_value(&head, "CSeq").and_then(|v| v.parse().ok()),
+            },
+        }))
+    }
+}
+
+impl Default for RtspDemuxer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn header_value<'a>(head: &'a str, name: &str) -> Option<&'a str> {
+    head.lines().skip(1).find_map(|line| {
+        let (key, value) = line.split_once(':')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then_some(value.trim())
+    })
+}
@@ -1,5 +1,8 @@
 use std::collections::HashMap;
 
+use super::Method;
+use super::transport::{TransportHeader, TransportParseError};
+
 /// Well-known RTSP header names
 pub mod names {
     pub const CSEQ: &str = "CSeq";
@@ -7,6 +10,7 @@ pub mod names {
     pub const CONTENT_LENGTH: &str = "Content-Length";
     pub const SESSION: &str = "Session";
     pub const TRANSPORT: &str = "Transport";
+    pub const PUBLIC: &str = "Public";
     pub const USER_AGENT: &str = "User-Agent";
     pub const ACTIVE_REMOTE: &str = "Active-Remote";
     pub const DACP_ID: &str = "DACP-ID";
@@ -36,6 +40,10 @@ pub mod raop {
     pub const SERVER: &str = "Server";
     /// Range header for RECORD
     pub const RANGE: &str = "Range";
+    /// Digest challenge from a password-protected receiver
+    pub const WWW_AUTHENTICATE: &str = "WWW-Authenticate";
+    /// Digest credentials
+    pub const AUTHORIZATION: &str = "Authorization";
 }
 
 /// RTSP header collection
@@ -102,6 +110,59 @@ impl Headers {
         self.get(names::SESSION)
     }
 
+    /// Get the Session header split into ID and timeout
+    #[must_use]
+    pub fn session_header(&self) -> Option<SessionHeader> {
+        SessionHeader::parse(self.session()?)
+    }
+
+    /// Get the parsed Transport header
+    ///
+    /// # Errors
+    ///
+    /// Returns `TransportParseError` if the header is present but invalid.
+    pub fn transport(&self) -> Result<Option<TransportHeader>, TransportParseError> {
+        self.get(names::TRANSPORT)
+            .map(TransportHeader::parse)
+            .transpose()
+    }
+
+    /// Get the methods listed in the Public header
+    #[must_use]
+    pub fn public(&self) -> Option<PublicMethods> {
+        self.get(names::PUBLIC).map(PublicMethods::parse)
+    }
+
+    /// Get Audio-Latency, in samples
+    #[must_use]
+    pub fn audio_latency(&self) -> Option<u32> {
+        self.get(raop::AUDIO_LATENCY)?.trim().parse().ok()
+    }
+
+    /// Get the Active-Remote token a DACP client must present
+    #[must_use]
+    pub fn active_remote(&self) -> Option<u32> {
+        self.get(names::ACTIVE_REMOTE)?.trim().parse().ok()
+    }
+
+    /// Get the DACP-ID, sent as hex
+    #[must_use]
+    pub fn dacp_id(&self) -> Option<u64> {
+        u64::from_str_radix(self.get(names::DACP_ID)?.trim(), 16).ok()
+    }
+
+    /// Get the Base64 Apple-Challenge
+    #[must_use]
+    pub fn apple_challenge(&self) -> Option<&str> {
+        self.get(raop::APPLE_CHALLENGE)
+    }
+
+    /// Get the Base64 Apple-Response
+    #[must_use]
+    pub fn apple_response(&self) -> Option<&str> {
+        self.get(raop::APPLE_RESPONSE)
+    }
+
     /// Iterate over all headers
     pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
         self.inner.iter().map(|(k, v)| (k.as_str(), v.as_str()))
@@ -129,3 +190,87 @@ impl FromIterator<(String, String)> for Headers {
         headers
     }
 }
+
+/// Session header value: an ID, optionally followed by `;timeout=<seconds>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionHeader {
+    /// Session ID
+    pub id: String,
+    /// Seconds the server keeps the session without requests
+    pub timeout: Option<u32>,
+}
+
+impl SessionHeader {
+    /// Session ID without a timeout
+    #[must_use]
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            timeout: None,
+        }
+    }
+
+    /// Parse a Session header value
+    ///
+    /// Returns `None` if the ID is empty. An unreadable timeout is ignored.
+    #[must_use]
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut parts = value.split(';');
+        let id = parts.next()?.trim();
+        if id.is_empty() {
+            return None;
+        }
+        let timeout = parts.find_map(|part| part.trim().strip_prefix("timeout=")?.parse().ok());
+        Some(Self {
+            id: id.to_string(),
+            timeout,
+        })
+    }
+}
+
+impl std::fmt::Display for SessionHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.id)?;
+        if let Some(timeout) = self.timeout {
+            write!(f, ";timeout={timeout}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Methods listed in an OPTIONS response's Public header
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublicMethods(pub Vec<Method>);
+
+impl PublicMethods {
+    /// Parse a comma-separated Public header value
+    ///
+    /// Methods this crate does not know are skipped.
+    #[must_use]
+    pub fn parse(value: &str) -> Self {
+        Self(
+            value
+                .split(',')
+                .filter_map(|method| method.trim().parse().ok())
+                .collect(),
+        )
+    }
+
+    /// Check whether `method` is listed
+    #[must_use]
+    pub fn contains(&self, method: Method) -> bool {
+        self.0.contains(&method)
+    }
+}
+
+impl std::fmt::Display for PublicMethods {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, method) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(method.as_str())?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,61 @@
+//! Interleaved binary data on the RTSP connection
+//!
+//! With a `RTP/AVP/TCP;interleaved=0-1` transport, RTP and RTCP packets
+//! travel on the RTSP connection itself instead of UDP, which gets audio
+//! through networks that block UDP. Each packet is framed as `$`, a channel
+//! byte and a big-endian 16-bit length (RFC 2326 §10.12). By convention the
+//! even channel of the negotiated pair carries RTP and the odd one RTCP.
+
+use super::RtspCodecError;
+
+/// Byte that starts an interleaved frame
+pub const MAGIC: u8 = b'$';
+
+/// Size of the `$`, channel and length prefix
+pub const HEADER_LEN: usize = 4;
+
+/// Largest payload a frame can carry
+pub const MAX_PAYLOAD: usize = u16::MAX as usize;
+
+/// A binary packet sent on the RTSP connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InterleavedFrame {
+    /// Channel from the Transport header's `interleaved` pair
+    pub channel: u8,
+    /// RTP or RTCP packet
+    pub payload: Vec<u8>,
+}
+
+impl InterleavedFrame {
+    /// Parse a frame at the start of `buffer`
+    ///
+    /// Returns the frame and the bytes it took, or `None` if the buffer does
+    /// not start with `$` or the frame is not complete yet.
+    #[must_use]
+    pub fn parse(buffer: &[u8]) -> Option<(Self, usize)> {
+        let [MAGIC, channel, high, low, ..] = *buffer else {
+            return None;
+        };
+        let total = HEADER_LEN + usize::from(u16::from_be_bytes([high, low]));
+        let payload = buffer.get(HEADER_LEN..total)?.to_vec();
+        Some((Self { channel, payload }, total))
+    }
+
+    /// Encode to wire format
+    ///
+    /// # Errors
+    /// Returns `RtspCodecError::InterleavedTooLarge` if the payload does not
+    /// fit the 16-bit length.
+    pub fn encode(&self) -> Result<Vec<u8>, RtspCodecError> {
+        let len =
+            u16::try_from(self.payload.len()).map_err(|_| RtspCodecError::InterleavedTooLarge {
+                size: self.payload.len(),
+            })?;
+        let mut frame = Vec::with_capacity(HEADER_LEN + self.payload.len());
+        frame.push(MAGIC);
+        frame.push(self.channel);
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(&self.payload);
+        Ok(frame)
+    }
+}
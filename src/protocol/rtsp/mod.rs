@@ -4,7 +4,9 @@
 #![allow(dead_code)]
 
 pub mod codec;
+pub mod demux;
 pub mod headers;
+pub mod interleaved;
 pub mod request;
 pub mod response;
 pub mod server_codec;
@@ -15,11 +17,13 @@ pub mod transport;
 mod tests;
 
 pub use codec::{RtspCodec, RtspCodecError};
+pub use demux::{RtspDemuxer, RtspMessage};
 pub use headers::Headers;
+pub use interleaved::InterleavedFrame;
 pub use request::{RtspRequest, RtspRequestBuilder};
 pub use response::{RtspResponse, StatusCode};
 pub use server_codec::{RtspServerCodec, encode_response};
-pub use session::{RtspSession, SessionState};
+pub use session::{RtspSession, SessionState, SessionStream};
 
 /// RTSP methods used in `AirPlay`
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -38,6 +42,8 @@ pub enum Method {
     Pause,
     /// Flush buffers
     Flush,
+    /// Flush a buffered (`AirPlay` 2 type 103) stream
+    FlushBuffered,
     /// Tear down session
     Teardown,
     /// Set parameter (volume, progress, etc.)
@@ -66,6 +72,7 @@ impl Method {
             Method::Play => "PLAY",
             Method::Pause => "PAUSE",
             Method::Flush => "FLUSH",
+            Method::FlushBuffered => "FLUSHBUFFERED",
             Method::Teardown => "TEARDOWN",
             Method::SetParameter => "SET_PARAMETER",
             Method::GetParameter => "GET_PARAMETER",
@@ -89,6 +96,7 @@ impl std::str::FromStr for Method {
             "PLAY" => Ok(Method::Play),
             "PAUSE" => Ok(Method::Pause),
             "FLUSH" => Ok(Method::Flush),
+            "FLUSHBUFFERED" => Ok(Method::FlushBuffered),
             "TEARDOWN" => Ok(Method::Teardown),
             "SET_PARAMETER" => Ok(Method::SetParameter),
             "GET_PARAMETER" => Ok(Method::GetParameter),
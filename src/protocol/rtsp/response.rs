@@ -1,10 +1,14 @@
 use super::Headers;
 
 /// RTSP status codes
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// Any numeric value is kept, so codes without a constant here (including
+/// vendor-specific ones) pass through unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct StatusCode(pub u16);
 
 impl StatusCode {
+    pub const CONTINUE: StatusCode = StatusCode(100);
     pub const OK: StatusCode = StatusCode(200);
     pub const BAD_REQUEST: StatusCode = StatusCode(400);
     pub const UNAUTHORIZED: StatusCode = StatusCode(401);
@@ -12,11 +16,72 @@ impl StatusCode {
     pub const NOT_FOUND: StatusCode = StatusCode(404);
     pub const METHOD_NOT_ALLOWED: StatusCode = StatusCode(405);
     pub const NOT_ACCEPTABLE: StatusCode = StatusCode(406);
+    pub const REQUEST_TIMEOUT: StatusCode = StatusCode(408);
+    pub const UNSUPPORTED_MEDIA_TYPE: StatusCode = StatusCode(415);
+    pub const PARAMETER_NOT_UNDERSTOOD: StatusCode = StatusCode(451);
+    pub const NOT_ENOUGH_BANDWIDTH: StatusCode = StatusCode(453);
     pub const SESSION_NOT_FOUND: StatusCode = StatusCode(454);
     pub const METHOD_NOT_VALID: StatusCode = StatusCode(455);
+    pub const INVALID_RANGE: StatusCode = StatusCode(457);
+    pub const AGGREGATE_OPERATION_NOT_ALLOWED: StatusCode = StatusCode(459);
+    pub const ONLY_AGGREGATE_OPERATION_ALLOWED: StatusCode = StatusCode(460);
+    pub const UNSUPPORTED_TRANSPORT: StatusCode = StatusCode(461);
+    /// Sent by `AirPlay` receivers when a password or PIN is required
+    pub const CONNECTION_AUTHORIZATION_REQUIRED: StatusCode = StatusCode(470);
     pub const INTERNAL_ERROR: StatusCode = StatusCode(500);
     pub const NOT_IMPLEMENTED: StatusCode = StatusCode(501);
+    pub const BAD_GATEWAY: StatusCode = StatusCode(502);
     pub const SERVICE_UNAVAILABLE: StatusCode = StatusCode(503);
+    pub const VERSION_NOT_SUPPORTED: StatusCode = StatusCode(505);
+    pub const OPTION_NOT_SUPPORTED: StatusCode = StatusCode(551);
+
+    /// Standard reason phrase, if the code is known
+    #[must_use]
+    pub fn reason_phrase(self) -> Option<&'static str> {
+        Some(match self.0 {
+            100 => "Continue",
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            403 => "Forbidden",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            406 => "Not Acceptable",
+            408 => "Request Timeout",
+            415 => "Unsupported Media Type",
+            451 => "Parameter Not Understood",
+            453 => "Not Enough Bandwidth",
+            454 => "Session Not Found",
+            455 => "Method Not Valid in This State",
+            457 => "Invalid Range",
+            459 => "Aggregate Operation Not Allowed",
+            460 => "Only Aggregate Operation Allowed",
+            461 => "Unsupported Transport",
+            470 => "Connection Authorization Required",
+            500 => "Internal Server Error",
+            501 => "Not Implemented",
+            502 => "Bad Gateway",
+            503 => "Service Unavailable",
+            505 => "RTSP Version Not Supported",
+            551 => "Option Not Supported",
+            _ => return None,
+        })
+    }
+
+    /// Check if the receiver requires authentication (401, 470)
+    #[must_use]
+    pub fn is_auth_required(self) -> bool {
+        matches!(
+            self,
+            Self::UNAUTHORIZED | Self::CONNECTION_AUTHORIZATION_REQUIRED
+        )
+    }
+
+    /// Check if the receiver no longer knows the session (454)
+    #[must_use]
+    pub fn is_session_invalid(self) -> bool {
+        self == Self::SESSION_NOT_FOUND
+    }
 
     /// Check if this is a success status (2xx)
     #[must_use]
@@ -36,6 +101,17 @@ impl StatusCode {
         self.0 >= 500 && self.0 < 600
     }
 
+    /// Check if the receiver rejected a request as invalid in its current
+    /// state
+    ///
+    /// 455 (Method Not Valid in This State), and the 499 some receivers
+    /// answer with instead. The session state has diverged from the
+    /// receiver's; setting the stream up again resynchronizes it.
+    #[must_use]
+    pub fn is_state_error(self) -> bool {
+        matches!(self.0, 455 | 499)
+    }
+
     /// Get status code as u16
     #[must_use]
     pub fn as_u16(self) -> u16 {
@@ -43,6 +119,21 @@ impl StatusCode {
     }
 }
 
+impl From<u16> for StatusCode {
+    fn from(code: u16) -> Self {
+        Self(code)
+    }
+}
+
+impl std::fmt::Display for StatusCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.reason_phrase() {
+            Some(reason) => write!(f, "{} {reason}", self.0),
+            None => write!(f, "{}", self.0),
+        }
+    }
+}
+
 /// An RTSP response message
 #[derive(Debug, Clone)]
 pub struct RtspResponse {
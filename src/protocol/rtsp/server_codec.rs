@@ -4,10 +4,14 @@
 //! parsing. Both share the same request/response types but differ in what
 //! they parse vs. generate.
 
+use std::collections::VecDeque;
 use std::str::{self, FromStr};
 
 use bytes::{Buf, BytesMut};
 
+use super::headers::{PublicMethods, SessionHeader, names, raop};
+use super::interleaved::{self, InterleavedFrame};
+use super::transport::TransportHeader;
 use super::{Headers, Method, RtspRequest, RtspResponse, StatusCode};
 
 /// Errors during RTSP parsing
@@ -53,6 +57,9 @@ const MAX_HEADER_SIZE: usize = 64 * 1024;
 /// - `decode()` attempts to parse a complete request
 /// - `encode_response()` generates response bytes
 ///
+/// Interleaved binary frames (RTP over the RTSP connection) between requests
+/// are set aside as they are reached and taken with `decode_interleaved()`.
+///
 /// # Example
 ///
 /// ```rust
@@ -70,6 +77,8 @@ const MAX_HEADER_SIZE: usize = 64 * 1024;
 /// ```
 pub struct RtspServerCodec {
     buffer: BytesMut,
+    /// Interleaved frames parsed but not yet taken
+    frames: VecDeque<InterleavedFrame>,
 }
 
 impl RtspServerCodec {
@@ -78,6 +87,7 @@ impl RtspServerCodec {
     pub fn new() -> Self {
         Self {
             buffer: BytesMut::with_capacity(4096),
+            frames: VecDeque::new(),
         }
     }
 
@@ -95,6 +105,7 @@ impl RtspServerCodec {
     /// Clear the buffer
     pub fn clear(&mut self) {
         self.buffer.clear();
+        self.frames.clear();
     }
 
     /// Attempt to decode a complete RTSP request
@@ -107,6 +118,11 @@ impl RtspServerCodec {
     /// # Errors
     /// Returns `ParseError` if the request is malformed.
     pub fn decode(&mut self) -> Result<Option<RtspRequest>, ParseError> {
+        self.take_frames();
+        if self.buffer.first() == Some(&interleaved::MAGIC) {
+            return Ok(None); // Need the rest of the frame
+        }
+
         // Find header/body separator
         let Some(header_end) = self.find_header_end() else {
             // Check for header overflow
@@ -161,6 +177,24 @@ impl RtspServerCodec {
         }))
     }
 
+    /// Take the next interleaved frame
+    ///
+    /// Frames are returned in the order they arrived. Ones that follow a
+    /// request are reached once that request has been decoded.
+    pub fn decode_interleaved(&mut self) -> Option<InterleavedFrame> {
+        self.take_frames();
+        self.frames.pop_front()
+    }
+
+    /// Move complete interleaved frames at the front of the buffer to the
+    /// queue
+    fn take_frames(&mut self) {
+        while let Some((frame, len)) = InterleavedFrame::parse(&self.buffer) {
+            self.buffer.advance(len);
+            self.frames.push_back(frame);
+        }
+    }
+
     /// Find the position of header/body separator (\r\n\r\n)
     fn find_header_end(&self) -> Option<usize> {
         let needle = b"\r\n\r\n";
@@ -258,15 +292,34 @@ impl ResponseBuilder {
     /// Set the `CSeq` header (required - should match request)
     #[must_use]
     pub fn cseq(mut self, cseq: u32) -> Self {
-        self.headers.insert("CSeq".to_string(), cseq.to_string());
+        self.headers.insert(names::CSEQ, cseq.to_string());
         self
     }
 
     /// Set the Session header
     #[must_use]
-    pub fn session(mut self, session_id: &str) -> Self {
-        self.headers
-            .insert("Session".to_string(), session_id.to_string());
+    pub fn session(self, session_id: &str) -> Self {
+        self.session_header(&SessionHeader::new(session_id))
+    }
+
+    /// Set the Session header with its timeout
+    #[must_use]
+    pub fn session_header(mut self, session: &SessionHeader) -> Self {
+        self.headers.insert(names::SESSION, session.to_string());
+        self
+    }
+
+    /// Set the Transport header
+    #[must_use]
+    pub fn transport(mut self, transport: &TransportHeader) -> Self {
+        self.headers.insert(names::TRANSPORT, transport.to_string());
+        self
+    }
+
+    /// Set the Public header listing supported methods
+    #[must_use]
+    pub fn public(mut self, methods: &PublicMethods) -> Self {
+        self.headers.insert(names::PUBLIC, methods.to_string());
         self
     }
 
@@ -308,7 +361,7 @@ impl ResponseBuilder {
     #[must_use]
     pub fn audio_latency(mut self, samples: u32) -> Self {
         self.headers
-            .insert("Audio-Latency".to_string(), samples.to_string());
+            .insert(raop::AUDIO_LATENCY, samples.to_string());
         self
     }
 
@@ -324,7 +377,7 @@ impl ResponseBuilder {
         RtspResponse {
             version: "RTSP/1.0".to_string(),
             status: self.status,
-            reason: status_reason(self.status).to_string(),
+            reason: self.status.reason_phrase().unwrap_or("Unknown").to_string(),
             headers: self.headers,
             body: self.body.unwrap_or_default(),
         }
@@ -369,28 +422,3 @@ pub fn encode_response(response: &RtspResponse) -> Vec<u8> {
 
     output
 }
-
-/// Get reason phrase for status code
-fn status_reason(status: StatusCode) -> &'static str {
-    match status.as_u16() {
-        200 => "OK",
-        400 => "Bad Request",
-        401 => "Unauthorized",
-        403 => "Forbidden",
-        404 => "Not Found",
-        405 => "Method Not Allowed",
-        406 => "Not Acceptable",
-        451 => "Parameter Not Understood",
-        453 => "Not Enough Bandwidth",
-        454 => "Session Not Found",
-        455 => "Method Not Valid in This State",
-        457 => "Invalid Range",
-        459 => "Aggregate Operation Not Allowed",
-        460 => "Only Aggregate Operation Allowed",
-        461 => "Unsupported Transport",
-        500 => "Internal Server Error",
-        501 => "Not Implemented",
-        503 => "Service Unavailable",
-        _ => "Unknown",
-    }
-}
@@ -1,5 +1,6 @@
 use super::headers::names;
 use super::{Method, RtspRequest, RtspRequestBuilder, RtspResponse};
+use crate::protocol::plist::{DictBuilder, PlistValue};
 
 /// RTSP session states
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -18,6 +19,19 @@ pub enum SessionState {
     Terminated,
 }
 
+/// A stream set up within an `AirPlay` 2 session
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionStream {
+    /// `streamID` assigned by the receiver
+    pub id: u64,
+    /// Stream type (96 = realtime, 103 = buffered)
+    pub stream_type: u64,
+    /// Receiver data port
+    pub data_port: Option<u16>,
+    /// Receiver control port
+    pub control_port: Option<u16>,
+}
+
 /// RTSP session manager (sans-IO)
 ///
 /// Manages session state, `CSeq` numbering, and session ID tracking.
@@ -30,12 +44,18 @@ pub struct RtspSession {
     session_id: Option<String>,
     /// Our device ID
     device_id: String,
+    /// DACP-ID naming our `_dacp._tcp` service to the device
+    dacp_id: String,
+    /// Token the device sends back with DACP commands
+    active_remote: String,
     /// Our session ID (generated)
     client_session_id: String,
     /// Base URI for requests
     base_uri: String,
     /// User agent string
     user_agent: String,
+    /// Streams set up in this session, in SETUP order
+    streams: Vec<SessionStream>,
 }
 
 impl RtspSession {
@@ -46,6 +66,7 @@ impl RtspSession {
 
         let mut rng = rand::thread_rng();
         let device_id: u64 = rng.r#gen();
+        let dacp_id: u64 = rng.r#gen();
         let session_id: u64 = rng.r#gen();
 
         Self {
@@ -53,9 +74,12 @@ impl RtspSession {
             cseq: 0,
             session_id: None,
             device_id: format!("{device_id:016X}"),
+            dacp_id: format!("{dacp_id:016X}"),
+            active_remote: rng.r#gen::<u32>().to_string(),
             client_session_id: format!("{session_id:016X}"),
             base_uri: format!("rtsp://{device_address}:{port}"),
             user_agent: "AirPlay/540.31".to_string(),
+            streams: Vec::new(),
         }
     }
 
@@ -83,12 +107,136 @@ impl RtspSession {
         &self.device_id
     }
 
+    /// Get DACP-ID
+    ///
+    /// Random per session, and the suffix of the `iTunes_Ctrl_<DACP-ID>`
+    /// service the device looks up to send commands.
+    #[must_use]
+    pub fn dacp_id(&self) -> &str {
+        &self.dacp_id
+    }
+
+    /// Get Active-Remote token
+    ///
+    /// Random per session; the device returns it with each DACP command.
+    #[must_use]
+    pub fn active_remote(&self) -> &str {
+        &self.active_remote
+    }
+
     /// Get user agent
     #[must_use]
     pub fn user_agent(&self) -> &str {
         &self.user_agent
     }
 
+    /// Streams set up in this session, in SETUP order
+    #[must_use]
+    pub fn streams(&self) -> &[SessionStream] {
+        &self.streams
+    }
+
+    /// Look up a stream by `streamID`
+    #[must_use]
+    pub fn stream(&self, id: u64) -> Option<&SessionStream> {
+        self.streams.iter().find(|s| s.id == id)
+    }
+
+    /// The first stream set up (main audio)
+    #[must_use]
+    pub fn primary_stream(&self) -> Option<&SessionStream> {
+        self.streams.first()
+    }
+
+    /// Record the streams a SETUP response created
+    ///
+    /// `requested_types` are the stream types from the request, in order; they
+    /// fill in `type` when the receiver omits it. Streams without a `streamID`,
+    /// or responses without a `streams` array at all, are assigned the next
+    /// free ID. Returns the streams added.
+    pub fn record_setup_response(
+        &mut self,
+        requested_types: &[u64],
+        response: &PlistValue,
+    ) -> Vec<SessionStream> {
+        let Some(entries) = response
+            .as_dict()
+            .and_then(|dict| dict.get("streams"))
+            .and_then(PlistValue::as_array)
+        else {
+            // No stream details: assume the requested streams were created
+            return requested_types
+                .iter()
+                .map(|&stream_type| {
+                    let stream = SessionStream {
+                        id: self.next_stream_id(),
+                        stream_type,
+                        data_port: None,
+                        control_port: None,
+                    };
+                    self.streams.push(stream);
+                    stream
+                })
+                .collect();
+        };
+
+        let mut added = Vec::with_capacity(entries.len());
+        for (index, entry) in entries.iter().enumerate() {
+            let Some(dict) = entry.as_dict() else {
+                continue;
+            };
+            let port = |key: &str| {
+                dict.get(key)
+                    .and_then(PlistValue::as_u64)
+                    .and_then(|p| u16::try_from(p).ok())
+            };
+            let Some(stream_type) = dict
+                .get("type")
+                .and_then(PlistValue::as_u64)
+                .or_else(|| requested_types.get(index).copied())
+            else {
+                continue;
+            };
+            let id = dict
+                .get("streamID")
+                .and_then(PlistValue::as_u64)
+                .unwrap_or_else(|| self.next_stream_id());
+
+            let stream = SessionStream {
+                id,
+                stream_type,
+                data_port: port("dataPort"),
+                control_port: port("controlPort"),
+            };
+            self.streams.retain(|s| s.id != id);
+            self.streams.push(stream);
+            added.push(stream);
+        }
+        added
+    }
+
+    /// Forget a stream after it has been torn down
+    pub fn remove_stream(&mut self, id: u64) -> Option<SessionStream> {
+        let index = self.streams.iter().position(|s| s.id == id)?;
+        Some(self.streams.remove(index))
+    }
+
+    /// Make a stream the primary (main audio) stream
+    ///
+    /// Returns false if no such stream exists.
+    pub fn set_primary_stream(&mut self, id: u64) -> bool {
+        let Some(index) = self.streams.iter().position(|s| s.id == id) else {
+            return false;
+        };
+        let stream = self.streams.remove(index);
+        self.streams.insert(0, stream);
+        true
+    }
+
+    fn next_stream_id(&self) -> u64 {
+        self.streams.iter().map(|s| s.id + 1).max().unwrap_or(0)
+    }
+
     /// Get next `CSeq` and increment counter
     fn next_cseq(&mut self) -> u32 {
         self.cseq += 1;
@@ -108,8 +256,8 @@ impl RtspSession {
             .user_agent(&self.user_agent)
             .header(names::X_APPLE_DEVICE_ID, &self.device_id)
             .header(names::X_APPLE_SESSION_ID, &self.client_session_id)
-            .header(names::ACTIVE_REMOTE, "4294967295")
-            .header(names::DACP_ID, &self.device_id)
+            .header(names::ACTIVE_REMOTE, &self.active_remote)
+            .header(names::DACP_ID, &self.dacp_id)
             .header(names::CLIENT_INSTANCE, &self.device_id);
 
         if let Some(ref session) = self.session_id {
@@ -223,12 +371,67 @@ impl RtspSession {
             .build()
     }
 
+    /// Create FLUSHBUFFERED request
+    ///
+    /// Discards buffered audio of a type 103 stream up to (excluding)
+    /// `until_seq`/`until_timestamp`. `from` limits the flush to start at a
+    /// given sequence number and timestamp, for seeking within the buffer.
+    #[must_use]
+    pub fn flush_buffered_request(
+        &mut self,
+        stream_id: u64,
+        from: Option<(u32, u32)>,
+        until_seq: u32,
+        until_timestamp: u32,
+    ) -> RtspRequest {
+        let mut body = DictBuilder::new()
+            .insert("streamID", stream_id)
+            .insert("flushUntilSeq", u64::from(until_seq))
+            .insert("flushUntilTS", u64::from(until_timestamp));
+        if let Some((seq, timestamp)) = from {
+            body = body
+                .insert("flushFromSeq", u64::from(seq))
+                .insert("flushFromTS", u64::from(timestamp));
+        }
+
+        let path = format!("/{}", self.client_session_id);
+        self.request_builder(Method::FlushBuffered, &path)
+            .body_plist(&body.build())
+            .build()
+    }
+
     /// Create TEARDOWN request
     #[must_use]
     pub fn teardown_request(&mut self) -> RtspRequest {
         self.request_builder(Method::Teardown, "").build()
     }
 
+    /// Create TEARDOWN request for a single stream, leaving the session up
+    ///
+    /// Returns `None` if the stream is unknown.
+    #[must_use]
+    pub fn teardown_stream_request(&mut self, stream_id: u64) -> Option<RtspRequest> {
+        let stream = *self.stream(stream_id)?;
+        let body = DictBuilder::new()
+            .insert(
+                "streams",
+                vec![
+                    DictBuilder::new()
+                        .insert("type", stream.stream_type)
+                        .insert("streamID", stream.id)
+                        .build(),
+                ],
+            )
+            .build();
+
+        let path = format!("/{}", self.client_session_id);
+        Some(
+            self.request_builder(Method::Teardown, &path)
+                .body_plist(&body)
+                .build(),
+        )
+    }
+
     /// Create PAUSE request
     #[must_use]
     pub fn pause_request(&mut self) -> RtspRequest {
@@ -287,10 +490,8 @@ impl RtspSession {
         }
 
         // Extract session ID if present
-        if let Some(session) = response.session() {
-            // Session ID may have ";timeout=X" suffix
-            let session_id = session.split(';').next().unwrap_or(session);
-            self.session_id = Some(session_id.to_string());
+        if let Some(session) = response.headers.session_header() {
+            self.session_id = Some(session.id);
         }
 
         // Update state based on method
@@ -328,6 +529,7 @@ impl RtspSession {
                     SessionState::Playing,
                     Method::Pause
                         | Method::Flush
+                        | Method::FlushBuffered
                         | Method::SetParameter
                         | Method::GetParameter
                         | Method::Teardown,
@@ -335,6 +537,7 @@ impl RtspSession {
                 | (
                     SessionState::Paused,
                     Method::Record
+                        | Method::FlushBuffered
                         | Method::Play
                         | Method::Teardown
                         | Method::SetParameter
@@ -1,9 +1,11 @@
 mod codec;
 mod codec_extra;
 mod compliance;
+mod demux;
 mod extra_codec;
 mod header_parsing;
 mod headers;
+mod interleaved;
 mod request;
 mod response;
 mod server_codec;
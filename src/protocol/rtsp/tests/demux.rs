@@ -0,0 +1,71 @@
+use crate::protocol::rtsp::{Method, RtspDemuxer, RtspMessage, StatusCode};
+
+#[test]
+fn test_interleaved_response_and_request() {
+    let mut demux = RtspDemuxer::new();
+    demux
+        .feed(
+            b"RTSP/1.0 200 OK\r\n\
+              CSeq: 3\r\n\
+              Content-Length: 4\r\n\
+              \r\n\
+              bodySETPEERS rtsp://10.0.0.2/1 RTSP/1.0\r\n\
+              CSeq: 7\r\n\
+              \r\n",
+        )
+        .unwrap();
+
+    let Some(RtspMessage::Response(response)) = demux.decode().unwrap() else {
+        panic!("expected a response");
+    };
+    assert_eq!(response.status, StatusCode::OK);
+    assert_eq!(response.cseq(), Some(3));
+    assert_eq!(response.body, b"body");
+
+    let Some(RtspMessage::Request(request)) = demux.decode().unwrap() else {
+        panic!("expected a request");
+    };
+    assert_eq!(request.method, Method::SetPeers);
+    assert_eq!(request.headers.cseq(), Some(7));
+
+    assert!(demux.decode().unwrap().is_none());
+}
+
+#[test]
+fn test_partial_message_waits_for_body() {
+    let mut demux = RtspDemuxer::new();
+    demux
+        .feed(b"FLUSHBUFFERED * RTSP/1.0\r\nCSeq: 2\r\ncontent-length: 3\r\n\r\nab")
+        .unwrap();
+    assert!(demux.decode().unwrap().is_none());
+
+    demux.feed(b"c").unwrap();
+    let Some(RtspMessage::Request(request)) = demux.decode().unwrap() else {
+        panic!("expected a request");
+    };
+    assert_eq!(request.method, Method::FlushBuffered);
+    assert_eq!(request.body, b"abc");
+}
+
+#[test]
+fn test_unknown_method_is_reported_with_cseq() {
+    let mut demux = RtspDemuxer::new();
+    demux
+        .feed(b"FROBNICATE * RTSP/1.0\r\nCSeq: 9\r\n\r\n")
+        .unwrap();
+
+    let Some(RtspMessage::Unsupported { request_line, cseq }) = demux.decode().unwrap() else {
+        panic!("expected an unsupported request");
+    };
+    assert_eq!(request_line, "FROBNICATE * RTSP/1.0");
+    assert_eq!(cseq, Some(9));
+}
+
+#[test]
+fn test_invalid_content_length_is_an_error() {
+    let mut demux = RtspDemuxer::new();
+    demux
+        .feed(b"RTSP/1.0 200 OK\r\nContent-Length: lots\r\n\r\n")
+        .unwrap();
+    assert!(demux.decode().is_err());
+}
@@ -1,5 +1,6 @@
-use crate::protocol::rtsp::Headers;
-use crate::protocol::rtsp::headers::names;
+use crate::protocol::rtsp::headers::{PublicMethods, SessionHeader, names};
+use crate::protocol::rtsp::transport::LowerTransport;
+use crate::protocol::rtsp::{Headers, Method};
 
 #[test]
 fn test_new_headers_is_empty() {
@@ -108,3 +109,87 @@ fn test_constants() {
     assert_eq!(names::CSEQ, "CSeq");
     assert_eq!(names::CONTENT_TYPE, "Content-Type");
 }
+
+#[test]
+fn test_session_header_timeout() {
+    let session = SessionHeader::parse("DEADBEEF;timeout=60").unwrap();
+    assert_eq!(session.id, "DEADBEEF");
+    assert_eq!(session.timeout, Some(60));
+    assert_eq!(session.to_string(), "DEADBEEF;timeout=60");
+
+    let session = SessionHeader::parse(" 1234 ").unwrap();
+    assert_eq!(session, SessionHeader::new("1234"));
+    assert_eq!(session.to_string(), "1234");
+
+    assert_eq!(SessionHeader::parse(";timeout=60"), None);
+}
+
+#[test]
+fn test_session_header_accessor() {
+    let mut headers = Headers::new();
+    assert_eq!(headers.session_header(), None);
+
+    headers.insert("Session", "ABC;timeout=oops");
+    let session = headers.session_header().unwrap();
+    assert_eq!(session.id, "ABC");
+    assert_eq!(session.timeout, None);
+}
+
+#[test]
+fn test_public_methods_round_trip() {
+    let mut headers = Headers::new();
+    headers.insert(
+        names::PUBLIC,
+        "ANNOUNCE, SETUP,RECORD, SETPEERS, DESCRIBE, SET_PARAMETER",
+    );
+    let methods = headers.public().unwrap();
+
+    assert_eq!(
+        methods.0,
+        [
+            Method::Announce,
+            Method::Setup,
+            Method::Record,
+            Method::SetPeers,
+            Method::SetParameter,
+        ]
+    );
+    assert!(methods.contains(Method::SetPeers));
+    assert!(!methods.contains(Method::Play));
+    assert_eq!(
+        methods.to_string(),
+        "ANNOUNCE, SETUP, RECORD, SETPEERS, SET_PARAMETER"
+    );
+    assert_eq!(PublicMethods::parse(&methods.to_string()), methods);
+}
+
+#[test]
+fn test_transport_accessor() {
+    let mut headers = Headers::new();
+    assert!(headers.transport().unwrap().is_none());
+
+    headers.insert(names::TRANSPORT, "RTP/AVP/TCP;unicast;interleaved=0-1");
+    let transport = headers.transport().unwrap().unwrap();
+    assert_eq!(transport.lower_transport, LowerTransport::Tcp);
+
+    headers.insert(names::TRANSPORT, "HTTP/1.1");
+    assert!(headers.transport().is_err());
+}
+
+#[test]
+fn test_apple_header_accessors() {
+    let mut headers = Headers::new();
+    headers.insert("Audio-Latency", "11025");
+    headers.insert("Active-Remote", "1986535575");
+    headers.insert("DACP-ID", "14413BE4996FEA4D");
+    headers.insert("Apple-Response", "c2lnbmF0dXJl");
+
+    assert_eq!(headers.audio_latency(), Some(11025));
+    assert_eq!(headers.active_remote(), Some(1_986_535_575));
+    assert_eq!(headers.dacp_id(), Some(0x1441_3BE4_996F_EA4D));
+    assert_eq!(headers.apple_response(), Some("c2lnbmF0dXJl"));
+    assert_eq!(headers.apple_challenge(), None);
+
+    headers.insert("DACP-ID", "not hex");
+    assert_eq!(headers.dacp_id(), None);
+}
@@ -0,0 +1,116 @@
+use crate::protocol::rtsp::interleaved::MAX_PAYLOAD;
+use crate::protocol::rtsp::{
+    InterleavedFrame, Method, RtspCodec, RtspCodecError, RtspDemuxer, RtspMessage, RtspServerCodec,
+    StatusCode,
+};
+
+fn frame(channel: u8, payload: &[u8]) -> InterleavedFrame {
+    InterleavedFrame {
+        channel,
+        payload: payload.to_vec(),
+    }
+}
+
+#[test]
+fn test_frame_round_trip() {
+    let encoded = frame(1, b"\x80\xd4rtcp").encode().unwrap();
+    assert_eq!(&encoded[..4], b"$\x01\x00\x06");
+
+    let (parsed, len) = InterleavedFrame::parse(&encoded).unwrap();
+    assert_eq!(parsed, frame(1, b"\x80\xd4rtcp"));
+    assert_eq!(len, encoded.len());
+}
+
+#[test]
+fn test_parse_incomplete_frame() {
+    assert!(InterleavedFrame::parse(b"$\x00").is_none());
+    assert!(InterleavedFrame::parse(b"$\x00\x00\x04ab").is_none());
+    assert!(InterleavedFrame::parse(b"RTSP/1.0 200 OK\r\n").is_none());
+}
+
+#[test]
+fn test_encode_rejects_oversized_payload() {
+    let result = frame(0, &vec![0; MAX_PAYLOAD + 1]).encode();
+    assert!(matches!(
+        result,
+        Err(RtspCodecError::InterleavedTooLarge { size }) if size == MAX_PAYLOAD + 1
+    ));
+    assert!(frame(0, &vec![0; MAX_PAYLOAD]).encode().is_ok());
+}
+
+#[test]
+fn test_client_codec_sets_frames_aside() {
+    let mut codec = RtspCodec::new();
+    let mut data = frame(0, b"rtp").encode().unwrap();
+    data.extend_from_slice(b"RTSP/1.0 200 OK\r\nCSeq: 1\r\n\r\n");
+    data.extend_from_slice(&frame(1, b"rtcp").encode().unwrap());
+    codec.feed(&data).unwrap();
+
+    let response = codec.decode().unwrap().unwrap();
+    assert_eq!(response.status, StatusCode::OK);
+    assert!(codec.decode().unwrap().is_none());
+
+    assert_eq!(codec.decode_interleaved(), Some(frame(0, b"rtp")));
+    assert_eq!(codec.decode_interleaved(), Some(frame(1, b"rtcp")));
+    assert_eq!(codec.decode_interleaved(), None);
+    assert_eq!(codec.buffered_len(), 0);
+}
+
+#[test]
+fn test_client_codec_frame_split_across_reads() {
+    let mut codec = RtspCodec::new();
+    let encoded = frame(0, b"\r\n\r\npayload").encode().unwrap();
+    codec.feed(&encoded[..6]).unwrap();
+
+    assert!(codec.decode().unwrap().is_none());
+    assert_eq!(codec.decode_interleaved(), None);
+
+    codec.feed(&encoded[6..]).unwrap();
+    codec.feed(b"RTSP/1.0 200 OK\r\nCSeq: 2\r\n\r\n").unwrap();
+    assert_eq!(codec.decode().unwrap().unwrap().cseq(), Some(2));
+    assert_eq!(
+        codec.decode_interleaved(),
+        Some(frame(0, b"\r\n\r\npayload"))
+    );
+}
+
+#[test]
+fn test_server_codec_sets_frames_aside() {
+    let mut codec = RtspServerCodec::new();
+    codec.feed(&frame(0, b"audio").encode().unwrap());
+    codec.feed(b"OPTIONS * RTSP/1.0\r\nCSeq: 1\r\n\r\n");
+    let encoded = frame(1, b"sync").encode().unwrap();
+    codec.feed(&encoded[..3]);
+
+    let request = codec.decode().unwrap().unwrap();
+    assert_eq!(request.method, Method::Options);
+    assert!(codec.decode().unwrap().is_none());
+    assert_eq!(codec.decode_interleaved(), Some(frame(0, b"audio")));
+    assert_eq!(codec.decode_interleaved(), None);
+
+    codec.feed(&encoded[3..]);
+    assert_eq!(codec.decode_interleaved(), Some(frame(1, b"sync")));
+    assert_eq!(codec.buffer_len(), 0);
+}
+
+#[test]
+fn test_demuxer_yields_frames_in_order() {
+    let mut demux = RtspDemuxer::new();
+    let mut data = b"RTSP/1.0 200 OK\r\nCSeq: 4\r\n\r\n".to_vec();
+    data.extend_from_slice(&frame(1, b"rtcp").encode().unwrap());
+    data.extend_from_slice(b"SETPEERS rtsp://10.0.0.2/1 RTSP/1.0\r\nCSeq: 9\r\n\r\n");
+    demux.feed(&data).unwrap();
+
+    assert!(matches!(
+        demux.decode().unwrap(),
+        Some(RtspMessage::Response(_))
+    ));
+    let Some(RtspMessage::Interleaved(received)) = demux.decode().unwrap() else {
+        panic!("expected an interleaved frame");
+    };
+    assert_eq!(received, frame(1, b"rtcp"));
+    assert!(matches!(
+        demux.decode().unwrap(),
+        Some(RtspMessage::Request(_))
+    ));
+}
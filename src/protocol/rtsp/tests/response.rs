@@ -14,6 +14,42 @@ fn test_status_code_classification() {
     assert!(StatusCode(500).is_server_error());
     assert!(StatusCode(503).is_server_error());
     assert!(!StatusCode(500).is_success());
+
+    assert!(StatusCode::METHOD_NOT_VALID.is_state_error());
+    assert!(StatusCode(499).is_state_error());
+    assert!(!StatusCode::SESSION_NOT_FOUND.is_state_error());
+}
+
+#[test]
+fn test_status_code_reason_phrases() {
+    assert_eq!(StatusCode::OK.reason_phrase(), Some("OK"));
+    assert_eq!(
+        StatusCode::NOT_ENOUGH_BANDWIDTH.reason_phrase(),
+        Some("Not Enough Bandwidth")
+    );
+    assert_eq!(
+        StatusCode::OPTION_NOT_SUPPORTED.reason_phrase(),
+        Some("Option Not Supported")
+    );
+    assert_eq!(StatusCode(499).reason_phrase(), None);
+
+    assert_eq!(
+        StatusCode::METHOD_NOT_VALID.to_string(),
+        "455 Method Not Valid in This State"
+    );
+    // Unknown codes keep their value
+    assert_eq!(StatusCode::from(599).to_string(), "599");
+    assert_eq!(StatusCode::from(599).as_u16(), 599);
+}
+
+#[test]
+fn test_status_code_helpers() {
+    assert!(StatusCode::UNAUTHORIZED.is_auth_required());
+    assert!(StatusCode(470).is_auth_required());
+    assert!(!StatusCode::FORBIDDEN.is_auth_required());
+
+    assert!(StatusCode(454).is_session_invalid());
+    assert!(!StatusCode::METHOD_NOT_VALID.is_session_invalid());
 }
 
 #[test]
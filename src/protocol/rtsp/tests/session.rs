@@ -78,6 +78,25 @@ fn test_request_includes_common_headers() {
     assert!(request.headers.get("User-Agent").is_some());
 }
 
+#[test]
+fn test_session_dacp_identity() {
+    let mut session = RtspSession::new("192.168.1.10", 7000);
+    let other = RtspSession::new("192.168.1.10", 7000);
+
+    assert_eq!(session.dacp_id().len(), 16);
+    assert!(session.active_remote().parse::<u32>().is_ok());
+    assert_ne!(session.dacp_id(), other.dacp_id());
+
+    // The same values are sent with every request of the session
+    for request in [session.options_request(), session.options_request()] {
+        assert_eq!(request.headers.get("DACP-ID"), Some(session.dacp_id()));
+        assert_eq!(
+            request.headers.get("Active-Remote"),
+            Some(session.active_remote())
+        );
+    }
+}
+
 #[test]
 fn test_invalid_state_transitions() {
     let session = RtspSession::new("192.168.1.10", 7000);
@@ -250,3 +269,87 @@ fn test_record_request_headers() {
 
     assert_eq!(request.method, Method::Record);
 }
+
+#[test]
+fn test_session_records_setup_streams() {
+    use crate::protocol::plist::DictBuilder;
+
+    let mut session = RtspSession::new("192.168.1.10", 7000);
+    let response = DictBuilder::new()
+        .insert(
+            "streams",
+            vec![
+                DictBuilder::new()
+                    .insert("streamID", 7_u64)
+                    .insert("type", 103_u64)
+                    .insert("dataPort", 50000_u64)
+                    .insert("controlPort", 50001_u64)
+                    .build(),
+            ],
+        )
+        .build();
+
+    let added = session.record_setup_response(&[103], &response);
+    assert_eq!(added.len(), 1);
+    let primary = session.primary_stream().unwrap();
+    assert_eq!(primary.id, 7);
+    assert_eq!(primary.stream_type, 103);
+    assert_eq!(primary.data_port, Some(50000));
+    assert_eq!(primary.control_port, Some(50001));
+
+    // Receiver without stream details: requested type, next free ID
+    let added = session.record_setup_response(&[96], &DictBuilder::new().build());
+    assert_eq!(added[0].id, 8);
+    assert_eq!(added[0].stream_type, 96);
+    assert_eq!(session.streams().len(), 2);
+
+    assert!(session.set_primary_stream(8));
+    assert_eq!(session.primary_stream().unwrap().id, 8);
+    assert!(!session.set_primary_stream(9));
+
+    assert!(session.remove_stream(8).is_some());
+    assert!(session.stream(8).is_none());
+    assert_eq!(session.streams().len(), 1);
+}
+
+#[test]
+fn test_stream_teardown_and_flush_buffered_bodies() {
+    use crate::protocol::plist::{DictBuilder, decode};
+
+    let mut session = RtspSession::new("192.168.1.10", 7000);
+    assert!(session.teardown_stream_request(1).is_none());
+
+    let response = DictBuilder::new()
+        .insert(
+            "streams",
+            vec![
+                DictBuilder::new()
+                    .insert("streamID", 1_u64)
+                    .insert("type", 103_u64)
+                    .build(),
+            ],
+        )
+        .build();
+    session.record_setup_response(&[103], &response);
+
+    let request = session.teardown_stream_request(1).unwrap();
+    assert_eq!(request.method, Method::Teardown);
+    let body = decode(&request.body).unwrap();
+    let stream = body.as_dict().unwrap()["streams"].as_array().unwrap()[0]
+        .as_dict()
+        .unwrap()
+        .clone();
+    assert_eq!(stream["streamID"].as_u64(), Some(1));
+    assert_eq!(stream["type"].as_u64(), Some(103));
+
+    let request = session.flush_buffered_request(1, Some((10, 3520)), 200, 70400);
+    assert_eq!(request.method, Method::FlushBuffered);
+    assert_eq!(request.method.as_str(), "FLUSHBUFFERED");
+    let body = decode(&request.body).unwrap();
+    let body = body.as_dict().unwrap();
+    assert_eq!(body["streamID"].as_u64(), Some(1));
+    assert_eq!(body["flushFromSeq"].as_u64(), Some(10));
+    assert_eq!(body["flushFromTS"].as_u64(), Some(3520));
+    assert_eq!(body["flushUntilSeq"].as_u64(), Some(200));
+    assert_eq!(body["flushUntilTS"].as_u64(), Some(70400));
+}
@@ -89,3 +89,48 @@ fn test_multicast_parsing() {
     let transport = TransportHeader::parse("RTP/AVP/UDP;multicast").unwrap();
     assert_eq!(transport.cast, CastMode::Multicast);
 }
+
+#[test]
+fn test_parse_server_ports() {
+    let transport = TransportHeader::parse(
+        "RTP/AVP/UDP;unicast;mode=record;server_port=6000-6001;control_port=6001;\
+         timing_port=6002",
+    )
+    .unwrap();
+
+    assert_eq!(transport.server_port, Some(6000));
+    assert_eq!(transport.control_port, Some(6001));
+    assert_eq!(transport.timing_port, Some(6002));
+}
+
+#[test]
+fn test_display_round_trip() {
+    let transport = TransportHeader {
+        client_port: Some(5000),
+        control_port: Some(5001),
+        timing_port: Some(5002),
+        ..TransportHeader::record(LowerTransport::Udp)
+    };
+    let header = transport.to_string();
+
+    assert_eq!(
+        header,
+        "RTP/AVP/UDP;unicast;mode=record;client_port=5000;control_port=5001;timing_port=5002"
+    );
+    assert_eq!(TransportHeader::parse(&header).unwrap(), transport);
+}
+
+#[test]
+fn test_response_keeps_interleaved_only_for_tcp() {
+    let udp = TransportHeader::parse("RTP/AVP/UDP;unicast;interleaved=0-1;mode=record").unwrap();
+    let response = udp.response(6000, 6001, 6002);
+    assert_eq!(response.interleaved, None);
+    assert_eq!(response.server_port, Some(6000));
+
+    let tcp = TransportHeader::parse("RTP/AVP/TCP;unicast;interleaved=0-1;mode=record").unwrap();
+    assert_eq!(
+        tcp.to_response_header(6000, 6001, 6002),
+        "RTP/AVP/TCP;unicast;interleaved=0-1;mode=record;server_port=6000;control_port=6001;\
+         timing_port=6002"
+    );
+}
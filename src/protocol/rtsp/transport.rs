@@ -2,6 +2,9 @@
 //!
 //! The Transport header in SETUP requests specifies how audio will be delivered.
 //! Format: `RTP/AVP/UDP;unicast;mode=record;control_port=6001;timing_port=6002`
+//!
+//! Responses carry the same header with the receiver's ports added, so
+//! [`TransportHeader`] parses both and formats back with `Display`.
 
 /// Parsed Transport header
 #[derive(Debug, Clone, PartialEq)]
@@ -14,6 +17,10 @@ pub struct TransportHeader {
     pub cast: CastMode,
     /// Mode (usually "record" for RAOP)
     pub mode: Option<String>,
+    /// Client's audio port
+    pub client_port: Option<u16>,
+    /// Server's audio port, in responses
+    pub server_port: Option<u16>,
     /// Client's control port
     pub control_port: Option<u16>,
     /// Client's timing port
@@ -35,6 +42,22 @@ pub enum CastMode {
 }
 
 impl TransportHeader {
+    /// Unicast `RTP/AVP` in `record` mode, as senders request
+    #[must_use]
+    pub fn record(lower_transport: LowerTransport) -> Self {
+        Self {
+            protocol: "RTP/AVP".to_string(),
+            lower_transport,
+            cast: CastMode::Unicast,
+            mode: Some("record".to_string()),
+            client_port: None,
+            server_port: None,
+            control_port: None,
+            timing_port: None,
+            interleaved: None,
+        }
+    }
+
     /// Parse a Transport header value
     ///
     /// # Errors
@@ -52,6 +75,8 @@ impl TransportHeader {
             lower_transport,
             cast: CastMode::Unicast, // Default
             mode: None,
+            client_port: None,
+            server_port: None,
             control_port: None,
             timing_port: None,
             interleaved: None,
@@ -67,18 +92,14 @@ impl TransportHeader {
                 transport.cast = CastMode::Multicast;
             } else if let Some(value) = part.strip_prefix("mode=") {
                 transport.mode = Some(value.to_string());
+            } else if let Some(value) = part.strip_prefix("client_port=") {
+                transport.client_port = Some(Self::parse_port(value)?);
+            } else if let Some(value) = part.strip_prefix("server_port=") {
+                transport.server_port = Some(Self::parse_port(value)?);
             } else if let Some(value) = part.strip_prefix("control_port=") {
-                transport.control_port = Some(
-                    value
-                        .parse()
-                        .map_err(|_| TransportParseError::InvalidPort)?,
-                );
+                transport.control_port = Some(Self::parse_port(value)?);
             } else if let Some(value) = part.strip_prefix("timing_port=") {
-                transport.timing_port = Some(
-                    value
-                        .parse()
-                        .map_err(|_| TransportParseError::InvalidPort)?,
-                );
+                transport.timing_port = Some(Self::parse_port(value)?);
             } else if let Some(value) = part.strip_prefix("interleaved=") {
                 transport.interleaved = Some(Self::parse_interleaved(value)?);
             }
@@ -100,6 +121,12 @@ impl TransportHeader {
         }
     }
 
+    /// Parse a port, or the first of an RTP/RTCP pair such as `6000-6001`
+    fn parse_port(value: &str) -> Result<u16, TransportParseError> {
+        let first = value.split_once('-').map_or(value, |(first, _)| first);
+        first.parse().map_err(|_| TransportParseError::InvalidPort)
+    }
+
     fn parse_interleaved(value: &str) -> Result<(u8, u8), TransportParseError> {
         let parts: Vec<&str> = value.split('-').collect();
         match parts.as_slice() {
@@ -114,6 +141,24 @@ impl TransportHeader {
         }
     }
 
+    /// Transport for the response, with the receiver's ports
+    ///
+    /// Echoes the request's protocol, cast and mode. Interleaved channels
+    /// are kept only for TCP.
+    #[must_use]
+    pub fn response(&self, server_port: u16, control_port: u16, timing_port: u16) -> Self {
+        Self {
+            client_port: None,
+            server_port: Some(server_port),
+            control_port: Some(control_port),
+            timing_port: Some(timing_port),
+            interleaved: self
+                .interleaved
+                .filter(|_| self.lower_transport == LowerTransport::Tcp),
+            ..self.clone()
+        }
+    }
+
     /// Generate Transport header for response
     #[must_use]
     pub fn to_response_header(
@@ -122,27 +167,37 @@ impl TransportHeader {
         control_port: u16,
         timing_port: u16,
     ) -> String {
-        let mut parts = vec![
-            format!(
-                "{}/{}",
-                self.protocol,
-                match self.lower_transport {
-                    LowerTransport::Udp => "UDP",
-                    LowerTransport::Tcp => "TCP",
-                }
-            ),
-            self.cast.to_string(),
-        ];
+        self.response(server_port, control_port, timing_port)
+            .to_string()
+    }
+}
+
+impl std::fmt::Display for TransportHeader {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let lower = match self.lower_transport {
+            LowerTransport::Udp => "UDP",
+            LowerTransport::Tcp => "TCP",
+        };
+        write!(f, "{}/{lower};{}", self.protocol, self.cast)?;
 
+        if let Some((start, end)) = self.interleaved {
+            write!(f, ";interleaved={start}-{end}")?;
+        }
         if let Some(ref mode) = self.mode {
-            parts.push(format!("mode={mode}"));
+            write!(f, ";mode={mode}")?;
         }
-
-        parts.push(format!("server_port={server_port}"));
-        parts.push(format!("control_port={control_port}"));
-        parts.push(format!("timing_port={timing_port}"));
-
-        parts.join(";")
+        let ports = [
+            ("client_port", self.client_port),
+            ("server_port", self.server_port),
+            ("control_port", self.control_port),
+            ("timing_port", self.timing_port),
+        ];
+        for (name, port) in ports {
+            if let Some(port) = port {
+                write!(f, ";{name}={port}")?;
+            }
+        }
+        Ok(())
     }
 }
 
@@ -4,9 +4,10 @@
 
 mod builder;
 mod parser;
+#[cfg(feature = "receiver")]
 pub mod raop;
 
-#[cfg(test)]
+#[cfg(all(test, feature = "receiver"))]
 mod raop_tests;
 #[cfg(test)]
 mod tests;
@@ -9,9 +9,13 @@ use std::sync::Arc;
 use base64::Engine;
 use mdns_sd::{ServiceDaemon, ServiceInfo};
 use sha2::{Digest, Sha256};
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, broadcast};
+use tokio::task::JoinHandle;
 
+use crate::discovery::names::instance_name;
 use crate::receiver::ap2::config::Ap2Config;
+use crate::receiver::ap2::features::StatusFlag;
+use crate::receiver::session_manager::SessionEvent;
 
 /// TXT record keys for `AirPlay` 2 service advertisement
 pub mod txt_keys {
@@ -174,13 +178,17 @@ impl Ap2TxtRecord {
         self.entries.insert(key.into(), value.into());
     }
 
+    /// Current status flags
+    #[must_use]
+    pub fn status_flags(&self) -> u32 {
+        self.get(txt_keys::STATUS_FLAGS)
+            .and_then(|s| u32::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            .unwrap_or(0)
+    }
+
     /// Update password status in TXT record
     pub fn update_password_status(&mut self, has_password: bool) {
-        // Update status flags
-        let mut status_flags = self
-            .get(txt_keys::STATUS_FLAGS)
-            .and_then(|s| u32::from_str_radix(s.trim_start_matches("0x"), 16).ok())
-            .unwrap_or(0);
+        let mut status_flags = self.status_flags();
 
         if has_password {
             status_flags |= PASSWORD_REQUIRED_FLAG | PASSWORD_CONFIGURED_FLAG;
@@ -191,6 +199,19 @@ impl Ap2TxtRecord {
         self.set(txt_keys::STATUS_FLAGS, format!("0x{status_flags:X}"));
     }
 
+    /// Update the session active flag in TXT record
+    pub fn update_session_status(&mut self, active: bool) {
+        let mut status_flags = self.status_flags();
+
+        if active {
+            status_flags |= StatusFlag::ReceiverSessionActive.mask();
+        } else {
+            status_flags &= !StatusFlag::ReceiverSessionActive.mask();
+        }
+
+        self.set(txt_keys::STATUS_FLAGS, format!("0x{status_flags:X}"));
+    }
+
     /// Convert to mdns-sd compatible format
     #[must_use]
     pub fn to_txt_properties(&self) -> Vec<(String, String)> {
@@ -213,6 +234,15 @@ pub struct Ap2ServiceAdvertiser {
     daemon: ServiceDaemon,
     service_info: Arc<RwLock<Option<ServiceInfo>>>,
     public_key: [u8; 32],
+    /// Status that changes while advertised
+    status: RwLock<DynamicStatus>,
+}
+
+/// Status flags that follow the receiver's sessions and authentication
+#[derive(Debug, Clone, Copy)]
+struct DynamicStatus {
+    session_active: bool,
+    has_password: bool,
 }
 
 impl Ap2ServiceAdvertiser {
@@ -224,22 +254,51 @@ impl Ap2ServiceAdvertiser {
     pub fn new(config: Ap2Config, public_key: [u8; 32]) -> Result<Self, AdvertisementError> {
         let daemon =
             ServiceDaemon::new().map_err(|e| AdvertisementError::MdnsInit(e.to_string()))?;
+        crate::discovery::restrict_interfaces(&daemon, &config.interfaces)
+            .map_err(|e| AdvertisementError::MdnsInit(e.to_string()))?;
 
+        let status = DynamicStatus {
+            session_active: false,
+            has_password: config.has_password(),
+        };
         Ok(Self {
             config,
             daemon,
             service_info: Arc::new(RwLock::new(None)),
             public_key,
+            status: RwLock::new(status),
         })
     }
 
+    /// TXT record for the configuration and current status
+    pub async fn txt_record(&self) -> Ap2TxtRecord {
+        let status = *self.status.read().await;
+        let mut txt = Ap2TxtRecord::from_config(&self.config, &self.public_key);
+        txt.update_password_status(status.has_password);
+        txt.update_session_status(status.session_active);
+        txt
+    }
+
     /// Start advertising the service
     ///
     /// # Errors
     ///
     /// Returns error if service creation or registration fails.
     pub async fn start(&self) -> Result<(), AdvertisementError> {
-        let txt = Ap2TxtRecord::from_config(&self.config, &self.public_key);
+        self.register().await?;
+
+        tracing::info!(
+            "AirPlay 2 service advertised: {} on port {}",
+            self.config.name,
+            self.config.server_port
+        );
+
+        Ok(())
+    }
+
+    /// Register the service, replacing the TXT record if already advertised
+    async fn register(&self) -> Result<(), AdvertisementError> {
+        let txt = self.txt_record().await;
 
         // Get local hostname
         let hostname = hostname::get().map_or_else(
@@ -249,7 +308,7 @@ impl Ap2ServiceAdvertiser {
 
         let service_info = ServiceInfo::new(
             AIRPLAY2_SERVICE_TYPE,
-            &self.config.name,
+            &instance_name("", &self.config.name),
             &format!("{hostname}.local."),
             "", // Let mdns-sd determine IP
             self.config.server_port,
@@ -267,15 +326,90 @@ impl Ap2ServiceAdvertiser {
         // Store for later updates/unregistration
         *self.service_info.write().await = Some(service_info);
 
-        tracing::info!(
-            "AirPlay 2 service advertised: {} on port {}",
-            self.config.name,
-            self.config.server_port
-        );
+        Ok(())
+    }
 
+    /// Re-announce the TXT record after a status change, if advertised
+    ///
+    /// Registering the same service again updates its TXT record in place,
+    /// so browsers see the new flags without the service disappearing.
+    async fn refresh(&self) -> Result<(), AdvertisementError> {
+        if self.service_info.read().await.is_none() {
+            return Ok(());
+        }
+        self.register().await?;
+        tracing::debug!(
+            "AirPlay 2 status flags updated: 0x{:X}",
+            self.status_flags().await
+        );
         Ok(())
     }
 
+    /// Advertised status flags
+    pub async fn status_flags(&self) -> u32 {
+        self.txt_record().await.status_flags()
+    }
+
+    /// Mark whether a sender has a session with the receiver
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the TXT record cannot be re-announced.
+    pub async fn set_session_active(&self, active: bool) -> Result<(), AdvertisementError> {
+        {
+            let mut status = self.status.write().await;
+            if status.session_active == active {
+                return Ok(());
+            }
+            status.session_active = active;
+        }
+        self.refresh().await
+    }
+
+    /// Mark whether senders need a password
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the TXT record cannot be re-announced.
+    pub async fn set_password_required(&self, required: bool) -> Result<(), AdvertisementError> {
+        {
+            let mut status = self.status.write().await;
+            if status.has_password == required {
+                return Ok(());
+            }
+            status.has_password = required;
+        }
+        self.refresh().await
+    }
+
+    /// Follow a session manager's sessions in the status flags
+    ///
+    /// The session active flag is set while a session is running and
+    /// cleared when it ends, including when it is reaped as stale.
+    #[must_use]
+    pub fn track_sessions(
+        self: &Arc<Self>,
+        mut events: broadcast::Receiver<SessionEvent>,
+    ) -> JoinHandle<()> {
+        let advertiser = Arc::downgrade(self);
+        tokio::spawn(async move {
+            loop {
+                let active = match events.recv().await {
+                    Ok(SessionEvent::SessionStarted { .. }) => true,
+                    Ok(SessionEvent::SessionEnded { .. }) => false,
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                };
+                let Some(advertiser) = advertiser.upgrade() else {
+                    break;
+                };
+                if let Err(e) = advertiser.set_session_active(active).await {
+                    tracing::warn!("Failed to update session status: {}", e);
+                }
+            }
+        })
+    }
+
     /// Stop advertising the service
     ///
     /// # Errors
@@ -303,7 +437,7 @@ impl Ap2ServiceAdvertiser {
         self.stop().await?;
 
         // Update config
-        self.config.name = new_name;
+        self.config.name = crate::discovery::normalize_name(&new_name);
 
         // Re-advertise
         self.start().await
@@ -5,7 +5,7 @@
 
 use std::collections::HashMap;
 
-use crate::protocol::plist::{self, PlistValue};
+use crate::protocol::plist::{self, PlistDict, PlistValue};
 
 /// Content types used in `AirPlay` 2
 pub mod content_types {
@@ -26,7 +26,7 @@ pub mod content_types {
 /// Returns `BodyParseError` if the body is invalid or cannot be parsed.
 pub fn parse_bplist_body(body: &[u8]) -> Result<PlistValue, BodyParseError> {
     if body.is_empty() {
-        return Ok(PlistValue::Dictionary(HashMap::new()));
+        return Ok(PlistValue::Dictionary(PlistDict::new()));
     }
 
     // Check magic header
@@ -91,7 +91,7 @@ pub trait PlistExt {
     /// Get a boolean value from the dictionary
     fn get_bool(&self, key: &str) -> Option<bool>;
     /// Get a dictionary from the dictionary
-    fn get_dict(&self, key: &str) -> Option<&HashMap<String, PlistValue>>;
+    fn get_dict(&self, key: &str) -> Option<&PlistDict>;
     /// Get an array from the dictionary
     fn get_array(&self, key: &str) -> Option<&Vec<PlistValue>>;
 }
@@ -133,7 +133,7 @@ impl PlistExt for PlistValue {
         None
     }
 
-    fn get_dict(&self, key: &str) -> Option<&HashMap<String, PlistValue>> {
+    fn get_dict(&self, key: &str) -> Option<&PlistDict> {
         if let PlistValue::Dictionary(dict) = self {
             if let Some(PlistValue::Dictionary(d)) = dict.get(key) {
                 return Some(d);
@@ -155,7 +155,7 @@ impl PlistExt for PlistValue {
 /// Builder for plist response bodies
 #[derive(Debug, Default)]
 pub struct PlistResponseBuilder {
-    values: HashMap<String, PlistValue>,
+    values: PlistDict,
 }
 
 impl PlistResponseBuilder {
@@ -198,7 +198,7 @@ impl PlistResponseBuilder {
 
     /// Add a dictionary value
     #[must_use]
-    pub fn dict(mut self, key: &str, value: HashMap<String, PlistValue>) -> Self {
+    pub fn dict(mut self, key: &str, value: PlistDict) -> Self {
         self.values
             .insert(key.to_string(), PlistValue::Dictionary(value));
         self
@@ -3,10 +3,11 @@
 //! These structures define what our receiver advertises to senders
 //! via the /info endpoint.
 
-use std::collections::HashMap;
-
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
+use crate::receiver::ap2::advertisement::{Ap2TxtRecord, txt_keys};
+use crate::receiver::ap2::config::Ap2Config;
 use crate::receiver::ap2::features::{FeatureFlag, FeatureFlags};
+use crate::receiver::ap2::stream::{AudioFormatDescriptor, StreamCodec};
 
 /// Device capabilities for /info response
 #[derive(Debug, Clone)]
@@ -47,6 +48,10 @@ pub struct DeviceCapabilities {
 
     /// Pairing identity (UUID)
     pub pairing_identity: String,
+    /// Public system identifier (UUID), stable across restarts
+    pub public_system_identifier: String,
+    /// `_airplay._tcp` TXT record entries, echoed as `txtAirPlay`
+    pub txt_airplay: Vec<(String, String)>,
 
     /// Audio capabilities
     pub audio_formats: Vec<AudioFormatCapability>,
@@ -92,6 +97,46 @@ pub struct AudioFormatCapability {
     pub encryption_types: Vec<EncryptionType>,
 }
 
+impl AudioFormatCapability {
+    /// Codec for the RTP payload type
+    fn codec(&self) -> Option<StreamCodec> {
+        match self.type_id {
+            96 => Some(StreamCodec::Alac),
+            97 => Some(StreamCodec::AacLc),
+            98 => Some(StreamCodec::AacEld),
+            100 => Some(StreamCodec::Pcm),
+            _ => None,
+        }
+    }
+
+    /// `audioFormat` bits covered by this capability
+    ///
+    /// Every combination of the listed sample rates and sample sizes, for one
+    /// channel up to `channels`, that has an `audioFormat` bit.
+    #[must_use]
+    pub fn audio_format_mask(&self) -> u64 {
+        let Some(codec) = self.codec() else {
+            return 0;
+        };
+
+        let mut mask = 0;
+        for &sample_rate in &self.sample_rates {
+            for &bits_per_sample in &self.bits_per_sample {
+                for channels in 1..=self.channels {
+                    let descriptor = AudioFormatDescriptor {
+                        codec,
+                        sample_rate,
+                        bits_per_sample,
+                        channels,
+                    };
+                    mask |= descriptor.to_mask().unwrap_or(0);
+                }
+            }
+        }
+        mask
+    }
+}
+
 /// Encryption types for audio
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum EncryptionType {
@@ -167,6 +212,8 @@ impl DeviceCapabilities {
 
             public_key,
             pairing_identity: Self::derive_pairing_identity(device_id),
+            public_system_identifier: derive_uuid(device_id, b"AirPlay2-PSI"),
+            txt_airplay: Vec::new(),
 
             audio_formats: Self::default_audio_formats(),
             audio_latencies: AudioLatencies::default(),
@@ -187,6 +234,34 @@ impl DeviceCapabilities {
         }
     }
 
+    /// Create capabilities matching a receiver configuration
+    ///
+    /// Features, status flags and identity are taken from the same sources as
+    /// the mDNS advertisement, so `/info` and the TXT record agree.
+    #[must_use]
+    pub fn from_config(config: &Ap2Config, public_key: [u8; 32]) -> Self {
+        let txt = Ap2TxtRecord::from_config(config, &public_key);
+        let mut txt_airplay = txt.to_txt_properties();
+        txt_airplay.sort();
+        let status_flags = txt
+            .get(txt_keys::STATUS_FLAGS)
+            .and_then(|s| u32::from_str_radix(s.trim_start_matches("0x"), 16).ok())
+            .unwrap_or_else(|| config.status_flags());
+
+        Self {
+            model: config.model.clone(),
+            manufacturer: config.manufacturer.clone(),
+            serial_number: config.serial_number.clone(),
+            firmware_version: config.firmware_version.clone(),
+            features: config.feature_flags(),
+            status_flags,
+            txt_airplay,
+            supports_ptp: config.multi_room_enabled,
+            requires_password: config.has_password(),
+            ..Self::audio_receiver(&config.device_id, &config.name, public_key)
+        }
+    }
+
     /// Default feature flags for audio receiver
     fn default_audio_features() -> u64 {
         let mut flags = FeatureFlags::new();
@@ -195,10 +270,13 @@ impl DeviceCapabilities {
         flags.set(FeatureFlag::Audio);
         flags.set(FeatureFlag::AudioRedundant);
 
-        // Audio formats
+        // Audio formats; AAC only when the receiver can decode it
         flags.set(FeatureFlag::AudioFormatAlac);
-        flags.set(FeatureFlag::AudioFormatAacLc);
-        flags.set(FeatureFlag::AudioFormatAacEld);
+        #[cfg(feature = "aac-fdk")]
+        {
+            flags.set(FeatureFlag::AudioFormatAacLc);
+            flags.set(FeatureFlag::AudioFormatAacEld);
+        }
 
         // Authentication
         flags.set(FeatureFlag::AuthenticationSetup);
@@ -219,8 +297,10 @@ impl DeviceCapabilities {
     }
 
     /// Default audio formats for receiver
+    ///
+    /// AAC is listed only in builds with the fdk-aac decoder.
     fn default_audio_formats() -> Vec<AudioFormatCapability> {
-        vec![
+        let mut formats = vec![
             // ALAC
             AudioFormatCapability {
                 type_id: 96,
@@ -229,6 +309,10 @@ impl DeviceCapabilities {
                 bits_per_sample: vec![16, 24],
                 encryption_types: vec![EncryptionType::None],
             },
+        ];
+
+        #[cfg(feature = "aac-fdk")]
+        formats.extend([
             // AAC-LC
             AudioFormatCapability {
                 type_id: 97,
@@ -245,54 +329,35 @@ impl DeviceCapabilities {
                 bits_per_sample: vec![16],
                 encryption_types: vec![EncryptionType::None],
             },
-            // PCM
-            AudioFormatCapability {
-                type_id: 100,
-                channels: 2,
-                sample_rates: vec![44100, 48000, 96000],
-                bits_per_sample: vec![16, 24],
-                encryption_types: vec![EncryptionType::None],
-            },
-        ]
+        ]);
+
+        // PCM
+        formats.push(AudioFormatCapability {
+            type_id: 100,
+            channels: 2,
+            sample_rates: vec![44100, 48000, 96000],
+            bits_per_sample: vec![16, 24],
+            encryption_types: vec![EncryptionType::None],
+        });
+        formats
     }
 
     /// Derive pairing identity from device ID
     fn derive_pairing_identity(device_id: &str) -> String {
-        use sha2::{Digest, Sha256};
-
-        let mut hasher = Sha256::new();
-        hasher.update(device_id.as_bytes());
-        hasher.update(b"AirPlay2-PI");
-        let hash = hasher.finalize();
-
-        format!(
-            "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:\
-             02x}{:02x}{:02x}",
-            hash[0],
-            hash[1],
-            hash[2],
-            hash[3],
-            hash[4],
-            hash[5],
-            (hash[6] & 0x0f) | 0x40,
-            hash[7],
-            (hash[8] & 0x3f) | 0x80,
-            hash[9],
-            hash[10],
-            hash[11],
-            hash[12],
-            hash[13],
-            hash[14],
-            hash[15]
-        )
+        derive_uuid(device_id, b"AirPlay2-PI")
     }
 
     /// Convert to binary plist value
     #[must_use]
     pub fn to_plist(&self) -> PlistValue {
-        let mut dict: HashMap<String, PlistValue> = HashMap::new();
+        let mut dict: PlistDict = PlistDict::new();
 
-        // Device identification
+        // Device identification. Senders read the camel-case keys; the
+        // lower-case TXT-style names are kept for older tooling.
+        dict.insert(
+            "deviceID".to_string(),
+            PlistValue::String(self.device_id.clone()),
+        );
         dict.insert(
             "deviceid".to_string(),
             PlistValue::String(self.device_id.clone()),
@@ -312,10 +377,18 @@ impl DeviceCapabilities {
         }
 
         // Version information
+        dict.insert(
+            "sourceVersion".to_string(),
+            PlistValue::String(self.source_version.clone()),
+        );
         dict.insert(
             "srcvers".to_string(),
             PlistValue::String(self.source_version.clone()),
         );
+        dict.insert(
+            "protocolVersion".to_string(),
+            PlistValue::String(self.protocol_version.clone()),
+        );
         dict.insert(
             "protovers".to_string(),
             PlistValue::String(self.protocol_version.clone()),
@@ -347,6 +420,26 @@ impl DeviceCapabilities {
             "pi".to_string(),
             PlistValue::String(self.pairing_identity.clone()),
         );
+        dict.insert(
+            "psi".to_string(),
+            PlistValue::String(self.public_system_identifier.clone()),
+        );
+
+        // Raw TXT record, used by senders to verify the advertisement
+        if let Some(txt) = self.txt_airplay_data() {
+            dict.insert("txtAirPlay".to_string(), PlistValue::Data(txt));
+        }
+
+        // Displays must be present (even if empty) for iOS to treat the
+        // response as complete
+        dict.insert("displays".to_string(), self.displays_to_plist());
+
+        // Keep-alive: senders POST /feedback with stats in the body
+        dict.insert("keepAliveLowPower".to_string(), PlistValue::Boolean(true));
+        dict.insert(
+            "keepAliveSendStatsAsBody".to_string(),
+            PlistValue::Boolean(true),
+        );
 
         // Audio formats
         dict.insert("audioFormats".to_string(), self.audio_formats_to_plist());
@@ -362,6 +455,9 @@ impl DeviceCapabilities {
             "initialVolume".to_string(),
             PlistValue::Real(f64::from(self.initial_volume)),
         );
+        if self.supports_volume {
+            dict.insert("vv".to_string(), PlistValue::Integer(2));
+        }
 
         // Timing
         if self.supports_ptp {
@@ -391,7 +487,7 @@ impl DeviceCapabilities {
             .audio_formats
             .iter()
             .map(|fmt| {
-                let mut dict: HashMap<String, PlistValue> = HashMap::new();
+                let mut dict: PlistDict = PlistDict::new();
                 dict.insert(
                     "type".to_string(),
                     PlistValue::Integer(i64::from(fmt.type_id)),
@@ -400,6 +496,9 @@ impl DeviceCapabilities {
                     "ch".to_string(),
                     PlistValue::Integer(i64::from(fmt.channels)),
                 );
+                let mask = PlistValue::from(fmt.audio_format_mask());
+                dict.insert("audioInputFormats".to_string(), mask.clone());
+                dict.insert("audioOutputFormats".to_string(), mask);
 
                 let sample_rates: Vec<PlistValue> = fmt
                     .sample_rates
@@ -429,8 +528,43 @@ impl DeviceCapabilities {
         PlistValue::Array(formats)
     }
 
+    /// `txtAirPlay` payload, if TXT entries are known
+    #[must_use]
+    pub fn txt_airplay_data(&self) -> Option<Vec<u8>> {
+        (!self.txt_airplay.is_empty()).then(|| encode_txt_record(&self.txt_airplay))
+    }
+
+    fn displays_to_plist(&self) -> PlistValue {
+        let displays = self
+            .displays
+            .iter()
+            .map(|display| {
+                let mut dict: PlistDict = PlistDict::new();
+                dict.insert(
+                    "width".to_string(),
+                    PlistValue::Integer(i64::from(display.width)),
+                );
+                dict.insert(
+                    "height".to_string(),
+                    PlistValue::Integer(i64::from(display.height)),
+                );
+                dict.insert(
+                    "refreshRate".to_string(),
+                    PlistValue::Real(f64::from(display.refresh_rate)),
+                );
+                dict.insert("uuid".to_string(), PlistValue::String(display.uuid.clone()));
+                dict.insert(
+                    "features".to_string(),
+                    PlistValue::Integer(i64::from(display.features)),
+                );
+                PlistValue::Dictionary(dict)
+            })
+            .collect();
+        PlistValue::Array(displays)
+    }
+
     fn audio_latencies_to_plist(&self) -> PlistValue {
-        let mut latency_entry: HashMap<String, PlistValue> = HashMap::new();
+        let mut latency_entry: PlistDict = PlistDict::new();
         latency_entry.insert("inputLatencyMicros".to_string(), PlistValue::Integer(0));
         latency_entry.insert(
             "outputLatencyMicros".to_string(),
@@ -445,3 +579,46 @@ impl DeviceCapabilities {
         PlistValue::Array(vec![PlistValue::Dictionary(latency_entry)])
     }
 }
+
+/// Derive a deterministic UUID (version 4 layout) from a device ID and salt
+fn derive_uuid(device_id: &str, salt: &[u8]) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut hasher = Sha256::new();
+    hasher.update(device_id.as_bytes());
+    hasher.update(salt);
+    let hash = hasher.finalize();
+
+    format!(
+        "{:02x}{:02x}{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}-{:02x}{:02x}{:02x}{:\
+         02x}{:02x}{:02x}",
+        hash[0],
+        hash[1],
+        hash[2],
+        hash[3],
+        hash[4],
+        hash[5],
+        (hash[6] & 0x0f) | 0x40,
+        hash[7],
+        (hash[8] & 0x3f) | 0x80,
+        hash[9],
+        hash[10],
+        hash[11],
+        hash[12],
+        hash[13],
+        hash[14],
+        hash[15]
+    )
+}
+
+/// Encode TXT entries as DNS TXT record data (length-prefixed `key=value` strings)
+fn encode_txt_record(entries: &[(String, String)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    for (key, value) in entries {
+        let entry = format!("{key}={value}");
+        let bytes = &entry.as_bytes()[..entry.len().min(255)];
+        data.push(u8::try_from(bytes.len()).unwrap_or(u8::MAX));
+        data.extend_from_slice(bytes);
+    }
+    data
+}
@@ -1,11 +1,9 @@
 //! /command Endpoint Handler
 
-use std::collections::HashMap;
-
 use super::body_handler::{PlistExt, encode_bplist_body, parse_bplist_body};
 use super::request_handler::{Ap2Event, Ap2HandleResult, Ap2RequestContext};
 use super::response_builder::Ap2ResponseBuilder;
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::{RtspRequest, StatusCode};
 
 /// Playback command types
@@ -104,7 +102,7 @@ pub fn handle_command(
 
     // Build response
     let response_plist = PlistValue::Dictionary({
-        let mut d = HashMap::new();
+        let mut d = PlistDict::new();
         d.insert("status".to_string(), PlistValue::Integer(0)); // Success
         d
     });
@@ -1,6 +1,6 @@
 //! Configuration for `AirPlay` 2 Receiver
 
-use super::features::{FeatureFlag, FeatureFlags, StatusFlags};
+use super::features::{AIRPLAY2_SPEAKER_FEATURES, FeatureFlag, FeatureFlags, StatusFlags};
 use crate::types::RaopCodec as AudioFormat;
 
 /// Configuration for an `AirPlay` 2 receiver instance
@@ -44,6 +44,9 @@ pub struct Ap2Config {
 
     /// Enable verbose protocol logging
     pub debug_logging: bool,
+
+    /// Network interfaces to advertise on (e.g. "eth0"); empty means all
+    pub interfaces: Vec<String>,
 }
 
 impl Default for Ap2Config {
@@ -62,6 +65,7 @@ impl Default for Ap2Config {
             buffer_size_ms: 2000,
             max_sessions: 1,
             debug_logging: false,
+            interfaces: Vec::new(),
         }
     }
 }
@@ -97,6 +101,13 @@ impl Ap2Config {
         self
     }
 
+    /// Only advertise on the given network interfaces
+    #[must_use]
+    pub fn with_interfaces(mut self, interfaces: Vec<String>) -> Self {
+        self.interfaces = interfaces;
+        self
+    }
+
     /// Generate a random device ID in MAC address format
     fn generate_device_id() -> String {
         use rand::Rng;
@@ -122,6 +133,11 @@ impl Ap2Config {
         flags.set(FeatureFlag::Photo);
         flags.set(FeatureFlag::UnifiedMediaControl);
 
+        if self.multi_room_enabled {
+            // Everything iOS expects from an AirPlay 2 speaker
+            flags = FeatureFlags::from_raw(flags.raw() | AIRPLAY2_SPEAKER_FEATURES);
+        }
+
         flags.raw()
     }
 
@@ -184,10 +200,10 @@ impl Ap2ConfigBuilder {
         }
     }
 
-    /// Set device name
+    /// Set device name (stored in Unicode NFC)
     #[must_use]
     pub fn name(mut self, name: impl Into<String>) -> Self {
-        self.config.name = name.into();
+        self.config.name = crate::discovery::normalize_name(&name.into());
         self
     }
 
@@ -212,6 +228,13 @@ impl Ap2ConfigBuilder {
         self
     }
 
+    /// Set network interfaces to advertise on
+    #[must_use]
+    pub fn interfaces(mut self, interfaces: Vec<String>) -> Self {
+        self.config.interfaces = interfaces;
+        self
+    }
+
     /// Set audio buffer size in milliseconds
     #[must_use]
     pub fn buffer_size_ms(mut self, ms: u32) -> Self {
@@ -96,6 +96,14 @@ impl FeatureFlag {
     }
 }
 
+/// Feature mask advertised by shipping `AirPlay` 2 speakers
+///
+/// iOS 17/18 only offers a receiver as an `AirPlay` 2 speaker, rather than
+/// falling back to RAOP, when the buffered audio, PTP and `HomeKit`/`CoreUtils`
+/// pairing bits in the upper word are present. This is the mask used by
+/// shairport-sync in `AirPlay` 2 mode (`0x405D4A00,0x1C340`).
+pub const AIRPLAY2_SPEAKER_FEATURES: u64 = 0x0001_C340_405D_4A00;
+
 /// Feature flag set builder
 #[derive(Debug, Clone, Default)]
 pub struct FeatureFlags {
@@ -119,10 +127,13 @@ impl FeatureFlags {
         flags.set(FeatureFlag::AudioRedundant);
         flags.set(FeatureFlag::SupportsVolume);
 
-        // Audio formats
+        // Audio formats; AAC only when the receiver can decode it
         flags.set(FeatureFlag::AudioFormatAlac);
-        flags.set(FeatureFlag::AudioFormatAacLc);
-        flags.set(FeatureFlag::AudioFormatAacEld);
+        #[cfg(feature = "aac-fdk")]
+        {
+            flags.set(FeatureFlag::AudioFormatAacLc);
+            flags.set(FeatureFlag::AudioFormatAacEld);
+        }
 
         // Authentication
         flags.set(FeatureFlag::AuthenticationSetup);
@@ -168,6 +179,12 @@ impl FeatureFlags {
         (self.flags & flag.mask()) != 0
     }
 
+    /// Create from a raw flags value
+    #[must_use]
+    pub fn from_raw(flags: u64) -> Self {
+        Self { flags }
+    }
+
     /// Get raw flags value
     #[must_use]
     pub fn raw(&self) -> u64 {
@@ -217,6 +234,8 @@ pub enum StatusFlag {
     DeviceLocked = 6,
     /// Bit 11: Accessory problems
     AccessoryProblems = 11,
+    /// Bit 17: A sender has a session with the receiver
+    ReceiverSessionActive = 17,
 }
 
 impl StatusFlag {
@@ -4,10 +4,11 @@
 
 use std::sync::Arc;
 
-use super::body_handler::encode_bplist_body;
+use super::body_handler::{encode_bplist_body, parse_bplist_body};
 use super::capabilities::DeviceCapabilities;
 use super::request_handler::{Ap2HandleResult, Ap2RequestContext};
 use super::response_builder::Ap2ResponseBuilder;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::{RtspRequest, StatusCode};
 
 /// Handler for GET /info endpoint
@@ -34,13 +35,16 @@ impl InfoEndpoint {
     ) -> Ap2HandleResult {
         tracing::debug!("Handling GET /info request");
 
-        // Check for qualifier header (optional, indicates specific info requested)
-        let qualifier = request.headers.get("X-Apple-Info-Qualifier");
-
-        // Generate capabilities plist
-        let plist = if let Some(_qualifier) = qualifier {
-            // Could filter based on qualifier
-            self.capabilities.to_plist()
+        // Stage 1 (before pairing): the sender asks for just the TXT record,
+        // either in a plist body or the qualifier header. Stage 2 (after
+        // pair-verify) has no qualifier and gets the full capabilities.
+        let plist = if Self::qualifiers(request).iter().any(|q| q == "txtAirPlay") {
+            let mut dict = PlistDict::new();
+            dict.insert(
+                "txtAirPlay".to_string(),
+                PlistValue::Data(self.capabilities.txt_airplay_data().unwrap_or_default()),
+            );
+            PlistValue::Dictionary(dict)
         } else {
             self.capabilities.to_plist()
         };
@@ -75,6 +79,33 @@ impl InfoEndpoint {
         }
     }
 
+    /// Qualifiers from the request body (`qualifier` array) or the
+    /// `X-Apple-Info-Qualifier` header
+    fn qualifiers(request: &RtspRequest) -> Vec<String> {
+        let mut qualifiers: Vec<String> = request
+            .headers
+            .get("X-Apple-Info-Qualifier")
+            .map(|header| header.split(',').map(|q| q.trim().to_string()).collect())
+            .unwrap_or_default();
+
+        if !request.body.is_empty() {
+            if let Ok(body) = parse_bplist_body(&request.body) {
+                if let Some(PlistValue::Array(items)) =
+                    body.as_dict().and_then(|dict| dict.get("qualifier"))
+                {
+                    qualifiers.extend(
+                        items
+                            .iter()
+                            .filter_map(PlistValue::as_str)
+                            .map(String::from),
+                    );
+                }
+            }
+        }
+
+        qualifiers
+    }
+
     /// Update capabilities (e.g., when configuration changes)
     pub fn update_capabilities(&mut self, capabilities: DeviceCapabilities) {
         self.capabilities = Arc::new(capabilities);
@@ -6,6 +6,7 @@
 use std::collections::BTreeMap;
 
 use super::rtp_receiver::AudioFrame;
+use super::stream::AudioStreamFormat;
 
 /// Jitter buffer configuration
 #[derive(Debug, Clone)]
@@ -20,6 +21,8 @@ pub struct JitterBufferConfig {
     pub sample_rate: u32,
     /// Channels
     pub channels: u8,
+    /// Samples per packet, used to size concealment for missing frames
+    pub frames_per_packet: u32,
 }
 
 impl Default for JitterBufferConfig {
@@ -30,6 +33,20 @@ impl Default for JitterBufferConfig {
             target_depth_ms: 200,
             sample_rate: 44100,
             channels: 2,
+            frames_per_packet: 352,
+        }
+    }
+}
+
+impl JitterBufferConfig {
+    /// Default depths with rate, channels and packet size from a negotiated format
+    #[must_use]
+    pub fn for_format(format: &AudioStreamFormat) -> Self {
+        Self {
+            sample_rate: format.sample_rate,
+            channels: format.channels,
+            frames_per_packet: format.samples_per_packet(),
+            ..Self::default()
         }
     }
 }
@@ -209,8 +226,8 @@ impl JitterBuffer {
                     // Generate silence for concealment.
                     // How much? We need `remaining_output_capacity` samples?
                     // But we also need to advance `playback_position` correctly.
-                    // Let's assume a "virtual" missing frame of 352 samples duration.
-                    let concealment_frames = 352;
+                    // Let's assume a "virtual" missing frame of one packet's duration.
+                    let concealment_frames = self.config.frames_per_packet as usize;
                     let concealment_samples = concealment_frames * channels;
 
                     // We can return as much silence as needed from this "virtual frame",
@@ -364,6 +381,12 @@ impl JitterBuffer {
         self.state = BufferState::Playing;
     }
 
+    /// Get the buffer configuration
+    #[must_use]
+    pub fn config(&self) -> &JitterBufferConfig {
+        &self.config
+    }
+
     /// Get current buffer state
     #[must_use]
     pub fn state(&self) -> BufferState {
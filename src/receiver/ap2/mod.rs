@@ -49,6 +49,6 @@ pub use receiver::{
     AirPlay2Receiver, ReceiverBuilder, ReceiverError, ReceiverEvent, ReceiverState,
 };
 pub use session_state::Ap2SessionState;
-pub use setup_handler::SetupHandler;
+pub use setup_handler::{SetupHandler, TeardownScope};
 pub use stream::StreamType;
 pub mod jitter_buffer;
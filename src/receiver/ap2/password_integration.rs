@@ -152,7 +152,7 @@ impl AuthenticationHandler {
             .build();
 
         Ap2HandleResult {
-            response: Ap2ResponseBuilder::error(StatusCode(503))
+            response: Ap2ResponseBuilder::error(StatusCode::SERVICE_UNAVAILABLE)
                 .cseq(cseq)
                 .header("Retry-After", &remaining.to_string())
                 .binary_body(response_tlv)
@@ -5,7 +5,10 @@ use tokio::sync::{RwLock, broadcast};
 
 use super::advertisement::Ap2ServiceAdvertiser;
 use super::config::Ap2Config;
+use super::request_handler::Ap2Event;
+use super::stream::StreamType;
 use crate::protocol::crypto::Ed25519KeyPair;
+use crate::receiver::session_manager::{SessionManager, SessionManagerConfig};
 
 /// `AirPlay` 2 Receiver
 ///
@@ -51,8 +54,11 @@ pub struct AirPlay2Receiver {
     state: Arc<RwLock<ReceiverState>>,
     event_tx: broadcast::Sender<ReceiverEvent>,
     shutdown_tx: Option<broadcast::Sender<()>>,
-    advertiser: Option<Ap2ServiceAdvertiser>,
+    advertiser: Option<Arc<Ap2ServiceAdvertiser>>,
+    sessions: Arc<SessionManager>,
     accept_task: Option<tokio::task::JoinHandle<()>>,
+    /// Status flag updates and stale session reaping
+    session_tasks: Vec<tokio::task::JoinHandle<()>>,
 }
 
 /// Receiver state
@@ -110,6 +116,14 @@ pub enum ReceiverEvent {
         /// The MIME type of the artwork
         mime_type: String,
     },
+    /// Sender tore down some streams but kept the session, typically
+    /// before setting up a new one
+    StreamStopped {
+        /// Streams that ended
+        streams: Vec<StreamType>,
+    },
+    /// Sender tore down the whole session
+    SessionEnded,
     /// Client disconnected
     Disconnected,
     /// Receiver stopped
@@ -121,6 +135,24 @@ pub enum ReceiverEvent {
     },
 }
 
+impl ReceiverEvent {
+    /// Receiver event announcing a request handler event, if it is one
+    /// applications see
+    #[must_use]
+    pub fn from_ap2_event(event: &Ap2Event) -> Option<Self> {
+        match event {
+            Ap2Event::PairingComplete { .. } => Some(Self::PairingComplete),
+            Ap2Event::StreamingStarted { .. } => Some(Self::StreamingStarted),
+            Ap2Event::VolumeChanged { volume } => Some(Self::VolumeChanged { volume_db: *volume }),
+            Ap2Event::StreamTeardown { streams } => Some(Self::StreamStopped {
+                streams: streams.clone(),
+            }),
+            Ap2Event::Teardown => Some(Self::SessionEnded),
+            _ => None,
+        }
+    }
+}
+
 impl AirPlay2Receiver {
     /// Create a new receiver with the given configuration
     #[must_use]
@@ -135,10 +167,39 @@ impl AirPlay2Receiver {
             event_tx,
             shutdown_tx: None,
             advertiser: None,
+            sessions: Arc::new(SessionManager::new(SessionManagerConfig::default())),
             accept_task: None,
+            session_tasks: Vec::new(),
         }
     }
 
+    /// Session manager tracking the sender's session
+    ///
+    /// Sessions started and ended here, or reaped after inactivity, are
+    /// reflected in the advertised status flags while the receiver runs.
+    #[must_use]
+    pub fn session_manager(&self) -> Arc<SessionManager> {
+        self.sessions.clone()
+    }
+
+    /// Change the password, or remove it with `None`
+    ///
+    /// The advertised status flags are updated at once if the receiver is
+    /// running.
+    ///
+    /// # Errors
+    /// Returns an error if the advertisement cannot be updated.
+    pub async fn set_password(&mut self, password: Option<String>) -> Result<(), ReceiverError> {
+        self.config.password = password;
+        if let Some(advertiser) = &self.advertiser {
+            advertiser
+                .set_password_required(self.config.has_password())
+                .await
+                .map_err(|e| ReceiverError::Advertisement(e.to_string()))?;
+        }
+        Ok(())
+    }
+
     /// Subscribe to receiver events
     #[must_use]
     pub fn subscribe(&self) -> broadcast::Receiver<ReceiverEvent> {
@@ -164,12 +225,23 @@ impl AirPlay2Receiver {
 
         // Start mDNS advertisement
         let public_key = *self.identity.public_key().as_bytes();
-        let advertiser = Ap2ServiceAdvertiser::new(self.config.clone(), public_key)
-            .map_err(|e| ReceiverError::Advertisement(e.to_string()))?;
+        let advertiser = Arc::new(
+            Ap2ServiceAdvertiser::new(self.config.clone(), public_key)
+                .map_err(|e| ReceiverError::Advertisement(e.to_string()))?,
+        );
+        // Subscribe first so a session starting meanwhile is not missed
+        let session_events = self.sessions.subscribe();
+        if self.sessions.has_active_session().await {
+            let _ = advertiser.set_session_active(true).await;
+        }
         advertiser
             .start()
             .await
             .map_err(|e| ReceiverError::Advertisement(e.to_string()))?;
+        self.session_tasks = vec![
+            advertiser.track_sessions(session_events),
+            self.sessions.start_timeout_monitor(),
+        ];
         self.advertiser = Some(advertiser);
 
         // Start TCP listener
@@ -235,6 +307,9 @@ impl AirPlay2Receiver {
         drop(state);
 
         // Stop advertisement
+        for task in self.session_tasks.drain(..) {
+            task.abort();
+        }
         if let Some(advertiser) = self.advertiser.take() {
             let _ = advertiser.stop().await;
         }
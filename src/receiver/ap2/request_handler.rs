@@ -6,8 +6,11 @@
 use super::request_router::{Ap2Endpoint, Ap2RequestType, RtspMethod};
 use super::response_builder::Ap2ResponseBuilder;
 use super::session_state::Ap2SessionState;
-use super::stream::{AudioStreamFormat, EncryptionType, TimingPeerInfo, TimingProtocol};
-use crate::protocol::rtsp::{RtspRequest, StatusCode};
+use super::stream::{
+    AudioStreamFormat, EncryptionType, StreamType, TimingPeerInfo, TimingProtocol,
+};
+use crate::protocol::rtsp::headers::PublicMethods;
+use crate::protocol::rtsp::{Method, RtspRequest, StatusCode};
 
 /// Result of handling a request
 #[derive(Debug)]
@@ -79,6 +82,12 @@ pub enum Ap2Event {
         until_timestamp: Option<u32>,
     },
 
+    /// Some streams torn down; the session stays up
+    StreamTeardown {
+        /// Streams that ended
+        streams: Vec<StreamType>,
+    },
+
     /// Session teardown
     Teardown,
 
@@ -246,24 +255,23 @@ fn handle_endpoint(
 }
 
 fn handle_options(cseq: u32) -> Ap2HandleResult {
-    let methods = [
-        "OPTIONS",
-        "GET",
-        "POST",
-        "SETUP",
-        "RECORD",
-        "PAUSE",
-        "FLUSH",
-        "TEARDOWN",
-        "GET_PARAMETER",
-        "SET_PARAMETER",
-    ]
-    .join(", ");
+    let methods = PublicMethods(vec![
+        Method::Options,
+        Method::Get,
+        Method::Post,
+        Method::Setup,
+        Method::Record,
+        Method::Pause,
+        Method::Flush,
+        Method::Teardown,
+        Method::GetParameter,
+        Method::SetParameter,
+    ]);
 
     Ap2HandleResult {
         response: Ap2ResponseBuilder::ok()
             .cseq(cseq)
-            .header("Public", &methods)
+            .public(&methods)
             .server("366.0")
             .encode(),
         new_state: None,
@@ -3,8 +3,9 @@
 //! Adds support for binary plist bodies and `AirPlay` 2-specific headers.
 
 use super::body_handler::{content_types, encode_bplist_body};
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::StatusCode;
+use crate::protocol::rtsp::headers::PublicMethods;
 use crate::protocol::rtsp::server_codec::ResponseBuilder;
 
 /// Extended response builder for `AirPlay` 2
@@ -43,6 +44,13 @@ impl Ap2ResponseBuilder {
         self
     }
 
+    /// Set the Public header listing supported methods
+    #[must_use]
+    pub fn public(mut self, methods: &PublicMethods) -> Self {
+        self.inner = self.inner.public(methods);
+        self
+    }
+
     /// Add a custom header
     #[must_use]
     pub fn header(mut self, name: &str, value: &str) -> Self {
@@ -114,8 +122,7 @@ impl Ap2ResponseBuilder {
     /// Create response for authentication required
     #[must_use]
     pub fn auth_required(cseq: u32) -> Self {
-        Self::error(StatusCode(470)) // Connection Authorization Required
-            .cseq(cseq)
+        Self::error(StatusCode::CONNECTION_AUTHORIZATION_REQUIRED).cseq(cseq)
     }
 
     /// Create response for bad request with error dict
@@ -128,9 +135,7 @@ impl Ap2ResponseBuilder {
         code: i64,
         message: &str,
     ) -> Result<Self, Ap2ResponseError> {
-        use std::collections::HashMap;
-
-        let mut error_dict = HashMap::new();
+        let mut error_dict = PlistDict::new();
         error_dict.insert("code".to_string(), PlistValue::Integer(code));
         error_dict.insert(
             "message".to_string(),
@@ -2,6 +2,7 @@
 //!
 //! Decrypts RTP audio payloads using ChaCha20-Poly1305 AEAD.
 
+use super::stream::{AudioStreamFormat, StreamCodec};
 use crate::protocol::crypto::{ChaCha20Poly1305Cipher, Nonce};
 use crate::protocol::rtp::RtpPacket;
 
@@ -249,3 +250,190 @@ impl AudioDecoder for AlacDecoder {
         self.channels
     }
 }
+
+/// AAC (LC and ELD) decoder using fdk-aac
+///
+/// `AirPlay` carries raw AAC access units without ADTS headers, so the
+/// decoder is primed with an `AudioSpecificConfig` derived from SETUP.
+#[cfg(feature = "aac-fdk")]
+pub struct AacDecoder {
+    decoder: fdk_aac::dec::Decoder,
+    sample_rate: u32,
+    channels: u8,
+    pcm: Vec<i16>,
+}
+
+#[cfg(feature = "aac-fdk")]
+impl AacDecoder {
+    /// Largest frame the decoder can emit (samples per channel)
+    const MAX_FRAME_SAMPLES: usize = 2048;
+
+    /// Create a new AAC decoder
+    ///
+    /// # Errors
+    /// Returns `AudioDecodeError` if the format cannot be described or the
+    /// decoder rejects the configuration.
+    pub fn new(
+        codec: StreamCodec,
+        sample_rate: u32,
+        channels: u8,
+        frames_per_packet: u32,
+    ) -> Result<Self, AudioDecodeError> {
+        let asc = Self::audio_specific_config(codec, sample_rate, channels, frames_per_packet)
+            .ok_or(AudioDecodeError::UnsupportedFormat)?;
+
+        let mut decoder = fdk_aac::dec::Decoder::new(fdk_aac::dec::Transport::Raw);
+        decoder
+            .config_raw(&asc)
+            .map_err(|e| AudioDecodeError::DecoderError(e.to_string()))?;
+
+        Ok(Self {
+            decoder,
+            sample_rate,
+            channels,
+            pcm: vec![0i16; Self::MAX_FRAME_SAMPLES * usize::from(channels.max(1))],
+        })
+    }
+
+    /// Build the MPEG-4 `AudioSpecificConfig` for an `AirPlay` AAC stream
+    #[must_use]
+    pub fn audio_specific_config(
+        codec: StreamCodec,
+        sample_rate: u32,
+        channels: u8,
+        frames_per_packet: u32,
+    ) -> Option<Vec<u8>> {
+        let freq_index: u32 = match sample_rate {
+            96000 => 0,
+            88200 => 1,
+            64000 => 2,
+            48000 => 3,
+            44100 => 4,
+            32000 => 5,
+            24000 => 6,
+            22050 => 7,
+            16000 => 8,
+            12000 => 9,
+            11025 => 10,
+            8000 => 11,
+            _ => return None,
+        };
+        if !(1..=7).contains(&channels) {
+            return None;
+        }
+
+        let mut bits = BitWriter::default();
+        match codec {
+            StreamCodec::AacLc => {
+                bits.write(2, 5); // AOT_AAC_LC
+                bits.write(freq_index, 4);
+                bits.write(u32::from(channels), 4);
+                // GASpecificConfig: frameLengthFlag, dependsOnCoreCoder, extensionFlag
+                bits.write(u32::from(frames_per_packet == 960), 1);
+                bits.write(0, 2);
+            }
+            StreamCodec::AacEld => {
+                bits.write(31, 5); // escape
+                bits.write(39 - 32, 6); // AOT_ER_AAC_ELD
+                bits.write(freq_index, 4);
+                bits.write(u32::from(channels), 4);
+                // ELDSpecificConfig: frameLengthFlag (480 vs 512), resilience flags,
+                // ldSbrPresentFlag, ELDEXT_TERM
+                bits.write(u32::from(frames_per_packet == 480), 1);
+                bits.write(0, 3);
+                bits.write(0, 1);
+                bits.write(0, 4);
+                bits.write(0, 2); // epConfig
+            }
+            _ => return None,
+        }
+        Some(bits.finish())
+    }
+}
+
+#[cfg(feature = "aac-fdk")]
+impl AudioDecoder for AacDecoder {
+    fn decode(&mut self, data: &[u8]) -> Result<Vec<i16>, AudioDecodeError> {
+        if data.is_empty() {
+            return Err(AudioDecodeError::InvalidData);
+        }
+
+        self.decoder
+            .fill(data)
+            .map_err(|e| AudioDecodeError::DecoderError(e.to_string()))?;
+        self.decoder
+            .decode_frame(&mut self.pcm)
+            .map_err(|e| AudioDecodeError::DecoderError(e.to_string()))?;
+
+        let len = self.decoder.decoded_frame_size().min(self.pcm.len());
+        Ok(self.pcm[..len].to_vec())
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+    fn channels(&self) -> u8 {
+        self.channels
+    }
+}
+
+/// Create the decoder matching a negotiated stream format
+///
+/// # Errors
+/// Returns `AudioDecodeError::UnsupportedFormat` for codecs the receiver
+/// cannot decode, including AAC in builds without fdk-aac.
+pub fn create_decoder(
+    format: &AudioStreamFormat,
+) -> Result<Box<dyn AudioDecoder>, AudioDecodeError> {
+    match format.stream_codec() {
+        StreamCodec::Pcm => Ok(Box::new(PcmDecoder::new(
+            format.sample_rate,
+            format.channels,
+            format.bits_per_sample,
+        ))),
+        StreamCodec::Alac => Ok(Box::new(AlacDecoder::new(
+            format.sample_rate,
+            format.channels,
+            &[],
+        )?)),
+        #[cfg(feature = "aac-fdk")]
+        codec @ (StreamCodec::AacLc | StreamCodec::AacEld) => Ok(Box::new(AacDecoder::new(
+            codec,
+            format.sample_rate,
+            format.channels,
+            format.samples_per_packet(),
+        )?)),
+        #[cfg(not(feature = "aac-fdk"))]
+        StreamCodec::AacLc | StreamCodec::AacEld => Err(AudioDecodeError::UnsupportedFormat),
+        StreamCodec::Opus | StreamCodec::Unknown(_) => Err(AudioDecodeError::UnsupportedFormat),
+    }
+}
+
+/// MSB-first bit writer for building codec configuration blobs
+#[cfg(feature = "aac-fdk")]
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    used: u8,
+}
+
+#[cfg(feature = "aac-fdk")]
+impl BitWriter {
+    fn write(&mut self, value: u32, bits: u8) {
+        for i in (0..bits).rev() {
+            if self.used % 8 == 0 {
+                self.bytes.push(0);
+                self.used = 0;
+            }
+            let bit = u8::from((value >> i) & 1 == 1);
+            if let Some(last) = self.bytes.last_mut() {
+                *last |= bit << (7 - self.used);
+            }
+            self.used += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
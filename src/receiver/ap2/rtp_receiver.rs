@@ -6,7 +6,8 @@ use tokio::net::UdpSocket;
 use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
-use super::rtp_decryptor::{Ap2RtpDecryptor, AudioDecoder, DecryptionError};
+use super::rtp_decryptor::{Ap2RtpDecryptor, AudioDecoder, DecryptionError, create_decoder};
+use super::stream::{AudioStreamFormat, StreamCodec};
 use crate::protocol::rtp::RtpPacket;
 
 /// Received audio frame containing decoded PCM samples
@@ -39,9 +40,30 @@ pub struct RtpReceiverConfig {
     pub codec_type: u8,
 }
 
+impl RtpReceiverConfig {
+    /// Build a configuration from the format negotiated in SETUP
+    #[must_use]
+    pub fn from_stream_format(port: u16, key: [u8; 32], format: &AudioStreamFormat) -> Self {
+        let codec_type = match format.stream_codec() {
+            StreamCodec::Pcm => 100,
+            StreamCodec::Alac => 96,
+            // Payload types for the remaining codecs are not used for dispatch
+            _ => 0,
+        };
+
+        Self {
+            port,
+            key,
+            sample_rate: format.sample_rate,
+            channels: format.channels,
+            bits_per_sample: format.bits_per_sample,
+            codec_type,
+        }
+    }
+}
+
 /// RTP receiver
 pub struct RtpReceiver {
-    #[allow(dead_code, reason = "Reserved for future use")]
     config: RtpReceiverConfig,
     decryptor: Ap2RtpDecryptor,
     decoder: Box<dyn AudioDecoder>,
@@ -79,8 +101,6 @@ impl RtpReceiver {
         config: RtpReceiverConfig,
         frame_tx: mpsc::Sender<AudioFrame>,
     ) -> Result<Self, ReceiverError> {
-        let decryptor = Ap2RtpDecryptor::new(config.key);
-
         // Create appropriate decoder based on codec type
         let decoder: Box<dyn AudioDecoder> = match config.codec_type {
             100 => Box::new(super::rtp_decryptor::PcmDecoder::new(
@@ -95,13 +115,44 @@ impl RtpReceiver {
             _ => return Err(ReceiverError::UnsupportedCodec(config.codec_type)),
         };
 
-        Ok(Self {
+        Ok(Self::with_decoder(config, decoder, frame_tx))
+    }
+
+    /// Create a receiver configured from the format negotiated in SETUP
+    ///
+    /// Selects the PCM, ALAC or AAC decoder matching the sender's `ct` and
+    /// `audioFormat` fields.
+    ///
+    /// # Errors
+    /// Returns `ReceiverError` if no decoder supports the format.
+    pub fn for_format(
+        port: u16,
+        key: [u8; 32],
+        format: &AudioStreamFormat,
+        frame_tx: mpsc::Sender<AudioFrame>,
+    ) -> Result<Self, ReceiverError> {
+        let decoder =
+            create_decoder(format).map_err(|e| ReceiverError::DecodeError(e.to_string()))?;
+        let config = RtpReceiverConfig::from_stream_format(port, key, format);
+        Ok(Self::with_decoder(config, decoder, frame_tx))
+    }
+
+    /// Create a receiver with an explicit decoder
+    #[must_use]
+    pub fn with_decoder(
+        config: RtpReceiverConfig,
+        decoder: Box<dyn AudioDecoder>,
+        frame_tx: mpsc::Sender<AudioFrame>,
+    ) -> Self {
+        let decryptor = Ap2RtpDecryptor::new(config.key);
+
+        Self {
             config,
             decryptor,
             decoder,
             frame_tx,
             stats: ReceiverStats::default(),
-        })
+        }
     }
 
     /// Process a received UDP packet
@@ -192,6 +243,18 @@ impl RtpReceiver {
         Ok(())
     }
 
+    /// Get the receiver configuration
+    #[must_use]
+    pub fn config(&self) -> &RtpReceiverConfig {
+        &self.config
+    }
+
+    /// Get the decoder for the negotiated format
+    #[must_use]
+    pub fn decoder(&self) -> &dyn AudioDecoder {
+        self.decoder.as_ref()
+    }
+
     /// Get receiver statistics
     #[must_use]
     pub fn stats(&self) -> &ReceiverStats {
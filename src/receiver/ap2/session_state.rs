@@ -168,6 +168,9 @@ impl Ap2SessionState {
             // From Paused
             (Self::Paused, Self::Streaming) => true,
 
+            // Stream-only TEARDOWN keeps the event and timing channels
+            (Self::SetupPhase2 | Self::Streaming | Self::Paused, Self::SetupPhase1) => true,
+
             _ => false,
         };
 
@@ -1,21 +1,24 @@
 //! Multi-phase SETUP handler for `AirPlay` 2
 //!
 //! Handles the two-phase SETUP process that configures event, timing,
-//! and audio channels.
+//! and audio channels, and the TEARDOWN that takes them down again.
 
-use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+use tokio::sync::mpsc;
 use tracing::{error, info, warn};
 
 use super::body_handler::{encode_bplist_body, parse_bplist_body};
+use super::jitter_buffer::{JitterBuffer, JitterBufferConfig};
 use super::request_handler::{Ap2Event, Ap2HandleResult, Ap2RequestContext};
 use super::response_builder::Ap2ResponseBuilder;
+use super::rtp_receiver::{AudioFrame, ReceiverError, RtpReceiver};
 use super::session_state::Ap2SessionState;
 use super::stream::{
-    AudioStreamFormat, EncryptionType, StreamType, TimingPeerInfo, TimingProtocol,
+    AudioFormatDescriptor, AudioStreamFormat, EncryptionType, StreamCodec, StreamType,
+    TimingPeerInfo, TimingProtocol,
 };
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::{RtspRequest, StatusCode};
 
 /// Parsed SETUP request
@@ -35,6 +38,54 @@ pub struct SetupRequest {
     pub shared_key: Option<Vec<u8>>,
 }
 
+/// What a TEARDOWN request ends
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TeardownScope {
+    /// Only the listed streams; the session, pairing and event and timing
+    /// channels stay up for a new audio SETUP
+    Streams(Vec<StreamType>),
+    /// The whole session
+    Session,
+}
+
+impl TeardownScope {
+    /// Read the scope from a TEARDOWN body
+    ///
+    /// A body listing `streams` ends just those; an empty or unreadable
+    /// body ends the session.
+    #[must_use]
+    pub fn parse(body: &[u8]) -> Self {
+        if body.is_empty() {
+            return Self::Session;
+        }
+        let Ok(PlistValue::Dictionary(dict)) = parse_bplist_body(body) else {
+            warn!("Unreadable TEARDOWN body, ending the session");
+            return Self::Session;
+        };
+
+        let streams: Vec<StreamType> = match dict.get("streams") {
+            Some(PlistValue::Array(streams)) => streams
+                .iter()
+                .filter_map(|stream| match stream {
+                    PlistValue::Dictionary(stream) => match stream.get("type") {
+                        Some(PlistValue::Integer(i)) => u32::try_from(*i).ok(),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .map(StreamType::from)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        if streams.is_empty() {
+            Self::Session
+        } else {
+            Self::Streams(streams)
+        }
+    }
+}
+
 /// Individual stream request
 #[derive(Debug, Clone)]
 pub struct StreamRequest {
@@ -137,9 +188,7 @@ impl SetupRequest {
         })
     }
 
-    fn parse_streams(
-        dict: &HashMap<String, PlistValue>,
-    ) -> Result<Vec<StreamRequest>, SetupParseError> {
+    fn parse_streams(dict: &PlistDict) -> Result<Vec<StreamRequest>, SetupParseError> {
         let streams_value = dict
             .get("streams")
             .ok_or(SetupParseError::MissingField("streams"))?;
@@ -198,75 +247,52 @@ impl SetupRequest {
         Ok(streams)
     }
 
-    fn parse_audio_format(dict: &HashMap<String, PlistValue>) -> Option<AudioStreamFormat> {
-        let codec = dict.get("ct").and_then(|v| {
-            if let PlistValue::Integer(i) = v {
-                u32::try_from(*i).ok()
+    fn parse_audio_format(dict: &PlistDict) -> Option<AudioStreamFormat> {
+        let int = |key: &str| {
+            if let Some(PlistValue::Integer(i)) = dict.get(key) {
+                Some(*i)
             } else {
                 None
             }
-        })?;
+        };
+
+        // `audioFormat` is a single-bit mask describing codec, rate, depth and channels
+        let audio_format = int("audioFormat").and_then(|i| u64::try_from(i).ok());
+        let descriptor = audio_format.and_then(AudioFormatDescriptor::from_mask);
+
+        // `ct` is authoritative; fall back to the codec implied by `audioFormat`
+        let codec = int("ct")
+            .and_then(|i| u32::try_from(i).ok())
+            .or_else(|| descriptor.map(|d| d.codec.compression_type()))?;
+        let spf = int("spf").and_then(|i| u32::try_from(i).ok());
 
         Some(AudioStreamFormat {
             codec,
-            sample_rate: dict
-                .get("sr")
-                .and_then(|v| {
-                    if let PlistValue::Integer(i) = v {
-                        u32::try_from(*i).ok()
-                    } else {
-                        None
-                    }
-                })
+            sample_rate: int("sr")
+                .and_then(|i| u32::try_from(i).ok())
+                .or(descriptor.map(|d| d.sample_rate))
                 .unwrap_or(44100),
-            channels: dict
-                .get("ch")
-                .and_then(|v| {
-                    if let PlistValue::Integer(i) = v {
-                        u8::try_from(*i).ok()
-                    } else {
-                        None
-                    }
-                })
+            channels: int("ch")
+                .and_then(|i| u8::try_from(i).ok())
+                .or(descriptor.map(|d| d.channels))
                 .unwrap_or(2),
-            bits_per_sample: dict
-                .get("ss")
-                .and_then(|v| {
-                    if let PlistValue::Integer(i) = v {
-                        u8::try_from(*i).ok()
-                    } else {
-                        None
-                    }
-                })
+            bits_per_sample: int("ss")
+                .and_then(|i| u8::try_from(i).ok())
+                .or(descriptor.map(|d| d.bits_per_sample))
                 .unwrap_or(16),
-            frames_per_packet: dict
-                .get("fp")
-                .and_then(|v| {
-                    if let PlistValue::Integer(i) = v {
-                        u32::try_from(*i).ok()
-                    } else {
-                        None
-                    }
-                })
-                .unwrap_or(352),
-            compression_type: dict.get("compressionType").and_then(|v| {
-                if let PlistValue::Integer(i) = v {
-                    u32::try_from(*i).ok()
-                } else {
-                    None
-                }
-            }),
-            spf: dict.get("spf").and_then(|v| {
-                if let PlistValue::Integer(i) = v {
-                    u32::try_from(*i).ok()
-                } else {
-                    None
-                }
-            }),
+            frames_per_packet: int("fp")
+                .and_then(|i| u32::try_from(i).ok())
+                .or(spf)
+                .unwrap_or_else(|| {
+                    StreamCodec::from_compression_type(codec).default_frames_per_packet()
+                }),
+            compression_type: int("compressionType").and_then(|i| u32::try_from(i).ok()),
+            spf,
+            audio_format,
         })
     }
 
-    fn parse_timing_peer_info(dict: &HashMap<String, PlistValue>) -> Option<TimingPeerInfo> {
+    fn parse_timing_peer_info(dict: &PlistDict) -> Option<TimingPeerInfo> {
         let peer_info = dict.get("timingPeerInfo")?;
         let PlistValue::Dictionary(info_dict) = peer_info else {
             return None;
@@ -422,7 +448,7 @@ impl SetupResponse {
     /// Convert to binary plist
     #[must_use]
     pub fn to_plist(&self) -> PlistValue {
-        let mut dict: HashMap<String, PlistValue> = HashMap::new();
+        let mut dict: PlistDict = PlistDict::new();
 
         // Event port
         if let Some(port) = self.event_port {
@@ -464,7 +490,7 @@ impl SetupResponse {
             .streams
             .iter()
             .map(|s| {
-                let mut stream_dict: HashMap<String, PlistValue> = HashMap::new();
+                let mut stream_dict: PlistDict = PlistDict::new();
                 stream_dict.insert(
                     "type".to_string(),
                     PlistValue::Integer(i64::from(s.stream_type)),
@@ -585,6 +611,8 @@ pub struct SetupHandler {
     audio_latency_samples: u32,
     /// Allocated ports for current session
     session_ports: Arc<Mutex<SessionPorts>>,
+    /// Audio pipeline built by the last phase 2 SETUP
+    stream_pipeline: Arc<Mutex<Option<StreamPipeline>>>,
 }
 
 /// Setup phases
@@ -614,6 +642,42 @@ pub struct SessionPorts {
     pub audio_control_port: Option<u16>,
 }
 
+/// Decoded frames the receiver can queue ahead of the jitter buffer
+const FRAME_CHANNEL_CAPACITY: usize = 256;
+
+/// Receive pipeline for the audio stream described in SETUP
+pub struct StreamPipeline {
+    /// Format the sender described
+    pub format: AudioStreamFormat,
+    /// Decrypts and decodes packets arriving on the data port
+    pub receiver: RtpReceiver,
+    /// Orders decoded frames for playback
+    pub jitter_buffer: JitterBuffer,
+    /// Frames from `receiver`, to be pushed into `jitter_buffer`
+    pub frames: mpsc::Receiver<AudioFrame>,
+}
+
+impl StreamPipeline {
+    /// Build the receiver, decoder and jitter buffer for a stream format
+    ///
+    /// # Errors
+    /// Returns `ReceiverError` if no decoder supports the format.
+    pub fn for_format(
+        format: &AudioStreamFormat,
+        data_port: u16,
+        key: [u8; 32],
+    ) -> Result<Self, ReceiverError> {
+        let (frame_tx, frames) = mpsc::channel(FRAME_CHANNEL_CAPACITY);
+
+        Ok(Self {
+            format: format.clone(),
+            receiver: RtpReceiver::for_format(data_port, key, format, frame_tx)?,
+            jitter_buffer: JitterBuffer::new(JitterBufferConfig::for_format(format)),
+            frames,
+        })
+    }
+}
+
 impl SetupHandler {
     /// Create a new SETUP handler
     #[must_use]
@@ -626,9 +690,20 @@ impl SetupHandler {
             current_phase: Arc::new(Mutex::new(SetupPhase::None)),
             audio_latency_samples,
             session_ports: Arc::new(Mutex::new(SessionPorts::default())),
+            stream_pipeline: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Take the audio pipeline built by the last phase 2 SETUP
+    ///
+    /// # Panics
+    ///
+    /// Panics if the internal lock is poisoned.
+    #[must_use]
+    pub fn take_stream_pipeline(&self) -> Option<StreamPipeline> {
+        self.stream_pipeline.lock().unwrap().take()
+    }
+
     /// Handle SETUP request
     pub fn handle(
         &self,
@@ -735,6 +810,13 @@ impl SetupHandler {
     }
 
     fn handle_phase2(&self, request: SetupRequest, cseq: u32) -> Ap2HandleResult {
+        // We assume the first audio stream is the relevant one for configuration
+        let audio_format = request
+            .streams
+            .iter()
+            .find(|s| matches!(s.stream_type, StreamType::Audio | StreamType::BufferedAudio))
+            .and_then(|s| s.audio_format.clone());
+
         let mut allocator = self.port_allocator.lock().unwrap();
         let mut session_ports = self.session_ports.lock().unwrap();
 
@@ -752,9 +834,16 @@ impl SetupHandler {
             }
         };
 
-        // Store allocated ports
+        let pipeline = Self::build_pipeline(
+            audio_format.as_ref(),
+            request.shared_key.as_deref(),
+            data_port,
+        );
+
+        // Store allocated ports and pipeline
         session_ports.audio_data_port = Some(data_port);
         session_ports.audio_control_port = Some(control_port);
+        *self.stream_pipeline.lock().unwrap() = pipeline;
 
         // Update phase
         *self.current_phase.lock().unwrap() = SetupPhase::Phase2Complete;
@@ -781,15 +870,6 @@ impl SetupHandler {
             data_port, control_port, self.audio_latency_samples
         );
 
-        // Extract audio stream info
-        // We assume the first audio stream is the relevant one for configuration
-        let audio_stream = request
-            .streams
-            .iter()
-            .find(|s| matches!(s.stream_type, StreamType::Audio | StreamType::BufferedAudio));
-
-        let audio_format = audio_stream.and_then(|s| s.audio_format.clone());
-
         Ap2HandleResult {
             response: Ap2ResponseBuilder::ok()
                 .cseq(cseq)
@@ -808,9 +888,41 @@ impl SetupHandler {
         }
     }
 
+    /// Build the pipeline for the described format, keyed with `shk`
+    ///
+    /// Without a format, a 32-byte key or a decoder for the format, the
+    /// stream is set up without a pipeline.
+    fn build_pipeline(
+        format: Option<&AudioStreamFormat>,
+        shared_key: Option<&[u8]>,
+        data_port: u16,
+    ) -> Option<StreamPipeline> {
+        let format = format?;
+        let Some(key) = shared_key.and_then(|k| <[u8; 32]>::try_from(k).ok()) else {
+            warn!("SETUP has no 32-byte shk, audio pipeline not built");
+            return None;
+        };
+
+        let pipeline = match StreamPipeline::for_format(format, data_port, key) {
+            Ok(p) => p,
+            Err(e) => {
+                warn!("Cannot decode the SETUP audio format: {e}");
+                return None;
+            }
+        };
+        info!(
+            "Audio pipeline: {:?} {} Hz, {} ch, {} samples/packet",
+            format.stream_codec(),
+            format.sample_rate,
+            format.channels,
+            format.samples_per_packet()
+        );
+        Some(pipeline)
+    }
+
     fn allocation_error(cseq: u32, error: PortAllocationError) -> Ap2HandleResult {
         Ap2HandleResult {
-            response: Ap2ResponseBuilder::error(StatusCode(453)) // Not Enough Bandwidth
+            response: Ap2ResponseBuilder::error(StatusCode::NOT_ENOUGH_BANDWIDTH)
                 .cseq(cseq)
                 .encode(),
             new_state: None,
@@ -819,6 +931,71 @@ impl SetupHandler {
         }
     }
 
+    /// Handle TEARDOWN request
+    ///
+    /// A stream teardown releases the audio ports and returns the session to
+    /// [`Ap2SessionState::SetupPhase1`], ready for the sender to set up a new
+    /// stream; a session teardown releases everything.
+    pub fn handle_teardown(
+        &self,
+        request: &RtspRequest,
+        cseq: u32,
+        context: &Ap2RequestContext<'_>,
+    ) -> Ap2HandleResult {
+        let response = Ap2ResponseBuilder::ok().cseq(cseq).encode();
+
+        match TeardownScope::parse(&request.body) {
+            TeardownScope::Streams(streams) => {
+                info!("TEARDOWN of streams {:?}", streams);
+                self.release_audio_ports();
+
+                let new_state = matches!(
+                    context.state,
+                    Ap2SessionState::SetupPhase2
+                        | Ap2SessionState::Streaming
+                        | Ap2SessionState::Paused
+                )
+                .then_some(Ap2SessionState::SetupPhase1);
+
+                Ap2HandleResult {
+                    response,
+                    new_state,
+                    event: Some(Ap2Event::StreamTeardown { streams }),
+                    error: None,
+                }
+            }
+            TeardownScope::Session => {
+                info!("TEARDOWN of session");
+                self.cleanup();
+
+                Ap2HandleResult {
+                    response,
+                    new_state: Some(Ap2SessionState::Teardown),
+                    event: Some(Ap2Event::Teardown),
+                    error: None,
+                }
+            }
+        }
+    }
+
+    fn release_audio_ports(&self) {
+        let mut allocator = self.port_allocator.lock().unwrap();
+        let mut session_ports = self.session_ports.lock().unwrap();
+
+        if let Some(port) = session_ports.audio_data_port.take() {
+            allocator.release(port);
+        }
+        if let Some(port) = session_ports.audio_control_port.take() {
+            allocator.release(port);
+        }
+        self.stream_pipeline.lock().unwrap().take();
+
+        let mut phase = self.current_phase.lock().unwrap();
+        if matches!(*phase, SetupPhase::Phase2Complete) {
+            *phase = SetupPhase::Phase1Complete;
+        }
+    }
+
     /// Release all ports for session cleanup
     ///
     /// # Panics
@@ -840,6 +1017,7 @@ impl SetupHandler {
         if let Some(port) = session_ports.audio_control_port {
             allocator.release(port);
         }
+        self.stream_pipeline.lock().unwrap().take();
 
         *self.current_phase.lock().unwrap() = SetupPhase::None;
     }
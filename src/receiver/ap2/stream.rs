@@ -2,6 +2,8 @@
 
 use std::net::SocketAddr;
 
+pub use crate::audio::format::{AudioFormatDescriptor, StreamCodec};
+
 /// Stream types in SETUP
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StreamType {
@@ -89,7 +91,7 @@ pub struct TimingPeerInfo {
 /// Audio stream format parameters
 #[derive(Debug, Clone)]
 pub struct AudioStreamFormat {
-    /// Codec type (96=ALAC, 97=AAC, etc.)
+    /// Compression type from `ct` (0x1=PCM, 0x2=ALAC, 0x4=AAC-LC, 0x8=AAC-ELD)
     pub codec: u32,
     /// Sample rate (Hz)
     pub sample_rate: u32,
@@ -103,4 +105,20 @@ pub struct AudioStreamFormat {
     pub compression_type: Option<u32>,
     /// Spf (samples per frame)
     pub spf: Option<u32>,
+    /// Raw `audioFormat` bitmask, if the sender provided one
+    pub audio_format: Option<u64>,
+}
+
+impl AudioStreamFormat {
+    /// Codec for this stream
+    #[must_use]
+    pub fn stream_codec(&self) -> StreamCodec {
+        StreamCodec::from_compression_type(self.codec)
+    }
+
+    /// Samples per packet, preferring the sender's `spf`
+    #[must_use]
+    pub fn samples_per_packet(&self) -> u32 {
+        self.spf.unwrap_or(self.frames_per_packet)
+    }
 }
@@ -1,7 +1,15 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
 use base64::Engine;
 
-use crate::receiver::ap2::advertisement::{Ap2TxtRecord, txt_keys};
+use crate::receiver::ap2::advertisement::{Ap2ServiceAdvertiser, Ap2TxtRecord, txt_keys};
 use crate::receiver::ap2::config::Ap2Config;
+use crate::receiver::ap2::features::StatusFlag;
+use crate::receiver::session_manager::{SessionManager, SessionManagerConfig};
+
+const SESSION_ACTIVE: u32 = 1 << 17;
 
 #[test]
 fn test_txt_record_contains_required_fields() {
@@ -60,3 +68,62 @@ fn test_public_key_base64_encoded() {
     assert_eq!(decoded.len(), 32);
     assert_eq!(decoded, public_key.to_vec());
 }
+
+#[test]
+fn test_session_status_in_txt() {
+    let config = Ap2Config::new("Test Speaker");
+    let mut txt = Ap2TxtRecord::from_config(&config, &[0u8; 32]);
+    let idle = txt.status_flags();
+    assert_eq!(idle & SESSION_ACTIVE, 0);
+
+    txt.update_session_status(true);
+    assert_eq!(txt.status_flags(), idle | SESSION_ACTIVE);
+    assert_eq!(StatusFlag::ReceiverSessionActive.mask(), SESSION_ACTIVE);
+
+    txt.update_session_status(false);
+    assert_eq!(txt.status_flags(), idle);
+}
+
+/// Wait for the advertised flags to satisfy `check`
+async fn wait_for_flags(advertiser: &Ap2ServiceAdvertiser, check: impl Fn(u32) -> bool) -> u32 {
+    for _ in 0..50 {
+        let flags = advertiser.status_flags().await;
+        if check(flags) {
+            return flags;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("status flags never updated");
+}
+
+#[tokio::test]
+async fn test_status_follows_sessions() {
+    let advertiser =
+        Arc::new(Ap2ServiceAdvertiser::new(Ap2Config::new("Test Speaker"), [0u8; 32]).unwrap());
+    let sessions = SessionManager::new(SessionManagerConfig::default());
+    let task = advertiser.track_sessions(sessions.subscribe());
+
+    assert_eq!(advertiser.status_flags().await & SESSION_ACTIVE, 0);
+
+    let client = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 12345);
+    sessions.start_session(client).await.unwrap();
+    wait_for_flags(&advertiser, |flags| flags & SESSION_ACTIVE != 0).await;
+
+    sessions.end_session("Test").await;
+    wait_for_flags(&advertiser, |flags| flags & SESSION_ACTIVE == 0).await;
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_password_status_update() {
+    let advertiser = Ap2ServiceAdvertiser::new(Ap2Config::new("Test Speaker"), [0u8; 32]).unwrap();
+    let password_required = StatusFlag::RequiresPassword.mask();
+    assert_eq!(advertiser.status_flags().await & password_required, 0);
+
+    advertiser.set_password_required(true).await.unwrap();
+    assert_ne!(advertiser.status_flags().await & password_required, 0);
+
+    advertiser.set_password_required(false).await.unwrap();
+    assert_eq!(advertiser.status_flags().await & password_required, 0);
+}
@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::receiver::ap2::body_handler::{
     BodyParseError, PlistExt, PlistResponseBuilder, encode_bplist_body, encode_text_parameters,
     parse_bplist_body, parse_text_parameters,
@@ -34,7 +34,7 @@ fn test_plist_builder() {
 
 #[test]
 fn test_plist_types() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("data".to_string(), PlistValue::Data(vec![1, 2, 3]));
     dict.insert("bool".to_string(), PlistValue::Boolean(false));
 
@@ -78,7 +78,7 @@ fn test_parse_text_parameters_malformed() {
 
 #[test]
 fn test_bplist_roundtrip() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("key".to_string(), PlistValue::Integer(42));
     let plist = PlistValue::Dictionary(dict);
 
@@ -26,3 +26,49 @@ fn test_pairing_identity_deterministic() {
     assert_eq!(caps1.pairing_identity, caps2.pairing_identity);
     assert_eq!(caps1.pairing_identity.len(), 36); // UUID format
 }
+
+#[test]
+fn test_from_config_matches_advertisement() {
+    use crate::receiver::ap2::config::Ap2Config;
+    use crate::receiver::ap2::features::AIRPLAY2_SPEAKER_FEATURES;
+
+    let config = Ap2Config::new("Kitchen");
+    let caps = DeviceCapabilities::from_config(&config, [3u8; 32]);
+
+    assert_eq!(
+        caps.features & AIRPLAY2_SPEAKER_FEATURES,
+        AIRPLAY2_SPEAKER_FEATURES
+    );
+    assert_eq!(caps.public_system_identifier.len(), 36);
+    assert_ne!(caps.public_system_identifier, caps.pairing_identity);
+
+    let txt = caps.txt_airplay_data().unwrap();
+    assert!(txt.windows(b"features=".len()).any(|w| w == b"features="));
+
+    let PlistValue::Dictionary(dict) = caps.to_plist() else {
+        panic!("Expected Dictionary");
+    };
+    assert!(matches!(&dict["displays"], PlistValue::Array(d) if d.is_empty()));
+    assert!(dict.contains_key("psi"));
+    assert!(dict.contains_key("sourceVersion"));
+}
+
+#[test]
+fn test_aac_advertised_only_with_decoder() {
+    use crate::receiver::ap2::features::FeatureFlag;
+
+    let caps = DeviceCapabilities::audio_receiver("AA:BB:CC:DD:EE:FF", "Test Speaker", [0u8; 32]);
+    let aac = cfg!(feature = "aac-fdk");
+
+    for type_id in [97, 98] {
+        assert_eq!(caps.audio_formats.iter().any(|f| f.type_id == type_id), aac);
+    }
+    for flag in [
+        FeatureFlag::AudioFormatAacLc,
+        FeatureFlag::AudioFormatAacEld,
+    ] {
+        assert_eq!(caps.features & flag.mask() != 0, aac);
+    }
+    assert!(caps.audio_formats.iter().any(|f| f.type_id == 96));
+    assert!(caps.audio_formats.iter().any(|f| f.type_id == 100));
+}
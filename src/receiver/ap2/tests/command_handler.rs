@@ -1,6 +1,4 @@
-use std::collections::HashMap;
-
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::{Method, RtspRequest};
 use crate::receiver::ap2::body_handler::{encode_bplist_body, parse_bplist_body};
 use crate::receiver::ap2::command_handler::{PlaybackCommand, handle_command, handle_feedback};
@@ -9,7 +7,7 @@ use crate::receiver::ap2::session_state::Ap2SessionState;
 
 #[test]
 fn test_parse_play_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("type".to_string(), PlistValue::String("play".to_string()));
     let plist = PlistValue::Dictionary(dict);
 
@@ -19,7 +17,7 @@ fn test_parse_play_command() {
 
 #[test]
 fn test_parse_seek_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("seekToPosition".to_string()),
@@ -33,7 +31,7 @@ fn test_parse_seek_command() {
 
 #[test]
 fn test_parse_missing_type() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("position".to_string(), PlistValue::Integer(30000));
     let plist = PlistValue::Dictionary(dict);
 
@@ -43,7 +41,7 @@ fn test_parse_missing_type() {
 
 #[test]
 fn test_handle_command_success() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("type".to_string(), PlistValue::String("play".to_string()));
     let plist = PlistValue::Dictionary(dict);
     let body = encode_bplist_body(&plist).unwrap();
@@ -114,7 +112,7 @@ fn test_handle_command_invalid_body() {
 
 #[test]
 fn test_handle_command_missing_type() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("position".to_string(), PlistValue::Integer(30000));
     let plist = PlistValue::Dictionary(dict);
     let body = encode_bplist_body(&plist).unwrap();
@@ -178,7 +176,7 @@ fn test_handle_feedback_invalid_body() {
 
 #[test]
 fn test_parse_pause_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("type".to_string(), PlistValue::String("pause".to_string()));
     let plist = PlistValue::Dictionary(dict);
 
@@ -188,7 +186,7 @@ fn test_parse_pause_command() {
 
 #[test]
 fn test_parse_stop_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("type".to_string(), PlistValue::String("stop".to_string()));
     let plist = PlistValue::Dictionary(dict);
 
@@ -198,7 +196,7 @@ fn test_parse_stop_command() {
 
 #[test]
 fn test_parse_skip_next_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("skipNext".to_string()),
@@ -208,7 +206,7 @@ fn test_parse_skip_next_command() {
     let cmd = PlaybackCommand::from_plist(&plist).unwrap();
     assert!(matches!(cmd, PlaybackCommand::SkipNext));
 
-    let mut dict2 = HashMap::new();
+    let mut dict2 = PlistDict::new();
     dict2.insert(
         "type".to_string(),
         PlistValue::String("nextItem".to_string()),
@@ -220,7 +218,7 @@ fn test_parse_skip_next_command() {
 
 #[test]
 fn test_parse_skip_previous_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("skipPrevious".to_string()),
@@ -230,7 +228,7 @@ fn test_parse_skip_previous_command() {
     let cmd = PlaybackCommand::from_plist(&plist).unwrap();
     assert!(matches!(cmd, PlaybackCommand::SkipPrevious));
 
-    let mut dict2 = HashMap::new();
+    let mut dict2 = PlistDict::new();
     dict2.insert(
         "type".to_string(),
         PlistValue::String("previousItem".to_string()),
@@ -242,7 +240,7 @@ fn test_parse_skip_previous_command() {
 
 #[test]
 fn test_parse_set_rate_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("setPlaybackRate".to_string()),
@@ -256,7 +254,7 @@ fn test_parse_set_rate_command() {
 
 #[test]
 fn test_parse_set_rate_default() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("setPlaybackRate".to_string()),
@@ -269,7 +267,7 @@ fn test_parse_set_rate_default() {
 
 #[test]
 fn test_parse_unknown_command() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("customCommand".to_string()),
@@ -286,7 +284,7 @@ fn test_parse_unknown_command() {
 
 #[test]
 fn test_parse_seek_missing_position() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "type".to_string(),
         PlistValue::String("seekToPosition".to_string()),
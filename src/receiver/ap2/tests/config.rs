@@ -50,3 +50,20 @@ fn test_builder_validation() {
         _ => panic!("Expected InvalidName error"),
     }
 }
+
+#[test]
+fn test_interface_selection() {
+    use crate::receiver::ap2::advertisement::Ap2ServiceAdvertiser;
+
+    assert!(Ap2Config::default().interfaces.is_empty());
+
+    let config = Ap2ConfigBuilder::new()
+        .interfaces(vec!["eth1".to_string()])
+        .build()
+        .unwrap();
+    assert_eq!(config.interfaces, vec!["eth1".to_string()]);
+
+    let config = Ap2Config::new("Speaker").with_interfaces(vec!["lo".to_string()]);
+    let advertiser = Ap2ServiceAdvertiser::new(config, [0u8; 32]).unwrap();
+    assert_eq!(advertiser.config().interfaces, vec!["lo".to_string()]);
+}
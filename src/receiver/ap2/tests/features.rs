@@ -17,6 +17,14 @@ fn test_audio_receiver_defaults() {
 
     assert!(flags.has(FeatureFlag::Audio));
     assert!(flags.has(FeatureFlag::AudioFormatAlac));
+    assert_eq!(
+        flags.has(FeatureFlag::AudioFormatAacLc),
+        cfg!(feature = "aac-fdk")
+    );
+    assert_eq!(
+        flags.has(FeatureFlag::AudioFormatAacEld),
+        cfg!(feature = "aac-fdk")
+    );
     assert!(flags.has(FeatureFlag::SupportsHomeKit));
     assert!(!flags.has(FeatureFlag::Video));
 }
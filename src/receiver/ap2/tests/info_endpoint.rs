@@ -58,3 +58,35 @@ fn test_state_transition() {
         Some(Ap2SessionState::InfoExchanged)
     ));
 }
+
+#[test]
+fn test_info_txt_qualifier_header() {
+    let caps = DeviceCapabilities::audio_receiver("AA:BB:CC:DD:EE:FF", "Test Speaker", [0u8; 32]);
+    let endpoint = InfoEndpoint::new(caps);
+
+    let mut request = make_info_request();
+    request.headers.insert(
+        "X-Apple-Info-Qualifier".to_string(),
+        "txtAirPlay".to_string(),
+    );
+    let state = Ap2SessionState::Connected;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+
+    let result = endpoint.handle(&request, 1, &context);
+
+    let body_start = result
+        .response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .unwrap()
+        + 4;
+    let plist = crate::protocol::plist::decode(&result.response[body_start..]).unwrap();
+    let dict = plist.as_dict().unwrap();
+    assert_eq!(dict.len(), 1);
+    assert!(dict.contains_key("txtAirPlay"));
+}
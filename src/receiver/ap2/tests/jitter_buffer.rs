@@ -2,6 +2,7 @@ use std::time::Instant;
 
 use crate::receiver::ap2::jitter_buffer::{BufferState, JitterBuffer, JitterBufferConfig};
 use crate::receiver::ap2::rtp_receiver::AudioFrame;
+use crate::receiver::ap2::stream::AudioStreamFormat;
 
 fn make_frame(seq: u16, ts: u32) -> AudioFrame {
     AudioFrame {
@@ -241,3 +242,26 @@ fn test_depth_accuracy_with_partial_read() {
         "Depth after full frame 1 read mismatch"
     );
 }
+
+#[test]
+fn test_config_for_format() {
+    let format = AudioStreamFormat {
+        codec: 0x4,
+        sample_rate: 48000,
+        channels: 2,
+        bits_per_sample: 16,
+        frames_per_packet: 1024,
+        compression_type: None,
+        spf: Some(1024),
+        audio_format: Some(1 << 23),
+    };
+
+    let config = JitterBufferConfig::for_format(&format);
+    assert_eq!(config.sample_rate, 48000);
+    assert_eq!(config.channels, 2);
+    assert_eq!(config.frames_per_packet, 1024);
+    assert_eq!(
+        config.target_depth_ms,
+        JitterBufferConfig::default().target_depth_ms
+    );
+}
@@ -2,8 +2,9 @@ use std::time::Duration;
 
 use tokio::net::TcpStream;
 
+use crate::receiver::ap2::request_handler::Ap2Event;
 use crate::receiver::ap2::{
-    AirPlay2Receiver, Ap2Config, ReceiverBuilder, ReceiverEvent, ReceiverState,
+    AirPlay2Receiver, Ap2Config, ReceiverBuilder, ReceiverEvent, ReceiverState, StreamType,
 };
 
 #[tokio::test]
@@ -78,3 +79,36 @@ async fn test_accept_connection() {
     receiver.stop().await.unwrap();
     drop(stream);
 }
+
+#[test]
+fn test_teardown_events_are_distinct() {
+    let stream = ReceiverEvent::from_ap2_event(&Ap2Event::StreamTeardown {
+        streams: vec![StreamType::Audio],
+    });
+    assert!(matches!(
+        stream,
+        Some(ReceiverEvent::StreamStopped { ref streams }) if streams == &[StreamType::Audio]
+    ));
+
+    let session = ReceiverEvent::from_ap2_event(&Ap2Event::Teardown);
+    assert!(matches!(session, Some(ReceiverEvent::SessionEnded)));
+
+    assert!(ReceiverEvent::from_ap2_event(&Ap2Event::MetadataUpdated).is_none());
+}
+
+#[tokio::test]
+async fn test_set_password_updates_config() {
+    let mut receiver = ReceiverBuilder::new("Test Speaker").port(0).build();
+    receiver.start().await.unwrap();
+
+    receiver
+        .set_password(Some("secret".to_string()))
+        .await
+        .unwrap();
+    assert!(receiver.config().has_password());
+
+    receiver.set_password(None).await.unwrap();
+    assert!(!receiver.config().has_password());
+
+    receiver.stop().await.unwrap();
+}
@@ -1,11 +1,9 @@
-use std::collections::HashMap;
-
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::receiver::ap2::response_builder::Ap2ResponseBuilder;
 
 #[test]
 fn test_bplist_response() {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert("status".to_string(), PlistValue::Integer(0));
     dict.insert("message".to_string(), PlistValue::String("OK".to_string()));
 
@@ -1,6 +1,15 @@
+#[cfg(feature = "aac-fdk")]
+use crate::audio::AacEncoder;
 use crate::protocol::crypto::{ChaCha20Poly1305Cipher, Nonce};
 use crate::protocol::rtp::{RtpHeader, RtpPacket};
-use crate::receiver::ap2::rtp_decryptor::{Ap2RtpDecryptor, AudioDecoder, PcmDecoder};
+#[cfg(feature = "aac-fdk")]
+use crate::receiver::ap2::rtp_decryptor::AacDecoder;
+use crate::receiver::ap2::rtp_decryptor::{
+    Ap2RtpDecryptor, AudioDecodeError, AudioDecoder, PcmDecoder, create_decoder,
+};
+use crate::receiver::ap2::stream::AudioStreamFormat;
+#[cfg(feature = "aac-fdk")]
+use crate::receiver::ap2::stream::StreamCodec;
 
 #[test]
 fn test_rtp_decryption() {
@@ -115,3 +124,71 @@ fn test_pcm_decoder_24bit() {
     assert_eq!(samples.len(), 1);
     assert_eq!(samples[0], 16384);
 }
+
+fn stream_format(ct: u32, sample_rate: u32, spf: u32) -> AudioStreamFormat {
+    AudioStreamFormat {
+        codec: ct,
+        sample_rate,
+        channels: 2,
+        bits_per_sample: 16,
+        frames_per_packet: spf,
+        compression_type: None,
+        spf: Some(spf),
+        audio_format: None,
+    }
+}
+
+#[cfg(feature = "aac-fdk")]
+#[test]
+fn test_aac_audio_specific_config() {
+    assert_eq!(
+        AacDecoder::audio_specific_config(StreamCodec::AacLc, 44100, 2, 1024).unwrap(),
+        vec![0x12, 0x10]
+    );
+    assert_eq!(
+        AacDecoder::audio_specific_config(StreamCodec::AacLc, 48000, 2, 1024).unwrap(),
+        vec![0x11, 0x90]
+    );
+    assert_eq!(
+        AacDecoder::audio_specific_config(StreamCodec::AacEld, 44100, 2, 480).unwrap(),
+        vec![0xF8, 0xE8, 0x50, 0x00]
+    );
+    assert!(AacDecoder::audio_specific_config(StreamCodec::AacLc, 12345, 2, 1024).is_none());
+    assert!(AacDecoder::audio_specific_config(StreamCodec::Pcm, 44100, 2, 352).is_none());
+}
+
+#[cfg(feature = "aac-fdk")]
+#[test]
+fn test_aac_lc_48k_round_trip() {
+    let mut encoder = AacEncoder::new(
+        48000,
+        2,
+        128_000,
+        crate::audio::aac_encoder::AudioObjectType::Mpeg4LowComplexity,
+    )
+    .unwrap();
+    let mut decoder = create_decoder(&stream_format(0x4, 48000, 1024)).unwrap();
+    assert_eq!(decoder.sample_rate(), 48000);
+
+    let pcm: Vec<i16> = (0..1024 * 2)
+        .map(|i| if (i / 2) % 48 < 24 { 8000 } else { -8000 })
+        .collect();
+
+    let mut output = Vec::new();
+    for _ in 0..8 {
+        let frame = encoder.encode(&pcm).unwrap();
+        if frame.is_empty() {
+            continue;
+        }
+        output = decoder.decode(&frame).unwrap();
+    }
+
+    assert_eq!(output.len(), 1024 * 2);
+    assert!(output.iter().any(|&s| s != 0));
+}
+
+#[test]
+fn test_create_decoder_rejects_unknown_codec() {
+    let result = create_decoder(&stream_format(0x20, 48000, 480));
+    assert!(matches!(result, Err(AudioDecodeError::UnsupportedFormat)));
+}
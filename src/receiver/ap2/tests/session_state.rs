@@ -126,3 +126,22 @@ fn test_teardown_constraints() {
             .is_ok()
     );
 }
+
+#[test]
+fn test_stream_teardown_returns_to_setup_phase1() {
+    for state in [
+        Ap2SessionState::SetupPhase2,
+        Ap2SessionState::Streaming,
+        Ap2SessionState::Paused,
+    ] {
+        assert_eq!(
+            state.transition_to(Ap2SessionState::SetupPhase1).unwrap(),
+            Ap2SessionState::SetupPhase1
+        );
+    }
+    assert!(
+        Ap2SessionState::Teardown
+            .transition_to(Ap2SessionState::SetupPhase1)
+            .is_err()
+    );
+}
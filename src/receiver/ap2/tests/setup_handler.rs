@@ -1,12 +1,14 @@
-use std::collections::HashMap;
-
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::{Method, RtspRequest};
 use crate::receiver::ap2::body_handler::{encode_bplist_body, parse_bplist_body};
-use crate::receiver::ap2::request_handler::{Ap2Event, Ap2RequestContext};
+use crate::receiver::ap2::request_handler::{Ap2Event, Ap2HandleResult, Ap2RequestContext};
 use crate::receiver::ap2::session_state::Ap2SessionState;
-use crate::receiver::ap2::setup_handler::{PortAllocator, SetupHandler, SetupPhase};
-use crate::receiver::ap2::stream::{EncryptionType, TimingProtocol};
+use crate::receiver::ap2::setup_handler::{
+    PortAllocator, SetupHandler, SetupPhase, SetupRequest, TeardownScope,
+};
+use crate::receiver::ap2::stream::{
+    AudioFormatDescriptor, EncryptionType, StreamCodec, StreamType, TimingProtocol,
+};
 
 fn create_setup_request(body: &[u8]) -> RtspRequest {
     RtspRequest::builder(Method::Setup, "rtsp://localhost/stream")
@@ -15,7 +17,7 @@ fn create_setup_request(body: &[u8]) -> RtspRequest {
 }
 
 fn create_phase1_plist() -> PlistValue {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "timingProtocol".to_string(),
         PlistValue::String("NTP".to_string()),
@@ -24,19 +26,19 @@ fn create_phase1_plist() -> PlistValue {
     let mut streams = Vec::new();
 
     // Event stream
-    let mut event_dict = HashMap::new();
+    let mut event_dict = PlistDict::new();
     event_dict.insert("type".to_string(), PlistValue::Integer(130)); // Event
     streams.push(PlistValue::Dictionary(event_dict));
 
     // Timing stream
-    let mut timing_dict = HashMap::new();
+    let mut timing_dict = PlistDict::new();
     timing_dict.insert("type".to_string(), PlistValue::Integer(150)); // Timing
     streams.push(PlistValue::Dictionary(timing_dict));
 
     dict.insert("streams".to_string(), PlistValue::Array(streams));
 
     // Timing peer info
-    let mut peer_info = HashMap::new();
+    let mut peer_info = PlistDict::new();
     peer_info.insert("ID".to_string(), PlistValue::Integer(12345));
     dict.insert(
         "timingPeerInfo".to_string(),
@@ -47,12 +49,12 @@ fn create_phase1_plist() -> PlistValue {
 }
 
 fn create_phase2_plist() -> PlistValue {
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
 
     let mut streams = Vec::new();
 
     // Audio stream
-    let mut audio_dict = HashMap::new();
+    let mut audio_dict = PlistDict::new();
     audio_dict.insert("type".to_string(), PlistValue::Integer(96)); // Audio
     audio_dict.insert("ct".to_string(), PlistValue::Integer(0x1)); // PCM
     streams.push(PlistValue::Dictionary(audio_dict));
@@ -287,7 +289,7 @@ fn test_setup_missing_streams() {
         decrypt: None,
     };
 
-    let mut dict = HashMap::new();
+    let mut dict = PlistDict::new();
     dict.insert(
         "timingProtocol".to_string(),
         PlistValue::String("NTP".to_string()),
@@ -336,3 +338,302 @@ fn test_port_allocator() {
     let p5 = allocator.allocate().expect("p5");
     assert_eq!(p5, 1002);
 }
+
+fn create_audio_setup_plist(fields: &[(&str, i64)]) -> PlistValue {
+    let mut audio_dict = PlistDict::new();
+    audio_dict.insert("type".to_string(), PlistValue::Integer(96));
+    for (key, value) in fields {
+        audio_dict.insert((*key).to_string(), PlistValue::Integer(*value));
+    }
+
+    let mut dict = PlistDict::new();
+    dict.insert(
+        "streams".to_string(),
+        PlistValue::Array(vec![PlistValue::Dictionary(audio_dict)]),
+    );
+    PlistValue::Dictionary(dict)
+}
+
+#[test]
+fn test_setup_audio_format_aac_48k() {
+    // iOS AAC-LC session: ct + audioFormat (AAC_LC_48000_2) + spf, no explicit sr
+    let plist = create_audio_setup_plist(&[("ct", 0x4), ("audioFormat", 1 << 23), ("spf", 1024)]);
+    let request = SetupRequest::from_plist(&plist).unwrap();
+
+    let format = request.streams[0].audio_format.clone().unwrap();
+    assert_eq!(format.stream_codec(), StreamCodec::AacLc);
+    assert_eq!(format.sample_rate, 48000);
+    assert_eq!(format.channels, 2);
+    assert_eq!(format.bits_per_sample, 16);
+    assert_eq!(format.frames_per_packet, 1024);
+    assert_eq!(format.audio_format, Some(1 << 23));
+}
+
+#[test]
+fn test_setup_audio_format_codec_from_mask() {
+    // No ct: codec comes from audioFormat (ALAC_48000_24_2)
+    let plist = create_audio_setup_plist(&[("audioFormat", 1 << 21)]);
+    let request = SetupRequest::from_plist(&plist).unwrap();
+
+    let format = request.streams[0].audio_format.clone().unwrap();
+    assert_eq!(format.stream_codec(), StreamCodec::Alac);
+    assert_eq!(format.sample_rate, 48000);
+    assert_eq!(format.bits_per_sample, 24);
+    assert_eq!(format.frames_per_packet, 352);
+}
+
+#[test]
+fn test_setup_audio_format_explicit_fields_win() {
+    let plist = create_audio_setup_plist(&[
+        ("ct", 0x1),
+        ("audioFormat", 1 << 11),
+        ("sr", 48000),
+        ("ss", 24),
+    ]);
+    let request = SetupRequest::from_plist(&plist).unwrap();
+
+    let format = request.streams[0].audio_format.clone().unwrap();
+    assert_eq!(format.stream_codec(), StreamCodec::Pcm);
+    assert_eq!(format.sample_rate, 48000);
+    assert_eq!(format.bits_per_sample, 24);
+    assert_eq!(format.channels, 2);
+}
+
+#[test]
+fn test_setup_audio_format_defaults() {
+    let plist = create_audio_setup_plist(&[("ct", 0x8)]);
+    let request = SetupRequest::from_plist(&plist).unwrap();
+
+    let format = request.streams[0].audio_format.clone().unwrap();
+    assert_eq!(format.stream_codec(), StreamCodec::AacEld);
+    assert_eq!(format.sample_rate, 44100);
+    assert_eq!(format.frames_per_packet, 480);
+    assert!(format.audio_format.is_none());
+}
+
+#[test]
+fn test_audio_format_descriptor_mask() {
+    let aac = AudioFormatDescriptor::from_mask(1 << 23).unwrap();
+    assert_eq!(aac.codec, StreamCodec::AacLc);
+    assert_eq!(aac.sample_rate, 48000);
+    assert_eq!(aac.to_mask(), Some(1 << 23));
+
+    let eld_mono = AudioFormatDescriptor::from_mask(1 << 32).unwrap();
+    assert_eq!(eld_mono.codec, StreamCodec::AacEld);
+    assert_eq!(eld_mono.channels, 1);
+
+    assert!(AudioFormatDescriptor::from_mask(0).is_none());
+    assert!(AudioFormatDescriptor::from_mask(1).is_none());
+}
+
+fn create_teardown_request(body: &[u8]) -> RtspRequest {
+    RtspRequest::builder(Method::Teardown, "rtsp://localhost/stream")
+        .body(body.to_vec())
+        .build()
+}
+
+fn stream_teardown_body() -> Vec<u8> {
+    let mut audio_dict = PlistDict::new();
+    audio_dict.insert("type".to_string(), PlistValue::Integer(96));
+    let mut dict = PlistDict::new();
+    dict.insert(
+        "streams".to_string(),
+        PlistValue::Array(vec![PlistValue::Dictionary(audio_dict)]),
+    );
+    encode_bplist_body(&PlistValue::Dictionary(dict)).unwrap()
+}
+
+/// Handler with both SETUP phases done, as in a streaming session
+fn streaming_handler() -> SetupHandler {
+    let handler = SetupHandler::new(50000, 50100, 22050);
+    let state = Ap2SessionState::Paired;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+    for plist in [create_phase1_plist(), create_phase2_plist()] {
+        let body = encode_bplist_body(&plist).unwrap();
+        assert!(
+            handler
+                .handle(&create_setup_request(&body), 1, &context)
+                .error
+                .is_none()
+        );
+    }
+    handler
+}
+
+#[test]
+fn test_teardown_scope_parse() {
+    assert_eq!(TeardownScope::parse(&[]), TeardownScope::Session);
+    assert_eq!(TeardownScope::parse(b"garbage"), TeardownScope::Session);
+    assert_eq!(
+        TeardownScope::parse(&stream_teardown_body()),
+        TeardownScope::Streams(vec![StreamType::Audio])
+    );
+
+    let empty = encode_bplist_body(&PlistValue::Dictionary(PlistDict::new())).unwrap();
+    assert_eq!(TeardownScope::parse(&empty), TeardownScope::Session);
+}
+
+#[test]
+fn test_stream_teardown_keeps_session() {
+    let handler = streaming_handler();
+    let state = Ap2SessionState::Streaming;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+
+    let request = create_teardown_request(&stream_teardown_body());
+    let result = handler.handle_teardown(&request, 5, &context);
+
+    let (headers, _) = parse_response(&result.response);
+    assert!(headers.starts_with("RTSP/1.0 200"));
+    assert_eq!(result.new_state, Some(Ap2SessionState::SetupPhase1));
+    assert!(matches!(
+        result.event,
+        Some(Ap2Event::StreamTeardown { ref streams }) if streams == &[StreamType::Audio]
+    ));
+    assert!(matches!(
+        *handler.current_phase.lock().unwrap(),
+        SetupPhase::Phase1Complete
+    ));
+
+    // The sender can set a new stream up straight away
+    let state = Ap2SessionState::SetupPhase1;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+    let body = encode_bplist_body(&create_phase2_plist()).unwrap();
+    let result = handler.handle(&create_setup_request(&body), 6, &context);
+    assert_eq!(result.new_state, Some(Ap2SessionState::SetupPhase2));
+}
+
+#[test]
+fn test_session_teardown_releases_everything() {
+    let handler = streaming_handler();
+    let state = Ap2SessionState::Streaming;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+
+    let result = handler.handle_teardown(&create_teardown_request(&[]), 5, &context);
+
+    assert_eq!(result.new_state, Some(Ap2SessionState::Teardown));
+    assert!(matches!(result.event, Some(Ap2Event::Teardown)));
+    assert!(matches!(
+        *handler.current_phase.lock().unwrap(),
+        SetupPhase::None
+    ));
+}
+
+/// Send a phase 2 SETUP describing `fields`, keyed with a 32-byte `shk`
+fn setup_keyed_stream(handler: &SetupHandler, fields: &[(&str, i64)]) -> Ap2HandleResult {
+    let PlistValue::Dictionary(mut dict) = create_audio_setup_plist(fields) else {
+        unreachable!();
+    };
+    dict.insert("shk".to_string(), PlistValue::Data(vec![0x42; 32]));
+
+    let state = Ap2SessionState::SetupPhase1;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+    let body = encode_bplist_body(&PlistValue::Dictionary(dict)).unwrap();
+    handler.handle(&create_setup_request(&body), 2, &context)
+}
+
+#[cfg(feature = "aac-fdk")]
+#[test]
+fn test_setup_aac_builds_pipeline() {
+    let handler = SetupHandler::new(50000, 50100, 22050);
+    let result = setup_keyed_stream(
+        &handler,
+        &[("ct", 0x4), ("audioFormat", 1 << 23), ("spf", 1024)],
+    );
+    assert!(result.error.is_none());
+
+    let Some(Ap2Event::SetupPhase2Complete {
+        audio_data_port, ..
+    }) = result.event
+    else {
+        panic!("Wrong event type");
+    };
+
+    let pipeline = handler.take_stream_pipeline().expect("pipeline");
+    assert_eq!(pipeline.format.stream_codec(), StreamCodec::AacLc);
+    assert_eq!(pipeline.receiver.config().port, audio_data_port);
+    assert_eq!(pipeline.receiver.config().sample_rate, 48000);
+    assert_eq!(pipeline.receiver.decoder().sample_rate(), 48000);
+    assert_eq!(pipeline.receiver.decoder().channels(), 2);
+    assert_eq!(pipeline.jitter_buffer.config().sample_rate, 48000);
+    assert_eq!(pipeline.jitter_buffer.config().frames_per_packet, 1024);
+    assert!(handler.take_stream_pipeline().is_none());
+}
+
+#[cfg(not(feature = "aac-fdk"))]
+#[test]
+fn test_setup_aac_without_decoder_has_no_pipeline() {
+    let handler = SetupHandler::new(50000, 50100, 22050);
+    let result = setup_keyed_stream(
+        &handler,
+        &[("ct", 0x4), ("audioFormat", 1 << 23), ("spf", 1024)],
+    );
+
+    let (headers, _) = parse_response(&result.response);
+    assert!(headers.starts_with("RTSP/1.0 200"));
+    assert_eq!(result.new_state, Some(Ap2SessionState::SetupPhase2));
+    assert!(handler.take_stream_pipeline().is_none());
+}
+
+#[test]
+fn test_setup_pcm_pipeline() {
+    let handler = SetupHandler::new(50000, 50100, 22050);
+    let result = setup_keyed_stream(&handler, &[("ct", 0x1), ("audioFormat", 1 << 17)]);
+    assert!(result.error.is_none());
+
+    let pipeline = handler.take_stream_pipeline().expect("pipeline");
+    assert_eq!(pipeline.format.stream_codec(), StreamCodec::Pcm);
+    assert_eq!(pipeline.receiver.decoder().sample_rate(), 48000);
+    assert_eq!(pipeline.receiver.config().bits_per_sample, 24);
+    assert_eq!(pipeline.jitter_buffer.config().frames_per_packet, 352);
+}
+
+#[test]
+fn test_setup_without_key_has_no_pipeline() {
+    let handler = streaming_handler();
+    assert!(handler.take_stream_pipeline().is_none());
+}
+
+#[test]
+fn test_stream_teardown_drops_pipeline() {
+    let handler = SetupHandler::new(50000, 50100, 22050);
+    setup_keyed_stream(&handler, &[("ct", 0x1)]);
+
+    let state = Ap2SessionState::Streaming;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+    handler.handle_teardown(
+        &create_teardown_request(&stream_teardown_body()),
+        3,
+        &context,
+    );
+    assert!(handler.take_stream_pipeline().is_none());
+}
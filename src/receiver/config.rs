@@ -17,8 +17,17 @@ pub struct ReceiverConfig {
     pub capabilities: RaopCapabilities,
 
     /// Session timeout
+    ///
+    /// A session with no RTSP requests or audio traffic for this long is
+    /// ended and the receiver advertised as free again.
     pub session_timeout: Duration,
 
+    /// Inactivity timeout while streaming
+    ///
+    /// Audio and sync packets arrive continuously during playback, so a
+    /// silent streaming session is reaped after this shorter timeout.
+    pub streaming_timeout: Duration,
+
     /// Allow session preemption
     pub allow_preemption: bool,
 
@@ -45,6 +54,7 @@ impl Default for ReceiverConfig {
             port: 5000,
             capabilities: RaopCapabilities::default(),
             session_timeout: Duration::from_secs(60),
+            streaming_timeout: Duration::from_secs(10),
             allow_preemption: true,
             latency_ms: 2000,
             jitter_buffer_depth: 50,
@@ -78,6 +88,14 @@ impl ReceiverConfig {
         self
     }
 
+    /// Set session and streaming inactivity timeouts
+    #[must_use]
+    pub fn timeouts(mut self, session: Duration, streaming: Duration) -> Self {
+        self.session_timeout = session;
+        self.streaming_timeout = streaming;
+        self
+    }
+
     /// Set audio device
     #[must_use]
     pub fn audio_device(mut self, device: impl Into<String>) -> Self {
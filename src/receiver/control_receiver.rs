@@ -1,11 +1,16 @@
 //! Control port receiver
 //!
-//! Handles sync packets and retransmission requests on the control UDP port.
+//! Handles sync packets and retransmission requests on the control UDP port,
+//! and sends our own retransmit requests for packets that went missing.
 
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use tokio::net::UdpSocket;
-use tokio::sync::mpsc;
+use tokio::sync::{Mutex, RwLock, mpsc};
+
+use super::sequence_tracker::SequenceTracker;
 
 /// Control packet types
 const PACKET_TYPE_SYNC: u8 = 0x54;
@@ -140,3 +145,160 @@ impl ControlReceiver {
         }))
     }
 }
+
+/// Retransmit requester configuration
+#[derive(Debug, Clone)]
+pub struct RetransmitRequesterConfig {
+    /// How often missing packets are checked
+    pub poll_interval: Duration,
+    /// Maximum packets requested per second across all requests
+    pub max_packets_per_second: u32,
+}
+
+impl Default for RetransmitRequesterConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_millis(10),
+            max_packets_per_second: 200,
+        }
+    }
+}
+
+/// Counters for retransmit requests sent by the receiver
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RetransmitRequestStats {
+    /// Request datagrams sent
+    pub requests_sent: u64,
+    /// Packets covered by sent requests
+    pub packets_requested: u64,
+    /// Times the per-second cap held back due packets
+    pub rate_limited: u64,
+    /// Request datagrams that failed to send
+    pub send_errors: u64,
+}
+
+/// Sends retransmit requests for missing packets to the sender's control port
+///
+/// Which packets to request, and when, is decided by the shared
+/// [`SequenceTracker`]'s retransmit policy (reorder window, backoff and
+/// playout deadline). The requester adds a per-second cap on top so a burst
+/// of loss cannot flood the sender.
+pub struct RetransmitRequester {
+    socket: Arc<UdpSocket>,
+    sender_addr: SocketAddr,
+    tracker: Arc<RwLock<SequenceTracker>>,
+    config: RetransmitRequesterConfig,
+    stats: Arc<Mutex<RetransmitRequestStats>>,
+    window_start: Option<Instant>,
+    sent_in_window: u32,
+    request_seq: u16,
+}
+
+impl RetransmitRequester {
+    /// Create a new requester
+    #[must_use]
+    pub fn new(
+        socket: Arc<UdpSocket>,
+        sender_addr: SocketAddr,
+        tracker: Arc<RwLock<SequenceTracker>>,
+        config: RetransmitRequesterConfig,
+    ) -> Self {
+        Self {
+            socket,
+            sender_addr,
+            tracker,
+            config,
+            stats: Arc::new(Mutex::new(RetransmitRequestStats::default())),
+            window_start: None,
+            sent_in_window: 0,
+            request_seq: 0,
+        }
+    }
+
+    /// Shared handle to the request counters
+    #[must_use]
+    pub fn stats_handle(&self) -> Arc<Mutex<RetransmitRequestStats>> {
+        self.stats.clone()
+    }
+
+    /// Snapshot of the request counters
+    pub async fn stats(&self) -> RetransmitRequestStats {
+        self.stats.lock().await.clone()
+    }
+
+    /// Run the request loop until the task is cancelled
+    pub async fn run(mut self) {
+        let mut interval = tokio::time::interval(self.config.poll_interval);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        loop {
+            interval.tick().await;
+            self.poll_at(Instant::now()).await;
+        }
+    }
+
+    /// Send any requests that are due, returning the number of packets
+    /// requested
+    pub async fn poll_at(&mut self, now: Instant) -> usize {
+        if self
+            .window_start
+            .is_none_or(|start| now.saturating_duration_since(start) >= Duration::from_secs(1))
+        {
+            self.window_start = Some(now);
+            self.sent_in_window = 0;
+        }
+
+        let budget = self
+            .config
+            .max_packets_per_second
+            .saturating_sub(self.sent_in_window);
+
+        let (requests, held_back) = {
+            let mut tracker = self.tracker.write().await;
+            let requests = tracker
+                .retransmit_requests_up_to(now, usize::try_from(budget).unwrap_or(usize::MAX));
+            (requests, tracker.has_due_retransmits(now))
+        };
+        if held_back {
+            self.stats.lock().await.rate_limited += 1;
+        }
+
+        let mut requested = 0;
+        for request in &requests {
+            let packet = self.encode(request);
+            let mut stats = self.stats.lock().await;
+            match self.socket.send_to(&packet, self.sender_addr).await {
+                Ok(_) => {
+                    stats.requests_sent += 1;
+                    stats.packets_requested += u64::from(request.count);
+                    requested += usize::from(request.count);
+                }
+                Err(e) => {
+                    tracing::debug!("Failed to send retransmit request: {}", e);
+                    stats.send_errors += 1;
+                }
+            }
+        }
+
+        self.sent_in_window = self
+            .sent_in_window
+            .saturating_add(u32::try_from(requested).unwrap_or(u32::MAX));
+        requested
+    }
+
+    /// Encode a retransmit request packet
+    ///
+    /// Layout: `0x80`, `0xD5` (marker + type 0x55), request sequence,
+    /// first missing sequence, count.
+    fn encode(&mut self, request: &RetransmitRequest) -> [u8; 8] {
+        let seq = self.request_seq;
+        self.request_seq = self.request_seq.wrapping_add(1);
+
+        let mut packet = [0u8; 8];
+        packet[0] = 0x80;
+        packet[1] = 0x80 | PACKET_TYPE_RETRANSMIT_REQUEST;
+        packet[2..4].copy_from_slice(&seq.to_be_bytes());
+        packet[4..6].copy_from_slice(&request.first_seq.to_be_bytes());
+        packet[6..8].copy_from_slice(&request.count.to_be_bytes());
+        packet
+    }
+}
@@ -72,6 +72,12 @@ pub enum ReceiverEvent {
         underrun: bool,
     },
 
+    /// Sender asked the receiver to identify itself (beep or flash)
+    Identify {
+        /// Client address
+        address: SocketAddr,
+    },
+
     /// Error occurred
     Error {
         /// Error message
@@ -15,6 +15,7 @@ pub mod control_receiver;
 pub mod playback_timing;
 pub mod receiver_manager;
 pub mod rtp_receiver;
+pub mod runtime;
 pub mod sequence_tracker;
 pub mod timing;
 
@@ -33,6 +34,7 @@ pub use config::ReceiverConfig;
 pub use events::{EventCallback, ReceiverEvent};
 pub use metadata_handler::TrackMetadata;
 pub use progress_handler::PlaybackProgress;
+pub use runtime::{ReceiverRuntime, RuntimeConfig, RuntimeError, ZoneLease};
 pub use server::{AirPlayReceiver, ReceiverError, ReceiverState};
 pub use session::{AudioCodec, SessionState, StreamParameters};
 pub use volume_handler::VolumeUpdate;
@@ -3,15 +3,21 @@
 //! Manages all three UDP receive loops and coordinates
 //! packet flow to the audio pipeline.
 
+use std::net::SocketAddr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use tokio::net::UdpSocket;
-use tokio::sync::{RwLock, mpsc};
-use tokio::task::JoinHandle;
+use tokio::sync::{Mutex, RwLock, mpsc};
+use tokio::task::JoinSet;
 
-use super::control_receiver::{ControlEvent, ControlReceiver};
+use super::control_receiver::{
+    ControlEvent, ControlReceiver, RetransmitRequestStats, RetransmitRequester,
+    RetransmitRequesterConfig,
+};
 use super::rtp_receiver::{AudioPacket, RtpAudioReceiver};
-use super::sequence_tracker::SequenceTracker;
+use super::sequence_tracker::{PacketStatus, SequenceTracker, SequenceTrackerConfig};
+use super::session_manager::ActivityTracker;
 use crate::receiver::session::StreamParameters;
 
 /// Receiver manager configuration
@@ -21,6 +27,10 @@ pub struct ReceiverConfig {
     pub audio_buffer_size: usize,
     /// Control event channel buffer size
     pub control_buffer_size: usize,
+    /// Sequence tracking (reordering, duplicates, retransmit policy)
+    pub sequence: SequenceTrackerConfig,
+    /// Outgoing retransmit request pacing
+    pub retransmit: RetransmitRequesterConfig,
 }
 
 impl Default for ReceiverConfig {
@@ -28,18 +38,31 @@ impl Default for ReceiverConfig {
         Self {
             audio_buffer_size: 512,
             control_buffer_size: 64,
+            sequence: SequenceTrackerConfig::default(),
+            retransmit: RetransmitRequesterConfig::default(),
         }
     }
 }
 
+/// Packet from either receive loop
+#[derive(Debug)]
+pub enum ReceivedPacket {
+    /// Audio packet from the RTP socket
+    Audio(AudioPacket),
+    /// Sync or retransmit packet from the control socket
+    Control(ControlEvent),
+}
+
 /// Manages all RTP receive operations
 pub struct ReceiverManager {
-    #[allow(dead_code, reason = "Reserved for future use")]
     config: ReceiverConfig,
     audio_rx: mpsc::Receiver<AudioPacket>,
     control_rx: mpsc::Receiver<ControlEvent>,
     sequence_tracker: Arc<RwLock<SequenceTracker>>,
-    handles: Vec<JoinHandle<()>>,
+    retransmit_stats: Option<Arc<Mutex<RetransmitRequestStats>>>,
+    activity: Option<ActivityTracker>,
+    /// Receive loops, aborted when the manager is stopped or dropped
+    tasks: JoinSet<()>,
 }
 
 impl ReceiverManager {
@@ -53,12 +76,22 @@ impl ReceiverManager {
     ) -> Self {
         let (audio_tx, audio_rx) = mpsc::channel(config.audio_buffer_size);
         let (control_tx, control_rx) = mpsc::channel(config.control_buffer_size);
-        let sequence_tracker = Arc::new(RwLock::new(SequenceTracker::new()));
+        let mut tracker = SequenceTracker::with_config(config.sequence.clone());
+        if let Some(latency) = stream_params.min_latency
+            && stream_params.sample_rate > 0
+        {
+            // Missing packets are only worth requesting until they are played
+            tracker.set_playout_latency(Duration::from_secs_f64(
+                f64::from(latency) / f64::from(stream_params.sample_rate),
+            ));
+        }
+        let sequence_tracker = Arc::new(RwLock::new(tracker));
 
         // Start audio receiver
         let audio_receiver = RtpAudioReceiver::new(audio_socket, stream_params, audio_tx);
 
-        let audio_handle = tokio::spawn(async move {
+        let mut tasks = JoinSet::new();
+        tasks.spawn(async move {
             if let Err(e) = audio_receiver.run().await {
                 tracing::error!("Audio receiver error: {}", e);
             }
@@ -67,7 +100,7 @@ impl ReceiverManager {
         // Start control receiver
         let control_receiver = ControlReceiver::new(control_socket, control_tx);
 
-        let control_handle = tokio::spawn(async move {
+        tasks.spawn(async move {
             if let Err(e) = control_receiver.run().await {
                 tracing::error!("Control receiver error: {}", e);
             }
@@ -78,30 +111,89 @@ impl ReceiverManager {
             audio_rx,
             control_rx,
             sequence_tracker,
-            handles: vec![audio_handle, control_handle],
+            retransmit_stats: None,
+            activity: None,
+            tasks,
         }
     }
 
     /// Receive next audio packet
+    ///
+    /// Duplicate packets are dropped.
     pub async fn recv_audio(&mut self) -> Option<AudioPacket> {
-        let packet = self.audio_rx.recv().await?;
+        loop {
+            let packet = self.audio_rx.recv().await?;
+            if let Some(packet) = self.accept_audio(packet).await {
+                return Some(packet);
+            }
+        }
+    }
 
-        // Track sequence
-        let mut tracker = self.sequence_tracker.write().await;
-        if let Some(gap) = tracker.record(packet.sequence) {
-            tracing::debug!(
-                "Packet loss detected: {} packets starting at seq {}",
-                gap.count,
-                gap.start
-            );
+    /// Receive next control event
+    pub async fn recv_control(&mut self) -> Option<ControlEvent> {
+        let event = self.control_rx.recv().await?;
+        self.touch();
+        Some(event)
+    }
+
+    /// Receive the next audio packet or control event, whichever comes first
+    ///
+    /// Duplicate audio packets are dropped. Returns `None` once both receive
+    /// loops have stopped.
+    pub async fn recv(&mut self) -> Option<ReceivedPacket> {
+        loop {
+            tokio::select! {
+                Some(packet) = self.audio_rx.recv() => {
+                    if let Some(packet) = self.accept_audio(packet).await {
+                        return Some(ReceivedPacket::Audio(packet));
+                    }
+                }
+                Some(event) = self.control_rx.recv() => {
+                    self.touch();
+                    return Some(ReceivedPacket::Control(event));
+                }
+                else => return None,
+            }
         }
+    }
+
+    /// Track an audio packet's sequence, returning it unless it is a duplicate
+    async fn accept_audio(&self, packet: AudioPacket) -> Option<AudioPacket> {
+        self.touch();
 
+        let mut tracker = self.sequence_tracker.write().await;
+        match tracker.observe(packet.sequence) {
+            PacketStatus::Gap(gap) => {
+                tracing::debug!(
+                    "Packet loss detected: {} packets starting at seq {}",
+                    gap.count,
+                    gap.start
+                );
+            }
+            PacketStatus::Duplicate => {
+                tracing::trace!("Dropping duplicate packet seq {}", packet.sequence);
+                return None;
+            }
+            PacketStatus::Resync => {
+                tracing::debug!("Sequence resync at seq {}", packet.sequence);
+            }
+            PacketStatus::InOrder | PacketStatus::Recovered | PacketStatus::Late => {}
+        }
         Some(packet)
     }
 
-    /// Receive next control event
-    pub async fn recv_control(&mut self) -> Option<ControlEvent> {
-        self.control_rx.recv().await
+    fn touch(&self) {
+        if let Some(activity) = &self.activity {
+            activity.touch();
+        }
+    }
+
+    /// Record received audio and control packets as session activity
+    ///
+    /// Keeps the session from being reaped as stale while the sender is
+    /// streaming, see [`SessionManager::activity`](super::session_manager::SessionManager::activity).
+    pub fn track_activity(&mut self, activity: ActivityTracker) {
+        self.activity = Some(activity);
     }
 
     /// Get sequence tracker for statistics
@@ -110,10 +202,35 @@ impl ReceiverManager {
         self.sequence_tracker.clone()
     }
 
-    /// Stop all receivers
-    pub fn stop(self) {
-        for handle in self.handles {
-            handle.abort();
+    /// Start requesting retransmission of missing packets
+    ///
+    /// Requests are sent from `control_socket` to the sender's control port
+    /// at `sender_addr`.
+    pub fn request_retransmits(&mut self, control_socket: Arc<UdpSocket>, sender_addr: SocketAddr) {
+        if self.retransmit_stats.is_some() {
+            return;
+        }
+
+        let requester = RetransmitRequester::new(
+            control_socket,
+            sender_addr,
+            self.sequence_tracker.clone(),
+            self.config.retransmit.clone(),
+        );
+        self.retransmit_stats = Some(requester.stats_handle());
+        self.tasks.spawn(requester.run());
+    }
+
+    /// Counters for retransmit requests sent, if requesting is enabled
+    pub async fn retransmit_stats(&self) -> Option<RetransmitRequestStats> {
+        match &self.retransmit_stats {
+            Some(stats) => Some(stats.lock().await.clone()),
+            None => None,
         }
     }
+
+    /// Stop all receivers
+    pub fn stop(mut self) {
+        self.tasks.abort_all();
+    }
 }
@@ -4,8 +4,8 @@
 //! Handlers are pure functions that take a request and session state,
 //! returning a response. No I/O is performed.
 
+use crate::protocol::rtsp::headers::PublicMethods;
 use crate::protocol::rtsp::server_codec::ResponseBuilder;
-use crate::protocol::rtsp::transport::TransportHeader;
 use crate::protocol::rtsp::{Method, RtspRequest, RtspResponse, StatusCode};
 use crate::receiver::announce_handler;
 use crate::receiver::session::{ReceiverSession, SessionState, StreamParameters};
@@ -28,6 +28,8 @@ pub struct HandleResult {
     pub stop_streaming: bool,
     /// Parameter updates (from `SET_PARAMETER`)
     pub parameter_updates: Vec<ParameterUpdate>,
+    /// Sender asked the receiver to identify itself (`POST /identify`)
+    pub identify: bool,
 }
 
 /// Ports allocated during SETUP
@@ -71,24 +73,20 @@ pub fn handle_request(
 
 /// Handle OPTIONS request
 fn handle_options(cseq: u32) -> HandleResult {
-    let methods = [
-        "ANNOUNCE",
-        "SETUP",
-        "RECORD",
-        "PAUSE",
-        "FLUSH",
-        "TEARDOWN",
-        "OPTIONS",
-        "GET_PARAMETER",
-        "SET_PARAMETER",
-        "POST",
-    ]
-    .join(", ");
-
-    let response = ResponseBuilder::ok()
-        .cseq(cseq)
-        .header("Public", &methods)
-        .build();
+    let methods = PublicMethods(vec![
+        Method::Announce,
+        Method::Setup,
+        Method::Record,
+        Method::Pause,
+        Method::Flush,
+        Method::Teardown,
+        Method::Options,
+        Method::GetParameter,
+        Method::SetParameter,
+        Method::Post,
+    ]);
+
+    let response = ResponseBuilder::ok().cseq(cseq).public(&methods).build();
 
     HandleResult {
         response,
@@ -98,6 +96,7 @@ fn handle_options(cseq: u32) -> HandleResult {
         start_streaming: false,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -124,6 +123,7 @@ fn handle_announce(
                 start_streaming: false,
                 stop_streaming: false,
                 parameter_updates: Vec::new(),
+                identify: false,
             }
         }
         Err(e) => {
@@ -135,12 +135,7 @@ fn handle_announce(
 
 /// Handle SETUP request
 fn handle_setup(request: &RtspRequest, cseq: u32, _session: &ReceiverSession) -> HandleResult {
-    // Parse Transport header
-    let Some(transport_str) = request.headers.get("Transport") else {
-        return error_result(StatusCode::BAD_REQUEST, cseq);
-    };
-
-    let Ok(client_transport) = TransportHeader::parse(transport_str) else {
+    let Ok(Some(client_transport)) = request.headers.transport() else {
         return error_result(StatusCode::BAD_REQUEST, cseq);
     };
 
@@ -157,17 +152,13 @@ fn handle_setup(request: &RtspRequest, cseq: u32, _session: &ReceiverSession) ->
     // Generate session ID
     let session_id = generate_session_id();
 
-    // Build response Transport header
-    let response_transport = client_transport.to_response_header(
-        ports.audio_port,
-        ports.control_port,
-        ports.timing_port,
-    );
+    let response_transport =
+        client_transport.response(ports.audio_port, ports.control_port, ports.timing_port);
 
     let response = ResponseBuilder::ok()
         .cseq(cseq)
         .session(&session_id)
-        .header("Transport", &response_transport)
+        .transport(&response_transport)
         .build();
 
     HandleResult {
@@ -178,6 +169,7 @@ fn handle_setup(request: &RtspRequest, cseq: u32, _session: &ReceiverSession) ->
         start_streaming: false,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -208,6 +200,7 @@ fn handle_record(request: &RtspRequest, cseq: u32, session: &ReceiverSession) ->
         start_streaming: true,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -227,6 +220,7 @@ fn handle_pause(cseq: u32, session: &ReceiverSession) -> HandleResult {
         start_streaming: false,
         stop_streaming: false, // Keep session alive, just pause output
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -253,6 +247,7 @@ fn handle_flush(request: &RtspRequest, cseq: u32, session: &ReceiverSession) ->
         start_streaming: false,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -268,6 +263,7 @@ fn handle_teardown(cseq: u32, _session: &ReceiverSession) -> HandleResult {
         start_streaming: false,
         stop_streaming: true,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -305,6 +301,7 @@ fn handle_get_parameter(
         start_streaming: false,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -327,17 +324,21 @@ fn handle_set_parameter(
         start_streaming: false,
         stop_streaming: false,
         parameter_updates,
+        identify: false,
     }
 }
 
-/// Handle POST (pairing, auth)
-fn handle_post(_request: &RtspRequest, cseq: u32, _session: &ReceiverSession) -> HandleResult {
-    // POST is used for pairing endpoints like /pair-setup, /pair-verify
-    // For now, return not implemented
-
-    let response = ResponseBuilder::error(StatusCode::NOT_IMPLEMENTED)
-        .cseq(cseq)
-        .build();
+/// Handle POST (pairing, auth, identify)
+fn handle_post(request: &RtspRequest, cseq: u32, _session: &ReceiverSession) -> HandleResult {
+    // Pairing endpoints like /pair-setup, /pair-verify are not implemented
+    let identify = request.uri.ends_with("/identify");
+    let response = if identify {
+        ResponseBuilder::ok().cseq(cseq).build()
+    } else {
+        ResponseBuilder::error(StatusCode::NOT_IMPLEMENTED)
+            .cseq(cseq)
+            .build()
+    };
 
     HandleResult {
         response,
@@ -347,6 +348,7 @@ fn handle_post(_request: &RtspRequest, cseq: u32, _session: &ReceiverSession) ->
         start_streaming: false,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify,
     }
 }
 
@@ -367,6 +369,7 @@ fn error_result(status: StatusCode, cseq: u32) -> HandleResult {
         start_streaming: false,
         stop_streaming: false,
         parameter_updates: Vec::new(),
+        identify: false,
     }
 }
 
@@ -0,0 +1,233 @@
+//! Shared runtime for hosting several receivers in one process
+//!
+//! A multi-DAC server may want to expose independent zones (e.g. "Kitchen"
+//! and "Office") from a single process. Each zone is a separate
+//! [`AirPlayReceiver`](super::AirPlayReceiver), but process-wide resources
+//! such as the mDNS daemon and the PTP clock are owned by a
+//! [`ReceiverRuntime`] and shared between them, and each zone is given its
+//! own slice of UDP ports.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use mdns_sd::ServiceDaemon;
+
+use crate::discovery::advertiser::AdvertiserError;
+use crate::protocol::ptp::{PtpRole, SharedPtpClock, create_shared_clock};
+
+/// Size of the UDP port slice handed to each zone
+pub const ZONE_UDP_PORT_RANGE: u16 = 100;
+
+/// Errors from the shared receiver runtime
+#[derive(Debug, thiserror::Error)]
+pub enum RuntimeError {
+    /// A zone with this name is already registered
+    #[error("Zone name already in use: {0}")]
+    DuplicateName(String),
+
+    /// A zone is already listening on this port
+    #[error("Zone port already in use: {0}")]
+    DuplicatePort(u16),
+
+    /// No free UDP port slice left for a new zone
+    #[error("No UDP port range available for zone")]
+    PortRangeExhausted,
+
+    /// mDNS daemon could not be created
+    #[error("mDNS error: {0}")]
+    Mdns(#[from] AdvertiserError),
+}
+
+/// Runtime configuration
+#[derive(Debug, Clone)]
+pub struct RuntimeConfig {
+    /// First UDP port handed out to zones
+    pub udp_base_port: u16,
+    /// Maximum number of concurrently registered zones
+    pub max_zones: u16,
+    /// PTP clock identity used by the shared clock
+    pub ptp_clock_id: u64,
+}
+
+impl Default for RuntimeConfig {
+    fn default() -> Self {
+        Self {
+            udp_base_port: 6000,
+            max_zones: 16,
+            ptp_clock_id: 0,
+        }
+    }
+}
+
+/// Resources reserved for a single zone
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZoneLease {
+    /// Zone (receiver) name
+    pub name: String,
+    /// RTSP port requested by the zone (0 = auto-assign)
+    pub port: u16,
+    /// First UDP port of the zone's slice
+    pub udp_base_port: u16,
+    /// Number of UDP ports in the slice
+    pub udp_port_range: u16,
+}
+
+#[derive(Default)]
+struct ZoneRegistry {
+    /// Zone name -> (UDP slot index, RTSP port)
+    zones: HashMap<String, (u16, u16)>,
+}
+
+struct RuntimeInner {
+    config: RuntimeConfig,
+    daemon: Mutex<Option<ServiceDaemon>>,
+    ptp_clock: SharedPtpClock,
+    registry: Mutex<ZoneRegistry>,
+}
+
+/// Process-wide resources shared by multiple receivers
+///
+/// Cloning a runtime is cheap; all clones refer to the same resources.
+#[derive(Clone)]
+pub struct ReceiverRuntime {
+    inner: Arc<RuntimeInner>,
+}
+
+impl ReceiverRuntime {
+    /// Create a runtime with default configuration
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(RuntimeConfig::default())
+    }
+
+    /// Create a runtime with custom configuration
+    #[must_use]
+    pub fn with_config(config: RuntimeConfig) -> Self {
+        let ptp_clock = create_shared_clock(config.ptp_clock_id, PtpRole::Slave);
+        Self {
+            inner: Arc::new(RuntimeInner {
+                config,
+                daemon: Mutex::new(None),
+                ptp_clock,
+                registry: Mutex::new(ZoneRegistry::default()),
+            }),
+        }
+    }
+
+    /// Get the runtime configuration
+    #[must_use]
+    pub fn config(&self) -> &RuntimeConfig {
+        &self.inner.config
+    }
+
+    /// Get the shared mDNS daemon, creating it on first use
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the mDNS daemon cannot be started.
+    pub fn mdns_daemon(&self) -> Result<ServiceDaemon, RuntimeError> {
+        let mut daemon = self
+            .inner
+            .daemon
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        if let Some(daemon) = daemon.as_ref() {
+            return Ok(daemon.clone());
+        }
+
+        let created = ServiceDaemon::new().map_err(AdvertiserError::Mdns)?;
+        *daemon = Some(created.clone());
+        Ok(created)
+    }
+
+    /// Get the PTP clock shared by all zones
+    #[must_use]
+    pub fn ptp_clock(&self) -> SharedPtpClock {
+        self.inner.ptp_clock.clone()
+    }
+
+    /// Reserve a zone name, RTSP port and UDP port slice
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the name or a non-zero port is already in use, or if
+    /// all UDP port slices are taken.
+    pub fn register_zone(&self, name: &str, port: u16) -> Result<ZoneLease, RuntimeError> {
+        let mut registry = self.registry();
+
+        if registry.zones.contains_key(name) {
+            return Err(RuntimeError::DuplicateName(name.to_string()));
+        }
+        if port != 0 && registry.zones.values().any(|(_, p)| *p == port) {
+            return Err(RuntimeError::DuplicatePort(port));
+        }
+
+        let slot = (0..self.inner.config.max_zones)
+            .find(|slot| !registry.zones.values().any(|(s, _)| s == slot))
+            .ok_or(RuntimeError::PortRangeExhausted)?;
+
+        let udp_base_port = slot
+            .checked_mul(ZONE_UDP_PORT_RANGE)
+            .and_then(|offset| self.inner.config.udp_base_port.checked_add(offset))
+            .ok_or(RuntimeError::PortRangeExhausted)?;
+
+        registry.zones.insert(name.to_string(), (slot, port));
+
+        Ok(ZoneLease {
+            name: name.to_string(),
+            port,
+            udp_base_port,
+            udp_port_range: ZONE_UDP_PORT_RANGE,
+        })
+    }
+
+    /// Release a previously registered zone
+    pub fn release_zone(&self, name: &str) {
+        self.registry().zones.remove(name);
+    }
+
+    /// Names of the currently registered zones
+    #[must_use]
+    pub fn zones(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.registry().zones.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Shut down shared resources
+    ///
+    /// Receivers should be stopped before calling this.
+    pub fn shutdown(&self) {
+        let daemon = self
+            .inner
+            .daemon
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .take();
+        if let Some(daemon) = daemon {
+            let _ = daemon.shutdown();
+        }
+    }
+
+    fn registry(&self) -> std::sync::MutexGuard<'_, ZoneRegistry> {
+        self.inner
+            .registry
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Default for ReceiverRuntime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl std::fmt::Debug for ReceiverRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ReceiverRuntime")
+            .field("config", &self.inner.config)
+            .field("zones", &self.zones())
+            .finish_non_exhaustive()
+    }
+}
@@ -1,23 +1,148 @@
 //! RTP sequence number tracking and packet loss detection
 
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::time::{Duration, Instant};
+
+use super::control_receiver::RetransmitRequest;
+
+/// Policy for generating retransmit requests for missing packets
+#[derive(Debug, Clone)]
+pub struct RetransmitPolicy {
+    /// Generate retransmit requests at all
+    pub enabled: bool,
+    /// Maximum number of times a single packet is re-requested
+    pub max_rerequests: u32,
+    /// Delay before the first re-request of the same packet
+    pub retry_interval: Duration,
+    /// Multiplier applied to the retry delay after each request
+    pub backoff_factor: u32,
+    /// Upper bound on the retry delay
+    pub max_retry_interval: Duration,
+    /// Time between a packet's arrival and its playout (buffer latency)
+    pub playout_latency: Duration,
+    /// Stop requesting a packet this long before it is due to be played
+    pub playout_margin: Duration,
+}
+
+impl Default for RetransmitPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_rerequests: 3,
+            retry_interval: Duration::from_millis(50),
+            backoff_factor: 2,
+            max_retry_interval: Duration::from_millis(400),
+            playout_latency: Duration::from_secs(2),
+            playout_margin: Duration::from_millis(100),
+        }
+    }
+}
+
+impl RetransmitPolicy {
+    /// Time after detection past which a missing packet is no longer worth
+    /// requesting
+    #[must_use]
+    pub fn deadline(&self) -> Duration {
+        self.playout_latency.saturating_sub(self.playout_margin)
+    }
+
+    /// Delay to wait after the `requests`-th request before asking again
+    #[must_use]
+    pub fn retry_delay(&self, requests: u32) -> Duration {
+        let factor = self
+            .backoff_factor
+            .max(1)
+            .saturating_pow(requests.saturating_sub(1));
+        self.retry_interval
+            .saturating_mul(factor)
+            .min(self.max_retry_interval.max(self.retry_interval))
+    }
+}
+
+/// Sequence tracker configuration
+#[derive(Debug, Clone)]
+pub struct SequenceTrackerConfig {
+    /// Packets a missing sequence may trail the newest one before it is
+    /// treated as lost rather than reordered
+    pub reorder_window: u16,
+    /// Number of recent sequence numbers remembered for duplicate detection
+    pub duplicate_history: u16,
+    /// Forward jumps of this many packets or more are a resync, not loss
+    pub max_gap: u16,
+    /// Maximum number of missing packets tracked at once
+    pub max_missing: usize,
+    /// Number of recent gaps kept for statistics
+    pub max_gap_history: usize,
+    /// Retransmit request policy
+    pub retransmit: RetransmitPolicy,
+}
+
+impl Default for SequenceTrackerConfig {
+    fn default() -> Self {
+        Self {
+            reorder_window: 4,
+            duplicate_history: 512,
+            max_gap: 1000,
+            max_missing: 512,
+            max_gap_history: 100,
+            retransmit: RetransmitPolicy::default(),
+        }
+    }
+}
+
+/// Classification of a received packet
+#[derive(Debug, Clone)]
+pub enum PacketStatus {
+    /// Next packet in sequence (or first packet)
+    InOrder,
+    /// Ahead of the expected sequence; the skipped packets are now missing
+    Gap(GapInfo),
+    /// A previously missing packet arrived late (reordered or retransmitted)
+    Recovered,
+    /// Already received
+    Duplicate,
+    /// Behind the expected sequence and no longer tracked as missing
+    Late,
+    /// Jump too large to be loss; tracking restarted at this packet
+    Resync,
+}
 
 /// Tracks RTP sequence numbers to detect gaps
 pub struct SequenceTracker {
+    /// Configuration
+    config: SequenceTrackerConfig,
     /// Last received sequence number
     last_seq: Option<u16>,
     /// Expected next sequence number
     expected_seq: Option<u16>,
     /// Recent gap history for statistics
     recent_gaps: VecDeque<GapInfo>,
-    /// Maximum history size
-    max_history: usize,
+    /// Packets currently missing, oldest first
+    missing: VecDeque<MissingPacket>,
+    /// Recently received sequence numbers, oldest first
+    recent_seqs: VecDeque<u16>,
+    /// Set view of `recent_seqs`
+    recent_set: HashSet<u16>,
     /// Total packets received
     packets_received: u64,
     /// Total gaps detected
     total_gaps: u64,
     /// Total packets lost
     total_lost: u64,
+    /// Duplicate packets received
+    duplicates: u64,
+    /// Missing packets that arrived later
+    recovered: u64,
+    /// Packets that arrived after they stopped being tracked
+    late: u64,
+    /// Sequence resyncs
+    resyncs: u64,
+    /// Largest single gap
+    largest_gap: u16,
+    /// Retransmit requests generated (counted per packet)
+    retransmits_requested: u64,
+    /// Missing packets given up on
+    abandoned: u64,
 }
 
 /// Information about a detected gap
@@ -31,56 +156,181 @@ pub struct GapInfo {
     pub detected_at: std::time::Instant,
 }
 
+/// A missing packet awaiting recovery
+#[derive(Debug, Clone)]
+struct MissingPacket {
+    seq: u16,
+    detected_at: Instant,
+    requests: u32,
+    last_request: Option<Instant>,
+}
+
+impl MissingPacket {
+    /// Whether this packet should be requested now
+    fn is_due(
+        &self,
+        expected: u16,
+        reorder_window: u16,
+        policy: &RetransmitPolicy,
+        now: Instant,
+    ) -> bool {
+        expected.wrapping_sub(self.seq) > reorder_window
+            && self.requests < policy.max_rerequests
+            && self.last_request.is_none_or(|last| {
+                now.saturating_duration_since(last) >= policy.retry_delay(self.requests)
+            })
+    }
+}
+
 impl SequenceTracker {
     /// Create a new sequence tracker
     #[must_use]
     pub fn new() -> Self {
+        Self::with_config(SequenceTrackerConfig::default())
+    }
+
+    /// Create a sequence tracker with custom configuration
+    #[must_use]
+    pub fn with_config(config: SequenceTrackerConfig) -> Self {
         Self {
+            recent_gaps: VecDeque::with_capacity(config.max_gap_history),
+            recent_seqs: VecDeque::with_capacity(usize::from(config.duplicate_history)),
+            config,
             last_seq: None,
             expected_seq: None,
-            recent_gaps: VecDeque::with_capacity(100),
-            max_history: 100,
+            missing: VecDeque::new(),
+            recent_set: HashSet::new(),
             packets_received: 0,
             total_gaps: 0,
             total_lost: 0,
+            duplicates: 0,
+            recovered: 0,
+            late: 0,
+            resyncs: 0,
+            largest_gap: 0,
+            retransmits_requested: 0,
+            abandoned: 0,
         }
     }
 
+    /// Get the configuration
+    #[must_use]
+    pub fn config(&self) -> &SequenceTrackerConfig {
+        &self.config
+    }
+
     /// Record a received packet, returning any detected gap
     pub fn record(&mut self, seq: u16) -> Option<GapInfo> {
-        self.packets_received += 1;
+        match self.observe(seq) {
+            PacketStatus::Gap(gap) => Some(gap),
+            _ => None,
+        }
+    }
 
-        let gap = if let Some(expected) = self.expected_seq {
-            let gap_size = Self::sequence_gap(expected, seq);
+    /// Record a received packet and classify it
+    pub fn observe(&mut self, seq: u16) -> PacketStatus {
+        self.observe_at(seq, Instant::now())
+    }
 
-            if gap_size > 0 && gap_size < 1000 {
-                // Gap detected (but not wrap-around)
-                self.total_gaps += 1;
-                self.total_lost += u64::from(gap_size);
+    /// Record a received packet at a given time and classify it
+    pub fn observe_at(&mut self, seq: u16, now: Instant) -> PacketStatus {
+        let Some(expected) = self.expected_seq else {
+            self.packets_received += 1;
+            self.advance(seq);
+            return PacketStatus::InOrder;
+        };
 
-                let gap_info = GapInfo {
-                    start: expected,
-                    count: gap_size,
-                    detected_at: std::time::Instant::now(),
-                };
+        let ahead = Self::sequence_gap(expected, seq);
+        if ahead == 0 {
+            self.packets_received += 1;
+            self.advance(seq);
+            return PacketStatus::InOrder;
+        }
+        if ahead < self.config.max_gap {
+            self.packets_received += 1;
+            let gap = self.open_gap(expected, ahead, now);
+            self.advance(seq);
+            return PacketStatus::Gap(gap);
+        }
 
-                if self.recent_gaps.len() >= self.max_history {
-                    self.recent_gaps.pop_front();
-                }
-                self.recent_gaps.push_back(gap_info.clone());
+        let behind = expected.wrapping_sub(seq);
+        if behind > self.config.duplicate_history {
+            // Too far from anything we know about: treat as a new stream
+            self.packets_received += 1;
+            self.resyncs += 1;
+            self.missing.clear();
+            self.advance(seq);
+            return PacketStatus::Resync;
+        }
 
-                Some(gap_info)
-            } else {
-                None
-            }
+        if let Some(pos) = self.missing.iter().position(|m| m.seq == seq) {
+            self.missing.remove(pos);
+            self.packets_received += 1;
+            self.recovered += 1;
+            self.total_lost = self.total_lost.saturating_sub(1);
+            self.remember(seq);
+            PacketStatus::Recovered
+        } else if self.recent_set.contains(&seq) {
+            self.duplicates += 1;
+            PacketStatus::Duplicate
         } else {
-            None
+            self.late += 1;
+            PacketStatus::Late
+        }
+    }
+
+    /// Register the packets skipped between `expected` and the new packet
+    fn open_gap(&mut self, expected: u16, count: u16, now: Instant) -> GapInfo {
+        self.total_gaps += 1;
+        self.total_lost += u64::from(count);
+        self.largest_gap = self.largest_gap.max(count);
+
+        for offset in 0..count {
+            if self.missing.len() >= self.config.max_missing {
+                self.missing.pop_front();
+                self.abandoned += 1;
+            }
+            self.missing.push_back(MissingPacket {
+                seq: expected.wrapping_add(offset),
+                detected_at: now,
+                requests: 0,
+                last_request: None,
+            });
+        }
+
+        let gap_info = GapInfo {
+            start: expected,
+            count,
+            detected_at: now,
         };
 
+        if self.recent_gaps.len() >= self.config.max_gap_history {
+            self.recent_gaps.pop_front();
+        }
+        if self.config.max_gap_history > 0 {
+            self.recent_gaps.push_back(gap_info.clone());
+        }
+
+        gap_info
+    }
+
+    fn advance(&mut self, seq: u16) {
         self.last_seq = Some(seq);
         self.expected_seq = Some(seq.wrapping_add(1));
+        self.remember(seq);
+    }
 
-        gap
+    fn remember(&mut self, seq: u16) {
+        if self.config.duplicate_history == 0 {
+            return;
+        }
+        if self.recent_seqs.len() >= usize::from(self.config.duplicate_history)
+            && let Some(old) = self.recent_seqs.pop_front()
+        {
+            self.recent_set.remove(&old);
+        }
+        self.recent_seqs.push_back(seq);
+        self.recent_set.insert(seq);
     }
 
     /// Calculate gap between expected and actual sequence numbers
@@ -93,15 +343,115 @@ impl SequenceTracker {
     #[must_use]
     pub fn is_expected(&self, seq: u16) -> bool {
         if let Some(expected) = self.expected_seq {
-            let diff = seq.wrapping_sub(expected);
-            // Accept if within reasonable window (ahead or slightly behind)
-            // diff < 1000 || diff > 65000
-            !(1000..=65000).contains(&diff)
+            if Self::sequence_gap(expected, seq) < self.config.max_gap {
+                return true;
+            }
+            self.missing.iter().any(|m| m.seq == seq)
         } else {
             true // First packet
         }
     }
 
+    /// Sequence numbers currently considered missing, oldest first
+    #[must_use]
+    pub fn missing(&self) -> Vec<u16> {
+        self.missing.iter().map(|m| m.seq).collect()
+    }
+
+    /// Recent gaps, oldest first
+    #[must_use]
+    pub fn recent_gaps(&self) -> &VecDeque<GapInfo> {
+        &self.recent_gaps
+    }
+
+    /// Generate retransmit requests for missing packets that are due
+    ///
+    /// A packet becomes eligible once it trails the newest packet by more
+    /// than the reorder window. It is requested at most `max_rerequests`
+    /// times with exponentially growing delays between attempts, and dropped
+    /// once the policy deadline has passed. Consecutive sequences are
+    /// coalesced.
+    pub fn retransmit_requests(&mut self, now: Instant) -> Vec<RetransmitRequest> {
+        self.retransmit_requests_up_to(now, usize::MAX)
+    }
+
+    /// Like [`retransmit_requests`](Self::retransmit_requests), but request
+    /// at most `max_packets` packets
+    ///
+    /// Packets left out because of the limit stay due and are picked up by a
+    /// later call.
+    pub fn retransmit_requests_up_to(
+        &mut self,
+        now: Instant,
+        max_packets: usize,
+    ) -> Vec<RetransmitRequest> {
+        let policy = self.config.retransmit.clone();
+        if !policy.enabled {
+            return Vec::new();
+        }
+        let Some(expected) = self.expected_seq else {
+            return Vec::new();
+        };
+
+        let deadline = policy.deadline();
+        let before = self.missing.len();
+        self.missing.retain(|m| {
+            now.saturating_duration_since(m.detected_at) < deadline
+                && (m.requests < policy.max_rerequests
+                    || m.last_request.is_none_or(|last| {
+                        now.saturating_duration_since(last) < policy.retry_delay(m.requests)
+                    }))
+        });
+        self.abandoned += (before - self.missing.len()) as u64;
+
+        let mut requests: Vec<RetransmitRequest> = Vec::new();
+        let mut budget = max_packets;
+        for missing in &mut self.missing {
+            if budget == 0 {
+                break;
+            }
+            if !missing.is_due(expected, self.config.reorder_window, &policy, now) {
+                continue;
+            }
+
+            missing.requests += 1;
+            missing.last_request = Some(now);
+            self.retransmits_requested += 1;
+            budget -= 1;
+
+            match requests.last_mut() {
+                Some(last) if last.first_seq.wrapping_add(last.count) == missing.seq => {
+                    last.count += 1;
+                }
+                _ => requests.push(RetransmitRequest {
+                    first_seq: missing.seq,
+                    count: 1,
+                }),
+            }
+        }
+
+        requests
+    }
+
+    /// Whether any missing packet is currently due for a retransmit request
+    #[must_use]
+    pub fn has_due_retransmits(&self, now: Instant) -> bool {
+        let policy = &self.config.retransmit;
+        let Some(expected) = self.expected_seq else {
+            return false;
+        };
+        policy.enabled
+            && self.missing.iter().any(|m| {
+                now.saturating_duration_since(m.detected_at) < policy.deadline()
+                    && m.is_due(expected, self.config.reorder_window, policy, now)
+            })
+    }
+
+    /// Update the playout latency used for retransmit deadlines
+    pub fn set_playout_latency(&mut self, latency: Duration) {
+        self.config.retransmit.playout_latency = latency;
+    }
+
     /// Get packet loss ratio (0.0 to 1.0)
     #[must_use]
     #[allow(
@@ -124,17 +474,21 @@ impl SequenceTracker {
             total_gaps: self.total_gaps,
             total_lost: self.total_lost,
             loss_ratio: self.loss_ratio(),
+            duplicates: self.duplicates,
+            recovered: self.recovered,
+            late: self.late,
+            resyncs: self.resyncs,
+            largest_gap: self.largest_gap,
+            currently_missing: self.missing.len(),
+            retransmits_requested: self.retransmits_requested,
+            abandoned: self.abandoned,
         }
     }
 
     /// Reset the tracker
     pub fn reset(&mut self) {
-        self.last_seq = None;
-        self.expected_seq = None;
-        self.recent_gaps.clear();
-        self.packets_received = 0;
-        self.total_gaps = 0;
-        self.total_lost = 0;
+        let config = self.config.clone();
+        *self = Self::with_config(config);
     }
 }
 
@@ -145,10 +499,26 @@ pub struct SequenceStats {
     pub packets_received: u64,
     /// Total gaps detected
     pub total_gaps: u64,
-    /// Total packets lost
+    /// Total packets lost (net of recovered packets)
     pub total_lost: u64,
     /// Loss ratio (0.0 to 1.0)
     pub loss_ratio: f64,
+    /// Duplicate packets received
+    pub duplicates: u64,
+    /// Missing packets that later arrived
+    pub recovered: u64,
+    /// Packets that arrived too late to be matched to a gap
+    pub late: u64,
+    /// Number of times tracking restarted after a large jump
+    pub resyncs: u64,
+    /// Largest single gap, in packets
+    pub largest_gap: u16,
+    /// Packets currently missing
+    pub currently_missing: usize,
+    /// Retransmit requests generated, counted per packet
+    pub retransmits_requested: u64,
+    /// Missing packets given up on
+    pub abandoned: u64,
 }
 
 impl Default for SequenceTracker {
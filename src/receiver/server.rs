@@ -5,14 +5,18 @@ use std::sync::Arc;
 
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{RwLock, broadcast, mpsc};
+use tokio::task::{JoinHandle, JoinSet};
 
 use super::config::ReceiverConfig;
 use super::events::ReceiverEvent;
-use super::session_manager::{SessionManager, SessionManagerConfig};
+use super::receiver_manager::{ReceivedPacket, ReceiverManager};
+use super::runtime::{ReceiverRuntime, ZoneLease};
+use super::session_manager::{SessionEvent, SessionManager, SessionManagerConfig};
 use super::set_parameter_handler::ParameterUpdate;
 use crate::discovery::advertiser::{AdvertiserConfig, AsyncRaopAdvertiser};
 use crate::net::{AsyncReadExt, AsyncWriteExt};
-use crate::protocol::rtsp::transport::TransportHeader;
+use crate::protocol::ptp::{PtpRole, SharedPtpClock, create_shared_clock};
+use crate::protocol::rtsp::headers::names;
 use crate::protocol::rtsp::{RtspRequest, RtspServerCodec, encode_response};
 
 /// `AirPlay` 1 receiver
@@ -21,6 +25,9 @@ pub struct AirPlayReceiver {
     state: Arc<RwLock<ReceiverState>>,
     event_tx: broadcast::Sender<ReceiverEvent>,
     shutdown_tx: Option<mpsc::Sender<()>>,
+    server_task: Option<JoinHandle<()>>,
+    runtime: Option<ReceiverRuntime>,
+    ptp_clock: SharedPtpClock,
 }
 
 /// Receiver state
@@ -47,6 +54,22 @@ impl AirPlayReceiver {
             state: Arc::new(RwLock::new(ReceiverState::Stopped)),
             event_tx,
             shutdown_tx: None,
+            server_task: None,
+            runtime: None,
+            ptp_clock: create_shared_clock(0, PtpRole::Slave),
+        }
+    }
+
+    /// Create a receiver that shares resources with other zones
+    ///
+    /// All receivers created from the same [`ReceiverRuntime`] share one mDNS
+    /// daemon and PTP clock, and are given disjoint UDP port ranges.
+    #[must_use]
+    pub fn with_runtime(config: ReceiverConfig, runtime: ReceiverRuntime) -> Self {
+        Self {
+            ptp_clock: runtime.ptp_clock(),
+            runtime: Some(runtime),
+            ..Self::new(config)
         }
     }
 
@@ -66,6 +89,26 @@ impl AirPlayReceiver {
         *self.state.read().await
     }
 
+    /// Get the configuration
+    #[must_use]
+    pub fn config(&self) -> &ReceiverConfig {
+        &self.config
+    }
+
+    /// Get the shared runtime, if this receiver is part of one
+    #[must_use]
+    pub fn runtime(&self) -> Option<&ReceiverRuntime> {
+        self.runtime.as_ref()
+    }
+
+    /// Get the receiver's PTP clock
+    ///
+    /// Zones of one [`ReceiverRuntime`] all return the runtime's clock.
+    #[must_use]
+    pub fn ptp_clock(&self) -> SharedPtpClock {
+        self.ptp_clock.clone()
+    }
+
     /// Start the receiver
     ///
     /// # Errors
@@ -80,6 +123,41 @@ impl AirPlayReceiver {
             *state = ReceiverState::Starting;
         }
 
+        // Reserve zone resources when sharing a runtime
+        let lease = match self
+            .runtime
+            .as_ref()
+            .map(|runtime| runtime.register_zone(&self.config.name, self.config.port))
+            .transpose()
+        {
+            Ok(lease) => lease,
+            Err(e) => {
+                *self.state.write().await = ReceiverState::Stopped;
+                return Err(ReceiverError::Runtime(e.to_string()));
+            }
+        };
+
+        match self.start_inner(lease).await {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                if let Some(runtime) = &self.runtime {
+                    runtime.release_zone(&self.config.name);
+                }
+                self.shutdown_tx = None;
+                *self.state.write().await = ReceiverState::Stopped;
+                Err(e)
+            }
+        }
+    }
+
+    async fn start_inner(&mut self, lease: Option<ZoneLease>) -> Result<(), ReceiverError> {
+        let daemon = self
+            .runtime
+            .as_ref()
+            .map(ReceiverRuntime::mdns_daemon)
+            .transpose()
+            .map_err(|e| ReceiverError::Advertisement(e.to_string()))?;
+
         // Create shutdown channel
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
         self.shutdown_tx = Some(shutdown_tx);
@@ -92,7 +170,7 @@ impl AirPlayReceiver {
             ..Default::default()
         };
 
-        let advertiser = AsyncRaopAdvertiser::start(advertiser_config)
+        let advertiser = AsyncRaopAdvertiser::start_with_daemon(advertiser_config, daemon)
             .await
             .map_err(|e| ReceiverError::Advertisement(e.to_string()))?;
 
@@ -104,15 +182,28 @@ impl AirPlayReceiver {
         let actual_port = listener.local_addr()?.port();
 
         // Create session manager
-        let session_manager = Arc::new(SessionManager::new(SessionManagerConfig {
+        let mut session_config = SessionManagerConfig {
             idle_timeout: self.config.session_timeout,
+            streaming_timeout: self.config.streaming_timeout,
             preemption_policy: if self.config.allow_preemption {
                 super::session_manager::PreemptionPolicy::AllowPreempt
             } else {
                 super::session_manager::PreemptionPolicy::Reject
             },
             ..Default::default()
-        }));
+        };
+        if let Some(ZoneLease {
+            udp_base_port,
+            udp_port_range,
+            ..
+        }) = lease
+        {
+            session_config.udp_base_port = udp_base_port;
+            session_config.udp_port_range = udp_port_range;
+        }
+        let session_manager = Arc::new(SessionManager::new(session_config));
+        let mut session_events = session_manager.subscribe();
+        let timeout_monitor = session_manager.start_timeout_monitor();
 
         // Emit started event
         let _ = self.event_tx.send(ReceiverEvent::Started {
@@ -126,35 +217,37 @@ impl AirPlayReceiver {
         let event_tx = self.event_tx.clone();
         let state = self.state.clone();
         let config = self.config.clone();
+        let runtime = self.runtime.clone();
 
         // Main server loop
-        tokio::spawn(async move {
+        self.server_task = Some(tokio::spawn(async move {
+            // Connections end with the server
+            let mut connections = JoinSet::new();
             loop {
                 tokio::select! {
                     result = listener.accept() => {
                         match result {
                             Ok((stream, addr)) => {
-                                let session_manager = session_manager.clone();
-                                let event_tx = event_tx.clone();
-                                let config = config.clone();
-
-                                tokio::spawn(async move {
-                                    if let Err(e) = handle_connection(
-                                        stream,
-                                        addr,
-                                        session_manager,
-                                        event_tx,
-                                        config,
-                                    ).await {
-                                        tracing::error!("Connection error: {}", e);
-                                    }
-                                });
+                                connections.spawn(handle_connection(
+                                    stream,
+                                    addr,
+                                    session_manager.clone(),
+                                    event_tx.clone(),
+                                    config.clone(),
+                                ));
                             }
                             Err(e) => {
                                 tracing::error!("Accept error: {}", e);
                             }
                         }
                     }
+                    Some(result) = connections.join_next() => log_connection_end(result),
+                    event = session_events.recv() => {
+                        if matches!(event, Err(broadcast::error::RecvError::Closed)) {
+                            break;
+                        }
+                        update_busy_status(&advertiser, &session_manager, event).await;
+                    }
                     _ = shutdown_rx.recv() => {
                         break;
                     }
@@ -162,10 +255,16 @@ impl AirPlayReceiver {
             }
 
             // Cleanup
+            connections.shutdown().await;
+            timeout_monitor.abort();
+            session_manager.end_session("Receiver stopped").await;
             advertiser.shutdown().await;
+            if let Some(runtime) = runtime {
+                runtime.release_zone(&config.name);
+            }
             *state.write().await = ReceiverState::Stopped;
             let _ = event_tx.send(ReceiverEvent::Stopped);
-        });
+        }));
 
         Ok(())
     }
@@ -177,13 +276,44 @@ impl AirPlayReceiver {
     /// Returns error if receiver cannot stop (should not happen).
     pub async fn stop(&mut self) -> Result<(), ReceiverError> {
         if let Some(tx) = self.shutdown_tx.take() {
-            let _ = tx.send(()).await;
             *self.state.write().await = ReceiverState::Stopping;
+            let _ = tx.send(()).await;
+        }
+        // Wait for connections to close and the advertisement to go
+        if let Some(task) = self.server_task.take() {
+            let _ = task.await;
         }
         Ok(())
     }
 }
 
+/// Report a connection that ended with an error or panicked
+fn log_connection_end(result: Result<Result<(), ReceiverError>, tokio::task::JoinError>) {
+    match result {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => tracing::error!("Connection error: {}", e),
+        Err(e) => tracing::error!("Connection task failed: {}", e),
+    }
+}
+
+/// Advertise the receiver as busy only while a sender holds the session
+async fn update_busy_status(
+    advertiser: &AsyncRaopAdvertiser,
+    session_manager: &SessionManager,
+    event: Result<SessionEvent, broadcast::error::RecvError>,
+) {
+    let busy = match event {
+        Ok(SessionEvent::SessionStarted { .. }) => true,
+        Ok(SessionEvent::SessionEnded { .. }) | Err(_) => {
+            session_manager.has_active_session().await
+        }
+        Ok(_) => return,
+    };
+    if let Err(e) = advertiser.set_busy(busy).await {
+        tracing::warn!("Failed to update busy status: {}", e);
+    }
+}
+
 /// Handle a single client connection
 async fn handle_connection(
     mut stream: TcpStream,
@@ -197,8 +327,11 @@ async fn handle_connection(
         user_agent: None,
     });
 
+    // Subscribe before starting so the session's end is never missed
+    let mut session_events = session_manager.subscribe();
+
     // Start session
-    let _session_id = session_manager
+    let session_id = session_manager
         .start_session(addr)
         .await
         .map_err(|e| ReceiverError::Session(e.to_string()))?;
@@ -208,9 +341,21 @@ async fn handle_connection(
 
     let mut codec = RtspServerCodec::new();
     let mut buf = vec![0u8; 4096];
+    let mut reason = "Connection closed".to_string();
+    // UDP receive loops, started by SETUP and stopped with the connection
+    let mut receivers: Option<ReceiverManager> = None;
 
     loop {
-        let n = match stream.read(&mut buf).await {
+        let read = tokio::select! {
+            read = stream.read(&mut buf) => read,
+            ended = session_ended(&mut session_events, &session_id) => {
+                // Reaped or preempted: drop the connection too
+                reason = ended;
+                break;
+            }
+            () = next_packet(receivers.as_mut()) => continue,
+        };
+        let n = match read {
             Ok(0) => break, // Connection closed
             Ok(n) => n,
             Err(e) => {
@@ -222,6 +367,9 @@ async fn handle_connection(
         codec.feed(&buf[..n]);
 
         while let Ok(Some(request)) = codec.decode() {
+            // Any request, including keep-alive OPTIONS, counts as activity
+            session_manager.touch_session().await;
+
             // Process request
             let mut result = session_manager
                 .with_session(|session| {
@@ -235,6 +383,12 @@ async fn handle_connection(
             // Handle parameter updates
             process_parameter_updates(&result.parameter_updates, &session_manager, &event_tx).await;
 
+            if let Some(params) = result.stream_params.take() {
+                let _ = session_manager
+                    .with_session(|session| session.set_stream_params(params))
+                    .await;
+            }
+
             // Handle port allocation for SETUP
             if let Some(ref ports_req) = result.allocated_ports {
                 handle_setup_ports(
@@ -245,6 +399,7 @@ async fn handle_connection(
                     addr,
                 )
                 .await?;
+                receivers = start_receivers(&session_manager).await;
             }
 
             // Send response
@@ -253,40 +408,125 @@ async fn handle_connection(
                 break;
             }
 
-            // Handle state changes
-            if let Some(new_state) = result.new_state {
-                let _ = session_manager.update_state(new_state).await;
+            if result.identify {
+                let _ = event_tx.send(ReceiverEvent::Identify { address: addr });
+            }
 
-                match new_state {
-                    super::session::SessionState::Streaming => {
-                        let _ = event_tx.send(ReceiverEvent::PlaybackStarted);
-                    }
-                    super::session::SessionState::Paused => {
-                        let _ = event_tx.send(ReceiverEvent::PlaybackPaused);
-                    }
-                    super::session::SessionState::Teardown => {
-                        let _ = event_tx.send(ReceiverEvent::PlaybackStopped);
-                    }
-                    _ => {}
-                }
+            if let Some(new_state) = result.new_state {
+                apply_state_change(new_state, &session_manager, &event_tx).await;
             }
 
             if result.stop_streaming {
                 break;
             }
         }
+
+        // RTP and RTCP interleaved on the connection by a sender using a
+        // TCP transport
+        while let Some(frame) = codec.decode_interleaved() {
+            session_manager.touch_session().await;
+            tracing::trace!(
+                "Interleaved frame on channel {} ({} bytes)",
+                frame.channel,
+                frame.payload.len()
+            );
+        }
     }
 
     // Cleanup
-    session_manager.end_session("Connection closed").await;
+    session_manager
+        .end_session_with_id(&session_id, "Connection closed")
+        .await;
     let _ = event_tx.send(ReceiverEvent::ClientDisconnected {
         address: addr,
-        reason: "Connection closed".to_string(),
+        reason,
     });
 
     Ok(())
 }
 
+/// Move the session to `new_state` and report playback changes
+async fn apply_state_change(
+    new_state: super::session::SessionState,
+    session_manager: &SessionManager,
+    event_tx: &broadcast::Sender<ReceiverEvent>,
+) {
+    let _ = session_manager.update_state(new_state).await;
+
+    match new_state {
+        super::session::SessionState::Streaming => {
+            let _ = event_tx.send(ReceiverEvent::PlaybackStarted);
+        }
+        super::session::SessionState::Paused => {
+            let _ = event_tx.send(ReceiverEvent::PlaybackPaused);
+        }
+        super::session::SessionState::Teardown => {
+            let _ = event_tx.send(ReceiverEvent::PlaybackStopped);
+        }
+        _ => {}
+    }
+}
+
+/// Start the RTP and control receive loops on the session's UDP sockets
+///
+/// Their packets count as session activity, so a sender that streams over
+/// UDP without sending RTSP requests is not reaped as stale.
+async fn start_receivers(session_manager: &SessionManager) -> Option<ReceiverManager> {
+    let (audio, control) = {
+        let sockets = session_manager.get_sockets()?;
+        let sockets = sockets.lock().await;
+        let sockets = sockets.as_ref()?;
+        (sockets.audio.clone(), sockets.control.clone())
+    };
+    let params = session_manager
+        .with_session(|session| session.stream_params().cloned())
+        .await
+        .ok()
+        .flatten()
+        .unwrap_or_default();
+
+    let mut receivers = ReceiverManager::start(
+        audio,
+        control,
+        params,
+        super::receiver_manager::ReceiverConfig::default(),
+    );
+    receivers.track_activity(session_manager.activity().await);
+    Some(receivers)
+}
+
+/// Take the next packet from the receive loops, if they are running
+///
+/// There is no audio output yet, so packets are only logged.
+async fn next_packet(receivers: Option<&mut ReceiverManager>) {
+    let Some(receivers) = receivers else {
+        return std::future::pending().await;
+    };
+    match receivers.recv().await {
+        Some(ReceivedPacket::Audio(packet)) => tracing::trace!(
+            "RTP packet seq {} ({} bytes)",
+            packet.sequence,
+            packet.audio_data.len()
+        ),
+        Some(ReceivedPacket::Control(event)) => tracing::trace!("Control event {:?}", event),
+        None => std::future::pending().await,
+    }
+}
+
+/// Wait for the session with `session_id` to end, returning the reason
+async fn session_ended(events: &mut broadcast::Receiver<SessionEvent>, session_id: &str) -> String {
+    loop {
+        match events.recv().await {
+            Ok(SessionEvent::SessionEnded {
+                session_id: ended,
+                reason,
+            }) if ended == session_id => return reason,
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => std::future::pending().await,
+        }
+    }
+}
+
 async fn process_parameter_updates(
     updates: &[ParameterUpdate],
     session_manager: &SessionManager,
@@ -346,11 +586,9 @@ async fn handle_setup_ports(
         .await;
 
     // Update Transport header in response
-    if let Some(transport_str) = request.headers.get("Transport") {
-        if let Ok(transport) = TransportHeader::parse(transport_str) {
-            let new_header = transport.to_response_header(audio_port, control_port, timing_port);
-            response.headers.insert("Transport".to_string(), new_header);
-        }
+    if let Ok(Some(transport)) = request.headers.transport() {
+        let new_header = transport.to_response_header(audio_port, control_port, timing_port);
+        response.headers.insert(names::TRANSPORT, new_header);
     }
     Ok(())
 }
@@ -378,6 +616,10 @@ pub enum ReceiverError {
     #[error("Audio error: {0}")]
     Audio(String),
 
+    /// Shared runtime error (e.g. duplicate zone)
+    #[error("Runtime error: {0}")]
+    Runtime(String),
+
     /// IO error
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -5,7 +5,8 @@
 
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
 
 use tokio::net::UdpSocket;
 use tokio::sync::{Mutex, RwLock, broadcast};
@@ -29,6 +30,12 @@ pub enum PreemptionPolicy {
 pub struct SessionManagerConfig {
     /// Session idle timeout
     pub idle_timeout: Duration,
+    /// Inactivity timeout while streaming
+    ///
+    /// Senders deliver audio and sync packets continuously while playing, so
+    /// a streaming session with no traffic for this long is assumed gone,
+    /// e.g. a phone that left the network without a TEARDOWN.
+    pub streaming_timeout: Duration,
     /// Maximum session duration (0 = unlimited)
     pub max_duration: Duration,
     /// Preemption policy
@@ -43,6 +50,7 @@ impl Default for SessionManagerConfig {
     fn default() -> Self {
         Self {
             idle_timeout: Duration::from_secs(60),
+            streaming_timeout: Duration::from_secs(10),
             max_duration: Duration::ZERO, // Unlimited
             preemption_policy: PreemptionPolicy::AllowPreempt,
             udp_base_port: 6000,
@@ -84,6 +92,47 @@ pub enum SessionEvent {
     },
 }
 
+/// Time of the last traffic from the sender
+///
+/// Cheap to clone and to touch, so the RTP and control receive loops can
+/// record every packet without locking the session.
+#[derive(Debug, Clone)]
+pub struct ActivityTracker {
+    epoch: Instant,
+    /// Milliseconds after `epoch` of the last activity
+    last_ms: Arc<AtomicU64>,
+}
+
+impl ActivityTracker {
+    /// Create a tracker with activity recorded now
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            epoch: Instant::now(),
+            last_ms: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Record activity now
+    pub fn touch(&self) {
+        let elapsed = u64::try_from(self.epoch.elapsed().as_millis()).unwrap_or(u64::MAX);
+        self.last_ms.fetch_max(elapsed, Ordering::Relaxed);
+    }
+
+    /// Time since the last activity
+    #[must_use]
+    pub fn idle_time(&self) -> Duration {
+        let last = Duration::from_millis(self.last_ms.load(Ordering::Relaxed));
+        self.epoch.elapsed().saturating_sub(last)
+    }
+}
+
+impl Default for ActivityTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 /// Manages receiver sessions
 pub struct SessionManager {
     config: SessionManagerConfig,
@@ -95,16 +144,20 @@ pub struct SessionManager {
     port_allocator: Arc<Mutex<PortAllocator>>,
     /// Event broadcaster
     event_tx: broadcast::Sender<SessionEvent>,
+    /// Traffic on the current session
+    activity: RwLock<ActivityTracker>,
 }
 
 /// Allocated UDP sockets for a session
+///
+/// Shared so the receive loops can read them while the session owns them.
 pub struct AllocatedSockets {
     /// Audio data socket
-    pub audio: UdpSocket,
+    pub audio: Arc<UdpSocket>,
     /// Control socket
-    pub control: UdpSocket,
+    pub control: Arc<UdpSocket>,
     /// Timing socket
-    pub timing: UdpSocket,
+    pub timing: Arc<UdpSocket>,
 }
 
 impl AllocatedSockets {
@@ -170,6 +223,7 @@ impl SessionManager {
             active_session: Arc::new(RwLock::new(None)),
             sockets: Arc::new(Mutex::new(None)),
             event_tx,
+            activity: RwLock::new(ActivityTracker::new()),
         }
     }
 
@@ -193,6 +247,14 @@ impl SessionManager {
             .map(|s| s.id().to_string())
     }
 
+    /// Activity tracker for the current session
+    ///
+    /// Hand this to the RTP and control receive loops so their traffic keeps
+    /// the session alive. A new tracker is created for each session.
+    pub async fn activity(&self) -> ActivityTracker {
+        self.activity.read().await.clone()
+    }
+
     /// Start a new session
     ///
     /// # Errors
@@ -263,6 +325,7 @@ impl SessionManager {
         // Create new session
         let session = ReceiverSession::new(client_addr);
         let session_id = session.id().to_string();
+        *self.activity.write().await = ActivityTracker::new();
 
         let _ = self.event_tx.send(SessionEvent::SessionStarted {
             session_id: session_id.clone(),
@@ -324,9 +387,9 @@ impl SessionManager {
 
         let mut sockets = self.sockets.lock().await;
         *sockets = Some(AllocatedSockets {
-            audio,
-            control,
-            timing,
+            audio: Arc::new(audio),
+            control: Arc::new(control),
+            timing: Arc::new(timing),
         });
 
         Ok(ports)
@@ -341,9 +404,9 @@ impl SessionManager {
         let control = UdpSocket::bind(format!("0.0.0.0:{cp}")).await?;
         let timing = UdpSocket::bind(format!("0.0.0.0:{tp}")).await?;
         Ok(AllocatedSockets {
-            audio,
-            control,
-            timing,
+            audio: Arc::new(audio),
+            control: Arc::new(control),
+            timing: Arc::new(timing),
         })
     }
 
@@ -409,6 +472,30 @@ impl SessionManager {
         }
     }
 
+    /// End the session with `session_id`, if it is still the current one
+    ///
+    /// Used when a connection closes, so a session that has since been
+    /// reaped or preempted does not take its successor down with it.
+    pub async fn end_session_with_id(&self, session_id: &str, reason: &str) {
+        let session_to_end = {
+            let mut active = self.active_session.write().await;
+            if active.as_ref().is_some_and(|s| s.id() == session_id) {
+                active.take()
+            } else {
+                None
+            }
+        };
+
+        if let Some(session) = session_to_end {
+            self.cleanup_sockets().await;
+
+            let _ = self.event_tx.send(SessionEvent::SessionEnded {
+                session_id: session.id().to_string(),
+                reason: reason.to_string(),
+            });
+        }
+    }
+
     /// Cleanup UDP sockets
     async fn cleanup_sockets(&self) {
         let mut sockets = self.sockets.lock().await;
@@ -418,19 +505,28 @@ impl SessionManager {
 
     /// Check for session timeout
     pub async fn check_timeout(&self) -> bool {
+        let activity = self.activity.read().await.clone();
         let active = self.active_session.read().await;
 
-        if let Some(ref session) = *active {
-            if session.is_timed_out(self.config.idle_timeout) {
-                return true;
-            }
-        }
+        active
+            .as_ref()
+            .is_some_and(|session| self.is_inactive(session, &activity))
+    }
 
-        false
+    /// Whether the session has seen no traffic for its state's timeout
+    fn is_inactive(&self, session: &ReceiverSession, activity: &ActivityTracker) -> bool {
+        let timeout = if session.state() == SessionState::Streaming {
+            self.config.streaming_timeout.min(self.config.idle_timeout)
+        } else {
+            self.config.idle_timeout
+        };
+        session.is_timed_out(timeout) && activity.idle_time() > timeout
     }
 
     /// Touch session to reset idle timeout
     pub async fn touch_session(&self) {
+        self.activity.read().await.touch();
+
         let mut active = self.active_session.write().await;
 
         if let Some(ref mut session) = *active {
@@ -457,13 +553,14 @@ impl SessionManager {
     pub async fn enforce_timeouts(&self) {
         // Step 1: Identify if session needs ending and take it if so
         // holding the lock only for the check and removal
+        let activity = self.activity.read().await.clone();
         let (session_to_end, reason) = {
             let mut active = self.active_session.write().await;
             let mut should_end = false;
             let mut reason = String::new();
 
             if let Some(ref session) = *active {
-                if session.is_timed_out(self.config.idle_timeout) {
+                if self.is_inactive(session, &activity) {
                     should_end = true;
                     reason = "Idle timeout".to_string();
                 } else if self.config.max_duration > Duration::ZERO
@@ -483,6 +580,7 @@ impl SessionManager {
 
         // Step 2: Perform cleanup (async) without holding the session lock
         if let Some(session) = session_to_end {
+            tracing::info!("Reaping session {}: {}", session.id(), reason);
             self.cleanup_sockets().await;
 
             let _ = self.event_tx.send(SessionEvent::SessionEnded {
@@ -498,7 +596,8 @@ impl SessionManager {
     #[must_use]
     pub fn start_timeout_monitor(self: &Arc<Self>) -> tokio::task::JoinHandle<()> {
         let manager_weak = Arc::downgrade(self);
-        let check_interval = self.config.idle_timeout / 4;
+        let check_interval = (self.config.streaming_timeout.min(self.config.idle_timeout) / 4)
+            .max(Duration::from_millis(10));
 
         tokio::spawn(async move {
             let mut ticker = interval(check_interval);
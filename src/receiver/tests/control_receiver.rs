@@ -137,3 +137,83 @@ async fn test_unknown_type() {
     let result = fixture.recv_timeout(Duration::from_millis(100)).await;
     assert!(result.is_none()); // Ignored
 }
+
+async fn requester_fixture(
+    max_packets_per_second: u32,
+) -> (
+    RetransmitRequester,
+    UdpSocket,
+    Arc<tokio::sync::RwLock<crate::receiver::sequence_tracker::SequenceTracker>>,
+) {
+    use crate::receiver::sequence_tracker::{SequenceTracker, SequenceTrackerConfig};
+
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let local = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let tracker = Arc::new(tokio::sync::RwLock::new(SequenceTracker::with_config(
+        SequenceTrackerConfig {
+            reorder_window: 0,
+            ..SequenceTrackerConfig::default()
+        },
+    )));
+    let requester = RetransmitRequester::new(
+        Arc::new(local),
+        sender.local_addr().unwrap(),
+        tracker.clone(),
+        RetransmitRequesterConfig {
+            max_packets_per_second,
+            ..RetransmitRequesterConfig::default()
+        },
+    );
+    (requester, sender, tracker)
+}
+
+#[tokio::test]
+async fn test_retransmit_requester_sends_request() {
+    let (mut requester, sender, tracker) = requester_fixture(100).await;
+    let now = std::time::Instant::now();
+    {
+        let mut tracker = tracker.write().await;
+        tracker.observe_at(10, now);
+        tracker.observe_at(14, now);
+    }
+
+    assert_eq!(requester.poll_at(now).await, 3);
+
+    let mut buf = [0u8; 16];
+    let (len, _) = tokio::time::timeout(Duration::from_secs(1), sender.recv_from(&mut buf))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(len, 8);
+    assert_eq!(buf[0], 0x80);
+    assert_eq!(buf[1], 0xD5);
+    assert_eq!(u16::from_be_bytes([buf[4], buf[5]]), 11);
+    assert_eq!(u16::from_be_bytes([buf[6], buf[7]]), 3);
+
+    // Backoff: nothing is due again immediately
+    assert_eq!(requester.poll_at(now).await, 0);
+
+    let stats = requester.stats().await;
+    assert_eq!(stats.requests_sent, 1);
+    assert_eq!(stats.packets_requested, 3);
+    assert_eq!(stats.rate_limited, 0);
+}
+
+#[tokio::test]
+async fn test_retransmit_requester_rate_limit() {
+    let (mut requester, _sender, tracker) = requester_fixture(2).await;
+    let now = std::time::Instant::now();
+    {
+        let mut tracker = tracker.write().await;
+        tracker.observe_at(0, now);
+        tracker.observe_at(6, now); // 1..=5 missing
+    }
+
+    assert_eq!(requester.poll_at(now).await, 2);
+    assert_eq!(requester.poll_at(now + Duration::from_millis(500)).await, 0);
+    assert_eq!(requester.stats().await.rate_limited, 2);
+
+    // A new one-second window allows more
+    assert_eq!(requester.poll_at(now + Duration::from_secs(1)).await, 2);
+    assert_eq!(requester.stats().await.packets_requested, 4);
+}
@@ -3,6 +3,7 @@ mod control_receiver;
 mod playback_timing;
 mod rtp_receiver;
 mod rtsp_handler;
+mod runtime;
 mod sequence_tracker;
 mod server;
 mod session;
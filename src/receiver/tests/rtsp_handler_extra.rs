@@ -24,6 +24,18 @@ fn test_post_not_implemented() {
     let request = create_request(Method::Post);
     let result = handle_request(&request, &session, None);
     assert_eq!(result.response.status, StatusCode::NOT_IMPLEMENTED);
+    assert!(!result.identify);
+}
+
+#[test]
+fn test_post_identify() {
+    let session = ReceiverSession::new(test_addr());
+    let mut request = create_request(Method::Post);
+    request.uri = "/identify".to_string();
+    let result = handle_request(&request, &session, None);
+    assert_eq!(result.response.status, StatusCode::OK);
+    assert!(result.identify);
+    assert!(result.new_state.is_none());
 }
 
 #[test]
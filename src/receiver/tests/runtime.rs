@@ -0,0 +1,87 @@
+use std::sync::Arc;
+
+use crate::receiver::runtime::{ReceiverRuntime, RuntimeConfig, RuntimeError, ZONE_UDP_PORT_RANGE};
+use crate::receiver::{AirPlayReceiver, ReceiverConfig};
+
+#[test]
+fn test_register_zones_get_disjoint_udp_ranges() {
+    let runtime = ReceiverRuntime::new();
+
+    let kitchen = runtime.register_zone("Kitchen", 5000).unwrap();
+    let office = runtime.register_zone("Office", 5001).unwrap();
+
+    assert_eq!(kitchen.udp_base_port, 6000);
+    assert_eq!(office.udp_base_port, 6000 + ZONE_UDP_PORT_RANGE);
+    assert_eq!(runtime.zones(), vec!["Kitchen", "Office"]);
+}
+
+#[test]
+fn test_duplicate_zone_name_rejected() {
+    let runtime = ReceiverRuntime::new();
+    runtime.register_zone("Kitchen", 5000).unwrap();
+
+    let err = runtime.register_zone("Kitchen", 5001).unwrap_err();
+    assert!(matches!(err, RuntimeError::DuplicateName(name) if name == "Kitchen"));
+}
+
+#[test]
+fn test_duplicate_zone_port_rejected() {
+    let runtime = ReceiverRuntime::new();
+    runtime.register_zone("Kitchen", 5000).unwrap();
+
+    let err = runtime.register_zone("Office", 5000).unwrap_err();
+    assert!(matches!(err, RuntimeError::DuplicatePort(5000)));
+
+    // Auto-assigned ports never conflict
+    runtime.register_zone("Den", 0).unwrap();
+    runtime.register_zone("Patio", 0).unwrap();
+}
+
+#[test]
+fn test_release_zone_frees_slot() {
+    let runtime = ReceiverRuntime::with_config(RuntimeConfig {
+        max_zones: 1,
+        ..Default::default()
+    });
+
+    runtime.register_zone("Kitchen", 0).unwrap();
+    assert!(matches!(
+        runtime.register_zone("Office", 0),
+        Err(RuntimeError::PortRangeExhausted)
+    ));
+
+    runtime.release_zone("Kitchen");
+    let lease = runtime.register_zone("Office", 0).unwrap();
+    assert_eq!(lease.udp_base_port, 6000);
+}
+
+#[test]
+fn test_clones_share_resources() {
+    let runtime = ReceiverRuntime::new();
+    let other = runtime.clone();
+
+    runtime.register_zone("Kitchen", 0).unwrap();
+    assert_eq!(other.zones(), vec!["Kitchen"]);
+    assert!(Arc::ptr_eq(&runtime.ptp_clock(), &other.ptp_clock()));
+}
+
+#[tokio::test]
+async fn test_receiver_with_runtime() {
+    let runtime = ReceiverRuntime::new();
+    let receiver = AirPlayReceiver::with_runtime(ReceiverConfig::with_name("Kitchen"), runtime);
+
+    assert!(receiver.runtime().is_some());
+    assert_eq!(receiver.config().name, "Kitchen");
+}
+
+#[test]
+fn test_zones_share_ptp_clock() {
+    let runtime = ReceiverRuntime::new();
+    let kitchen =
+        AirPlayReceiver::with_runtime(ReceiverConfig::with_name("Kitchen"), runtime.clone());
+    let office = AirPlayReceiver::with_runtime(ReceiverConfig::with_name("Office"), runtime);
+    assert!(Arc::ptr_eq(&kitchen.ptp_clock(), &office.ptp_clock()));
+
+    let standalone = AirPlayReceiver::with_name("Den");
+    assert!(!Arc::ptr_eq(&kitchen.ptp_clock(), &standalone.ptp_clock()));
+}
@@ -47,3 +47,202 @@ fn test_loss_ratio() {
 
     assert!((tracker.loss_ratio() - 0.666).abs() < 0.01);
 }
+
+#[test]
+fn test_duplicate_and_reordered_packets() {
+    let mut tracker = SequenceTracker::new();
+
+    tracker.record(100);
+    assert!(matches!(tracker.observe(103), PacketStatus::Gap(_)));
+    assert_eq!(tracker.missing(), vec![101, 102]);
+
+    // 102 arrives out of order
+    assert!(matches!(tracker.observe(102), PacketStatus::Recovered));
+    assert!(matches!(tracker.observe(102), PacketStatus::Duplicate));
+    assert!(matches!(tracker.observe(100), PacketStatus::Duplicate));
+    assert!(matches!(tracker.observe(104), PacketStatus::InOrder));
+
+    let stats = tracker.stats();
+    assert_eq!(stats.packets_received, 4);
+    assert_eq!(stats.duplicates, 2);
+    assert_eq!(stats.recovered, 1);
+    assert_eq!(stats.total_lost, 1);
+    assert_eq!(stats.largest_gap, 2);
+    assert_eq!(stats.currently_missing, 1);
+}
+
+#[test]
+fn test_large_jump_resyncs() {
+    let mut tracker = SequenceTracker::new();
+
+    tracker.record(100);
+    tracker.record(105);
+    assert!(matches!(tracker.observe(30000), PacketStatus::Resync));
+    assert!(tracker.missing().is_empty());
+    assert!(matches!(tracker.observe(30001), PacketStatus::InOrder));
+    assert_eq!(tracker.stats().resyncs, 1);
+}
+
+#[test]
+fn test_retransmit_requests_respect_reorder_window() {
+    let config = SequenceTrackerConfig {
+        reorder_window: 2,
+        ..SequenceTrackerConfig::default()
+    };
+    let mut tracker = SequenceTracker::with_config(config);
+    let now = std::time::Instant::now();
+
+    tracker.observe_at(10, now);
+    tracker.observe_at(12, now); // 11 missing, trails by 2
+    assert!(tracker.retransmit_requests(now).is_empty());
+
+    tracker.observe_at(13, now); // 11 now trails by 3
+    let requests = tracker.retransmit_requests(now);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].first_seq, 11);
+    assert_eq!(requests[0].count, 1);
+}
+
+#[test]
+fn test_retransmit_requests_coalesce_and_wrap() {
+    let mut tracker = SequenceTracker::with_config(SequenceTrackerConfig {
+        reorder_window: 0,
+        ..SequenceTrackerConfig::default()
+    });
+    let now = std::time::Instant::now();
+
+    tracker.observe_at(65533, now);
+    tracker.observe_at(2, now); // 65534, 65535, 0, 1 missing
+    let requests = tracker.retransmit_requests(now);
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].first_seq, 65534);
+    assert_eq!(requests[0].count, 4);
+    assert_eq!(tracker.stats().retransmits_requested, 4);
+}
+
+#[test]
+fn test_retransmit_policy_limits() {
+    use std::time::Duration;
+
+    let policy = RetransmitPolicy {
+        max_rerequests: 2,
+        retry_interval: Duration::from_millis(10),
+        playout_latency: Duration::from_millis(500),
+        playout_margin: Duration::from_millis(100),
+        ..RetransmitPolicy::default()
+    };
+    assert_eq!(policy.deadline(), Duration::from_millis(400));
+
+    let mut tracker = SequenceTracker::with_config(SequenceTrackerConfig {
+        reorder_window: 0,
+        retransmit: policy,
+        ..SequenceTrackerConfig::default()
+    });
+    let start = std::time::Instant::now();
+
+    tracker.observe_at(1, start);
+    tracker.observe_at(3, start);
+
+    assert_eq!(tracker.retransmit_requests(start).len(), 1);
+    // Too soon to ask again
+    assert!(
+        tracker
+            .retransmit_requests(start + Duration::from_millis(5))
+            .is_empty()
+    );
+    assert_eq!(
+        tracker
+            .retransmit_requests(start + Duration::from_millis(10))
+            .len(),
+        1
+    );
+    // Max rerequests reached
+    assert!(
+        tracker
+            .retransmit_requests(start + Duration::from_millis(20))
+            .is_empty()
+    );
+    // Given up after the retry interval elapses without recovery
+    assert!(
+        tracker
+            .retransmit_requests(start + Duration::from_millis(30))
+            .is_empty()
+    );
+    assert!(tracker.missing().is_empty());
+    assert_eq!(tracker.stats().abandoned, 1);
+
+    // Packets past the playout deadline are never requested
+    tracker.observe_at(5, start);
+    assert!(
+        tracker
+            .retransmit_requests(start + Duration::from_millis(450))
+            .is_empty()
+    );
+    assert_eq!(tracker.stats().abandoned, 2);
+}
+
+#[test]
+fn test_retransmit_disabled() {
+    let mut tracker = SequenceTracker::with_config(SequenceTrackerConfig {
+        reorder_window: 0,
+        retransmit: RetransmitPolicy {
+            enabled: false,
+            ..RetransmitPolicy::default()
+        },
+        ..SequenceTrackerConfig::default()
+    });
+
+    tracker.record(1);
+    tracker.record(5);
+    assert!(
+        tracker
+            .retransmit_requests(std::time::Instant::now())
+            .is_empty()
+    );
+    assert_eq!(tracker.missing(), vec![2, 3, 4]);
+}
+
+#[test]
+fn test_retransmit_backoff() {
+    use std::time::Duration;
+
+    let policy = RetransmitPolicy {
+        retry_interval: Duration::from_millis(20),
+        backoff_factor: 2,
+        max_retry_interval: Duration::from_millis(100),
+        ..RetransmitPolicy::default()
+    };
+    assert_eq!(policy.retry_delay(1), Duration::from_millis(20));
+    assert_eq!(policy.retry_delay(2), Duration::from_millis(40));
+    assert_eq!(policy.retry_delay(3), Duration::from_millis(80));
+    assert_eq!(policy.retry_delay(4), Duration::from_millis(100));
+
+    let mut tracker = SequenceTracker::with_config(SequenceTrackerConfig {
+        reorder_window: 0,
+        retransmit: RetransmitPolicy {
+            max_rerequests: 5,
+            ..policy
+        },
+        ..SequenceTrackerConfig::default()
+    });
+    let start = std::time::Instant::now();
+    tracker.observe_at(1, start);
+    tracker.observe_at(3, start);
+
+    assert_eq!(tracker.retransmit_requests(start).len(), 1);
+    let second = start + Duration::from_millis(20);
+    assert_eq!(tracker.retransmit_requests(second).len(), 1);
+    // Second retry waits twice as long
+    assert!(
+        tracker
+            .retransmit_requests(second + Duration::from_millis(20))
+            .is_empty()
+    );
+    assert!(tracker.has_due_retransmits(second + Duration::from_millis(40)));
+    assert_eq!(
+        tracker
+            .retransmit_requests_up_to(second + Duration::from_millis(40), 0)
+            .len(),
+        0
+    );
+}
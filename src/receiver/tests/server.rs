@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::receiver::{AirPlayReceiver, ReceiverConfig, ReceiverState};
 
 #[tokio::test]
@@ -19,6 +21,17 @@ async fn test_receiver_config_builder() {
     assert_eq!(config.latency_ms, 1500);
 }
 
+#[test]
+fn test_receiver_config_timeouts() {
+    let config = ReceiverConfig::default();
+    assert!(config.streaming_timeout < config.session_timeout);
+
+    let config = ReceiverConfig::with_name("Kitchen")
+        .timeouts(Duration::from_secs(30), Duration::from_secs(5));
+    assert_eq!(config.session_timeout, Duration::from_secs(30));
+    assert_eq!(config.streaming_timeout, Duration::from_secs(5));
+}
+
 #[tokio::test]
 async fn test_event_subscription() {
     let config = ReceiverConfig::default();
@@ -29,3 +42,129 @@ async fn test_event_subscription() {
     // Events should be receivable (even if none sent yet)
     assert!(events.try_recv().is_err()); // Empty
 }
+
+#[tokio::test]
+async fn test_stop_waits_for_shutdown() {
+    let mut receiver = AirPlayReceiver::new(ReceiverConfig::with_name("Stop Test").port(0));
+    receiver.start().await.unwrap();
+    assert_eq!(receiver.state().await, ReceiverState::Running);
+
+    receiver.stop().await.unwrap();
+    assert_eq!(receiver.state().await, ReceiverState::Stopped);
+}
+
+const SDP: &str = "v=0\r\no=- 0 0 IN IP4 127.0.0.1\r\ns=AirTunes\r\nt=0 0\r\nm=audio 0 RTP/AVP \
+                   96\r\na=rtpmap:96 AppleLossless\r\na=fmtp:96 352 0 16 40 10 14 2 255 0 0 \
+                   44100\r\n";
+
+/// Send an RTSP request and wait for its response
+async fn request(
+    stream: &mut tokio::net::TcpStream,
+    codec: &mut crate::protocol::rtsp::RtspCodec,
+    head: &str,
+    body: &[u8],
+) -> crate::protocol::rtsp::RtspResponse {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut message = format!("{head}Content-Length: {}\r\n\r\n", body.len()).into_bytes();
+    message.extend_from_slice(body);
+    stream.write_all(&message).await.unwrap();
+
+    let mut buf = [0u8; 4096];
+    loop {
+        if let Some(response) = codec.decode().unwrap() {
+            return response;
+        }
+        let n = stream.read(&mut buf).await.unwrap();
+        assert!(n > 0, "connection closed before the response");
+        codec.feed(&buf[..n]).unwrap();
+    }
+}
+
+#[tokio::test]
+async fn test_rtp_traffic_keeps_session_alive() {
+    use tokio::net::{TcpStream, UdpSocket};
+
+    use crate::protocol::rtsp::{RtspCodec, StatusCode};
+    use crate::receiver::ReceiverEvent;
+
+    let streaming_timeout = Duration::from_millis(300);
+    let config = ReceiverConfig::with_name("Activity Test")
+        .port(0)
+        .timeouts(Duration::from_secs(30), streaming_timeout);
+    let mut receiver = AirPlayReceiver::new(config);
+    let mut events = receiver.subscribe();
+    receiver.start().await.unwrap();
+    let port = loop {
+        if let ReceiverEvent::Started { port, .. } = events.recv().await.unwrap() {
+            break port;
+        }
+    };
+
+    let mut rtsp = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+    let mut codec = RtspCodec::new();
+    let uri = "rtsp://127.0.0.1/1";
+    let announce = request(
+        &mut rtsp,
+        &mut codec,
+        &format!("ANNOUNCE {uri} RTSP/1.0\r\nCSeq: 1\r\nContent-Type: application/sdp\r\n"),
+        SDP.as_bytes(),
+    )
+    .await;
+    assert_eq!(announce.status, StatusCode::OK);
+    let setup = request(
+        &mut rtsp,
+        &mut codec,
+        &format!(
+            "SETUP {uri} RTSP/1.0\r\nCSeq: 2\r\nTransport: \
+             RTP/AVP/UDP;unicast;mode=record;control_port=6001;timing_port=6002\r\n"
+        ),
+        b"",
+    )
+    .await;
+    let audio_port = setup.headers.transport().unwrap().unwrap().server_port;
+    let audio_port = audio_port.expect("SETUP response names the audio port");
+    let record = request(
+        &mut rtsp,
+        &mut codec,
+        &format!("RECORD {uri} RTSP/1.0\r\nCSeq: 3\r\n"),
+        b"",
+    )
+    .await;
+    assert_eq!(record.status, StatusCode::OK);
+
+    // RTP alone, with no RTSP requests, for several streaming timeouts
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    for sequence in 0..20u16 {
+        let mut packet = vec![0x80, 0x60];
+        packet.extend_from_slice(&sequence.to_be_bytes());
+        packet.extend_from_slice(&(u32::from(sequence) * 352).to_be_bytes());
+        packet.extend_from_slice(&0x1234_5678u32.to_be_bytes());
+        packet.extend_from_slice(&[0; 32]);
+        sender
+            .send_to(&packet, ("127.0.0.1", audio_port))
+            .await
+            .unwrap();
+        tokio::time::sleep(streaming_timeout / 6).await;
+    }
+    while let Ok(event) = events.try_recv() {
+        assert!(
+            !matches!(event, ReceiverEvent::ClientDisconnected { .. }),
+            "session reaped while RTP was arriving: {event:?}"
+        );
+    }
+
+    // Once the packets stop the session is reaped
+    let reason = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let ReceiverEvent::ClientDisconnected { reason, .. } = events.recv().await.unwrap() {
+                break reason;
+            }
+        }
+    })
+    .await
+    .expect("session should be reaped once RTP stops");
+    assert_eq!(reason, "Idle timeout");
+
+    receiver.stop().await.unwrap();
+}
@@ -4,8 +4,9 @@ use std::time::Duration;
 
 use tokio::time::sleep;
 
+use crate::receiver::session::SessionState;
 use crate::receiver::session_manager::{
-    PreemptionPolicy, SessionEvent, SessionManager, SessionManagerConfig,
+    ActivityTracker, PreemptionPolicy, SessionEvent, SessionManager, SessionManagerConfig,
 };
 
 fn test_addr() -> SocketAddr {
@@ -139,3 +140,128 @@ async fn test_volume_events() {
         panic!("Expected VolumeChanged event");
     }
 }
+
+async fn start_streaming(manager: &SessionManager) -> String {
+    let id = manager.start_session(test_addr()).await.unwrap();
+    for state in [
+        SessionState::Announced,
+        SessionState::Setup,
+        SessionState::Streaming,
+    ] {
+        manager.update_state(state).await.unwrap();
+    }
+    id
+}
+
+#[tokio::test]
+async fn test_activity_tracker() {
+    let tracker = ActivityTracker::new();
+    sleep(Duration::from_millis(50)).await;
+    assert!(tracker.idle_time() >= Duration::from_millis(40));
+
+    // Clones share the timestamp
+    tracker.clone().touch();
+    assert!(tracker.idle_time() < Duration::from_millis(40));
+}
+
+#[tokio::test]
+async fn test_streaming_timeout_reaps_silent_sender() {
+    let config = SessionManagerConfig {
+        idle_timeout: Duration::from_secs(60),
+        streaming_timeout: Duration::from_millis(100),
+        ..Default::default()
+    };
+    let manager = SessionManager::new(config);
+    let mut rx = manager.subscribe();
+    let id = start_streaming(&manager).await;
+
+    sleep(Duration::from_millis(150)).await;
+    assert!(manager.check_timeout().await);
+
+    manager.enforce_timeouts().await;
+    assert!(!manager.has_active_session().await);
+
+    let mut reason = None;
+    while let Ok(event) = rx.try_recv() {
+        if let SessionEvent::SessionEnded {
+            session_id,
+            reason: r,
+        } = event
+        {
+            assert_eq!(session_id, id);
+            reason = Some(r);
+        }
+    }
+    assert_eq!(reason.as_deref(), Some("Idle timeout"));
+}
+
+#[tokio::test]
+async fn test_streaming_traffic_keeps_session_alive() {
+    let config = SessionManagerConfig {
+        idle_timeout: Duration::from_secs(60),
+        streaming_timeout: Duration::from_millis(100),
+        ..Default::default()
+    };
+    let manager = SessionManager::new(config);
+    start_streaming(&manager).await;
+
+    // RTP and control loops touch the tracker on every packet
+    let activity = manager.activity().await;
+    for _ in 0..4 {
+        sleep(Duration::from_millis(50)).await;
+        activity.touch();
+    }
+
+    manager.enforce_timeouts().await;
+    assert!(manager.has_active_session().await);
+}
+
+#[tokio::test]
+async fn test_idle_timeout_applies_outside_streaming() {
+    let config = SessionManagerConfig {
+        idle_timeout: Duration::from_secs(60),
+        streaming_timeout: Duration::from_millis(50),
+        ..Default::default()
+    };
+    let manager = SessionManager::new(config);
+    manager.start_session(test_addr()).await.unwrap();
+
+    // A connected but not yet streaming sender is not expected to send RTP
+    sleep(Duration::from_millis(100)).await;
+    assert!(!manager.check_timeout().await);
+}
+
+#[tokio::test]
+async fn test_timeout_monitor_reaps_session() {
+    let config = SessionManagerConfig {
+        idle_timeout: Duration::from_millis(100),
+        ..Default::default()
+    };
+    let manager = Arc::new(SessionManager::new(config));
+    let monitor = manager.start_timeout_monitor();
+
+    manager.start_session(test_addr()).await.unwrap();
+    sleep(Duration::from_millis(250)).await;
+
+    assert!(!manager.has_active_session().await);
+    monitor.abort();
+}
+
+#[tokio::test]
+async fn test_end_session_with_id_spares_successor() {
+    let manager = SessionManager::new(SessionManagerConfig::default());
+
+    let old_id = manager.start_session(test_addr()).await.unwrap();
+    let new_id = manager.start_session(test_addr_2()).await.unwrap();
+
+    // The preempted connection closing must not end the new session
+    manager
+        .end_session_with_id(&old_id, "Connection closed")
+        .await;
+    assert_eq!(manager.current_session_id().await, Some(new_id.clone()));
+
+    manager
+        .end_session_with_id(&new_id, "Connection closed")
+        .await;
+    assert!(!manager.has_active_session().await);
+}
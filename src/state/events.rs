@@ -2,6 +2,9 @@
 
 use tokio::sync::broadcast;
 
+use crate::connection::StreamFeedback;
+#[cfg(feature = "dacp")]
+use crate::protocol::dacp::DacpCommand;
 use crate::types::{AirPlayDevice, PlaybackState, TrackInfo};
 
 /// Client events
@@ -25,6 +28,22 @@ pub enum ClientEvent {
         /// Error message
         message: String,
     },
+    /// Session answers but the device stopped sending events
+    ConnectionDegraded {
+        /// What was detected
+        message: String,
+    },
+    /// Connected with settings adjusted to the device
+    Warning {
+        /// What was adjusted and why
+        message: String,
+    },
+    /// Another sender took over the device; streaming stopped and the
+    /// session was closed
+    SessionPreempted {
+        /// Name of the sender that took over, if the device gave one
+        by: Option<String>,
+    },
 
     // Playback events
     /// Playback state changed
@@ -52,6 +71,20 @@ pub enum ClientEvent {
         position: f64,
     },
 
+    /// Command from the device's buttons, remote or Siri, received over
+    /// DACP while streaming; the client only reports it
+    #[cfg(feature = "dacp")]
+    RemoteCommand {
+        /// Command sent
+        command: DacpCommand,
+    },
+
+    /// Stream state reported by the device's `/feedback` keep-alive
+    Feedback {
+        /// State of each active stream
+        streams: Vec<StreamFeedback>,
+    },
+
     // Volume events
     /// Volume changed
     VolumeChanged {
@@ -214,6 +247,8 @@ impl EventFilter {
                 ClientEvent::Connected { .. }
                     | ClientEvent::Disconnected { .. }
                     | ClientEvent::ConnectionError { .. }
+                    | ClientEvent::ConnectionDegraded { .. }
+                    | ClientEvent::Warning { .. }
             )
         })
     }
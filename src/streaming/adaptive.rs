@@ -0,0 +1,120 @@
+//! Adapting a running stream to receiver feedback
+//!
+//! The `/feedback` replies report packets the receiver lost and how much
+//! audio it holds. Sustained loss lowers the AAC bitrate, stepping it back
+//! up once reports are clean again, and a receiver running short of audio
+//! gets a larger send lead so more is kept in flight.
+
+use std::time::Duration;
+
+use crate::connection::StreamFeedback;
+
+/// Clean reports needed before the bitrate is raised again
+const CLEAN_REPORTS_TO_RAISE: u32 = 5;
+
+/// Bitrate added per step back up (bits/s)
+const BITRATE_STEP_UP: u32 = 16_000;
+
+/// Receiver buffer below which the send lead grows
+const LOW_BUFFER: Duration = Duration::from_millis(250);
+
+/// Lead added each time the receiver runs low
+const LEAD_STEP: Duration = Duration::from_millis(100);
+
+/// Largest send lead the adaptation sets
+const MAX_LEAD: Duration = Duration::from_secs(1);
+
+/// Changes to apply to the stream after a feedback report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Adjustment {
+    /// New encoder bitrate (bits/s)
+    pub bitrate: Option<u32>,
+    /// New send lead
+    pub lead: Option<Duration>,
+}
+
+/// Sans-IO controller turning feedback into stream adjustments
+#[derive(Debug, Clone)]
+pub struct Adaptation {
+    sample_rate: u32,
+    /// Lowest and configured bitrate, for compressed streams
+    bitrate_range: Option<(u32, u32)>,
+    bitrate: u32,
+    lead: Duration,
+    last_lost: Option<u64>,
+    clean_reports: u32,
+}
+
+impl Adaptation {
+    /// Create a controller for a stream at `sample_rate`
+    ///
+    /// `bitrate_range` is the lowest bitrate to fall back to and the
+    /// configured one, which is never exceeded; `None` for uncompressed or
+    /// lossless streams, whose bitrate is fixed.
+    #[must_use]
+    pub fn new(sample_rate: u32, bitrate_range: Option<(u32, u32)>, lead: Duration) -> Self {
+        Self {
+            sample_rate: sample_rate.max(1),
+            bitrate_range,
+            bitrate: bitrate_range.map_or(0, |(_, max)| max),
+            lead,
+            last_lost: None,
+            clean_reports: 0,
+        }
+    }
+
+    /// Current encoder bitrate, if it is adapted
+    #[must_use]
+    pub fn bitrate(&self) -> Option<u32> {
+        self.bitrate_range.map(|_| self.bitrate)
+    }
+
+    /// Current send lead
+    #[must_use]
+    pub fn lead(&self) -> Duration {
+        self.lead
+    }
+
+    /// Process the report for the stream being sent
+    pub fn on_feedback(&mut self, feedback: &StreamFeedback) -> Adjustment {
+        Adjustment {
+            bitrate: self.adapt_bitrate(feedback.lost_packets),
+            lead: self.adapt_lead(feedback.buffered_frames),
+        }
+    }
+
+    fn adapt_bitrate(&mut self, lost_packets: Option<u64>) -> Option<u32> {
+        let (min, max) = self.bitrate_range?;
+        let lost = lost_packets?;
+        // The counter is cumulative; a reset receiver starts it again
+        let new_losses = self.last_lost.map_or(0, |last| lost.saturating_sub(last));
+        self.last_lost = Some(lost);
+
+        let bitrate = if new_losses > 0 {
+            self.clean_reports = 0;
+            (self.bitrate / 4 * 3).max(min)
+        } else {
+            self.clean_reports += 1;
+            if self.clean_reports < CLEAN_REPORTS_TO_RAISE {
+                return None;
+            }
+            self.clean_reports = 0;
+            self.bitrate.saturating_add(BITRATE_STEP_UP).min(max)
+        };
+
+        (bitrate != self.bitrate).then(|| {
+            self.bitrate = bitrate;
+            bitrate
+        })
+    }
+
+    fn adapt_lead(&mut self, buffered_frames: Option<u64>) -> Option<Duration> {
+        let buffered = buffered_frames?;
+        let low_frames = LOW_BUFFER.as_millis() * u128::from(self.sample_rate) / 1000;
+        if u128::from(buffered) >= low_frames || self.lead >= MAX_LEAD {
+            return None;
+        }
+        self.lead = (self.lead + LEAD_STEP).min(MAX_LEAD);
+        Some(self.lead)
+    }
+}
@@ -0,0 +1,310 @@
+//! Announcements mixed into a running stream
+//!
+//! A doorbell chime or spoken notification is played through the same RTP
+//! stream as the music, either over it (with the music ducked) or in place
+//! of it (with the music held and resumed where it left off). Alarms
+//! outrank announcements, and either can be held until the music finishes.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use super::resampler::ResamplingSource;
+use super::source::AudioSource;
+use crate::audio::convert::{from_f32, to_f32};
+use crate::audio::{AudioFormat, DUCK_FADE, SoftGain};
+
+/// How an announcement is combined with the main stream
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MixMode {
+    /// Mix over the main stream, ducked to `level` (0.0 - 1.0) meanwhile
+    Duck {
+        /// Main stream level while the announcement plays
+        level: f32,
+    },
+    /// Hold the main stream and play the announcement alone; the main
+    /// stream resumes where it stopped
+    Replace,
+}
+
+impl Default for MixMode {
+    fn default() -> Self {
+        Self::Duck { level: 0.25 }
+    }
+}
+
+/// Priority of an announcement relative to other announcements
+///
+/// A higher priority announcement interrupts a lower one, which resumes
+/// where it stopped once the higher one has finished. Equal or lower
+/// priorities wait their turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum StreamPriority {
+    /// Notification, spoken message
+    #[default]
+    Announcement,
+    /// Alarm, doorbell
+    Alarm,
+}
+
+/// What happens to the main stream when an announcement arrives
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreemptionPolicy {
+    /// Mix over the main stream, ducked to `level` (0.0 - 1.0) meanwhile
+    Duck {
+        /// Main stream level while the announcement plays
+        level: f32,
+    },
+    /// Pause the main stream and resume it at the same position afterward
+    Pause,
+    /// Wait until the main stream has finished
+    Queue,
+}
+
+impl PreemptionPolicy {
+    fn mode(self) -> MixMode {
+        match self {
+            Self::Duck { level } => MixMode::Duck { level },
+            Self::Pause | Self::Queue => MixMode::Replace,
+        }
+    }
+}
+
+struct Entry {
+    source: Box<dyn AudioSource>,
+    mode: MixMode,
+    priority: StreamPriority,
+}
+
+#[derive(Default)]
+struct Slots {
+    active: Option<Entry>,
+    /// Waiting for the active announcement, in play order
+    pending: VecDeque<Entry>,
+    /// Waiting for the main stream to finish
+    deferred: VecDeque<Entry>,
+}
+
+/// Slot for an announcement shared between the client and the streamer
+///
+/// [`play`](Self::play) replaces an announcement that is still running;
+/// [`play_with_priority`](Self::play_with_priority) queues or preempts
+/// according to priority and policy.
+pub struct AnnouncementChannel {
+    slots: Mutex<Slots>,
+    gain: Arc<SoftGain>,
+}
+
+impl AnnouncementChannel {
+    /// Create a channel that ducks the main stream through `gain`
+    #[must_use]
+    pub fn new(gain: Arc<SoftGain>) -> Self {
+        Self {
+            slots: Mutex::new(Slots::default()),
+            gain,
+        }
+    }
+
+    /// Start an announcement
+    pub fn play(&self, source: Box<dyn AudioSource>, mode: MixMode) {
+        let mut slots = self.lock();
+        let previous = slots.active.replace(Entry {
+            source,
+            mode,
+            priority: StreamPriority::default(),
+        });
+        self.switched(previous.as_ref(), slots.active.as_ref());
+    }
+
+    /// Play an announcement according to its priority and policy
+    ///
+    /// With [`PreemptionPolicy::Queue`] it waits for the main stream to
+    /// finish. Otherwise it starts immediately if nothing is playing or it
+    /// outranks the running announcement, which is interrupted and resumed
+    /// afterward; if not, it waits behind announcements of equal or higher
+    /// priority.
+    pub fn play_with_priority(
+        &self,
+        source: Box<dyn AudioSource>,
+        priority: StreamPriority,
+        policy: PreemptionPolicy,
+    ) {
+        let entry = Entry {
+            source,
+            mode: policy.mode(),
+            priority,
+        };
+        let mut slots = self.lock();
+
+        if policy == PreemptionPolicy::Queue {
+            insert_by_priority(&mut slots.deferred, entry);
+            return;
+        }
+
+        match slots.active.as_ref().map(|active| active.priority) {
+            Some(current) if current >= priority => {
+                insert_by_priority(&mut slots.pending, entry);
+            }
+            _ => {
+                let interrupted = slots.active.replace(entry);
+                self.switched(interrupted.as_ref(), slots.active.as_ref());
+                if let Some(interrupted) = interrupted {
+                    slots.pending.push_front(interrupted);
+                }
+            }
+        }
+    }
+
+    /// Stop the current announcement and drop any that are waiting
+    pub fn cancel(&self) {
+        let mut slots = self.lock();
+        let active = slots.active.take();
+        slots.pending.clear();
+        slots.deferred.clear();
+        self.switched(active.as_ref(), None);
+    }
+
+    /// Whether an announcement is playing
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.lock().active.is_some()
+    }
+
+    /// Number of announcements waiting to play
+    #[must_use]
+    pub fn queued(&self) -> usize {
+        let slots = self.lock();
+        slots.pending.len() + slots.deferred.len()
+    }
+
+    /// Whether an announcement is replacing the main stream
+    #[must_use]
+    pub fn replaces_main(&self) -> bool {
+        self.lock()
+            .active
+            .as_ref()
+            .is_some_and(|a| a.mode == MixMode::Replace)
+    }
+
+    /// Release announcements waiting for the main stream to finish
+    ///
+    /// Called by the streamer when its source runs out. Returns `true` if
+    /// there is anything left to play, in which case the streamer keeps
+    /// rendering until [`render`](Self::render) returns `false`.
+    pub fn main_finished(&self) -> bool {
+        let mut slots = self.lock();
+        let deferred = std::mem::take(&mut slots.deferred);
+        slots.pending.extend(deferred);
+        if slots.active.is_none() {
+            let next = slots.pending.pop_front();
+            slots.active = next;
+            self.switched(None, slots.active.as_ref());
+        }
+        slots.active.is_some()
+    }
+
+    /// Render the next packet of the announcement into `packet`
+    ///
+    /// In [`MixMode::Duck`] the announcement is added to the main stream
+    /// already in `packet`; in [`MixMode::Replace`] it overwrites it. Returns
+    /// `false` if no announcement was playing.
+    pub fn render(&self, packet: &mut [u8], format: AudioFormat) -> bool {
+        let mut slots = self.lock();
+        let mut clip = vec![0u8; packet.len()];
+        let (mode, filled) = loop {
+            let Some(active) = slots.active.as_mut() else {
+                return false;
+            };
+
+            if active.source.format() != format {
+                let source = std::mem::replace(
+                    &mut active.source,
+                    Box::new(super::SilenceSource::new(format)),
+                );
+                match ResamplingSource::new(source, format) {
+                    Ok(resampled) => active.source = Box::new(resampled),
+                    Err(e) => {
+                        tracing::warn!("Cannot convert announcement: {e}");
+                        self.advance(&mut slots);
+                        continue;
+                    }
+                }
+            }
+
+            let mut filled = 0;
+            while filled < clip.len() {
+                match active.source.read(&mut clip[filled..]) {
+                    Ok(0) => break,
+                    Ok(n) => filled += n,
+                    Err(e) => {
+                        tracing::warn!("Announcement read failed: {e}");
+                        break;
+                    }
+                }
+            }
+
+            // A clip that ended exactly on the previous packet gives way to
+            // the next one without a gap
+            if filled > 0 {
+                break (active.mode, filled);
+            }
+            self.advance(&mut slots);
+        };
+
+        match mode {
+            MixMode::Replace => packet.copy_from_slice(&clip),
+            MixMode::Duck { .. } => {
+                let mixed: Vec<f32> = to_f32(packet, format.sample_format)
+                    .iter()
+                    .zip(to_f32(&clip[..filled], format.sample_format))
+                    .map(|(main, clip)| main + clip)
+                    .collect();
+                let bytes = from_f32(&mixed, format.sample_format);
+                packet[..bytes.len()].copy_from_slice(&bytes);
+            }
+        }
+
+        if filled < clip.len() {
+            self.advance(&mut slots);
+        }
+        true
+    }
+
+    /// Finish the active announcement and start the next pending one
+    fn advance(&self, slots: &mut Slots) {
+        let finished = slots.active.take();
+        slots.active = slots.pending.pop_front();
+        self.switched(finished.as_ref(), slots.active.as_ref());
+    }
+
+    /// Move the main stream gain from one announcement to the next
+    fn switched(&self, from: Option<&Entry>, to: Option<&Entry>) {
+        if let Some(MixMode::Duck { level }) = to.map(|e| e.mode) {
+            self.gain.duck(level, None, DUCK_FADE);
+        } else if from.is_some_and(|e| matches!(e.mode, MixMode::Duck { .. })) {
+            self.gain.restore(DUCK_FADE);
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Slots> {
+        self.slots
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+/// Queue `entry` behind everything of equal or higher priority
+fn insert_by_priority(queue: &mut VecDeque<Entry>, entry: Entry) {
+    let index = queue
+        .iter()
+        .position(|queued| queued.priority < entry.priority)
+        .unwrap_or(queue.len());
+    queue.insert(index, entry);
+}
+
+impl std::fmt::Debug for AnnouncementChannel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnnouncementChannel")
+            .field("active", &self.is_active())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,368 @@
+//! System audio capture
+//!
+//! [`CaptureSource`] records from a `cpal` input device — a microphone, or a
+//! loopback device carrying what the computer is playing — so it can be
+//! streamed like any other [`AudioSource`]. Audio is delivered as 16-bit
+//! stereo at the device's own rate; the streamer resamples it if the
+//! session runs at a different rate.
+//!
+//! Loopback uses WASAPI loopback of the default output device on Windows.
+//! Elsewhere it needs a loopback input device: a `PulseAudio`/`PipeWire`
+//! monitor source on Linux, or `BlackHole`/`Soundflower` on macOS.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex, PoisonError};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+use super::source::AudioSource;
+use crate::audio::{AudioFormat, ChannelConfig, SampleFormat, SampleRate};
+
+/// Longest backlog kept before the oldest audio is dropped
+///
+/// Capture and playback run on different clocks; bounding the backlog keeps
+/// the delay from creeping up over a long session.
+const MAX_BACKLOG: Duration = Duration::from_millis(250);
+
+/// How long a read waits for the device before handing out silence
+const READ_WAIT: Duration = Duration::from_millis(20);
+
+/// Device names that identify loopback inputs, lower case
+const LOOPBACK_NAMES: [&str; 4] = ["monitor", "loopback", "blackhole", "soundflower"];
+
+/// Audio source recording from a `cpal` input device
+///
+/// The device runs on its own thread until the source is dropped. Reads
+/// never report end of stream: when the device delivers nothing, silence is
+/// returned so the session keeps running.
+pub struct CaptureSource {
+    buffer: Arc<CaptureBuffer>,
+    format: AudioFormat,
+    frames: usize,
+    stop: Option<mpsc::Sender<()>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Recorded samples shared with the device callback
+pub(crate) struct CaptureBuffer {
+    samples: Mutex<VecDeque<i16>>,
+    ready: Condvar,
+    error: Mutex<Option<String>>,
+    max_samples: usize,
+}
+
+impl CaptureSource {
+    /// Record from the default input device, usually the microphone
+    ///
+    /// # Errors
+    ///
+    /// Returns error if there is no input device or it cannot be opened.
+    pub fn default_input() -> io::Result<Self> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| not_found("No input device"))?;
+        Self::open(device, false)
+    }
+
+    /// Record what the computer is playing
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no loopback device is available or it cannot be
+    /// opened.
+    #[cfg(target_os = "windows")]
+    pub fn default_output_loopback() -> io::Result<Self> {
+        let device = cpal::default_host()
+            .default_output_device()
+            .ok_or_else(|| not_found("No output device"))?;
+        Self::open(device, true)
+    }
+
+    /// Record what the computer is playing
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no loopback device is available or it cannot be
+    /// opened.
+    #[cfg(not(target_os = "windows"))]
+    pub fn default_output_loopback() -> io::Result<Self> {
+        let device = cpal::default_host()
+            .input_devices()
+            .map_err(io::Error::other)?
+            .find(|device| {
+                device.name().is_ok_and(|name| {
+                    let name = name.to_lowercase();
+                    LOOPBACK_NAMES.iter().any(|tag| name.contains(tag))
+                })
+            })
+            .ok_or_else(|| not_found("No loopback input device"))?;
+        Self::open(device, false)
+    }
+
+    /// Record from the input device called `name`
+    ///
+    /// # Errors
+    ///
+    /// Returns error if no input device has that name or it cannot be
+    /// opened.
+    pub fn from_device_name(name: &str) -> io::Result<Self> {
+        let device = cpal::default_host()
+            .input_devices()
+            .map_err(io::Error::other)?
+            .find(|device| device.name().is_ok_and(|n| n == name))
+            .ok_or_else(|| not_found(&format!("No input device named {name}")))?;
+        Self::open(device, false)
+    }
+
+    /// Start recording from `device`, reading its output mix if `loopback`
+    fn open(device: cpal::Device, loopback: bool) -> io::Result<Self> {
+        let supported = if loopback {
+            device.default_output_config().map_err(io::Error::other)?
+        } else {
+            input_config(&device)?
+        };
+        let sample_rate = SampleRate::from_hz(supported.sample_rate().0).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unsupported capture rate {} Hz", supported.sample_rate().0),
+            )
+        })?;
+        let format = AudioFormat {
+            sample_format: SampleFormat::I16,
+            sample_rate,
+            channels: ChannelConfig::Stereo,
+        };
+
+        let samples = Arc::new(CaptureBuffer::new(
+            format.duration_to_frames(MAX_BACKLOG) * 2,
+        ));
+        let (status_tx, status_rx) = mpsc::channel();
+        let (stop, stop_rx) = mpsc::channel::<()>();
+
+        // cpal streams are not `Send` on every platform, so the stream lives
+        // on its own thread until the source is dropped
+        let thread = std::thread::spawn({
+            let samples = samples.clone();
+            move || match build_stream(&device, &supported, &samples) {
+                Ok(stream) => {
+                    let _ = status_tx.send(Ok(()));
+                    let _ = stop_rx.recv();
+                    drop(stream);
+                }
+                Err(e) => {
+                    let _ = status_tx.send(Err(e));
+                }
+            }
+        });
+
+        status_rx
+            .recv()
+            .map_err(|_| io::Error::other("Capture thread exited"))??;
+
+        Ok(Self {
+            buffer: samples,
+            format,
+            frames: 0,
+            stop: Some(stop),
+            thread: Some(thread),
+        })
+    }
+}
+
+impl AudioSource for CaptureSource {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if let Some(message) = self
+            .buffer
+            .error
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+        {
+            return Err(io::Error::other(message));
+        }
+
+        let frame_bytes = self.format.bytes_per_frame();
+        let wanted = buffer.len() / frame_bytes * 2;
+        let samples = self.buffer.take(wanted, READ_WAIT);
+
+        let len = if samples.is_empty() {
+            // Nothing recorded in time: keep the session alive with silence
+            let len = wanted * 2;
+            buffer[..len].fill(0);
+            len
+        } else {
+            for (bytes, sample) in buffer.chunks_exact_mut(2).zip(&samples) {
+                bytes.copy_from_slice(&sample.to_le_bytes());
+            }
+            samples.len() * 2
+        };
+
+        self.frames += len / frame_bytes;
+        Ok(len)
+    }
+
+    fn position(&self) -> Duration {
+        self.format.frames_to_duration(self.frames)
+    }
+}
+
+impl Drop for CaptureSource {
+    fn drop(&mut self) {
+        self.stop.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl CaptureBuffer {
+    /// Create a buffer holding at most `max_samples` interleaved samples
+    pub(crate) fn new(max_samples: usize) -> Self {
+        Self {
+            samples: Mutex::new(VecDeque::with_capacity(max_samples)),
+            ready: Condvar::new(),
+            error: Mutex::new(None),
+            max_samples,
+        }
+    }
+
+    /// Append interleaved `data` of `channels` channels as stereo
+    ///
+    /// Mono is copied to both sides and channels past the first two are
+    /// dropped. The oldest audio is discarded beyond [`MAX_BACKLOG`].
+    pub(crate) fn push(&self, data: &[f32], channels: usize) {
+        let channels = channels.max(1);
+        let mut samples = self.samples.lock().unwrap_or_else(PoisonError::into_inner);
+        for frame in data.chunks_exact(channels) {
+            let left = frame[0];
+            let right = frame.get(1).copied().unwrap_or(left);
+            samples.push_back(to_i16(left));
+            samples.push_back(to_i16(right));
+        }
+        let excess = samples.len().saturating_sub(self.max_samples);
+        samples.drain(..excess);
+        drop(samples);
+        self.ready.notify_one();
+    }
+
+    /// Take up to `max` samples, waiting up to `wait` if none are buffered
+    pub(crate) fn take(&self, max: usize, wait: Duration) -> Vec<i16> {
+        let samples = self.samples.lock().unwrap_or_else(PoisonError::into_inner);
+        let (mut samples, _) = self
+            .ready
+            .wait_timeout_while(samples, wait, |samples| samples.is_empty())
+            .unwrap_or_else(PoisonError::into_inner);
+        let count = max.min(samples.len());
+        samples.drain(..count).collect()
+    }
+
+    fn fail(&self, message: String) {
+        *self.error.lock().unwrap_or_else(PoisonError::into_inner) = Some(message);
+        self.ready.notify_one();
+    }
+}
+
+/// Pick an input configuration the session can use
+///
+/// The device default is kept if it runs at a supported rate; otherwise the
+/// first configuration covering 44.1 or 48 kHz is used.
+fn input_config(device: &cpal::Device) -> io::Result<cpal::SupportedStreamConfig> {
+    let default = device.default_input_config().map_err(io::Error::other)?;
+    if SampleRate::from_hz(default.sample_rate().0).is_some() && usable(default.sample_format()) {
+        return Ok(default);
+    }
+
+    device
+        .supported_input_configs()
+        .map_err(io::Error::other)?
+        .filter(|range| usable(range.sample_format()))
+        .find_map(|range| {
+            [SampleRate::Hz44100, SampleRate::Hz48000]
+                .into_iter()
+                .find_map(|rate| range.try_with_sample_rate(cpal::SampleRate(rate.as_u32())))
+        })
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                "Input device supports neither 44.1 nor 48 kHz",
+            )
+        })
+}
+
+fn usable(format: cpal::SampleFormat) -> bool {
+    matches!(format, cpal::SampleFormat::F32 | cpal::SampleFormat::I16)
+}
+
+fn build_stream(
+    device: &cpal::Device,
+    supported: &cpal::SupportedStreamConfig,
+    shared: &Arc<CaptureBuffer>,
+) -> io::Result<cpal::Stream> {
+    let channels = usize::from(supported.channels());
+    let config = supported.config();
+    let err_fn = {
+        let shared = shared.clone();
+        move |err: cpal::StreamError| {
+            tracing::error!("CPAL capture stream error: {}", err);
+            shared.fail(err.to_string());
+        }
+    };
+
+    let stream = match supported.sample_format() {
+        cpal::SampleFormat::F32 => {
+            let shared = shared.clone();
+            device.build_input_stream(
+                &config,
+                move |data: &[f32], _: &cpal::InputCallbackInfo| shared.push(data, channels),
+                err_fn,
+                None,
+            )
+        }
+        cpal::SampleFormat::I16 => {
+            let shared = shared.clone();
+            device.build_input_stream(
+                &config,
+                move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                    let data: Vec<f32> = data
+                        .iter()
+                        .map(|&s| f32::from(s) / f32::from(i16::MAX))
+                        .collect();
+                    shared.push(&data, channels);
+                },
+                err_fn,
+                None,
+            )
+        }
+        other => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("Unsupported capture sample format {other:?}"),
+            ));
+        }
+    }
+    .map_err(io::Error::other)?;
+
+    stream.play().map_err(io::Error::other)?;
+    Ok(stream)
+}
+
+fn to_i16(sample: f32) -> i16 {
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "Value is clamped to the i16 range"
+    )]
+    let sample = (sample.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+    sample
+}
+
+fn not_found(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, message.to_string())
+}
@@ -2,25 +2,39 @@ use std::fs::File;
 use std::io;
 use std::path::Path;
 
-use symphonia::core::audio::{AudioBufferRef, SampleBuffer, SignalSpec};
+use symphonia::core::audio::SampleBuffer;
 use symphonia::core::codecs::{CODEC_TYPE_NULL, Decoder, DecoderOptions};
-use symphonia::core::formats::{FormatOptions, FormatReader};
-use symphonia::core::io::{MediaSourceStream, MediaSourceStreamOptions};
-use symphonia::core::meta::MetadataOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::{FormatOptions, FormatReader, SeekMode, SeekTo};
+use symphonia::core::io::{MediaSource, MediaSourceStream, MediaSourceStreamOptions};
+use symphonia::core::meta::{MetadataOptions, MetadataRevision, StandardTagKey, Value};
+use symphonia::core::probe::Hint;
+use symphonia::core::units::{Time, TimeBase};
 
+use super::normalize::{NormalizingSource, measure_loudness};
 use super::source::AudioSource;
-use crate::audio::{AudioFormat, ChannelConfig, SampleFormat, SampleRate};
+use crate::audio::{AudioFormat, ChannelConfig, GainMode, ReplayGain, SampleFormat, SampleRate};
 
 /// Audio source that decodes a local file
+///
+/// The file is read and decoded one packet at a time as samples are asked
+/// for, so memory use stays at a single packet however long the file is and
+/// playback can start as soon as the first packet is decoded.
 pub struct FileSource {
     decoder: Box<dyn Decoder>,
     format: Box<dyn FormatReader>,
     track_id: u32,
+    time_base: Option<TimeBase>,
+    /// Decoded samples of the current packet
     buffer: Vec<i16>,
     buffer_pos: usize,
     audio_format: AudioFormat,
     sample_buf: Option<SampleBuffer<i16>>,
-    sample_spec: Option<SignalSpec>,
+    total_frames: Option<u64>,
+    frames_read: u64,
+    /// Frames still to drop after an accurate seek landed early
+    skip_frames: u64,
+    replay_gain: Option<ReplayGain>,
 }
 
 impl FileSource {
@@ -30,18 +44,36 @@ impl FileSource {
     ///
     /// Returns error if file cannot be opened or format is not supported
     pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let src = File::open(path)?;
-        let mss = MediaSourceStream::new(Box::new(src), MediaSourceStreamOptions::default());
+        let path = path.as_ref();
+        let mut hint = Hint::new();
+        if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+            hint.with_extension(extension);
+        }
+        Self::from_media(Box::new(File::open(path)?), &hint)
+    }
+
+    /// Create a source decoding `media`, probed with the help of `hint`
+    pub(crate) fn from_media(media: Box<dyn MediaSource>, hint: &Hint) -> io::Result<Self> {
+        let mss = MediaSourceStream::new(media, MediaSourceStreamOptions::default());
 
-        let hint = symphonia::core::probe::Hint::new();
         let meta_opts = MetadataOptions::default();
         let fmt_opts = FormatOptions::default();
 
         let probed = symphonia::default::get_probe()
-            .format(&hint, mss, &fmt_opts, &meta_opts)
+            .format(hint, mss, &fmt_opts, &meta_opts)
             .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
 
-        let format = probed.format;
+        let mut probed_meta = probed.metadata;
+        let mut format = probed.format;
+        let replay_gain = format
+            .metadata()
+            .current()
+            .and_then(replay_gain_tags)
+            .or_else(|| {
+                probed_meta
+                    .get()
+                    .and_then(|meta| meta.current().and_then(replay_gain_tags))
+            });
         let track = format
             .tracks()
             .iter()
@@ -50,105 +82,145 @@ impl FileSource {
                 io::Error::new(io::ErrorKind::InvalidData, "no supported audio tracks")
             })?;
 
-        let dec_opts = DecoderOptions::default();
-        let decoder = symphonia::default::get_codecs()
-            .make(&track.codec_params, &dec_opts)
-            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-
         let track_id = track.id;
+        let params = track.codec_params.clone();
+        let decoder = make_decoder(format.as_ref(), track_id)?;
 
-        // AirPlay expects 44100Hz Stereo usually, but we expose what we have.
-        // The PcmStreamer might need to resample/rechannel mix if it doesn't match?
-        // For now, let's assume the mp3 is close enough or the streamer handles it.
-        // Actually, PcmStreamer expects specific format?
-        // Checking PcmStreamer... it takes whatever source gives and sends it.
-        // AirPlay devices expect ALAC or PCM (44100/2).
-        // If the file is not 44100/2, we should probably warn or try to convert.
-        // For this task, I'll implement basic decoding.
-
-        let rate = track.codec_params.sample_rate.unwrap_or(44100);
-        let channels = track.codec_params.channels.unwrap_or(
-            symphonia::core::audio::Channels::FRONT_LEFT
-                | symphonia::core::audio::Channels::FRONT_RIGHT,
-        );
-
-        // Map Symphonia channels to our ChannelConfig
-        let channel_config = if channels.count() == 1 {
-            ChannelConfig::Mono
-        } else {
-            ChannelConfig::Stereo
-        };
-
-        // Map sample rate
-        let sample_rate = match rate {
-            48000 => SampleRate::Hz48000,
-            _ => SampleRate::Hz44100, // Fallback/Incorrect mapping (should be precise)
+        let rate = params.sample_rate.unwrap_or(44100);
+        let sample_rate = SampleRate::from_hz(rate).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("unsupported sample rate {rate} Hz"),
+            )
+        })?;
+        let channels = match params
+            .channels
+            .map_or(2, symphonia::core::audio::Channels::count)
+        {
+            1 => ChannelConfig::Mono,
+            2 => ChannelConfig::Stereo,
+            6 => ChannelConfig::Surround51,
+            8 => ChannelConfig::Surround71,
+            count => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("unsupported channel count {count}"),
+                ));
+            }
         };
 
         Ok(Self {
             decoder,
             format,
             track_id,
+            time_base: params.time_base,
             buffer: Vec::new(),
             buffer_pos: 0,
             audio_format: AudioFormat {
                 sample_rate,
-                channels: channel_config,
+                channels,
                 sample_format: SampleFormat::I16,
             },
             sample_buf: None,
-            sample_spec: None,
+            total_frames: params.n_frames,
+            frames_read: 0,
+            skip_frames: 0,
+            replay_gain,
         })
     }
-}
 
-impl AudioSource for FileSource {
-    fn format(&self) -> AudioFormat {
-        self.audio_format
+    /// `ReplayGain` values from the file's tags, if any
+    #[must_use]
+    pub fn replay_gain(&self) -> Option<ReplayGain> {
+        self.replay_gain
     }
 
-    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
-        let mut dest_pos = 0;
+    /// Open a file normalized to a consistent loudness
+    ///
+    /// Uses the file's `ReplayGain` tags when present; otherwise the file is
+    /// decoded once to measure its loudness, then opened again for playback.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be opened or decoded
+    pub fn normalized<P: AsRef<Path>>(path: P, mode: GainMode) -> io::Result<NormalizingSource> {
+        let path = path.as_ref();
+        let mut source = Self::new(path)?;
+        if let Some(replay_gain) = source.replay_gain {
+            return Ok(NormalizingSource::with_replay_gain(
+                source,
+                &replay_gain,
+                mode,
+            ));
+        }
 
-        // Provide i16 samples as bytes (Little Endian)
-        loop {
-            // Fill from internal buffer first
-            while self.buffer_pos < self.buffer.len() {
-                if dest_pos + 2 > buffer.len() {
-                    return Ok(dest_pos);
-                }
-                let sample = self.buffer[self.buffer_pos];
-                self.buffer_pos += 1;
+        let loudness = measure_loudness(&mut source)?;
+        let source = Self::new(path)?;
+        Ok(match ReplayGain::from_loudness(&loudness) {
+            Some(replay_gain) => NormalizingSource::with_replay_gain(source, &replay_gain, mode),
+            // Silent throughout
+            None => NormalizingSource::new(source, 1.0),
+        })
+    }
+}
 
-                let bytes = sample.to_le_bytes();
-                buffer[dest_pos] = bytes[0];
-                buffer[dest_pos + 1] = bytes[1];
-                dest_pos += 2;
+/// Read `ReplayGain` or Opus R128 gain tags
+fn replay_gain_tags(revision: &MetadataRevision) -> Option<ReplayGain> {
+    let mut track_gain = None;
+    let mut replay_gain = ReplayGain::default();
+    for tag in revision.tags() {
+        let text = match &tag.value {
+            Value::String(text) => text.clone(),
+            value => value.to_string(),
+        };
+        match tag.std_key {
+            Some(StandardTagKey::ReplayGainTrackGain) => {
+                track_gain = ReplayGain::parse_gain(&text);
             }
-
-            if dest_pos >= buffer.len() {
-                return Ok(dest_pos);
+            Some(StandardTagKey::ReplayGainTrackPeak) => {
+                replay_gain.track_peak = ReplayGain::parse_peak(&text);
+            }
+            Some(StandardTagKey::ReplayGainAlbumGain) => {
+                replay_gain.album_gain_db = ReplayGain::parse_gain(&text);
             }
+            Some(StandardTagKey::ReplayGainAlbumPeak) => {
+                replay_gain.album_peak = ReplayGain::parse_peak(&text);
+            }
+            _ if tag.key.eq_ignore_ascii_case("R128_TRACK_GAIN") && track_gain.is_none() => {
+                track_gain = text
+                    .trim()
+                    .parse()
+                    .ok()
+                    .map(|q78| ReplayGain::from_r128_track_gain(q78).track_gain_db);
+            }
+            _ => {}
+        }
+    }
+    track_gain.map(|gain| ReplayGain {
+        track_gain_db: gain,
+        ..replay_gain
+    })
+}
 
-            // Internal buffer empty, decode next packet
-            self.buffer.clear();
-            self.buffer_pos = 0;
+impl FileSource {
+    /// Decode the next packet of the track into the buffer
+    ///
+    /// Returns false at the end of the file.
+    fn decode_next(&mut self) -> io::Result<bool> {
+        self.buffer.clear();
+        self.buffer_pos = 0;
 
+        loop {
             let packet = match self.format.next_packet() {
                 Ok(packet) => packet,
-                Err(symphonia::core::errors::Error::IoError(e)) => {
-                    if e.kind() == io::ErrorKind::UnexpectedEof {
-                        if dest_pos > 0 {
-                            return Ok(dest_pos);
-                        }
-                        return Ok(0); // EOF
-                    }
-                    return Err(e);
+                Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                    return Ok(false);
                 }
-                Err(symphonia::core::errors::Error::ResetRequired) => {
-                    // The track list has been changed. Re-instantiate the decoder.
-                    // For now, treat as error or EOF?
-                    return Err(io::Error::new(io::ErrorKind::InvalidData, "Reset Required"));
+                Err(SymphoniaError::IoError(e)) => return Err(e),
+                Err(SymphoniaError::ResetRequired) => {
+                    // The track list changed; start over with a fresh decoder
+                    self.decoder = make_decoder(self.format.as_ref(), self.track_id)?;
+                    continue;
                 }
                 Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
             };
@@ -157,54 +229,140 @@ impl AudioSource for FileSource {
                 continue;
             }
 
-            match self.decoder.decode(&packet) {
-                Ok(decoded) => {
-                    // Convert to i16 and push to buffer
-                    // Note: spec says frames have N samples per channel.
-                    // We need to interleave them: L, R, L, R...
-                    let spec = *decoded.spec();
-                    let capacity = decoded.capacity() as u64;
-
-                    // Ensure the sample buffer is allocated and matches the packet's spec and
-                    // capacity.
-                    #[allow(
-                        clippy::cast_possible_truncation,
-                        reason = "capacity fits in usize in realistic scenarios"
-                    )]
-                    let required_capacity = capacity as usize * spec.channels.count();
-                    let needs_new_buffer = self.sample_spec != Some(spec)
-                        || self
-                            .sample_buf
-                            .as_ref()
-                            .is_none_or(|buf| buf.capacity() < required_capacity);
-
-                    if needs_new_buffer {
-                        self.sample_buf = Some(SampleBuffer::<i16>::new(capacity, spec));
-                        self.sample_spec = Some(spec);
-                    }
-
-                    if let Some(ref mut sample_buf) = self.sample_buf {
-                        match decoded {
-                            AudioBufferRef::S16(_)
-                            | AudioBufferRef::U8(_)
-                            | AudioBufferRef::F32(_) => {
-                                sample_buf.copy_interleaved_ref(decoded);
-                                self.buffer.reserve(sample_buf.samples().len());
-                                self.buffer.extend_from_slice(sample_buf.samples());
-                            }
-                            _ => {
-                                return Err(io::Error::new(
-                                    io::ErrorKind::InvalidData,
-                                    "Unsupported sample format",
-                                ));
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
+            let decoded = match self.decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                Err(SymphoniaError::DecodeError(e)) => {
                     tracing::warn!("Decode error: {}", e);
+                    continue;
                 }
+                Err(e) => return Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+            };
+
+            // Any sample format is converted to interleaved i16
+            let spec = *decoded.spec();
+            let capacity = decoded.capacity() as u64;
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "capacity fits in usize in realistic scenarios"
+            )]
+            let required_capacity = capacity as usize * spec.channels.count();
+            let sample_buf = match &mut self.sample_buf {
+                Some(buf) if buf.capacity() >= required_capacity => buf,
+                buf => buf.insert(SampleBuffer::<i16>::new(capacity, spec)),
+            };
+            sample_buf.copy_interleaved_ref(decoded);
+            self.buffer.extend_from_slice(sample_buf.samples());
+
+            // Drop what an accurate seek landed before the target
+            let channels = self.audio_format.channels.channels() as usize;
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "Skipped frames never exceed one packet's worth"
+            )]
+            let skip = (self.skip_frames as usize).min(self.buffer.len() / channels);
+            self.buffer_pos = skip * channels;
+            self.skip_frames -= skip as u64;
+            if self.buffer_pos < self.buffer.len() {
+                return Ok(true);
             }
         }
     }
+
+    /// Convert a timestamp in the track's time base to frames
+    fn ts_to_frames(&self, ts: u64) -> u64 {
+        let Some(time_base) = self.time_base else {
+            return ts;
+        };
+        let time = time_base.calc_time(ts);
+        let rate = u64::from(self.audio_format.sample_rate.as_u32());
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss,
+            reason = "Fraction of a second in frames is small and non-negative"
+        )]
+        let fraction = (time.frac * rate as f64).round() as u64;
+        time.seconds * rate + fraction
+    }
+}
+
+impl AudioSource for FileSource {
+    fn format(&self) -> AudioFormat {
+        self.audio_format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut dest_pos = 0;
+
+        // Provide i16 samples as bytes (Little Endian)
+        while dest_pos + 2 <= buffer.len() {
+            if self.buffer_pos == self.buffer.len() && !self.decode_next()? {
+                break;
+            }
+
+            let samples = &self.buffer[self.buffer_pos..];
+            let count = samples.len().min((buffer.len() - dest_pos) / 2);
+            for (bytes, sample) in buffer[dest_pos..dest_pos + count * 2]
+                .chunks_exact_mut(2)
+                .zip(&samples[..count])
+            {
+                bytes.copy_from_slice(&sample.to_le_bytes());
+            }
+            self.buffer_pos += count;
+            dest_pos += count * 2;
+        }
+
+        self.frames_read += (dest_pos / self.audio_format.bytes_per_frame()) as u64;
+        Ok(dest_pos)
+    }
+
+    fn duration(&self) -> Option<std::time::Duration> {
+        let frames = usize::try_from(self.total_frames?).ok()?;
+        Some(self.audio_format.frames_to_duration(frames))
+    }
+
+    fn position(&self) -> std::time::Duration {
+        usize::try_from(self.frames_read).map_or(std::time::Duration::ZERO, |frames| {
+            self.audio_format.frames_to_duration(frames)
+        })
+    }
+
+    fn seek(&mut self, position: std::time::Duration) -> io::Result<()> {
+        let time = Time::new(position.as_secs(), f64::from(position.subsec_nanos()) / 1e9);
+        let seeked = self
+            .format
+            .seek(
+                SeekMode::Accurate,
+                SeekTo::Time {
+                    time,
+                    track_id: Some(self.track_id),
+                },
+            )
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        self.decoder.reset();
+        self.buffer.clear();
+        self.buffer_pos = 0;
+        let actual = self.ts_to_frames(seeked.actual_ts);
+        let required = self.ts_to_frames(seeked.required_ts);
+        self.skip_frames = required.saturating_sub(actual);
+        self.frames_read = actual.max(required);
+        Ok(())
+    }
+
+    fn is_seekable(&self) -> bool {
+        true
+    }
+}
+
+/// Create a decoder for `track_id` of `format`
+fn make_decoder(format: &dyn FormatReader, track_id: u32) -> io::Result<Box<dyn Decoder>> {
+    let track = format
+        .tracks()
+        .iter()
+        .find(|t| t.id == track_id)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "track disappeared"))?;
+    symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
 }
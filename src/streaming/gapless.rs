@@ -0,0 +1,370 @@
+//! Gapless queue playback
+//!
+//! Consecutive tracks are spliced into a single source, so the RTP stream
+//! and the device's anchor carry on across a track change and the next
+//! track starts on the sample after the last one ends. The next track is
+//! opened while the current one plays, and each splice is recorded as a
+//! [`TrackBoundary`] at its offset into the stream, so the track change can
+//! be reported when playback reaches it rather than when it is buffered.
+//!
+//! With a crossfade set, the end of each track is held back and mixed with
+//! the start of the next using an equal-power fade, and the boundary is
+//! placed where the fade begins.
+
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use super::resampler::ResamplingSource;
+use super::source::AudioSource;
+use crate::audio::AudioFormat;
+use crate::audio::convert::{from_f32, to_f32};
+use crate::types::{QueueItem, TrackInfo};
+
+/// Opens the audio of a queued track
+pub type SourceOpener = Arc<dyn Fn(&TrackInfo) -> io::Result<Box<dyn AudioSource>> + Send + Sync>;
+
+/// Silence returned while the next track is still being opened
+const OPENING_SILENCE_FRAMES: usize = 352;
+
+/// Point in the stream where a queued track starts
+#[derive(Debug, Clone)]
+pub struct TrackBoundary {
+    /// Track starting here
+    pub item: QueueItem,
+    /// Stream time at which its first sample plays
+    pub offset: Duration,
+}
+
+#[derive(Default)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Flags are set from either side independently"
+)]
+struct Shared {
+    /// Track to splice in when the current one ends
+    next: Option<(QueueItem, Box<dyn AudioSource>)>,
+    /// Whether the next track is being opened
+    opening: bool,
+    /// Whether the source wants the track after the current one
+    wants_next: bool,
+    /// Splices not yet reported, in stream order
+    boundaries: VecDeque<TrackBoundary>,
+    /// The last track ended with nothing after it
+    exhausted: bool,
+    /// The source has been dropped
+    closed: bool,
+}
+
+/// Source playing queued tracks back to back without gaps
+///
+/// Tracks in other formats than the first are converted to it.
+pub struct GaplessSource {
+    format: AudioFormat,
+    current: Box<dyn AudioSource>,
+    bytes_read: usize,
+    shared: Arc<Mutex<Shared>>,
+    /// Length of the crossfade, in bytes; zero splices without overlap
+    fade_bytes: usize,
+    /// End of the current track held back for the crossfade
+    tail: VecDeque<u8>,
+    /// Bytes ready to be returned ahead of the current track
+    ready: VecDeque<u8>,
+    scratch: Vec<u8>,
+}
+
+impl GaplessSource {
+    /// Start with `first`; tracks after it are supplied through the
+    /// returned [`GaplessQueue`]
+    #[must_use]
+    pub fn new(first: Box<dyn AudioSource>) -> (Self, GaplessQueue) {
+        let format = first.format();
+        let shared = Arc::new(Mutex::new(Shared {
+            wants_next: true,
+            ..Shared::default()
+        }));
+        let queue = GaplessQueue {
+            format,
+            shared: shared.clone(),
+        };
+        let source = Self {
+            format,
+            current: first,
+            bytes_read: 0,
+            shared,
+            fade_bytes: 0,
+            tail: VecDeque::new(),
+            ready: VecDeque::new(),
+            scratch: Vec::new(),
+        };
+        (source, queue)
+    }
+
+    /// Overlap consecutive tracks by `fade` with an equal-power crossfade
+    ///
+    /// Tracks shorter than the fade are faded over their whole length. The
+    /// fade is skipped if the next track is still being opened when the
+    /// current one ends.
+    #[must_use]
+    pub fn with_crossfade(mut self, fade: Duration) -> Self {
+        self.fade_bytes = self.format.duration_to_frames(fade) * self.format.bytes_per_frame();
+        self
+    }
+
+    fn offset_of(&self, bytes: usize) -> Duration {
+        self.format
+            .frames_to_duration(bytes / self.format.bytes_per_frame())
+    }
+}
+
+impl AudioSource for GaplessSource {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        if self.fade_bytes > 0 {
+            return self.read_crossfaded(buffer);
+        }
+
+        let mut filled = 0;
+        while filled < buffer.len() {
+            let n = self.current.read(&mut buffer[filled..])?;
+            if n > 0 {
+                filled += n;
+                continue;
+            }
+
+            let mut shared = lock(&self.shared);
+            if let Some((item, source)) = shared.next.take() {
+                let offset = self.offset_of(self.bytes_read + filled);
+                tracing::debug!("Splicing in {:?} at {:?}", item.track.title, offset);
+                shared.boundaries.push_back(TrackBoundary { item, offset });
+                shared.wants_next = true;
+                self.current = source;
+                continue;
+            }
+
+            if shared.opening && filled == 0 {
+                // Keep the stream running until the next track is open
+                let bytes_per_frame = self.format.bytes_per_frame();
+                filled = buffer.len().min(OPENING_SILENCE_FRAMES * bytes_per_frame)
+                    / bytes_per_frame
+                    * bytes_per_frame;
+                buffer[..filled].fill(0);
+            } else if !shared.opening {
+                shared.exhausted = true;
+            }
+            break;
+        }
+
+        self.bytes_read += filled;
+        Ok(filled)
+    }
+
+    fn position(&self) -> Duration {
+        self.offset_of(self.bytes_read)
+    }
+}
+
+impl GaplessSource {
+    fn read_crossfaded(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            if !self.ready.is_empty() {
+                let n = (buffer.len() - filled).min(self.ready.len());
+                for (dst, src) in buffer[filled..filled + n]
+                    .iter_mut()
+                    .zip(self.ready.drain(..n))
+                {
+                    *dst = src;
+                }
+                filled += n;
+                continue;
+            }
+
+            self.scratch.resize(buffer.len() - filled, 0);
+            let n = self.current.read(&mut self.scratch)?;
+            if n > 0 {
+                self.tail.extend(&self.scratch[..n]);
+                let excess = self.tail.len().saturating_sub(self.fade_bytes);
+                self.ready.extend(self.tail.drain(..excess));
+                continue;
+            }
+
+            let mut shared = lock(&self.shared);
+            if let Some((item, mut next)) = shared.next.take() {
+                drop(shared);
+                let offset = self.offset_of(self.bytes_read + filled);
+                tracing::debug!("Crossfading into {:?} at {:?}", item.track.title, offset);
+                let outgoing: Vec<u8> = self.tail.drain(..).collect();
+                let incoming = read_up_to(next.as_mut(), outgoing.len())?;
+                self.ready
+                    .extend(equal_power_mix(&outgoing, &incoming, self.format));
+
+                let mut shared = lock(&self.shared);
+                shared.boundaries.push_back(TrackBoundary { item, offset });
+                shared.wants_next = true;
+                self.current = next;
+                continue;
+            }
+
+            if !self.tail.is_empty() {
+                // Nothing to fade into yet; play the end of the track as is
+                self.ready.extend(self.tail.drain(..));
+                continue;
+            }
+
+            if shared.opening && filled == 0 {
+                let bytes_per_frame = self.format.bytes_per_frame();
+                filled = buffer.len().min(OPENING_SILENCE_FRAMES * bytes_per_frame)
+                    / bytes_per_frame
+                    * bytes_per_frame;
+                buffer[..filled].fill(0);
+            } else if !shared.opening {
+                shared.exhausted = true;
+            }
+            break;
+        }
+
+        self.bytes_read += filled;
+        Ok(filled)
+    }
+}
+
+/// Read up to `len` bytes, stopping early only at the end of `source`
+fn read_up_to(source: &mut dyn AudioSource, len: usize) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![0; len];
+    let mut filled = 0;
+    while filled < len {
+        let n = source.read(&mut bytes[filled..])?;
+        if n == 0 {
+            break;
+        }
+        filled += n;
+    }
+    bytes.truncate(filled);
+    Ok(bytes)
+}
+
+/// Mix the end of one track into the start of the next
+///
+/// The outgoing track follows a cosine curve and the incoming one a sine
+/// curve, keeping the combined power constant across the fade. The result
+/// is as long as `outgoing`; a shorter `incoming` is padded with silence.
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "Frame counts in a fade are far below f32 precision limits"
+)]
+fn equal_power_mix(outgoing: &[u8], incoming: &[u8], format: AudioFormat) -> Vec<u8> {
+    let channels = usize::from(format.channels.channels());
+    let out = to_f32(outgoing, format.sample_format);
+    let mut inc = to_f32(incoming, format.sample_format);
+    inc.resize(out.len(), 0.0);
+
+    let frames = out.len() / channels;
+    let mixed: Vec<f32> = out
+        .iter()
+        .zip(&inc)
+        .enumerate()
+        .map(|(i, (&o, &n))| {
+            let t = ((i / channels) as f32 + 0.5) / frames as f32;
+            let angle = t * std::f32::consts::FRAC_PI_2;
+            o * angle.cos() + n * angle.sin()
+        })
+        .collect();
+    from_f32(&mixed, format.sample_format)
+}
+
+impl Drop for GaplessSource {
+    fn drop(&mut self) {
+        lock(&self.shared).closed = true;
+    }
+}
+
+/// Supplies the tracks a [`GaplessSource`] plays next
+#[derive(Clone)]
+pub struct GaplessQueue {
+    format: AudioFormat,
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl GaplessQueue {
+    /// Whether the source is ready for the track after the current one
+    #[must_use]
+    pub fn needs_next(&self) -> bool {
+        let shared = lock(&self.shared);
+        shared.wants_next && shared.next.is_none() && !shared.opening && !shared.exhausted
+    }
+
+    /// Mark the next track as being opened
+    ///
+    /// Until it is [`preload`](Self::preload)ed or
+    /// [`cancel_opening`](Self::cancel_opening) is called, the source plays
+    /// silence rather than ending if the current track runs out first.
+    pub fn start_opening(&self) {
+        lock(&self.shared).opening = true;
+    }
+
+    /// Give up on the track being opened; the stream ends after the
+    /// current track
+    pub fn cancel_opening(&self) {
+        let mut shared = lock(&self.shared);
+        shared.opening = false;
+        shared.wants_next = false;
+    }
+
+    /// Queue `source` to start when the current track ends
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the source cannot be converted to the stream format;
+    /// nothing is queued then.
+    pub fn preload(&self, item: QueueItem, source: Box<dyn AudioSource>) -> io::Result<()> {
+        let source: Box<dyn AudioSource> = if source.format() == self.format {
+            source
+        } else {
+            Box::new(ResamplingSource::new(source, self.format).inspect_err(|_| {
+                self.cancel_opening();
+            })?)
+        };
+
+        let mut shared = lock(&self.shared);
+        shared.next = Some((item, source));
+        shared.opening = false;
+        shared.wants_next = false;
+        Ok(())
+    }
+
+    /// Number of splices not yet taken with
+    /// [`pop_boundary`](Self::pop_boundary)
+    #[must_use]
+    pub fn pending_boundaries(&self) -> usize {
+        lock(&self.shared).boundaries.len()
+    }
+
+    /// Stream offset of the next splice
+    #[must_use]
+    pub fn next_boundary(&self) -> Option<Duration> {
+        lock(&self.shared).boundaries.front().map(|b| b.offset)
+    }
+
+    /// Take the next splice
+    #[must_use]
+    pub fn pop_boundary(&self) -> Option<TrackBoundary> {
+        lock(&self.shared).boundaries.pop_front()
+    }
+
+    /// Whether the source has been dropped, ending the stream
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        lock(&self.shared).closed
+    }
+}
+
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_, Shared> {
+    shared
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+}
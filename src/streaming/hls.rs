@@ -0,0 +1,387 @@
+//! HTTP Live Streaming playback
+//!
+//! Audio-only HLS streams are played by chaining their segments into one
+//! byte stream for the decoder. Packed audio segments (ADTS AAC, MP3) and
+//! fragmented MP4 with an `EXT-X-MAP` initialization segment concatenate
+//! into a valid stream; MPEG-TS segments are not supported.
+//!
+//! For live playlists, playback starts three target durations from the end,
+//! as the HLS specification asks, and the playlist is reloaded for new
+//! segments until it is ended.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use symphonia::core::probe::Hint;
+
+use super::http::{Body, HttpUrl, Response, get};
+
+/// Playlist content types
+const PLAYLIST_TYPES: [&str; 4] = [
+    "application/vnd.apple.mpegurl",
+    "application/x-mpegurl",
+    "audio/mpegurl",
+    "audio/x-mpegurl",
+];
+
+/// Codec prefixes of `CODECS` entries that are audio
+const AUDIO_CODECS: [&str; 6] = ["mp4a", "mp3", "ac-3", "ec-3", "flac", "opus"];
+
+/// Target durations kept between the live edge and the start of playback
+const LIVE_EDGE_SEGMENTS: u32 = 3;
+
+/// Whether a response is an HLS playlist
+pub(crate) fn is_playlist(content_type: Option<&str>, path: &str) -> bool {
+    content_type.is_some_and(|content_type| PLAYLIST_TYPES.contains(&content_type))
+        || path
+            .split('?')
+            .next()
+            .is_some_and(|file| file.to_ascii_lowercase().ends_with(".m3u8"))
+}
+
+/// A parsed playlist
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Playlist {
+    /// Master playlist listing renditions
+    Master(MasterPlaylist),
+    /// Media playlist listing segments
+    Media(MediaPlaylist),
+}
+
+/// Renditions offered by a master playlist
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MasterPlaylist {
+    /// `EXT-X-STREAM-INF` variants
+    pub(crate) variants: Vec<Variant>,
+    /// URIs of `EXT-X-MEDIA` audio renditions, default first
+    pub(crate) audio_renditions: Vec<String>,
+}
+
+/// A variant stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Variant {
+    pub(crate) uri: String,
+    pub(crate) bandwidth: u64,
+    pub(crate) codecs: Option<String>,
+}
+
+impl Variant {
+    /// Whether the variant is known to carry only audio
+    fn is_audio_only(&self) -> bool {
+        self.codecs.as_deref().is_some_and(|codecs| {
+            codecs.split(',').all(|codec| {
+                let codec = codec.trim().to_ascii_lowercase();
+                AUDIO_CODECS.iter().any(|audio| codec.starts_with(audio))
+            })
+        })
+    }
+}
+
+/// Segments of a media playlist
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct MediaPlaylist {
+    pub(crate) target_duration: Duration,
+    /// Sequence number of the first segment
+    pub(crate) media_sequence: u64,
+    pub(crate) segments: Vec<Segment>,
+    /// `EXT-X-MAP` initialization segment URI
+    pub(crate) map: Option<String>,
+    /// `EXT-X-ENDLIST` seen: no more segments will be added
+    pub(crate) ended: bool,
+}
+
+/// A media segment
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Segment {
+    pub(crate) uri: String,
+    pub(crate) duration: Duration,
+}
+
+impl MediaPlaylist {
+    /// Index of the segment playback should start at
+    ///
+    /// The first segment of an ended playlist; for a live one, the last
+    /// segment starting at least three target durations before the end.
+    pub(crate) fn start_index(&self) -> usize {
+        if self.ended {
+            return 0;
+        }
+        let edge = self.target_duration * LIVE_EDGE_SEGMENTS;
+        let mut remaining = Duration::ZERO;
+        for (index, segment) in self.segments.iter().enumerate().rev() {
+            remaining += segment.duration;
+            if remaining >= edge {
+                return index;
+            }
+        }
+        0
+    }
+}
+
+/// Parse an M3U8 playlist
+pub(crate) fn parse_playlist(text: &str) -> io::Result<Playlist> {
+    let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+    if lines.next() != Some("#EXTM3U") {
+        return Err(invalid("Not an M3U8 playlist"));
+    }
+
+    let mut master = MasterPlaylist::default();
+    let mut media = MediaPlaylist::default();
+    let mut is_master = false;
+    let mut pending_variant: Option<Variant> = None;
+    let mut pending_duration: Option<Duration> = None;
+
+    for line in lines {
+        if let Some(attributes) = line.strip_prefix("#EXT-X-STREAM-INF:") {
+            is_master = true;
+            let attributes = parse_attributes(attributes);
+            pending_variant = Some(Variant {
+                uri: String::new(),
+                bandwidth: attribute(&attributes, "BANDWIDTH")
+                    .and_then(|bandwidth| bandwidth.parse().ok())
+                    .unwrap_or(0),
+                codecs: attribute(&attributes, "CODECS").map(str::to_string),
+            });
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MEDIA:") {
+            let attributes = parse_attributes(attributes);
+            if attribute(&attributes, "TYPE") == Some("AUDIO") {
+                if let Some(uri) = attribute(&attributes, "URI") {
+                    if attribute(&attributes, "DEFAULT") == Some("YES") {
+                        master.audio_renditions.insert(0, uri.to_string());
+                    } else {
+                        master.audio_renditions.push(uri.to_string());
+                    }
+                }
+            }
+        } else if let Some(value) = line.strip_prefix("#EXT-X-TARGETDURATION:") {
+            let seconds = value
+                .parse()
+                .map_err(|_| invalid(&format!("Invalid target duration {value:?}")))?;
+            media.target_duration = Duration::from_secs(seconds);
+        } else if let Some(value) = line.strip_prefix("#EXT-X-MEDIA-SEQUENCE:") {
+            media.media_sequence = value
+                .parse()
+                .map_err(|_| invalid(&format!("Invalid media sequence {value:?}")))?;
+        } else if let Some(value) = line.strip_prefix("#EXTINF:") {
+            let seconds = value.split(',').next().unwrap_or_default().trim();
+            let seconds: f64 = seconds
+                .parse()
+                .map_err(|_| invalid(&format!("Invalid segment duration {seconds:?}")))?;
+            pending_duration = Some(
+                Duration::try_from_secs_f64(seconds)
+                    .map_err(|_| invalid(&format!("Invalid segment duration {seconds}")))?,
+            );
+        } else if let Some(attributes) = line.strip_prefix("#EXT-X-MAP:") {
+            media.map = attribute(&parse_attributes(attributes), "URI").map(str::to_string);
+        } else if line == "#EXT-X-ENDLIST" {
+            media.ended = true;
+        } else if line.starts_with('#') {
+            // Other tags and comments are not needed for audio playback
+        } else if let Some(mut variant) = pending_variant.take() {
+            variant.uri = line.to_string();
+            master.variants.push(variant);
+        } else if let Some(duration) = pending_duration.take() {
+            media.segments.push(Segment {
+                uri: line.to_string(),
+                duration,
+            });
+        }
+    }
+
+    Ok(if is_master {
+        Playlist::Master(master)
+    } else {
+        Playlist::Media(media)
+    })
+}
+
+/// Split an attribute list into names and unquoted values
+fn parse_attributes(list: &str) -> Vec<(String, String)> {
+    let mut attributes = Vec::new();
+    let mut rest = list.trim();
+    while let Some((name, after)) = rest.split_once('=') {
+        let (value, after) = match after.strip_prefix('"') {
+            Some(quoted) => quoted.split_once('"').unwrap_or((quoted, "")),
+            None => after.split_once(',').unwrap_or((after, "")),
+        };
+        attributes.push((name.trim().to_string(), value.to_string()));
+        rest = after.trim_start_matches(',').trim_start();
+    }
+    attributes
+}
+
+fn attribute<'a>(attributes: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    attributes
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, v)| v.as_str())
+}
+
+/// Segments of a media playlist read back to back
+pub(crate) struct SegmentReader {
+    playlist_url: HttpUrl,
+    queue: VecDeque<(u64, HttpUrl)>,
+    /// Sequence number of the first segment not yet queued
+    next_sequence: u64,
+    target_duration: Duration,
+    live: bool,
+    last_reload: Instant,
+    /// Whether the last reload found new segments
+    grew: bool,
+    current: Option<Body>,
+}
+
+impl SegmentReader {
+    /// Start reading the playlist in `response`
+    ///
+    /// A master playlist is resolved to its audio rendition first. Returns
+    /// the reader and a probe hint from the first segment.
+    pub(crate) fn open(response: Response) -> io::Result<(Self, Hint)> {
+        let (url, playlist) = load_media_playlist(response)?;
+        let start = playlist.start_index();
+
+        let mut queue: VecDeque<(u64, HttpUrl)> = VecDeque::new();
+        for (index, segment) in playlist.segments.iter().enumerate().skip(start) {
+            queue.push_back((
+                playlist.media_sequence + index as u64,
+                url.join(&segment.uri)?,
+            ));
+        }
+        let first = queue
+            .front()
+            .map(|(_, segment)| segment.clone())
+            .ok_or_else(|| invalid("HLS playlist has no segments"))?;
+        if first
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("ts"))
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "MPEG-TS HLS segments are not supported",
+            ));
+        }
+
+        let mut hint = Hint::new();
+        if let Some(extension) = first.extension() {
+            hint.with_extension(extension);
+        }
+        if let Some(map) = &playlist.map {
+            // The initialization segment goes first, outside the numbering
+            let map = url.join(map)?;
+            if let Some(extension) = map.extension() {
+                hint.with_extension(extension);
+            }
+            queue.push_front((queue[0].0, map));
+        }
+
+        let next_sequence = playlist.media_sequence + playlist.segments.len() as u64;
+        Ok((
+            Self {
+                playlist_url: url,
+                queue,
+                next_sequence,
+                target_duration: playlist.target_duration,
+                live: !playlist.ended,
+                last_reload: Instant::now(),
+                grew: true,
+                current: None,
+            },
+            hint,
+        ))
+    }
+
+    /// Wait for the next reload time and queue any new segments
+    fn reload(&mut self) -> io::Result<()> {
+        // Reload after a target duration, or half of one if the last reload
+        // found nothing new
+        let interval = if self.grew {
+            self.target_duration
+        } else {
+            self.target_duration / 2
+        };
+        let due = self.last_reload + interval.max(Duration::from_millis(100));
+        std::thread::sleep(due.saturating_duration_since(Instant::now()));
+        self.last_reload = Instant::now();
+
+        let response = get(&self.playlist_url)?;
+        let Playlist::Media(playlist) = parse_playlist(&read_text(response.body)?)? else {
+            return Err(invalid("HLS media playlist became a master playlist"));
+        };
+
+        self.grew = false;
+        for (index, segment) in playlist.segments.iter().enumerate() {
+            let sequence = playlist.media_sequence + index as u64;
+            if sequence >= self.next_sequence {
+                self.queue
+                    .push_back((sequence, self.playlist_url.join(&segment.uri)?));
+                self.next_sequence = sequence + 1;
+                self.grew = true;
+            }
+        }
+        self.target_duration = playlist.target_duration;
+        self.live = !playlist.ended;
+        Ok(())
+    }
+}
+
+impl Read for SegmentReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(body) = &mut self.current {
+                let n = body.read(buf)?;
+                if n > 0 || buf.is_empty() {
+                    return Ok(n);
+                }
+                self.current = None;
+            }
+
+            if let Some((_, url)) = self.queue.pop_front() {
+                self.current = Some(get(&url)?.body);
+            } else if self.live {
+                self.reload()?;
+            } else {
+                return Ok(0);
+            }
+        }
+    }
+}
+
+/// Follow a master playlist to the media playlist to play
+fn load_media_playlist(response: Response) -> io::Result<(HttpUrl, MediaPlaylist)> {
+    let url = response.url;
+    match parse_playlist(&read_text(response.body)?)? {
+        Playlist::Media(playlist) => Ok((url, playlist)),
+        Playlist::Master(master) => {
+            let uri = master
+                .audio_renditions
+                .first()
+                .or_else(|| {
+                    master
+                        .variants
+                        .iter()
+                        .filter(|variant| variant.is_audio_only())
+                        .max_by_key(|variant| variant.bandwidth)
+                        .or_else(|| master.variants.iter().min_by_key(|v| v.bandwidth))
+                        .map(|variant| &variant.uri)
+                })
+                .ok_or_else(|| invalid("HLS master playlist has no variants"))?;
+            let response = get(&url.join(uri)?)?;
+            let url = response.url.clone();
+            match parse_playlist(&read_text(response.body)?)? {
+                Playlist::Media(playlist) => Ok((url, playlist)),
+                Playlist::Master(_) => Err(invalid("Nested HLS master playlists")),
+            }
+        }
+    }
+}
+
+fn read_text(mut body: Body) -> io::Result<String> {
+    let mut text = String::new();
+    body.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
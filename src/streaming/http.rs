@@ -0,0 +1,571 @@
+//! Remote file decode-and-restream
+//!
+//! [`HttpSource`] downloads an MP3, FLAC, AAC or WAV file over HTTP, decodes
+//! it locally and hands out PCM like any other [`AudioSource`]. It serves
+//! receivers that reject `play_url`, such as RAOP-only speakers, which can
+//! only play audio the sender streams to them. HLS playlists are followed
+//! segment by segment, see [`hls`](super::hls), and internet radio titles
+//! are read from ICY metadata, see [`icy`](super::icy). Files that should
+//! be seekable are better played with
+//! [`HttpFileSource`](super::url_source::HttpFileSource).
+//!
+//! Only plain `http://` URLs are supported: the crate carries no TLS stack.
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use symphonia::core::io::ReadOnlySource;
+use symphonia::core::probe::Hint;
+use tokio::sync::watch;
+
+use super::file::FileSource;
+use super::hls;
+use super::icy::{IcyMetadata, IcyReader};
+use super::source::AudioSource;
+use crate::audio::AudioFormat;
+
+/// Redirects followed before giving up
+const MAX_REDIRECTS: usize = 5;
+
+/// Timeout for connecting and for each read from the server
+const NETWORK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Decoded audio buffered ahead of playback
+pub(crate) const BUFFER_AHEAD: Duration = Duration::from_secs(2);
+
+/// Decoded frames sent from the decoder thread at a time
+pub(crate) const CHUNK_FRAMES: usize = 1024;
+
+/// How long a read waits for the decoder before handing out silence
+const READ_WAIT: Duration = Duration::from_millis(20);
+
+/// Audio source decoding a file downloaded over HTTP
+///
+/// The download and decoding run on their own thread, a couple of seconds
+/// ahead of playback; dropping the source stops the thread once its current
+/// network read completes. When the network stalls, reads return silence
+/// rather than blocking the streamer.
+pub struct HttpSource {
+    chunks: DecodedChunks,
+    format: AudioFormat,
+    duration: Option<Duration>,
+    frames: usize,
+    metadata: watch::Receiver<Option<IcyMetadata>>,
+}
+
+impl HttpSource {
+    /// Start downloading and decoding `url`
+    ///
+    /// An `.m3u8` playlist is played as an HLS stream.
+    ///
+    /// Blocks until the response headers have arrived and the stream has
+    /// been probed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL is not `http://`, the request fails or the
+    /// audio format is not supported.
+    pub fn open(url: &str) -> io::Result<Self> {
+        let response = get(&HttpUrl::parse(url)?)?;
+        let (metadata_tx, metadata) = watch::channel(None);
+        if hls::is_playlist(response.content_type.as_deref(), &response.url.path) {
+            let (segments, hint) = hls::SegmentReader::open(response)?;
+            return Self::decode(segments, hint, metadata);
+        }
+        let hint = response.hint();
+        match response.icy_metaint {
+            Some(interval) => Self::decode(
+                IcyReader::new(response.body, interval, response.icy_name, metadata_tx),
+                hint,
+                metadata,
+            ),
+            None => Self::decode(response.body, hint, metadata),
+        }
+    }
+
+    /// Now-playing updates from the stream's ICY metadata
+    ///
+    /// Titles arrive as the download reaches them, a couple of seconds
+    /// before they play. The value stays `None` for streams without ICY
+    /// metadata, and the channel closes when the download ends.
+    #[must_use]
+    pub fn metadata(&self) -> watch::Receiver<Option<IcyMetadata>> {
+        self.metadata.clone()
+    }
+
+    /// Decode `body` on a new thread
+    fn decode(
+        body: impl Read + Send + Sync + 'static,
+        hint: Hint,
+        metadata: watch::Receiver<Option<IcyMetadata>>,
+    ) -> io::Result<Self> {
+        let (status_tx, status_rx) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let mut decoder =
+                match FileSource::from_media(Box::new(ReadOnlySource::new(body)), &hint) {
+                    Ok(decoder) => decoder,
+                    Err(e) => {
+                        let _ = status_tx.send(Err(e));
+                        return;
+                    }
+                };
+
+            let format = decoder.format();
+            let chunk_bytes = CHUNK_FRAMES * format.bytes_per_frame();
+            let ahead = format
+                .duration_to_frames(BUFFER_AHEAD)
+                .div_ceil(CHUNK_FRAMES);
+            let (chunk_tx, chunk_rx) = mpsc::sync_channel(ahead);
+            if status_tx
+                .send(Ok((format, decoder.duration(), chunk_rx)))
+                .is_err()
+            {
+                return;
+            }
+
+            loop {
+                // Reads fill the chunk until the end of the file, so every
+                // chunk holds whole frames
+                let mut chunk = vec![0; chunk_bytes];
+                match decoder.read(&mut chunk) {
+                    Ok(0) => return,
+                    Ok(n) => {
+                        chunk.truncate(n);
+                        if chunk_tx.send(Ok(chunk)).is_err() {
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        let _ = chunk_tx.send(Err(e));
+                        return;
+                    }
+                }
+            }
+        });
+
+        let (format, duration, chunks) = status_rx
+            .recv()
+            .map_err(|_| io::Error::other("Decoder thread exited"))??;
+
+        Ok(Self {
+            chunks: DecodedChunks::new(chunks),
+            format,
+            duration,
+            frames: 0,
+            metadata,
+        })
+    }
+}
+
+impl AudioSource for HttpSource {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let frame_bytes = self.format.bytes_per_frame();
+        let filled = self.chunks.read(buffer, frame_bytes)?;
+        self.frames += filled / frame_bytes;
+        Ok(filled)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    fn position(&self) -> Duration {
+        self.format.frames_to_duration(self.frames)
+    }
+}
+
+/// PCM decoded ahead on another thread, handed out without blocking
+pub(crate) struct DecodedChunks {
+    chunks: mpsc::Receiver<io::Result<Vec<u8>>>,
+    pending: Vec<u8>,
+    pending_pos: usize,
+    finished: bool,
+}
+
+impl DecodedChunks {
+    /// Hand out chunks of whole frames from `chunks`, which the decoder
+    /// thread drops at the end of the stream
+    pub(crate) fn new(chunks: mpsc::Receiver<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            chunks,
+            pending: Vec::new(),
+            pending_pos: 0,
+            finished: false,
+        }
+    }
+
+    /// Copy whole frames of `frame_bytes` into `buffer`
+    ///
+    /// When the decoder is behind, returns silence rather than blocking the
+    /// streamer; returns 0 once the decoder has finished.
+    pub(crate) fn read(&mut self, buffer: &mut [u8], frame_bytes: usize) -> io::Result<usize> {
+        let wanted = buffer.len() / frame_bytes * frame_bytes;
+        let mut filled = 0;
+
+        while filled < wanted && !self.finished {
+            if self.pending_pos == self.pending.len() {
+                // Only wait for the decoder while nothing has been copied
+                let next = if filled == 0 {
+                    self.chunks.recv_timeout(READ_WAIT)
+                } else {
+                    self.chunks
+                        .try_recv()
+                        .map_err(|_| mpsc::RecvTimeoutError::Timeout)
+                };
+                match next {
+                    Ok(chunk) => {
+                        self.pending = chunk?;
+                        self.pending_pos = 0;
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => break,
+                    Err(mpsc::RecvTimeoutError::Disconnected) => self.finished = true,
+                }
+                continue;
+            }
+
+            let count = (self.pending.len() - self.pending_pos).min(wanted - filled);
+            buffer[filled..filled + count]
+                .copy_from_slice(&self.pending[self.pending_pos..self.pending_pos + count]);
+            self.pending_pos += count;
+            filled += count;
+        }
+
+        if filled == 0 && !self.finished {
+            // The download is behind: keep the session alive with silence
+            buffer[..wanted].fill(0);
+            filled = wanted;
+        }
+        Ok(filled)
+    }
+}
+
+/// Parts of an `http://` URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HttpUrl {
+    pub(crate) host: String,
+    pub(crate) port: u16,
+    pub(crate) path: String,
+}
+
+impl HttpUrl {
+    /// Parse an absolute `http://` URL
+    pub(crate) fn parse(url: &str) -> io::Result<Self> {
+        let has_scheme = |scheme: &str| {
+            url.get(..scheme.len())
+                .is_some_and(|s| s.eq_ignore_ascii_case(scheme))
+        };
+        let rest = if has_scheme("http://") {
+            &url[7..]
+        } else if has_scheme("https://") {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "https URLs are not supported",
+            ));
+        } else {
+            return Err(invalid(&format!("Not an http URL: {url}")));
+        };
+
+        let (authority, path) = rest.find('/').map_or((rest, "/"), |i| rest.split_at(i));
+        let authority = authority.rsplit('@').next().unwrap_or(authority);
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) if !port.contains(']') => (
+                host,
+                port.parse()
+                    .map_err(|_| invalid(&format!("Invalid port in {url}")))?,
+            ),
+            _ => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid(&format!("No host in {url}")));
+        }
+        let path = path.split('#').next().unwrap_or(path);
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+
+    /// Extension of the path's last segment, if any
+    pub(crate) fn extension(&self) -> Option<&str> {
+        let file = self.path.split('?').next().unwrap_or_default();
+        let (_, extension) = file.rsplit_once('.')?;
+        (!extension.contains('/')).then_some(extension)
+    }
+
+    /// Resolve a `Location` header or playlist entry against this URL
+    pub(crate) fn join(&self, location: &str) -> io::Result<Self> {
+        if location.contains("://") {
+            Self::parse(location)
+        } else if location.starts_with('/') {
+            Ok(Self {
+                host: self.host.clone(),
+                port: self.port,
+                path: location.to_string(),
+            })
+        } else {
+            let dir = self.path.rfind('/').map_or("/", |i| &self.path[..=i]);
+            Ok(Self {
+                host: self.host.clone(),
+                port: self.port,
+                path: format!("{dir}{location}"),
+            })
+        }
+    }
+}
+
+/// Successful HTTP response, headers read
+pub(crate) struct Response {
+    /// URL the response came from, after redirects
+    pub(crate) url: HttpUrl,
+    /// `Content-Type` without parameters
+    pub(crate) content_type: Option<String>,
+    /// Audio bytes between ICY metadata blocks, if the server sends them
+    pub(crate) icy_metaint: Option<usize>,
+    /// Station name from `icy-name`
+    pub(crate) icy_name: Option<String>,
+    /// First byte and full length from `Content-Range`, for a partial
+    /// response
+    pub(crate) content_range: Option<(u64, Option<u64>)>,
+    /// `Content-Length`, if sent
+    pub(crate) content_length: Option<u64>,
+    pub(crate) body: Body,
+}
+
+impl Response {
+    /// Probe hint from the content type and file extension
+    pub(crate) fn hint(&self) -> Hint {
+        let mut hint = Hint::new();
+        if let Some(content_type) = &self.content_type {
+            hint.mime_type(content_type);
+        }
+        if let Some(extension) = self.url.extension() {
+            hint.with_extension(extension);
+        }
+        hint
+    }
+}
+
+/// Body of an HTTP response
+pub(crate) enum Body {
+    /// Read up to a length, or until the server closes the connection
+    Plain(io::Take<BufReader<TcpStream>>),
+    /// `Transfer-Encoding: chunked`
+    Chunked(ChunkedReader<BufReader<TcpStream>>),
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(reader) => reader.read(buf),
+            Self::Chunked(reader) => reader.read(buf),
+        }
+    }
+}
+
+/// Decoder for a chunked transfer-encoded body
+pub(crate) struct ChunkedReader<R> {
+    inner: R,
+    remaining: usize,
+    done: bool,
+}
+
+impl<R: BufRead> ChunkedReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        Self {
+            inner,
+            remaining: 0,
+            done: false,
+        }
+    }
+}
+
+impl<R: BufRead> Read for ChunkedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+        if self.remaining == 0 {
+            let line = read_line(&mut self.inner)?;
+            let size = line.split(';').next().unwrap_or_default().trim();
+            self.remaining = usize::from_str_radix(size, 16)
+                .map_err(|_| invalid(&format!("Invalid chunk size {size:?}")))?;
+            if self.remaining == 0 {
+                // Skip trailers up to the blank line
+                while !read_line(&mut self.inner)?.is_empty() {}
+                self.done = true;
+                return Ok(0);
+            }
+        }
+
+        let len = buf.len().min(self.remaining);
+        let n = self.inner.read(&mut buf[..len])?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= n;
+        if self.remaining == 0 {
+            read_line(&mut self.inner)?;
+        }
+        Ok(n)
+    }
+}
+
+/// Fetch `url`, following redirects
+pub(crate) fn get(url: &HttpUrl) -> io::Result<Response> {
+    request(url, "Icy-MetaData: 1\r\n")
+}
+
+/// Fetch `url` from byte `start` on, following redirects
+///
+/// A server that ignores the range answers with the whole file, without
+/// [`Response::content_range`].
+pub(crate) fn get_range(url: &HttpUrl, start: u64) -> io::Result<Response> {
+    request(url, &format!("Range: bytes={start}-\r\n"))
+}
+
+/// Send a `GET` with `headers`, each ending in CRLF, following redirects
+fn request(url: &HttpUrl, headers: &str) -> io::Result<Response> {
+    let mut url = url.clone();
+
+    for _ in 0..=MAX_REDIRECTS {
+        let mut stream = connect(&url)?;
+        write!(
+            stream,
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: airplay2/{}\r\n\
+             Accept: */*\r\n{headers}Connection: close\r\n\r\n",
+            url.path,
+            url.host,
+            crate::VERSION
+        )?;
+
+        let mut reader = BufReader::new(stream);
+        let status_line = read_line(&mut reader)?;
+        let status: u16 = status_line
+            .split_whitespace()
+            .nth(1)
+            .and_then(|code| code.parse().ok())
+            .ok_or_else(|| invalid(&format!("Invalid status line {status_line:?}")))?;
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(&mut reader)?;
+            if line.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+            }
+        }
+        let header = |name: &str| {
+            headers
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, v)| v.as_str())
+        };
+
+        match status {
+            200..=299 => {
+                let content_type = header("content-type").map(|content_type| {
+                    content_type
+                        .split(';')
+                        .next()
+                        .unwrap_or_default()
+                        .trim()
+                        .to_ascii_lowercase()
+                });
+                let chunked = header("transfer-encoding")
+                    .is_some_and(|te| te.to_ascii_lowercase().contains("chunked"));
+                let content_length = header("content-length").and_then(|len| len.parse().ok());
+                let body = if chunked {
+                    Body::Chunked(ChunkedReader::new(reader))
+                } else {
+                    Body::Plain(reader.take(content_length.unwrap_or(u64::MAX)))
+                };
+                return Ok(Response {
+                    url,
+                    content_type,
+                    icy_metaint: header("icy-metaint")
+                        .and_then(|interval| interval.parse().ok())
+                        .filter(|&interval| interval > 0),
+                    icy_name: header("icy-name")
+                        .filter(|name| !name.is_empty())
+                        .map(str::to_string),
+                    content_range: (status == 206)
+                        .then(|| header("content-range").and_then(parse_content_range))
+                        .flatten(),
+                    content_length,
+                    body,
+                });
+            }
+            301 | 302 | 303 | 307 | 308 => {
+                let location = header("location")
+                    .ok_or_else(|| invalid(&format!("HTTP {status} without Location")))?;
+                url = url.join(location)?;
+            }
+            _ => {
+                return Err(io::Error::other(format!(
+                    "HTTP request failed: {status_line}"
+                )));
+            }
+        }
+    }
+
+    Err(io::Error::other("Too many HTTP redirects"))
+}
+
+/// Parse a `Content-Range` value such as `bytes 100-999/1000` into its first
+/// byte and full length, which may be unknown (`*`)
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (unit, range) = value.trim().split_once(' ')?;
+    if !unit.eq_ignore_ascii_case("bytes") {
+        return None;
+    }
+    let (range, total) = range.split_once('/')?;
+    let (start, _) = range.split_once('-')?;
+    Some((start.trim().parse().ok()?, total.trim().parse().ok()))
+}
+
+fn connect(url: &HttpUrl) -> io::Result<TcpStream> {
+    let host = url.host.trim_start_matches('[').trim_end_matches(']');
+    let mut last_error = None;
+    for addr in std::net::ToSocketAddrs::to_socket_addrs(&(host, url.port))? {
+        match TcpStream::connect_timeout(&addr, NETWORK_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_read_timeout(Some(NETWORK_TIMEOUT))?;
+                stream.set_write_timeout(Some(NETWORK_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("No address for {}", url.host),
+        )
+    }))
+}
+
+/// Read a CRLF-terminated line, without the terminator
+fn read_line(reader: &mut impl BufRead) -> io::Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(line)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
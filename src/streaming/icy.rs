@@ -0,0 +1,148 @@
+//! ICY (SHOUTcast/Icecast) in-stream metadata
+//!
+//! Internet radio servers asked for `Icy-MetaData: 1` interleave a metadata
+//! block into the audio every `icy-metaint` bytes: a length byte counting
+//! 16-byte units, then text like `StreamTitle='Artist - Title';` padded
+//! with NULs. [`IcyReader`] strips the blocks out of the body so the decoder
+//! sees plain audio, and publishes each new title.
+
+use std::io::{self, Read};
+
+use tokio::sync::watch;
+
+use crate::protocol::daap::TrackMetadata;
+
+/// Now-playing information from a radio stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IcyMetadata {
+    /// Song title
+    pub title: String,
+    /// Artist, when the title has the usual `Artist - Title` form
+    pub artist: Option<String>,
+    /// Station name from the `icy-name` header
+    pub station: Option<String>,
+}
+
+impl IcyMetadata {
+    /// Parse a metadata block, without its length byte
+    ///
+    /// Returns `None` if the block has no `StreamTitle` or it is empty, as
+    /// stations send between songs.
+    #[must_use]
+    pub fn parse(block: &[u8]) -> Option<Self> {
+        let end = block.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+        let text = decode_text(&block[..end]);
+
+        let start = text.find("StreamTitle='")? + "StreamTitle='".len();
+        let rest = &text[start..];
+        // Titles may contain quotes, so only `';` or the final quote ends one
+        let stream_title = rest
+            .find("';")
+            .map_or_else(|| rest.strip_suffix('\'').unwrap_or(rest), |i| &rest[..i])
+            .trim();
+        if stream_title.is_empty() {
+            return None;
+        }
+
+        Some(match stream_title.split_once(" - ") {
+            Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+                Self {
+                    title: title.trim().to_string(),
+                    artist: Some(artist.trim().to_string()),
+                    station: None,
+                }
+            }
+            _ => Self {
+                title: stream_title.to_string(),
+                artist: None,
+                station: None,
+            },
+        })
+    }
+
+    /// Metadata to send to the device, with the station as the album
+    #[must_use]
+    pub fn track_metadata(&self) -> TrackMetadata {
+        TrackMetadata {
+            title: Some(self.title.clone()),
+            artist: self.artist.clone(),
+            album: self.station.clone(),
+            ..TrackMetadata::default()
+        }
+    }
+}
+
+/// Body reader removing ICY metadata blocks
+pub(crate) struct IcyReader<R> {
+    inner: R,
+    /// Audio bytes between metadata blocks
+    interval: usize,
+    /// Audio bytes left before the next block
+    until_block: usize,
+    station: Option<String>,
+    updates: watch::Sender<Option<IcyMetadata>>,
+}
+
+impl<R: Read> IcyReader<R> {
+    pub(crate) fn new(
+        inner: R,
+        interval: usize,
+        station: Option<String>,
+        updates: watch::Sender<Option<IcyMetadata>>,
+    ) -> Self {
+        Self {
+            inner,
+            interval,
+            until_block: interval,
+            station,
+            updates,
+        }
+    }
+
+    /// Read a metadata block and publish its title if it changed
+    fn read_block(&mut self) -> io::Result<()> {
+        let mut length = [0u8; 1];
+        self.inner.read_exact(&mut length)?;
+        let mut block = vec![0; usize::from(length[0]) * 16];
+        self.inner.read_exact(&mut block)?;
+        self.until_block = self.interval;
+
+        if let Some(mut metadata) = IcyMetadata::parse(&block) {
+            metadata.station.clone_from(&self.station);
+            self.updates.send_if_modified(|current| {
+                let changed = current.as_ref() != Some(&metadata);
+                if changed {
+                    tracing::debug!("Stream title: {:?}", metadata.title);
+                    *current = Some(metadata);
+                }
+                changed
+            });
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for IcyReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.until_block == 0 {
+            match self.read_block() {
+                // The stream may end right where a block would start
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(0),
+                result => result?,
+            }
+        }
+        let len = buf.len().min(self.until_block);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.until_block -= n;
+        Ok(n)
+    }
+}
+
+/// Decode metadata text: UTF-8 if valid, else Latin-1 as older servers send
+fn decode_text(bytes: &[u8]) -> String {
+    String::from_utf8(bytes.to_vec())
+        .unwrap_or_else(|_| bytes.iter().map(|&b| char::from(b)).collect())
+}
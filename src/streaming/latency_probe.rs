@@ -0,0 +1,416 @@
+//! Acoustic latency measurement
+//!
+//! A device's true output delay (network buffer plus whatever its DSP and
+//! amplifier add) can only be measured at the speaker. [`ChirpSource`] plays
+//! a short frequency sweep, and [`find_chirp`] locates it in a recording by
+//! cross-correlation. With the `loopback-measure` feature,
+//! `measure_latency` does both through the default microphone. The
+//! results feed
+//! [`DeviceGroup::apply_latency_measurements`](crate::group::DeviceGroup::apply_latency_measurements).
+
+use std::f32::consts::PI;
+use std::io;
+use std::time::Duration;
+
+use super::source::AudioSource;
+use crate::audio::AudioFormat;
+
+/// Lowest frequency of the sweep
+const CHIRP_START_HZ: f32 = 500.0;
+/// Highest frequency of the sweep, low enough for 8 kHz capture
+const CHIRP_END_HZ: f32 = 3500.0;
+/// Length of the sweep
+const CHIRP_LENGTH: Duration = Duration::from_millis(500);
+/// Fade at either end of the sweep, avoiding clicks
+const CHIRP_FADE: Duration = Duration::from_millis(10);
+/// Rate the coarse correlation search runs at
+const SEARCH_RATE: u32 = 11_025;
+/// Normalised correlation below which a match is rejected
+const MIN_SCORE: f32 = 0.2;
+
+/// Sweep at `sample_rate`, as mono samples in -1.0..1.0
+#[must_use]
+pub fn chirp(sample_rate: u32) -> Vec<f32> {
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "A half-second sweep has far fewer samples than usize::MAX"
+    )]
+    let len = (CHIRP_LENGTH.as_secs_f64() * f64::from(sample_rate)) as usize;
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "The fade is shorter than the sweep"
+    )]
+    let fade = (CHIRP_FADE.as_secs_f64() * f64::from(sample_rate)) as usize;
+    let length = CHIRP_LENGTH.as_secs_f32();
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "Sample rates are exactly representable"
+    )]
+    let rate = sample_rate as f32;
+
+    (0..len)
+        .map(|i| {
+            #[allow(
+                clippy::cast_precision_loss,
+                reason = "Sample indices in a sweep are exactly representable"
+            )]
+            let t = i as f32 / rate;
+            let phase = 2.0
+                * PI
+                * (CHIRP_START_HZ * t + (CHIRP_END_HZ - CHIRP_START_HZ) * t * t / (2.0 * length));
+            let edge = i.min(len - 1 - i);
+            #[allow(
+                clippy::cast_precision_loss,
+                reason = "Fade lengths are exactly representable"
+            )]
+            let gain = if edge < fade {
+                edge as f32 / fade as f32
+            } else {
+                1.0
+            };
+            0.5 * gain * phase.sin()
+        })
+        .collect()
+}
+
+/// Source playing the sweep between stretches of silence
+///
+/// The silence before gives the device time to start its output, and the
+/// silence after lets the end of the sweep play out before the stream
+/// stops.
+pub struct ChirpSource {
+    samples: Vec<f32>,
+    format: AudioFormat,
+    frames: usize,
+}
+
+impl ChirpSource {
+    /// Silence before the sweep
+    pub const LEAD_IN: Duration = Duration::from_millis(500);
+    /// Silence after the sweep
+    pub const TAIL: Duration = Duration::from_millis(500);
+
+    /// Create the source at CD quality
+    #[must_use]
+    pub fn new() -> Self {
+        let format = AudioFormat::CD_QUALITY;
+        let rate = format.sample_rate.as_u32();
+        let mut samples = vec![0.0; format.duration_to_frames(Self::LEAD_IN)];
+        samples.extend(chirp(rate));
+        samples.extend(std::iter::repeat_n(
+            0.0,
+            format.duration_to_frames(Self::TAIL),
+        ));
+        Self {
+            samples,
+            format,
+            frames: 0,
+        }
+    }
+}
+
+impl Default for ChirpSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioSource for ChirpSource {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let frame_bytes = self.format.bytes_per_frame();
+        let frames = (buffer.len() / frame_bytes).min(self.samples.len() - self.frames);
+        let samples = &self.samples[self.frames..self.frames + frames];
+
+        for (frame, &sample) in buffer.chunks_exact_mut(frame_bytes).zip(samples) {
+            #[allow(
+                clippy::cast_possible_truncation,
+                reason = "Samples are within -1.0..1.0"
+            )]
+            let sample = (sample * f32::from(i16::MAX)) as i16;
+            let bytes = sample.to_le_bytes();
+            frame[..2].copy_from_slice(&bytes);
+            frame[2..4].copy_from_slice(&bytes);
+        }
+        self.frames += frames;
+        Ok(frames * frame_bytes)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        Some(self.format.frames_to_duration(self.samples.len()))
+    }
+
+    fn position(&self) -> Duration {
+        self.format.frames_to_duration(self.frames)
+    }
+}
+
+/// Where the sweep was found in a recording
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChirpMatch {
+    /// Time from the start of the recording to the start of the sweep
+    pub offset: Duration,
+    /// Normalised correlation at the match (0.0 - 1.0)
+    pub score: f32,
+}
+
+/// Find the sweep in a mono recording made at `sample_rate`
+///
+/// The whole recording is searched at about 11 kHz, then the best match is
+/// refined at the full rate. Returns `None` if the recording is shorter
+/// than the sweep or nothing correlates well enough.
+#[must_use]
+pub fn find_chirp(captured: &[f32], sample_rate: u32) -> Option<ChirpMatch> {
+    let reference = chirp(sample_rate);
+    if captured.len() < reference.len() {
+        return None;
+    }
+
+    let step = usize::try_from((sample_rate / SEARCH_RATE).max(1)).unwrap_or(1);
+    let coarse_ref = decimate(&reference, step);
+    let coarse_cap = decimate(captured, step);
+    let (coarse_lag, _) = best_lag(
+        &coarse_cap,
+        &coarse_ref,
+        0..=coarse_cap.len() - coarse_ref.len(),
+    )?;
+
+    let last = captured.len() - reference.len();
+    let centre = coarse_lag * step;
+    let range = centre.saturating_sub(2 * step)..=(centre + 2 * step).min(last);
+    let (lag, score) = best_lag(captured, &reference, range)?;
+    if score < MIN_SCORE {
+        return None;
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "Recording lengths are far below f64 precision limits"
+    )]
+    let offset = Duration::from_secs_f64(lag as f64 / f64::from(sample_rate));
+    Some(ChirpMatch { offset, score })
+}
+
+/// Average each run of `step` samples
+fn decimate(samples: &[f32], step: usize) -> Vec<f32> {
+    if step == 1 {
+        return samples.to_vec();
+    }
+    #[allow(clippy::cast_precision_loss, reason = "Decimation steps are small")]
+    let scale = 1.0 / step as f32;
+    samples
+        .chunks_exact(step)
+        .map(|chunk| chunk.iter().sum::<f32>() * scale)
+        .collect()
+}
+
+/// Lag in `lags` where `reference` best matches `signal`, with its
+/// normalised correlation
+fn best_lag(
+    signal: &[f32],
+    reference: &[f32],
+    lags: std::ops::RangeInclusive<usize>,
+) -> Option<(usize, f32)> {
+    let ref_energy: f64 = reference.iter().map(|&s| f64::from(s * s)).sum();
+    // Running energies, so each window's costs one subtraction
+    let mut energies = Vec::with_capacity(signal.len() + 1);
+    energies.push(0.0f64);
+    for &s in signal {
+        energies.push(energies[energies.len() - 1] + f64::from(s * s));
+    }
+
+    lags.filter_map(|lag| {
+        let window = signal.get(lag..lag + reference.len())?;
+        let dot: f32 = window.iter().zip(reference).map(|(a, b)| a * b).sum();
+        let energy = energies[lag + reference.len()] - energies[lag];
+        let norm = (ref_energy * energy).sqrt();
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "Normalised correlation is within -1.0..1.0"
+        )]
+        (norm > 1e-9).then(|| (lag, (f64::from(dot) / norm) as f32))
+    })
+    .max_by(|a, b| a.1.total_cmp(&b.1))
+}
+
+#[cfg(feature = "loopback-measure")]
+pub use measure::{LatencyMeasurement, measure_latency};
+
+#[cfg(feature = "loopback-measure")]
+mod measure {
+    use std::sync::{Arc, Mutex, PoisonError};
+    use std::time::{Duration, Instant};
+
+    use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+
+    use super::{ChirpMatch, ChirpSource, find_chirp};
+    use crate::client::AirPlayClient;
+    use crate::error::AirPlayError;
+
+    /// Time the microphone runs before the sweep is sent
+    const CAPTURE_WARMUP: Duration = Duration::from_millis(300);
+    /// Longest output delay looked for
+    const MAX_LATENCY: Duration = Duration::from_secs(4);
+
+    /// Measured output delay of a device
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    pub struct LatencyMeasurement {
+        /// Time from handing the sweep to the stream to hearing it
+        pub latency: Duration,
+        /// Normalised correlation of the match (0.0 - 1.0)
+        pub score: f32,
+    }
+
+    struct Capture {
+        samples: Vec<f32>,
+        sample_rate: u32,
+        started: Instant,
+    }
+
+    /// Measure the acoustic output delay of the connected device
+    ///
+    /// Records the default microphone while a [`ChirpSource`] is streamed to
+    /// the device, then finds the sweep in the recording. Place the
+    /// microphone near the speaker and keep the room quiet; the sender's
+    /// own speakers should be muted.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the microphone cannot be opened, streaming fails, or
+    /// the sweep is not heard.
+    pub async fn measure_latency(
+        client: &AirPlayClient,
+    ) -> Result<LatencyMeasurement, AirPlayError> {
+        let source = ChirpSource::new();
+        let window = CAPTURE_WARMUP + ChirpSource::LEAD_IN + super::CHIRP_LENGTH + MAX_LATENCY;
+
+        let capture = tokio::task::spawn_blocking(move || capture_input(window));
+        tokio::time::sleep(CAPTURE_WARMUP).await;
+
+        let sent = Instant::now();
+        client.stream_audio(source).await?.wait().await?;
+
+        let capture = capture
+            .await
+            .map_err(|e| AirPlayError::InternalError {
+                message: format!("Capture task failed: {e}"),
+            })?
+            .map_err(|message| AirPlayError::IoError {
+                message,
+                source: None,
+            })?;
+
+        let ChirpMatch { offset, score } = find_chirp(&capture.samples, capture.sample_rate)
+            .ok_or_else(|| AirPlayError::PlaybackError {
+                message: "Sweep not heard by the microphone".to_string(),
+            })?;
+
+        let heard = capture.started + offset;
+        let played = sent + ChirpSource::LEAD_IN;
+        let latency = heard.saturating_duration_since(played);
+        tracing::info!("Measured output latency {latency:?} (score {score:.2})");
+        Ok(LatencyMeasurement { latency, score })
+    }
+
+    /// Record `window` of mono audio from the default input device
+    fn capture_input(window: Duration) -> Result<Capture, String> {
+        let host = cpal::default_host();
+        let device = host
+            .default_input_device()
+            .ok_or_else(|| "No input device".to_string())?;
+        let supported = device.default_input_config().map_err(|e| e.to_string())?;
+        let sample_rate = supported.sample_rate().0;
+        let channels = usize::from(supported.channels());
+        let config = supported.config();
+
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        let started = Arc::new(Mutex::new(None));
+        let err_fn = |err| tracing::error!("CPAL input stream error: {}", err);
+
+        let stream = match supported.sample_format() {
+            cpal::SampleFormat::F32 => {
+                let (samples, started) = (samples.clone(), started.clone());
+                device.build_input_stream(
+                    &config,
+                    move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                        record(data, channels, sample_rate, &samples, &started);
+                    },
+                    err_fn,
+                    None,
+                )
+            }
+            cpal::SampleFormat::I16 => {
+                let (samples, started) = (samples.clone(), started.clone());
+                device.build_input_stream(
+                    &config,
+                    move |data: &[i16], _: &cpal::InputCallbackInfo| {
+                        let data: Vec<f32> = data
+                            .iter()
+                            .map(|&s| f32::from(s) / f32::from(i16::MAX))
+                            .collect();
+                        record(&data, channels, sample_rate, &samples, &started);
+                    },
+                    err_fn,
+                    None,
+                )
+            }
+            other => return Err(format!("Unsupported input sample format {other:?}")),
+        }
+        .map_err(|e| e.to_string())?;
+
+        stream.play().map_err(|e| e.to_string())?;
+        std::thread::sleep(window);
+        drop(stream);
+
+        let started = started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .ok_or_else(|| "Input device delivered no audio".to_string())?;
+        let samples = std::mem::take(&mut *samples.lock().unwrap_or_else(PoisonError::into_inner));
+        Ok(Capture {
+            samples,
+            sample_rate,
+            started,
+        })
+    }
+
+    /// Append interleaved `data` to `samples` as mono
+    fn record(
+        data: &[f32],
+        channels: usize,
+        sample_rate: u32,
+        samples: &Mutex<Vec<f32>>,
+        started: &Mutex<Option<Instant>>,
+    ) {
+        let frames = data.len() / channels.max(1);
+        started
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert_with(|| {
+                // The first buffer was recorded before the callback ran
+                #[allow(
+                    clippy::cast_precision_loss,
+                    reason = "Buffer lengths are far below f64 precision limits"
+                )]
+                let recorded = Duration::from_secs_f64(frames as f64 / f64::from(sample_rate));
+                Instant::now()
+                    .checked_sub(recorded)
+                    .unwrap_or_else(Instant::now)
+            });
+
+        #[allow(clippy::cast_precision_loss, reason = "Channel counts are small")]
+        let scale = 1.0 / channels.max(1) as f32;
+        samples
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .extend(
+                data.chunks_exact(channels.max(1))
+                    .map(|frame| frame.iter().sum::<f32>() * scale),
+            );
+    }
+}
@@ -0,0 +1,132 @@
+//! Per-stream metrics for diagnosing stutter
+//!
+//! Slow encoding and late packet sends point at host CPU contention, while
+//! a steady sender with audible dropouts points at the network. The
+//! streamer records both, along with how much audio it holds ahead.
+
+use std::time::Duration;
+
+/// Upper bounds of the histogram buckets, in microseconds
+const BUCKET_BOUNDS_US: [u64; 11] = [
+    50, 100, 250, 500, 1_000, 2_500, 5_000, 10_000, 25_000, 50_000, 100_000,
+];
+
+/// Histogram of durations with fixed, roughly logarithmic buckets
+///
+/// Memory use is constant however long the stream runs. Percentiles are
+/// reported as the upper bound of the bucket they fall in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Histogram {
+    /// Counts per bucket; the last counts values above the largest bound
+    counts: [u64; BUCKET_BOUNDS_US.len() + 1],
+    max: Duration,
+}
+
+impl Histogram {
+    /// Record a value
+    pub fn record(&mut self, value: Duration) {
+        let micros = u64::try_from(value.as_micros()).unwrap_or(u64::MAX);
+        let bucket = BUCKET_BOUNDS_US
+            .iter()
+            .position(|&bound| micros <= bound)
+            .unwrap_or(BUCKET_BOUNDS_US.len());
+        self.counts[bucket] += 1;
+        self.max = self.max.max(value);
+    }
+
+    /// Number of values recorded
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Largest value recorded
+    #[must_use]
+    pub fn max(&self) -> Duration {
+        self.max
+    }
+
+    /// Value below which the given fraction (0.0 - 1.0) of values fall
+    ///
+    /// Returns `None` if nothing was recorded.
+    #[must_use]
+    pub fn percentile(&self, fraction: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            clippy::cast_precision_loss,
+            reason = "Rank is clamped to the sample count"
+        )]
+        let rank = ((fraction.clamp(0.0, 1.0) * count as f64).ceil() as u64).max(1);
+
+        let mut seen = 0;
+        for (bucket, &bucket_count) in self.counts.iter().enumerate() {
+            seen += bucket_count;
+            if seen >= rank {
+                return Some(BUCKET_BOUNDS_US.get(bucket).map_or(self.max, |&bound| {
+                    Duration::from_micros(bound).min(self.max)
+                }));
+            }
+        }
+        Some(self.max)
+    }
+
+    /// Bucket upper bounds with their counts
+    ///
+    /// The last bucket, without a bound, holds values above every bound.
+    pub fn buckets(&self) -> impl Iterator<Item = (Option<Duration>, u64)> + '_ {
+        self.counts.iter().enumerate().map(|(bucket, &count)| {
+            (
+                BUCKET_BOUNDS_US
+                    .get(bucket)
+                    .map(|&us| Duration::from_micros(us)),
+                count,
+            )
+        })
+    }
+}
+
+/// Snapshot of a stream's metrics
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StreamMetrics {
+    /// RTP audio packets sent
+    pub packets_sent: u64,
+    /// Audio frames sent
+    pub frames_sent: u64,
+    /// Packets padded with silence because the source fell behind
+    pub underruns: u64,
+    /// Time taken to encode each packet
+    pub encode_time: Histogram,
+    /// Deviation of each interval between packet sends from the packet
+    /// duration
+    pub send_jitter: Histogram,
+    /// Frames buffered ahead of the packet being sent
+    pub queue_depth: usize,
+    /// Lowest queue depth seen
+    pub min_queue_depth: Option<usize>,
+}
+
+impl StreamMetrics {
+    /// Record a packet send
+    pub(crate) fn record_packet(&mut self, frames: usize, queue_depth: usize, underrun: bool) {
+        self.packets_sent += 1;
+        self.frames_sent += frames as u64;
+        if underrun {
+            self.underruns += 1;
+        }
+        self.queue_depth = queue_depth;
+        self.min_queue_depth = Some(
+            self.min_queue_depth
+                .map_or(queue_depth, |min| min.min(queue_depth)),
+        );
+    }
+
+    /// Record the interval since the previous send
+    pub(crate) fn record_interval(&mut self, interval: Duration, expected: Duration) {
+        self.send_jitter.record(interval.abs_diff(expected));
+    }
+}
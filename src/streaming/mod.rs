@@ -1,19 +1,61 @@
 //! Audio streaming
 
+mod adaptive;
+mod announcement;
+/// System audio capture source (requires `capture` feature)
+#[cfg(feature = "capture")]
+pub mod capture;
 /// File-based audio source (requires `decoders` feature)
 #[cfg(feature = "decoders")]
 pub mod file;
+mod gapless;
+/// HTTP Live Streaming playback (requires `decoders` feature)
+#[cfg(feature = "decoders")]
+pub mod hls;
+/// Remote file decode-and-restream source (requires `decoders` feature)
+#[cfg(feature = "decoders")]
+pub mod http;
+/// ICY internet radio metadata (requires `decoders` feature)
+#[cfg(feature = "decoders")]
+pub mod icy;
+mod latency_probe;
+mod metrics;
+mod normalize;
+mod pacer;
 mod pcm;
+#[cfg(feature = "raop")]
 pub mod raop_streamer;
 mod resampler;
 pub mod source;
+mod timeline;
+/// Text-to-speech announcements (requires `tts` feature)
+#[cfg(feature = "tts")]
+pub mod tts;
 mod url;
+/// Remote file playback with HTTP range requests (requires `decoders`
+/// feature)
+#[cfg(feature = "decoders")]
+pub mod url_source;
 
 #[cfg(test)]
 mod tests;
 
+pub use adaptive::{Adaptation, Adjustment};
+pub use announcement::{AnnouncementChannel, MixMode, PreemptionPolicy, StreamPriority};
+pub use gapless::{GaplessQueue, GaplessSource, SourceOpener, TrackBoundary};
+pub use latency_probe::{ChirpMatch, ChirpSource, chirp, find_chirp};
+#[cfg(feature = "loopback-measure")]
+pub use latency_probe::{LatencyMeasurement, measure_latency};
+pub use metrics::{Histogram, StreamMetrics};
+pub(crate) use normalize::normalizing_opener;
+pub use normalize::{NormalizingSource, measure_loudness};
+pub use pacer::Pacing;
 pub use pcm::{PcmStreamer, RtpSender, StreamerState};
-pub use raop_streamer::{RaopStreamConfig, RaopStreamer};
+#[cfg(feature = "raop")]
+pub use raop_streamer::{NtpClock, RaopStreamConfig, RaopStreamer, TimingResponder};
 pub use resampler::ResamplingSource;
-pub use source::{AudioSource, CallbackSource, SilenceSource, SliceSource};
+pub use source::{
+    AudioSource, CalibrationTone, CallbackSource, ReaderSource, SilenceSource, SliceSource,
+};
+pub use timeline::Timeline;
 pub use url::{PlaybackInfo, UrlStreamer};
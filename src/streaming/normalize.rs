@@ -0,0 +1,169 @@
+//! Loudness normalization stage
+
+use std::io;
+use std::sync::Arc;
+
+use crate::audio::loudness::{GainMode, Loudness, LoudnessMeter, ReplayGain};
+use crate::audio::{AudioFormat, gain};
+use crate::streaming::SourceOpener;
+use crate::streaming::source::AudioSource;
+use crate::types::TrackInfo;
+
+/// Audio source that applies a fixed normalization gain
+///
+/// The gain comes from `ReplayGain`/R128 values, typically carried on
+/// [`TrackInfo::replay_gain`] or measured with [`measure_loudness`], so that
+/// tracks from different sources play at a consistent loudness.
+pub struct NormalizingSource {
+    inner: Box<dyn AudioSource>,
+    gain: f32,
+    /// Bytes of a sample split across inner reads
+    pending: Vec<u8>,
+}
+
+impl NormalizingSource {
+    /// Wrap `source`, scaling it by a linear `gain`
+    pub fn new<S: AudioSource + 'static>(source: S, gain: f32) -> Self {
+        Self {
+            inner: Box::new(source),
+            gain,
+            pending: Vec::new(),
+        }
+    }
+
+    /// Wrap `source`, applying `replay_gain` in `mode`
+    pub fn with_replay_gain<S: AudioSource + 'static>(
+        source: S,
+        replay_gain: &ReplayGain,
+        mode: GainMode,
+    ) -> Self {
+        Self::new(source, replay_gain.linear_gain(mode, 0.0))
+    }
+
+    /// Wrap `source`, applying the gain in `track`'s metadata
+    ///
+    /// Tracks without `ReplayGain` values play unchanged.
+    pub fn for_track<S: AudioSource + 'static>(
+        source: S,
+        track: &TrackInfo,
+        mode: GainMode,
+    ) -> Self {
+        match &track.replay_gain {
+            Some(replay_gain) => Self::with_replay_gain(source, replay_gain, mode),
+            None => Self::new(source, 1.0),
+        }
+    }
+
+    /// Linear gain being applied
+    #[must_use]
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Change the linear gain
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain;
+    }
+}
+
+impl AudioSource for NormalizingSource {
+    fn format(&self) -> AudioFormat {
+        self.inner.format()
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let format = self.inner.format().sample_format;
+        let sample_bytes = format.bytes_per_sample();
+
+        loop {
+            let carried = self.pending.len().min(buffer.len());
+            buffer[..carried].copy_from_slice(&self.pending[..carried]);
+            self.pending.drain(..carried);
+
+            let read = if carried < buffer.len() {
+                self.inner.read(&mut buffer[carried..])?
+            } else {
+                0
+            };
+            let total = carried + read;
+            let aligned = total - total % sample_bytes;
+            if aligned == 0 {
+                if read == 0 {
+                    // End of stream: pass any trailing partial sample through
+                    return Ok(total);
+                }
+                // Not a whole sample yet; hold it and read more
+                self.pending.splice(0..0, buffer[..total].iter().copied());
+                continue;
+            }
+
+            gain::scale_samples(&mut buffer[..aligned], format, self.gain);
+            self.pending
+                .splice(0..0, buffer[aligned..total].iter().copied());
+            return Ok(aligned);
+        }
+    }
+
+    fn duration(&self) -> Option<std::time::Duration> {
+        self.inner.duration()
+    }
+
+    fn position(&self) -> std::time::Duration {
+        self.inner.position()
+    }
+
+    fn seek(&mut self, position: std::time::Duration) -> io::Result<()> {
+        self.pending.clear();
+        self.inner.seek(position)
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.inner.is_seekable()
+    }
+}
+
+/// Read `source` to the end, measuring its loudness
+///
+/// This is the first pass of a two-pass normalization: reopen the source
+/// and wrap it with the gain from [`ReplayGain::from_loudness`].
+///
+/// # Errors
+///
+/// Returns error if reading the source fails
+pub fn measure_loudness<S: AudioSource + ?Sized>(source: &mut S) -> io::Result<Loudness> {
+    let format = source.format();
+    let channels = usize::from(format.channels.channels());
+    let mut meter = LoudnessMeter::new(format.sample_rate.as_u32(), channels);
+    let frame_bytes = format.sample_format.bytes_per_sample() * channels;
+    let mut buffer = vec![0u8; 16 * 1024];
+    let mut filled = 0;
+    loop {
+        let read = source.read(&mut buffer[filled..])?;
+        if read == 0 {
+            return Ok(meter.loudness());
+        }
+        filled += read;
+        let aligned = filled - filled % frame_bytes;
+        meter.push_pcm(&buffer[..aligned], format.sample_format);
+        buffer.copy_within(aligned..filled, 0);
+        filled -= aligned;
+    }
+}
+
+/// Wrap `opener` so tracks carrying `ReplayGain` values are normalized
+///
+/// Tracks without values are passed through; the opener may measure and
+/// normalize those itself.
+pub(crate) fn normalizing_opener(opener: SourceOpener, mode: GainMode) -> SourceOpener {
+    Arc::new(move |track: &TrackInfo| {
+        let source = opener(track)?;
+        Ok(match &track.replay_gain {
+            Some(replay_gain) => Box::new(NormalizingSource::with_replay_gain(
+                source,
+                replay_gain,
+                mode,
+            )),
+            None => source,
+        })
+    })
+}
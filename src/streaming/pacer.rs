@@ -0,0 +1,229 @@
+//! Packet pacing for the streaming loop
+//!
+//! Each packet is sent at a deadline derived from its RTP timestamp rather
+//! than one period after the previous send, so timer jitter never
+//! accumulates into drift. By default the deadlines are awaited on the tokio
+//! timer. On a loaded host the runtime may poll the timer late and then
+//! release several packets at once; the thread pacer instead sleeps to the
+//! deadlines on its own OS thread, raised to real-time priority with the
+//! `realtime` feature, and wakes the streaming task at each one.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+
+/// How the streaming loop paces RTP packets
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pacing {
+    /// Tokio interval timer
+    #[default]
+    Timer,
+    /// Dedicated OS thread sleeping to absolute deadlines
+    ///
+    /// With the `realtime` feature the thread requests real-time scheduling
+    /// (`SCHED_FIFO`), which usually needs `CAP_SYS_NICE` or an `rtprio`
+    /// limit; without it the thread keeps normal priority.
+    Thread,
+}
+
+/// Ticks queued by the pacing thread before it waits for the streamer
+const THREAD_TICK_BACKLOG: usize = 8;
+
+/// Maps RTP time to local send deadlines
+///
+/// The packet at `offset` samples past the anchor is due at
+/// `anchor + offset / sample_rate - lead`. Every deadline is computed from
+/// the anchor, so timer lateness on one packet does not push back the next
+/// and the stream does not drift from the sample clock however long it runs.
+/// Clones share the lead, which can change while pacing.
+#[derive(Debug, Clone)]
+pub(crate) struct Deadlines {
+    anchor: Instant,
+    sample_rate: u32,
+    /// Lead in nanoseconds
+    lead: Arc<AtomicU64>,
+}
+
+impl Deadlines {
+    /// Anchor the next packet to now
+    ///
+    /// `lead` sends packets that much ahead of their time, so the first
+    /// `lead` of audio goes out at once.
+    pub(crate) fn new(sample_rate: u32, lead: Duration) -> Self {
+        Self {
+            anchor: Instant::now(),
+            sample_rate: sample_rate.max(1),
+            lead: Arc::new(AtomicU64::new(Self::nanos(lead))),
+        }
+    }
+
+    /// Change the lead; a longer lead sends the difference at once
+    pub(crate) fn set_lead(&self, lead: Duration) {
+        self.lead.store(Self::nanos(lead), Ordering::Relaxed);
+    }
+
+    fn nanos(duration: Duration) -> u64 {
+        u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX)
+    }
+
+    /// Deadline of the packet `samples` past the anchor
+    pub(crate) fn at(&self, samples: u64) -> Instant {
+        let due = self.anchor + self.duration_of(samples);
+        let lead = Duration::from_nanos(self.lead.load(Ordering::Relaxed));
+        due.checked_sub(lead).unwrap_or(self.anchor)
+    }
+
+    /// Move the anchor `samples` later
+    fn advance(&mut self, samples: u64) {
+        self.anchor += self.duration_of(samples);
+    }
+
+    fn duration_of(&self, samples: u64) -> Duration {
+        let nanos = u128::from(samples) * 1_000_000_000 / u128::from(self.sample_rate);
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+}
+
+/// Source of packet deadlines
+pub(crate) enum Pacer {
+    Timer(TimerPacer),
+    Thread(ThreadPacer),
+}
+
+impl Pacer {
+    /// Start pacing packets of `frames_per_packet` samples
+    ///
+    /// The packet with RTP timestamp `rtp_time` is anchored to now.
+    pub(crate) fn start(
+        pacing: Pacing,
+        deadlines: Deadlines,
+        rtp_time: u32,
+        frames_per_packet: u32,
+    ) -> Self {
+        if pacing == Pacing::Thread {
+            match ThreadPacer::spawn(deadlines.clone(), frames_per_packet) {
+                Ok(pacer) => return Self::Thread(pacer),
+                Err(e) => tracing::warn!("Failed to start pacing thread, using a timer: {e}"),
+            }
+        }
+
+        Self::Timer(TimerPacer {
+            deadlines,
+            anchor_rtp: rtp_time,
+        })
+    }
+
+    /// Change how far ahead of their time packets are sent
+    pub(crate) fn set_lead(&self, lead: Duration) {
+        match self {
+            Self::Timer(pacer) => pacer.deadlines.set_lead(lead),
+            Self::Thread(pacer) => pacer.deadlines.set_lead(lead),
+        }
+    }
+
+    /// Wait until the packet with RTP timestamp `rtp_time` is due
+    pub(crate) async fn tick(&mut self, rtp_time: u32) {
+        match self {
+            Self::Timer(pacer) => {
+                let deadline = pacer.deadline(rtp_time);
+                tokio::time::sleep_until(deadline.into()).await;
+            }
+            Self::Thread(pacer) => {
+                if pacer.ticks.recv().await.is_none() {
+                    // The thread only exits once the pacer is dropped
+                    std::future::pending::<()>().await;
+                }
+            }
+        }
+    }
+}
+
+/// Deadlines awaited on the tokio timer
+pub(crate) struct TimerPacer {
+    deadlines: Deadlines,
+    anchor_rtp: u32,
+}
+
+impl TimerPacer {
+    fn deadline(&mut self, rtp_time: u32) -> Instant {
+        let samples = u64::from(rtp_time.wrapping_sub(self.anchor_rtp));
+        let deadline = self.deadlines.at(samples);
+        if samples > u64::from(u32::MAX / 2) {
+            // Move the anchor up before RTP time wraps past it
+            self.deadlines.advance(samples);
+            self.anchor_rtp = rtp_time;
+        }
+        deadline
+    }
+}
+
+/// Deadlines produced by a dedicated thread
+pub(crate) struct ThreadPacer {
+    ticks: mpsc::Receiver<()>,
+    stop: Arc<AtomicBool>,
+    /// Shares the lead with the thread's copy
+    deadlines: Deadlines,
+}
+
+impl ThreadPacer {
+    fn spawn(deadlines: Deadlines, frames_per_packet: u32) -> std::io::Result<Self> {
+        let (tx, ticks) = mpsc::channel(THREAD_TICK_BACKLOG);
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let thread_stop = stop.clone();
+        let thread_deadlines = deadlines.clone();
+        std::thread::Builder::new()
+            .name("airplay-pacer".to_string())
+            .spawn(move || {
+                raise_priority();
+                let mut samples = 0u64;
+                while !thread_stop.load(Ordering::Relaxed) {
+                    let deadline = thread_deadlines.at(samples);
+                    std::thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    // Blocks while the backlog is full
+                    if tx.blocking_send(()).is_err() {
+                        break;
+                    }
+                    samples += u64::from(frames_per_packet);
+                }
+            })?;
+
+        Ok(Self {
+            ticks,
+            stop,
+            deadlines,
+        })
+    }
+}
+
+impl Drop for ThreadPacer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+        self.ticks.close();
+    }
+}
+
+/// Request real-time scheduling for the calling thread
+#[cfg(all(feature = "realtime", unix))]
+fn raise_priority() {
+    // SAFETY: `sched_param` is plain data (zeroed covers platform padding
+    // fields) and the call only affects the calling thread.
+    let result = unsafe {
+        let mut param: libc::sched_param = std::mem::zeroed();
+        param.sched_priority = libc::sched_get_priority_min(libc::SCHED_FIFO);
+        libc::pthread_setschedparam(libc::pthread_self(), libc::SCHED_FIFO, &raw const param)
+    };
+    if result == 0 {
+        tracing::debug!("Pacing thread running with SCHED_FIFO");
+    } else {
+        tracing::warn!(
+            "Could not raise pacing thread priority: {}",
+            std::io::Error::from_raw_os_error(result)
+        );
+    }
+}
+
+#[cfg(not(all(feature = "realtime", unix)))]
+fn raise_priority() {}
@@ -1,18 +1,25 @@
 //! PCM audio streaming to `AirPlay` devices
 
-use std::borrow::Cow;
 use std::sync::Arc;
 use std::time::Duration;
 
 use async_trait::async_trait;
-use tokio::sync::{Mutex, RwLock, mpsc};
+use tokio::sync::{Mutex, RwLock, mpsc, oneshot};
 
 use super::ResamplingSource;
+use super::adaptive::{Adaptation, Adjustment};
+use super::announcement::AnnouncementChannel;
+use super::metrics::StreamMetrics;
+use super::pacer::{Deadlines, Pacer, Pacing};
 use super::source::AudioSource;
-use crate::audio::aac_encoder::AacEncoder;
-use crate::audio::{AudioFormat, AudioRingBuffer};
-use crate::connection::ConnectionManager;
+use crate::audio::aac_encoder::{AacBackend, AacEncoder};
+use crate::audio::raop_encoder::AlacFrameEncoder;
+use crate::audio::{
+    AudioEncoder, AudioFormat, AudioRingBuffer, DUCK_FADE, DspChain, DspStage, SoftGain,
+};
+use crate::connection::{ConnectionEvent, ConnectionManager, StreamFeedback};
 use crate::error::AirPlayError;
+use crate::protocol::crypto::SecretBytes;
 use crate::protocol::rtp::RtpCodec;
 
 /// RTP packet sender trait
@@ -98,14 +105,28 @@ pub struct PcmStreamer {
     cmd_tx: mpsc::Sender<StreamerCommand>,
     /// Command receiver
     cmd_rx: Mutex<mpsc::Receiver<StreamerCommand>>,
-    /// ALAC encoder
-    encoder: Mutex<Option<alac_encoder::AlacEncoder>>,
-    /// AAC encoder
-    encoder_aac: Mutex<Option<AacEncoder>>,
+    /// Packet encoder, `None` to send PCM
+    encoder: Mutex<Option<Box<dyn AudioEncoder>>>,
     /// Codec type
     codec_type: RwLock<AudioCodec>,
     /// Outgoing packet buffer for retransmissions
     packet_buffer: Mutex<crate::protocol::rtp::packet_buffer::PacketBuffer>,
+    /// Software gain (digital volume and ducking)
+    gain: RwLock<Arc<SoftGain>>,
+    /// DSP stages run over the samples before encoding
+    dsp: RwLock<Option<Arc<std::sync::Mutex<DspChain>>>>,
+    /// Announcements mixed into the stream
+    announcements: RwLock<Option<Arc<AnnouncementChannel>>>,
+    /// Metrics of the current stream
+    metrics: std::sync::Mutex<StreamMetrics>,
+    /// How packets are paced
+    pacing: RwLock<Pacing>,
+    /// How far ahead of their time packets are sent
+    send_lead: RwLock<Duration>,
+    /// Configured bitrate of the AAC encoder (bits/s)
+    aac_bitrate: RwLock<Option<u32>>,
+    /// Backend of the built-in AAC encoder
+    aac_backend: RwLock<Option<AacBackend>>,
 }
 
 /// Commands for the streamer
@@ -117,8 +138,10 @@ enum StreamerCommand {
     Resume,
     /// Stop streaming
     Stop,
-    /// Seek to position
-    Seek(Duration),
+    /// Seek to position, optionally reporting the RTP sequence number and
+    /// timestamp of the first packet from it (`None` if the source cannot
+    /// seek)
+    Seek(Duration, Option<oneshot::Sender<Option<(u16, u32)>>>),
     /// Retransmit request
     Retransmit(u16, u16),
 }
@@ -153,18 +176,98 @@ impl PcmStreamer {
             cmd_tx,
             cmd_rx: Mutex::new(cmd_rx),
             encoder: Mutex::new(None),
-            encoder_aac: Mutex::new(None),
             codec_type: RwLock::new(AudioCodec::Pcm),
             packet_buffer: Mutex::new(crate::protocol::rtp::packet_buffer::PacketBuffer::new(
                 crate::protocol::rtp::packet_buffer::PacketBuffer::DEFAULT_SIZE,
             )),
+            gain: RwLock::new(Arc::new(SoftGain::new())),
+            dsp: RwLock::new(None),
+            announcements: RwLock::new(None),
+            metrics: std::sync::Mutex::new(StreamMetrics::default()),
+            pacing: RwLock::new(Pacing::default()),
+            send_lead: RwLock::new(Duration::ZERO),
+            aac_bitrate: RwLock::new(None),
+            aac_backend: RwLock::new(None),
         }
     }
 
     /// Set ChaCha20-Poly1305 encryption key
-    pub async fn set_encryption_key(&self, key: [u8; 32]) {
+    pub async fn set_encryption_key(&self, key: impl Into<SecretBytes>) {
         let mut codec = self.rtp_codec.lock().await;
-        codec.set_chacha_encryption(key);
+        codec.set_chacha_encryption(*key.into().expose());
+    }
+
+    /// Share a software gain with the streamer
+    ///
+    /// Takes effect from the next stream.
+    pub async fn share_gain(&self, gain: Arc<SoftGain>) {
+        *self.gain.write().await = gain;
+    }
+
+    /// Set the digital volume (0.0 - 1.0) applied to the samples
+    ///
+    /// Independent of the device volume; fades over [`DUCK_FADE`] so the
+    /// change is not heard as a step.
+    pub async fn set_gain(&self, level: f32) {
+        self.gain.read().await.set_level(level, DUCK_FADE);
+    }
+
+    /// Fade the streamed audio down to `to` (0.0 - 1.0) over `over`
+    ///
+    /// Holds until [`unduck`](Self::unduck), on top of the level set with
+    /// [`set_gain`](Self::set_gain).
+    pub async fn duck(&self, to: f32, over: Duration) {
+        self.gain.read().await.duck(to, None, over);
+    }
+
+    /// Fade ducked audio back up over `over`
+    pub async fn unduck(&self, over: Duration) {
+        self.gain.read().await.restore(over);
+    }
+
+    /// Run a shared DSP chain over the samples before they are encoded
+    ///
+    /// Takes effect from the next stream; the chain's stages can be swapped
+    /// while streaming.
+    pub async fn share_dsp(&self, dsp: Arc<std::sync::Mutex<DspChain>>) {
+        *self.dsp.write().await = Some(dsp);
+    }
+
+    /// Mix announcements from a shared channel into the stream
+    ///
+    /// Takes effect from the next stream.
+    pub async fn set_announcements(&self, announcements: Arc<AnnouncementChannel>) {
+        *self.announcements.write().await = Some(announcements);
+    }
+
+    /// Choose how packets are paced
+    ///
+    /// Takes effect from the next stream.
+    pub async fn set_pacing(&self, pacing: Pacing) {
+        *self.pacing.write().await = pacing;
+    }
+
+    /// Send packets ahead of their time by `lead`
+    ///
+    /// The first `lead` of audio is sent at once when a stream starts, then
+    /// packets follow in real time, keeping the receiver that much ahead.
+    /// Takes effect from the next stream.
+    pub async fn set_send_lead(&self, lead: Duration) {
+        *self.send_lead.write().await = lead;
+    }
+
+    /// Metrics of the current (or last) stream
+    ///
+    /// Reset when a stream starts.
+    #[must_use]
+    pub fn metrics(&self) -> StreamMetrics {
+        self.lock_metrics().clone()
+    }
+
+    fn lock_metrics(&self) -> std::sync::MutexGuard<'_, StreamMetrics> {
+        self.metrics
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
     }
 
     /// Get current state
@@ -181,6 +284,8 @@ impl PcmStreamer {
         &self,
         mut source: S,
     ) -> Result<(), AirPlayError> {
+        *self.lock_metrics() = StreamMetrics::default();
+
         // Check format compatibility
         if source.format() == self.format {
             *self.state.write().await = StreamerState::Buffering;
@@ -262,27 +367,23 @@ impl PcmStreamer {
     )]
     async fn streaming_loop<S: AudioSource>(&self, mut source: S) -> Result<(), AirPlayError> {
         let codec_type = *self.codec_type.read().await;
-        let frames_per_packet = match codec_type {
-            AudioCodec::Aac => 1024,
-            AudioCodec::AacEld => {
-                let guard = self.encoder_aac.lock().await;
-                if let Some(encoder) = guard.as_ref() {
-                    let len = encoder.get_frame_length().unwrap_or(512);
-                    tracing::info!("Using AAC-ELD frame length: {}", len);
-                    len as usize
-                } else {
-                    512
-                }
-            }
-            _ => Self::FRAMES_PER_PACKET,
-        };
+        let frames_per_packet = self
+            .encoder
+            .lock()
+            .await
+            .as_ref()
+            .map_or(Self::FRAMES_PER_PACKET, |encoder| {
+                encoder.frames_per_packet()
+            });
+        tracing::info!("Using {codec_type:?} with {frames_per_packet} frames per packet");
 
         // Update RTP codec with correct frame size
-        {
-            #[allow(clippy::cast_possible_truncation, reason = "Frame count fits in u32")]
-            let frames = frames_per_packet as u32;
-            self.rtp_codec.lock().await.set_frames_per_packet(frames);
-        }
+        #[allow(clippy::cast_possible_truncation, reason = "Frame count fits in u32")]
+        let rtp_frames_per_packet = frames_per_packet as u32;
+        self.rtp_codec
+            .lock()
+            .await
+            .set_frames_per_packet(rtp_frames_per_packet);
 
         let bytes_per_packet = frames_per_packet * self.format.bytes_per_frame();
         let packet_duration = self.format.frames_to_duration(frames_per_packet);
@@ -295,12 +396,36 @@ impl PcmStreamer {
 
         let mut packet_data = vec![0u8; bytes_per_packet];
         let mut cmd_rx = self.cmd_rx.lock().await;
+        let gain = self.gain.read().await.clone();
+        let dsp = self.dsp.read().await.clone();
+        let announcements = self.announcements.read().await.clone();
+        let channels = usize::from(self.format.channels.channels());
+
+        // Deadlines for audio packets, anchored on the next RTP timestamp
+        let pacing = *self.pacing.read().await;
+        let sample_rate = self.format.sample_rate.as_u32();
+        let send_lead = *self.send_lead.read().await;
+        let mut pacer = Pacer::start(
+            pacing,
+            Deadlines::new(sample_rate, send_lead),
+            self.rtp_codec.lock().await.timestamp(),
+            rtp_frames_per_packet,
+        );
 
-        // Use interval for precise timing of audio packets
-        let mut audio_interval = tokio::time::interval(packet_duration);
-        audio_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Burst);
-        // The first tick completes immediately
-        audio_interval.tick().await;
+        // Receiver feedback adapts the AAC bitrate and the send lead
+        let aac_bitrate = match codec_type {
+            AudioCodec::Aac | AudioCodec::AacEld => *self.aac_bitrate.read().await,
+            _ => None,
+        };
+        let min_bitrate = *crate::types::AirPlayConfig::AAC_BITRATE_RANGE.start();
+        let mut adaptation = Adaptation::new(
+            sample_rate,
+            aac_bitrate.map(|max| (min_bitrate.min(max), max)),
+            send_lead,
+        );
+        // Lead already in flight when the pacer was last anchored
+        let mut lead_base = Duration::ZERO;
+        let mut events = self.connection.subscribe_events();
 
         // Use a separate interval for periodic time announcements (every 1 second)
         let mut announce_interval = tokio::time::interval(Duration::from_secs(1));
@@ -313,157 +438,147 @@ impl PcmStreamer {
         // Reusable buffer for RTP packet to avoid allocations
         let mut rtp_packet_buffer = Vec::with_capacity(bytes_per_packet + 64);
 
-        // Reusable buffer for samples to avoid allocations
-        let mut samples_buffer = Vec::with_capacity(bytes_per_packet / 2);
-
         // Reusable buffer for encoding output to avoid allocations
-        let mut encoding_buffer = vec![0u8; 4096];
+        let mut encoding_buffer = Vec::with_capacity(4096);
+
+        let mut main_finished = false;
+        let mut last_send: Option<std::time::Instant> = None;
 
         loop {
+            let next_rtp_time = self.rtp_codec.lock().await.timestamp();
             tokio::select! {
                 // Audio packet processing
-                _ = audio_interval.tick() => {
-                    // Read from buffer
-                    let mut bytes_read = self.buffer.read(&mut packet_data);
-                    tracing::trace!(
-                        "Read {} bytes from buffer, available={}",
-                        bytes_read,
-                        self.buffer.available()
-                    );
-
-                    if bytes_read == 0 {
-                        // Try to fill buffer
-                        let n = source
-                            .read(&mut refill_buffer)
-                            .map_err(|e| AirPlayError::IoError {
-                                message: "Read failed".to_string(),
-                                source: Some(Box::new(e)),
-                            })?;
-
-                        if n == 0 {
-                            // EOF
-                            tracing::debug!("Source EOF after {} packets sent", packets_sent);
+                () = pacer.tick(next_rtp_time) => {
+                    // An announcement in replace mode holds the main stream
+                    let replaced = if main_finished {
+                        packet_data.fill(0);
+                        if !announcements
+                            .as_ref()
+                            .is_some_and(|a| a.render(&mut packet_data, self.format))
+                        {
+                            self.flush_encoder(&mut rtp_packet_buffer).await?;
                             *self.state.write().await = StreamerState::Finished;
                             return Ok(());
                         }
+                        true
+                    } else {
+                        announcements
+                            .as_ref()
+                            .is_some_and(|a| a.replaces_main() && a.render(&mut packet_data, self.format))
+                    };
 
-                        self.buffer.write(&refill_buffer[..n]);
+                    let mut underrun = false;
+                    if !replaced {
+                        // Read from buffer
+                        let mut bytes_read = self.buffer.read(&mut packet_data);
+                        tracing::trace!(
+                            "Read {} bytes from buffer, available={}",
+                            bytes_read,
+                            self.buffer.available()
+                        );
 
-                        // Try to read again from the refilled buffer
-                        bytes_read = self.buffer.read(&mut packet_data);
-                    }
+                        if bytes_read == 0 {
+                            // Try to fill buffer
+                            let n = source
+                                .read(&mut refill_buffer)
+                                .map_err(|e| AirPlayError::IoError {
+                                    message: "Read failed".to_string(),
+                                    source: Some(Box::new(e)),
+                                })?;
+
+                            if n == 0 {
+                                // EOF
+                                tracing::debug!("Source EOF after {} packets sent", packets_sent);
+                                // Announcements queued behind the main stream play now
+                                main_finished = announcements
+                                    .as_ref()
+                                    .is_some_and(|a| a.main_finished());
+                                if !main_finished {
+                                    self.flush_encoder(&mut rtp_packet_buffer).await?;
+                                    *self.state.write().await = StreamerState::Finished;
+                                    return Ok(());
+                                }
+                            } else {
+                                self.buffer.write(&refill_buffer[..n]);
 
-                    // Pad if needed
-                    if bytes_read < bytes_per_packet {
-                        packet_data[bytes_read..].fill(0);
+                                // Try to read again from the refilled buffer
+                                bytes_read = self.buffer.read(&mut packet_data);
+                            }
+                        }
+
+                        // Pad if needed
+                        if bytes_read < bytes_per_packet {
+                            packet_data[bytes_read..].fill(0);
+                            underrun = !main_finished;
+                        }
+
+                        if let Some(dsp) = &dsp {
+                            dsp.lock()
+                                .unwrap_or_else(std::sync::PoisonError::into_inner)
+                                .process_pcm(
+                                    &mut packet_data,
+                                    self.format.sample_format,
+                                    channels,
+                                    sample_rate,
+                                );
+                        }
+
+                        gain.apply(
+                            &mut packet_data,
+                            self.format.sample_format,
+                            channels,
+                            std::time::Instant::now(),
+                            packet_duration,
+                        );
+
+                        if let Some(announcements) = &announcements {
+                            announcements.render(&mut packet_data, self.format);
+                        }
                     }
 
                     // Encode payload
-                    let encoded_payload: Cow<'_, [u8]> = {
-                        match codec_type {
-                            AudioCodec::Alac => {
-                                let mut encoder_guard = self.encoder.lock().await;
-                                if let Some(encoder) = encoder_guard.as_mut() {
-                                    // alac-encoder 0.3.0 expects byte slice of PCM data
-                                    // and a FormatDescription for that input
-                                    let input_format = alac_encoder::FormatDescription::pcm::<i16>(
-                                        f64::from(self.format.sample_rate.as_u32()),
-                                        u32::from(self.format.channels.channels()),
-                                    );
-
-                                    // Ensure encoding buffer has enough capacity
-                                    if encoding_buffer.len() < 4096 {
-                                        encoding_buffer.resize(4096, 0);
-                                    }
-
-                                    let size =
-                                        encoder.encode(&input_format, &packet_data, &mut encoding_buffer);
-                                    // Safety: clamp size to buffer length to prevent panic if encoder returns a size
-                                    // larger than the buffer (which would have been safe with the original .truncate(size))
-                                    let safe_size = size.min(encoding_buffer.len());
-                                    Cow::Borrowed(&encoding_buffer[..safe_size])
-                                } else {
-                                    Cow::Borrowed(&packet_data)
-                                }
-                            }
-                            AudioCodec::Aac | AudioCodec::AacEld => {
-                                let mut encoder_guard = self.encoder_aac.lock().await;
-                                if let Some(encoder) = encoder_guard.as_mut() {
-                                    // Convert bytes to i16 (Little Endian)
-                                    // We assume input is always I16 Little Endian (standard AirPlay/PCM)
-                                    samples_buffer.clear();
-                                    samples_buffer.extend(
-                                        packet_data
-                                            .chunks_exact(2)
-                                            .map(|chunk| i16::from_le_bytes([chunk[0], chunk[1]])),
-                                    );
-
-                                    match encoder.encode(&samples_buffer) {
-                                        Ok(encoded) => {
-                                            // Add AU Header Section for mpeg4-generic (RFC 3640)
-                                            // AU-headers-length: 16 bits (0x0010) = 16
-                                            // AU-header: size (13 bits) | index (3 bits)
-                                            let mut payload = Vec::with_capacity(4 + encoded.len());
-                                            payload.extend_from_slice(&[0x00, 0x10]);
-
-                                            // AAC frames are small enough to fit in u16
-                                            #[allow(
-                                                clippy::cast_possible_truncation,
-                                                reason = "AAC frame size fits in u16"
-                                            )]
-                                            let size = encoded.len() as u16;
-                                            let header = (size << 3) & 0xFFF8;
-                                            payload.extend_from_slice(&header.to_be_bytes());
-
-                                            payload.extend_from_slice(&encoded);
-                                            Cow::Owned(payload)
-                                        }
-                                        Err(e) => {
-                                            tracing::error!("AAC encoding error: {}", e);
-                                            Cow::Borrowed(&packet_data) // Fallback (will likely sound like static)
-                                        }
-                                    }
-                                } else {
-                                    Cow::Borrowed(&packet_data)
+                    let encode_start = std::time::Instant::now();
+                    let mut encoder_guard = self.encoder.lock().await;
+                    let encoded_payload: Option<&[u8]> = match encoder_guard.as_mut() {
+                        Some(encoder) => {
+                            match encoder.encode_packet(&packet_data, &mut encoding_buffer) {
+                                Ok(()) => Some(&encoding_buffer),
+                                Err(e) => {
+                                    tracing::error!("{codec_type:?} encoding error: {e}");
+                                    None
                                 }
                             }
-                            _ => Cow::Borrowed(&packet_data),
                         }
+                        None => Some(&packet_data),
                     };
+                    drop(encoder_guard);
 
-                    // Encrypt and wrap in RTP
-                    rtp_packet_buffer.clear();
-                    {
-                        let mut codec = self.rtp_codec.lock().await;
-                        codec
-                            .encode_arbitrary_payload(&encoded_payload, &mut rtp_packet_buffer)
-                            .map_err(|e| AirPlayError::RtpError {
-                                message: e.to_string(),
-                            })?;
-                    }
+                    let encode_time = encode_start.elapsed();
 
-                    // Send packet
-                    self.send_packet(&rtp_packet_buffer).await?;
+                    // PCM under a compressed payload type decodes as noise, so
+                    // drop the packet and let the receiver conceal the gap
+                    let Some(encoded_payload) = encoded_payload else {
+                        self.rtp_codec.lock().await.skip_packet();
+                        continue;
+                    };
+                    self.send_payload(encoded_payload, &mut rtp_packet_buffer).await?;
                     packets_sent += 1;
 
-                    // Buffer packet for retransmissions
-                    if rtp_packet_buffer.len() >= 12 {
-                        let seq = u16::from_be_bytes([rtp_packet_buffer[2], rtp_packet_buffer[3]]);
-                        let ts = u32::from_be_bytes([
-                            rtp_packet_buffer[4],
-                            rtp_packet_buffer[5],
-                            rtp_packet_buffer[6],
-                            rtp_packet_buffer[7],
-                        ]);
-                        self.packet_buffer
-                            .lock()
-                            .await
-                            .push(crate::protocol::rtp::packet_buffer::BufferedPacket {
-                                sequence: seq,
-                                timestamp: ts,
-                                data: bytes::Bytes::copy_from_slice(&rtp_packet_buffer),
-                            });
+                    let sent_at = std::time::Instant::now();
+                    {
+                        let mut metrics = self.lock_metrics();
+                        metrics.encode_time.record(encode_time);
+                        if let Some(previous) = last_send {
+                            metrics.record_interval(sent_at - previous, packet_duration);
+                        }
+                        metrics.record_packet(
+                            frames_per_packet,
+                            self.buffer.available() / self.format.bytes_per_frame(),
+                            underrun,
+                        );
                     }
+                    last_send = Some(sent_at);
+
                     if packets_sent == 1 {
                         tracing::info!(
                             "First RTP audio packet sent ({} bytes)",
@@ -497,6 +612,16 @@ impl PcmStreamer {
                     }
                 }
 
+                // Receiver feedback
+                feedback = next_feedback(&mut events) => {
+                    let Some(stream) = feedback.first() else {
+                        continue;
+                    };
+                    let adjustment = adaptation.on_feedback(stream);
+                    self.apply_adjustment(adjustment, codec_type, &pacer, lead_base)
+                        .await;
+                }
+
                 // Command processing
                 cmd = cmd_rx.recv() => {
                     match cmd {
@@ -510,27 +635,31 @@ impl PcmStreamer {
                                         *self.state.write().await = StreamerState::Idle;
                                         return Ok(());
                                     }
+                                    Some(StreamerCommand::Seek(pos, reply)) => {
+                                        self.apply_seek(&mut source, pos, reply).await?;
+                                    }
                                     _ => {}
                                 }
                             }
                             *self.state.write().await = StreamerState::Streaming;
-                            // Reset intervals to avoid burst?
-                            // audio_interval.reset();
+                            // The pause is not send jitter
+                            last_send = None;
+                            // Re-anchor so the paused time is not sent as a
+                            // burst; the receiver still holds the lead
+                            pacer = Pacer::start(
+                                pacing,
+                                Deadlines::new(sample_rate, Duration::ZERO),
+                                self.rtp_codec.lock().await.timestamp(),
+                                rtp_frames_per_packet,
+                            );
+                            lead_base = adaptation.lead();
                         }
                         Some(StreamerCommand::Stop) => {
                             *self.state.write().await = StreamerState::Idle;
                             return Ok(());
                         }
-                        #[allow(clippy::collapsible_match, reason = "Collapsing introduces non-exhaustive pattern")]
-                        Some(StreamerCommand::Seek(pos)) => {
-                            if source.is_seekable() {
-                                source.seek(pos).map_err(|e| AirPlayError::IoError {
-                                    message: "Seek failed".to_string(),
-                                    source: Some(Box::new(e)),
-                                })?;
-                                self.buffer.clear();
-                                self.fill_buffer(&mut source)?;
-                            }
+                        Some(StreamerCommand::Seek(pos, reply)) => {
+                            self.apply_seek(&mut source, pos, reply).await?;
                         }
                         Some(StreamerCommand::Retransmit(seq_start, count)) => {
                             let packets_to_send: Vec<Vec<u8>> = {
@@ -570,6 +699,134 @@ impl PcmStreamer {
         }
     }
 
+    /// Wrap an encoded payload in RTP, send it and keep it for
+    /// retransmission
+    async fn send_payload(
+        &self,
+        payload: &[u8],
+        rtp_packet_buffer: &mut Vec<u8>,
+    ) -> Result<(), AirPlayError> {
+        // Encrypt and wrap in RTP
+        rtp_packet_buffer.clear();
+        self.rtp_codec
+            .lock()
+            .await
+            .encode_arbitrary_payload(payload, rtp_packet_buffer)
+            .map_err(|e| AirPlayError::RtpError {
+                message: e.to_string(),
+            })?;
+
+        self.send_packet(rtp_packet_buffer).await?;
+
+        // Buffer packet for retransmissions
+        if rtp_packet_buffer.len() >= 12 {
+            let seq = u16::from_be_bytes([rtp_packet_buffer[2], rtp_packet_buffer[3]]);
+            let ts = u32::from_be_bytes([
+                rtp_packet_buffer[4],
+                rtp_packet_buffer[5],
+                rtp_packet_buffer[6],
+                rtp_packet_buffer[7],
+            ]);
+            self.packet_buffer.lock().await.push(
+                crate::protocol::rtp::packet_buffer::BufferedPacket {
+                    sequence: seq,
+                    timestamp: ts,
+                    data: bytes::Bytes::copy_from_slice(rtp_packet_buffer),
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Send what the encoder still holds at the end of the stream
+    async fn flush_encoder(&self, rtp_packet_buffer: &mut Vec<u8>) -> Result<(), AirPlayError> {
+        let payloads = {
+            let mut guard = self.encoder.lock().await;
+            let Some(encoder) = guard.as_mut() else {
+                return Ok(());
+            };
+            encoder.flush().unwrap_or_else(|e| {
+                tracing::warn!("Failed to flush encoder: {e}");
+                Vec::new()
+            })
+        };
+        for payload in payloads {
+            self.send_payload(&payload, rtp_packet_buffer).await?;
+        }
+        Ok(())
+    }
+
+    /// Apply an adaptation to the running stream
+    async fn apply_adjustment(
+        &self,
+        adjustment: Adjustment,
+        codec_type: AudioCodec,
+        pacer: &Pacer,
+        lead_base: Duration,
+    ) {
+        if let Some(lead) = adjustment.lead {
+            tracing::info!("Receiver buffer low, sending {lead:?} ahead");
+            pacer.set_lead(lead.saturating_sub(lead_base));
+        }
+        if let Some(bitrate) = adjustment.bitrate {
+            let aot = if codec_type == AudioCodec::AacEld {
+                crate::audio::aac_encoder::AudioObjectType::Mpeg4EnhancedLowDelay
+            } else {
+                crate::audio::aac_encoder::AudioObjectType::Mpeg4LowComplexity
+            };
+            let Some(backend) = *self.aac_backend.read().await else {
+                return;
+            };
+            match AacEncoder::with_backend(
+                self.format.sample_rate.as_u32(),
+                u32::from(self.format.channels.channels()),
+                bitrate,
+                aot,
+                backend,
+            ) {
+                Ok(encoder) => {
+                    tracing::info!("Receiver reported packet loss, AAC bitrate now {bitrate}");
+                    *self.encoder.lock().await = Some(Box::new(encoder));
+                }
+                Err(e) => tracing::warn!("Failed to change AAC bitrate to {bitrate}: {e:?}"),
+            }
+        }
+    }
+
+    /// Move the source to a new position and refill the buffer
+    ///
+    /// Packets already sent are not affected; the next packet carries audio
+    /// from `position`.
+    async fn apply_seek<S: AudioSource>(
+        &self,
+        source: &mut S,
+        position: Duration,
+        reply: Option<oneshot::Sender<Option<(u16, u32)>>>,
+    ) -> Result<(), AirPlayError> {
+        let seekable = source.is_seekable();
+        if seekable {
+            source.seek(position).map_err(|e| AirPlayError::IoError {
+                message: "Seek failed".to_string(),
+                source: Some(Box::new(e)),
+            })?;
+            self.buffer.clear();
+            self.fill_buffer(source)?;
+            if let Some(dsp) = self.dsp.read().await.as_ref() {
+                dsp.lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .reset();
+            }
+            if let Some(encoder) = self.encoder.lock().await.as_mut() {
+                encoder.reset();
+            }
+        }
+        if let Some(reply) = reply {
+            let codec = self.rtp_codec.lock().await;
+            let _ = reply.send(seekable.then(|| (codec.sequence(), codec.timestamp())));
+        }
+        Ok(())
+    }
+
     /// Send an RTP packet
     async fn send_packet(&self, packet: &[u8]) -> Result<(), AirPlayError> {
         tracing::trace!("Sending RTP packet: {} bytes", packet.len());
@@ -644,7 +901,7 @@ impl PcmStreamer {
     /// Returns error if streamer is not running
     pub async fn seek(&self, position: Duration) -> Result<(), AirPlayError> {
         self.cmd_tx
-            .send(StreamerCommand::Seek(position))
+            .send(StreamerCommand::Seek(position, None))
             .await
             .map_err(|_| AirPlayError::InvalidState {
                 message: "Streamer not running".to_string(),
@@ -652,67 +909,166 @@ impl PcmStreamer {
             })
     }
 
+    /// Seek to position, waiting until the seek is applied
+    ///
+    /// Returns the RTP sequence number and timestamp of the first packet
+    /// carrying audio from `position`, for flushing a receiver's buffer up
+    /// to it. Works while paused.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if streamer is not running or the source cannot seek
+    pub async fn seek_to_packet(&self, position: Duration) -> Result<(u16, u32), AirPlayError> {
+        let not_running = || AirPlayError::InvalidState {
+            message: "Streamer not running".to_string(),
+            current_state: "unknown".to_string(),
+        };
+        let (tx, rx) = oneshot::channel();
+        self.cmd_tx
+            .send(StreamerCommand::Seek(position, Some(tx)))
+            .await
+            .map_err(|_| not_running())?;
+        rx.await
+            .map_err(|_| not_running())?
+            .ok_or_else(|| AirPlayError::InvalidState {
+                message: "Audio source is not seekable".to_string(),
+                current_state: "streaming".to_string(),
+            })
+    }
+
+    /// RTP sequence number and timestamp of the next packet to be sent
+    pub async fn next_packet(&self) -> (u16, u32) {
+        let codec = self.rtp_codec.lock().await;
+        (codec.sequence(), codec.timestamp())
+    }
+
+    /// Audio frames carried by each RTP packet
+    pub async fn frames_per_packet(&self) -> u32 {
+        self.rtp_codec.lock().await.frames_per_packet()
+    }
+
     /// Set codec to ALAC
     pub async fn use_alac(&self) {
+        self.set_alac(true).await;
+    }
+
+    /// Set codec to ALAC, sending uncompressed escape frames
+    ///
+    /// Costs the bandwidth of PCM but almost no CPU, for slow senders.
+    pub async fn use_alac_escape_frames(&self) {
+        self.set_alac(false).await;
+    }
+
+    async fn set_alac(&self, compress: bool) {
         // FRAMES_PER_PACKET (352) fits in u32
         #[allow(
             clippy::cast_possible_truncation,
             reason = "FRAMES_PER_PACKET fits in u32"
         )]
-        let format = alac_encoder::FormatDescription::alac(
-            f64::from(self.format.sample_rate.as_u32()),
+        let encoder = AlacFrameEncoder::new(
+            self.format.sample_rate.as_u32(),
+            self.format.channels.channels(),
             Self::FRAMES_PER_PACKET as u32,
-            u32::from(self.format.channels.channels()),
+            compress,
         );
-        *self.encoder.lock().await = Some(alac_encoder::AlacEncoder::new(&format));
-        *self.encoder_aac.lock().await = None;
-        *self.codec_type.write().await = AudioCodec::Alac;
+        match encoder {
+            Ok(encoder) => {
+                *self.aac_backend.write().await = None;
+                *self.encoder.lock().await = Some(Box::new(encoder));
+                *self.codec_type.write().await = AudioCodec::Alac;
+            }
+            Err(e) => tracing::error!("Cannot stream ALAC: {}", e),
+        }
     }
 
     /// Set codec to AAC
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the AAC encoder cannot be initialized (e.g. invalid parameters).
-    pub async fn use_aac(&self, bitrate: u32) {
+    /// Returns `AirPlayError::CodecError` if the AAC encoder cannot be
+    /// initialized (e.g. invalid parameters, or no AAC backend in this build).
+    /// The current codec is kept.
+    pub async fn use_aac(&self, bitrate: u32) -> Result<(), AirPlayError> {
         // Standard AAC-LC: 44100Hz, Stereo
-        let encoder = AacEncoder::new(
-            self.format.sample_rate.as_u32(),
-            u32::from(self.format.channels.channels()),
-            bitrate,
-            fdk_aac::enc::AudioObjectType::Mpeg4LowComplexity,
-        )
-        .expect("Failed to initialize AAC encoder");
-
-        *self.encoder_aac.lock().await = Some(encoder);
-        *self.encoder.lock().await = None;
-        *self.codec_type.write().await = AudioCodec::Aac;
+        self.set_aac(bitrate, AudioCodec::Aac).await
     }
 
     /// Set codec to AAC-ELD
     ///
-    /// # Panics
+    /// # Errors
     ///
-    /// Panics if the AAC encoder cannot be initialized (e.g. invalid parameters).
-    pub async fn use_aac_eld(&self, bitrate: u32) {
+    /// Returns `AirPlayError::CodecError` if the AAC-ELD encoder cannot be
+    /// initialized, which is always the case in builds without the `aac-fdk`
+    /// feature. The current codec is kept.
+    pub async fn use_aac_eld(&self, bitrate: u32) -> Result<(), AirPlayError> {
         // AAC-ELD: 44100Hz, Stereo
+        self.set_aac(bitrate, AudioCodec::AacEld).await
+    }
+
+    async fn set_aac(&self, bitrate: u32, codec: AudioCodec) -> Result<(), AirPlayError> {
+        let aot = if codec == AudioCodec::AacEld {
+            crate::audio::aac_encoder::AudioObjectType::Mpeg4EnhancedLowDelay
+        } else {
+            crate::audio::aac_encoder::AudioObjectType::Mpeg4LowComplexity
+        };
         let encoder = AacEncoder::new(
             self.format.sample_rate.as_u32(),
             u32::from(self.format.channels.channels()),
             bitrate,
-            fdk_aac::enc::AudioObjectType::Mpeg4EnhancedLowDelay,
+            aot,
         )
-        .expect("Failed to initialize AAC-ELD encoder");
+        .map_err(|e| AirPlayError::CodecError {
+            message: format!("Failed to initialize {codec:?} encoder: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+        tracing::info!("Encoding {codec:?} with {}", encoder.backend());
+
+        *self.aac_backend.write().await = Some(encoder.backend());
+        *self.encoder.lock().await = Some(Box::new(encoder));
+        *self.aac_bitrate.write().await = Some(bitrate);
+        *self.codec_type.write().await = codec;
+        Ok(())
+    }
 
-        *self.encoder_aac.lock().await = Some(encoder);
-        *self.encoder.lock().await = None;
-        *self.codec_type.write().await = AudioCodec::AacEld;
+    /// AAC encoder backend in use, `None` unless streaming AAC with a
+    /// built-in encoder
+    pub async fn aac_backend(&self) -> Option<AacBackend> {
+        *self.aac_backend.read().await
     }
 
     /// Set codec to PCM (default)
     pub async fn use_pcm(&self) {
+        *self.aac_backend.write().await = None;
         *self.encoder.lock().await = None;
-        *self.encoder_aac.lock().await = None;
         *self.codec_type.write().await = AudioCodec::Pcm;
     }
+
+    /// Encode the stream with a user-provided encoder
+    ///
+    /// `codec` is the codec negotiated with the receiver, which `encoder`
+    /// must produce. Receiver feedback only adapts the bitrate of the
+    /// built-in AAC encoders.
+    pub async fn use_encoder(&self, codec: AudioCodec, encoder: impl AudioEncoder + 'static) {
+        *self.aac_backend.write().await = None;
+        *self.encoder.lock().await = Some(Box::new(encoder));
+        *self.aac_bitrate.write().await = None;
+        *self.codec_type.write().await = codec;
+    }
+}
+
+/// Wait for the next `/feedback` report; never completes without events
+async fn next_feedback(
+    events: &mut Option<tokio::sync::broadcast::Receiver<ConnectionEvent>>,
+) -> Vec<StreamFeedback> {
+    if let Some(receiver) = events {
+        loop {
+            match receiver.recv().await {
+                Ok(ConnectionEvent::Feedback { streams }) => return streams,
+                Ok(_) | Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => {}
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+        *events = None;
+    }
+    std::future::pending().await
 }
@@ -7,8 +7,9 @@ use bytes::{BufMut, Bytes, BytesMut};
 use crate::protocol::crypto::Aes128Ctr;
 use crate::protocol::raop::RaopSessionKeys;
 use crate::protocol::rtp::packet_buffer::{BufferedPacket, PacketBuffer};
-use crate::protocol::rtp::raop::{RaopAudioPacket, SyncPacket};
+use crate::protocol::rtp::raop::{RaopAudioPacket, RetransmitRequest, SyncPacket};
 use crate::protocol::rtp::raop_timing::TimingSync;
+use crate::protocol::rtp::{NtpTimestamp, TimingPacket, TimingResponse};
 
 /// RAOP streaming configuration
 #[derive(Debug, Clone)]
@@ -21,6 +22,8 @@ pub struct RaopStreamConfig {
     pub ssrc: u32,
     /// Enable retransmission buffer
     pub enable_retransmit: bool,
+    /// Receiver latency in samples, from the `Audio-Latency` header
+    pub latency: u32,
 }
 
 impl Default for RaopStreamConfig {
@@ -30,6 +33,7 @@ impl Default for RaopStreamConfig {
             samples_per_packet: 352,
             ssrc: rand::random(),
             enable_retransmit: true,
+            latency: 11025,
         }
     }
 }
@@ -54,10 +58,16 @@ pub struct RaopStreamer {
     timing: TimingSync,
     /// Is first packet after start/flush
     is_first_packet: bool,
+    /// Next sync packet is the first after start/flush
+    is_first_sync: bool,
     /// Last sync packet sent
     last_sync: Instant,
+    /// Clock for sync packets and timing responses
+    clock: NtpClock,
     /// Last timing request sent
     last_timing: Instant,
+    /// Background task answering the receiver's timing requests
+    timing_responder: Option<TimingResponder>,
 }
 
 impl RaopStreamer {
@@ -104,8 +114,11 @@ impl RaopStreamer {
             encode_buffer_index: 0,
             timing: TimingSync::new(),
             is_first_packet: true,
+            is_first_sync: true,
             last_sync: Instant::now(),
+            clock: NtpClock::new(),
             last_timing: Instant::now(),
+            timing_responder: None,
         }
     }
 
@@ -188,37 +201,70 @@ impl RaopStreamer {
     }
 
     /// Handle retransmit request
+    ///
+    /// Each resent packet is the original RTP packet behind a 4-byte
+    /// retransmit response header. Packets that have fallen out of the
+    /// buffer are skipped.
     #[must_use]
     pub fn handle_retransmit(&self, seq_start: u16, count: u16) -> Vec<Vec<u8>> {
         self.buffer
             .get_range(seq_start, count)
             .map(|p| {
-                // Wrap in retransmit response header
                 let mut response = Vec::with_capacity(4 + p.data.len());
                 response.push(0x80);
                 response.push(0xD6); // PT=0x56 (retransmit response)
                 response.extend_from_slice(&p.sequence.to_be_bytes());
-                response.extend_from_slice(&p.data[4..]); // Skip original header
+                response.extend_from_slice(&p.data);
                 response
             })
             .collect()
     }
 
+    /// Answer a packet received on the control channel
+    ///
+    /// Returns the packets to resend if it was a retransmit request.
+    #[must_use]
+    pub fn handle_control_packet(&self, packet: &[u8]) -> Vec<Vec<u8>> {
+        match RetransmitRequest::from_packet(packet) {
+            Some(request) => {
+                tracing::debug!(
+                    "Retransmit request: seq {} count {}",
+                    request.seq_start,
+                    request.count
+                );
+                self.handle_retransmit(request.seq_start, request.count)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Clock the sync packets and timing responses are stamped with
+    #[must_use]
+    pub fn clock(&self) -> NtpClock {
+        self.clock
+    }
+
     /// Check if sync packet should be sent
+    ///
+    /// Due before the first packet after start/flush, then every second.
     #[must_use]
     pub fn should_send_sync(&self) -> bool {
-        self.last_sync.elapsed() >= Self::SYNC_INTERVAL
+        self.is_first_sync || self.last_sync.elapsed() >= Self::SYNC_INTERVAL
     }
 
     /// Create sync packet
+    ///
+    /// Ties the next packet's RTP timestamp, less the receiver latency, to
+    /// the current time on [`clock`](Self::clock). The first after
+    /// start/flush has the extension bit set.
     pub fn create_sync_packet(&mut self) -> Vec<u8> {
-        let ntp_time = crate::protocol::rtp::NtpTimestamp::now();
         let packet = SyncPacket::new(
+            self.timestamp.wrapping_sub(self.config.latency),
+            self.clock.now(),
             self.timestamp,
-            ntp_time,
-            self.timestamp.wrapping_add(self.config.samples_per_packet),
-            false,
+            self.is_first_sync,
         );
+        self.is_first_sync = false;
         self.last_sync = Instant::now();
         packet.encode()
     }
@@ -247,9 +293,20 @@ impl RaopStreamer {
             .map_err(|e| e.to_string())
     }
 
+    /// Answer the receiver's timing requests arriving on `socket`
+    ///
+    /// RAOP receivers measure latency and clock offset by sending NTP-style
+    /// requests to the timing port given in SETUP. The responder runs until
+    /// the streamer is dropped. Its times come from the same clock as the
+    /// sync packets, so the receiver's offset estimate holds for both.
+    pub fn spawn_timing_responder(&mut self, socket: tokio::net::UdpSocket) {
+        self.timing_responder = Some(TimingResponder::spawn(socket, self.clock));
+    }
+
     /// Flush and prepare for new playback
     pub fn flush(&mut self) {
         self.is_first_packet = true;
+        self.is_first_sync = true;
         self.buffer.clear();
     }
 
@@ -258,7 +315,114 @@ impl RaopStreamer {
         self.sequence = 0;
         self.timestamp = 0;
         self.is_first_packet = true;
+        self.is_first_sync = true;
         self.buffer.clear();
         self.timing = TimingSync::new();
     }
 }
+
+/// NTP clock for a RAOP stream
+///
+/// Starts at the wall clock time and then advances with the monotonic
+/// clock, so adjustments to the system time during a long session do not
+/// make the receiver's view of the sender clock jump.
+#[derive(Debug, Clone, Copy)]
+pub struct NtpClock {
+    origin: Instant,
+    origin_micros: u64,
+}
+
+impl NtpClock {
+    /// Start a clock at the current wall clock time
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            origin: Instant::now(),
+            origin_micros: NtpTimestamp::now().to_micros(),
+        }
+    }
+
+    /// Current time
+    #[must_use]
+    pub fn now(&self) -> NtpTimestamp {
+        self.at(Instant::now())
+    }
+
+    /// Time at `instant`
+    #[must_use]
+    pub fn at(&self, instant: Instant) -> NtpTimestamp {
+        let elapsed = instant.saturating_duration_since(self.origin);
+        let elapsed = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        NtpTimestamp::from_micros(self.origin_micros.saturating_add(elapsed))
+    }
+}
+
+impl Default for NtpClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Background task answering RAOP timing requests
+///
+/// Aborted when dropped.
+#[derive(Debug)]
+pub struct TimingResponder {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl TimingResponder {
+    /// Start answering timing requests on `socket` with times from `clock`
+    #[must_use]
+    pub fn spawn(socket: tokio::net::UdpSocket, clock: NtpClock) -> Self {
+        let task = tokio::spawn(async move {
+            let mut buf = [0u8; 128];
+            loop {
+                let (n, from) = match socket.recv_from(&mut buf).await {
+                    Ok(received) => received,
+                    Err(e) => {
+                        tracing::debug!("Timing socket receive failed: {e}");
+                        continue;
+                    }
+                };
+                let received = clock.now();
+                let Some(mut response) = Self::answer(&buf[..n], received) else {
+                    continue;
+                };
+                response.send_time = clock.now();
+                let response = response.encode_raop(sequence_of(&buf[..n]));
+                if let Err(e) = socket.send_to(&response, from).await {
+                    tracing::debug!("Failed to send timing response: {e}");
+                }
+            }
+        });
+        Self { task }
+    }
+
+    /// Build the response to a timing request received at `received`
+    ///
+    /// Returns `None` for anything other than a timing request.
+    #[must_use]
+    pub fn respond(packet: &[u8], received: NtpTimestamp) -> Option<Vec<u8>> {
+        Some(Self::answer(packet, received)?.encode_raop(sequence_of(packet)))
+    }
+
+    fn answer(packet: &[u8], received: NtpTimestamp) -> Option<TimingResponse> {
+        let TimingPacket::Request(request) = TimingPacket::decode_raop(packet).ok()? else {
+            return None;
+        };
+        tracing::trace!("Answering timing request {}", sequence_of(packet));
+        Some(TimingResponse::answer(&request, received))
+    }
+}
+
+/// RTP sequence number of a decoded RAOP timing packet
+fn sequence_of(packet: &[u8]) -> u16 {
+    u16::from_be_bytes([packet[2], packet[3]])
+}
+
+impl Drop for TimingResponder {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
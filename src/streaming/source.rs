@@ -1,6 +1,10 @@
 //! Audio source abstraction
 
 use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
 
 use crate::audio::AudioFormat;
 
@@ -46,6 +50,32 @@ pub trait AudioSource: Send {
     }
 }
 
+impl<S: AudioSource + ?Sized> AudioSource for Box<S> {
+    fn format(&self) -> AudioFormat {
+        (**self).format()
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        (**self).read(buffer)
+    }
+
+    fn duration(&self) -> Option<std::time::Duration> {
+        (**self).duration()
+    }
+
+    fn position(&self) -> std::time::Duration {
+        (**self).position()
+    }
+
+    fn seek(&mut self, position: std::time::Duration) -> io::Result<()> {
+        (**self).seek(position)
+    }
+
+    fn is_seekable(&self) -> bool {
+        (**self).is_seekable()
+    }
+}
+
 /// Audio source from a byte slice
 pub struct SliceSource {
     data: Vec<u8>,
@@ -150,6 +180,107 @@ where
     }
 }
 
+/// Audio source reading raw PCM from an async reader
+///
+/// Takes interleaved little-endian samples in the given format from any
+/// [`AsyncRead`]: stdin, a pipe from `ffmpeg`, a socket. The reader is
+/// polled without blocking the streaming loop. Only whole frames are handed
+/// out; a frame split across reads is held back until the rest arrives.
+/// While the reader has nothing ready, silence is returned so the stream
+/// keeps its timing. End of input ends the stream, dropping any trailing
+/// partial frame.
+pub struct ReaderSource<R> {
+    reader: R,
+    format: AudioFormat,
+    partial: Vec<u8>,
+    frames: usize,
+    eof: bool,
+}
+
+impl<R> ReaderSource<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    /// Read PCM in `format` from `reader`
+    pub fn new(reader: R, format: AudioFormat) -> Self {
+        Self {
+            reader,
+            format,
+            partial: Vec::new(),
+            frames: 0,
+            eof: false,
+        }
+    }
+
+    /// Get the underlying reader back
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R> AudioSource for ReaderSource<R>
+where
+    R: AsyncRead + Unpin + Send,
+{
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let frame_bytes = self.format.bytes_per_frame();
+        let capacity = buffer.len() / frame_bytes * frame_bytes;
+        if self.eof || capacity == 0 {
+            return Ok(0);
+        }
+
+        let mut filled = self.partial.len();
+        buffer[..filled].copy_from_slice(&self.partial);
+        self.partial.clear();
+
+        let mut cx = Context::from_waker(futures::task::noop_waker_ref());
+        while filled < capacity {
+            let mut read_buf = ReadBuf::new(&mut buffer[filled..capacity]);
+            match Pin::new(&mut self.reader).poll_read(&mut cx, &mut read_buf) {
+                Poll::Ready(Ok(())) if read_buf.filled().is_empty() => {
+                    self.eof = true;
+                    break;
+                }
+                Poll::Ready(Ok(())) => filled += read_buf.filled().len(),
+                Poll::Ready(Err(e)) if e.kind() == io::ErrorKind::Interrupted => {}
+                Poll::Ready(Err(e)) => {
+                    self.partial.extend_from_slice(&buffer[..filled]);
+                    return Err(e);
+                }
+                Poll::Pending => break,
+            }
+        }
+
+        let whole = filled / frame_bytes * frame_bytes;
+        if self.eof {
+            if whole < filled {
+                tracing::debug!(
+                    "Dropping {} bytes of a partial frame at end of input",
+                    filled - whole
+                );
+            }
+        } else {
+            self.partial.extend_from_slice(&buffer[whole..filled]);
+            if whole == 0 {
+                // Nothing ready yet: keep the stream's timing with silence
+                buffer[..capacity].fill(0);
+                return Ok(capacity);
+            }
+        }
+
+        self.frames += whole / frame_bytes;
+        Ok(whole)
+    }
+
+    fn position(&self) -> std::time::Duration {
+        self.format.frames_to_duration(self.frames)
+    }
+}
+
 /// Silence generator
 pub struct SilenceSource {
     format: AudioFormat,
@@ -173,3 +304,122 @@ impl AudioSource for SilenceSource {
         Ok(buffer.len())
     }
 }
+
+/// Pink noise for calibrating speaker levels
+///
+/// Produces 16-bit stereo pink noise at -20 dBFS RMS, the usual reference
+/// level for matching loudspeakers. Played on each group member in turn at
+/// the same volume, the measured sound levels feed
+/// [`GroupVolumeController::apply_measurements`](crate::control::volume::GroupVolumeController::apply_measurements).
+pub struct CalibrationTone {
+    format: AudioFormat,
+    remaining: Option<usize>,
+    frames: usize,
+    rng: u32,
+    filter: [f32; 3],
+}
+
+impl CalibrationTone {
+    /// Scale bringing the filter output to -20 dBFS RMS
+    const GAIN: f32 = 0.0594;
+
+    /// Create an endless tone
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            format: AudioFormat::CD_QUALITY,
+            remaining: None,
+            frames: 0,
+            rng: 0x1234_5678,
+            filter: [0.0; 3],
+        }
+    }
+
+    /// Stop after `duration`
+    #[must_use]
+    pub fn with_duration(mut self, duration: std::time::Duration) -> Self {
+        let frames = duration.as_secs_f64() * f64::from(self.format.sample_rate.as_u32());
+        #[allow(
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss,
+            reason = "Frame count of a finite duration fits in usize"
+        )]
+        let frames = frames.round() as usize;
+        self.remaining = Some(frames);
+        self
+    }
+
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "Frame counts stay far below 2^52"
+    )]
+    fn frames_to_duration(&self, frames: usize) -> std::time::Duration {
+        std::time::Duration::from_secs_f64(
+            frames as f64 / f64::from(self.format.sample_rate.as_u32()),
+        )
+    }
+
+    fn next_sample(&mut self) -> i16 {
+        // xorshift32 white noise in -1.0..1.0
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        #[allow(
+            clippy::cast_precision_loss,
+            reason = "Noise does not need full precision"
+        )]
+        let white = (self.rng as f32 / u32::MAX as f32) * 2.0 - 1.0;
+
+        // Paul Kellet's economy pinking filter
+        let [b0, b1, b2] = &mut self.filter;
+        *b0 = 0.997_65 * *b0 + white * 0.099_046;
+        *b1 = 0.963 * *b1 + white * 0.296_516_4;
+        *b2 = 0.57 * *b2 + white * 1.052_691_3;
+        let pink = (*b0 + *b1 + *b2 + white * 0.1848) * Self::GAIN;
+
+        #[allow(
+            clippy::cast_possible_truncation,
+            reason = "Value is clamped to the i16 range"
+        )]
+        let sample = (pink.clamp(-1.0, 1.0) * f32::from(i16::MAX)) as i16;
+        sample
+    }
+}
+
+impl Default for CalibrationTone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AudioSource for CalibrationTone {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let frame_bytes = self.format.bytes_per_frame();
+        let mut frames = buffer.len() / frame_bytes;
+        if let Some(remaining) = self.remaining {
+            frames = frames.min(remaining);
+            self.remaining = Some(remaining - frames);
+        }
+
+        for frame in buffer[..frames * frame_bytes].chunks_exact_mut(frame_bytes) {
+            let sample = self.next_sample().to_le_bytes();
+            frame[..2].copy_from_slice(&sample);
+            frame[2..4].copy_from_slice(&sample);
+        }
+        self.frames += frames;
+        Ok(frames * frame_bytes)
+    }
+
+    fn duration(&self) -> Option<std::time::Duration> {
+        self.remaining
+            .map(|remaining| self.frames_to_duration(self.frames + remaining))
+    }
+
+    fn position(&self) -> std::time::Duration {
+        self.frames_to_duration(self.frames)
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::connection::StreamFeedback;
+use crate::streaming::{Adaptation, Adjustment};
+
+fn report(lost_packets: u64, buffered_frames: u64) -> StreamFeedback {
+    StreamFeedback {
+        lost_packets: Some(lost_packets),
+        buffered_frames: Some(buffered_frames),
+        ..StreamFeedback::default()
+    }
+}
+
+#[test]
+fn test_loss_lowers_bitrate_and_clean_reports_restore_it() {
+    let mut adaptation = Adaptation::new(44100, Some((32_000, 128_000)), Duration::ZERO);
+    assert_eq!(adaptation.bitrate(), Some(128_000));
+
+    // The first report only sets the baseline of the cumulative counter
+    assert_eq!(
+        adaptation.on_feedback(&report(10, 88200)),
+        Adjustment::default()
+    );
+
+    let adjustment = adaptation.on_feedback(&report(14, 88200));
+    assert_eq!(adjustment.bitrate, Some(96_000));
+    assert_eq!(
+        adaptation.on_feedback(&report(20, 88200)).bitrate,
+        Some(72_000)
+    );
+
+    for _ in 0..4 {
+        assert_eq!(adaptation.on_feedback(&report(20, 88200)).bitrate, None);
+    }
+    assert_eq!(
+        adaptation.on_feedback(&report(20, 88200)).bitrate,
+        Some(88_000)
+    );
+}
+
+#[test]
+fn test_bitrate_stays_within_range() {
+    let mut adaptation = Adaptation::new(44100, Some((32_000, 40_000)), Duration::ZERO);
+    let mut lost = 0;
+    adaptation.on_feedback(&report(lost, 88200));
+    for _ in 0..5 {
+        lost += 5;
+        adaptation.on_feedback(&report(lost, 88200));
+    }
+    assert_eq!(adaptation.bitrate(), Some(32_000));
+
+    for _ in 0..20 {
+        adaptation.on_feedback(&report(lost, 88200));
+    }
+    assert_eq!(adaptation.bitrate(), Some(40_000));
+}
+
+#[test]
+fn test_fixed_bitrate_streams_are_not_adapted() {
+    let mut adaptation = Adaptation::new(44100, None, Duration::ZERO);
+    adaptation.on_feedback(&report(0, 88200));
+    assert_eq!(adaptation.on_feedback(&report(50, 88200)).bitrate, None);
+    assert_eq!(adaptation.bitrate(), None);
+}
+
+#[test]
+fn test_low_receiver_buffer_raises_lead() {
+    let mut adaptation = Adaptation::new(44100, None, Duration::from_millis(900));
+
+    // A quarter of a second is enough
+    assert_eq!(adaptation.on_feedback(&report(0, 11025)).lead, None);
+
+    let adjustment = adaptation.on_feedback(&report(0, 4410));
+    assert_eq!(adjustment.lead, Some(Duration::from_secs(1)));
+    // Capped at one second
+    assert_eq!(adaptation.on_feedback(&report(0, 0)).lead, None);
+    assert_eq!(adaptation.lead(), Duration::from_secs(1));
+}
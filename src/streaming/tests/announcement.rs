@@ -0,0 +1,221 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::audio::{AudioFormat, ChannelConfig, SampleFormat, SampleRate, SoftGain};
+use crate::streaming::{
+    AnnouncementChannel, AudioSource, MixMode, PreemptionPolicy, SliceSource, StreamPriority,
+};
+
+fn format() -> AudioFormat {
+    AudioFormat {
+        sample_rate: SampleRate::Hz44100,
+        channels: ChannelConfig::Stereo,
+        sample_format: SampleFormat::I16,
+    }
+}
+
+fn samples(data: &[u8]) -> Vec<i16> {
+    data.chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+fn packet(value: i16, samples: usize) -> Vec<u8> {
+    std::iter::repeat_n(value, samples)
+        .flat_map(i16::to_le_bytes)
+        .collect()
+}
+
+#[test]
+fn test_replace_overwrites_and_finishes() {
+    let gain = Arc::new(SoftGain::new());
+    let channel = AnnouncementChannel::new(gain.clone());
+    let clip = SliceSource::from_i16(&[1000; 6], format());
+    channel.play(Box::new(clip), MixMode::Replace);
+
+    assert!(channel.is_active());
+    assert!(channel.replaces_main());
+    assert!(!gain.is_ducked(Instant::now()));
+
+    let mut main = packet(5000, 4);
+    assert!(channel.render(&mut main, format()));
+    assert_eq!(samples(&main), vec![1000; 4]);
+
+    // The clip runs out part-way through: the rest is silence
+    let mut main = packet(5000, 4);
+    assert!(channel.render(&mut main, format()));
+    assert_eq!(samples(&main), vec![1000, 1000, 0, 0]);
+    assert!(!channel.is_active());
+
+    let mut main = packet(5000, 4);
+    assert!(!channel.render(&mut main, format()));
+    assert_eq!(samples(&main), vec![5000; 4]);
+}
+
+#[test]
+fn test_duck_mixes_and_restores() {
+    let gain = Arc::new(SoftGain::new());
+    let channel = AnnouncementChannel::new(gain.clone());
+    let clip = SliceSource::from_i16(&[1000; 4], format());
+    channel.play(Box::new(clip), MixMode::Duck { level: 0.2 });
+
+    assert!(!channel.replaces_main());
+    let later = Instant::now() + Duration::from_secs(10);
+    assert!(gain.is_ducked(later));
+    assert!((gain.gain_at(later) - 0.2).abs() < f32::EPSILON);
+
+    let mut main = packet(2000, 4);
+    assert!(channel.render(&mut main, format()));
+    for sample in samples(&main) {
+        assert!((2999..=3001).contains(&sample), "{sample}");
+    }
+
+    // End of clip fades the main stream back up
+    let mut main = packet(2000, 4);
+    channel.render(&mut main, format());
+    assert!(!channel.is_active());
+    assert!(!gain.is_ducked(later));
+}
+
+#[test]
+fn test_cancel_restores_gain() {
+    let gain = Arc::new(SoftGain::new());
+    let channel = AnnouncementChannel::new(gain.clone());
+    channel.play(
+        Box::new(SliceSource::from_i16(&[1000; 1024], format())),
+        MixMode::default(),
+    );
+    channel.cancel();
+
+    assert!(!channel.is_active());
+    assert!(!gain.is_ducked(Instant::now() + Duration::from_secs(1)));
+}
+
+#[test]
+fn test_announcement_is_converted_to_stream_format() {
+    let channel = AnnouncementChannel::new(Arc::new(SoftGain::new()));
+    let mono = AudioFormat {
+        sample_rate: SampleRate::Hz44100,
+        channels: ChannelConfig::Mono,
+        sample_format: SampleFormat::I16,
+    };
+    channel.play(
+        Box::new(SliceSource::from_i16(&[1000; 4096], mono)),
+        MixMode::Replace,
+    );
+
+    let mut main = packet(0, 2 * 352);
+    assert!(channel.render(&mut main, format()));
+    assert!(samples(&main).iter().any(|&s| s != 0));
+}
+
+fn clip(value: i16, samples: usize) -> Box<dyn AudioSource> {
+    Box::new(SliceSource::from_i16(&vec![value; samples], format()))
+}
+
+fn render(channel: &AnnouncementChannel, main: i16) -> Vec<i16> {
+    let mut data = packet(main, 4);
+    channel.render(&mut data, format());
+    samples(&data)
+}
+
+#[test]
+fn test_alarm_preempts_and_announcement_resumes() {
+    let gain = Arc::new(SoftGain::new());
+    let channel = AnnouncementChannel::new(gain.clone());
+    channel.play_with_priority(
+        clip(1000, 8),
+        StreamPriority::Announcement,
+        PreemptionPolicy::Duck { level: 0.5 },
+    );
+    assert_eq!(render(&channel, 0), vec![1000; 4]);
+
+    channel.play_with_priority(
+        clip(3000, 4),
+        StreamPriority::Alarm,
+        PreemptionPolicy::Pause,
+    );
+    assert!(channel.replaces_main());
+    assert_eq!(channel.queued(), 1);
+    let later = Instant::now() + Duration::from_secs(10);
+    assert!(!gain.is_ducked(later));
+
+    assert_eq!(render(&channel, 2000), vec![3000; 4]);
+    // The alarm ran out; the announcement picks up where it stopped
+    assert_eq!(render(&channel, 0), vec![1000; 4]);
+    assert!(!channel.replaces_main());
+    assert!(gain.is_ducked(later));
+}
+
+#[test]
+fn test_equal_priority_waits_its_turn() {
+    let channel = AnnouncementChannel::new(Arc::new(SoftGain::new()));
+    channel.play_with_priority(
+        clip(1000, 4),
+        StreamPriority::Alarm,
+        PreemptionPolicy::Pause,
+    );
+    channel.play_with_priority(
+        clip(2000, 4),
+        StreamPriority::Announcement,
+        PreemptionPolicy::Pause,
+    );
+    channel.play_with_priority(
+        clip(3000, 4),
+        StreamPriority::Alarm,
+        PreemptionPolicy::Pause,
+    );
+    assert_eq!(channel.queued(), 2);
+
+    // The second alarm goes ahead of the lower priority announcement
+    assert_eq!(render(&channel, 0), vec![1000; 4]);
+    assert_eq!(render(&channel, 0), vec![3000; 4]);
+    assert_eq!(render(&channel, 0), vec![2000; 4]);
+    assert_eq!(render(&channel, 5000), vec![5000; 4]);
+    assert!(!channel.is_active());
+}
+
+#[test]
+fn test_queue_waits_for_main_stream() {
+    let channel = AnnouncementChannel::new(Arc::new(SoftGain::new()));
+    channel.play_with_priority(
+        clip(1000, 4),
+        StreamPriority::Announcement,
+        PreemptionPolicy::Queue,
+    );
+
+    assert!(!channel.is_active());
+    assert_eq!(render(&channel, 5000), vec![5000; 4]);
+
+    assert!(channel.main_finished());
+    assert!(channel.replaces_main());
+    assert_eq!(render(&channel, 0), vec![1000; 4]);
+    assert_eq!(render(&channel, 0), vec![0; 4]);
+    assert!(!channel.is_active());
+    assert!(!channel.main_finished());
+}
+
+#[test]
+fn test_cancel_drops_waiting_announcements() {
+    let channel = AnnouncementChannel::new(Arc::new(SoftGain::new()));
+    channel.play_with_priority(
+        clip(1000, 4),
+        StreamPriority::Alarm,
+        PreemptionPolicy::Pause,
+    );
+    channel.play_with_priority(
+        clip(2000, 4),
+        StreamPriority::Alarm,
+        PreemptionPolicy::Pause,
+    );
+    channel.play_with_priority(
+        clip(3000, 4),
+        StreamPriority::Alarm,
+        PreemptionPolicy::Queue,
+    );
+    channel.cancel();
+
+    assert!(!channel.is_active());
+    assert_eq!(channel.queued(), 0);
+    assert!(!channel.main_finished());
+}
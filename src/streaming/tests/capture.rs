@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use crate::streaming::capture::CaptureBuffer;
+
+#[test]
+fn test_capture_buffer_converts_to_stereo() {
+    let buffer = CaptureBuffer::new(64);
+
+    buffer.push(&[0.5, -1.0], 1);
+    assert_eq!(
+        buffer.take(16, Duration::ZERO),
+        vec![16383, 16383, -32767, -32767]
+    );
+
+    // Channels past the first two are dropped
+    buffer.push(&[1.0, 0.0, 0.25, -0.5, 2.0, 0.75], 3);
+    assert_eq!(
+        buffer.take(16, Duration::ZERO),
+        vec![32767, 0, -16383, 32767]
+    );
+}
+
+#[test]
+fn test_capture_buffer_drops_oldest_beyond_backlog() {
+    let buffer = CaptureBuffer::new(4);
+    buffer.push(&[0.0, 0.0, 0.5, 0.5, 1.0, 1.0], 2);
+
+    assert_eq!(buffer.take(2, Duration::ZERO), vec![16383, 16383]);
+    assert_eq!(buffer.take(8, Duration::ZERO), vec![32767, 32767]);
+    assert!(buffer.take(8, Duration::from_millis(1)).is_empty());
+}
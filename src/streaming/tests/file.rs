@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use crate::audio::{ChannelConfig, SampleRate};
+use crate::streaming::AudioSource;
+use crate::streaming::file::FileSource;
+
+/// A PCM WAV file of `channels` channels, 48 kHz, `bits` per sample
+fn wav(channels: u16, bits: u16, data: &[u8]) -> Vec<u8> {
+    let rate = 48_000u32;
+    let block_align = channels * bits / 8;
+    let data_len = u32::try_from(data.len()).unwrap();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&rate.to_le_bytes());
+    out.extend_from_slice(&(rate * u32::from(block_align)).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&bits.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(data);
+    out
+}
+
+/// One second of stereo 16-bit audio whose left sample is the frame index
+fn ramp() -> Vec<u8> {
+    (0..48_000u32)
+        .flat_map(|frame| {
+            #[allow(clippy::cast_possible_truncation, reason = "Wrapping is fine")]
+            let sample = frame as i16;
+            [sample, 0]
+        })
+        .flat_map(i16::to_le_bytes)
+        .collect()
+}
+
+fn open(contents: &[u8]) -> (tempfile::TempDir, FileSource) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audio.wav");
+    std::fs::write(&path, contents).unwrap();
+    let source = FileSource::new(&path).unwrap();
+    (dir, source)
+}
+
+fn read_all(source: &mut FileSource) -> Vec<i16> {
+    let mut out = Vec::new();
+    let mut buf = [0u8; 1000];
+    loop {
+        let n = source.read(&mut buf).unwrap();
+        if n == 0 {
+            return out;
+        }
+        out.extend(
+            buf[..n]
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+    }
+}
+
+#[test]
+fn test_file_source_decodes_incrementally() {
+    let (_dir, mut source) = open(&wav(2, 16, &ramp()));
+
+    assert_eq!(source.format().sample_rate, SampleRate::Hz48000);
+    assert_eq!(source.format().channels, ChannelConfig::Stereo);
+    assert_eq!(source.duration(), Some(Duration::from_secs(1)));
+    assert!(source.is_seekable());
+
+    let mut buf = [0u8; 400];
+    assert_eq!(source.read(&mut buf).unwrap(), 400);
+    assert_eq!(source.position(), source.format().frames_to_duration(100));
+
+    let rest = read_all(&mut source);
+    assert_eq!(rest.len(), 48_000 * 2 - 200);
+    assert_eq!(rest[0], 100);
+    assert_eq!(source.position(), Duration::from_secs(1));
+}
+
+#[test]
+fn test_file_source_seek_lands_on_frame() {
+    let (_dir, mut source) = open(&wav(2, 16, &ramp()));
+
+    source.seek(Duration::from_millis(500)).unwrap();
+    assert_eq!(source.position(), Duration::from_millis(500));
+
+    let samples = read_all(&mut source);
+    assert_eq!(samples.len(), 24_000 * 2);
+    assert_eq!(samples[0], 24_000);
+
+    source.seek(Duration::ZERO).unwrap();
+    assert_eq!(read_all(&mut source)[0], 0);
+}
+
+#[test]
+fn test_file_source_converts_24_bit() {
+    // Two stereo frames: full scale positive, then -0.5
+    let data = [
+        0xFF, 0xFF, 0x7F, 0xFF, 0xFF, 0x7F, 0x00, 0x00, 0xC0, 0x00, 0x00, 0xC0,
+    ];
+    let (_dir, mut source) = open(&wav(2, 24, &data));
+
+    assert_eq!(read_all(&mut source), [32767, 32767, -16384, -16384]);
+}
+
+#[test]
+fn test_file_source_rejects_unsupported_channels() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audio.wav");
+    std::fs::write(&path, wav(3, 16, &[0; 6])).unwrap();
+
+    let err = FileSource::new(&path).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+}
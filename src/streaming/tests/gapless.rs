@@ -0,0 +1,143 @@
+use std::time::Duration;
+
+use crate::audio::{AudioFormat, ChannelConfig, SampleFormat, SampleRate};
+use crate::streaming::{AudioSource, GaplessSource, SliceSource};
+use crate::types::{QueueItem, TrackInfo};
+
+fn item(title: &str) -> QueueItem {
+    QueueItem::new(TrackInfo::new("", title, ""), 0)
+}
+
+fn track(value: i16, frames: usize) -> Box<dyn AudioSource> {
+    Box::new(SliceSource::from_i16(
+        &vec![value; frames * 2],
+        AudioFormat::CD_QUALITY,
+    ))
+}
+
+fn samples(data: &[u8]) -> Vec<i16> {
+    data.chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+#[test]
+fn test_next_track_starts_on_the_following_sample() {
+    let (mut source, queue) = GaplessSource::new(track(1, 441));
+    assert!(queue.needs_next());
+    queue.preload(item("second"), track(2, 100)).unwrap();
+    assert!(!queue.needs_next());
+
+    let mut buffer = vec![0u8; (441 + 50) * 4];
+    let n = source.read(&mut buffer).unwrap();
+    assert_eq!(n, buffer.len());
+    let read = samples(&buffer);
+    assert!(read[..882].iter().all(|&s| s == 1));
+    assert!(read[882..].iter().all(|&s| s == 2));
+
+    // The splice is 441 frames (10ms) into the stream
+    assert_eq!(queue.pending_boundaries(), 1);
+    assert_eq!(queue.next_boundary(), Some(Duration::from_millis(10)));
+    assert_eq!(queue.pop_boundary().unwrap().item.track.title, "second");
+    assert!(queue.needs_next());
+
+    assert_eq!(source.read(&mut buffer).unwrap(), 50 * 4);
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+    assert!(!queue.needs_next());
+}
+
+#[test]
+fn test_silence_while_next_track_opens() {
+    let (mut source, queue) = GaplessSource::new(track(1, 10));
+    let mut buffer = vec![0u8; 4096];
+    assert_eq!(source.read(&mut buffer).unwrap(), 40);
+
+    queue.start_opening();
+    let n = source.read(&mut buffer).unwrap();
+    assert!(n > 0);
+    assert!(buffer[..n].iter().all(|&b| b == 0));
+
+    queue.cancel_opening();
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+    assert!(!queue.needs_next());
+}
+
+#[test]
+fn test_preload_converts_format() {
+    let (mut source, queue) = GaplessSource::new(track(1, 4));
+    let format_48k = AudioFormat {
+        sample_rate: SampleRate::Hz48000,
+        channels: ChannelConfig::Stereo,
+        sample_format: SampleFormat::I16,
+    };
+    let other = SliceSource::from_i16(&vec![1000; 9600], format_48k);
+    queue.preload(item("48k"), Box::new(other)).unwrap();
+
+    assert_eq!(source.format(), AudioFormat::CD_QUALITY);
+    let mut buffer = vec![0u8; 65536];
+    let mut total = 0;
+    loop {
+        let n = source.read(&mut buffer).unwrap();
+        if n == 0 {
+            break;
+        }
+        total += n;
+    }
+    // 4 frames, then 100ms of 48kHz audio at 44.1kHz
+    let frames = total / 4;
+    assert!((4 + 4400..=4 + 4420).contains(&frames), "{frames} frames");
+}
+
+#[test]
+fn test_queue_reports_dropped_source() {
+    let (source, queue) = GaplessSource::new(track(1, 4));
+    assert!(!queue.is_closed());
+    drop(source);
+    assert!(queue.is_closed());
+}
+
+#[test]
+fn test_crossfade_overlaps_tracks() {
+    // 20ms + 20ms with a 10ms fade plays 30ms
+    let (source, queue) = GaplessSource::new(track(10_000, 882));
+    let mut source = source.with_crossfade(Duration::from_millis(10));
+    queue.preload(item("second"), track(20_000, 882)).unwrap();
+
+    let mut read = Vec::new();
+    let mut buffer = vec![0u8; 1000];
+    loop {
+        let n = source.read(&mut buffer).unwrap();
+        if n == 0 {
+            break;
+        }
+        read.extend(samples(&buffer[..n]));
+    }
+    assert_eq!(read.len(), 1323 * 2);
+    assert_eq!(queue.next_boundary(), Some(Duration::from_millis(10)));
+
+    // Untouched on either side of the fade
+    assert!(read[..441 * 2].iter().all(|&s| s == 10_000));
+    assert!(read[882 * 2..].iter().all(|&s| s == 20_000));
+
+    // Equal power: louder than either track at the midpoint, not their sum
+    let start = read[441 * 2];
+    let middle = read[661 * 2];
+    let end = read[881 * 2];
+    assert!((9_900..=10_100).contains(&start), "{start}");
+    assert!((21_000..=21_300).contains(&middle), "{middle}");
+    assert!((19_900..=20_100).contains(&end), "{end}");
+}
+
+#[test]
+fn test_crossfade_skipped_while_next_track_opens() {
+    let (source, queue) = GaplessSource::new(track(1, 10));
+    let mut source = source.with_crossfade(Duration::from_millis(100));
+    queue.start_opening();
+
+    let mut buffer = vec![0u8; 4096];
+    assert_eq!(source.read(&mut buffer).unwrap(), 40);
+    assert!(samples(&buffer[..40]).iter().all(|&s| s == 1));
+
+    let n = source.read(&mut buffer).unwrap();
+    assert!(buffer[..n].iter().all(|&b| b == 0));
+}
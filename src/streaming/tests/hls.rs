@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use super::http_server;
+use crate::streaming::AudioSource;
+use crate::streaming::hls::{Playlist, is_playlist, parse_playlist};
+use crate::streaming::http::HttpSource;
+
+/// Serve `routes`, answering each path with its responses in turn and
+/// repeating the last one; returns the base URL
+fn serve(routes: Vec<(&'static str, Vec<Vec<u8>>)>) -> String {
+    let mut routes: HashMap<String, Vec<Vec<u8>>> = routes
+        .into_iter()
+        .map(|(path, responses)| (path.to_string(), responses))
+        .collect();
+    http_server::serve(move |request| match routes.get_mut(&request.path) {
+        Some(responses) if responses.len() > 1 => responses.remove(0),
+        Some(responses) => responses[0].clone(),
+        None => b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+    })
+}
+
+fn ok(content_type: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+        body.len()
+    )
+    .into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+/// WAV header for 44.1 kHz stereo 16-bit audio of `frames` frames
+fn wav_header(frames: u32) -> Vec<u8> {
+    let data_len = frames * 4;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&44_100u32.to_le_bytes());
+    out.extend_from_slice(&(44_100u32 * 4).to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out
+}
+
+/// 1000 stereo frames whose samples are all `value`
+fn segment(value: i16) -> Vec<u8> {
+    [value; 2000].iter().flat_map(|s| s.to_le_bytes()).collect()
+}
+
+fn read_all(source: &mut HttpSource) -> Vec<i16> {
+    let mut out = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = source.read(&mut buf).unwrap();
+        if n == 0 {
+            return out;
+        }
+        out.extend(
+            buf[..n]
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+    }
+}
+
+#[test]
+fn test_parse_master_playlist() {
+    let Playlist::Master(master) = parse_playlist(
+        "#EXTM3U\n\
+         #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",URI=\"en/audio.m3u8\"\n\
+         #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Main\",DEFAULT=YES,URI=\"main.m3u8\"\n\
+         #EXT-X-STREAM-INF:BANDWIDTH=128000,CODECS=\"mp4a.40.2\"\n\
+         audio.m3u8\n\
+         #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.4d401f,mp4a.40.2\",AUDIO=\"aac\"\n\
+         video.m3u8\n",
+    )
+    .unwrap() else {
+        panic!("expected a master playlist");
+    };
+
+    assert_eq!(master.audio_renditions, ["main.m3u8", "en/audio.m3u8"]);
+    assert_eq!(master.variants.len(), 2);
+    assert_eq!(master.variants[0].uri, "audio.m3u8");
+    assert_eq!(master.variants[0].bandwidth, 128_000);
+    assert_eq!(
+        master.variants[1].codecs.as_deref(),
+        Some("avc1.4d401f,mp4a.40.2")
+    );
+}
+
+#[test]
+fn test_parse_media_playlist_and_live_edge() {
+    let Playlist::Media(media) = parse_playlist(
+        "#EXTM3U\n\
+         #EXT-X-VERSION:3\n\
+         #EXT-X-TARGETDURATION:6\n\
+         #EXT-X-MEDIA-SEQUENCE:100\n\
+         #EXTINF:6.0,\nseg100.aac\n\
+         #EXTINF:6.0,\nseg101.aac\n\
+         #EXTINF:6.0,\nseg102.aac\n\
+         #EXTINF:5.5,title\nseg103.aac\n\
+         #EXTINF:6.0,\nseg104.aac\n",
+    )
+    .unwrap() else {
+        panic!("expected a media playlist");
+    };
+
+    assert_eq!(media.target_duration, Duration::from_secs(6));
+    assert_eq!(media.media_sequence, 100);
+    assert_eq!(media.segments.len(), 5);
+    assert_eq!(media.segments[3].duration, Duration::from_millis(5500));
+    assert!(!media.ended);
+    // 17.5 s from seg102 to the end is short of 18 s, so start at seg101
+    assert_eq!(media.start_index(), 1);
+
+    let ended = crate::streaming::hls::MediaPlaylist {
+        ended: true,
+        ..media
+    };
+    assert_eq!(ended.start_index(), 0);
+
+    assert!(parse_playlist("not a playlist").is_err());
+}
+
+#[test]
+fn test_is_playlist() {
+    assert!(is_playlist(None, "/live/stream.M3U8?token=1"));
+    assert!(is_playlist(Some("application/vnd.apple.mpegurl"), "/live"));
+    assert!(!is_playlist(Some("audio/mpeg"), "/song.mp3"));
+    assert!(crate::streaming::UrlStreamer::is_hls(
+        "http://host/a/index.m3u8#t=1"
+    ));
+    assert!(!crate::streaming::UrlStreamer::is_hls("http://host/a.mp3"));
+}
+
+#[test]
+fn test_hls_source_follows_master_to_vod_playlist() {
+    let header = wav_header(2000);
+    let mut first = header.clone();
+    first.extend_from_slice(&segment(1));
+    let url = serve(vec![
+        (
+            "/master.m3u8",
+            vec![ok(
+                "application/vnd.apple.mpegurl",
+                b"#EXTM3U\n\
+                  #EXT-X-STREAM-INF:BANDWIDTH=900000,CODECS=\"avc1.4d401f,mp4a.40.2\"\n\
+                  video.m3u8\n\
+                  #EXT-X-STREAM-INF:BANDWIDTH=64000,CODECS=\"mp4a.40.2\"\n\
+                  audio/index.m3u8\n",
+            )],
+        ),
+        (
+            "/audio/index.m3u8",
+            vec![ok(
+                "application/vnd.apple.mpegurl",
+                b"#EXTM3U\n#EXT-X-TARGETDURATION:1\n\
+                  #EXTINF:1.0,\na.wav\n#EXTINF:1.0,\n/audio/b.wav\n#EXT-X-ENDLIST\n",
+            )],
+        ),
+        ("/audio/a.wav", vec![ok("audio/wav", &first)]),
+        ("/audio/b.wav", vec![ok("audio/wav", &segment(2))]),
+    ]);
+
+    let mut source = HttpSource::open(&format!("{url}/master.m3u8")).unwrap();
+    let samples: Vec<i16> = read_all(&mut source)
+        .into_iter()
+        .skip_while(|&s| s == 0)
+        .collect();
+    assert_eq!(samples.len(), 4000);
+    assert!(samples[..2000].iter().all(|&s| s == 1));
+    assert!(samples[2000..].iter().all(|&s| s == 2));
+}
+
+#[test]
+fn test_hls_source_plays_live_edge_and_reloads() {
+    let live = b"#EXTM3U\n#EXT-X-TARGETDURATION:1\n#EXT-X-MEDIA-SEQUENCE:0\n\
+                 #EXT-X-MAP:URI=\"init.wav\"\n\
+                 #EXTINF:1.0,\n0.wav\n#EXTINF:1.0,\n1.wav\n\
+                 #EXTINF:1.0,\n2.wav\n#EXTINF:1.0,\n3.wav\n";
+    let mut ended = live.to_vec();
+    ended.extend_from_slice(b"#EXTINF:1.0,\n4.wav\n#EXT-X-ENDLIST\n");
+    let url = serve(vec![
+        (
+            "/live.m3u8",
+            vec![
+                ok("application/vnd.apple.mpegurl", live),
+                ok("application/vnd.apple.mpegurl", &ended),
+            ],
+        ),
+        // Playback starts three segments from the end, so four are heard
+        ("/init.wav", vec![ok("audio/wav", &wav_header(4000))]),
+        ("/0.wav", vec![ok("audio/wav", &segment(10))]),
+        ("/1.wav", vec![ok("audio/wav", &segment(11))]),
+        ("/2.wav", vec![ok("audio/wav", &segment(12))]),
+        ("/3.wav", vec![ok("audio/wav", &segment(13))]),
+        ("/4.wav", vec![ok("audio/wav", &segment(14))]),
+    ]);
+
+    let mut source = HttpSource::open(&format!("{url}/live.m3u8")).unwrap();
+    let mut values: Vec<i16> = read_all(&mut source)
+        .into_iter()
+        .filter(|&s| s != 0)
+        .collect();
+    assert_eq!(values.len(), 8000);
+    values.dedup();
+    assert_eq!(values, [11, 12, 13, 14]);
+}
+
+#[test]
+fn test_hls_source_rejects_transport_stream() {
+    let url = serve(vec![(
+        "/ts.m3u8",
+        vec![ok(
+            "application/vnd.apple.mpegurl",
+            b"#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXTINF:6.0,\nseg0.ts\n#EXT-X-ENDLIST\n",
+        )],
+    )]);
+
+    let err = HttpSource::open(&format!("{url}/ts.m3u8")).err().unwrap();
+    assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+}
@@ -0,0 +1,197 @@
+use std::io::Read;
+
+use super::http_server::serve_responses as serve;
+use crate::audio::{ChannelConfig, SampleRate};
+use crate::streaming::AudioSource;
+use crate::streaming::http::{ChunkedReader, HttpSource, HttpUrl};
+
+/// A 44.1 kHz stereo 16-bit WAV file of `frames` frames counting up
+pub(super) fn wav(frames: u16) -> Vec<u8> {
+    let data: Vec<u8> = (0..frames)
+        .flat_map(|frame| {
+            let sample = i16::try_from(frame).unwrap();
+            [sample, -sample]
+        })
+        .flat_map(i16::to_le_bytes)
+        .collect();
+    let data_len = u32::try_from(data.len()).unwrap();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&2u16.to_le_bytes());
+    out.extend_from_slice(&44_100u32.to_le_bytes());
+    out.extend_from_slice(&(44_100u32 * 4).to_le_bytes());
+    out.extend_from_slice(&4u16.to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(&data);
+    out
+}
+
+fn ok(headers: &str, body: &[u8]) -> Vec<u8> {
+    let mut response = format!("HTTP/1.1 200 OK\r\n{headers}\r\n").into_bytes();
+    response.extend_from_slice(body);
+    response
+}
+
+fn chunked(body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for chunk in body.chunks(1000) {
+        out.extend_from_slice(format!("{:x};ext=1\r\n", chunk.len()).as_bytes());
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\r\n");
+    }
+    out.extend_from_slice(b"0\r\nTrailer: x\r\n\r\n");
+    out
+}
+
+fn read_all(source: &mut HttpSource) -> Vec<i16> {
+    let mut out = Vec::new();
+    let mut buf = [0u8; 1024];
+    loop {
+        let n = source.read(&mut buf).unwrap();
+        if n == 0 {
+            return out;
+        }
+        out.extend(
+            buf[..n]
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+    }
+}
+
+/// Drop the leading silence handed out while the decoder warms up
+fn audio(samples: &[i16]) -> &[i16] {
+    let start = samples
+        .chunks_exact(2)
+        .position(|frame| frame != [0, 0])
+        .map_or(samples.len(), |frame| frame * 2);
+    &samples[start.saturating_sub(2)..]
+}
+
+#[test]
+fn test_http_source_decodes_content_length_body() {
+    let body = wav(5000);
+    let url = serve(vec![ok(
+        &format!(
+            "Content-Type: audio/wav\r\nContent-Length: {}\r\n",
+            body.len()
+        ),
+        &body,
+    )]);
+
+    let mut source = HttpSource::open(&format!("{url}/song.wav")).unwrap();
+    assert_eq!(source.format().sample_rate, SampleRate::Hz44100);
+    assert_eq!(source.format().channels, ChannelConfig::Stereo);
+
+    let samples = read_all(&mut source);
+    let samples = audio(&samples);
+    assert_eq!(samples.len(), 5000 * 2);
+    assert_eq!(&samples[2..6], [1, -1, 2, -2]);
+    assert_eq!(samples[9998], 4999);
+}
+
+#[test]
+fn test_http_source_follows_redirect_to_chunked_body() {
+    let body = wav(3000);
+    let url = serve(vec![
+        b"HTTP/1.1 302 Found\r\nLocation: /files/song.wav\r\nContent-Length: 0\r\n\r\n".to_vec(),
+        ok("Transfer-Encoding: chunked\r\n", &chunked(&body)),
+    ]);
+
+    let mut source = HttpSource::open(&format!("{url}/redirect")).unwrap();
+    let samples = read_all(&mut source);
+    assert_eq!(audio(&samples).len(), 3000 * 2);
+}
+
+#[test]
+fn test_http_source_strips_icy_metadata() {
+    let body = wav(3000);
+    let mut stream = Vec::new();
+    for (i, audio) in body.chunks(4096).enumerate() {
+        if i > 0 {
+            let text = format!("StreamTitle='Artist - Song {i}';");
+            let mut block = text.into_bytes();
+            block.resize(block.len().div_ceil(16) * 16, 0);
+            stream.push(u8::try_from(block.len() / 16).unwrap());
+            stream.extend_from_slice(&block);
+        }
+        stream.extend_from_slice(audio);
+    }
+    let url = serve(vec![ok(
+        "Content-Type: audio/wav\r\nicy-metaint: 4096\r\nicy-name: Test FM\r\n",
+        &stream,
+    )]);
+
+    let mut source = HttpSource::open(&format!("{url}/radio")).unwrap();
+    let metadata = source.metadata();
+    let samples = read_all(&mut source);
+    let samples = audio(&samples);
+    assert_eq!(samples.len(), 3000 * 2);
+    assert_eq!(samples[5998], 2999);
+
+    let latest = metadata.borrow().clone().unwrap();
+    assert_eq!(latest.artist.as_deref(), Some("Artist"));
+    assert_eq!(latest.title, format!("Song {}", body.len() / 4096));
+    assert_eq!(latest.station.as_deref(), Some("Test FM"));
+}
+
+#[test]
+fn test_http_source_reports_http_errors() {
+    let url = serve(vec![
+        b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n".to_vec(),
+    ]);
+
+    let err = HttpSource::open(&format!("{url}/missing.mp3"))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("404"));
+}
+
+#[test]
+fn test_http_url_parse() {
+    assert_eq!(
+        HttpUrl::parse("HTTP://user@example.com:8000/a/b.mp3?x=1#frag").unwrap(),
+        HttpUrl {
+            host: "example.com".to_string(),
+            port: 8000,
+            path: "/a/b.mp3?x=1".to_string(),
+        }
+    );
+    assert_eq!(
+        HttpUrl::parse("http://[::1]").unwrap(),
+        HttpUrl {
+            host: "[::1]".to_string(),
+            port: 80,
+            path: "/".to_string(),
+        }
+    );
+    assert_eq!(
+        HttpUrl::parse("https://example.com/").unwrap_err().kind(),
+        std::io::ErrorKind::Unsupported
+    );
+    assert!(HttpUrl::parse("ftp://example.com/").is_err());
+    assert!(HttpUrl::parse("http://:80/").is_err());
+}
+
+#[test]
+fn test_chunked_reader() {
+    let mut body = Vec::new();
+    ChunkedReader::new(&b"5\r\nhello\r\n7\r\n, world\r\n0\r\n\r\n"[..])
+        .read_to_end(&mut body)
+        .unwrap();
+    assert_eq!(body, b"hello, world");
+
+    let mut truncated = Vec::new();
+    assert!(
+        ChunkedReader::new(&b"5\r\nhel"[..])
+            .read_to_end(&mut truncated)
+            .is_err()
+    );
+}
@@ -0,0 +1,60 @@
+//! One-shot HTTP server shared by the HTTP streaming tests
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+
+/// A request read by [`serve`]
+pub(super) struct Request {
+    /// Request target, e.g. `/song.wav`
+    pub path: String,
+    /// Header lines, without line endings
+    pub headers: Vec<String>,
+}
+
+impl Request {
+    /// Value of the header `name`, matched case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+}
+
+/// Answer each connection with the bytes `respond` returns for its request,
+/// then close it; returns the base URL
+pub(super) fn serve(mut respond: impl FnMut(&Request) -> Vec<u8> + Send + 'static) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let path = line
+                .split_whitespace()
+                .nth(1)
+                .unwrap_or_default()
+                .to_string();
+
+            let mut headers = Vec::new();
+            line.clear();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                headers.push(line.trim_end().to_string());
+                line.clear();
+            }
+
+            let response = respond(&Request { path, headers });
+            // The client may hang up once it has read what it needs
+            let _ = stream.write_all(&response);
+        }
+    });
+    format!("http://{addr}")
+}
+
+/// Answer successive connections with `responses` in order
+pub(super) fn serve_responses(responses: Vec<Vec<u8>>) -> String {
+    let mut responses = responses.into_iter();
+    serve(move |_| responses.next().unwrap_or_default())
+}
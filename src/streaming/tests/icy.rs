@@ -0,0 +1,109 @@
+use std::io::Read;
+
+use tokio::sync::watch;
+
+use crate::streaming::icy::{IcyMetadata, IcyReader};
+
+/// A metadata block with its length byte, padded to 16 bytes
+fn block(text: &str) -> Vec<u8> {
+    let mut block = text.as_bytes().to_vec();
+    block.resize(text.len().div_ceil(16) * 16, 0);
+    let mut out = vec![u8::try_from(block.len() / 16).unwrap()];
+    out.extend_from_slice(&block);
+    out
+}
+
+#[test]
+fn test_parse_artist_and_title() {
+    let metadata =
+        IcyMetadata::parse(b"StreamTitle='Daft Punk - Around the World';StreamUrl='';\0\0")
+            .unwrap();
+    assert_eq!(metadata.artist.as_deref(), Some("Daft Punk"));
+    assert_eq!(metadata.title, "Around the World");
+    assert_eq!(metadata.station, None);
+}
+
+#[test]
+fn test_parse_title_forms() {
+    // No artist separator
+    let metadata = IcyMetadata::parse(b"StreamTitle='Station jingle';").unwrap();
+    assert_eq!(metadata.title, "Station jingle");
+    assert_eq!(metadata.artist, None);
+
+    // Quotes inside the title, and no trailing semicolon
+    let metadata = IcyMetadata::parse(b"StreamTitle='Guns N' Roses - Don't Cry'").unwrap();
+    assert_eq!(metadata.artist.as_deref(), Some("Guns N' Roses"));
+    assert_eq!(metadata.title, "Don't Cry");
+
+    // Latin-1 from older servers
+    let metadata = IcyMetadata::parse(b"StreamTitle='Bj\xf6rk - J\xf3ga';").unwrap();
+    assert_eq!(metadata.artist.as_deref(), Some("Björk"));
+    assert_eq!(metadata.title, "Jóga");
+
+    assert_eq!(IcyMetadata::parse(b"StreamTitle='';"), None);
+    assert_eq!(IcyMetadata::parse(b"StreamUrl='http://x';"), None);
+    assert_eq!(IcyMetadata::parse(&[0; 16]), None);
+}
+
+#[test]
+fn test_track_metadata_uses_station_as_album() {
+    let metadata = IcyMetadata {
+        title: "Teardrop".to_string(),
+        artist: Some("Massive Attack".to_string()),
+        station: Some("Radio Paradise".to_string()),
+    };
+    let track = metadata.track_metadata();
+    assert_eq!(track.title.as_deref(), Some("Teardrop"));
+    assert_eq!(track.artist.as_deref(), Some("Massive Attack"));
+    assert_eq!(track.album.as_deref(), Some("Radio Paradise"));
+}
+
+#[test]
+fn test_reader_strips_blocks_and_publishes_titles() {
+    let mut stream = Vec::new();
+    stream.extend_from_slice(&[1; 8]);
+    stream.extend_from_slice(&block("StreamTitle='A - One';"));
+    stream.extend_from_slice(&[2; 8]);
+    // Empty block: no metadata change
+    stream.push(0);
+    stream.extend_from_slice(&[3; 8]);
+    stream.extend_from_slice(&block("StreamTitle='A - One';"));
+    stream.extend_from_slice(&[4; 8]);
+    stream.extend_from_slice(&block("StreamTitle='B - Two';"));
+    stream.extend_from_slice(&[5; 3]);
+
+    let (tx, mut rx) = watch::channel(None);
+    let mut reader = IcyReader::new(stream.as_slice(), 8, Some("Test FM".to_string()), tx);
+
+    let mut audio = Vec::new();
+    let mut titles = Vec::new();
+    let mut buf = [0u8; 5];
+    loop {
+        let n = reader.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        audio.extend_from_slice(&buf[..n]);
+        if rx.has_changed().unwrap_or(false) {
+            titles.push(rx.borrow_and_update().clone().unwrap());
+        }
+    }
+
+    let expected: Vec<u8> = [1, 2, 3, 4]
+        .iter()
+        .flat_map(|&b| [b; 8])
+        .chain([5; 3])
+        .collect();
+    assert_eq!(audio, expected);
+    let titles: Vec<_> = titles
+        .iter()
+        .map(|m| (m.artist.as_deref(), m.title.as_str(), m.station.as_deref()))
+        .collect();
+    assert_eq!(
+        titles,
+        [
+            (Some("A"), "One", Some("Test FM")),
+            (Some("B"), "Two", Some("Test FM"))
+        ]
+    );
+}
@@ -0,0 +1,66 @@
+use std::time::Duration;
+
+use crate::streaming::{AudioSource, ChirpSource, chirp, find_chirp};
+
+#[test]
+fn test_find_chirp_in_noisy_recording() {
+    let rate = 8000;
+    let sweep = chirp(rate);
+    let start = 5321;
+
+    // Attenuated sweep over low-level noise
+    let mut rng = 0x2468_ace1u32;
+    let mut captured: Vec<f32> = (0..16_000)
+        .map(|_| {
+            rng ^= rng << 13;
+            rng ^= rng >> 17;
+            rng ^= rng << 5;
+            #[allow(clippy::cast_precision_loss, reason = "Test noise")]
+            let noise = rng as f32 / u32::MAX as f32 - 0.5;
+            noise * 0.05
+        })
+        .collect();
+    for (sample, s) in captured[start..].iter_mut().zip(&sweep) {
+        *sample += s * 0.3;
+    }
+
+    let found = find_chirp(&captured, rate).expect("sweep not found");
+    let expected = Duration::from_secs_f64(f64::from(5321u32) / f64::from(rate));
+    assert_eq!(found.offset, expected);
+    assert!(found.score > 0.8, "score {}", found.score);
+}
+
+#[test]
+fn test_find_chirp_rejects_silence_and_short_recordings() {
+    assert!(find_chirp(&[0.0; 100], 8000).is_none());
+    assert!(find_chirp(&vec![0.0; 16_000], 8000).is_none());
+
+    let tone: Vec<f32> = (0..16_000)
+        .map(|i| {
+            #[allow(clippy::cast_precision_loss, reason = "Test tone")]
+            let t = i as f32 / 8000.0;
+            (2.0 * std::f32::consts::PI * 1000.0 * t).sin()
+        })
+        .collect();
+    assert!(find_chirp(&tone, 8000).is_none());
+}
+
+#[test]
+fn test_chirp_source_layout() {
+    let mut source = ChirpSource::new();
+    let total = source.duration().unwrap();
+    assert_eq!(
+        total,
+        ChirpSource::LEAD_IN + Duration::from_millis(500) + ChirpSource::TAIL
+    );
+
+    let mut buffer = vec![0u8; 4 * 44_100 * 2];
+    let n = source.read(&mut buffer).unwrap();
+    assert_eq!(n, 4 * 66_150);
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+
+    // Silent lead-in, then the sweep
+    let lead_in = 4 * 22_050;
+    assert!(buffer[..lead_in].iter().all(|&b| b == 0));
+    assert!(buffer[lead_in..lead_in + 4 * 4410].iter().any(|&b| b != 0));
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use crate::streaming::{Histogram, StreamMetrics};
+
+#[test]
+fn test_histogram_percentiles() {
+    let mut histogram = Histogram::default();
+    assert_eq!(histogram.percentile(0.5), None);
+
+    for _ in 0..90 {
+        histogram.record(Duration::from_micros(80));
+    }
+    for _ in 0..10 {
+        histogram.record(Duration::from_millis(3));
+    }
+
+    assert_eq!(histogram.count(), 100);
+    assert_eq!(histogram.max(), Duration::from_millis(3));
+    assert_eq!(histogram.percentile(0.5), Some(Duration::from_micros(100)));
+    assert_eq!(histogram.percentile(0.9), Some(Duration::from_micros(100)));
+    // The bucket bound is capped at the largest value seen
+    assert_eq!(histogram.percentile(0.99), Some(Duration::from_millis(3)));
+}
+
+#[test]
+fn test_histogram_overflow_bucket() {
+    let mut histogram = Histogram::default();
+    histogram.record(Duration::from_secs(1));
+
+    assert_eq!(histogram.percentile(1.0), Some(Duration::from_secs(1)));
+    let (bound, count) = histogram.buckets().last().unwrap();
+    assert_eq!(bound, None);
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn test_stream_metrics_queue_depth() {
+    let mut metrics = StreamMetrics::default();
+    metrics.record_packet(352, 4000, false);
+    metrics.record_packet(352, 1000, true);
+    metrics.record_packet(352, 2000, false);
+    metrics.record_interval(Duration::from_millis(10), Duration::from_millis(8));
+
+    assert_eq!(metrics.packets_sent, 3);
+    assert_eq!(metrics.frames_sent, 1056);
+    assert_eq!(metrics.underruns, 1);
+    assert_eq!(metrics.queue_depth, 2000);
+    assert_eq!(metrics.min_queue_depth, Some(1000));
+    assert_eq!(metrics.send_jitter.max(), Duration::from_millis(2));
+}
@@ -1,5 +1,30 @@
+mod adaptive;
+mod announcement;
+#[cfg(feature = "capture")]
+mod capture;
+#[cfg(feature = "decoders")]
+mod file;
+mod gapless;
+#[cfg(feature = "decoders")]
+mod hls;
+#[cfg(feature = "decoders")]
+mod http;
+#[cfg(feature = "decoders")]
+mod http_server;
+#[cfg(feature = "decoders")]
+mod icy;
+mod latency_probe;
+mod metrics;
+mod normalize;
+mod pacer;
 mod pcm;
+#[cfg(feature = "raop")]
 mod raop_streamer;
 mod resampler;
 mod source;
+mod timeline;
+#[cfg(feature = "tts")]
+mod tts;
 mod url;
+#[cfg(feature = "decoders")]
+mod url_source;
@@ -0,0 +1,122 @@
+use std::io;
+
+use crate::audio::AudioFormat;
+use crate::audio::loudness::{GainMode, ReplayGain, db_to_linear};
+use crate::streaming::{AudioSource, NormalizingSource, SliceSource, measure_loudness};
+use crate::types::TrackInfo;
+
+/// Returns at most `chunk` bytes per read, splitting samples
+struct Trickle {
+    inner: SliceSource,
+    chunk: usize,
+}
+
+impl AudioSource for Trickle {
+    fn format(&self) -> AudioFormat {
+        self.inner.format()
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let len = buffer.len().min(self.chunk);
+        self.inner.read(&mut buffer[..len])
+    }
+}
+
+fn read_all(source: &mut impl AudioSource) -> Vec<i16> {
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 1000];
+    loop {
+        let n = source.read(&mut buffer).unwrap();
+        if n == 0 {
+            break;
+        }
+        assert_eq!(n % 2, 0, "read split a sample");
+        bytes.extend_from_slice(&buffer[..n]);
+    }
+    bytes
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+#[test]
+fn test_gain_applied_across_split_reads() {
+    let samples: Vec<i16> = (0..2000).map(|i| (i % 200 - 100) * 100).collect();
+    let inner = Trickle {
+        inner: SliceSource::from_i16(&samples, AudioFormat::CD_QUALITY),
+        chunk: 7,
+    };
+    let mut source = NormalizingSource::new(inner, 0.5);
+    let output = read_all(&mut source);
+
+    assert_eq!(output.len(), samples.len());
+    for (out, original) in output.iter().zip(&samples) {
+        assert_eq!(*out, original / 2);
+    }
+}
+
+#[test]
+fn test_track_metadata_gain() {
+    let samples = vec![10000i16; 64];
+    let track =
+        TrackInfo::new("file:///a.flac", "A", "B").with_replay_gain(ReplayGain::track(-6.020_6));
+    let mut source = NormalizingSource::for_track(
+        SliceSource::from_i16(&samples, AudioFormat::CD_QUALITY),
+        &track,
+        GainMode::Track,
+    );
+    assert!(read_all(&mut source).iter().all(|&s| s == 5000));
+
+    // Without values the track plays unchanged
+    let track = TrackInfo::new("file:///b.flac", "B", "C");
+    let source = NormalizingSource::for_track(
+        SliceSource::from_i16(&samples, AudioFormat::CD_QUALITY),
+        &track,
+        GainMode::Track,
+    );
+    assert!((source.gain() - 1.0).abs() < f32::EPSILON);
+}
+
+#[test]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    reason = "Test signal is in range"
+)]
+fn test_two_pass_levels_mixed_sources() {
+    let tone = |amplitude: f32| -> Vec<i16> {
+        (0..44100 * 4)
+            .flat_map(|i| {
+                let t = i as f32 / 44100.0;
+                let s =
+                    (amplitude * 32767.0 * (2.0 * std::f32::consts::PI * 1000.0 * t).sin()) as i16;
+                [s, s]
+            })
+            .collect()
+    };
+
+    let mut levels = Vec::new();
+    for amplitude in [db_to_linear(-30.0), db_to_linear(-12.0)] {
+        let samples = tone(amplitude);
+        let mut first = SliceSource::from_i16(&samples, AudioFormat::CD_QUALITY);
+        let loudness = measure_loudness(&mut first).unwrap();
+        let gain = ReplayGain::from_loudness(&loudness).unwrap();
+        let mut second = NormalizingSource::with_replay_gain(
+            SliceSource::from_i16(&samples, AudioFormat::CD_QUALITY),
+            &gain,
+            GainMode::Track,
+        );
+        let normalized = read_all(&mut second);
+        let mut check = SliceSource::from_i16(&normalized, AudioFormat::CD_QUALITY);
+        levels.push(
+            measure_loudness(&mut check)
+                .unwrap()
+                .integrated_lufs
+                .unwrap(),
+        );
+    }
+
+    for lufs in &levels {
+        assert!((lufs + 18.0).abs() < 0.2, "{lufs:.2} LUFS");
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::{Duration, Instant};
+
+use crate::streaming::Pacing;
+use crate::streaming::pacer::{Deadlines, Pacer};
+
+#[test]
+fn test_deadlines_do_not_drift() {
+    let deadlines = Deadlines::new(44100, Duration::ZERO);
+    let start = deadlines.at(0);
+
+    // A 352-frame packet is 7.98 ms; an hour of them lands exactly on the hour
+    assert_eq!(deadlines.at(352) - start, Duration::from_nanos(7_981_859));
+    assert_eq!(
+        deadlines.at(44100 * 3600) - start,
+        Duration::from_secs(3600)
+    );
+}
+
+#[test]
+fn test_deadlines_lead() {
+    let deadlines = Deadlines::new(48000, Duration::from_millis(500));
+
+    // Audio within the lead is already due
+    assert!(deadlines.at(48000 / 4) <= Instant::now());
+    assert!(deadlines.at(48000) > Instant::now());
+    assert_eq!(
+        deadlines.at(48000) - deadlines.at(0),
+        Duration::from_secs(1)
+    );
+}
+
+#[tokio::test]
+async fn test_timer_pacer_waits_for_rtp_time() {
+    let rtp_start = u32::MAX - 1000;
+    let mut pacer = Pacer::start(
+        Pacing::Timer,
+        Deadlines::new(44100, Duration::ZERO),
+        rtp_start,
+        352,
+    );
+
+    let started = Instant::now();
+    pacer.tick(rtp_start).await;
+    assert!(started.elapsed() < Duration::from_millis(5));
+
+    // Deadlines follow RTP time across the 32-bit wrap
+    pacer.tick(rtp_start.wrapping_add(441 * 3)).await;
+    assert!(started.elapsed() >= Duration::from_millis(30));
+}
+
+#[tokio::test]
+async fn test_raising_lead_releases_packets() {
+    let mut pacer = Pacer::start(Pacing::Timer, Deadlines::new(44100, Duration::ZERO), 0, 352);
+    pacer.set_lead(Duration::from_millis(500));
+
+    // A quarter of a second ahead is now due
+    let started = Instant::now();
+    pacer.tick(44100 / 4).await;
+    assert!(started.elapsed() < Duration::from_millis(50));
+}
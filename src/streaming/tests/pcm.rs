@@ -282,3 +282,301 @@ async fn test_resampling_integration() {
     // We can't easily verify the content is resampled without decoding,
     // but we verify it ran without error and produced output.
 }
+
+#[tokio::test]
+async fn test_queued_announcement_plays_after_source() {
+    use crate::audio::SoftGain;
+    use crate::streaming::{AnnouncementChannel, PreemptionPolicy, StreamPriority};
+
+    let sender = Arc::new(MockRtpSender::default());
+    let packets = sender.packets.clone();
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = PcmStreamer::new(sender, format, 44100);
+
+    let announcements = Arc::new(AnnouncementChannel::new(Arc::new(SoftGain::new())));
+    // 0x0101 reads the same in either byte order
+    let clip = SliceSource::from_i16(&[0x0101; 352 * 2 * 3], format);
+    announcements.play_with_priority(
+        Box::new(clip),
+        StreamPriority::Announcement,
+        PreemptionPolicy::Queue,
+    );
+    streamer.set_announcements(announcements.clone()).await;
+
+    streamer
+        .stream(SliceSource::new(vec![0u8; 1408 * 2], format))
+        .await
+        .unwrap();
+
+    assert_eq!(streamer.state().await, StreamerState::Finished);
+    assert!(!announcements.is_active());
+    let sent = packets.lock().unwrap();
+    assert!(sent.len() >= 5, "sent {} packets", sent.len());
+    assert!(sent.last().unwrap()[12..].iter().all(|&b| b == 1));
+}
+
+#[tokio::test]
+async fn test_stream_metrics_count_packets() {
+    let sender = Arc::new(MockRtpSender::default());
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = PcmStreamer::new(sender, format, 44100);
+
+    // Exactly ten packets of audio
+    let source = SliceSource::new(vec![1u8; 352 * 4 * 10], format);
+    streamer.stream(source).await.unwrap();
+
+    let metrics = streamer.metrics();
+    assert_eq!(metrics.packets_sent, 10);
+    assert_eq!(metrics.frames_sent, 3520);
+    assert_eq!(metrics.underruns, 0);
+    assert_eq!(metrics.encode_time.count(), 10);
+    assert_eq!(metrics.send_jitter.count(), 9);
+    assert_eq!(metrics.min_queue_depth, Some(0));
+}
+
+#[tokio::test]
+async fn test_thread_pacing_streams_every_packet() {
+    use crate::streaming::Pacing;
+
+    let sender = Arc::new(MockRtpSender::default());
+    let packets = sender.packets.clone();
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = PcmStreamer::new(sender, format, 44100);
+    streamer.set_pacing(Pacing::Thread).await;
+
+    let source = SliceSource::new(vec![1u8; 352 * 4 * 10], format);
+    let started = std::time::Instant::now();
+    streamer.stream(source).await.unwrap();
+
+    assert_eq!(packets.lock().unwrap().len(), 10);
+    // Ten packets of 8 ms each are paced, not sent at once
+    assert!(started.elapsed() >= Duration::from_millis(70));
+    assert_eq!(streamer.state().await, StreamerState::Finished);
+}
+
+#[tokio::test]
+async fn test_seek_to_packet_reports_next_packet() {
+    let sender = Arc::new(MockRtpSender::default());
+    let packets = sender.packets.clone();
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = Arc::new(PcmStreamer::new(sender, format, 44100));
+
+    let source = SliceSource::new(vec![0u8; 352 * 4 * 1000], format);
+    let streamer_task = streamer.clone();
+    let handle = tokio::spawn(async move { streamer_task.stream(source).await });
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    streamer.pause().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    // Applied while paused
+    let (seq, timestamp) = streamer
+        .seek_to_packet(Duration::from_secs(1))
+        .await
+        .unwrap();
+    let sent = packets.lock().unwrap().len();
+    assert_eq!(usize::from(seq), sent);
+    assert_eq!(timestamp, u32::try_from(sent * 352).unwrap());
+    assert_eq!(streamer.frames_per_packet().await, 352);
+
+    streamer.resume().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(30)).await;
+    let first_after = packets.lock().unwrap()[sent].clone();
+    assert_eq!(u16::from_be_bytes([first_after[2], first_after[3]]), seq);
+
+    streamer.stop().await.unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_send_lead_sends_ahead() {
+    let sender = Arc::new(MockRtpSender::default());
+    let packets = sender.packets.clone();
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = Arc::new(PcmStreamer::new(sender, format, 44100));
+    streamer.set_send_lead(Duration::from_millis(80)).await;
+
+    let source = SliceSource::new(vec![1u8; 352 * 4 * 100], format);
+    let streamer_task = streamer.clone();
+    let handle = tokio::spawn(async move { streamer_task.stream(source).await });
+
+    // The first 80 ms (ten packets) go out at once
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(packets.lock().unwrap().len() >= 10);
+
+    streamer.stop().await.unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_seek_to_packet_requires_seekable_source() {
+    let sender = Arc::new(MockRtpSender::default());
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = Arc::new(PcmStreamer::new(sender, format, 44100));
+
+    let source = crate::streaming::SilenceSource::new(format);
+    let streamer_task = streamer.clone();
+    let handle = tokio::spawn(async move { streamer_task.stream(source).await });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let result = streamer.seek_to_packet(Duration::from_secs(1)).await;
+    assert!(matches!(
+        result,
+        Err(AirPlayError::InvalidState { ref message, .. }) if message.contains("seekable")
+    ));
+
+    streamer.stop().await.unwrap();
+    handle.await.unwrap().unwrap();
+}
+
+/// Encoder tagging each packet with its frame count
+struct TaggingEncoder;
+
+impl crate::audio::AudioEncoder for TaggingEncoder {
+    fn frames_per_packet(&self) -> usize {
+        100
+    }
+
+    fn encode_packet(
+        &mut self,
+        pcm: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), crate::audio::AudioEncodeError> {
+        out.clear();
+        out.push(0xE0);
+        out.extend_from_slice(&u16::try_from(pcm.len() / 4).unwrap().to_be_bytes());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<Vec<Vec<u8>>, crate::audio::AudioEncodeError> {
+        Ok(vec![vec![0xEF]])
+    }
+}
+
+#[tokio::test]
+async fn test_custom_encoder_packets() {
+    let sender = Arc::new(MockRtpSender::default());
+    let packets = sender.packets.clone();
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = PcmStreamer::new(sender, format, 44100);
+    streamer
+        .use_encoder(crate::audio::AudioCodec::Alac, TaggingEncoder)
+        .await;
+
+    // Three packets of 100 frames
+    let source = SliceSource::new(vec![1u8; 300 * 4], format);
+    streamer.stream(source).await.unwrap();
+    assert_eq!(streamer.frames_per_packet().await, 100);
+
+    let sent = packets.lock().unwrap();
+    let payloads: Vec<&[u8]> = sent.iter().map(|p| &p[12..]).collect();
+    assert_eq!(
+        payloads,
+        [
+            &[0xE0, 0, 100][..],
+            &[0xE0, 0, 100],
+            &[0xE0, 0, 100],
+            &[0xEF]
+        ]
+    );
+    // Timestamps advance by the encoder's packet size
+    let timestamps: Vec<u32> = sent
+        .iter()
+        .map(|p| u32::from_be_bytes([p[4], p[5], p[6], p[7]]))
+        .collect();
+    assert_eq!(timestamps, [0, 100, 200, 300]);
+}
+
+/// Encoder failing on its second packet
+#[derive(Default)]
+struct FlakyEncoder {
+    packets: usize,
+}
+
+impl crate::audio::AudioEncoder for FlakyEncoder {
+    fn frames_per_packet(&self) -> usize {
+        100
+    }
+
+    fn encode_packet(
+        &mut self,
+        _pcm: &[u8],
+        out: &mut Vec<u8>,
+    ) -> Result<(), crate::audio::AudioEncodeError> {
+        self.packets += 1;
+        if self.packets == 2 {
+            return Err(crate::audio::AudioEncodeError::Encoding("flaky".into()));
+        }
+        out.clear();
+        out.push(0xE0);
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_encode_failure_drops_packet() {
+    let sender = Arc::new(MockRtpSender::default());
+    let packets = sender.packets.clone();
+    let format = AudioFormat::CD_QUALITY;
+    let streamer = PcmStreamer::new(sender, format, 44100);
+    streamer
+        .use_encoder(crate::audio::AudioCodec::Alac, FlakyEncoder::default())
+        .await;
+
+    let source = SliceSource::new(vec![1u8; 300 * 4], format);
+    streamer.stream(source).await.unwrap();
+
+    // No PCM goes out in place of the failed packet, and its slot is skipped
+    let sent = packets.lock().unwrap();
+    let payloads: Vec<&[u8]> = sent.iter().map(|p| &p[12..]).collect();
+    assert_eq!(payloads, [&[0xE0][..], &[0xE0]]);
+    let sequences: Vec<u16> = sent
+        .iter()
+        .map(|p| u16::from_be_bytes([p[2], p[3]]))
+        .collect();
+    assert_eq!(sequences, [0, 2]);
+    let timestamps: Vec<u32> = sent
+        .iter()
+        .map(|p| u32::from_be_bytes([p[4], p[5], p[6], p[7]]))
+        .collect();
+    assert_eq!(timestamps, [0, 200]);
+}
+
+#[cfg(any(feature = "aac-fdk", feature = "aac-rs"))]
+#[tokio::test]
+async fn test_aac_backend_reported() {
+    use crate::audio::AacBackend;
+
+    let streamer = PcmStreamer::new(
+        Arc::new(MockRtpSender::default()),
+        AudioFormat::CD_QUALITY,
+        44100,
+    );
+    assert_eq!(streamer.aac_backend().await, None);
+
+    streamer.use_aac(64000).await.unwrap();
+    assert_eq!(streamer.aac_backend().await, Some(AacBackend::AVAILABLE[0]));
+
+    streamer.use_alac().await;
+    assert_eq!(streamer.aac_backend().await, None);
+}
+
+#[cfg(not(feature = "aac-fdk"))]
+#[tokio::test]
+async fn test_aac_eld_without_encoder_is_an_error() {
+    let streamer = PcmStreamer::new(
+        Arc::new(MockRtpSender::default()),
+        AudioFormat::CD_QUALITY,
+        44100,
+    );
+
+    let result = streamer.use_aac_eld(64000).await;
+    assert!(matches!(
+        result,
+        Err(AirPlayError::CodecError {
+            source: Some(_),
+            ..
+        })
+    ));
+    assert_eq!(streamer.aac_backend().await, None);
+}
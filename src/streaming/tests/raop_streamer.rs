@@ -54,7 +54,7 @@ fn test_retransmit_buffer() {
     let retransmits = streamer.handle_retransmit(0, 2);
     assert_eq!(retransmits.len(), 2);
     // Check RTP header sequence numbers in retransmit packets
-    // Retransmit packet format: [Header 4 bytes] [Original RTP Header minus 4 bytes]
+    // Retransmit packet format: [Header 4 bytes] [Original RTP packet]
     // Header: 0x80 0xD6 Seq(2)
     // Sequence number is at offset 2 and 3.
     assert_eq!(retransmits[0][2], 0); // seq 0 high
@@ -64,6 +64,60 @@ fn test_retransmit_buffer() {
     assert_eq!(retransmits[1][3], 1); // seq 1 low
 }
 
+#[test]
+fn test_control_packet_retransmit() {
+    use crate::protocol::rtp::raop::RetransmitRequest;
+
+    let keys = create_test_keys();
+    let mut streamer = RaopStreamer::new(&keys, RaopStreamConfig::default());
+    let frame = vec![0u8; 352 * 4];
+    let sent: Vec<_> = (0..4).map(|_| streamer.encode_frame(&frame)).collect();
+
+    let request = RetransmitRequest {
+        seq_start: 1,
+        count: 2,
+    }
+    .encode(0);
+    let resent = streamer.handle_control_packet(&request);
+
+    assert_eq!(resent.len(), 2);
+    for (packet, original) in resent.iter().zip(&sent[1..3]) {
+        assert_eq!(&packet[..2], &[0x80, 0xD6]);
+        assert_eq!(&packet[2..4], &original[2..4]);
+        assert_eq!(&packet[4..], &original[..]);
+    }
+
+    // Other control packets are ignored
+    let sync = streamer.create_sync_packet();
+    assert!(streamer.handle_control_packet(&sync).is_empty());
+}
+
+#[test]
+fn test_timing_responder_answers_requests() {
+    use crate::protocol::rtp::raop_timing::RaopTimingRequest;
+    use crate::protocol::rtp::{NtpTimestamp, TimingPacket};
+    use crate::streaming::TimingResponder;
+
+    let request = RaopTimingRequest::new();
+    let response =
+        TimingResponder::respond(&request.encode(3), NtpTimestamp::now()).expect("no response");
+
+    assert_eq!(&response[2..4], &3u16.to_be_bytes());
+    let Ok(TimingPacket::Response(response)) = TimingPacket::decode_raop(&response) else {
+        panic!("expected a timing response");
+    };
+    assert_eq!(
+        response.reference_time.encode(),
+        request.reference_time.encode()
+    );
+
+    // Responses and other packets are not answered
+    let keys = create_test_keys();
+    let mut streamer = RaopStreamer::new(&keys, RaopStreamConfig::default());
+    let sync = streamer.create_sync_packet();
+    assert!(TimingResponder::respond(&sync, NtpTimestamp::now()).is_none());
+}
+
 #[test]
 fn test_sync_packet() {
     let keys = create_test_keys();
@@ -106,9 +160,8 @@ fn test_timing_packet_generation_interval() {
     let config = RaopStreamConfig::default();
     let mut streamer = RaopStreamer::new(&keys, config);
 
-    // Initially false (because initialized with now())
-    // Unless test runs extremely slow or system clock jumps.
-    assert!(!streamer.should_send_sync());
+    // The first sync is due straight away; timing waits for its interval
+    assert!(streamer.should_send_sync());
     assert!(!streamer.should_send_timing());
 
     let _sync = streamer.create_sync_packet();
@@ -118,3 +171,51 @@ fn test_timing_packet_generation_interval() {
     assert!(!streamer.should_send_sync());
     assert!(!streamer.should_send_timing());
 }
+
+#[test]
+fn test_sync_packets_follow_stream_position() {
+    use crate::protocol::rtp::raop::SyncPacket;
+
+    let keys = create_test_keys();
+    let config = RaopStreamConfig {
+        latency: 11025,
+        ..RaopStreamConfig::default()
+    };
+    let mut streamer = RaopStreamer::new(&keys, config);
+    for _ in 0..100 {
+        streamer.encode_frame(&[0u8; 16]);
+    }
+
+    let before = streamer.clock().now().to_micros();
+    let first = SyncPacket::decode(&streamer.create_sync_packet()).unwrap();
+    let after = streamer.clock().now().to_micros();
+    assert!(first.extension);
+    assert_eq!(first.next_timestamp, 35200);
+    assert_eq!(first.rtp_timestamp, 35200 - 11025);
+    assert!((before..=after).contains(&first.ntp_time.to_micros()));
+
+    let second = SyncPacket::decode(&streamer.create_sync_packet()).unwrap();
+    assert!(!second.extension);
+
+    streamer.flush();
+    assert!(streamer.should_send_sync());
+    let after_flush = SyncPacket::decode(&streamer.create_sync_packet()).unwrap();
+    assert!(after_flush.extension);
+}
+
+#[test]
+fn test_ntp_clock_is_monotonic() {
+    use std::time::{Duration, Instant};
+
+    use crate::streaming::NtpClock;
+
+    let clock = NtpClock::new();
+    let start = Instant::now();
+    let later = clock.at(start + Duration::from_millis(1500)).to_micros();
+    let now = clock.at(start).to_micros();
+    assert_eq!(later - now, 1_500_000);
+
+    // Instants before the clock started read as its start
+    let earlier = clock.at(start.checked_sub(Duration::from_secs(1)).unwrap());
+    assert!(earlier.to_micros() <= now);
+}
@@ -1,5 +1,35 @@
+use std::collections::VecDeque;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use tokio::io::{AsyncRead, ReadBuf};
+
 use crate::audio::AudioFormat;
-use crate::streaming::{AudioSource, CallbackSource, SilenceSource, SliceSource};
+use crate::streaming::{
+    AudioSource, CalibrationTone, CallbackSource, ReaderSource, SilenceSource, SliceSource,
+};
+
+/// Reader replaying a script of chunks; `None` is a poll with nothing ready
+struct ScriptedReader(VecDeque<Option<io::Result<Vec<u8>>>>);
+
+impl AsyncRead for ScriptedReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        match self.0.pop_front() {
+            None => Poll::Ready(Ok(())),
+            Some(None) => Poll::Pending,
+            Some(Some(Err(e))) => Poll::Ready(Err(e)),
+            Some(Some(Ok(chunk))) => {
+                buf.put_slice(&chunk);
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+}
 
 #[test]
 fn test_slice_source() {
@@ -47,3 +77,86 @@ fn test_callback_source() {
     source.read(&mut buffer).unwrap();
     assert_eq!(buffer, vec![2, 2, 2, 2]);
 }
+
+#[test]
+fn test_calibration_tone_level() {
+    let mut source = CalibrationTone::new();
+    // Ten seconds of audio
+    let mut buffer = vec![0u8; 44_100 * 4 * 10];
+    assert_eq!(source.read(&mut buffer).unwrap(), buffer.len());
+
+    let samples: Vec<f64> = buffer
+        .chunks_exact(4)
+        .map(|frame| {
+            let left = i16::from_le_bytes([frame[0], frame[1]]);
+            let right = i16::from_le_bytes([frame[2], frame[3]]);
+            assert_eq!(left, right);
+            f64::from(left) / f64::from(i16::MAX)
+        })
+        .collect();
+    #[allow(clippy::cast_precision_loss, reason = "Test sample count")]
+    let rms = (samples.iter().map(|s| s * s).sum::<f64>() / samples.len() as f64).sqrt();
+    let dbfs = 20.0 * rms.log10();
+    assert!((dbfs + 20.0).abs() < 1.5, "level {dbfs:.1} dBFS");
+    assert!(samples.iter().all(|s| s.abs() < 1.0));
+}
+
+#[test]
+fn test_calibration_tone_duration() {
+    let mut source = CalibrationTone::new().with_duration(std::time::Duration::from_millis(100));
+    assert_eq!(
+        source.duration(),
+        Some(std::time::Duration::from_millis(100))
+    );
+
+    let mut buffer = vec![0u8; 44_100 * 4];
+    assert_eq!(source.read(&mut buffer).unwrap(), 4_410 * 4);
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+    assert_eq!(source.position(), std::time::Duration::from_millis(100));
+}
+
+#[test]
+fn test_reader_source_holds_partial_frames() {
+    let reader = ScriptedReader(VecDeque::from([
+        Some(Ok(vec![1, 2, 3, 4, 5, 6])),
+        None,
+        Some(Ok(vec![7, 8, 9])),
+        Some(Err(io::Error::other("broken pipe"))),
+        Some(Ok(vec![10, 11])),
+    ]));
+    let mut source = ReaderSource::new(reader, AudioFormat::CD_QUALITY);
+    let mut buffer = [0xff_u8; 16];
+
+    // One whole frame; the other two bytes wait for the rest of their frame
+    assert_eq!(source.read(&mut buffer).unwrap(), 4);
+    assert_eq!(buffer[..4], [1, 2, 3, 4]);
+
+    // The held bytes are completed by the next chunk, then an error ends
+    // the read; nothing is lost
+    assert!(source.read(&mut buffer).is_err());
+    assert_eq!(source.read(&mut buffer).unwrap(), 4);
+    assert_eq!(buffer[..4], [5, 6, 7, 8]);
+
+    // The three bytes left at end of input were dropped
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+    assert_eq!(
+        source.position(),
+        AudioFormat::CD_QUALITY.frames_to_duration(2)
+    );
+}
+
+#[test]
+fn test_reader_source_silence_while_pending() {
+    let reader = ScriptedReader(VecDeque::from([None, Some(Ok(vec![1, 2, 3, 4]))]));
+    let mut source = ReaderSource::new(reader, AudioFormat::CD_QUALITY);
+    let mut buffer = [0xff_u8; 10];
+
+    // Nothing ready: whole frames of silence
+    assert_eq!(source.read(&mut buffer).unwrap(), 8);
+    assert!(buffer[..8].iter().all(|&b| b == 0));
+
+    assert_eq!(source.read(&mut buffer).unwrap(), 4);
+    assert_eq!(buffer[..4], [1, 2, 3, 4]);
+    assert_eq!(source.read(&mut buffer).unwrap(), 0);
+}
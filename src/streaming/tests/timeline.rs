@@ -0,0 +1,116 @@
+use std::time::Duration;
+
+use crate::protocol::ptp::timestamp::PtpTimestamp;
+use crate::streaming::Timeline;
+
+fn anchored(rtp_time: u32, position: Duration, network_time: PtpTimestamp) -> Timeline {
+    let mut timeline = Timeline::new(44100);
+    timeline.anchor(rtp_time, position, Some(network_time));
+    timeline
+}
+
+#[test]
+fn test_new_timeline_is_paused_at_zero() {
+    let timeline = Timeline::new(48000);
+    assert_eq!(timeline.sample_rate(), 48000);
+    assert_eq!(timeline.anchor_rtp_time(), 0);
+    assert!(!timeline.is_playing());
+    assert_eq!(timeline.position(), Duration::ZERO);
+    assert!(timeline.anchor_network_time().is_none());
+    assert!(timeline.network_time_at_rtp(1000).is_none());
+}
+
+#[test]
+fn test_position_rtp_roundtrip() {
+    let timeline = anchored(1000, Duration::from_secs(10), PtpTimestamp::new(100, 0));
+
+    assert_eq!(timeline.rtp_time_at(Duration::from_secs(11)), 1000 + 44100);
+    assert_eq!(
+        timeline.rtp_time_at(Duration::from_secs(9)),
+        1000u32.wrapping_sub(44100)
+    );
+    assert_eq!(
+        timeline.position_at_rtp(1000 + 44100 * 2),
+        Duration::from_secs(12)
+    );
+    assert_eq!(
+        timeline.position_at_rtp(timeline.rtp_time_at(Duration::from_millis(9500))),
+        Duration::from_millis(9500)
+    );
+    // Before position zero clamps to zero
+    assert_eq!(
+        timeline.position_at_rtp(1000u32.wrapping_sub(44100 * 20)),
+        Duration::ZERO
+    );
+}
+
+#[test]
+fn test_rtp_network_time_roundtrip() {
+    let timeline = anchored(u32::MAX - 100, Duration::ZERO, PtpTimestamp::new(100, 0));
+
+    // Across the 32-bit wrap
+    let rtp = (u32::MAX - 100).wrapping_add(44100);
+    assert_eq!(
+        timeline.network_time_at_rtp(rtp),
+        Some(PtpTimestamp::new(101, 0))
+    );
+    assert_eq!(
+        timeline.rtp_time_at_network_time(PtpTimestamp::new(101, 0)),
+        Some(rtp)
+    );
+    assert_eq!(
+        timeline.network_time_at_rtp(u32::MAX - 100 - 22050),
+        Some(PtpTimestamp::new(99, 500_000_000))
+    );
+}
+
+#[test]
+fn test_position_at_network_time_follows_rate() {
+    let mut timeline = anchored(0, Duration::from_secs(5), PtpTimestamp::new(100, 0));
+    assert_eq!(
+        timeline.position_at_network_time(PtpTimestamp::new(102, 0)),
+        Duration::from_secs(5)
+    );
+
+    timeline.set_rate(1.0);
+    let later = timeline
+        .anchor_network_time()
+        .unwrap()
+        .add_duration(Duration::from_secs(2));
+    let position = timeline.position_at_network_time(later);
+    assert!(position >= Duration::from_secs(7));
+    assert!(position < Duration::from_millis(7100));
+}
+
+#[test]
+fn test_seek_keeps_rtp_stream() {
+    let mut timeline = anchored(5000, Duration::ZERO, PtpTimestamp::new(100, 0));
+    timeline.seek(Duration::from_secs(60));
+
+    assert_eq!(timeline.anchor_position(), Duration::from_secs(60));
+    assert_eq!(timeline.anchor_rtp_time(), 5000);
+    assert_eq!(timeline.rtp_time_at(Duration::from_secs(61)), 5000 + 44100);
+    assert_eq!(
+        timeline.anchor_network_time(),
+        Some(PtpTimestamp::new(100, 0))
+    );
+}
+
+#[test]
+fn test_delayed_shifts_network_time_only() {
+    let timeline = anchored(0, Duration::ZERO, PtpTimestamp::new(100, 0));
+    let delayed = timeline.delayed(Duration::from_millis(250));
+
+    assert_eq!(delayed.rtp_time_at(Duration::from_secs(1)), 44100);
+    assert_eq!(
+        delayed.network_time_at_rtp(44100),
+        Some(PtpTimestamp::new(101, 250_000_000))
+    );
+}
+
+#[test]
+fn test_sample_conversions() {
+    let timeline = Timeline::new(44100);
+    assert_eq!(timeline.samples_for(Duration::from_millis(500)), 22050);
+    assert_eq!(timeline.duration_for(88200), Duration::from_secs(2));
+}
@@ -0,0 +1,95 @@
+use crate::audio::{ChannelConfig, SampleFormat, SampleRate};
+use crate::streaming::AudioSource;
+use crate::streaming::tts::wav_to_source;
+
+fn wav(channels: u16, rate: u32, bits: u16, samples: &[i16], data_size: Option<u32>) -> Vec<u8> {
+    let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+    let block_align = channels * bits / 8;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    // An odd-sized chunk before fmt exercises padding
+    out.extend_from_slice(b"LIST");
+    out.extend_from_slice(&3u32.to_le_bytes());
+    out.extend_from_slice(&[1, 2, 3, 0]);
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&rate.to_le_bytes());
+    out.extend_from_slice(&(rate * u32::from(block_align)).to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&bits.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(
+        &data_size
+            .unwrap_or_else(|| u32::try_from(data.len()).unwrap())
+            .to_le_bytes(),
+    );
+    out.extend_from_slice(&data);
+    out
+}
+
+fn read_all(mut source: impl AudioSource) -> Vec<i16> {
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 256];
+    loop {
+        let n = source.read(&mut buffer).unwrap();
+        if n == 0 {
+            break;
+        }
+        bytes.extend_from_slice(&buffer[..n]);
+    }
+    bytes
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]))
+        .collect()
+}
+
+#[test]
+fn test_stereo_44k_passes_through() {
+    let source = wav_to_source(&wav(2, 44_100, 16, &[100, -100, 200, -200], None)).unwrap();
+
+    let format = source.format();
+    assert_eq!(format.sample_rate, SampleRate::Hz44100);
+    assert_eq!(format.channels, ChannelConfig::Stereo);
+    assert_eq!(format.sample_format, SampleFormat::I16);
+    assert_eq!(read_all(source), vec![100, -100, 200, -200]);
+}
+
+#[test]
+fn test_mono_is_upmixed() {
+    let source = wav_to_source(&wav(1, 44_100, 16, &[1000, -1000], None)).unwrap();
+
+    let samples = read_all(source);
+    assert_eq!(samples.len(), 4);
+    assert_eq!(samples[0], samples[1]);
+    assert_eq!(samples[2], samples[3]);
+    assert!(samples[0] > 0 && samples[2] < 0);
+}
+
+#[test]
+fn test_low_rate_is_resampled() {
+    // espeak-ng produces 22.05 kHz mono
+    let input = vec![500i16; 2205];
+    let source = wav_to_source(&wav(1, 22_050, 16, &input, None)).unwrap();
+
+    let frames = read_all(source).len() / 2;
+    assert!((4400..=4420).contains(&frames), "got {frames} frames");
+}
+
+#[test]
+fn test_streamed_data_size_is_clamped() {
+    // Engines writing to a pipe leave the data size at its maximum
+    let source = wav_to_source(&wav(2, 44_100, 16, &[1, 2, 3, 4], Some(u32::MAX))).unwrap();
+    assert_eq!(read_all(source), vec![1, 2, 3, 4]);
+}
+
+#[test]
+fn test_rejects_unsupported_input() {
+    assert!(wav_to_source(b"not a wav file").is_err());
+    assert!(wav_to_source(&wav(2, 44_100, 8, &[0, 0], None)).is_err());
+    assert!(wav_to_source(&wav(6, 44_100, 16, &[0; 6], None)).is_err());
+}
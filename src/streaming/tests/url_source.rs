@@ -0,0 +1,126 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::http::wav;
+use super::http_server;
+use crate::streaming::AudioSource;
+use crate::streaming::http::HttpUrl;
+use crate::streaming::url_source::{HttpFileSource, RangeReader};
+
+/// Serve `file` to any number of requests, honouring `Range` if `ranges`
+///
+/// Returns the base URL and the first byte each request asked for.
+fn serve(file: Vec<u8>, ranges: bool) -> (String, Arc<Mutex<Vec<u64>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let log = requests.clone();
+    let url = http_server::serve(move |request| {
+        let start: u64 = request
+            .header("Range")
+            .and_then(|range| range.strip_prefix("bytes="))
+            .map_or(0, |range| range.trim_end_matches('-').parse().unwrap());
+        log.lock().unwrap().push(start);
+
+        if ranges {
+            let mut response = format!(
+                "HTTP/1.1 206 Partial Content\r\nContent-Range: bytes {start}-{}/{}\r\n\
+                 Content-Length: {}\r\n\r\n",
+                file.len() - 1,
+                file.len(),
+                file.len() - usize::try_from(start).unwrap()
+            )
+            .into_bytes();
+            response.extend_from_slice(&file[usize::try_from(start).unwrap()..]);
+            response
+        } else {
+            let mut response =
+                format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\n\r\n", file.len()).into_bytes();
+            response.extend_from_slice(&file);
+            response
+        }
+    });
+    (url, requests)
+}
+
+/// Read samples until `count` have come out after the leading silence
+fn read_samples(source: &mut HttpFileSource, count: usize) -> Vec<i16> {
+    let mut out: Vec<i16> = Vec::new();
+    let mut buf = [0u8; 1024];
+    while out.len() < count {
+        let n = source.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        out.extend(
+            buf[..n]
+                .chunks_exact(2)
+                .map(|b| i16::from_le_bytes([b[0], b[1]])),
+        );
+        if out.iter().all(|&sample| sample == 0) {
+            out.clear();
+        }
+    }
+    out
+}
+
+#[test]
+fn test_http_file_source_plays_file() {
+    let (url, requests) = serve(wav(5000), true);
+
+    let mut source = HttpFileSource::open(&format!("{url}/song.wav")).unwrap();
+    assert!(source.is_seekable());
+    assert_eq!(source.duration(), Some(Duration::from_nanos(113_378_685)));
+
+    let samples = read_samples(&mut source, usize::MAX);
+    assert_eq!(samples.len(), 5000 * 2);
+    assert_eq!(&samples[2..6], [1, -1, 2, -2]);
+    assert_eq!(requests.lock().unwrap()[0], 0);
+}
+
+#[test]
+fn test_http_file_source_seeks_with_range_request() {
+    let file = wav(20_000);
+    let (url, requests) = serve(file, true);
+    let mut source =
+        HttpFileSource::with_buffer_ahead(&format!("{url}/song.wav"), Duration::from_millis(50))
+            .unwrap();
+    read_samples(&mut source, 100);
+
+    source.seek(Duration::from_millis(400)).unwrap();
+    assert_eq!(source.position(), Duration::from_millis(400));
+    let samples = read_samples(&mut source, 4);
+    assert_eq!(&samples[..2], [17_640, -17_640]);
+
+    // The decoder asks for the packet holding the target, behind the 44
+    // byte header and before the target frame's 4 bytes
+    let requests = requests.lock().unwrap();
+    assert_eq!(requests.len(), 2);
+    assert!((45..=44 + 17_640 * 4).contains(&requests[1]));
+}
+
+#[test]
+fn test_http_file_source_without_range_support() {
+    let (url, _) = serve(wav(3000), false);
+
+    let mut source = HttpFileSource::open(&format!("{url}/song.wav")).unwrap();
+    assert!(!source.is_seekable());
+    assert_eq!(
+        source.seek(Duration::from_millis(10)).unwrap_err().kind(),
+        std::io::ErrorKind::Unsupported
+    );
+    assert_eq!(read_samples(&mut source, usize::MAX).len(), 3000 * 2);
+}
+
+#[test]
+fn test_range_reader_skips_when_server_ignores_range() {
+    let file: Vec<u8> = (0..=255).collect();
+    let (url, requests) = serve(file, false);
+    let mut reader = RangeReader::open(&HttpUrl::parse(&url).unwrap()).unwrap();
+
+    reader.seek(SeekFrom::Start(200)).unwrap();
+    let mut rest = Vec::new();
+    reader.read_to_end(&mut rest).unwrap();
+
+    assert_eq!(rest, (200..=255).collect::<Vec<u8>>());
+    assert_eq!(*requests.lock().unwrap(), [0, 200]);
+}
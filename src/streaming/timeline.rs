@@ -0,0 +1,274 @@
+//! Media timeline
+//!
+//! A stream runs on three clocks: the media position the listener hears, the
+//! RTP timestamp stamped on each packet, and the PTP network time at which a
+//! receiver renders a sample. `SETRATEANCHORTIME` ties the three together at
+//! one anchor point; every other conversion follows from the sample rate and
+//! the playback rate. [`Timeline`] holds that anchor so group members,
+//! progress reports, seeking and local monitoring all share one mapping.
+
+use std::time::{Duration, Instant};
+
+use crate::protocol::ptp::timestamp::PtpTimestamp;
+
+const NANOS_PER_SEC: i128 = 1_000_000_000;
+
+/// Mapping between media position, RTP time and PTP network time
+///
+/// RTP timestamps wrap at 32 bits; conversions treat any timestamp within
+/// 2^31 samples of the anchor as relative to it, so they stay valid across
+/// a wrap.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timeline {
+    sample_rate: u32,
+    /// RTP timestamp at the anchor
+    rtp_time: u32,
+    /// Media position at the anchor
+    position: Duration,
+    /// PTP network time at which the anchor sample is rendered
+    network_time: Option<PtpTimestamp>,
+    /// Local time the anchor was taken
+    anchored_at: Instant,
+    /// Playback rate (0.0 = paused, 1.0 = normal speed)
+    rate: f64,
+}
+
+impl Timeline {
+    /// Create a paused timeline with RTP time 0 at position 0
+    #[must_use]
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            rtp_time: 0,
+            position: Duration::ZERO,
+            network_time: None,
+            anchored_at: Instant::now(),
+            rate: 0.0,
+        }
+    }
+
+    /// Sample rate of the RTP clock
+    #[must_use]
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// RTP timestamp at the anchor
+    #[must_use]
+    pub fn anchor_rtp_time(&self) -> u32 {
+        self.rtp_time
+    }
+
+    /// Media position at the anchor
+    #[must_use]
+    pub fn anchor_position(&self) -> Duration {
+        self.position
+    }
+
+    /// PTP network time of the anchor, if one was sent
+    #[must_use]
+    pub fn anchor_network_time(&self) -> Option<PtpTimestamp> {
+        self.network_time
+    }
+
+    /// Playback rate
+    #[must_use]
+    pub fn rate(&self) -> f64 {
+        self.rate
+    }
+
+    /// Whether the timeline is advancing
+    #[must_use]
+    pub fn is_playing(&self) -> bool {
+        self.rate > 0.0
+    }
+
+    /// Re-anchor: `rtp_time` holds the sample at `position`, rendered at
+    /// `network_time`
+    pub fn anchor(
+        &mut self,
+        rtp_time: u32,
+        position: Duration,
+        network_time: Option<PtpTimestamp>,
+    ) {
+        self.rtp_time = rtp_time;
+        self.position = position;
+        self.network_time = network_time;
+        self.anchored_at = Instant::now();
+    }
+
+    /// Change the playback rate, keeping the current position
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rebase();
+        self.rate = rate;
+    }
+
+    /// Jump to a new media position
+    ///
+    /// The RTP stream carries on where it is; the sample playing now becomes
+    /// `position`.
+    pub fn seek(&mut self, position: Duration) {
+        self.rebase();
+        self.position = position;
+    }
+
+    /// The same timeline rendered `delay` later
+    ///
+    /// Used for group members with a latency trim: their anchor maps the
+    /// same samples to a later network time.
+    #[must_use]
+    pub fn delayed(&self, delay: Duration) -> Self {
+        Self {
+            network_time: self.network_time.map(|t| t.add_duration(delay)),
+            anchored_at: self.anchored_at + delay,
+            ..*self
+        }
+    }
+
+    /// Current media position, as estimated from local time
+    #[must_use]
+    pub fn position(&self) -> Duration {
+        let now = Instant::now();
+        if now < self.anchored_at {
+            // A delayed timeline has not reached its anchor yet
+            return self
+                .position
+                .saturating_sub(Self::scale(self.anchored_at.duration_since(now), self.rate));
+        }
+        self.position + Self::scale(now.duration_since(self.anchored_at), self.rate)
+    }
+
+    /// Media position being rendered at a PTP network time
+    ///
+    /// Falls back to [`position`](Self::position) before a network anchor
+    /// has been set.
+    #[must_use]
+    pub fn position_at_network_time(&self, network_time: PtpTimestamp) -> Duration {
+        let Some(anchor) = self.network_time else {
+            return self.position();
+        };
+        let elapsed = network_time.diff_nanos(&anchor);
+        Self::offset(self.position, Self::scale_nanos(elapsed, self.rate))
+    }
+
+    /// Number of RTP samples spanning a duration
+    #[must_use]
+    pub fn samples_for(&self, duration: Duration) -> u64 {
+        let samples = duration.as_nanos() * u128::from(self.sample_rate) / 1_000_000_000;
+        u64::try_from(samples).unwrap_or(u64::MAX)
+    }
+
+    /// RTP time to anchor in place of `rtp_time` to render it `delay` late
+    ///
+    /// Anchoring an earlier sample to the same network time delays output
+    /// just as anchoring this one to a later network time would.
+    #[must_use]
+    pub fn delayed_rtp_time(&self, rtp_time: u32, delay: Duration) -> u32 {
+        #[allow(clippy::cast_possible_truncation, reason = "RTP time wraps at 32 bits")]
+        rtp_time.wrapping_sub(self.samples_for(delay) as u32)
+    }
+
+    /// Duration of a number of RTP samples
+    #[must_use]
+    pub fn duration_for(&self, samples: u64) -> Duration {
+        let nanos = u128::from(samples) * 1_000_000_000 / u128::from(self.sample_rate.max(1));
+        Duration::from_nanos(u64::try_from(nanos).unwrap_or(u64::MAX))
+    }
+
+    /// RTP timestamp of the sample at a media position
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        reason = "RTP timestamps wrap at 32 bits"
+    )]
+    pub fn rtp_time_at(&self, position: Duration) -> u32 {
+        if position >= self.position {
+            let samples = self.samples_for(position.saturating_sub(self.position));
+            self.rtp_time.wrapping_add(samples as u32)
+        } else {
+            let samples = self.samples_for(self.position.saturating_sub(position));
+            self.rtp_time.wrapping_sub(samples as u32)
+        }
+    }
+
+    /// Media position of the sample with an RTP timestamp
+    ///
+    /// Timestamps before position zero map to zero.
+    #[must_use]
+    pub fn position_at_rtp(&self, rtp_time: u32) -> Duration {
+        Self::offset(self.position, self.rtp_offset_nanos(rtp_time))
+    }
+
+    /// PTP network time at which the sample with an RTP timestamp is rendered
+    ///
+    /// `None` before a network anchor has been set.
+    #[must_use]
+    pub fn network_time_at_rtp(&self, rtp_time: u32) -> Option<PtpTimestamp> {
+        let anchor = self.network_time?;
+        let nanos = anchor.to_nanos() + self.rtp_offset_nanos(rtp_time);
+        Some(PtpTimestamp::from_nanos(nanos.max(0)))
+    }
+
+    /// RTP timestamp of the sample rendered at a PTP network time
+    ///
+    /// `None` before a network anchor has been set.
+    #[must_use]
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_sign_loss,
+        reason = "RTP timestamps wrap at 32 bits"
+    )]
+    pub fn rtp_time_at_network_time(&self, network_time: PtpTimestamp) -> Option<u32> {
+        let anchor = self.network_time?;
+        let nanos = network_time.diff_nanos(&anchor);
+        let samples = nanos * i128::from(self.sample_rate) / NANOS_PER_SEC;
+        Some(self.rtp_time.wrapping_add(samples as u32))
+    }
+
+    /// Move the anchor to now, so rate changes and seeks apply from here
+    fn rebase(&mut self) {
+        let position = self.position();
+        let rtp_time = self.rtp_time_at(position);
+        let network_time = self.network_time_at_rtp(rtp_time);
+        self.anchor(rtp_time, position, network_time);
+    }
+
+    /// Signed nanoseconds from the anchor to an RTP timestamp
+    #[allow(
+        clippy::cast_possible_wrap,
+        reason = "RTP timestamp wrapping arithmetic"
+    )]
+    fn rtp_offset_nanos(&self, rtp_time: u32) -> i128 {
+        let samples = i128::from(rtp_time.wrapping_sub(self.rtp_time) as i32);
+        samples * NANOS_PER_SEC / i128::from(self.sample_rate.max(1))
+    }
+
+    fn offset(position: Duration, nanos: i128) -> Duration {
+        let magnitude =
+            Duration::from_nanos(u64::try_from(nanos.unsigned_abs()).unwrap_or(u64::MAX));
+        if nanos >= 0 {
+            position + magnitude
+        } else {
+            position.saturating_sub(magnitude)
+        }
+    }
+
+    fn scale(duration: Duration, rate: f64) -> Duration {
+        if rate <= 0.0 {
+            return Duration::ZERO;
+        }
+        duration.mul_f64(rate)
+    }
+
+    #[allow(
+        clippy::cast_possible_truncation,
+        clippy::cast_precision_loss,
+        reason = "Playback-rate scaling tolerates sub-nanosecond error"
+    )]
+    fn scale_nanos(nanos: i128, rate: f64) -> i128 {
+        if rate <= 0.0 {
+            return 0;
+        }
+        (nanos as f64 * rate) as i128
+    }
+}
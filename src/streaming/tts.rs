@@ -0,0 +1,168 @@
+//! Text-to-speech announcements
+//!
+//! Speech is synthesized by the platform's engine (`say` on macOS,
+//! `espeak-ng` or `espeak` elsewhere), read back as WAV and converted to
+//! 44.1 kHz stereo so it can be mixed into a stream as an announcement.
+
+use std::io;
+use std::process::Command;
+
+use super::source::SliceSource;
+use crate::audio::convert::{convert_channels, from_f32, resample_linear, to_f32};
+use crate::audio::{AudioFormat, ChannelConfig, SampleFormat, SampleRate};
+use crate::error::AirPlayError;
+
+/// Synthesize `text` with the platform speech engine
+///
+/// `voice` is passed to the engine as-is (`say -v`, `espeak-ng -v`); `None`
+/// uses its default voice.
+///
+/// # Errors
+///
+/// Returns error if no speech engine is installed, synthesis fails or the
+/// engine produces audio that cannot be decoded.
+pub async fn synthesize(text: &str, voice: Option<&str>) -> Result<SliceSource, AirPlayError> {
+    let text = text.to_string();
+    let voice = voice.map(str::to_string);
+    let wav = tokio::task::spawn_blocking(move || run_engine(&text, voice.as_deref()))
+        .await
+        .map_err(|e| AirPlayError::InternalError {
+            message: format!("speech synthesis task failed: {e}"),
+        })?
+        .map_err(|e| AirPlayError::IoError {
+            message: format!("speech synthesis failed: {e}"),
+            source: Some(Box::new(e)),
+        })?;
+
+    wav_to_source(&wav).map_err(|e| AirPlayError::UnsupportedFormat {
+        format: format!("synthesized speech: {e}"),
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn run_engine(text: &str, voice: Option<&str>) -> io::Result<Vec<u8>> {
+    let path = std::env::temp_dir().join(format!("airplay2-tts-{}.wav", rand::random::<u64>()));
+    let mut command = Command::new("say");
+    command
+        .arg("-o")
+        .arg(&path)
+        .arg("--file-format=WAVE")
+        .arg("--data-format=LEI16@44100");
+    if let Some(voice) = voice {
+        command.arg("-v").arg(voice);
+    }
+    let status = command.arg("--").arg(text).status()?;
+    let wav = if status.success() {
+        std::fs::read(&path)
+    } else {
+        Err(io::Error::other(format!("say exited with {status}")))
+    };
+    let _ = std::fs::remove_file(&path);
+    wav
+}
+
+#[cfg(not(target_os = "macos"))]
+fn run_engine(text: &str, voice: Option<&str>) -> io::Result<Vec<u8>> {
+    let mut last_error = io::Error::new(io::ErrorKind::NotFound, "no speech engine found");
+    for engine in ["espeak-ng", "espeak"] {
+        let mut command = Command::new(engine);
+        command.arg("--stdout");
+        if let Some(voice) = voice {
+            command.arg("-v").arg(voice);
+        }
+        match command.arg("--").arg(text).output() {
+            Ok(output) if output.status.success() => return Ok(output.stdout),
+            Ok(output) => {
+                return Err(io::Error::other(format!(
+                    "{engine} exited with {}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            Err(e) => last_error = e,
+        }
+    }
+    Err(last_error)
+}
+
+/// Decode 16-bit PCM WAV into a 44.1 kHz stereo source
+///
+/// Engines that write to a pipe leave the data length unset, so the data
+/// chunk is allowed to run to the end of the buffer.
+///
+/// # Errors
+///
+/// Returns error if the data is not 16-bit PCM WAV with one or two channels.
+pub fn wav_to_source(wav: &[u8]) -> io::Result<SliceSource> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+
+    if wav.len() < 12 || &wav[0..4] != b"RIFF" || &wav[8..12] != b"WAVE" {
+        return Err(invalid("not a WAV file"));
+    }
+
+    let mut fmt: Option<(u16, u16, u32, u16)> = None;
+    let mut offset = 12;
+    while offset + 8 <= wav.len() {
+        let id = &wav[offset..offset + 4];
+        let size = u32::from_le_bytes([
+            wav[offset + 4],
+            wav[offset + 5],
+            wav[offset + 6],
+            wav[offset + 7],
+        ]) as usize;
+        let body = &wav[offset + 8..wav.len().min(offset + 8 + size)];
+
+        match id {
+            b"fmt " if body.len() >= 16 => {
+                fmt = Some((
+                    u16::from_le_bytes([body[0], body[1]]),
+                    u16::from_le_bytes([body[2], body[3]]),
+                    u32::from_le_bytes([body[4], body[5], body[6], body[7]]),
+                    u16::from_le_bytes([body[14], body[15]]),
+                ));
+            }
+            b"data" => {
+                let (tag, channels, rate, bits) = fmt.ok_or_else(|| invalid("data before fmt"))?;
+                // 1 = PCM, 0xFFFE = WAVE_FORMAT_EXTENSIBLE
+                if !matches!(tag, 1 | 0xFFFE) || bits != 16 {
+                    return Err(invalid("only 16-bit PCM is supported"));
+                }
+                let layout = match channels {
+                    1 => ChannelConfig::Mono,
+                    2 => ChannelConfig::Stereo,
+                    _ => return Err(invalid("only mono and stereo are supported")),
+                };
+                return Ok(to_stereo_44k(body, layout, rate));
+            }
+            _ => {}
+        }
+
+        // Chunks are padded to an even length
+        offset = offset.saturating_add(8 + size + (size & 1));
+    }
+
+    Err(invalid("no data chunk"))
+}
+
+fn to_stereo_44k(pcm: &[u8], layout: ChannelConfig, rate: u32) -> SliceSource {
+    let format = AudioFormat {
+        sample_rate: SampleRate::Hz44100,
+        channels: ChannelConfig::Stereo,
+        sample_format: SampleFormat::I16,
+    };
+
+    let mut samples = to_f32(pcm, SampleFormat::I16);
+    if rate != format.sample_rate.as_u32() {
+        samples = resample_linear(
+            &samples,
+            rate,
+            format.sample_rate.as_u32(),
+            layout.channels(),
+        );
+    }
+    if layout != format.channels {
+        samples = convert_channels(&samples, layout, format.channels);
+    }
+
+    SliceSource::new(from_f32(&samples, format.sample_format), format)
+}
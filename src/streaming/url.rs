@@ -60,6 +60,13 @@ impl UrlStreamer {
         }
     }
 
+    /// Whether `url` points at an HLS playlist (`.m3u8`)
+    #[must_use]
+    pub fn is_hls(url: &str) -> bool {
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        path.to_ascii_lowercase().ends_with(".m3u8")
+    }
+
     /// Start playing a URL
     ///
     /// # Errors
@@ -201,6 +208,7 @@ impl UrlStreamer {
             Some(
                 crate::protocol::plist::encode(&body).map_err(|e| AirPlayError::CodecError {
                     message: format!("Failed to encode plist: {e}"),
+                    source: Some(Box::new(e)),
                 })?,
             )
         } else {
@@ -215,6 +223,7 @@ impl UrlStreamer {
         // Parse plist response
         let plist = crate::protocol::plist::decode(data).map_err(|e| AirPlayError::CodecError {
             message: format!("Failed to parse playback info: {e}"),
+            source: Some(Box::new(e)),
         })?;
 
         // Extract fields from plist
@@ -265,6 +274,7 @@ impl UrlStreamer {
         } else {
             Err(AirPlayError::CodecError {
                 message: "Expected dictionary in playback info".to_string(),
+                source: None,
             })
         }
     }
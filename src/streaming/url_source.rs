@@ -0,0 +1,356 @@
+//! Remote file playback with HTTP range requests
+//!
+//! [`HttpFileSource`] plays an audio file on a web server that the device
+//! cannot fetch itself, such as a RAOP-only speaker or a server only the
+//! sender can reach. Unlike [`HttpSource`](super::http::HttpSource), which
+//! reads a response front to back, it fetches the file with `Range` requests
+//! as the decoder asks for bytes, so the file can be seeked without
+//! downloading what lies before the new position.
+//!
+//! Only plain `http://` URLs are supported: the crate carries no TLS stack.
+
+use std::io::{self, Read, Seek, SeekFrom};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use symphonia::core::io::MediaSource;
+use symphonia::core::probe::Hint;
+
+use super::file::FileSource;
+use super::http::{self, BUFFER_AHEAD, Body, CHUNK_FRAMES, DecodedChunks, HttpUrl};
+use super::source::AudioSource;
+use crate::audio::AudioFormat;
+
+/// Audio source decoding a remote file fetched with range requests
+///
+/// Decoding runs on its own thread, up to the buffer-ahead duration ahead
+/// of playback, and only requests the bytes it needs next. When the network
+/// stalls, reads return silence rather than blocking the streamer.
+///
+/// The file can be seeked if the server honours `Range` requests; one that
+/// ignores them still plays from the start.
+///
+/// ```rust,no_run
+/// # async fn example(client: &airplay2::AirPlayClient) -> Result<(), airplay2::AirPlayError> {
+/// use airplay2::streaming::url_source::HttpFileSource;
+///
+/// let source = HttpFileSource::open("http://nas.local/music/song.flac")?;
+/// let handle = client.stream_audio(source).await?;
+/// handle.wait().await?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HttpFileSource {
+    chunks: DecodedChunks,
+    commands: mpsc::Sender<Command>,
+    format: AudioFormat,
+    duration: Option<Duration>,
+    frames: usize,
+    seekable: bool,
+}
+
+/// Requests to the decoder thread
+enum Command {
+    /// Seek, replying with the position reached and the chunks from there
+    Seek {
+        position: Duration,
+        reply: mpsc::Sender<io::Result<(Duration, ChunkReceiver)>>,
+    },
+}
+
+type ChunkReceiver = mpsc::Receiver<io::Result<Vec<u8>>>;
+
+impl HttpFileSource {
+    /// Start fetching and decoding `url`, buffering two seconds ahead
+    ///
+    /// Blocks until the first response has arrived and the file has been
+    /// probed.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL is not `http://`, the request fails or the
+    /// audio format is not supported.
+    pub fn open(url: &str) -> io::Result<Self> {
+        Self::with_buffer_ahead(url, BUFFER_AHEAD)
+    }
+
+    /// Start fetching and decoding `url`, decoding up to `buffer_ahead` of
+    /// audio ahead of playback
+    ///
+    /// A longer buffer rides out slower networks at the cost of memory and
+    /// of decoding that a seek throws away.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the URL is not `http://`, the request fails or the
+    /// audio format is not supported.
+    pub fn with_buffer_ahead(url: &str, buffer_ahead: Duration) -> io::Result<Self> {
+        let reader = RangeReader::open(&HttpUrl::parse(url)?)?;
+        let seekable = reader.seekable;
+        let mut hint = Hint::new();
+        if let Some(content_type) = &reader.content_type {
+            hint.mime_type(content_type);
+        }
+        if let Some(extension) = reader.url.extension() {
+            hint.with_extension(extension);
+        }
+
+        let (status_tx, status_rx) = mpsc::channel();
+        let (commands, commands_rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let decoder = match FileSource::from_media(Box::new(reader), &hint) {
+                Ok(decoder) => decoder,
+                Err(e) => {
+                    let _ = status_tx.send(Err(e));
+                    return;
+                }
+            };
+
+            let format = decoder.format();
+            let ahead = format
+                .duration_to_frames(buffer_ahead)
+                .div_ceil(CHUNK_FRAMES)
+                .max(1);
+            let (chunk_tx, chunk_rx) = mpsc::sync_channel(ahead);
+            if status_tx
+                .send(Ok((format, decoder.duration(), chunk_rx)))
+                .is_err()
+            {
+                return;
+            }
+            decode(decoder, ahead, chunk_tx, &commands_rx);
+        });
+
+        let (format, duration, chunks) = status_rx
+            .recv()
+            .map_err(|_| io::Error::other("Decoder thread exited"))??;
+
+        Ok(Self {
+            chunks: DecodedChunks::new(chunks),
+            commands,
+            format,
+            duration,
+            frames: 0,
+            seekable,
+        })
+    }
+}
+
+impl AudioSource for HttpFileSource {
+    fn format(&self) -> AudioFormat {
+        self.format
+    }
+
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let frame_bytes = self.format.bytes_per_frame();
+        let filled = self.chunks.read(buffer, frame_bytes)?;
+        self.frames += filled / frame_bytes;
+        Ok(filled)
+    }
+
+    fn duration(&self) -> Option<Duration> {
+        self.duration
+    }
+
+    fn position(&self) -> Duration {
+        self.format.frames_to_duration(self.frames)
+    }
+
+    /// Seek by fetching the file from the byte offset the decoder asks for
+    ///
+    /// Blocks until the new range has been requested. Audio decoded ahead
+    /// is discarded. If the seek fails the source ends.
+    fn seek(&mut self, position: Duration) -> io::Result<()> {
+        if !self.seekable {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "server does not support range requests",
+            ));
+        }
+        let (reply, reply_rx) = mpsc::channel();
+        self.commands
+            .send(Command::Seek { position, reply })
+            .map_err(|_| io::Error::other("Decoder thread exited"))?;
+        // Dropping the old chunks frees the decoder thread if it is waiting
+        // for room in them
+        self.chunks = DecodedChunks::new(mpsc::channel().1);
+
+        let (reached, chunks) = reply_rx
+            .recv()
+            .map_err(|_| io::Error::other("Decoder thread exited"))??;
+        self.chunks = DecodedChunks::new(chunks);
+        self.frames = self.format.duration_to_frames(reached);
+        Ok(())
+    }
+
+    fn is_seekable(&self) -> bool {
+        self.seekable
+    }
+}
+
+/// Decode into `chunks` until the source is dropped, seeking on request
+///
+/// At the end of the file, after an error, or once the consumer has dropped
+/// its chunks, the thread idles until the next seek.
+fn decode(
+    mut decoder: FileSource,
+    ahead: usize,
+    chunks: mpsc::SyncSender<io::Result<Vec<u8>>>,
+    commands: &mpsc::Receiver<Command>,
+) {
+    let chunk_bytes = CHUNK_FRAMES * decoder.format().bytes_per_frame();
+    let mut chunks = Some(chunks);
+
+    loop {
+        let command = if chunks.is_some() {
+            match commands.try_recv() {
+                Ok(command) => Some(command),
+                Err(mpsc::TryRecvError::Empty) => None,
+                Err(mpsc::TryRecvError::Disconnected) => return,
+            }
+        } else {
+            match commands.recv() {
+                Ok(command) => Some(command),
+                Err(_) => return,
+            }
+        };
+
+        if let Some(Command::Seek { position, reply }) = command {
+            chunks = None;
+            let result = decoder.seek(position).map(|()| {
+                let (chunk_tx, chunk_rx) = mpsc::sync_channel(ahead);
+                chunks = Some(chunk_tx);
+                (decoder.position(), chunk_rx)
+            });
+            let _ = reply.send(result);
+            continue;
+        }
+
+        let Some(chunk_tx) = &chunks else {
+            continue;
+        };
+        // Reads fill the chunk until the end of the file, so every chunk
+        // holds whole frames
+        let mut chunk = vec![0; chunk_bytes];
+        let (next, ended) = match decoder.read(&mut chunk) {
+            Ok(0) => (None, true),
+            Ok(n) => {
+                chunk.truncate(n);
+                (Some(Ok(chunk)), false)
+            }
+            Err(e) => (Some(Err(e)), true),
+        };
+        let sent = next.is_none_or(|next| chunk_tx.send(next).is_ok());
+        if ended || !sent {
+            chunks = None;
+        }
+    }
+}
+
+/// A remote file read through range requests
+///
+/// Sequential reads share one response; a seek drops it and the next read
+/// requests the file from the new offset.
+pub(crate) struct RangeReader {
+    /// URL of the file, after redirects
+    url: HttpUrl,
+    content_type: Option<String>,
+    /// File length, if the server told
+    len: Option<u64>,
+    /// Whether the server honours `Range`
+    seekable: bool,
+    pos: u64,
+    /// Response positioned at `pos`
+    body: Option<Body>,
+}
+
+impl RangeReader {
+    /// Request the file from its start, learning its length and whether
+    /// the server supports ranges
+    pub(crate) fn open(url: &HttpUrl) -> io::Result<Self> {
+        let response = http::get_range(url, 0)?;
+        let (seekable, len) = match response.content_range {
+            Some((_, len)) => (true, len),
+            None => (false, response.content_length),
+        };
+        Ok(Self {
+            url: response.url,
+            content_type: response.content_type,
+            len,
+            seekable,
+            pos: 0,
+            body: Some(response.body),
+        })
+    }
+
+    /// Open a response at `pos`
+    fn fetch(&mut self) -> io::Result<&mut Body> {
+        let response = http::get_range(&self.url, self.pos)?;
+        let mut body = response.body;
+        match response.content_range {
+            Some((start, _)) if start == self.pos => {}
+            Some((start, _)) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("asked for byte {} but got {start}", self.pos),
+                ));
+            }
+            None => {
+                // The whole file came back: skip to the position
+                let skipped = io::copy(&mut (&mut body).take(self.pos), &mut io::sink())?;
+                if skipped < self.pos {
+                    return Err(io::ErrorKind::UnexpectedEof.into());
+                }
+            }
+        }
+        Ok(self.body.insert(body))
+    }
+}
+
+impl Read for RangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() || self.len.is_some_and(|len| self.pos >= len) {
+            return Ok(0);
+        }
+        let mut n = match &mut self.body {
+            Some(body) => body.read(buf)?,
+            None => self.fetch()?.read(buf)?,
+        };
+        if n == 0 && self.seekable && self.len.is_some_and(|len| self.pos < len) {
+            // The server closed the response early: carry on from here
+            n = self.fetch()?.read(buf)?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        self.pos += n as u64;
+        Ok(n)
+    }
+}
+
+impl Seek for RangeReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.and_then(|len| len.checked_add_signed(offset)),
+            SeekFrom::Current(offset) => self.pos.checked_add_signed(offset),
+        }
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "seek out of range"))?;
+
+        if target != self.pos {
+            self.pos = target;
+            self.body = None;
+        }
+        Ok(self.pos)
+    }
+}
+
+impl MediaSource for RangeReader {
+    fn is_seekable(&self) -> bool {
+        self.seekable && self.len.is_some()
+    }
+
+    fn byte_len(&self) -> Option<u64> {
+        self.len
+    }
+}
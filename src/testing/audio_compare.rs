@@ -0,0 +1,285 @@
+//! PCM comparison helpers for end-to-end audio tests
+//!
+//! Audio that has been through a sender, the network and a receiver is rarely
+//! byte-identical to what went in: it may be delayed, gain-adjusted, or have
+//! lost packets replaced by silence. These helpers align the two signals and
+//! report windowed RMS and cross-correlation so tests can assert on quality
+//! instead of exact equality.
+
+/// Options for [`compare`]
+#[derive(Debug, Clone)]
+pub struct CompareOptions {
+    /// Interleaved channel count of both buffers
+    pub channels: usize,
+    /// Frames per analysis window
+    pub window_frames: usize,
+    /// Largest delay (in frames, either direction) searched when aligning
+    pub max_offset_frames: usize,
+    /// Windows of received audio with RMS below this level, while the sent
+    /// audio is above it, are treated as concealed gaps and excluded
+    pub silence_threshold: f64,
+}
+
+impl Default for CompareOptions {
+    fn default() -> Self {
+        Self {
+            channels: 2,
+            window_frames: 352,
+            max_offset_frames: 4410,
+            silence_threshold: 16.0,
+        }
+    }
+}
+
+/// Statistics for one analysis window
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowStats {
+    /// First frame of the window, in sent-audio frames
+    pub start_frame: usize,
+    /// RMS level of the sent audio
+    pub rms_sent: f64,
+    /// RMS level of the received audio
+    pub rms_received: f64,
+    /// Normalized cross-correlation (-1.0 to 1.0)
+    pub correlation: f64,
+    /// Whether the window looks like a concealed gap
+    pub concealed: bool,
+}
+
+/// Result of comparing sent and received PCM
+#[derive(Debug, Clone)]
+pub struct AudioComparison {
+    /// Delay of the received audio relative to the sent audio, in frames
+    pub offset_frames: isize,
+    /// Normalized cross-correlation over all non-concealed windows
+    pub correlation: f64,
+    /// Signal-to-error ratio over non-concealed windows, in dB
+    pub snr_db: f64,
+    /// Per-window statistics
+    pub windows: Vec<WindowStats>,
+}
+
+impl AudioComparison {
+    /// Number of windows treated as concealed gaps
+    #[must_use]
+    pub fn concealed_windows(&self) -> usize {
+        self.windows.iter().filter(|w| w.concealed).count()
+    }
+
+    /// Fraction of windows treated as concealed gaps
+    #[must_use]
+    #[allow(
+        clippy::cast_precision_loss,
+        reason = "Window counts are far below f64 precision limits"
+    )]
+    pub fn concealed_ratio(&self) -> f64 {
+        if self.windows.is_empty() {
+            return 0.0;
+        }
+        self.concealed_windows() as f64 / self.windows.len() as f64
+    }
+
+    /// Lowest correlation of any non-concealed window
+    #[must_use]
+    pub fn min_window_correlation(&self) -> Option<f64> {
+        self.windows
+            .iter()
+            .filter(|w| !w.concealed)
+            .map(|w| w.correlation)
+            .reduce(f64::min)
+    }
+
+    /// Check the comparison against quality thresholds
+    #[must_use]
+    pub fn is_match(&self, min_correlation: f64, max_concealed_ratio: f64) -> bool {
+        !self.windows.is_empty()
+            && self.correlation >= min_correlation
+            && self.concealed_ratio() <= max_concealed_ratio
+    }
+}
+
+/// Compare sent and received interleaved PCM
+///
+/// The received audio is first aligned to the sent audio by searching for the
+/// delay with the highest cross-correlation, then both are split into windows.
+/// Windows where the receiver output silence in place of sent audio are
+/// flagged as concealed and left out of the overall figures.
+#[must_use]
+pub fn compare(sent: &[i16], received: &[i16], options: &CompareOptions) -> AudioComparison {
+    let channels = options.channels.max(1);
+    let window = options.window_frames.max(1) * channels;
+
+    let (offset_frames, _) = find_offset(sent, received, channels, options.max_offset_frames);
+    let (sent, received) = align(sent, received, offset_frames, channels);
+    let len = sent.len().min(received.len());
+
+    let mut windows = Vec::new();
+    let mut totals = Correlation::default();
+    let mut error_energy = 0.0;
+
+    for start in (0..len).step_by(window) {
+        let end = (start + window).min(len);
+        let (a, b) = (&sent[start..end], &received[start..end]);
+        let stats = Correlation::of(a, b);
+        let rms_sent = rms(a);
+        let rms_received = rms(b);
+        let concealed =
+            rms_received < options.silence_threshold && rms_sent >= options.silence_threshold;
+
+        if !concealed {
+            totals.add(&stats);
+            error_energy += a
+                .iter()
+                .zip(b)
+                .map(|(&x, &y)| (f64::from(x) - f64::from(y)).powi(2))
+                .sum::<f64>();
+        }
+
+        windows.push(WindowStats {
+            start_frame: start / channels,
+            rms_sent,
+            rms_received,
+            correlation: stats.normalized(),
+            concealed,
+        });
+    }
+
+    let snr_db = if error_energy == 0.0 {
+        f64::INFINITY
+    } else {
+        10.0 * (totals.energy_a / error_energy).log10()
+    };
+
+    AudioComparison {
+        offset_frames,
+        correlation: totals.normalized(),
+        snr_db,
+        windows,
+    }
+}
+
+/// Find the delay of `received` relative to `sent` that maximizes correlation
+///
+/// Searches delays from `-max_offset_frames` to `max_offset_frames` and
+/// returns the best delay in frames with its normalized correlation. Delays
+/// that would leave less than half of the shorter buffer overlapping are
+/// skipped, since tiny overlaps correlate well by chance. Received samples
+/// that are digital silence are ignored so concealed gaps do not pull the
+/// alignment, and near-ties (periodic signals) resolve to the smallest delay.
+#[must_use]
+#[allow(
+    clippy::cast_possible_wrap,
+    reason = "Offsets are bounded by buffer lengths, well below isize::MAX"
+)]
+pub fn find_offset(
+    sent: &[i16],
+    received: &[i16],
+    channels: usize,
+    max_offset_frames: usize,
+) -> (isize, f64) {
+    let channels = channels.max(1);
+    let max_offset = max_offset_frames as isize;
+    let min_overlap = sent.len().min(received.len()) / 2;
+    let mut best: Option<(isize, f64)> = None;
+
+    let offsets = std::iter::once(0).chain((1..=max_offset).flat_map(|d| [d, -d]));
+    for offset in offsets {
+        let (a, b) = align(sent, received, offset, channels);
+        let len = a.len().min(b.len());
+        if len == 0 || len < min_overlap {
+            continue;
+        }
+        let score = Correlation::of_audible(&a[..len], &b[..len]).normalized();
+        if best.is_none_or(|(_, best_score)| score > best_score + TIE_TOLERANCE) {
+            best = Some((offset, score));
+        }
+    }
+
+    best.unwrap_or((0, 0.0))
+}
+
+/// RMS level of a block of samples
+#[must_use]
+#[allow(
+    clippy::cast_precision_loss,
+    reason = "Sample counts are far below f64 precision limits"
+)]
+pub fn rms(samples: &[i16]) -> f64 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let energy: f64 = samples.iter().map(|&s| f64::from(s).powi(2)).sum();
+    (energy / samples.len() as f64).sqrt()
+}
+
+/// FNV-1a checksum of PCM samples, for bit-exact (lossless) paths
+#[must_use]
+pub fn checksum(samples: &[i16]) -> u64 {
+    samples
+        .iter()
+        .flat_map(|s| s.to_le_bytes())
+        .fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01B3)
+        })
+}
+
+/// Correlation difference below which two candidate delays are considered equal
+const TIE_TOLERANCE: f64 = 1e-6;
+
+/// Slice both buffers so that sample `i` of each lines up for the given delay
+fn align<'a>(
+    sent: &'a [i16],
+    received: &'a [i16],
+    offset_frames: isize,
+    channels: usize,
+) -> (&'a [i16], &'a [i16]) {
+    let shift = offset_frames.unsigned_abs() * channels;
+    if offset_frames >= 0 {
+        (sent, received.get(shift..).unwrap_or_default())
+    } else {
+        (sent.get(shift..).unwrap_or_default(), received)
+    }
+}
+
+#[derive(Debug, Default)]
+struct Correlation {
+    dot: f64,
+    energy_a: f64,
+    energy_b: f64,
+}
+
+impl Correlation {
+    fn of(a: &[i16], b: &[i16]) -> Self {
+        Self::accumulate(a.iter().zip(b))
+    }
+
+    /// Like [`of`](Self::of), skipping pairs where `b` is digital silence
+    fn of_audible(a: &[i16], b: &[i16]) -> Self {
+        Self::accumulate(a.iter().zip(b).filter(|&(_, &y)| y != 0))
+    }
+
+    fn accumulate<'a>(pairs: impl Iterator<Item = (&'a i16, &'a i16)>) -> Self {
+        let mut corr = Self::default();
+        for (&x, &y) in pairs {
+            let (x, y) = (f64::from(x), f64::from(y));
+            corr.dot += x * y;
+            corr.energy_a += x * x;
+            corr.energy_b += y * y;
+        }
+        corr
+    }
+
+    fn add(&mut self, other: &Self) {
+        self.dot += other.dot;
+        self.energy_a += other.energy_a;
+        self.energy_b += other.energy_b;
+    }
+
+    fn normalized(&self) -> f64 {
+        if self.energy_a == 0.0 && self.energy_b == 0.0 {
+            return 1.0;
+        }
+        let denom = (self.energy_a * self.energy_b).sqrt();
+        if denom == 0.0 { 0.0 } else { self.dot / denom }
+    }
+}
@@ -3,7 +3,6 @@
 //! Simulates an iOS/macOS device connecting to our receiver,
 //! performing pairing, and streaming audio.
 
-use std::collections::HashMap;
 use std::net::SocketAddr;
 
 use tokio::net::TcpStream;
@@ -11,7 +10,7 @@ use tokio::net::TcpStream;
 use crate::net::{AsyncReadExt, AsyncWriteExt};
 use crate::protocol::crypto::{Ed25519KeyPair, X25519KeyPair};
 use crate::protocol::pairing::tlv::TlvEncoder;
-use crate::protocol::plist::PlistValue;
+use crate::protocol::plist::{PlistDict, PlistValue};
 use crate::protocol::rtsp::{Headers, Method, RtspRequest};
 use crate::receiver::ap2::body_handler::encode_bplist_body;
 
@@ -130,7 +129,7 @@ impl MockAp2Sender {
         let request = self.build_request(Method::Get, "/info", None);
         let _response = self.send_request(&request).await?;
         // Parse response body as plist
-        Ok(PlistValue::Dictionary(HashMap::new())) // Simplified
+        Ok(PlistValue::Dictionary(PlistDict::new())) // Simplified
     }
 
     /// Perform pair-setup (M1-M4)
@@ -179,11 +178,11 @@ impl MockAp2Sender {
     /// # Errors
     /// Returns `MockSenderError` on protocol or connection failures.
     pub async fn setup_timing(&mut self) -> Result<(u16, u16), MockSenderError> {
-        let mut streams = HashMap::new();
+        let mut streams = PlistDict::new();
         streams.insert("type".to_string(), PlistValue::Integer(150)); // Timing
 
         let body = encode_bplist_body(&PlistValue::Dictionary({
-            let mut d = HashMap::new();
+            let mut d = PlistDict::new();
             d.insert(
                 "streams".to_string(),
                 PlistValue::Array(vec![PlistValue::Dictionary(streams)]),
@@ -207,7 +206,7 @@ impl MockAp2Sender {
     /// # Errors
     /// Returns `MockSenderError` on protocol or connection failures.
     pub async fn setup_audio(&mut self) -> Result<(u16, u16), MockSenderError> {
-        let mut streams = HashMap::new();
+        let mut streams = PlistDict::new();
         streams.insert("type".to_string(), PlistValue::Integer(96)); // Audio
         streams.insert("ct".to_string(), PlistValue::Integer(100)); // PCM
         streams.insert("sr".to_string(), PlistValue::Integer(44100));
@@ -215,7 +214,7 @@ impl MockAp2Sender {
         streams.insert("ss".to_string(), PlistValue::Integer(16));
 
         let body = encode_bplist_body(&PlistValue::Dictionary({
-            let mut d = HashMap::new();
+            let mut d = PlistDict::new();
             d.insert(
                 "streams".to_string(),
                 PlistValue::Array(vec![PlistValue::Dictionary(streams)]),
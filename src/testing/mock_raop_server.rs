@@ -15,10 +15,14 @@ use tokio::net::{TcpListener, UdpSocket};
 use tokio::sync::broadcast;
 
 use crate::net::{AsyncReadExt, AsyncWriteExt};
-#[cfg(feature = "raop")]
 use crate::protocol::crypto::RaopRsaPrivateKey;
 use crate::protocol::rtsp::{Headers, Method, RtspRequest};
 
+/// Digest realm announced when a password is required
+const MOCK_DIGEST_REALM: &str = "raop";
+/// Digest nonce announced when a password is required
+const MOCK_DIGEST_NONCE: &str = "6b0e1f6d3a9c4e58";
+
 /// Mock RAOP server state
 #[derive(Debug, Clone, Default)]
 pub struct MockRaopState {
@@ -38,6 +42,14 @@ pub struct MockRaopState {
     pub aes_key: Option<[u8; 16]>,
     /// AES IV
     pub aes_iv: Option<[u8; 16]>,
+    /// Packets received on the control channel (including retransmissions)
+    pub control_packets: Vec<Vec<u8>>,
+    /// Client control port from SETUP
+    pub client_control_port: Option<u16>,
+    /// Packets received on the timing channel
+    pub timing_packets: Vec<Vec<u8>>,
+    /// Client timing port from SETUP
+    pub client_timing_port: Option<u16>,
 }
 
 /// Mock RAOP server configuration
@@ -61,6 +73,11 @@ pub struct MockRaopConfig {
     pub encryption_types: Vec<u8>,
     /// Require Apple-Challenge
     pub require_challenge: bool,
+    /// Answer the Apple-Challenge with an Apple-Response signed by the
+    /// server's own key (see [`MockRaopServer::public_key`])
+    pub sign_challenge: bool,
+    /// Require RTSP digest authentication with this password
+    pub password: Option<String>,
 }
 
 impl Default for MockRaopConfig {
@@ -75,12 +92,13 @@ impl Default for MockRaopConfig {
             codecs: vec![0, 1, 2],        // PCM, ALAC, AAC
             encryption_types: vec![0, 1], // None, RSA
             require_challenge: true,
+            sign_challenge: false,
+            password: None,
         }
     }
 }
 
 /// Mock RAOP server
-#[cfg(feature = "raop")]
 pub struct MockRaopServer {
     /// Configuration
     pub config: MockRaopConfig,
@@ -92,9 +110,12 @@ pub struct MockRaopServer {
     running: bool,
     /// Shutdown signal sender
     shutdown: Option<broadcast::Sender<()>>,
+    /// Control socket, for sending retransmit requests
+    control_socket: Option<Arc<UdpSocket>>,
+    /// Timing socket, for sending timing requests
+    timing_socket: Option<Arc<UdpSocket>>,
 }
 
-#[cfg(feature = "raop")]
 impl MockRaopServer {
     /// Create new mock server
     #[must_use]
@@ -105,6 +126,8 @@ impl MockRaopServer {
             rsa_key: RaopRsaPrivateKey::generate().expect("failed to generate RSA key"),
             running: false,
             shutdown: None,
+            control_socket: None,
+            timing_socket: None,
         }
     }
 
@@ -254,15 +277,20 @@ impl MockRaopServer {
             }
         });
 
-        // Control Listener (Dummy consume)
+        // Control Listener
+        let control_socket = Arc::new(control_socket);
+        self.control_socket = Some(control_socket.clone());
+        let state_control = self.state.clone();
         let mut shutdown_rx_control = shutdown_tx.subscribe();
         tokio::spawn(async move {
             let mut buf = [0u8; 4096];
             loop {
                 tokio::select! {
                     res = control_socket.recv_from(&mut buf) => {
-                        // Ignore control packets for now
-                        let _ = res;
+                        if let Ok((n, _)) = res {
+                            let mut state = state_control.lock().unwrap();
+                            state.control_packets.push(buf[..n].to_vec());
+                        }
                     }
                     _ = shutdown_rx_control.recv() => {
                         break;
@@ -271,15 +299,20 @@ impl MockRaopServer {
             }
         });
 
-        // Timing Listener (Dummy consume)
+        // Timing Listener
+        let timing_socket = Arc::new(timing_socket);
+        self.timing_socket = Some(timing_socket.clone());
+        let state_timing = self.state.clone();
         let mut shutdown_rx_timing = shutdown_tx.subscribe();
         tokio::spawn(async move {
             let mut buf = [0u8; 4096];
             loop {
                 tokio::select! {
                     res = timing_socket.recv_from(&mut buf) => {
-                        // Ignore timing packets for now
-                        let _ = res;
+                        if let Ok((n, _)) = res {
+                            let mut state = state_timing.lock().unwrap();
+                            state.timing_packets.push(buf[..n].to_vec());
+                        }
                     }
                     _ = shutdown_rx_timing.recv() => {
                         break;
@@ -310,6 +343,45 @@ impl MockRaopServer {
         *self.state.lock().unwrap() = MockRaopState::default();
     }
 
+    /// Ask the client to resend packets, as a receiver does on packet loss
+    ///
+    /// Sent to the control port the client gave in SETUP, on the loopback
+    /// address.
+    pub async fn request_retransmit(&self, seq_start: u16, count: u16) -> std::io::Result<()> {
+        use crate::protocol::rtp::raop::RetransmitRequest;
+
+        let port = self.state.lock().unwrap().client_control_port;
+        let (Some(socket), Some(port)) = (&self.control_socket, port) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "no client control port",
+            ));
+        };
+        let packet = RetransmitRequest { seq_start, count }.encode(1);
+        socket.send_to(&packet, ("127.0.0.1", port)).await?;
+        Ok(())
+    }
+
+    /// Send a timing request to the client, as a receiver does to measure
+    /// latency
+    ///
+    /// Sent to the timing port the client gave in SETUP, on the loopback
+    /// address.
+    pub async fn request_timing(&self, sequence: u16) -> std::io::Result<()> {
+        use crate::protocol::rtp::raop_timing::RaopTimingRequest;
+
+        let port = self.state.lock().unwrap().client_timing_port;
+        let (Some(socket), Some(port)) = (&self.timing_socket, port) else {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotConnected,
+                "no client timing port",
+            ));
+        };
+        let packet = RaopTimingRequest::new().encode(sequence);
+        socket.send_to(&packet, ("127.0.0.1", port)).await?;
+        Ok(())
+    }
+
     /// Get RSA public key (for testing client)
     #[must_use]
     pub fn public_key(&self) -> rsa::RsaPublicKey {
@@ -324,6 +396,7 @@ impl MockRaopServer {
     ) {
         let mut buffer = Vec::new();
         let mut temp_buf = vec![0u8; 4096];
+        let local_ip = stream.local_addr().ok().map(|addr| addr.ip());
 
         loop {
             let n = match stream.read(&mut temp_buf).await {
@@ -336,7 +409,8 @@ impl MockRaopServer {
                 match Self::try_parse_request(&buffer) {
                     Ok(Some((request, consumed))) => {
                         buffer.drain(..consumed);
-                        let response = Self::process_request(&request, &state, &config, &rsa_key);
+                        let response =
+                            Self::process_request(&request, &state, &config, &rsa_key, local_ip);
                         let bytes = Self::encode_response(&response);
                         if stream.write_all(&bytes).await.is_err() {
                             return;
@@ -417,9 +491,16 @@ impl MockRaopServer {
         state: &Arc<Mutex<MockRaopState>>,
         config: &MockRaopConfig,
         rsa_key: &RaopRsaPrivateKey,
+        local_ip: Option<std::net::IpAddr>,
     ) -> crate::protocol::rtsp::RtspResponse {
+        if let Some(password) = &config.password {
+            if !Self::is_authorized(request, password) {
+                return Self::unauthorized(request);
+            }
+        }
+
         match request.method {
-            Method::Options => Self::handle_options_static(request, config),
+            Method::Options => Self::handle_options_static(request, config, rsa_key, local_ip),
             Method::Announce => Self::handle_announce_static(request, state, rsa_key),
             Method::Setup => Self::handle_setup_static(request, state, config),
             Method::Record => Self::handle_record_static(request, state),
@@ -441,15 +522,64 @@ impl MockRaopServer {
         }
     }
 
+    /// Check a request's digest `Authorization` against the password
+    fn is_authorized(request: &RtspRequest, password: &str) -> bool {
+        use crate::protocol::raop::{DigestChallenge, DigestCredentials};
+
+        let Some(authorization) = request.headers.get("Authorization") else {
+            return false;
+        };
+        let param = |name: &str| {
+            authorization
+                .split(',')
+                .filter_map(|p| p.trim().trim_start_matches("Digest ").split_once('='))
+                .find(|(k, _)| k.trim() == name)
+                .map(|(_, v)| v.trim().trim_matches('"').to_string())
+        };
+        let (Some(username), Some(response)) = (param("username"), param("response")) else {
+            return false;
+        };
+
+        let challenge = DigestChallenge {
+            realm: MOCK_DIGEST_REALM.to_string(),
+            nonce: MOCK_DIGEST_NONCE.to_string(),
+        };
+        let credentials = DigestCredentials {
+            username,
+            password: password.to_string(),
+        };
+        challenge.response(&credentials, request.method.as_str(), &request.uri) == response
+    }
+
+    fn unauthorized(request: &RtspRequest) -> crate::protocol::rtsp::RtspResponse {
+        use crate::protocol::rtsp::{RtspResponse, StatusCode};
+
+        let mut headers = Headers::new();
+        headers.insert("CSeq", request.headers.cseq().unwrap_or(0).to_string());
+        headers.insert(
+            "WWW-Authenticate",
+            format!("Digest realm=\"{MOCK_DIGEST_REALM}\", nonce=\"{MOCK_DIGEST_NONCE}\""),
+        );
+        RtspResponse {
+            version: "RTSP/1.0".to_string(),
+            status: StatusCode::UNAUTHORIZED,
+            reason: "Unauthorized".to_string(),
+            headers,
+            body: Vec::new(),
+        }
+    }
+
     /// Handle RTSP OPTIONS request
     #[must_use]
     pub fn handle_options(&self, request: &RtspRequest) -> crate::protocol::rtsp::RtspResponse {
-        Self::handle_options_static(request, &self.config)
+        Self::handle_options_static(request, &self.config, &self.rsa_key, None)
     }
 
     fn handle_options_static(
         request: &RtspRequest,
         config: &MockRaopConfig,
+        rsa_key: &RaopRsaPrivateKey,
+        local_ip: Option<std::net::IpAddr>,
     ) -> crate::protocol::rtsp::RtspResponse {
         use crate::protocol::rtsp::{Headers, RtspResponse, StatusCode};
 
@@ -462,11 +592,20 @@ impl MockRaopServer {
         );
 
         // Handle Apple-Challenge if present
-        if config.require_challenge {
-            if let Some(_challenge) = request.headers.get("Apple-Challenge") {
-                // Generate Apple-Response (stub)
-                // In a real mock we might compute it, but for now just acknowledge
-                // headers.insert("Apple-Response", "...");
+        if config.require_challenge && config.sign_challenge {
+            let challenge = request
+                .headers
+                .apple_challenge()
+                .and_then(|c| crate::protocol::raop::decode_challenge(c).ok());
+            if let (Some(challenge), Some(ip)) = (challenge, local_ip) {
+                if let Ok(response) = crate::protocol::raop::generate_response(
+                    rsa_key,
+                    &challenge,
+                    &ip,
+                    &config.mac_address,
+                ) {
+                    headers.insert("Apple-Response", response);
+                }
             }
         }
 
@@ -537,9 +676,15 @@ impl MockRaopServer {
 
         let session_id = format!("{:016X}", rand::thread_rng().r#gen::<u64>());
 
+        let transport = request.headers.transport().ok().flatten();
+        let client_control_port = transport.as_ref().and_then(|t| t.control_port);
+        let client_timing_port = transport.as_ref().and_then(|t| t.timing_port);
+
         {
             let mut state = state.lock().unwrap();
             state.session_id = Some(session_id.clone());
+            state.client_control_port = client_control_port;
+            state.client_timing_port = client_timing_port;
         }
 
         let mut headers = Headers::new();
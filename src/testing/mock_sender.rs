@@ -163,7 +163,7 @@ impl MockSender {
 
         // Parse response for server ports and session
         if response.status.0 == 200 {
-            self.session_id = response.headers.get("Session").map(ToString::to_string);
+            self.session_id = response.headers.session_header().map(|session| session.id);
             self.server_ports = Self::parse_transport(&response);
 
             self.audio_socket = Some(audio_socket);
@@ -247,16 +247,21 @@ impl MockSender {
                 return Ok(());
             }
 
+            let copies = if sim.should_duplicate() { 2 } else { 1 };
             let delay = sim.get_delay();
             if delay.is_zero() && !sim.should_reorder() {
-                socket.send(&packet).await?;
+                for _ in 0..copies {
+                    socket.send(&packet).await?;
+                }
             } else {
                 // Simulate delay/reordering by spawning a task
                 tokio::spawn(async move {
                     if !delay.is_zero() {
                         tokio::time::sleep(delay).await;
                     }
-                    let _ = socket.send(&packet).await;
+                    for _ in 0..copies {
+                        let _ = socket.send(&packet).await;
+                    }
                 });
             }
         } else {
@@ -463,35 +468,13 @@ impl MockSender {
     }
 
     fn parse_transport(response: &RtspResponse) -> Option<ServerPorts> {
-        let transport = response.headers.get("Transport")?;
-
-        let mut audio = 0u16;
-        let mut control = 0u16;
-        let mut timing = 0u16;
-
-        for part in transport.split(';') {
-            if let Some(value) = part.strip_prefix("server_port=") {
-                if let Some(port_str) = value.split('-').next() {
-                    audio = port_str.parse().unwrap_or(0);
-                }
-            }
-            if let Some(value) = part.strip_prefix("control_port=") {
-                control = value.parse().unwrap_or(0);
-            }
-            if let Some(value) = part.strip_prefix("timing_port=") {
-                timing = value.parse().unwrap_or(0);
-            }
-        }
-
-        if audio > 0 && control > 0 && timing > 0 {
-            Some(ServerPorts {
-                audio,
-                control,
-                timing,
-            })
-        } else {
-            None
-        }
+        let transport = response.headers.transport().ok()??;
+        let ports = ServerPorts {
+            audio: transport.server_port?,
+            control: transport.control_port?,
+            timing: transport.timing_port?,
+        };
+        (ports.audio > 0 && ports.control > 0 && ports.timing > 0).then_some(ports)
     }
 }
 
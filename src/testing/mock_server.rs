@@ -2,7 +2,11 @@
 //!
 //! This module provides a minimal `AirPlay` server implementation that can be used
 //! to test the client functionality without requiring real hardware. It supports
-//! basic RTSP negotiation, audio data reception (stub), and control commands.
+//! basic RTSP negotiation, audio data reception, and control commands.
+//!
+//! Network impairments (loss, reordering, duplication, jitter and connection
+//! resets) can be injected on the RTSP and RTP paths to exercise sender
+//! retransmit logic and receiver concealment.
 
 use std::fmt::Write;
 use std::net::SocketAddr;
@@ -10,14 +14,18 @@ use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use tokio::net::{TcpListener, TcpStream};
+use tokio::net::{TcpListener, TcpStream, UdpSocket};
 use tokio::sync::{RwLock, mpsc};
 
 use crate::net::{AsyncReadExt, AsyncWriteExt};
 use crate::protocol::crypto::Ed25519KeyPair;
+use crate::protocol::pairing::tlv::{TlvDecoder, TlvEncoder, TlvType, methods};
+use crate::protocol::pairing::{PairingEntry, pairings};
+use crate::protocol::plist::PlistValue;
 use crate::protocol::rtp::RtpPacket;
 use crate::protocol::rtsp::{Headers, Method, RtspRequest, StatusCode};
 use crate::receiver::ap2::PairingServer;
+use crate::testing::network_sim::NetworkSimulator;
 
 /// Configuration for the Mock `AirPlay` Server.
 #[derive(Debug, Clone)]
@@ -38,6 +46,17 @@ pub struct MockServerConfig {
     pub latency_ms: u32,
     /// Whether to accept pairing requests.
     pub accept_pairing: bool,
+    /// Network impairment applied to incoming RTP packets (loss, reordering,
+    /// duplication, delay) and to RTSP responses (delay only).
+    pub impairment: Option<NetworkSimulator>,
+    /// Reset the RTSP connection (TCP RST) instead of answering the Nth
+    /// request, counted across all connections.
+    pub rtsp_reset_after: Option<u32>,
+    /// Close the RTP audio socket after receiving this many packets.
+    pub rtp_reset_after: Option<u32>,
+    /// PTP clock ID returned in the SETUP `timingPeerInfo`, so the client
+    /// sends network times in its anchors.
+    pub clock_id: Option<u64>,
 }
 
 impl Default for MockServerConfig {
@@ -51,10 +70,33 @@ impl Default for MockServerConfig {
             require_auth: false,
             latency_ms: 0,
             accept_pairing: true,
+            impairment: None,
+            rtsp_reset_after: None,
+            rtp_reset_after: None,
+            clock_id: None,
         }
     }
 }
 
+/// Counters for impairments applied by the mock server.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ImpairmentStats {
+    /// RTP packets that arrived at the audio socket.
+    pub rtp_received: u64,
+    /// RTP packets dropped.
+    pub rtp_dropped: u64,
+    /// RTP packets delivered twice.
+    pub rtp_duplicated: u64,
+    /// RTP packets held back and delivered after their successor.
+    pub rtp_reordered: u64,
+    /// RTP audio socket closed by `rtp_reset_after`.
+    pub rtp_reset: bool,
+    /// RTSP requests received.
+    pub rtsp_requests: u64,
+    /// RTSP connections reset.
+    pub rtsp_resets: u64,
+}
+
 /// Internal state of the Mock Server.
 struct ServerState {
     /// Whether the server is currently in a streaming state.
@@ -65,10 +107,30 @@ struct ServerState {
     audio_packets: Vec<RtpPacket>,
     /// Current volume level in dB (or similar scale).
     volume: f32,
+    /// Every volume level set, in order
+    volume_changes: Vec<f32>,
     /// Whether the client is paired.
     paired: bool,
     /// Pairing server instance
     pairing_server: PairingServer,
+    /// Impairment counters
+    impairment_stats: ImpairmentStats,
+    /// Plist bodies of received SETUP requests
+    setup_plists: Vec<PlistValue>,
+    /// IDs of streams set up and not yet torn down
+    streams: Vec<u64>,
+    /// Plist bodies of received FLUSHBUFFERED requests
+    flush_buffered_plists: Vec<PlistValue>,
+    /// Whether a `FairPlay` fp-setup exchange completed
+    fairplay_completed: bool,
+    /// Number of `POST /identify` requests received
+    identify_requests: usize,
+    /// Controllers added through `/pair-add`
+    pairings: Vec<PairingEntry>,
+    /// Plist bodies of received `/command` requests
+    command_plists: Vec<PlistValue>,
+    /// Plist bodies of received SETRATEANCHORTIME requests
+    rate_anchor_plists: Vec<PlistValue>,
 }
 
 /// A Mock `AirPlay` server.
@@ -103,6 +165,16 @@ impl MockServer {
                 volume: 0.0,
                 paired: false,
                 pairing_server,
+                impairment_stats: ImpairmentStats::default(),
+                setup_plists: Vec::new(),
+                streams: Vec::new(),
+                flush_buffered_plists: Vec::new(),
+                fairplay_completed: false,
+                identify_requests: 0,
+                volume_changes: Vec::new(),
+                pairings: Vec::new(),
+                command_plists: Vec::new(),
+                rate_anchor_plists: Vec::new(),
             })),
             shutdown: None,
             address: None,
@@ -117,23 +189,33 @@ impl MockServer {
 
     /// Starts the server.
     ///
-    /// This spawns a background task to accept connections.
+    /// This spawns background tasks to accept connections and receive audio.
     /// Returns the socket address the server is bound to.
     ///
     /// # Errors
     ///
-    /// Returns an error if the TCP listener cannot be bound.
+    /// Returns an error if the TCP listener or the UDP audio socket cannot be
+    /// bound.
     pub async fn start(&mut self) -> Result<SocketAddr, std::io::Error> {
         let listener = TcpListener::bind(format!("127.0.0.1:{}", self.config.rtsp_port)).await?;
         let addr = listener.local_addr()?;
         self.address = Some(addr);
 
+        let audio_socket = UdpSocket::bind(format!("127.0.0.1:{}", self.config.audio_port)).await?;
+        self.config.audio_port = audio_socket.local_addr()?.port();
+
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel(1);
         self.shutdown = Some(shutdown_tx);
 
         let state = self.state.clone();
         let config = self.config.clone();
 
+        let audio_task = tokio::spawn(Self::receive_audio(
+            audio_socket,
+            self.state.clone(),
+            self.config.clone(),
+        ));
+
         // Spawn the main server loop
         tokio::spawn(async move {
             loop {
@@ -157,11 +239,82 @@ impl MockServer {
                     }
                 }
             }
+            audio_task.abort();
         });
 
         Ok(addr)
     }
 
+    /// Receives RTP audio packets, applying the configured impairment.
+    async fn receive_audio(
+        socket: UdpSocket,
+        state: Arc<RwLock<ServerState>>,
+        config: MockServerConfig,
+    ) {
+        let mut buf = [0u8; 4096];
+        let mut held_back: Option<RtpPacket> = None;
+
+        loop {
+            let Ok(n) = socket.recv(&mut buf).await else {
+                break;
+            };
+            let Ok(packet) = RtpPacket::decode(&buf[..n]) else {
+                continue;
+            };
+
+            let received = {
+                let mut state = state.write().await;
+                state.impairment_stats.rtp_received += 1;
+                state.impairment_stats.rtp_received
+            };
+            if config
+                .rtp_reset_after
+                .is_some_and(|limit| received > u64::from(limit))
+            {
+                state.write().await.impairment_stats.rtp_reset = true;
+                // Dropping the socket makes further sends fail with ICMP port unreachable
+                break;
+            }
+
+            let Some(sim) = &config.impairment else {
+                state.write().await.audio_packets.push(packet);
+                continue;
+            };
+
+            if sim.should_drop() {
+                state.write().await.impairment_stats.rtp_dropped += 1;
+                continue;
+            }
+
+            let mut batch = vec![packet.clone()];
+            if sim.should_duplicate() {
+                batch.push(packet);
+                state.write().await.impairment_stats.rtp_duplicated += 1;
+            }
+
+            if held_back.is_none() && sim.should_reorder() {
+                held_back = batch.pop();
+                state.write().await.impairment_stats.rtp_reordered += 1;
+                if batch.is_empty() {
+                    continue;
+                }
+            } else if let Some(previous) = held_back.take() {
+                batch.push(previous);
+            }
+
+            let delay = sim.get_delay();
+            if delay.is_zero() {
+                state.write().await.audio_packets.extend(batch);
+            } else {
+                let state = state.clone();
+                tokio::spawn(async move {
+                    tokio::time::sleep(delay).await;
+                    state.write().await.audio_packets.extend(batch);
+                });
+            }
+        }
+    }
+
     /// Stops the server.
     ///
     /// Signals the background task to shut down.
@@ -182,11 +335,79 @@ impl MockServer {
         self.state.read().await.audio_packets.len()
     }
 
+    /// Returns the RTP sequence numbers of received audio packets, in
+    /// delivery order (after impairment).
+    pub async fn audio_sequences(&self) -> Vec<u16> {
+        self.state
+            .read()
+            .await
+            .audio_packets
+            .iter()
+            .map(|p| p.header.sequence)
+            .collect()
+    }
+
+    /// Returns the plist bodies of SETUP requests received so far.
+    pub async fn setup_plists(&self) -> Vec<PlistValue> {
+        self.state.read().await.setup_plists.clone()
+    }
+
+    /// Returns the IDs of streams set up and not yet torn down.
+    pub async fn streams(&self) -> Vec<u64> {
+        self.state.read().await.streams.clone()
+    }
+
+    /// Returns the plist bodies of FLUSHBUFFERED requests received so far.
+    pub async fn flush_buffered_plists(&self) -> Vec<PlistValue> {
+        self.state.read().await.flush_buffered_plists.clone()
+    }
+
+    /// Returns the plist bodies of SETRATEANCHORTIME requests received so far.
+    pub async fn rate_anchor_plists(&self) -> Vec<PlistValue> {
+        self.state.read().await.rate_anchor_plists.clone()
+    }
+
+    /// Returns whether a client completed the `FairPlay` fp-setup exchange.
+    pub async fn fairplay_completed(&self) -> bool {
+        self.state.read().await.fairplay_completed
+    }
+
+    /// Returns how many times a client asked the server to identify itself.
+    pub async fn identify_requests(&self) -> usize {
+        self.state.read().await.identify_requests
+    }
+
+    /// Returns the controllers added through `/pair-add` and not removed.
+    pub async fn pairings(&self) -> Vec<PairingEntry> {
+        self.state.read().await.pairings.clone()
+    }
+
+    /// Returns the plist bodies of `MediaRemote` `/command` requests received so far.
+    pub async fn command_plists(&self) -> Vec<PlistValue> {
+        self.state.read().await.command_plists.clone()
+    }
+
+    /// Returns the UDP port audio is received on.
+    #[must_use]
+    pub fn audio_port(&self) -> u16 {
+        self.config.audio_port
+    }
+
+    /// Returns counters for the impairments applied so far.
+    pub async fn impairment_stats(&self) -> ImpairmentStats {
+        self.state.read().await.impairment_stats.clone()
+    }
+
     /// Returns the current volume level.
     pub async fn volume(&self) -> f32 {
         self.state.read().await.volume
     }
 
+    /// Returns every volume level set, in dB, in the order received.
+    pub async fn volume_changes(&self) -> Vec<f32> {
+        self.state.read().await.volume_changes.clone()
+    }
+
     /// Checks if the server is currently streaming.
     pub async fn is_streaming(&self) -> bool {
         self.state.read().await.streaming
@@ -264,10 +485,30 @@ impl MockServer {
                         // Remove consumed bytes
                         buffer.drain(..consumed);
 
+                        let requests = {
+                            let mut state = state.write().await;
+                            state.impairment_stats.rtsp_requests += 1;
+                            state.impairment_stats.rtsp_requests
+                        };
+                        if config
+                            .rtsp_reset_after
+                            .is_some_and(|limit| requests == u64::from(limit))
+                        {
+                            let mut state = state.write().await;
+                            state.impairment_stats.rtsp_resets += 1;
+                            state.pairing_server.reset();
+                            drop(state);
+                            Self::reset_connection(stream);
+                            return;
+                        }
+
                         // Simulate latency if configured
-                        if config.latency_ms > 0 {
-                            tokio::time::sleep(Duration::from_millis(u64::from(config.latency_ms)))
-                                .await;
+                        let mut latency = Duration::from_millis(u64::from(config.latency_ms));
+                        if let Some(sim) = &config.impairment {
+                            latency += sim.get_delay();
+                        }
+                        if !latency.is_zero() {
+                            tokio::time::sleep(latency).await;
                         }
 
                         // Determine if we should encrypt response based on CURRENT session state
@@ -311,6 +552,13 @@ impl MockServer {
         state.write().await.pairing_server.reset();
     }
 
+    /// Closes a connection with a TCP RST rather than a FIN.
+    fn reset_connection(stream: TcpStream) {
+        // A zero linger timeout makes close() abort the connection
+        let _ = socket2::SockRef::from(&stream).set_linger(Some(Duration::ZERO));
+        drop(stream);
+    }
+
     /// Tries to parse an RTSP request from the buffer.
     ///
     /// Returns `Ok(Some((request, consumed_bytes)))` if a complete request is found.
@@ -396,6 +644,31 @@ impl MockServer {
             ),
             Method::Setup => {
                 let mut state = state.write().await;
+                let mut created = Vec::new();
+                if let Ok(plist) = crate::protocol::plist::decode(&request.body) {
+                    // Assign an ID to each requested stream
+                    let requested = plist
+                        .as_dict()
+                        .and_then(|d| d.get("streams"))
+                        .and_then(PlistValue::as_array)
+                        .unwrap_or_default();
+                    for stream in requested {
+                        let id = state.streams.iter().max().map_or(1, |id| id + 1);
+                        state.streams.push(id);
+                        let stream_type = stream
+                            .as_dict()
+                            .and_then(|d| d.get("type"))
+                            .and_then(PlistValue::as_u64)
+                            .unwrap_or(96);
+                        created.push(
+                            crate::protocol::plist::DictBuilder::new()
+                                .insert("streamID", id)
+                                .insert("type", stream_type)
+                                .build(),
+                        );
+                    }
+                    state.setup_plists.push(plist);
+                }
                 state.session_id = Some(format!("{:X}", rand::random::<u64>()));
                 state.streaming = false;
 
@@ -409,12 +682,51 @@ impl MockServer {
 
                 let session_id = state.session_id.clone().unwrap();
 
-                let response = format!(
-                    "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nSession: {session_id}\r\nTransport: \
-                     {transport}\r\n\r\n",
-                );
+                if created.is_empty() {
+                    if let Some(clock_id) = config.clock_id {
+                        let body = crate::protocol::plist::encode(
+                            &crate::protocol::plist::DictBuilder::new()
+                                .insert(
+                                    "timingPeerInfo",
+                                    crate::protocol::plist::DictBuilder::new()
+                                        .insert("ClockID", clock_id)
+                                        .build(),
+                                )
+                                .build(),
+                        )
+                        .unwrap_or_default();
+                        let mut response = format!(
+                            "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nSession: {session_id}\r\n\
+                             Content-Type: application/x-apple-binary-plist\r\n\
+                             Content-Length: {}\r\n\r\n",
+                            body.len()
+                        )
+                        .into_bytes();
+                        response.extend_from_slice(&body);
+                        return response;
+                    }
+                    let response = format!(
+                        "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nSession: {session_id}\r\nTransport: \
+                         {transport}\r\n\r\n",
+                    );
+                    return response.into_bytes();
+                }
 
-                response.into_bytes()
+                let body = crate::protocol::plist::encode(
+                    &crate::protocol::plist::DictBuilder::new()
+                        .insert("streams", created)
+                        .build(),
+                )
+                .unwrap_or_default();
+                let mut response = format!(
+                    "RTSP/1.0 200 OK\r\nCSeq: {cseq}\r\nSession: {session_id}\r\nTransport: \
+                     {transport}\r\nContent-Type: application/x-apple-binary-plist\r\n\
+                     Content-Length: {}\r\n\r\n",
+                    body.len()
+                )
+                .into_bytes();
+                response.extend_from_slice(&body);
+                response
             }
             Method::Record | Method::Play => {
                 state.write().await.streaming = true;
@@ -422,7 +734,8 @@ impl MockServer {
             }
             Method::SetRateAnchorTime => {
                 // Parse body to check rate
-                let streaming = if let Ok(plist) = crate::protocol::plist::decode(&request.body) {
+                let plist = crate::protocol::plist::decode(&request.body).ok();
+                let streaming = if let Some(plist) = &plist {
                     if let Some(dict) = plist.as_dict() {
                         if let Some(rate) = dict
                             .get("rate")
@@ -439,7 +752,9 @@ impl MockServer {
                     true
                 };
 
-                state.write().await.streaming = streaming;
+                let mut state = state.write().await;
+                state.streaming = streaming;
+                state.rate_anchor_plists.extend(plist);
                 Self::response(StatusCode::OK, cseq, None, None)
             }
             Method::Pause => {
@@ -448,8 +763,31 @@ impl MockServer {
             }
             Method::Teardown => {
                 let mut state = state.write().await;
-                state.streaming = false;
-                state.session_id = None;
+                let torn_down: Vec<u64> = crate::protocol::plist::decode(&request.body)
+                    .ok()
+                    .and_then(|plist| {
+                        let streams = plist.as_dict()?.get("streams")?.as_array()?.to_vec();
+                        Some(
+                            streams
+                                .iter()
+                                .filter_map(|s| s.as_dict()?.get("streamID")?.as_u64())
+                                .collect(),
+                        )
+                    })
+                    .unwrap_or_default();
+                if torn_down.is_empty() {
+                    state.streaming = false;
+                    state.session_id = None;
+                    state.streams.clear();
+                } else {
+                    state.streams.retain(|id| !torn_down.contains(id));
+                }
+                Self::response(StatusCode::OK, cseq, None, None)
+            }
+            Method::FlushBuffered => {
+                if let Ok(plist) = crate::protocol::plist::decode(&request.body) {
+                    state.write().await.flush_buffered_plists.push(plist);
+                }
                 Self::response(StatusCode::OK, cseq, None, None)
             }
             Method::SetParameter => {
@@ -458,47 +796,18 @@ impl MockServer {
                 if let Some(vol_line) = body_str.lines().find(|l| l.starts_with("volume:")) {
                     if let Some(vol) = vol_line.split(':').nth(1) {
                         if let Ok(v) = vol.trim().parse::<f32>() {
-                            state.write().await.volume = v;
+                            let mut state = state.write().await;
+                            state.volume = v;
+                            state.volume_changes.push(v);
                         }
                     }
                 }
                 Self::response(StatusCode::OK, cseq, None, None)
             }
+            Method::Get if request.uri.ends_with("/info") => Self::info_response(cseq, config),
             Method::GetParameter => {
                 if request.uri.ends_with("/info") {
-                    use std::collections::HashMap;
-
-                    use crate::protocol::plist::{PlistValue, encode};
-
-                    let mut dict = HashMap::new();
-                    dict.insert(
-                        "manufacturer".to_string(),
-                        PlistValue::String("OpenAirplay".to_string()),
-                    );
-                    dict.insert(
-                        "model".to_string(),
-                        PlistValue::String("MockServer".to_string()),
-                    );
-                    dict.insert(
-                        "name".to_string(),
-                        PlistValue::String("Mock Device".to_string()),
-                    );
-                    // Add supported features (AirPlay 2, Audio, etc.)
-                    // Bit 48 (AirPlay 2), Bit 9 (Audio) -> 1<<48 | 1<<9
-                    let features: u64 = (1 << 48) | (1 << 9) | (1 << 40); // + PTP
-                    dict.insert(
-                        "features".to_string(),
-                        PlistValue::UnsignedInteger(features),
-                    );
-
-                    let body = encode(&PlistValue::Dictionary(dict)).unwrap_or_default();
-                    Self::response_binary(
-                        StatusCode::OK,
-                        cseq,
-                        None,
-                        Some(&body),
-                        Some("application/x-apple-binary-plist"),
-                    )
+                    Self::info_response(cseq, config)
                 } else {
                     let volume = state.read().await.volume;
                     let body = format!("volume: {volume:.6}\r\n");
@@ -517,6 +826,22 @@ impl MockServer {
                         Some(&body),
                         Some("application/octet-stream"),
                     )
+                } else if request.uri.ends_with("/fp-setup") {
+                    Self::handle_fp_setup(request, state).await
+                } else if request.uri.ends_with("/pair-add")
+                    || request.uri.ends_with("/pair-remove")
+                    || request.uri.ends_with("/pair-list")
+                {
+                    Self::handle_pairings(request, state).await
+                } else if request.uri.ends_with("/command") {
+                    let Ok(plist) = crate::protocol::plist::decode(&request.body) else {
+                        return Self::response(StatusCode::BAD_REQUEST, cseq, None, None);
+                    };
+                    state.write().await.command_plists.push(plist);
+                    Self::response(StatusCode::OK, cseq, None, None)
+                } else if request.uri.ends_with("/identify") {
+                    state.write().await.identify_requests += 1;
+                    Self::response(StatusCode::OK, cseq, None, None)
                 } else if request.uri.ends_with("/pair-setup")
                     || request.uri.ends_with("/pair-verify")
                 {
@@ -535,6 +860,74 @@ impl MockServer {
         }
     }
 
+    /// Answers the two `FairPlay` fp-setup phases with well-formed replies.
+    ///
+    /// The key message is not verified; phase 2 echoes its last 20 bytes as a
+    /// real receiver does.
+    async fn handle_fp_setup(request: &RtspRequest, state: &Arc<RwLock<ServerState>>) -> Vec<u8> {
+        use crate::protocol::fairplay::{FplyHeader, HEADER_LEN, PHASE1_REPLY_LEN};
+
+        let cseq = request.headers.cseq().unwrap_or(0);
+        let body = &request.body;
+        let reply = match FplyHeader::parse(body).map(|h| h.message_type) {
+            Ok(1) if body.len() == 16 => {
+                let mut reply = FplyHeader::new(2, 130).encode().to_vec();
+                reply.extend_from_slice(&[0x02, 0x00, body[14]]);
+                reply.resize(PHASE1_REPLY_LEN, 0);
+                reply
+            }
+            Ok(3) if body.len() > HEADER_LEN + 20 => {
+                state.write().await.fairplay_completed = true;
+                let mut reply = FplyHeader::new(4, 20).encode().to_vec();
+                reply.extend_from_slice(&body[body.len() - 20..]);
+                reply
+            }
+            _ => return Self::response(StatusCode::BAD_REQUEST, cseq, None, None),
+        };
+        Self::response_binary(
+            StatusCode::OK,
+            cseq,
+            None,
+            Some(&reply),
+            Some("application/octet-stream"),
+        )
+    }
+
+    /// Builds the `/info` response plist describing the mock device.
+    fn info_response(cseq: u32, config: &MockServerConfig) -> Vec<u8> {
+        use crate::protocol::plist::{PlistDict, PlistValue, encode};
+
+        let mut dict = PlistDict::new();
+        dict.insert(
+            "manufacturer".to_string(),
+            PlistValue::String("OpenAirplay".to_string()),
+        );
+        dict.insert(
+            "model".to_string(),
+            PlistValue::String("MockServer".to_string()),
+        );
+        dict.insert(
+            "name".to_string(),
+            PlistValue::String(config.device_name.clone()),
+        );
+        // Add supported features (AirPlay 2, Audio, etc.)
+        // Bit 48 (AirPlay 2), Bit 9 (Audio) -> 1<<48 | 1<<9
+        let features: u64 = (1 << 48) | (1 << 9) | (1 << 40); // + PTP
+        dict.insert(
+            "features".to_string(),
+            PlistValue::UnsignedInteger(features),
+        );
+
+        let body = encode(&PlistValue::Dictionary(dict)).unwrap_or_default();
+        Self::response_binary(
+            StatusCode::OK,
+            cseq,
+            None,
+            Some(&body),
+            Some("application/x-apple-binary-plist"),
+        )
+    }
+
     /// Handles pairing requests (POST).
     async fn handle_pairing(request: &RtspRequest, state: &Arc<RwLock<ServerState>>) -> Vec<u8> {
         let cseq = request.headers.cseq().unwrap_or(0);
@@ -567,6 +960,50 @@ impl MockServer {
         response_vec
     }
 
+    /// Applies a pairings administration request to the mock's pairing list.
+    async fn handle_pairings(request: &RtspRequest, state: &Arc<RwLock<ServerState>>) -> Vec<u8> {
+        let cseq = request.headers.cseq().unwrap_or(0);
+        let Ok(tlv) = TlvDecoder::decode(&request.body) else {
+            return Self::response(StatusCode::BAD_REQUEST, cseq, None, None);
+        };
+        let identifier = tlv
+            .get(TlvType::Identifier)
+            .map(|id| String::from_utf8_lossy(id).into_owned());
+        let mut state = state.write().await;
+
+        let body = match tlv.get(TlvType::Method).and_then(|m| m.first().copied()) {
+            Some(methods::ADD_PAIRING) => {
+                let (Some(identifier), Some(Ok(public_key))) = (
+                    identifier,
+                    tlv.get(TlvType::PublicKey).map(<[u8; 32]>::try_from),
+                ) else {
+                    return Self::response(StatusCode::BAD_REQUEST, cseq, None, None);
+                };
+                let admin = tlv.get(TlvType::Permissions) == Some(&[pairings::permissions::ADMIN]);
+                state.pairings.retain(|p| p.identifier != identifier);
+                state
+                    .pairings
+                    .push(PairingEntry::new(identifier, public_key, admin));
+                TlvEncoder::new().add_state(2).build()
+            }
+            Some(methods::REMOVE_PAIRING) => {
+                state
+                    .pairings
+                    .retain(|p| Some(&p.identifier) != identifier.as_ref());
+                TlvEncoder::new().add_state(2).build()
+            }
+            Some(methods::LIST_PAIRINGS) => pairings::encode_list_response(&state.pairings),
+            _ => return Self::response(StatusCode::BAD_REQUEST, cseq, None, None),
+        };
+        Self::response_binary(
+            StatusCode::OK,
+            cseq,
+            None,
+            Some(&body),
+            Some(pairings::CONTENT_TYPE),
+        )
+    }
+
     /// Helper to build an RTSP response.
     fn response(
         status: StatusCode,
@@ -591,16 +1028,7 @@ impl MockServer {
         body: Option<&[u8]>,
         content_type: Option<&str>,
     ) -> Vec<u8> {
-        let reason = match status.0 {
-            200 => "OK",
-            401 => "Unauthorized",
-            404 => "Not Found",
-            405 => "Method Not Allowed",
-            406 => "Not Acceptable",
-            500 => "Internal Server Error",
-            501 => "Not Implemented",
-            _ => "Unknown",
-        };
+        let reason = status.reason_phrase().unwrap_or("Unknown");
 
         let mut response = format!("RTSP/1.0 {} {}\r\nCSeq: {}\r\n", status.0, reason, cseq);
 
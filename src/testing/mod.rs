@@ -1,11 +1,17 @@
+pub mod audio_compare;
+#[cfg(feature = "receiver")]
 pub mod mock_ap2_sender;
+#[cfg(feature = "raop")]
 pub mod mock_raop_server;
+#[cfg(feature = "receiver")]
 pub mod mock_sender;
+#[cfg(feature = "receiver")]
 pub mod mock_server;
 pub mod network_sim;
 pub mod packet_capture;
+#[cfg(feature = "receiver")]
 pub mod test_utils;
-#[cfg(test)]
+#[cfg(all(test, feature = "sender", feature = "receiver"))]
 /// Unit tests for the mock server.
 pub mod tests;
 
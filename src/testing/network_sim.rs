@@ -15,6 +15,8 @@ pub struct NetworkSimulator {
     pub delay_ms: u32,
     /// Probability of reordering
     pub reorder_rate: f64,
+    /// Probability of delivering a packet twice
+    pub duplicate_rate: f64,
 }
 
 impl NetworkSimulator {
@@ -26,6 +28,7 @@ impl NetworkSimulator {
             jitter_ms: 0,
             delay_ms: 0,
             reorder_rate: 0.0,
+            duplicate_rate: 0.0,
         }
     }
 
@@ -37,6 +40,7 @@ impl NetworkSimulator {
             jitter_ms: 5,
             delay_ms: 2,
             reorder_rate: 0.001,
+            duplicate_rate: 0.0,
         }
     }
 
@@ -48,6 +52,7 @@ impl NetworkSimulator {
             jitter_ms: 20,
             delay_ms: 10,
             reorder_rate: 0.01,
+            duplicate_rate: 0.001,
         }
     }
 
@@ -59,6 +64,7 @@ impl NetworkSimulator {
             jitter_ms: 50,
             delay_ms: 30,
             reorder_rate: 0.05,
+            duplicate_rate: 0.01,
         }
     }
 
@@ -70,6 +76,7 @@ impl NetworkSimulator {
             jitter_ms: 100,
             delay_ms: 50,
             reorder_rate: 0.10,
+            duplicate_rate: 0.02,
         }
     }
 
@@ -102,4 +109,13 @@ impl NetworkSimulator {
         }
         rand::thread_rng().gen_bool(self.reorder_rate)
     }
+
+    /// Should this packet be delivered twice?
+    #[must_use]
+    pub fn should_duplicate(&self) -> bool {
+        if self.duplicate_rate <= 0.0 {
+            return false;
+        }
+        rand::thread_rng().gen_bool(self.duplicate_rate)
+    }
 }
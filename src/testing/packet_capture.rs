@@ -5,6 +5,7 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
+#[cfg(feature = "tokio-runtime")]
 use std::time::Duration;
 
 /// Captured packet
@@ -116,6 +117,7 @@ impl CaptureReplay {
     }
 
     /// Get next packet with timing
+    #[cfg(feature = "tokio-runtime")]
     pub async fn next_timed(&mut self) -> Option<&CapturedPacket> {
         if self.current_index >= self.packets.len() {
             return None;
@@ -1,8 +1,10 @@
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 
+use super::audio_compare::{self, CompareOptions};
 use super::mock_ap2_sender::{MockAp2Sender, MockSenderConfig};
 use super::mock_server::{MockServer, MockServerConfig};
+use super::network_sim::NetworkSimulator;
 use super::test_utils::{generate_test_audio, samples_match};
 use crate::protocol::rtsp::Method;
 
@@ -26,6 +28,62 @@ async fn test_mock_server_starts() {
     server.stop().await;
 }
 
+#[tokio::test]
+async fn test_probe_device_without_mdns() {
+    let mut server = MockServer::new(test_config());
+    let addr = server.start().await.unwrap();
+
+    let device =
+        crate::connection::probe_device(addr.ip(), addr.port(), std::time::Duration::from_secs(2))
+            .await
+            .unwrap();
+    assert_eq!(device.name, "Mock AirPlay Device");
+    assert_eq!(device.model.as_deref(), Some("MockServer"));
+    assert!(device.supports_airplay2());
+    assert_eq!(device.port, addr.port());
+
+    server.stop().await;
+
+    let result =
+        crate::connection::probe_info(addr.ip(), addr.port(), std::time::Duration::from_secs(1))
+            .await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_preflight_against_mock_server() {
+    use crate::{AirPlayClient, AirPlayConfig, PreflightCheck};
+
+    let mut server = MockServer::new(test_config());
+    let addr = server.start().await.unwrap();
+    let client = AirPlayClient::new(
+        AirPlayConfig::builder()
+            .connection_timeout(std::time::Duration::from_secs(2))
+            .build(),
+    );
+
+    let device = crate::types::AirPlayDevice::from_address(addr.ip(), addr.port());
+    let report = client.preflight(&device).await;
+    assert_eq!(report.address, Some(addr));
+    assert_eq!(
+        report.status(PreflightCheck::Info),
+        Some(&crate::CheckStatus::Passed)
+    );
+    assert_eq!(report.device.name, "Mock AirPlay Device");
+    assert!(report.can_connect(), "{:?}", report.checks);
+
+    server.stop().await;
+
+    let report = client.preflight(&device).await;
+    assert!(!report.can_connect());
+    assert!(
+        report
+            .status(PreflightCheck::Reachability)
+            .unwrap()
+            .is_blocking()
+    );
+}
+
 #[test]
 fn test_mock_sender_creation() {
     let _sender = MockAp2Sender::new(MockSenderConfig::default());
@@ -59,6 +117,62 @@ fn test_samples_match() {
     assert!(!samples_match(&a, &b, 1));
 }
 
+#[test]
+fn test_audio_compare_identical_and_delayed() {
+    let sent = generate_test_audio(440.0, 44100, 200, 2);
+    let options = CompareOptions {
+        max_offset_frames: 500,
+        ..CompareOptions::default()
+    };
+
+    let result = audio_compare::compare(&sent, &sent, &options);
+    assert_eq!(result.offset_frames, 0);
+    assert!(result.correlation > 0.999);
+    assert!(result.snr_db.is_infinite());
+    assert_eq!(result.concealed_windows(), 0);
+    assert_eq!(
+        audio_compare::checksum(&sent),
+        audio_compare::checksum(&sent)
+    );
+
+    // Receiver output starts 100 frames late and at half volume
+    let mut received = vec![0i16; 200];
+    received.extend(sent.iter().map(|s| s / 2));
+    let result = audio_compare::compare(&sent, &received, &options);
+    assert_eq!(result.offset_frames, 100);
+    assert!(result.is_match(0.99, 0.0));
+    assert_ne!(
+        audio_compare::checksum(&sent),
+        audio_compare::checksum(&received)
+    );
+}
+
+#[test]
+fn test_audio_compare_tolerates_concealed_gaps() {
+    let sent = generate_test_audio(440.0, 44100, 200, 2);
+    let options = CompareOptions {
+        max_offset_frames: 500,
+        ..CompareOptions::default()
+    };
+
+    // One packet's worth of audio replaced with silence
+    let mut received = sent.clone();
+    received[1408..2112].fill(0);
+    let result = audio_compare::compare(&sent, &received, &options);
+    assert_eq!(result.offset_frames, 0);
+    assert_eq!(result.concealed_windows(), 1);
+    assert!(result.correlation > 0.999);
+    assert!(result.is_match(0.99, 0.1));
+    assert!(!result.is_match(0.99, 0.0));
+
+    // A different signal does not match
+    let other = generate_test_audio(1000.0, 44100, 200, 2);
+    let result = audio_compare::compare(&sent, &other, &options);
+    assert!(result.correlation < 0.5);
+    assert!(!result.is_match(0.9, 1.0));
+    assert!(result.min_window_correlation().unwrap() < 0.5);
+}
+
 #[tokio::test]
 async fn test_mock_server_accepts_connection() {
     let mut server = MockServer::new(test_config());
@@ -174,3 +288,128 @@ async fn test_set_parameter_volume() {
 
     server.stop().await;
 }
+
+async fn send_rtp(server: &MockServer, sequences: &[u16]) {
+    use crate::protocol::rtp::RtpPacket;
+
+    let socket = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    socket
+        .connect(("127.0.0.1", server.audio_port()))
+        .await
+        .unwrap();
+    for &seq in sequences {
+        let packet = RtpPacket::audio(seq, u32::from(seq) * 352, 0x1234, vec![0u8; 16], false);
+        let _ = socket.send(&packet.encode()).await;
+        tokio::time::sleep(tokio::time::Duration::from_millis(5)).await;
+    }
+    tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
+}
+
+fn impaired_config(sim: NetworkSimulator) -> MockServerConfig {
+    MockServerConfig {
+        impairment: Some(sim),
+        ..test_config()
+    }
+}
+
+#[tokio::test]
+async fn test_rtp_received_without_impairment() {
+    let mut server = MockServer::new(test_config());
+    server.start().await.unwrap();
+    assert!(server.audio_port() > 0);
+
+    send_rtp(&server, &[1, 2, 3]).await;
+    assert_eq!(server.audio_sequences().await, vec![1, 2, 3]);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_rtp_impairment_loss_and_duplication() {
+    let mut server = MockServer::new(impaired_config(NetworkSimulator {
+        loss_rate: 1.0,
+        ..NetworkSimulator::perfect()
+    }));
+    server.start().await.unwrap();
+    send_rtp(&server, &[1, 2, 3]).await;
+    assert!(server.audio_sequences().await.is_empty());
+    assert_eq!(server.impairment_stats().await.rtp_dropped, 3);
+    server.stop().await;
+
+    let mut server = MockServer::new(impaired_config(NetworkSimulator {
+        duplicate_rate: 1.0,
+        ..NetworkSimulator::perfect()
+    }));
+    server.start().await.unwrap();
+    send_rtp(&server, &[1, 2]).await;
+    assert_eq!(server.audio_sequences().await, vec![1, 1, 2, 2]);
+    assert_eq!(server.impairment_stats().await.rtp_duplicated, 2);
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_rtp_impairment_reordering() {
+    let mut server = MockServer::new(impaired_config(NetworkSimulator {
+        reorder_rate: 1.0,
+        ..NetworkSimulator::perfect()
+    }));
+    server.start().await.unwrap();
+
+    send_rtp(&server, &[1, 2, 3, 4]).await;
+    assert_eq!(server.audio_sequences().await, vec![2, 1, 4, 3]);
+    assert_eq!(server.impairment_stats().await.rtp_reordered, 2);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_rtp_reset() {
+    let mut server = MockServer::new(MockServerConfig {
+        rtp_reset_after: Some(2),
+        ..test_config()
+    });
+    server.start().await.unwrap();
+
+    send_rtp(&server, &[1, 2, 3, 4]).await;
+    assert_eq!(server.audio_sequences().await, vec![1, 2]);
+    let stats = server.impairment_stats().await;
+    assert!(stats.rtp_reset);
+    assert_eq!(stats.rtp_received, 3);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_rtsp_reset_mid_session() {
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_reset_after: Some(2),
+        ..test_config()
+    });
+    let addr = server.start().await.unwrap();
+
+    let mut stream = TcpStream::connect(addr).await.unwrap();
+    let mut buf = [0u8; 1024];
+
+    stream
+        .write_all(b"OPTIONS * RTSP/1.0\r\nCSeq: 1\r\n\r\n")
+        .await
+        .unwrap();
+    let n = stream.read(&mut buf).await.unwrap();
+    assert!(String::from_utf8_lossy(&buf[..n]).contains("200 OK"));
+
+    stream
+        .write_all(b"OPTIONS * RTSP/1.0\r\nCSeq: 2\r\n\r\n")
+        .await
+        .unwrap();
+    match stream.read(&mut buf).await {
+        Ok(0) => {}
+        Ok(n) => panic!(
+            "unexpected response: {}",
+            String::from_utf8_lossy(&buf[..n])
+        ),
+        Err(e) => assert_eq!(e.kind(), std::io::ErrorKind::ConnectionReset),
+    }
+    assert_eq!(server.impairment_stats().await.rtsp_resets, 1);
+
+    server.stop().await;
+}
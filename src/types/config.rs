@@ -1,6 +1,18 @@
+use std::ops::RangeInclusive;
+use std::sync::Arc;
 use std::time::Duration;
 
-use crate::audio::AudioCodec;
+use crate::audio::aac_encoder::AudioObjectType;
+use crate::audio::{
+    AacEncoder, AudioCodec, AudioFormat, ChannelConfig, GainMode, SampleFormat, SampleRate,
+};
+use crate::error::AirPlayError;
+use crate::protocol::crypto::SrpGroup;
+use crate::protocol::fairplay::FairPlayKeySource;
+use crate::protocol::pairing::HkpMode;
+use crate::protocol::plist::{PlistDict, PlistValue};
+use crate::streaming::{Pacing, PreemptionPolicy};
+use crate::types::AirPlayDevice;
 
 /// Timing protocol to use for clock synchronization.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -15,8 +27,37 @@ pub enum TimingProtocol {
     Auto,
 }
 
+/// Whether the player reconnects after another sender took over the device
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PreemptedReconnect {
+    /// Leave the device to the other sender
+    #[default]
+    Never,
+    /// Reconnect once the device has advertised itself idle for this long
+    AfterIdle(Duration),
+    /// Reconnect straight away, taking the device back
+    Always,
+}
+
+impl PreemptedReconnect {
+    /// Whether the device may be taken back, given how long it has been
+    /// idle (`None` while the other sender is still playing)
+    #[must_use]
+    pub fn allows(self, idle: Option<Duration>) -> bool {
+        match self {
+            Self::Never => false,
+            Self::AfterIdle(wait) => idle.is_some_and(|idle| idle >= wait),
+            Self::Always => true,
+        }
+    }
+}
+
 /// Configuration for `AirPlay` client behavior
 #[derive(Debug, Clone)]
+#[allow(
+    clippy::struct_excessive_bools,
+    reason = "Configuration switches are independent options"
+)]
 pub struct AirPlayConfig {
     /// Timeout for device discovery scan (default: 5 seconds)
     pub discovery_timeout: Duration,
@@ -24,9 +65,16 @@ pub struct AirPlayConfig {
     /// Timeout for connection attempts (default: 10 seconds)
     pub connection_timeout: Duration,
 
+    /// Time to wait for the response to an RTSP request (default: 10 seconds)
+    pub request_timeout: Duration,
+
     /// Interval for polling playback state (default: 500ms)
     pub state_poll_interval: Duration,
 
+    /// Event channel silence after which the session is verified and
+    /// reported as degraded (default: 60 seconds, `None` = never)
+    pub event_channel_timeout: Option<Duration>,
+
     /// Enable debug logging of protocol messages
     pub debug_protocol: bool,
 
@@ -36,9 +84,45 @@ pub struct AirPlayConfig {
     /// Delay between reconnection attempts (default: 1 second)
     pub reconnect_delay: Duration,
 
+    /// Whether the player reconnects after another sender took over the
+    /// device (default: never)
+    pub preempted_reconnect: PreemptedReconnect,
+
+    /// Set the audio stream up again when the device rejects a command as
+    /// invalid in its current state (RTSP 455 or 499), then retry the
+    /// command, instead of failing it (default: false)
+    pub resync_on_state_error: bool,
+
+    /// Time taken to move to a new volume; intermediate levels are sent
+    /// along the way to avoid pops (default: zero, jump at once)
+    pub volume_ramp: Duration,
+
+    /// Overlap consecutive queued tracks by this long with an equal-power
+    /// crossfade when the queue is streamed (default: None, gapless)
+    pub crossfade: Option<Duration>,
+
+    /// Normalize the loudness of queued tracks using their `ReplayGain`
+    /// values, or a two-pass analysis of local files that lack them
+    /// (default: None, play at recorded level)
+    pub normalization: Option<GainMode>,
+
+    /// How often the playback position is sent to the device while a PCM
+    /// stream plays, so its progress bar follows the audio (default: 2 s;
+    /// None sends progress only through `set_progress`)
+    pub progress_interval: Option<Duration>,
+
+    /// Accept DACP commands from the device while a PCM stream plays, so
+    /// its buttons and Siri reach the app as
+    /// `ClientEvent::RemoteCommand` (default: true; needs the `dacp`
+    /// feature)
+    pub remote_commands: bool,
+
     /// Audio buffer size in frames (default: 44100 = 1 second at 44.1kHz)
     pub audio_buffer_frames: usize,
 
+    /// How RTP packets are paced (default: tokio timer)
+    pub pacing: Pacing,
+
     /// Path to store persistent pairing keys (None = transient only)
     pub pairing_storage_path: Option<std::path::PathBuf>,
 
@@ -49,12 +133,33 @@ pub struct AirPlayConfig {
     /// Default is false (16-bit/44.1kHz).
     pub prefer_hires_audio: bool,
 
+    /// Stream format to ask the device for (default: 44.1 kHz 16-bit
+    /// stereo, or 48 kHz 24-bit with `prefer_hires_audio`)
+    ///
+    /// Stepped down towards 44.1 kHz 16-bit stereo when the device or the
+    /// codec cannot take it.
+    pub audio_format: Option<AudioFormat>,
+
     /// Optional PIN for pairing (if device requires one)
     pub pin: Option<String>,
 
     /// Bitrate for AAC encoding (bps) (default: `128_000`)
     pub aac_bitrate: u32,
 
+    /// Compress ALAC frames (default: true)
+    ///
+    /// When false, ALAC streams are sent as uncompressed escape frames,
+    /// trading the bandwidth of PCM for almost no encoding CPU.
+    pub alac_compression: bool,
+
+    /// Adjust the codec and AAC bitrate to what the device accepts instead
+    /// of failing to connect (default: false)
+    ///
+    /// Adjustments are reported with a [`ClientEvent::Warning`].
+    ///
+    /// [`ClientEvent::Warning`]: crate::ClientEvent::Warning
+    pub codec_fallback: bool,
+
     /// Timing protocol for clock synchronization (default: Auto)
     pub timing_protocol: TimingProtocol,
 
@@ -62,6 +167,50 @@ pub struct AirPlayConfig {
     /// When `None` (default), uses 255 so `HomePod` (248) wins BMCA and we become slave.
     /// Set to e.g. `Some(128)` to force this client to become PTP master.
     pub ptp_priority: Option<u8>,
+
+    /// Extra entries merged into the top level of the SETUP plists, replacing
+    /// any value the client would send for the same key. For experimenting
+    /// with undocumented device fields.
+    pub setup_overrides: PlistDict,
+
+    /// Extra entries merged into each stream dictionary of the stream SETUP
+    pub stream_overrides: PlistDict,
+
+    /// Connect for remote control only (default: false)
+    ///
+    /// Pairs and sets up the event channel but no timing or audio streams, so
+    /// volume, playback commands and state updates work while nothing can be
+    /// streamed. Transport methods send `MediaRemote` commands, controlling
+    /// whatever the device itself is playing.
+    pub remote_control_only: bool,
+
+    /// Key message source for the `FairPlay` `/fp-setup` handshake
+    ///
+    /// Used with devices advertising `FairPlay` auth. Without one the
+    /// handshake is skipped, which such devices may reject at SETUP.
+    pub fairplay_key_source: Option<Arc<dyn FairPlayKeySource>>,
+
+    /// Verify the device's `MFi` certificate chain during Auth-Setup
+    /// (default: false)
+    ///
+    /// When enabled, a device that fails Auth-Setup or presents a chain or
+    /// signature that does not verify is rejected. When disabled, Auth-Setup
+    /// failures are only logged.
+    pub verify_mfi: bool,
+
+    /// SRP group for Pair-Setup (default: RFC 5054 3072-bit, as used by
+    /// `HomeKit` accessories)
+    pub srp_group: SrpGroup,
+
+    /// `X-Apple-HKP` mode for PIN and stored-key pairing (default: `None`,
+    /// resolved from the device model and `GET /info`)
+    pub hkp_mode: Option<HkpMode>,
+
+    /// How announcements treat the main stream (default: duck to 25%)
+    pub announcement_policy: PreemptionPolicy,
+
+    /// How alarms treat the main stream (default: pause it)
+    pub alarm_policy: PreemptionPolicy,
 }
 
 impl Default for AirPlayConfig {
@@ -69,28 +218,191 @@ impl Default for AirPlayConfig {
         Self {
             discovery_timeout: Duration::from_secs(5),
             connection_timeout: Duration::from_secs(10),
+            request_timeout: Duration::from_secs(10),
+            event_channel_timeout: Some(Duration::from_secs(60)),
             state_poll_interval: Duration::from_millis(500),
             debug_protocol: false,
             reconnect_attempts: 3,
             reconnect_delay: Duration::from_secs(1),
+            preempted_reconnect: PreemptedReconnect::default(),
+            resync_on_state_error: false,
+            volume_ramp: Duration::ZERO,
+            crossfade: None,
+            normalization: None,
+            progress_interval: Some(Duration::from_secs(2)),
+            remote_commands: true,
             audio_buffer_frames: 44100,
+            pacing: Pacing::default(),
             pairing_storage_path: None,
             audio_codec: AudioCodec::Pcm, // Default to uncompressed PCM
             prefer_hires_audio: false,
+            audio_format: None,
             pin: None,
             aac_bitrate: 128_000,
+            alac_compression: true,
+            codec_fallback: false,
             timing_protocol: TimingProtocol::default(),
             ptp_priority: None,
+            setup_overrides: PlistDict::new(),
+            stream_overrides: PlistDict::new(),
+            remote_control_only: false,
+            fairplay_key_source: None,
+            verify_mfi: false,
+            srp_group: SrpGroup::default(),
+            hkp_mode: None,
+            announcement_policy: PreemptionPolicy::Duck { level: 0.25 },
+            alarm_policy: PreemptionPolicy::Pause,
         }
     }
 }
 
+/// Codec settings chosen for a device at connect time
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodecSelection {
+    /// Codec to stream with
+    pub(crate) codec: AudioCodec,
+    /// AAC bitrate (bps)
+    pub(crate) aac_bitrate: u32,
+    /// Format the stream is sent in
+    pub(crate) format: AudioFormat,
+    /// Adjustments made to the configuration
+    pub(crate) warnings: Vec<String>,
+}
+
 impl AirPlayConfig {
+    /// AAC bitrates receivers accept (bps)
+    pub const AAC_BITRATE_RANGE: RangeInclusive<u32> = 32_000..=320_000;
+
     /// Create a new config builder
     #[must_use]
     pub fn builder() -> AirPlayConfigBuilder {
         AirPlayConfigBuilder::default()
     }
+
+    /// Check the codec settings against what the device accepts
+    ///
+    /// With `codec_fallback` set, an unsupported codec is replaced by the
+    /// first the device accepts of ALAC, PCM, AAC and AAC-ELD, and an AAC
+    /// bitrate out of range is clamped, each with a warning. Receivers whose
+    /// [`ReceiverProfile`](crate::types::ReceiverProfile) only takes AAC are
+    /// treated as supporting nothing else, and AAC-ELD counts as unsupported
+    /// in builds without an encoder for it (the `aac-fdk` feature).
+    pub(crate) fn select_codec(
+        &self,
+        device: &AirPlayDevice,
+    ) -> Result<CodecSelection, AirPlayError> {
+        let profile = device.receiver_profile();
+        let mut supported = device.supported_codecs();
+        supported.retain(|codec| profile.accepts_codec(*codec));
+        // AAC-ELD needs fdk-aac; the pure-Rust encoder only does AAC-LC
+        if !AacEncoder::is_available(AudioObjectType::Mpeg4EnhancedLowDelay) {
+            supported.retain(|codec| *codec != AudioCodec::AacEld);
+        }
+        if supported.is_empty() {
+            supported.push(AudioCodec::Aac);
+        }
+        let mut selection = CodecSelection {
+            codec: self.audio_codec,
+            aac_bitrate: self.aac_bitrate,
+            format: AudioFormat::CD_QUALITY,
+            warnings: Vec::new(),
+        };
+
+        if !supported.contains(&self.audio_codec) {
+            let fallback = [
+                AudioCodec::Alac,
+                AudioCodec::Pcm,
+                AudioCodec::Aac,
+                AudioCodec::AacEld,
+            ]
+            .into_iter()
+            .find(|codec| supported.contains(codec));
+            let Some(fallback) = fallback.filter(|_| self.codec_fallback) else {
+                return Err(AirPlayError::UnsupportedCodec {
+                    codec: self.audio_codec,
+                    supported,
+                });
+            };
+            selection.codec = fallback;
+            selection.warnings.push(format!(
+                "{} does not support {:?}; streaming {fallback:?} instead",
+                device.name, self.audio_codec
+            ));
+        }
+
+        let uses_aac = matches!(selection.codec, AudioCodec::Aac | AudioCodec::AacEld);
+        if uses_aac && !Self::AAC_BITRATE_RANGE.contains(&self.aac_bitrate) {
+            let range = &Self::AAC_BITRATE_RANGE;
+            if !self.codec_fallback {
+                return Err(AirPlayError::InvalidParameter {
+                    name: "aac_bitrate".to_string(),
+                    message: format!(
+                        "{} bps is outside {}..={} bps",
+                        self.aac_bitrate,
+                        range.start(),
+                        range.end()
+                    ),
+                });
+            }
+            selection.aac_bitrate = self.aac_bitrate.clamp(*range.start(), *range.end());
+            selection.warnings.push(format!(
+                "AAC bitrate {} bps is outside {}..={} bps; using {} bps",
+                self.aac_bitrate,
+                range.start(),
+                range.end(),
+                selection.aac_bitrate
+            ));
+        }
+
+        let requested = self.requested_format();
+        selection.format = Self::negotiate_format(device, selection.codec, requested);
+        if self.audio_format.is_some() && selection.format != requested {
+            selection.warnings.push(format!(
+                "{} cannot take {requested:?} as {:?}; streaming {:?} instead",
+                device.name, selection.codec, selection.format
+            ));
+        }
+
+        Ok(selection)
+    }
+
+    /// Stream format asked for before negotiating with the device
+    #[must_use]
+    pub fn requested_format(&self) -> AudioFormat {
+        match self.audio_format {
+            Some(format) => format,
+            None if self.prefer_hires_audio => AudioFormat {
+                sample_format: SampleFormat::I24,
+                sample_rate: SampleRate::Hz48000,
+                channels: ChannelConfig::Stereo,
+            },
+            None => AudioFormat::CD_QUALITY,
+        }
+    }
+
+    /// Step `requested` down until the device and codec accept it
+    ///
+    /// Drops to 16 bits first, then to stereo, then to 44.1 kHz.
+    fn negotiate_format(
+        device: &AirPlayDevice,
+        codec: AudioCodec,
+        requested: AudioFormat,
+    ) -> AudioFormat {
+        let sixteen_bit = AudioFormat {
+            sample_format: SampleFormat::I16,
+            ..requested
+        };
+        let stereo = AudioFormat {
+            channels: ChannelConfig::Stereo,
+            ..sixteen_bit
+        };
+        [requested, sixteen_bit, stereo, AudioFormat::CD_QUALITY]
+            .into_iter()
+            .find(|&format| {
+                codec.encodes(format.sample_format) && device.supports_audio_format(codec, format)
+            })
+            .unwrap_or(AudioFormat::CD_QUALITY)
+    }
 }
 
 /// Builder for `AirPlayConfig`
@@ -114,6 +426,63 @@ impl AirPlayConfigBuilder {
         self
     }
 
+    /// Set how long to wait for the response to an RTSP request
+    #[must_use]
+    pub fn request_timeout(mut self, timeout: Duration) -> Self {
+        self.config.request_timeout = timeout;
+        self
+    }
+
+    /// Set the event channel silence that marks a session degraded
+    #[must_use]
+    pub fn event_channel_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.config.event_channel_timeout = timeout;
+        self
+    }
+
+    /// Recover from state errors by setting the audio stream up again
+    #[must_use]
+    pub fn resync_on_state_error(mut self, enabled: bool) -> Self {
+        self.config.resync_on_state_error = enabled;
+        self
+    }
+
+    /// Set the time taken to move to a new volume
+    #[must_use]
+    pub fn volume_ramp(mut self, ramp: Duration) -> Self {
+        self.config.volume_ramp = ramp;
+        self
+    }
+
+    /// Set the crossfade between queued tracks
+    #[must_use]
+    pub fn crossfade(mut self, fade: Option<Duration>) -> Self {
+        self.config.crossfade = fade;
+        self
+    }
+
+    /// Set loudness normalization of queued tracks
+    #[must_use]
+    pub fn normalization(mut self, mode: Option<GainMode>) -> Self {
+        self.config.normalization = mode;
+        self
+    }
+
+    /// Set how often the playback position is sent while streaming
+    #[must_use]
+    pub fn progress_interval(mut self, interval: Option<Duration>) -> Self {
+        self.config.progress_interval = interval;
+        self
+    }
+
+    /// Set whether DACP commands from the device are accepted while
+    /// streaming
+    #[must_use]
+    pub fn remote_commands(mut self, enabled: bool) -> Self {
+        self.config.remote_commands = enabled;
+        self
+    }
+
     /// Set state polling interval
     #[must_use]
     pub fn state_poll_interval(mut self, interval: Duration) -> Self {
@@ -128,6 +497,13 @@ impl AirPlayConfigBuilder {
         self
     }
 
+    /// Set how RTP packets are paced
+    #[must_use]
+    pub fn pacing(mut self, pacing: Pacing) -> Self {
+        self.config.pacing = pacing;
+        self
+    }
+
     /// Set pairing storage path for persistent pairing
     #[must_use]
     pub fn pairing_storage(mut self, path: impl Into<std::path::PathBuf>) -> Self {
@@ -149,6 +525,16 @@ impl AirPlayConfigBuilder {
         self
     }
 
+    /// Ask for a stream format other than 44.1 kHz 16-bit stereo
+    ///
+    /// Falls back towards it when the device or codec cannot take the
+    /// format.
+    #[must_use]
+    pub fn audio_format(mut self, format: AudioFormat) -> Self {
+        self.config.audio_format = Some(format);
+        self
+    }
+
     /// Set PIN for pairing
     #[must_use]
     pub fn pin(mut self, pin: impl Into<String>) -> Self {
@@ -163,6 +549,20 @@ impl AirPlayConfigBuilder {
         self
     }
 
+    /// Compress ALAC frames, or send uncompressed escape frames
+    #[must_use]
+    pub fn alac_compression(mut self, enabled: bool) -> Self {
+        self.config.alac_compression = enabled;
+        self
+    }
+
+    /// Adjust the codec to the device instead of failing to connect
+    #[must_use]
+    pub fn codec_fallback(mut self, enabled: bool) -> Self {
+        self.config.codec_fallback = enabled;
+        self
+    }
+
     /// Set timing protocol for clock synchronization
     #[must_use]
     pub fn timing_protocol(mut self, protocol: TimingProtocol) -> Self {
@@ -177,6 +577,85 @@ impl AirPlayConfigBuilder {
         self
     }
 
+    /// Merge extra entries into the SETUP plists
+    ///
+    /// Applied to the session SETUP (step 1) and the top level of the stream
+    /// SETUP (step 2). Values replace whatever the client would have sent.
+    #[must_use]
+    pub fn setup_overrides(
+        mut self,
+        overrides: impl IntoIterator<Item = (String, PlistValue)>,
+    ) -> Self {
+        self.config.setup_overrides = overrides.into_iter().collect();
+        self
+    }
+
+    /// Merge extra entries into each `streams` entry of the stream SETUP
+    #[must_use]
+    pub fn stream_overrides(
+        mut self,
+        overrides: impl IntoIterator<Item = (String, PlistValue)>,
+    ) -> Self {
+        self.config.stream_overrides = overrides.into_iter().collect();
+        self
+    }
+
+    /// Connect for remote control only, without setting up audio
+    #[must_use]
+    pub fn remote_control_only(mut self, enabled: bool) -> Self {
+        self.config.remote_control_only = enabled;
+        self
+    }
+
+    /// Set the key message source for the `FairPlay` handshake
+    #[must_use]
+    pub fn fairplay_key_source(mut self, source: impl FairPlayKeySource + 'static) -> Self {
+        self.config.fairplay_key_source = Some(Arc::new(source));
+        self
+    }
+
+    /// Verify the device's `MFi` certificate during Auth-Setup
+    #[must_use]
+    pub fn verify_mfi(mut self, enabled: bool) -> Self {
+        self.config.verify_mfi = enabled;
+        self
+    }
+
+    /// Set the SRP group used for Pair-Setup
+    #[must_use]
+    pub fn srp_group(mut self, group: SrpGroup) -> Self {
+        self.config.srp_group = group;
+        self
+    }
+
+    /// Force the `X-Apple-HKP` mode instead of resolving it per device
+    #[must_use]
+    pub fn hkp_mode(mut self, mode: HkpMode) -> Self {
+        self.config.hkp_mode = Some(mode);
+        self
+    }
+
+    /// Set whether the player reconnects after another sender took over
+    #[must_use]
+    pub fn preempted_reconnect(mut self, policy: PreemptedReconnect) -> Self {
+        self.config.preempted_reconnect = policy;
+        self
+    }
+
+    /// Set how announcements treat the main stream
+    #[must_use]
+    pub fn announcement_policy(mut self, policy: PreemptionPolicy) -> Self {
+        self.config.announcement_policy = policy;
+        self
+    }
+
+    /// Set how alarms treat the main stream
+    #[must_use]
+    pub fn alarm_policy(mut self, policy: PreemptionPolicy) -> Self {
+        self.config.alarm_policy = policy;
+        self
+    }
+
     /// Build the configuration
     #[must_use]
     pub fn build(self) -> AirPlayConfig {
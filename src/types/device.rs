@@ -1,7 +1,10 @@
 use std::collections::HashMap;
 use std::net::IpAddr;
 
-use super::raop::RaopCapabilities;
+use super::raop::{RaopCapabilities, RaopCodec};
+use crate::audio::{AudioCodec, AudioFormat, SampleFormat, SampleRate};
+use crate::discovery::txt::AirPlayTxt;
+use crate::protocol::plist::PlistValue;
 
 /// Represents a discovered `AirPlay` 2 device on the network
 #[derive(Debug, Clone)]
@@ -30,7 +33,8 @@ pub struct AirPlayDevice {
     /// RAOP capabilities parsed from TXT records
     pub raop_capabilities: Option<RaopCapabilities>,
 
-    /// Raw TXT record data for protocol use
+    /// Raw TXT record data for protocol use; see [`AirPlayDevice::txt`] for a
+    /// typed view
     pub txt_records: HashMap<String, String>,
 
     /// Last time the device was seen/announced
@@ -74,10 +78,208 @@ pub struct DeviceCapabilities {
     /// Supports PTP (IEEE 1588) clock synchronization
     pub supports_ptp: bool,
 
+    /// A password must be supplied to connect
+    pub requires_password: bool,
+
+    /// The device must be paired (PIN or `HomeKit` setup) before connecting
+    pub requires_homekit_pairing: bool,
+
+    /// Access is limited, e.g. to members of the device's `HomeKit` home
+    pub access_control_restricted: bool,
+
+    /// Codecs listed in the `GET /info` `audioFormats` (empty if unknown)
+    pub audio_codecs: Vec<AudioCodec>,
+
+    /// `audioFormat` bits the `GET /info` `audioFormats` accept as input,
+    /// across all codecs (0 if unknown)
+    pub audio_input_formats: u64,
+
     /// Raw features bitmask
     pub raw_features: u64,
 }
 
+/// Family of Apple hardware a device belongs to
+///
+/// Resolved from model identifiers such as "AppleTV11,1" to apply
+/// per-device protocol quirks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeviceModel {
+    /// `HomePod` (1st or 2nd generation)
+    HomePod,
+    /// `HomePod` mini
+    HomePodMini,
+    /// Apple TV HD (tvOS)
+    AppleTv,
+    /// Apple TV 4K (tvOS)
+    AppleTv4k,
+    /// Apple TV 2nd/3rd generation (pre-tvOS)
+    AppleTvLegacy,
+    /// `AirPort` Express
+    AirPortExpress,
+    /// Mac running the `AirPlay` receiver
+    Mac,
+    /// Anything else, including third-party receivers
+    Other,
+}
+
+impl DeviceModel {
+    /// Resolve a model identifier (e.g. "AppleTV6,2")
+    #[must_use]
+    pub fn from_identifier(model: &str) -> Self {
+        let family = model.split_once(',').map_or(model, |(family, _)| family);
+        let major: Option<u32> = family
+            .trim_start_matches(|c: char| !c.is_ascii_digit())
+            .parse()
+            .ok();
+        let family = family.trim_end_matches(|c: char| c.is_ascii_digit());
+
+        match (family, major) {
+            ("AudioAccessory", Some(5)) => Self::HomePodMini,
+            ("AudioAccessory", _) => Self::HomePod,
+            ("AppleTV", Some(2..=3)) => Self::AppleTvLegacy,
+            ("AppleTV", Some(5)) => Self::AppleTv,
+            ("AppleTV", Some(_)) => Self::AppleTv4k,
+            ("AirPort", _) => Self::AirPortExpress,
+            (
+                "Mac" | "MacBookPro" | "MacBookAir" | "MacBook" | "iMac" | "Macmini" | "MacPro",
+                _,
+            ) => Self::Mac,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether the device runs tvOS
+    #[must_use]
+    pub fn runs_tvos(self) -> bool {
+        matches!(self, Self::AppleTv | Self::AppleTv4k)
+    }
+
+    /// Whether to send ANNOUNCE before SETUP
+    ///
+    /// `AirPlay` 2 sessions negotiate the format in the SETUP plist, and
+    /// `HomePod`s answer ANNOUNCE with 455, so it is only sent there to carry
+    /// the AAC-ELD config. tvOS rejects it on `AirPlay` 2 sessions outright.
+    #[must_use]
+    pub fn accepts_announce(self, airplay2: bool, codec: AudioCodec) -> bool {
+        !airplay2 || (codec == AudioCodec::AacEld && !self.runs_tvos())
+    }
+}
+
+/// Maker of a receiver
+///
+/// Third-party `AirPlay` 2 stacks deviate from Apple's in ways the sender has
+/// to accommodate; see [`ReceiverProfile`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiverVendor {
+    /// Apple hardware
+    Apple,
+    /// Sonos speakers and soundbars
+    Sonos,
+    /// Bose smart speakers and soundbars
+    Bose,
+    /// Denon and Marantz (HEOS) receivers
+    Denon,
+    /// Anything else
+    Other,
+}
+
+impl ReceiverVendor {
+    /// Resolve a manufacturer or model name (e.g. "Sonos, Inc.", "Denon
+    /// AVR-X3700H")
+    #[must_use]
+    pub fn from_name(name: &str) -> Self {
+        let name = name.to_ascii_lowercase();
+        let has = |word: &str| {
+            name.split(|c: char| !c.is_ascii_alphanumeric() && c != '&')
+                .any(|part| part == word)
+        };
+        if has("apple") {
+            Self::Apple
+        } else if has("sonos") {
+            Self::Sonos
+        } else if has("bose") {
+            Self::Bose
+        } else if has("denon") || has("marantz") || has("heos") || has("d&m") {
+            Self::Denon
+        } else {
+            Self::Other
+        }
+    }
+}
+
+/// Protocol deviations a family of receivers needs
+///
+/// Selected from the vendor with [`AirPlayDevice::receiver_profile`]. Latency
+/// bounds are in samples at 44.1 kHz, as sent in the SETUP stream plist.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReceiverProfile {
+    /// Maker the profile applies to
+    pub vendor: ReceiverVendor,
+    /// `POST /auth-setup` must succeed before pairing, rather than being
+    /// attempted on a best-effort basis
+    pub auth_setup_required: bool,
+    /// Only real-time (type 96) AAC streams are accepted
+    pub realtime_aac_only: bool,
+    /// Lowest latency to request
+    pub latency_min: u32,
+    /// Highest latency to request
+    pub latency_max: u32,
+    /// `POST /feedback` keep-alives are answered; otherwise the session is
+    /// kept alive with `GET /info`
+    pub feedback: bool,
+}
+
+impl ReceiverProfile {
+    /// Behaviour of Apple receivers, assumed for unknown vendors
+    pub const STANDARD: Self = Self {
+        vendor: ReceiverVendor::Other,
+        auth_setup_required: false,
+        realtime_aac_only: false,
+        latency_min: 11_025,
+        latency_max: 88_200,
+        feedback: true,
+    };
+
+    /// Profile for receivers made by `vendor`
+    #[must_use]
+    pub fn for_vendor(vendor: ReceiverVendor) -> Self {
+        let third_party = Self {
+            vendor,
+            auth_setup_required: true,
+            realtime_aac_only: true,
+            feedback: false,
+            ..Self::STANDARD
+        };
+        match vendor {
+            ReceiverVendor::Apple | ReceiverVendor::Other => Self {
+                vendor,
+                ..Self::STANDARD
+            },
+            // Sonos drops audio that arrives under half a second early
+            ReceiverVendor::Sonos => Self {
+                latency_min: 22_050,
+                ..third_party
+            },
+            ReceiverVendor::Bose => Self {
+                latency_min: 44_100,
+                ..third_party
+            },
+            // HEOS buffers deeper than Apple's 2 s ceiling
+            ReceiverVendor::Denon => Self {
+                latency_min: 44_100,
+                latency_max: 132_300,
+                ..third_party
+            },
+        }
+    }
+
+    /// Whether the receiver takes a stream encoded with `codec`
+    #[must_use]
+    pub fn accepts_codec(&self, codec: AudioCodec) -> bool {
+        !self.realtime_aac_only || codec == AudioCodec::Aac
+    }
+}
+
 impl PartialEq for AirPlayDevice {
     fn eq(&self, other: &Self) -> bool {
         // Ignore `last_seen` when comparing devices for equality
@@ -94,6 +296,216 @@ impl PartialEq for AirPlayDevice {
 }
 
 impl AirPlayDevice {
+    /// Create a device for a known address, bypassing mDNS discovery
+    ///
+    /// Only the address and port are known; the ID and name default to the
+    /// socket address until updated from a `GET /info` response with
+    /// [`update_from_info`](Self::update_from_info).
+    #[must_use]
+    pub fn from_address(ip: IpAddr, port: u16) -> Self {
+        let id = std::net::SocketAddr::new(ip, port).to_string();
+        Self {
+            name: id.clone(),
+            id,
+            model: None,
+            addresses: vec![ip],
+            port,
+            capabilities: DeviceCapabilities::default(),
+            raop_port: None,
+            raop_capabilities: None,
+            txt_records: HashMap::new(),
+            last_seen: None,
+        }
+    }
+
+    /// Populate identity and capabilities from a `GET /info` response
+    ///
+    /// Fields the response does not contain are left unchanged. Values that
+    /// discovery would normally provide through TXT records are mirrored into
+    /// `txt_records` under their TXT key names.
+    pub fn update_from_info(&mut self, info: &PlistValue) {
+        let Some(dict) = info.as_dict() else {
+            return;
+        };
+        let string = |key: &str| dict.get(key).and_then(PlistValue::as_str);
+
+        if let Some(id) = string("deviceID") {
+            self.id = id.to_string();
+            self.txt_records
+                .insert("deviceid".to_string(), id.to_string());
+        }
+        if let Some(name) = string("name") {
+            self.name = name.to_string();
+        }
+        if let Some(model) = string("model") {
+            self.model = Some(model.to_string());
+            self.txt_records
+                .insert("model".to_string(), model.to_string());
+        }
+        if let Some(manufacturer) = string("manufacturer") {
+            self.txt_records
+                .insert("manufacturer".to_string(), manufacturer.to_string());
+        }
+        if let Some(version) = string("sourceVersion") {
+            self.txt_records
+                .insert("srcvers".to_string(), version.to_string());
+        }
+        if let Some(pk) = dict.get("pk").and_then(PlistValue::as_bytes) {
+            self.txt_records.insert("pk".to_string(), hex::encode(pk));
+        }
+
+        let features = match dict.get("features") {
+            Some(PlistValue::String(text)) => {
+                crate::discovery::parser::parse_features(text).map(|caps| caps.raw_features)
+            }
+            Some(value) => value.as_u64(),
+            None => None,
+        };
+        if let Some(features) = features {
+            self.capabilities = DeviceCapabilities::from_features(features);
+            self.txt_records.insert(
+                "features".to_string(),
+                format!("0x{:X},0x{:X}", features & 0xFFFF_FFFF, features >> 32),
+            );
+        }
+        if let Some(formats) = dict.get("audioFormats").and_then(PlistValue::as_array) {
+            self.capabilities.audio_codecs = formats
+                .iter()
+                .filter_map(|format| format.as_dict()?.get("type")?.as_u64())
+                .filter_map(|type_id| match type_id {
+                    96 => Some(AudioCodec::Alac),
+                    97 => Some(AudioCodec::Aac),
+                    98 => Some(AudioCodec::AacEld),
+                    100 => Some(AudioCodec::Pcm),
+                    _ => None,
+                })
+                .collect();
+            self.capabilities.audio_input_formats = formats
+                .iter()
+                .filter_map(|format| format.as_dict()?.get("audioInputFormats")?.as_u64())
+                .fold(0, |mask, formats| mask | formats);
+        }
+        if let Some(flags) = dict.get("statusFlags").and_then(PlistValue::as_u64) {
+            self.txt_records
+                .insert("flags".to_string(), format!("0x{flags:X}"));
+        }
+        self.capabilities.update_auth_hints(&self.txt_records);
+    }
+
+    /// Whether a password must be supplied to connect
+    ///
+    /// Set by either the `AirPlay` or RAOP advertisement.
+    #[must_use]
+    pub fn requires_password(&self) -> bool {
+        self.capabilities.requires_password
+            || self
+                .raop_capabilities
+                .as_ref()
+                .is_some_and(|raop| raop.password_required)
+    }
+
+    /// Hardware address of the device, if known
+    ///
+    /// Taken from the `deviceid` TXT record, falling back to the device ID,
+    /// which is the MAC address for devices found over RAOP.
+    #[must_use]
+    pub fn mac_address(&self) -> Option<[u8; 6]> {
+        self.txt()
+            .mac_address()
+            .or_else(|| crate::discovery::parser::parse_mac_address(&self.id))
+    }
+
+    /// Hardware family, from the model identifier
+    #[must_use]
+    pub fn device_model(&self) -> DeviceModel {
+        self.model
+            .as_deref()
+            .or(self.txt_records.get("model").map(String::as_str))
+            .map_or(DeviceModel::Other, DeviceModel::from_identifier)
+    }
+
+    /// Maker of the device
+    ///
+    /// From the advertised manufacturer, falling back to the model name.
+    #[must_use]
+    pub fn receiver_vendor(&self) -> ReceiverVendor {
+        if self.device_model() != DeviceModel::Other {
+            return ReceiverVendor::Apple;
+        }
+        [
+            self.txt_records.get("manufacturer").map(String::as_str),
+            self.model.as_deref(),
+            self.txt_records.get("model").map(String::as_str),
+        ]
+        .into_iter()
+        .flatten()
+        .map(ReceiverVendor::from_name)
+        .find(|vendor| *vendor != ReceiverVendor::Other)
+        .unwrap_or(ReceiverVendor::Other)
+    }
+
+    /// Protocol deviations to apply for the device
+    #[must_use]
+    pub fn receiver_profile(&self) -> ReceiverProfile {
+        ReceiverProfile::for_vendor(self.receiver_vendor())
+    }
+
+    /// Typed view of the TXT records
+    #[must_use]
+    pub fn txt(&self) -> AirPlayTxt {
+        AirPlayTxt::parse(&self.txt_records)
+    }
+
+    /// Whether the device accepts `format` streamed as `codec`
+    ///
+    /// Checked against the `audioInputFormats` from `GET /info` when known.
+    /// Otherwise 44.1 kHz 16-bit is assumed to work, and anything above it
+    /// only on devices advertising high-resolution audio.
+    #[must_use]
+    pub fn supports_audio_format(&self, codec: AudioCodec, format: AudioFormat) -> bool {
+        let Some(mask) = format.audio_format_mask(codec) else {
+            return false;
+        };
+        if self.capabilities.audio_input_formats != 0 {
+            return self.capabilities.audio_input_formats & mask != 0;
+        }
+        let cd_quality =
+            format.sample_rate == SampleRate::Hz44100 && format.sample_format == SampleFormat::I16;
+        cd_quality || self.capabilities.supports_hires_audio
+    }
+
+    /// Codecs the device accepts for audio streams
+    ///
+    /// Taken from the `GET /info` `audioFormats` when known, then from the
+    /// RAOP `cn` list of `AirPlay` 1 receivers. Without either, every codec
+    /// the `AirPlay` protocol defines is assumed (Opus is not among them).
+    #[must_use]
+    pub fn supported_codecs(&self) -> Vec<AudioCodec> {
+        if !self.capabilities.audio_codecs.is_empty() {
+            return self.capabilities.audio_codecs.clone();
+        }
+        if let Some(raop) = &self.raop_capabilities {
+            if !self.supports_airplay2() && !raop.codecs.is_empty() {
+                return raop
+                    .codecs
+                    .iter()
+                    .map(|codec| match codec {
+                        RaopCodec::Pcm => AudioCodec::Pcm,
+                        RaopCodec::Alac => AudioCodec::Alac,
+                        RaopCodec::Aac => AudioCodec::Aac,
+                        RaopCodec::AacEld => AudioCodec::AacEld,
+                    })
+                    .collect();
+            }
+        }
+        vec![
+            AudioCodec::Pcm,
+            AudioCodec::Alac,
+            AudioCodec::Aac,
+            AudioCodec::AacEld,
+        ]
+    }
+
     /// Check if this device supports `AirPlay` 2 features
     #[must_use]
     pub fn supports_airplay2(&self) -> bool {
@@ -121,7 +533,7 @@ impl AirPlayDevice {
     /// Get device volume if available from discovery
     #[must_use]
     pub fn discovered_volume(&self) -> Option<f32> {
-        self.txt_records.get("vv").and_then(|v| v.parse().ok())
+        self.txt().volume
     }
 
     /// Get the primary IP address (prefers IPv4 for better connectivity)
@@ -173,4 +585,30 @@ impl DeviceCapabilities {
             ..Default::default()
         }
     }
+
+    /// Whether the device plays HTTP Live Streaming URLs itself
+    ///
+    /// Feature bit 3; Apple TVs set it, speakers generally do not.
+    #[must_use]
+    pub fn supports_hls(&self) -> bool {
+        self.raw_features & crate::discovery::parser::feature_bits::VIDEO_HLS != 0
+    }
+
+    /// Update the authentication hints from TXT records
+    ///
+    /// Reads `pw`, the `flags` (or RAOP `sf`) status flags and `acl`, so
+    /// applications can ask for credentials before connecting instead of
+    /// failing during the handshake. Feature-derived fields are unchanged.
+    pub fn update_auth_hints(&mut self, txt_records: &HashMap<String, String>) {
+        use crate::discovery::parser::status_flags;
+        use crate::discovery::txt::AirPlayTxt;
+
+        let txt = AirPlayTxt::parse(txt_records);
+        self.requires_password =
+            txt.password || txt.has_status_flag(status_flags::PASSWORD_REQUIRED);
+        self.requires_homekit_pairing = txt
+            .has_status_flag(status_flags::PIN_REQUIRED | status_flags::ONE_TIME_PAIRING_REQUIRED);
+        self.access_control_restricted = txt.access_control_level.is_some_and(|acl| acl != 0)
+            || txt.has_status_flag(status_flags::HOMEKIT_ACCESS_CONTROL);
+    }
 }
@@ -1,5 +1,6 @@
 //! Core types for the airplay2 library
 
+#[cfg(feature = "sender")]
 mod config;
 mod device;
 /// RAOP (`AirPlay` 1) types
@@ -11,8 +12,11 @@ mod track;
 #[cfg(test)]
 mod tests;
 
-pub use config::{AirPlayConfig, AirPlayConfigBuilder, TimingProtocol};
-pub use device::{AirPlayDevice, DeviceCapabilities};
+#[cfg(feature = "sender")]
+pub(crate) use config::CodecSelection;
+#[cfg(feature = "sender")]
+pub use config::{AirPlayConfig, AirPlayConfigBuilder, PreemptedReconnect, TimingProtocol};
+pub use device::{AirPlayDevice, DeviceCapabilities, DeviceModel, ReceiverProfile, ReceiverVendor};
 pub use raop::{RaopCapabilities, RaopCodec, RaopEncryption, RaopMetadataType};
 pub use state::{ConnectionState, PlaybackInfo, PlaybackState, RepeatMode};
 pub use track::{QueueItem, QueueItemId, TrackInfo};
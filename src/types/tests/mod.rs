@@ -1,11 +1,13 @@
 mod raop;
 
+#[cfg(feature = "sender")]
 use std::time::Duration;
 
 use super::*;
 
 // --- config.rs tests ---
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_config_defaults() {
     let config = AirPlayConfig::default();
@@ -18,8 +20,11 @@ fn test_config_defaults() {
     assert_eq!(config.reconnect_delay, Duration::from_secs(1));
     assert_eq!(config.audio_buffer_frames, 44100);
     assert!(config.pairing_storage_path.is_none());
+    assert_eq!(config.progress_interval, Some(Duration::from_secs(2)));
+    assert!(config.remote_commands);
 }
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_config_builder() {
     let path = std::path::PathBuf::from("/tmp");
@@ -29,6 +34,8 @@ fn test_config_builder() {
         .state_poll_interval(Duration::from_secs(1))
         .debug_protocol(true)
         .pairing_storage(path.clone())
+        .progress_interval(None)
+        .remote_commands(false)
         .build();
 
     assert_eq!(config.discovery_timeout, Duration::from_secs(10));
@@ -36,6 +43,8 @@ fn test_config_builder() {
     assert_eq!(config.state_poll_interval, Duration::from_secs(1));
     assert!(config.debug_protocol);
     assert_eq!(config.pairing_storage_path, Some(path));
+    assert!(config.progress_interval.is_none());
+    assert!(!config.remote_commands);
 }
 
 // --- device.rs tests ---
@@ -132,20 +141,548 @@ fn test_device_discovered_volume() {
     assert_eq!(device.discovered_volume(), Some(2.0));
 }
 
+#[test]
+fn test_device_from_address_and_info() {
+    use crate::protocol::plist::{PlistDict, PlistValue};
+
+    let ip: std::net::IpAddr = "192.168.1.50".parse().unwrap();
+    let mut device = AirPlayDevice::from_address(ip, 7000);
+    assert_eq!(device.id, "192.168.1.50:7000");
+    assert_eq!(device.address(), ip);
+    assert!(!device.supports_airplay2());
+
+    let mut info = PlistDict::new();
+    info.insert(
+        "deviceID".to_string(),
+        PlistValue::String("AA:BB:CC:DD:EE:FF".to_string()),
+    );
+    info.insert(
+        "name".to_string(),
+        PlistValue::String("Kitchen".to_string()),
+    );
+    info.insert(
+        "model".to_string(),
+        PlistValue::String("AudioAccessory5,1".to_string()),
+    );
+    info.insert(
+        "features".to_string(),
+        PlistValue::UnsignedInteger(0x0001_C340_405F_8A00),
+    );
+    info.insert("pk".to_string(), PlistValue::Data(vec![0xAB, 0x01]));
+    device.update_from_info(&PlistValue::Dictionary(info));
+
+    assert_eq!(device.id, "AA:BB:CC:DD:EE:FF");
+    assert_eq!(device.name, "Kitchen");
+    assert_eq!(device.model.as_deref(), Some("AudioAccessory5,1"));
+    assert!(device.supports_airplay2());
+    assert_eq!(device.capabilities.raw_features, 0x0001_C340_405F_8A00);
+    assert_eq!(device.txt_records["features"], "0x405F8A00,0x1C340");
+    assert_eq!(device.txt_records["pk"], "ab01");
+
+    // String feature values use the TXT record format
+    let mut info = PlistDict::new();
+    info.insert(
+        "features".to_string(),
+        PlistValue::String("0x5A7FFFF7,0x1E".to_string()),
+    );
+    device.update_from_info(&PlistValue::Dictionary(info));
+    assert_eq!(device.capabilities.raw_features, 0x1E_5A7F_FFF7);
+    assert_eq!(device.name, "Kitchen");
+    assert!(!device.requires_password());
+
+    let mut info = PlistDict::new();
+    info.insert("statusFlags".to_string(), PlistValue::UnsignedInteger(0x88));
+    device.update_from_info(&PlistValue::Dictionary(info));
+    assert!(device.requires_password());
+    assert!(device.capabilities.requires_homekit_pairing);
+}
+
+#[test]
+fn test_device_model_from_identifier() {
+    use crate::audio::AudioCodec;
+
+    let model = DeviceModel::from_identifier;
+    assert_eq!(model("AudioAccessory1,1"), DeviceModel::HomePod);
+    assert_eq!(model("AudioAccessory6,1"), DeviceModel::HomePod);
+    assert_eq!(model("AudioAccessory5,1"), DeviceModel::HomePodMini);
+    assert_eq!(model("AppleTV3,2"), DeviceModel::AppleTvLegacy);
+    assert_eq!(model("AppleTV5,3"), DeviceModel::AppleTv);
+    assert_eq!(model("AppleTV6,2"), DeviceModel::AppleTv4k);
+    assert_eq!(model("AppleTV14,1"), DeviceModel::AppleTv4k);
+    assert_eq!(model("AirPort10,115"), DeviceModel::AirPortExpress);
+    assert_eq!(model("MacBookPro18,3"), DeviceModel::Mac);
+    assert_eq!(model("Sonos One"), DeviceModel::Other);
+    assert_eq!(model(""), DeviceModel::Other);
+
+    assert!(DeviceModel::AppleTv4k.runs_tvos());
+    assert!(!DeviceModel::AppleTvLegacy.runs_tvos());
+
+    assert!(DeviceModel::HomePod.accepts_announce(false, AudioCodec::Alac));
+    assert!(!DeviceModel::HomePod.accepts_announce(true, AudioCodec::Alac));
+    assert!(DeviceModel::HomePod.accepts_announce(true, AudioCodec::AacEld));
+    assert!(!DeviceModel::AppleTv4k.accepts_announce(true, AudioCodec::AacEld));
+    assert!(DeviceModel::AppleTv4k.accepts_announce(false, AudioCodec::AacEld));
+}
+
+/// Device described by the `GET /info` reply in `tests/fixtures/info/<name>.bplist`
+fn info_fixture(name: &str) -> AirPlayDevice {
+    let path = format!("tests/fixtures/info/{name}.bplist");
+    let data = std::fs::read(&path).expect("Fixture not found");
+    let info = crate::protocol::plist::decode(&data).unwrap();
+    let mut device = AirPlayDevice::from_address("192.168.1.60".parse().unwrap(), 7000);
+    device.update_from_info(&info);
+    device
+}
+
+#[test]
+fn test_device_model_from_info_fixtures() {
+    use crate::audio::AudioCodec;
+
+    // Apple TV 4K on tvOS 17: ANNOUNCE only outside AirPlay 2 sessions
+    let apple_tv = info_fixture("apple_tv_4k");
+    assert_eq!(apple_tv.model.as_deref(), Some("AppleTV11,1"));
+    assert_eq!(apple_tv.device_model(), DeviceModel::AppleTv4k);
+    assert_eq!(apple_tv.capabilities.raw_features, 0xBC15_7FDE_4A7F_DFD5);
+    assert!(apple_tv.supports_airplay2());
+    let model = apple_tv.device_model();
+    assert!(!model.accepts_announce(true, AudioCodec::Alac));
+    assert!(!model.accepts_announce(true, AudioCodec::AacEld));
+    assert!(model.accepts_announce(false, AudioCodec::Alac));
+
+    // HomePod mini: ANNOUNCE carries the AAC-ELD config on AirPlay 2
+    let homepod = info_fixture("homepod_mini");
+    assert_eq!(homepod.device_model(), DeviceModel::HomePodMini);
+    assert!(homepod.supports_airplay2());
+    let model = homepod.device_model();
+    assert!(!model.accepts_announce(true, AudioCodec::Alac));
+    assert!(model.accepts_announce(true, AudioCodec::AacEld));
+}
+
+#[cfg(feature = "sender")]
+#[test]
+fn test_hkp_mode_from_info() {
+    use crate::protocol::pairing::HkpMode;
+    use crate::protocol::plist::{PlistDict, PlistValue};
+
+    // GET /info from an Apple TV 4K (tvOS 17), reduced to the fields used
+    let info = |status_flags: u64| {
+        let mut info = PlistDict::new();
+        info.insert(
+            "model".to_string(),
+            PlistValue::String("AppleTV11,1".to_string()),
+        );
+        info.insert(
+            "features".to_string(),
+            PlistValue::UnsignedInteger(0xBC15_7FDE_4A7F_DFD5),
+        );
+        info.insert(
+            "statusFlags".to_string(),
+            PlistValue::UnsignedInteger(status_flags),
+        );
+        info.insert(
+            "sourceVersion".to_string(),
+            PlistValue::String("775.3.1".to_string()),
+        );
+        PlistValue::Dictionary(info)
+    };
+
+    let ip = "192.168.1.60".parse().unwrap();
+    let mut apple_tv = AirPlayDevice::from_address(ip, 7000);
+    assert_eq!(apple_tv.device_model(), DeviceModel::Other);
+    assert_eq!(HkpMode::resolve(&apple_tv), HkpMode::Transient);
+
+    apple_tv.update_from_info(&info(0x244));
+    assert_eq!(apple_tv.device_model(), DeviceModel::AppleTv4k);
+    assert_eq!(HkpMode::resolve(&apple_tv), HkpMode::Normal);
+
+    // Under HomeKit access control tvOS expects system pairing
+    apple_tv.update_from_info(&info(0x644));
+    assert_eq!(HkpMode::resolve(&apple_tv), HkpMode::System);
+
+    let mut homepod = AirPlayDevice::from_address(ip, 7000);
+    homepod.model = Some("AudioAccessory5,1".to_string());
+    assert_eq!(HkpMode::resolve(&homepod), HkpMode::Transient);
+
+    assert_eq!(HkpMode::Normal.header_value(), "3");
+    assert_eq!(HkpMode::Transient.header_value(), "4");
+    assert_eq!(HkpMode::System.header_value(), "6");
+
+    let config = AirPlayConfig::builder().hkp_mode(HkpMode::Normal).build();
+    assert_eq!(config.hkp_mode, Some(HkpMode::Normal));
+    assert_eq!(AirPlayConfig::default().hkp_mode, None);
+}
+
+#[test]
+fn test_auth_hints_from_txt_records() {
+    use std::collections::HashMap;
+
+    let txt = |pairs: &[(&str, &str)]| -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), (*v).to_string()))
+            .collect()
+    };
+
+    let mut caps = DeviceCapabilities::from_features(0x0001_C340_405F_8A00);
+    caps.update_auth_hints(&txt(&[("flags", "0x4"), ("pw", "false"), ("acl", "0")]));
+    assert!(!caps.requires_password);
+    assert!(!caps.requires_homekit_pairing);
+    assert!(!caps.access_control_restricted);
+    assert!(caps.airplay2);
+
+    caps.update_auth_hints(&txt(&[("pw", "true")]));
+    assert!(caps.requires_password);
+
+    caps.update_auth_hints(&txt(&[("flags", "0x608")]));
+    assert!(!caps.requires_password);
+    assert!(caps.requires_homekit_pairing);
+    assert!(caps.access_control_restricted);
+
+    caps.update_auth_hints(&txt(&[("acl", "1")]));
+    assert!(caps.access_control_restricted);
+    assert!(!caps.requires_homekit_pairing);
+
+    // RAOP advertises the same bits as decimal `sf`
+    caps.update_auth_hints(&txt(&[("sf", "128")]));
+    assert!(caps.requires_password);
+
+    // RAOP `pw` alone also counts for the device
+    let mut device = AirPlayDevice::from_address("10.0.0.2".parse().unwrap(), 5000);
+    assert!(!device.requires_password());
+    device.raop_capabilities = Some(crate::types::RaopCapabilities::from_txt_records(&txt(&[(
+        "pw", "true",
+    )])));
+    assert!(device.requires_password());
+}
+
+#[test]
+fn test_supported_codecs_from_info_audio_formats() {
+    use crate::audio::AudioCodec;
+    use crate::protocol::plist::{PlistDict, PlistValue};
+
+    let mut device = AirPlayDevice::from_address("10.0.0.3".parse().unwrap(), 7000);
+    assert!(device.supported_codecs().contains(&AudioCodec::AacEld));
+    assert!(!device.supported_codecs().contains(&AudioCodec::Opus));
+
+    let format = |type_id| {
+        PlistValue::Dictionary(PlistDict::from([(
+            "type".to_string(),
+            PlistValue::Integer(type_id),
+        )]))
+    };
+    let info = PlistDict::from([(
+        "audioFormats".to_string(),
+        PlistValue::Array(vec![format(100), format(96), format(101)]),
+    )]);
+    device.update_from_info(&PlistValue::Dictionary(info));
+    assert_eq!(
+        device.supported_codecs(),
+        vec![AudioCodec::Pcm, AudioCodec::Alac]
+    );
+}
+
+/// Device advertising the TXT records in `tests/fixtures/receivers/<name>.txt`
+#[cfg(feature = "sender")]
+fn receiver_fixture(name: &str) -> AirPlayDevice {
+    let path = format!("tests/fixtures/receivers/{name}.txt");
+    let records: Vec<String> = std::fs::read_to_string(&path)
+        .expect("Fixture not found")
+        .lines()
+        .map(str::to_string)
+        .collect();
+    let txt_records = crate::discovery::parse_txt_records(&records);
+    let mut device = AirPlayDevice::from_address("10.0.0.9".parse().unwrap(), 7000);
+    device.name = name.to_string();
+    device.capabilities = txt_records
+        .get("features")
+        .and_then(|features| crate::discovery::parser::parse_features(features))
+        .unwrap_or_default();
+    device.txt_records = txt_records;
+    device
+}
+
+#[cfg(feature = "sender")]
+#[test]
+fn test_receiver_profiles_from_fixtures() {
+    use crate::audio::AudioCodec;
+
+    let cases = [
+        ("sonos_one", ReceiverVendor::Sonos, 22_050, 88_200),
+        (
+            "bose_home_speaker_500",
+            ReceiverVendor::Bose,
+            44_100,
+            88_200,
+        ),
+        ("denon_avr_x3700h", ReceiverVendor::Denon, 44_100, 132_300),
+    ];
+    for (fixture, vendor, latency_min, latency_max) in cases {
+        let device = receiver_fixture(fixture);
+        let profile = device.receiver_profile();
+        assert_eq!(profile.vendor, vendor, "{fixture}");
+        assert!(profile.auth_setup_required, "{fixture}");
+        assert!(profile.realtime_aac_only, "{fixture}");
+        assert!(!profile.feedback, "{fixture}");
+        assert_eq!(
+            (profile.latency_min, profile.latency_max),
+            (latency_min, latency_max),
+            "{fixture}"
+        );
+
+        // Only AAC is offered, whatever the advertisement says
+        let strict = AirPlayConfig::builder()
+            .audio_codec(AudioCodec::Alac)
+            .build();
+        assert!(strict.select_codec(&device).is_err(), "{fixture}");
+        let fallback = AirPlayConfig::builder()
+            .audio_codec(AudioCodec::Alac)
+            .codec_fallback(true)
+            .build();
+        assert_eq!(
+            fallback.select_codec(&device).unwrap().codec,
+            AudioCodec::Aac,
+            "{fixture}"
+        );
+    }
+
+    for (fixture, vendor) in [
+        ("homepod_mini", ReceiverVendor::Apple),
+        ("unknown_vendor", ReceiverVendor::Other),
+    ] {
+        let device = receiver_fixture(fixture);
+        assert_eq!(device.receiver_vendor(), vendor, "{fixture}");
+        let profile = device.receiver_profile();
+        assert_eq!(
+            ReceiverProfile { vendor, ..profile },
+            ReceiverProfile {
+                vendor,
+                ..ReceiverProfile::STANDARD
+            },
+            "{fixture}"
+        );
+        let pcm = AirPlayConfig::builder()
+            .audio_codec(AudioCodec::Pcm)
+            .build();
+        assert_eq!(pcm.select_codec(&device).unwrap().codec, AudioCodec::Pcm);
+    }
+}
+
+#[test]
+fn test_receiver_vendor_from_name() {
+    assert_eq!(
+        ReceiverVendor::from_name("Sonos, Inc."),
+        ReceiverVendor::Sonos
+    );
+    assert_eq!(
+        ReceiverVendor::from_name("Marantz SR7015"),
+        ReceiverVendor::Denon
+    );
+    assert_eq!(
+        ReceiverVendor::from_name("Apple Inc."),
+        ReceiverVendor::Apple
+    );
+    // Whole words only
+    assert_eq!(ReceiverVendor::from_name("Embosed"), ReceiverVendor::Other);
+
+    // The manufacturer reported by /info is picked up
+    let mut device = AirPlayDevice::from_address("10.0.0.9".parse().unwrap(), 7000);
+    let mut info = crate::protocol::plist::PlistDict::new();
+    info.insert(
+        "manufacturer".to_string(),
+        crate::protocol::plist::PlistValue::String("Sonos".to_string()),
+    );
+    device.update_from_info(&crate::protocol::plist::PlistValue::Dictionary(info));
+    assert_eq!(device.receiver_vendor(), ReceiverVendor::Sonos);
+}
+
+#[cfg(feature = "sender")]
+#[test]
+fn test_select_codec_against_device() {
+    use crate::audio::AudioCodec;
+    use crate::error::AirPlayError;
+
+    let mut device = AirPlayDevice::from_address("10.0.0.3".parse().unwrap(), 7000);
+    device.capabilities.audio_codecs = vec![AudioCodec::Pcm, AudioCodec::Aac];
+
+    let strict = AirPlayConfig::builder()
+        .audio_codec(AudioCodec::Alac)
+        .build();
+    let Err(AirPlayError::UnsupportedCodec { codec, supported }) = strict.select_codec(&device)
+    else {
+        panic!("expected UnsupportedCodec");
+    };
+    assert_eq!(codec, AudioCodec::Alac);
+    assert_eq!(supported, vec![AudioCodec::Pcm, AudioCodec::Aac]);
+
+    let fallback = AirPlayConfig::builder()
+        .audio_codec(AudioCodec::Alac)
+        .codec_fallback(true)
+        .build();
+    let selection = fallback.select_codec(&device).unwrap();
+    assert_eq!(selection.codec, AudioCodec::Pcm);
+    assert_eq!(selection.warnings.len(), 1);
+
+    // The AAC bitrate is only checked when streaming AAC
+    let aac = AirPlayConfig::builder()
+        .audio_codec(AudioCodec::Aac)
+        .aac_bitrate(1_000_000)
+        .build();
+    assert!(matches!(
+        aac.select_codec(&device),
+        Err(AirPlayError::InvalidParameter { .. })
+    ));
+    let selection = AirPlayConfig {
+        codec_fallback: true,
+        ..aac.clone()
+    }
+    .select_codec(&device)
+    .unwrap();
+    assert_eq!(selection.codec, AudioCodec::Aac);
+    assert_eq!(selection.aac_bitrate, 320_000);
+    assert_eq!(selection.warnings.len(), 1);
+
+    let pcm = AirPlayConfig {
+        audio_codec: AudioCodec::Pcm,
+        ..aac
+    };
+    assert!(pcm.select_codec(&device).unwrap().warnings.is_empty());
+}
+
+#[cfg(feature = "sender")]
+#[test]
+fn test_select_codec_aac_eld_needs_encoder() {
+    use crate::audio::AudioCodec;
+    use crate::error::AirPlayError;
+
+    let mut device = AirPlayDevice::from_address("10.0.0.5".parse().unwrap(), 7000);
+    device.capabilities.audio_codecs = vec![AudioCodec::Aac, AudioCodec::AacEld];
+
+    let eld = AirPlayConfig::builder()
+        .audio_codec(AudioCodec::AacEld)
+        .build();
+    let result = eld.select_codec(&device);
+    if cfg!(feature = "aac-fdk") {
+        assert_eq!(result.unwrap().codec, AudioCodec::AacEld);
+    } else {
+        let Err(AirPlayError::UnsupportedCodec { codec, supported }) = result else {
+            panic!("expected UnsupportedCodec");
+        };
+        assert_eq!(codec, AudioCodec::AacEld);
+        assert_eq!(supported, vec![AudioCodec::Aac]);
+
+        let fallback = AirPlayConfig {
+            codec_fallback: true,
+            ..eld
+        };
+        assert_eq!(
+            fallback.select_codec(&device).unwrap().codec,
+            AudioCodec::Aac
+        );
+    }
+}
+#[cfg(feature = "sender")]
+#[test]
+fn test_select_stream_format_against_info() {
+    use crate::audio::{AudioCodec, AudioFormat, ChannelConfig, SampleFormat, SampleRate};
+    use crate::protocol::plist::PlistValue;
+
+    let hires = AudioFormat::new(
+        SampleFormat::I24,
+        SampleRate::Hz48000,
+        ChannelConfig::Stereo,
+    );
+    let mut device = AirPlayDevice::from_address("10.0.0.4".parse().unwrap(), 7000);
+
+    // Unknown formats: CD quality only, without the high-resolution feature
+    let pcm = AirPlayConfig::builder()
+        .audio_format(hires)
+        .codec_fallback(true)
+        .build();
+    let selection = pcm.select_codec(&device).unwrap();
+    assert_eq!(selection.format, AudioFormat::CD_QUALITY);
+    assert_eq!(selection.warnings.len(), 1);
+
+    // PCM 48 kHz 16/24-bit stereo and ALAC 48 kHz 16-bit stereo
+    let info = crate::plist_dict! {
+        "audioFormats" => PlistValue::Array(vec![
+            crate::plist_dict! { "type" => 100i64, "audioInputFormats" => (1u64 << 15) | (1 << 17) },
+            crate::plist_dict! { "type" => 96i64, "audioInputFormats" => 1u64 << 20 },
+        ]),
+    };
+    device.update_from_info(&info);
+
+    let selection = pcm.select_codec(&device).unwrap();
+    assert_eq!(selection.format, hires);
+    assert!(selection.warnings.is_empty());
+
+    // The ALAC encoder takes 16-bit samples
+    let alac = AirPlayConfig {
+        audio_codec: AudioCodec::Alac,
+        ..pcm
+    };
+    let selection = alac.select_codec(&device).unwrap();
+    assert_eq!(
+        selection.format,
+        AudioFormat::new(
+            SampleFormat::I16,
+            SampleRate::Hz48000,
+            ChannelConfig::Stereo
+        )
+    );
+
+    // 44.1 kHz is not in the device's list, and nothing lower is either
+    let surround = AirPlayConfig::builder()
+        .audio_format(AudioFormat::new(
+            SampleFormat::I16,
+            SampleRate::Hz44100,
+            ChannelConfig::Surround51,
+        ))
+        .build();
+    assert_eq!(
+        surround.select_codec(&device).unwrap().format,
+        AudioFormat::CD_QUALITY
+    );
+}
+
+#[cfg(feature = "sender")]
+#[test]
+fn test_preempted_reconnect_policy() {
+    assert_eq!(
+        AirPlayConfig::default().preempted_reconnect,
+        PreemptedReconnect::Never
+    );
+    let config = AirPlayConfig::builder()
+        .preempted_reconnect(PreemptedReconnect::Always)
+        .build();
+    assert_eq!(config.preempted_reconnect, PreemptedReconnect::Always);
+
+    let idle = Some(Duration::from_secs(30));
+    assert!(!PreemptedReconnect::Never.allows(idle));
+    assert!(PreemptedReconnect::Always.allows(None));
+
+    let after_idle = PreemptedReconnect::AfterIdle(Duration::from_secs(20));
+    assert!(!after_idle.allows(None));
+    assert!(!after_idle.allows(Some(Duration::from_secs(10))));
+    assert!(after_idle.allows(idle));
+}
+
 // --- PTP / TimingProtocol tests ---
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_timing_protocol_default_is_auto() {
     let tp = TimingProtocol::default();
     assert_eq!(tp, TimingProtocol::Auto);
 }
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_config_default_timing_protocol() {
     let config = AirPlayConfig::default();
     assert_eq!(config.timing_protocol, TimingProtocol::Auto);
 }
 
+#[cfg(feature = "sender")]
 #[test]
 fn test_config_builder_timing_protocol() {
     let config = AirPlayConfig::builder()
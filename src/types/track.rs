@@ -1,5 +1,7 @@
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::audio::ReplayGain;
+
 /// Information about a track for playback
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct TrackInfo {
@@ -32,6 +34,9 @@ pub struct TrackInfo {
 
     /// Content identifier for queue management
     pub content_id: Option<String>,
+
+    /// `ReplayGain`/R128 loudness values, applied when normalization is on
+    pub replay_gain: Option<ReplayGain>,
 }
 
 impl TrackInfo {
@@ -69,6 +74,13 @@ impl TrackInfo {
         self.duration_secs = Some(duration_secs);
         self
     }
+
+    /// Builder method to set `ReplayGain` values
+    #[must_use]
+    pub fn with_replay_gain(mut self, replay_gain: ReplayGain) -> Self {
+        self.replay_gain = Some(replay_gain);
+        self
+    }
 }
 
 /// Unique identifier for a queue item
@@ -52,7 +52,7 @@ fn test_ap2_handshake_simulation() {
             Ap2ResponseBuilder::ok()
                 .cseq(cseq)
                 .bplist_body(&airplay2::protocol::plist::PlistValue::Dictionary(
-                    std::collections::HashMap::new(),
+                    airplay2::protocol::plist::PlistDict::new(),
                 ))
                 .unwrap()
                 .into_result()
@@ -20,15 +20,11 @@ async fn test_client_integration_flow() {
     // Use port 0 to let OS assign a free port
     let config = MockServerConfig {
         rtsp_port: 0,
-        audio_port: 0,   // Mock server binds UDP ports too?
-        control_port: 0, // Mock server doesn't seem to bind UDP ports in start(), just RTSP TCP
+        audio_port: 0,   // Audio is received on an ephemeral UDP port
+        control_port: 0, // Control and timing ports are only advertised in SETUP
         timing_port: 0,
         ..Default::default()
     };
-    // Note: MockServer currently only binds TCP RTSP port in start().
-    // UDP ports are just used in Transport header response.
-    // For this integration test, we don't need actual UDP traffic unless the client expects it
-    // immediately.
 
     let mut server = MockServer::new(config);
     let addr = server.start().await.expect("Failed to start mock server");
@@ -216,3 +212,340 @@ async fn test_client_reconnect_logic() {
 
     server.stop().await;
 }
+
+#[tokio::test]
+async fn test_client_setup_overrides() {
+    use std::collections::HashMap;
+
+    use airplay2::protocol::plist::PlistValue;
+
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    let config = airplay2::AirPlayConfig::builder()
+        .setup_overrides(HashMap::from([(
+            "senderExperiment".to_string(),
+            PlistValue::from(true),
+        )]))
+        .stream_overrides(HashMap::from([(
+            "latencyMin".to_string(),
+            PlistValue::UnsignedInteger(22050),
+        )]))
+        .build();
+    let client = AirPlayClient::new(config);
+
+    let mut device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    device.capabilities = airplay2::types::DeviceCapabilities {
+        airplay2: true,
+        supports_audio: true,
+        supports_buffered_audio: true,
+        ..Default::default()
+    };
+    timeout(Duration::from_secs(5), client.connect(&device))
+        .await
+        .expect("Connection timed out")
+        .expect("Connection failed");
+
+    let plists = server.setup_plists().await;
+    assert!(!plists.is_empty());
+    for plist in &plists {
+        let dict = plist.as_dict().unwrap();
+        assert_eq!(dict["senderExperiment"].as_bool(), Some(true));
+    }
+
+    let stream = plists
+        .iter()
+        .filter_map(|p| p.as_dict()?.get("streams")?.as_array()?.first())
+        .next()
+        .expect("stream SETUP sent");
+    assert_eq!(
+        stream.as_dict().unwrap()["latencyMin"].as_u64(),
+        Some(22050)
+    );
+
+    client.disconnect().await.ok();
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_connection_stream_management() {
+    use airplay2::connection::ConnectionManager;
+    use airplay2::protocol::plist::DictBuilder;
+
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    let manager = ConnectionManager::new(airplay2::AirPlayConfig::default());
+    let mut device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    device.capabilities = airplay2::types::DeviceCapabilities {
+        airplay2: true,
+        supports_audio: true,
+        supports_buffered_audio: true,
+        ..Default::default()
+    };
+    timeout(Duration::from_secs(5), manager.connect(&device))
+        .await
+        .expect("Connection timed out")
+        .expect("Connection failed");
+
+    let streams = manager.streams().await;
+    assert_eq!(streams.len(), 1);
+    let primary = streams[0];
+    assert_eq!(server.streams().await, vec![primary.id]);
+
+    // Secondary stream alongside the main audio
+    let secondary = manager
+        .setup_stream(
+            DictBuilder::new()
+                .insert("type", 96_u64)
+                .insert("ct", 2_u64)
+                .build(),
+        )
+        .await
+        .expect("secondary stream SETUP");
+    assert_ne!(secondary.id, primary.id);
+    assert_eq!(secondary.stream_type, 96);
+    assert_eq!(manager.streams().await.len(), 2);
+
+    manager
+        .send_flush_buffered(None, None, 100, 35200)
+        .await
+        .expect("FLUSHBUFFERED");
+    let flushes = server.flush_buffered_plists().await;
+    assert_eq!(
+        flushes[0].as_dict().unwrap()["streamID"].as_u64(),
+        Some(primary.id)
+    );
+
+    manager
+        .teardown_stream(secondary.id)
+        .await
+        .expect("stream TEARDOWN");
+    assert_eq!(manager.streams().await, vec![primary]);
+    assert_eq!(server.streams().await, vec![primary.id]);
+    assert!(manager.teardown_stream(secondary.id).await.is_err());
+
+    manager.disconnect().await.ok();
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_remote_control_only() {
+    use airplay2::AirPlayError;
+    use airplay2::testing::test_utils::generate_test_audio;
+
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    let config = airplay2::AirPlayConfig::builder()
+        .remote_control_only(true)
+        .build();
+    let client = AirPlayClient::new(config);
+
+    let mut device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    device.capabilities = airplay2::types::DeviceCapabilities {
+        airplay2: true,
+        supports_audio: true,
+        supports_buffered_audio: true,
+        ..Default::default()
+    };
+    timeout(Duration::from_secs(5), client.connect(&device))
+        .await
+        .expect("Connection timed out")
+        .expect("Connection failed");
+    assert!(client.is_connected().await);
+
+    // A single SETUP without timing or streams
+    let plists = server.setup_plists().await;
+    assert_eq!(plists.len(), 1);
+    let setup = plists[0].as_dict().unwrap();
+    assert_eq!(setup["isRemoteControlOnly"].as_bool(), Some(true));
+    assert!(!setup.contains_key("streams"));
+    assert!(server.streams().await.is_empty());
+
+    // Control still works, audio does not
+    client.set_volume(0.5).await.expect("volume");
+    let source = airplay2::streaming::SliceSource::from_i16(
+        &generate_test_audio(440.0, 44100, 100, 2),
+        airplay2::audio::AudioFormat::CD_QUALITY,
+    );
+    let result = timeout(Duration::from_secs(2), client.stream_audio(source))
+        .await
+        .expect("stream_audio should fail fast");
+    assert!(matches!(result, Err(AirPlayError::InvalidState { .. })));
+
+    // Transport commands control the device's own content
+    client.pause().await.expect("pause");
+    client.next().await.expect("next");
+    client
+        .send_media_command(airplay2::MediaCommand::SkipForward(Duration::from_secs(30)))
+        .await
+        .expect("skip");
+    let commands: Vec<i64> = server
+        .command_plists()
+        .await
+        .iter()
+        .filter_map(|plist| plist.as_dict()?["params"].as_dict()?["command"].as_i64())
+        .collect();
+    assert_eq!(commands, vec![2, 5, 18]);
+    assert!(!client.playback_state().await.is_playing);
+
+    client.disconnect().await.ok();
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_fairplay_setup() {
+    use airplay2::protocol::fairplay::{KEY_MESSAGE_LEN, RecordedKeyMessage};
+
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    let key_message = RecordedKeyMessage::new(0, &[0x5A; KEY_MESSAGE_LEN]).unwrap();
+    let config = airplay2::AirPlayConfig::builder()
+        .fairplay_key_source(key_message)
+        .build();
+    let client = AirPlayClient::new(config);
+
+    // Bit 12: FairPlay auth
+    let mut device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    device.capabilities =
+        airplay2::types::DeviceCapabilities::from_features((1 << 48) | (1 << 38) | (1 << 12));
+    timeout(Duration::from_secs(5), client.connect(&device))
+        .await
+        .expect("Connection timed out")
+        .expect("Connection failed");
+    assert!(server.fairplay_completed().await);
+
+    client.disconnect().await.ok();
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_identify() {
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    // No session is needed to identify a device
+    let client = AirPlayClient::new(airplay2::AirPlayConfig::default());
+    let device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    client.identify(&device).await.expect("Identify failed");
+    assert_eq!(server.identify_requests().await, 1);
+    assert!(!client.is_connected().await);
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_pairings_management() {
+    use airplay2::protocol::pairing::PairingEntry;
+
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    let client = AirPlayClient::new(airplay2::AirPlayConfig::default());
+    let device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    timeout(Duration::from_secs(5), client.connect(&device))
+        .await
+        .expect("Connection timed out")
+        .expect("Connection failed");
+
+    let guest = PairingEntry::new("guest-controller", [9; 32], false);
+    client.add_pairing(guest.clone()).await.unwrap();
+    client
+        .add_pairing(PairingEntry::new("second-admin", [3; 32], true))
+        .await
+        .unwrap();
+
+    let listed = client.list_pairings().await.unwrap();
+    assert_eq!(listed.len(), 2);
+    assert_eq!(listed[0], guest);
+    assert!(listed[1].admin);
+
+    client.remove_pairing("guest-controller").await.unwrap();
+    let remaining = server.pairings().await;
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].identifier, "second-admin");
+
+    client.disconnect().await.ok();
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_client_volume_ramp() {
+    init_tracing();
+    let mut server = MockServer::new(MockServerConfig {
+        rtsp_port: 0,
+        audio_port: 0,
+        control_port: 0,
+        timing_port: 0,
+        ..Default::default()
+    });
+    let addr = server.start().await.expect("Failed to start mock server");
+
+    let config = airplay2::AirPlayConfig::builder()
+        .volume_ramp(Duration::from_millis(100))
+        .build();
+    let client = AirPlayClient::new(config);
+    let device = AirPlayDevice::from_address(addr.ip(), addr.port());
+    timeout(Duration::from_secs(5), client.connect(&device))
+        .await
+        .expect("Connection timed out")
+        .expect("Connection failed");
+
+    let before = server.volume_changes().await.len();
+    client.set_volume(0.25).await.expect("Set volume failed");
+    let steps = server.volume_changes().await.split_off(before);
+
+    // Several steps, each quieter than the last, ending on the target
+    assert!(steps.len() >= 3, "{steps:?}");
+    assert!(steps.windows(2).all(|w| w[1] < w[0]), "{steps:?}");
+    let target = 20.0 * 0.25f32.log10();
+    assert!((steps.last().unwrap() - target).abs() < 0.01);
+    assert!((client.volume().await - 0.25).abs() < 0.001);
+
+    client.disconnect().await.ok();
+    server.stop().await;
+}
@@ -1,11 +1,10 @@
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
     use std::fs;
     use std::path::Path;
 
     use airplay2::plist_dict;
-    use airplay2::protocol::plist::{self, PlistValue};
+    use airplay2::protocol::plist::{self, PlistDict, PlistValue};
 
     #[test]
     fn generate_plist_fixtures() {
@@ -40,7 +39,7 @@ mod tests {
         save_fixture(fixtures_dir, "array.bplist", &array);
 
         // 4. Large Dictionary
-        let mut large_map = HashMap::new();
+        let mut large_map = PlistDict::new();
         for i in 0..100 {
             large_map.insert(format!("key_{i}"), PlistValue::Integer(i));
         }
@@ -48,7 +47,7 @@ mod tests {
         save_fixture(fixtures_dir, "large_dict.bplist", &large_dict);
 
         // 5. Data Types
-        let mut data_map = HashMap::new();
+        let mut data_map = PlistDict::new();
         data_map.insert(
             "data".to_string(),
             PlistValue::Data(vec![0xCA, 0xFE, 0xBA, 0xBE]),
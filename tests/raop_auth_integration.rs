@@ -1,7 +1,9 @@
 // tests/raop_auth_integration.rs
 
-use airplay2::protocol::crypto::RaopRsaPrivateKey;
-use airplay2::protocol::raop::{build_response_message, generate_challenge, generate_response};
+use airplay2::protocol::crypto::{AppleRsaPublicKey, RaopRsaPrivateKey};
+use airplay2::protocol::raop::{
+    build_response_message, generate_challenge, generate_response, verify_response,
+};
 
 #[test]
 fn test_simulated_airplay1_auth() {
@@ -23,17 +25,20 @@ fn test_simulated_airplay1_auth() {
     let public = server_key.public_key();
 
     use base64::Engine;
-    use rsa::pkcs1v15::{Signature, VerifyingKey};
-    use rsa::signature::Verifier;
-    use sha1::Sha1;
+    use rsa::Pkcs1v15Sign;
 
     let sig_bytes = base64::engine::general_purpose::STANDARD_NO_PAD
         .decode(&response)
         .unwrap();
-    let verifying_key = VerifyingKey::<Sha1>::new(public);
-    let sig = Signature::try_from(sig_bytes.as_slice()).unwrap();
 
+    // The Apple-Response is a raw PKCS#1 v1.5 signature over the message
     let message = build_response_message(&challenge, &server_ip, &server_mac);
 
-    verifying_key.verify(&message, &sig).unwrap();
+    public
+        .verify(Pkcs1v15Sign::new_unprefixed(), &message, &sig_bytes)
+        .unwrap();
+
+    let public = AppleRsaPublicKey::from(public);
+    verify_response(&public, &response, &challenge, &server_ip, &server_mac).unwrap();
+    assert!(verify_response(&public, &response, &challenge, &server_ip, &[0; 6]).is_err());
 }
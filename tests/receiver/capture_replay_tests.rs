@@ -81,3 +81,116 @@ fn test_capture_file_format() {
     assert!(packets[0].inbound);
     assert!(!packets[1].inbound);
 }
+
+/// Replay an iOS `GET /info` capture through the info endpoint
+fn replay_info_capture(path: &str) -> Option<airplay2::protocol::plist::PlistValue> {
+    use airplay2::receiver::ap2::capabilities::DeviceCapabilities;
+    use airplay2::receiver::ap2::config::Ap2Config;
+    use airplay2::receiver::ap2::info_endpoint::InfoEndpoint;
+    use airplay2::receiver::ap2::request_handler::Ap2RequestContext;
+    use airplay2::receiver::ap2::session_state::Ap2SessionState;
+
+    let capture_path = Path::new(path);
+    if !capture_path.exists() {
+        eprintln!("Skipping: capture file not found");
+        return None;
+    }
+
+    let packets = CaptureLoader::load_hex_dump(capture_path).unwrap();
+    let mut replay = CaptureReplay::new(packets);
+    let packet = replay.next_inbound().unwrap();
+
+    let mut codec = RtspServerCodec::new();
+    codec.feed(&packet.data);
+    let request = codec.decode().unwrap().expect("complete request");
+    assert!(matches!(
+        Ap2RequestType::classify(&request),
+        Ap2RequestType::Endpoint(Ap2Endpoint::Info)
+    ));
+
+    let config = Ap2Config::new("Living Room");
+    let endpoint = InfoEndpoint::new(DeviceCapabilities::from_config(&config, [7u8; 32]));
+    let state = Ap2SessionState::Connected;
+    let context = Ap2RequestContext {
+        state: &state,
+        session_id: None,
+        encrypted: false,
+        decrypt: None,
+    };
+    let result = endpoint.handle(&request, request.headers.cseq().unwrap(), &context);
+
+    let response = String::from_utf8_lossy(&result.response).into_owned();
+    assert!(response.starts_with("RTSP/1.0 200 OK"));
+    let body_start = result
+        .response
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .unwrap()
+        + 4;
+    Some(airplay2::protocol::plist::decode(&result.response[body_start..]).unwrap())
+}
+
+/// Stage 1: iOS asks only for the TXT record before pairing
+#[test]
+fn test_captured_info_stage1_returns_txt_record() {
+    let Some(plist) = replay_info_capture("tests/captures/info_stage1_ios17.hex") else {
+        return;
+    };
+
+    let dict = plist.as_dict().unwrap();
+    assert_eq!(dict.len(), 1);
+    let txt = dict["txtAirPlay"].as_bytes().unwrap();
+
+    // Length-prefixed key=value entries, matching the mDNS advertisement
+    let mut entries = Vec::new();
+    let mut rest = txt;
+    while let Some((&len, tail)) = rest.split_first() {
+        let (entry, tail) = tail.split_at(usize::from(len));
+        entries.push(String::from_utf8(entry.to_vec()).unwrap());
+        rest = tail;
+    }
+    assert!(entries.iter().any(|e| e.starts_with("deviceid=")));
+    assert!(entries.iter().any(|e| e.starts_with("features=")));
+    assert!(entries.iter().any(|e| e.starts_with("pk=")));
+}
+
+/// Stage 2: after pair-verify iOS asks for the full capabilities
+#[test]
+fn test_captured_info_stage2_returns_full_capabilities() {
+    let Some(plist) = replay_info_capture("tests/captures/info_stage2_ios17.hex") else {
+        return;
+    };
+
+    let dict = plist.as_dict().unwrap();
+    for key in [
+        "deviceID",
+        "name",
+        "model",
+        "sourceVersion",
+        "protocolVersion",
+        "features",
+        "statusFlags",
+        "pk",
+        "pi",
+        "psi",
+        "txtAirPlay",
+        "audioFormats",
+        "audioLatencies",
+        "displays",
+        "keepAliveLowPower",
+        "keepAliveSendStatsAsBody",
+    ] {
+        assert!(dict.contains_key(key), "/info missing {key}");
+    }
+
+    assert_eq!(dict["name"].as_str(), Some("Living Room"));
+    assert_eq!(dict["pk"].as_bytes(), Some(&[7u8; 32][..]));
+    assert!(
+        matches!(&dict["displays"], airplay2::protocol::plist::PlistValue::Array(d) if d.is_empty())
+    );
+
+    // The features mask must include everything iOS needs for an AirPlay 2 speaker
+    let features = dict["features"].as_u64().unwrap();
+    let required = airplay2::receiver::ap2::features::AIRPLAY2_SPEAKER_FEATURES;
+    assert_eq!(features & required, required);
+}
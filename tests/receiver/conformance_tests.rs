@@ -2,10 +2,8 @@
 //!
 //! Validates that our receiver correctly implements the AirPlay 2 protocol.
 
-use std::collections::HashMap;
-
 use airplay2::protocol::pairing::tlv::{TlvDecoder, TlvEncoder, TlvType};
-use airplay2::protocol::plist::PlistValue;
+use airplay2::protocol::plist::{PlistDict, PlistValue};
 use airplay2::receiver::ap2::capabilities::DeviceCapabilities;
 use airplay2::receiver::ap2::pairing_server::PairingServer;
 use airplay2::receiver::ap2::setup_handler::SetupRequest;
@@ -67,10 +65,10 @@ fn test_feature_flags_valid() {
 #[test]
 fn test_setup_phase1_parsing() {
     // Simulated phase 1 SETUP body
-    let mut streams_dict = HashMap::new();
+    let mut streams_dict = PlistDict::new();
     streams_dict.insert("type".to_string(), PlistValue::Integer(130)); // Event
 
-    let mut body_dict = HashMap::new();
+    let mut body_dict = PlistDict::new();
     body_dict.insert(
         "streams".to_string(),
         PlistValue::Array(vec![PlistValue::Dictionary(streams_dict)]),
@@ -102,13 +100,13 @@ fn test_setup_phase1_parsing() {
 #[test]
 fn test_setup_phase2_parsing() {
     // Simulated phase 2 SETUP body
-    let mut streams_dict = HashMap::new();
+    let mut streams_dict = PlistDict::new();
     streams_dict.insert("type".to_string(), PlistValue::Integer(96)); // Audio
     streams_dict.insert("ct".to_string(), PlistValue::Integer(100)); // PCM
     streams_dict.insert("sr".to_string(), PlistValue::Integer(44100));
     streams_dict.insert("ch".to_string(), PlistValue::Integer(2));
 
-    let mut body_dict = HashMap::new();
+    let mut body_dict = PlistDict::new();
     body_dict.insert(
         "streams".to_string(),
         PlistValue::Array(vec![PlistValue::Dictionary(streams_dict)]),